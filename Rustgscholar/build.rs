@@ -0,0 +1,15 @@
+//! Compiles `proto/gscholar.proto` for the gRPC API (feature "grpc") with
+//! the vendored `protoc`, so no protobuf compiler has to be installed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/gscholar.proto");
+    // tonic-build and protoc-bin-vendored are only built with the feature
+    #[cfg(feature = "grpc")]
+    {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        tonic_build::configure()
+            .build_client(true)
+            .compile_protos(&["proto/gscholar.proto"], &["proto"])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,383 @@
+//! Abstract backfill chain.
+//!
+//! Papers without an abstract after Stage 5 tend to come back "uncertain"
+//! from the LLM. [`AbstractChain`] asks a configurable list of sources in
+//! order (Crossref, OpenAlex, Semantic Scholar, Scopus, then the article's
+//! landing page) and stops for each paper at the first one that has its
+//! abstract. It runs as an [`Enricher`], so it is added to the pipeline
+//! like any other and its hits are recorded in `5_provenance.csv`.
+
+use crate::cache::HttpCache;
+use crate::crossref::{self, CrossrefClient};
+use crate::enrich::{EnricherSettings, Enricher, Provenance, MAX_CONCURRENT_PAPERS};
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::openalex;
+use crate::paper::Paper;
+use crate::raw_archive::{self, BodyKind};
+use crate::scopus::{self, ScopusClient};
+use crate::semanticscholar::{self, SemanticScholarClient};
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::warn;
+
+/// Source names accepted by `--abstract-chain`, in the default order
+pub const ABSTRACT_SOURCES: &[&str] = &["crossref", "openalex", "semanticscholar", "scopus", "landing"];
+
+/// Shortest text taken from a landing page (shorter ones are site blurbs)
+const MIN_LANDING_ABSTRACT_CHARS: usize = 100;
+
+/// Browser-like user agent for landing pages
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// Landing-page elements holding the abstract, most specific first
+const LANDING_SELECTORS: &[&str] = &[
+    r#"meta[name="citation_abstract"]"#,
+    r#"meta[name="dc.description"]"#,
+    r#"meta[name="DC.Description"]"#,
+    r#"meta[name="DC.description"]"#,
+    "section.abstract",
+    "div.abstract",
+    "#abstract",
+    r#"meta[property="og:description"]"#,
+    r#"meta[name="description"]"#,
+];
+
+/// One source of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbstractSource {
+    Crossref,
+    OpenAlex,
+    SemanticScholar,
+    Scopus,
+    /// Scrape the article's landing page (`article_url`, else the DOI link)
+    LandingPage,
+}
+
+impl FromStr for AbstractSource {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "crossref" => Ok(Self::Crossref),
+            "openalex" => Ok(Self::OpenAlex),
+            "semanticscholar" | "s2" => Ok(Self::SemanticScholar),
+            "scopus" => Ok(Self::Scopus),
+            "landing" | "landing-page" => Ok(Self::LandingPage),
+            other => Err(GscholarError::Validation(format!(
+                "Unknown abstract source '{}' (expected one of: {})",
+                other,
+                ABSTRACT_SOURCES.join(", ")
+            ))),
+        }
+    }
+}
+
+impl AbstractSource {
+    /// Display name, recorded as the enricher in `5_provenance.csv`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Crossref => "Crossref",
+            Self::OpenAlex => "OpenAlex",
+            Self::SemanticScholar => "Semantic Scholar",
+            Self::Scopus => "Scopus",
+            Self::LandingPage => "Landing page",
+        }
+    }
+
+    fn url(self) -> String {
+        match self {
+            Self::Crossref => crossref::CROSSREF_API_URL.to_string(),
+            Self::OpenAlex => format!("{}/works", openalex::OPENALEX_API_BASE),
+            Self::SemanticScholar => format!("{}/paper/batch", semanticscholar::SS_API_BASE),
+            Self::Scopus => scopus::SCOPUS_API_URL.to_string(),
+            Self::LandingPage => "article landing pages".to_string(),
+        }
+    }
+
+    /// Parse a list of source names; an empty list is the full chain,
+    /// without Scopus when no Scopus key is configured
+    pub fn parse_list(names: &[String], settings: &EnricherSettings) -> Result<Vec<Self>> {
+        if names.iter().all(|n| n.trim().is_empty()) {
+            return ABSTRACT_SOURCES
+                .iter()
+                .map(|name| name.parse())
+                .filter(|source| settings.scopus_key.is_some() || !matches!(source, Ok(Self::Scopus)))
+                .collect();
+        }
+        names.iter().filter(|n| !n.trim().is_empty()).map(|name| name.parse()).collect()
+    }
+}
+
+/// Ordered abstract sources, tried until a paper has an abstract
+pub struct AbstractChain {
+    sources: Vec<AbstractSource>,
+    crossref: CrossrefClient,
+    openalex: reqwest::Client,
+    openalex_cache: Option<HttpCache>,
+    s2: SemanticScholarClient,
+    scopus: Option<ScopusClient>,
+    landing: reqwest::Client,
+    landing_cache: Option<HttpCache>,
+    url: String,
+}
+
+impl AbstractChain {
+    /// Build the chain; Scopus needs `settings.scopus_key`
+    pub fn new(sources: Vec<AbstractSource>, settings: &EnricherSettings) -> Result<Self> {
+        let crossref = CrossrefClient::new(3)?;
+        let crossref = match settings.http_cache("crossref") {
+            Some(cache) => crossref.with_cache(cache),
+            None => crossref,
+        };
+        let scopus = match (sources.contains(&AbstractSource::Scopus), &settings.scopus_key) {
+            (false, _) => None,
+            (true, Some(key)) => {
+                let client = ScopusClient::new(key)?;
+                Some(match settings.http_cache("scopus") {
+                    Some(cache) => client.with_cache(cache),
+                    None => client,
+                })
+            }
+            (true, None) => {
+                return Err(GscholarError::Config("The scopus abstract source needs --scopus-key".to_string()));
+            }
+        };
+        // Publisher pages get a browser user agent, so their own client
+        let landing = http::builder(http::settings())?
+            .timeout(Duration::from_secs(20))
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
+        let url = sources.iter().map(|s| s.url()).collect::<Vec<_>>().join(" -> ");
+
+        Ok(Self {
+            sources,
+            crossref,
+            openalex: http::client_for(crate::throttle::Upstream::OpenAlex),
+            openalex_cache: settings.http_cache("openalex"),
+            s2: settings.s2_client(),
+            scopus,
+            landing,
+            landing_cache: settings.http_cache("landing"),
+            url,
+        })
+    }
+
+    /// The sources, in the order they are tried
+    pub fn sources(&self) -> &[AbstractSource] {
+        &self.sources
+    }
+
+    /// Abstract of one paper from one source (empty when it has none)
+    async fn lookup(&self, source: AbstractSource, paper: &Paper) -> Result<String> {
+        let doi = paper.doi.trim();
+        let found = match source {
+            AbstractSource::Crossref => {
+                let metadata = match doi {
+                    "" => self.crossref.lookup_by_title(&paper.title).await,
+                    doi => self.crossref.lookup_by_doi(doi).await,
+                };
+                metadata.map(|m| m.abstract_text)
+            }
+            AbstractSource::OpenAlex => openalex::lookup_by_doi(&self.openalex, doi, self.openalex_cache.as_ref())
+                .await?
+                .map(|work| work.snippet),
+            AbstractSource::SemanticScholar => {
+                let mut found = self.semantic_scholar(&[doi.to_string()]).await?;
+                found.remove(&doi::normalize(doi))
+            }
+            AbstractSource::Scopus => match &self.scopus {
+                Some(scopus) => scopus.abstract_by_doi(doi).await?,
+                None => None,
+            },
+            AbstractSource::LandingPage => self.landing_page(paper).await?,
+        };
+        Ok(found.unwrap_or_default())
+    }
+
+    /// Semantic Scholar abstracts by canonical DOI, in one batch
+    async fn semantic_scholar(&self, dois: &[String]) -> Result<HashMap<String, String>> {
+        let dois: Vec<String> = dois.iter().filter(|d| !d.trim().is_empty()).cloned().collect();
+        if dois.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let found = self.s2.batch_lookup(&dois).await?;
+        Ok(found.into_iter().map(|r| (doi::normalize(&r.doi), r.ss_abstract)).collect())
+    }
+
+    /// Abstract scraped from the article's landing page
+    async fn landing_page(&self, paper: &Paper) -> Result<Option<String>> {
+        let url = match (paper.article_url.trim(), paper.doi.trim()) {
+            ("", "") => return Ok(None),
+            ("", doi) => format!("https://doi.org/{}", doi),
+            (url, _) => url.to_string(),
+        };
+        let key = crate::cache::request_key("GET", &url, "");
+        let html = match self.landing_cache.as_ref().and_then(|c| c.get(&key)) {
+            Some(html) => html,
+            None => {
+                let response = self.landing.get(&url).send().await?;
+                if !response.status().is_success() {
+                    return Err(GscholarError::Api {
+                        code: response.status().as_u16() as i32,
+                        message: format!("Landing page error: {}", response.status()),
+                    });
+                }
+                let html = response.text().await?;
+                raw_archive::record(&url, BodyKind::Html, &html);
+                if let Some(cache) = &self.landing_cache {
+                    if let Err(e) = cache.insert(&key, &html) {
+                        warn!(error = %e, "Failed to write landing page cache");
+                    }
+                }
+                html
+            }
+        };
+        Ok(extract_abstract(&html))
+    }
+
+    /// Abstracts for the papers at `missing`, from one source
+    async fn lookup_all(&self, source: AbstractSource, papers: &[Paper], missing: &[usize]) -> Vec<(usize, String)> {
+        if source == AbstractSource::SemanticScholar {
+            let dois: Vec<String> = missing.iter().map(|&i| papers[i].doi.clone()).collect();
+            let found = match self.semantic_scholar(&dois).await {
+                Ok(found) => found,
+                Err(e) => {
+                    warn!(source = source.name(), error = %e, "Abstract lookup failed");
+                    return Vec::new();
+                }
+            };
+            return missing
+                .iter()
+                .filter_map(|&i| found.get(&doi::normalize(&papers[i].doi)).map(|a| (i, a.clone())))
+                .collect();
+        }
+
+        // Build the lookups up front so the returned future stays `Send`
+        let lookups: Vec<_> = missing
+            .iter()
+            .map(|&i| {
+                let paper = &papers[i];
+                async move {
+                    match self.lookup(source, paper).await {
+                        Ok(found) => (i, found),
+                        Err(e) => {
+                            warn!(source = source.name(), doi = %paper.doi, error = %e, "Abstract lookup failed");
+                            (i, String::new())
+                        }
+                    }
+                }
+            })
+            .collect();
+        stream::iter(lookups).buffer_unordered(MAX_CONCURRENT_PAPERS).collect().await
+    }
+}
+
+impl Enricher for AbstractChain {
+    fn name(&self) -> &str {
+        "Abstract chain"
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            for &source in &self.sources {
+                if !paper.abstract_text.trim().is_empty() {
+                    break;
+                }
+                let found = self.lookup(source, paper).await?;
+                let mut provenance = Provenance::new(source.name(), paper);
+                provenance.fill("abstract_text", &mut paper.abstract_text, &found);
+                if !provenance.is_empty() {
+                    return Ok(provenance);
+                }
+            }
+            Ok(Provenance::new(self.name(), paper))
+        })
+    }
+
+    /// Each source in turn, over the papers still without an abstract
+    fn enrich_all<'a>(&'a self, papers: &'a mut [Paper]) -> BoxFuture<'a, Result<Vec<Provenance>>> {
+        Box::pin(async move {
+            let mut changed = Vec::new();
+            for &source in &self.sources {
+                let missing: Vec<usize> =
+                    (0..papers.len()).filter(|&i| papers[i].abstract_text.trim().is_empty()).collect();
+                if missing.is_empty() {
+                    break;
+                }
+                for (i, found) in self.lookup_all(source, papers, &missing).await {
+                    let paper = &mut papers[i];
+                    let mut provenance = Provenance::new(source.name(), paper);
+                    provenance.fill("abstract_text", &mut paper.abstract_text, &found);
+                    if !provenance.is_empty() {
+                        changed.push(provenance);
+                    }
+                }
+            }
+            Ok(changed)
+        })
+    }
+}
+
+/// Abstract from a landing page's metadata or abstract section
+pub fn extract_abstract(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    LANDING_SELECTORS.iter().find_map(|selector| {
+        let selector = Selector::parse(selector).ok()?;
+        document.select(&selector).find_map(|element| {
+            let raw = match element.value().attr("content") {
+                // Meta content may itself hold markup (e.g., JATS <p> tags)
+                Some(content) => Html::parse_fragment(content).root_element().text().collect::<String>(),
+                None => element.text().collect::<Vec<_>>().join(" "),
+            };
+            let text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+            let text = text.strip_prefix("Abstract").map(str::trim_start).unwrap_or(&text).to_string();
+            (text.chars().count() >= MIN_LANDING_ABSTRACT_CHARS).then_some(text)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() -> Result<()> {
+        let settings = EnricherSettings::default();
+        let default = AbstractSource::parse_list(&[], &settings)?;
+        assert!(!default.contains(&AbstractSource::Scopus));
+        assert_eq!(default.first(), Some(&AbstractSource::Crossref));
+        assert_eq!(default.last(), Some(&AbstractSource::LandingPage));
+
+        let chosen = AbstractSource::parse_list(&["s2".to_string(), "scopus".to_string()], &settings)?;
+        assert_eq!(chosen, vec![AbstractSource::SemanticScholar, AbstractSource::Scopus]);
+        assert!(AbstractChain::new(chosen, &settings).is_err());
+        assert!("pubmed".parse::<AbstractSource>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_abstract() {
+        let body = "Rainfall-induced shallow landslides were mapped across the study area using multi-temporal imagery and field surveys.";
+        let html = format!(
+            r#"<html><head><meta name="description" content="Journal home page">
+            <meta name="citation_abstract" content="&lt;p&gt;{}&lt;/p&gt;"></head><body></body></html>"#,
+            body
+        );
+        assert_eq!(extract_abstract(&html).as_deref(), Some(body));
+
+        let html = format!(r#"<html><body><section class="abstract"><h2>Abstract</h2><p>{}</p></section></body></html>"#, body);
+        assert_eq!(extract_abstract(&html).as_deref(), Some(body));
+        assert_eq!(extract_abstract(r#"<meta name="description" content="Journal home page">"#), None);
+    }
+}
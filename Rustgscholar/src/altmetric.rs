@@ -0,0 +1,161 @@
+//! Altmetric details API client.
+//!
+//! Looks up a DOI and returns its Altmetric Attention Score with the number
+//! of posts mentioning it (news, policy documents, ...). The free API needs
+//! no key but allows about one request per second; with a key requests are
+//! not spaced out.
+
+use crate::cache::{self, HttpCache};
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use serde::{Deserialize, Serialize};
+use crate::ratelimit::{Rate, TokenBucket};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Altmetric details API base URL
+pub const ALTMETRIC_API_URL: &str = "https://api.altmetric.com/v1";
+
+/// Pause between two requests without an API key
+const FREE_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attention metrics of one DOI
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AltmetricMetrics {
+    /// Altmetric Attention Score
+    pub score: f64,
+    /// Posts mentioning the paper, across all sources
+    pub mentions: u64,
+    /// News outlets
+    pub news: u64,
+    /// Policy documents
+    pub policy: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AltmetricResponse {
+    #[serde(default)]
+    score: f64,
+    #[serde(default)]
+    cited_by_posts_count: u64,
+    #[serde(default)]
+    cited_by_msm_count: u64,
+    #[serde(default)]
+    cited_by_policies_count: u64,
+}
+
+/// Altmetric API client
+pub struct AltmetricClient {
+    client: reqwest::Client,
+    api_key: Option<String>,
+    cache: Option<HttpCache>,
+    /// Pacing of the free API (none with a key)
+    free_tier: Option<TokenBucket>,
+}
+
+impl AltmetricClient {
+    /// Create a new AltmetricClient
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Optional Altmetric API key (lifts the free rate limit)
+    pub fn new(api_key: Option<String>) -> Result<Self> {
+        let api_key = api_key.filter(|k| !k.trim().is_empty());
+        Ok(Self {
+            client: http::client(),
+            free_tier: api_key.is_none().then(|| TokenBucket::new(Rate::every(FREE_REQUEST_INTERVAL))),
+            api_key,
+            cache: None,
+        })
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/altmetric`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Look up the attention metrics of a DOI (`None` when Altmetric has no mentions of it)
+    pub async fn lookup(&self, doi: &str) -> Result<Option<AltmetricMetrics>> {
+        let doi = doi.trim();
+        if doi.is_empty() {
+            return Ok(None);
+        }
+        let mut request = self.client.get(format!("{}/doi/{}", ALTMETRIC_API_URL, doi));
+        if let Some(ref key) = self.api_key {
+            request = request.query(&[("key", key.as_str())]);
+        }
+        let request = request.build()?;
+        // The key stays out of the cache key
+        let key = cache::request_key("GET", &format!("{}/doi/{}", ALTMETRIC_API_URL, doi.to_lowercase()), "");
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(doi = %doi, "Altmetric cache hit");
+            return parse_response(&body);
+        }
+
+        if let Some(free_tier) = &self.free_tier {
+            free_tier.acquire().await;
+        }
+        let url = request.url().to_string();
+        let response = http::send(&self.client, request).await?;
+        let body = match response.status() {
+            // Altmetric answers 404 for DOIs nobody has mentioned: cache that too
+            reqwest::StatusCode::NOT_FOUND => String::new(),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(60)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("Altmetric API error: {}", status),
+                });
+            }
+            _ => {
+                let body = response.text().await?;
+                raw_archive::record(&url, BodyKind::Json, &body);
+                body
+            }
+        };
+        let metrics = parse_response(&body)?;
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write Altmetric cache");
+            }
+        }
+        Ok(metrics)
+    }
+}
+
+/// Metrics from a response body (empty for a DOI Altmetric does not track)
+fn parse_response(body: &str) -> Result<Option<AltmetricMetrics>> {
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    let data: AltmetricResponse = serde_json::from_str(body)?;
+    Ok(Some(AltmetricMetrics {
+        score: data.score,
+        mentions: data.cited_by_posts_count,
+        news: data.cited_by_msm_count,
+        policy: data.cited_by_policies_count,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() -> Result<()> {
+        let body = r#"{
+            "doi": "10.1038/nature12373",
+            "score": 153.25,
+            "cited_by_posts_count": 210,
+            "cited_by_msm_count": 12,
+            "cited_by_tweeters_count": 180,
+            "readers": {"mendeley": "402"}
+        }"#;
+        let metrics = parse_response(body)?;
+        assert_eq!(metrics, Some(AltmetricMetrics { score: 153.25, mentions: 210, news: 12, policy: 0 }));
+        assert_eq!(parse_response("")?, None);
+        Ok(())
+    }
+}
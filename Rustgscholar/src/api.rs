@@ -0,0 +1,380 @@
+//! HTTP API of `serve`: error envelope and OpenAPI document.
+//!
+//! Failed requests answer with a matching HTTP status and an [`ApiError`]
+//! body (`{code, message, retry_after}`) instead of a 200 response. The
+//! routes and their bodies are described by [`openapi`], served at
+//! `/openapi.json`. Authentication is described by its security schemes (see
+//! [`crate::auth`]).
+
+use crate::error::GscholarError;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Error body of every failed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiError {
+    /// Machine-readable error kind (e.g., "validation", "rate_limited")
+    pub code: String,
+    pub message: String,
+    /// Seconds to wait before retrying (rate limits)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    #[serde(skip)]
+    status: u16,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), message: message.into(), retry_after: None, status: status.as_u16() }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl From<GscholarError> for ApiError {
+    fn from(error: GscholarError) -> Self {
+        let message = error.to_string();
+        match error {
+            GscholarError::Validation(_) | GscholarError::Config(_) => {
+                Self::new(StatusCode::BAD_REQUEST, "validation", message)
+            }
+            GscholarError::RateLimited(secs) => Self {
+                retry_after: Some(secs),
+                ..Self::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", message)
+            },
+            GscholarError::Captcha => Self::new(StatusCode::SERVICE_UNAVAILABLE, "captcha", message),
+            GscholarError::Network(_) | GscholarError::Api { .. } => {
+                Self::new(StatusCode::BAD_GATEWAY, "upstream", message)
+            }
+            GscholarError::Browser(_) | GscholarError::Parse(_) | GscholarError::Io(_) | GscholarError::Json(_) => {
+                Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", message)
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let retry_after = self.retry_after;
+        let mut response = (status, Json(self)).into_response();
+        if let Some(secs) = retry_after.and_then(|s| header::HeaderValue::from_str(&s.to_string()).ok()) {
+            response.headers_mut().insert(header::RETRY_AFTER, secs);
+        }
+        response
+    }
+}
+
+/// OpenAPI 3 document of the server routes
+pub fn openapi() -> Value {
+    let error_responses = json!({
+        "400": {"$ref": "#/components/responses/Error"},
+        "401": {"$ref": "#/components/responses/Error"},
+        "422": {"$ref": "#/components/responses/Error"},
+        "429": {"$ref": "#/components/responses/Error"},
+        "502": {"$ref": "#/components/responses/Error"},
+        "503": {"$ref": "#/components/responses/Error"}
+    });
+    let body = |schema: &str| json!({"content": {"application/json": {"schema": {"$ref": format!("#/components/schemas/{}", schema)}}}});
+    let response = |description: &str, schema: Value| {
+        json!({"description": description, "content": {"application/json": {"schema": schema}}})
+    };
+    let schema_ref = |name: &str| json!({"$ref": format!("#/components/schemas/{}", name)});
+    let with_errors = |mut responses: Value| {
+        if let (Some(responses), Some(errors)) = (responses.as_object_mut(), error_responses.as_object()) {
+            for (status, error) in errors {
+                responses.entry(status.clone()).or_insert_with(|| error.clone());
+            }
+        }
+        responses
+    };
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rustgscholar",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Literature search pipeline: Google Scholar search and background pipeline jobs"
+        },
+        "paths": {
+            "/": {"get": {
+                "summary": "Web UI",
+                "security": [],
+                "responses": {"200": {"description": "HTML page", "content": {"text/html": {"schema": {"type": "string"}}}}}
+            }},
+            "/health": {"get": {
+                "summary": "Health check",
+                "security": [],
+                "responses": {"200": {"description": "OK", "content": {"text/plain": {"schema": {"type": "string"}}}}}
+            }},
+            "/health/ready": {"get": {
+                "summary": "Readiness: probes the upstream services",
+                "security": [],
+                "responses": {
+                    "200": response("All probed services are up", schema_ref("Readiness")),
+                    "503": response("A probed service is down", schema_ref("Readiness"))
+                }
+            }},
+            "/search": {"post": {
+                "summary": "Search Google Scholar",
+                "requestBody": body("SearchRequest"),
+                "responses": with_errors(json!({"200": response("Search results", schema_ref("SearchResponse"))}))
+            }},
+            "/jobs": {
+                "post": {
+                    "summary": "Queue a pipeline run",
+                    "requestBody": body("JobRequest"),
+                    "responses": with_errors(json!({"202": response("Queued job", schema_ref("Job"))}))
+                },
+                "get": {
+                    "summary": "The tenant's jobs, newest first",
+                    "responses": with_errors(json!({"200": response("Jobs", json!({"type": "array", "items": schema_ref("Job")}))}))
+                }
+            },
+            "/jobs/{id}": {"get": {
+                "summary": "Status, progress and result of a job",
+                "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}}],
+                "responses": with_errors(json!({
+                    "200": response("Job", schema_ref("Job")),
+                    "404": {"$ref": "#/components/responses/Error"}
+                }))
+            }},
+            "/jobs/{id}/files": {"get": {
+                "summary": "Files in a job's run folder",
+                "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}}],
+                "responses": with_errors(json!({
+                    "200": response("Files", json!({"type": "array", "items": schema_ref("RunFile")})),
+                    "404": {"$ref": "#/components/responses/Error"}
+                }))
+            }},
+            "/jobs/{id}/files/{name}": {"get": {
+                "summary": "Download a file of a job's run folder",
+                "parameters": [
+                    {"name": "id", "in": "path", "required": true, "schema": {"type": "string"}},
+                    {"name": "name", "in": "path", "required": true, "schema": {"type": "string"}}
+                ],
+                "responses": with_errors(json!({
+                    "200": {"description": "File content", "content": {"application/octet-stream": {"schema": {"type": "string", "format": "binary"}}}},
+                    "404": {"$ref": "#/components/responses/Error"}
+                }))
+            }},
+            "/jobs/{id}/export": {"get": {
+                "summary": "Papers of a finished job as a bibliography",
+                "parameters": [
+                    {"name": "id", "in": "path", "required": true, "schema": {"type": "string"}},
+                    {"name": "format", "in": "query", "required": true, "schema": {"type": "string", "enum": ["bibtex", "ris", "csl-json"]}}
+                ],
+                "responses": with_errors(json!({
+                    "200": {"description": "Bibliography file", "content": {"text/plain": {"schema": {"type": "string"}}, "application/json": {"schema": {"type": "array", "items": {"type": "object"}}}}},
+                    "404": {"$ref": "#/components/responses/Error"}
+                }))
+            }},
+            "/papers": {"get": {
+                "summary": "Papers of finished jobs, filtered, sorted and paged",
+                "parameters": [
+                    {"name": "query", "in": "query", "description": "Words that must all appear in the title, abstract or journal", "schema": {"type": "string"}},
+                    {"name": "min_if", "in": "query", "description": "Minimum impact factor", "schema": {"type": "number"}},
+                    {"name": "min_citations", "in": "query", "description": "Minimum citation count", "schema": {"type": "integer", "minimum": 0}},
+                    {"name": "label", "in": "query", "schema": {"type": "string", "enum": ["relevant", "irrelevant", "uncertain"]}},
+                    {"name": "job", "in": "query", "description": "Only this job's papers", "schema": {"type": "string"}},
+                    {"name": "ylo", "in": "query", "description": "Published in or after this year", "schema": {"type": "integer"}},
+                    {"name": "sort", "in": "query", "description": "Sort field, '-' prefix for descending (default: newest job first)", "schema": {"type": "string", "enum": ["if_score", "-if_score", "jci_score", "-jci_score", "altmetric_score", "-altmetric_score", "citations", "-citations", "citations_per_year", "-citations_per_year", "normalized_citations", "-normalized_citations", "year", "-year", "title", "-title", "job", "-job"]}},
+                    {"name": "page", "in": "query", "schema": {"type": "integer", "minimum": 1, "default": 1}},
+                    {"name": "per_page", "in": "query", "schema": {"type": "integer", "minimum": 1, "maximum": crate::store::MAX_PER_PAGE, "default": crate::store::DEFAULT_PER_PAGE}}
+                ],
+                "responses": with_errors(json!({"200": response("A page of papers", schema_ref("PaperPage"))}))
+            }},
+            "/openapi.json": {"get": {
+                "summary": "This document",
+                "security": [],
+                "responses": {"200": response("OpenAPI document", json!({"type": "object"}))}
+            }}
+        },
+        "security": [{"bearerAuth": []}, {"apiKeyAuth": []}],
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": {"type": "http", "scheme": "bearer"},
+                "apiKeyAuth": {"type": "apiKey", "in": "header", "name": "X-API-Key"}
+            },
+            "responses": {
+                "Error": response("Error", schema_ref("Error"))
+            },
+            "schemas": {
+                "Error": {
+                    "type": "object",
+                    "required": ["code", "message"],
+                    "properties": {
+                        "code": {"type": "string", "enum": ["validation", "unauthorized", "not_found", "rate_limited", "quota_exceeded", "captcha", "upstream", "internal"]},
+                        "message": {"type": "string"},
+                        "retry_after": {"type": "integer", "description": "Seconds to wait before retrying"}
+                    }
+                },
+                "Readiness": {
+                    "type": "object",
+                    "properties": {
+                        "ready": {"type": "boolean"},
+                        "checked_at": {"type": "string", "format": "date-time"},
+                        "dependencies": {"type": "array", "items": schema_ref("DependencyHealth")}
+                    }
+                },
+                "DependencyHealth": {
+                    "type": "object",
+                    "required": ["name", "status"],
+                    "properties": {
+                        "name": {"type": "string", "enum": ["crossref", "openalex", "s2", "easyscholar", "llm"]},
+                        "status": {"type": "string", "enum": ["up", "down", "skipped"]},
+                        "latency_ms": {"type": "integer"},
+                        "http_status": {"type": "integer"},
+                        "error": {"type": "string"}
+                    }
+                },
+                "SearchRequest": {
+                    "type": "object",
+                    "required": ["keyword"],
+                    "properties": {
+                        "keyword": {"type": "string"},
+                        "pages": {"type": "array", "items": {"type": "integer"}, "default": [1]},
+                        "ylo": {"type": "integer", "nullable": true},
+                        "proxy": {"type": "string", "nullable": true}
+                    }
+                },
+                "SearchResponse": {
+                    "type": "object",
+                    "properties": {
+                        "status": {"type": "string"},
+                        "count": {"type": "integer"},
+                        "results": {"type": "array", "items": schema_ref("ScholarResult")}
+                    }
+                },
+                "ScholarResult": {
+                    "type": "object",
+                    "properties": {
+                        "title": {"type": "string"},
+                        "author": {"type": "string"},
+                        "year": {"type": "string"},
+                        "venue": {"type": "string"},
+                        "article_url": {"type": "string"},
+                        "citations": {"type": "string"},
+                        "snippet": {"type": "string"}
+                    }
+                },
+                "JobRequest": {
+                    "type": "object",
+                    "required": ["keyword"],
+                    "properties": {
+                        "keyword": {"type": "string"},
+                        "sources": {"type": "array", "items": {"type": "string", "enum": ["gscholar", "openalex", "s2"]}},
+                        "pages": {"type": "array", "items": {"type": "integer"}, "default": [1]},
+                        "ylo": {"type": "integer", "nullable": true},
+                        "proxy": {"type": "string", "nullable": true},
+                        "min_citations": {"type": "integer", "minimum": 0, "nullable": true},
+                        "max_results": {"type": "integer", "minimum": 1, "nullable": true}
+                    }
+                },
+                "PaperPage": {
+                    "type": "object",
+                    "properties": {
+                        "total": {"type": "integer"},
+                        "page": {"type": "integer"},
+                        "per_page": {"type": "integer"},
+                        "papers": {"type": "array", "items": schema_ref("StoredPaper")}
+                    }
+                },
+                "StoredPaper": {
+                    "type": "object",
+                    "description": "One paper of a job: the job, its Stage 6 label and the unified result columns",
+                    "properties": {
+                        "job_id": {"type": "string"},
+                        "keyword": {"type": "string"},
+                        "label": {"type": "string"},
+                        "title": {"type": "string"},
+                        "author": {"type": "string"},
+                        "date": {"type": "string"},
+                        "doi": {"type": "string"},
+                        "journal": {"type": "string"},
+                        "if_score": {"type": "string"},
+                        "abstract_text": {"type": "string"}
+                    },
+                    "additionalProperties": {"type": "string"}
+                },
+                "JobStatus": {"type": "string", "enum": ["queued", "running", "completed", "failed"]},
+                "RunFile": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "size": {"type": "integer", "description": "Bytes"}
+                    }
+                },
+                "JobResult": {
+                    "type": "object",
+                    "properties": {
+                        "unified": {"type": "integer"},
+                        "relevant": {"type": "integer"},
+                        "final_table": {"type": "string", "nullable": true},
+                        "failures": {"type": "integer"}
+                    }
+                },
+                "Job": {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string"},
+                        "status": schema_ref("JobStatus"),
+                        "request": schema_ref("JobRequest"),
+                        "tenant": {"type": "string", "description": "Tenant of the API key that submitted the job"},
+                        "output_folder": {"type": "string"},
+                        "created_at": {"type": "string", "format": "date-time"},
+                        "started_at": {"type": "string", "format": "date-time", "nullable": true},
+                        "finished_at": {"type": "string", "format": "date-time", "nullable": true},
+                        "progress": {"type": "array", "items": {"type": "string"}},
+                        "result": {"allOf": [schema_ref("JobResult")], "nullable": true},
+                        "error": {"type": "string", "nullable": true}
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_envelope() -> crate::error::Result<()> {
+        let error = ApiError::from(GscholarError::RateLimited(30));
+        assert_eq!(error.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            serde_json::to_value(&error)?,
+            json!({"code": "rate_limited", "message": "Rate limited, retry after 30s", "retry_after": 30})
+        );
+        let response = error.into_response();
+        assert_eq!(response.headers().get(header::RETRY_AFTER).and_then(|v| v.to_str().ok()), Some("30"));
+
+        let error = ApiError::from(GscholarError::Validation("Job keyword is empty".to_string()));
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(serde_json::to_value(&error)?, json!({"code": "validation", "message": "Validation error: Job keyword is empty"}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_references_resolve() {
+        let doc = openapi();
+        let text = doc.to_string();
+        for reference in text.split("\"$ref\":\"").skip(1).filter_map(|s| s.split('"').next()) {
+            let pointer = reference.trim_start_matches('#');
+            assert!(doc.pointer(pointer).is_some(), "Unresolved {}", reference);
+        }
+        for path in ["/", "/health", "/health/ready", "/search", "/jobs", "/jobs/{id}", "/jobs/{id}/files", "/papers", "/openapi.json"] {
+            assert!(doc["paths"][path].is_object(), "Missing {}", path);
+        }
+    }
+}
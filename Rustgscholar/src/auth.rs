@@ -0,0 +1,349 @@
+//! API-key authentication and quotas for `serve`.
+//!
+//! Keys come from a TOML/YAML file with one section per client, or from
+//! `GSCHOLAR_API_KEYS` as `name:key` pairs. Every request except the health
+//! checks, the OpenAPI document and the web UI's page must send a key as
+//! `Authorization: Bearer <key>` or `X-API-Key: <key>`. Each key has a
+//! per-minute request limit and a daily quota of searches and jobs (`POST`
+//! requests); both are counted in memory. Without any key the server stays
+//! open, which is only safe on localhost.
+//!
+//! Every key belongs to a tenant (by default its own name). Keys of one
+//! tenant share its jobs, workspace and limit counters, so several research
+//! groups can use one server without seeing each other's data.
+
+use crate::api::ApiError;
+use crate::config;
+use crate::error::{GscholarError, Result};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{Local, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Environment variable with `name:key` pairs, comma separated
+pub const API_KEYS_ENV: &str = "GSCHOLAR_API_KEYS";
+
+/// Header carrying the key when `Authorization` is not used
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Length of a rate-limit window
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits of keys that do not set their own (0 = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub rate_per_minute: u32,
+    pub daily_quota: u32,
+}
+
+/// One client's key
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    /// Client name, used in logs
+    pub name: String,
+    pub key: String,
+    /// Workspace the key works in (letters, digits, `-` and `_`)
+    pub tenant: String,
+    pub limits: Limits,
+}
+
+/// Section of the key file
+#[derive(Debug, Deserialize)]
+struct KeyEntry {
+    key: String,
+    #[serde(default)]
+    tenant: Option<String>,
+    #[serde(default)]
+    rate_per_minute: Option<u32>,
+    #[serde(default)]
+    daily_quota: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Usage {
+    window_start: Instant,
+    window_requests: u32,
+    day: NaiveDate,
+    day_requests: u32,
+}
+
+/// The server's keys and their usage
+#[derive(Debug, Default)]
+pub struct KeyStore {
+    keys: Vec<ApiKey>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl KeyStore {
+    pub fn new(keys: Vec<ApiKey>) -> Result<Self> {
+        for (i, key) in keys.iter().enumerate() {
+            if key.name.trim().is_empty() || key.key.trim().is_empty() {
+                return Err(GscholarError::Config("API keys need a name and a non-empty key".to_string()));
+            }
+            // The tenant names a folder of the output directory
+            if key.tenant.is_empty() || !key.tenant.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(GscholarError::Config(format!(
+                    "Tenant '{}' of key '{}' may only use letters, digits, '-' and '_'",
+                    key.tenant, key.name
+                )));
+            }
+            if keys[..i].iter().any(|k| k.name == key.name || k.key == key.key) {
+                return Err(GscholarError::Config(format!("Duplicate API key or name '{}'", key.name)));
+            }
+        }
+        Ok(Self { keys, usage: Mutex::new(HashMap::new()) })
+    }
+
+    /// Keys of a TOML/YAML file: one section per client with `key` and
+    /// optional `rate_per_minute` / `daily_quota`
+    pub fn load_file(path: &Path, defaults: Limits) -> Result<Vec<ApiKey>> {
+        let entries: HashMap<String, KeyEntry> = serde_json::from_value(config::read_document(path)?)
+            .map_err(|e| GscholarError::Config(format!("{:?}: invalid API key file: {}", path, e)))?;
+        let mut keys: Vec<ApiKey> = entries
+            .into_iter()
+            .map(|(name, entry)| ApiKey {
+                tenant: entry.tenant.unwrap_or_else(|| name.clone()),
+                name,
+                key: entry.key,
+                limits: Limits {
+                    rate_per_minute: entry.rate_per_minute.unwrap_or(defaults.rate_per_minute),
+                    daily_quota: entry.daily_quota.unwrap_or(defaults.daily_quota),
+                },
+            })
+            .collect();
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(keys)
+    }
+
+    /// Keys of a `name:key,name:key` list (see [`API_KEYS_ENV`]); each key
+    /// is its own tenant
+    pub fn parse_list(value: &str, defaults: Limits) -> Result<Vec<ApiKey>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                // The pair may be a bare key: keep it out of the message
+                let (name, key) = pair
+                    .split_once(':')
+                    .ok_or_else(|| GscholarError::Config(format!("Expected name:key pairs in {}", API_KEYS_ENV)))?;
+                let name = name.trim().to_string();
+                Ok(ApiKey { tenant: name.clone(), name, key: key.trim().to_string(), limits: defaults })
+            })
+            .collect()
+    }
+
+    /// Whether authentication is off
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The key sent with a request
+    pub fn authenticate(&self, headers: &HeaderMap) -> std::result::Result<&ApiKey, ApiError> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        let sent = bearer
+            .or_else(|| headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()))
+            .map(str::trim)
+            .ok_or_else(|| unauthorized("Missing API key (Authorization: Bearer <key> or X-API-Key)"))?;
+        self.keys
+            .iter()
+            .find(|k| constant_time_eq(k.key.as_bytes(), sent.as_bytes()))
+            .ok_or_else(|| unauthorized("Invalid API key"))
+    }
+
+    /// Count a request against the key's limits, shared by the keys of its
+    /// tenant; `billable` requests (searches and jobs) also count toward the
+    /// daily quota
+    pub fn check(&self, key: &ApiKey, billable: bool) -> std::result::Result<(), ApiError> {
+        self.check_at(key, billable, Instant::now(), Local::now().date_naive())
+    }
+
+    fn check_at(&self, key: &ApiKey, billable: bool, now: Instant, today: NaiveDate) -> std::result::Result<(), ApiError> {
+        let mut usage = self
+            .usage
+            .lock()
+            .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", "Usage lock poisoned"))?;
+        let usage = usage.entry(key.tenant.clone()).or_insert(Usage {
+            window_start: now,
+            window_requests: 0,
+            day: today,
+            day_requests: 0,
+        });
+        if now.duration_since(usage.window_start) >= RATE_WINDOW {
+            usage.window_start = now;
+            usage.window_requests = 0;
+        }
+        if usage.day != today {
+            usage.day = today;
+            usage.day_requests = 0;
+        }
+
+        let limits = key.limits;
+        if limits.rate_per_minute > 0 && usage.window_requests >= limits.rate_per_minute {
+            let wait = RATE_WINDOW.saturating_sub(now.duration_since(usage.window_start));
+            return Err(limited(
+                "rate_limited",
+                format!("Rate limit of {} requests per minute reached", limits.rate_per_minute),
+                wait.as_secs().max(1),
+            ));
+        }
+        if billable && limits.daily_quota > 0 && usage.day_requests >= limits.daily_quota {
+            return Err(limited(
+                "quota_exceeded",
+                format!("Daily quota of {} searches and jobs used up", limits.daily_quota),
+                seconds_until_midnight(),
+            ));
+        }
+        usage.window_requests += 1;
+        if billable {
+            usage.day_requests += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Authenticate, apply the limits and log every request
+///
+/// The matched [`ApiKey`] is added to the request extensions.
+pub async fn middleware(State(store): State<Arc<KeyStore>>, mut request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let public = path.starts_with("/health") || path == "/openapi.json" || crate::web::is_ui_path(&path);
+
+    let (mut client, mut tenant) = ("-".to_string(), "-".to_string());
+    let response = if store.is_empty() || public {
+        next.run(request).await
+    } else {
+        let checked = store.authenticate(request.headers()).and_then(|key| {
+            store.check(key, method == Method::POST)?;
+            Ok(key.clone())
+        });
+        match checked {
+            Ok(key) => {
+                client = key.name.clone();
+                tenant = key.tenant.clone();
+                request.extensions_mut().insert(key);
+                next.run(request).await
+            }
+            Err(e) => e.into_response(),
+        }
+    };
+    info!(
+        client = %client,
+        tenant = %tenant,
+        method = %method,
+        path = %path,
+        status = response.status().as_u16(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Request"
+    );
+    response
+}
+
+fn unauthorized(message: &str) -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+}
+
+fn limited(code: &str, message: String, retry_after: u64) -> ApiError {
+    let mut error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, code, message);
+    error.retry_after = Some(retry_after);
+    error
+}
+
+fn seconds_until_midnight() -> u64 {
+    let now = Local::now().naive_local();
+    let midnight = now.date().succ_opt().and_then(|d| d.and_hms_opt(0, 0, 0));
+    midnight.map_or(60, |m| (m - now).num_seconds().max(1) as u64)
+}
+
+/// Compare without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(rate_per_minute: u32, daily_quota: u32) -> Result<KeyStore> {
+        KeyStore::new(KeyStore::parse_list("lab-a:secret-a, lab-b:secret-b", Limits { rate_per_minute, daily_quota })?)
+    }
+
+    #[test]
+    fn test_authenticate() -> Result<()> {
+        let store = store(10, 10)?;
+        let mut headers = HeaderMap::new();
+        assert_eq!(store.authenticate(&headers).map_err(|e| e.code), Err("unauthorized".to_string()));
+
+        headers.insert(header::AUTHORIZATION, "Bearer secret-b".parse().map_err(|_| GscholarError::Parse("header".to_string()))?);
+        assert_eq!(store.authenticate(&headers).map(|k| k.name.as_str()).ok(), Some("lab-b"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(API_KEY_HEADER, "secret-x".parse().map_err(|_| GscholarError::Parse("header".to_string()))?);
+        assert!(store.authenticate(&headers).is_err());
+
+        assert!(KeyStore::parse_list("no-separator", Limits { rate_per_minute: 0, daily_quota: 0 }).is_err());
+        assert!(KeyStore::new(KeyStore::parse_list("a:x,b:x", Limits { rate_per_minute: 0, daily_quota: 0 })?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limit_and_daily_quota() -> Result<()> {
+        let store = store(2, 3)?;
+        let key = store.keys[0].clone();
+        let start = Instant::now();
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).ok_or_else(|| GscholarError::Parse("date".to_string()))?;
+
+        assert!(store.check_at(&key, true, start, day).is_ok());
+        assert!(store.check_at(&key, false, start, day).is_ok());
+        let limited = store.check_at(&key, true, start + Duration::from_secs(20), day).err();
+        assert_eq!(limited.as_ref().map(|e| e.code.as_str()), Some("rate_limited"));
+        assert_eq!(limited.and_then(|e| e.retry_after), Some(40));
+
+        // A new window: two more billable requests, the second exceeds the quota of 3
+        let later = start + RATE_WINDOW;
+        assert!(store.check_at(&key, true, later, day).is_ok());
+        assert!(store.check_at(&key, true, later, day).is_ok());
+        let later = later + RATE_WINDOW;
+        assert_eq!(store.check_at(&key, true, later, day).map_err(|e| e.code), Err("quota_exceeded".to_string()));
+        assert!(store.check_at(&key, false, later, day).is_ok());
+
+        // The quota resets the next day; other keys are counted separately
+        assert!(store.check_at(&key, true, later, day + chrono::Days::new(1)).is_ok());
+        assert!(store.check_at(&store.keys[1], true, later, day).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_file_tenants() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("keys.toml");
+        std::fs::write(
+            &path,
+            "[alice]\nkey = \"k-alice\"\ntenant = \"geo-lab\"\ndaily_quota = 1\n\n[bob]\nkey = \"k-bob\"\ntenant = \"geo-lab\"\n\n[carol]\nkey = \"k-carol\"\n",
+        )?;
+        let store = KeyStore::new(KeyStore::load_file(&path, Limits { rate_per_minute: 0, daily_quota: 5 })?)?;
+        let tenants: Vec<_> = store.keys.iter().map(|k| (k.name.as_str(), k.tenant.as_str(), k.limits.daily_quota)).collect();
+        assert_eq!(tenants, vec![("alice", "geo-lab", 1), ("bob", "geo-lab", 5), ("carol", "carol", 5)]);
+
+        // Keys of a tenant share its counters
+        let (alice, bob) = (store.keys[0].clone(), store.keys[1].clone());
+        assert!(store.check(&bob, true).is_ok());
+        assert_eq!(store.check(&alice, true).map_err(|e| e.code), Err("quota_exceeded".to_string()));
+
+        std::fs::write(&path, "[dave]\nkey = \"k-dave\"\ntenant = \"../other\"\n")?;
+        assert!(KeyStore::new(KeyStore::load_file(&path, Limits { rate_per_minute: 0, daily_quota: 0 })?).is_err());
+        Ok(())
+    }
+}
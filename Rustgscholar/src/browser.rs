@@ -0,0 +1,153 @@
+//! Google Scholar cookies from a real browser session (feature "browser").
+//!
+//! `cookies fetch` launches a local Chrome/Chromium through the DevTools
+//! protocol, opens Google Scholar and waits until the page shows Scholar
+//! itself rather than a CAPTCHA, so the user can solve one in the window.
+//! The session's cookies are then converted to the [`Cookie`] format the
+//! scraper sends. Headless sessions cannot solve a CAPTCHA and give up as
+//! soon as one appears.
+
+use crate::cookies::Cookie;
+use crate::error::{GscholarError, Result};
+use crate::gscholar::{is_captcha_page, DEFAULT_SCHOLAR_URL};
+use chromiumoxide::{Browser, BrowserConfig};
+use futures::StreamExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Time between two checks of the page
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Marker of the Scholar search box, present on the home and result pages
+const SCHOLAR_PAGE_MARKER: &str = "gs_hdr_tsi";
+
+/// How to run the browser
+#[derive(Debug, Clone)]
+pub struct BrowserOptions {
+    /// Run without a window (no CAPTCHA can be solved)
+    pub headless: bool,
+    /// How long to wait for the user to get past a CAPTCHA
+    pub timeout: Duration,
+    /// Chrome/Chromium binary; found on the PATH when not set
+    pub executable: Option<PathBuf>,
+    /// Scholar URL to open (default Google Scholar)
+    pub url: Option<String>,
+}
+
+impl Default for BrowserOptions {
+    fn default() -> Self {
+        Self { headless: false, timeout: Duration::from_secs(300), executable: None, url: None }
+    }
+}
+
+/// Open Google Scholar, wait until it is usable and return its cookies
+pub async fn fetch_scholar_cookies(options: &BrowserOptions) -> Result<Vec<Cookie>> {
+    let mut config = BrowserConfig::builder().window_size(1200, 900);
+    if !options.headless {
+        config = config.with_head();
+    }
+    if let Some(executable) = &options.executable {
+        config = config.chrome_executable(executable);
+    }
+    let config = config.build().map_err(GscholarError::Browser)?;
+    let (mut browser, mut handler) = Browser::launch(config).await.map_err(browser_error)?;
+    let events = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+    let result = scholar_session(&browser, options).await;
+    if let Err(e) = browser.close().await {
+        debug!(error = %e, "Failed to close the browser");
+    }
+    let _ = browser.wait().await;
+    events.abort();
+    result
+}
+
+async fn scholar_session(browser: &Browser, options: &BrowserOptions) -> Result<Vec<Cookie>> {
+    let url = options.url.as_deref().unwrap_or(DEFAULT_SCHOLAR_URL);
+    info!(url = %url, headless = options.headless, "Opening Google Scholar in the browser");
+    let page = browser.new_page(url).await.map_err(browser_error)?;
+
+    let started = Instant::now();
+    let mut captcha_seen = false;
+    loop {
+        let html = page.content().await.map_err(browser_error)?;
+        match page_state(&html) {
+            PageState::Ready => break,
+            PageState::Captcha if options.headless => {
+                return Err(GscholarError::Browser(
+                    "Google Scholar shows a CAPTCHA; run with a browser window to solve it".to_string(),
+                ))
+            }
+            PageState::Captcha if !captcha_seen => {
+                captcha_seen = true;
+                warn!("CAPTCHA shown; solve it in the browser window");
+            }
+            _ => {}
+        }
+        if started.elapsed() > options.timeout {
+            return Err(GscholarError::Browser(format!(
+                "Google Scholar did not load within {}s",
+                options.timeout.as_secs()
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    let cookies: Vec<Cookie> = page
+        .get_cookies()
+        .await
+        .map_err(browser_error)?
+        .into_iter()
+        .map(|c| Cookie {
+            name: c.name,
+            value: c.value,
+            domain: c.domain,
+            path: c.path,
+            secure: c.secure,
+            http_only: c.http_only,
+            // Session cookies report -1
+            expires: (!c.session && c.expires > 0.0).then_some(c.expires),
+        })
+        .collect();
+    info!(count = cookies.len(), "Collected Google Scholar cookies");
+    Ok(cookies)
+}
+
+/// What the browser tab currently shows
+#[derive(Debug, PartialEq, Eq)]
+enum PageState {
+    Loading,
+    Captcha,
+    Ready,
+}
+
+fn page_state(html: &str) -> PageState {
+    if is_captcha_page(html) {
+        PageState::Captcha
+    } else if html.contains(SCHOLAR_PAGE_MARKER) {
+        PageState::Ready
+    } else {
+        PageState::Loading
+    }
+}
+
+fn browser_error(error: chromiumoxide::error::CdpError) -> GscholarError {
+    GscholarError::Browser(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_state() {
+        assert_eq!(page_state("<html><body></body></html>"), PageState::Loading);
+        assert_eq!(page_state(r#"<input id="gs_hdr_tsi" name="q">"#), PageState::Ready);
+        assert_eq!(
+            page_state(r#"<form id="gs_captcha_f"><input id="gs_hdr_tsi"></form>"#),
+            PageState::Captcha
+        );
+        assert_eq!(page_state("Our systems have detected unusual traffic"), PageState::Captcha);
+    }
+}
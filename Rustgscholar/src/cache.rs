@@ -0,0 +1,471 @@
+//! Persistent on-disk caches.
+//!
+//! This module provides a small JSON-file backed key/value cache with a TTL,
+//! stored under `~/.cache/rustgscholar/`. It is used to avoid re-querying
+//! slow or rate-limited upstream APIs across runs.
+//!
+//! Raw API responses (Crossref, OpenAlex, Semantic Scholar) go to
+//! [`HttpCache`] instead, one file per response under `http/<api>/`, and
+//! so do LLM replies, under `llm/`.
+
+use crate::error::{GscholarError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// File name of the EasyScholar rankings cache
+pub const RANKINGS_CACHE_FILE: &str = "rankings.json";
+
+/// Directory (under the cache directory) of the LLM reply cache
+pub const LLM_CACHE_DIR: &str = "llm";
+
+/// Directory (under the cache directory) of the HTTP response caches
+pub const HTTP_CACHE_DIR: &str = "http";
+
+/// Default cache directory: `~/.cache/rustgscholar`
+pub fn default_cache_dir() -> Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|p| p.join("rustgscholar"))
+        .ok_or_else(|| GscholarError::Config("Cannot determine cache directory".to_string()))
+}
+
+/// A cached value with the time it was stored (Unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry<V> {
+    value: V,
+    stored_at: i64,
+}
+
+/// JSON-file backed cache with time-to-live expiry
+///
+/// Inserts only update memory; the file is rewritten by [`save`](Self::save)
+/// (or [`save_async`](Self::save_async) from async code), which callers run
+/// once a batch of lookups ends, and when the cache is dropped.
+pub struct DiskCache<V: Serialize> {
+    path: PathBuf,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry<V>>>,
+    /// Whether entries were inserted since the file was last written
+    unsaved: AtomicBool,
+    /// Snapshots of the entries taken for saving so far
+    snapshots: AtomicU64,
+    /// Number of the snapshot on disk, locked while writing so that
+    /// overlapping saves never replace a newer snapshot with an older one
+    written: Arc<Mutex<u64>>,
+}
+
+impl<V: Clone + Serialize + DeserializeOwned> DiskCache<V> {
+    /// Open a cache file, loading any existing entries
+    ///
+    /// Returns an empty cache if the file doesn't exist or is invalid
+    pub fn open(path: PathBuf, ttl: Duration) -> Self {
+        let entries = load_entries(&path);
+        Self {
+            path,
+            ttl,
+            entries: Mutex::new(entries),
+            unsaved: AtomicBool::new(false),
+            snapshots: AtomicU64::new(0),
+            written: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Get the cache file path
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Get a value if present and not expired
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(key)?;
+        if is_expired(entry.stored_at, self.ttl) {
+            debug!(key = key, "Cache entry expired");
+            return None;
+        }
+        Some(entry.value.clone())
+    }
+
+    /// Insert a value (written to disk by the next save)
+    pub fn insert(&self, key: &str, value: V) -> Result<()> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| GscholarError::Config("Cache lock poisoned".to_string()))?;
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                value,
+                stored_at: chrono::Utc::now().timestamp(),
+            },
+        );
+        self.unsaved.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Write the cache to disk if entries were inserted since the last save
+    pub fn save(&self) -> Result<()> {
+        let Some((snapshot, content)) = self.unsaved_snapshot()? else {
+            return Ok(());
+        };
+        let written = write_snapshot(&self.written, &self.path, snapshot, &content);
+        self.unsaved.fetch_or(written.is_err(), Ordering::Relaxed);
+        written
+    }
+
+    /// [`save`](Self::save) on a blocking thread, off the async workers
+    pub async fn save_async(&self) -> Result<()> {
+        let Some((snapshot, content)) = self.unsaved_snapshot()? else {
+            return Ok(());
+        };
+        let (written, path) = (self.written.clone(), self.path.clone());
+        let written = tokio::task::spawn_blocking(move || write_snapshot(&written, &path, snapshot, &content))
+            .await
+            .map_err(|e| GscholarError::Config(format!("Cache write task failed: {}", e)))?;
+        self.unsaved.fetch_or(written.is_err(), Ordering::Relaxed);
+        written
+    }
+
+    /// Number of entries (including expired ones not yet overwritten)
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Whether the cache has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Numbered snapshot of the entries, if any were inserted since the last save
+    fn unsaved_snapshot(&self) -> Result<Option<(u64, String)>> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| GscholarError::Config("Cache lock poisoned".to_string()))?;
+        if !self.unsaved.swap(false, Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let snapshot = self.snapshots.fetch_add(1, Ordering::Relaxed) + 1;
+        Ok(Some((snapshot, serde_json::to_string(&*entries)?)))
+    }
+}
+
+impl<V: Serialize> Drop for DiskCache<V> {
+    fn drop(&mut self) {
+        if !*self.unsaved.get_mut() {
+            return;
+        }
+        let snapshot = *self.snapshots.get_mut() + 1;
+        let written = self
+            .entries
+            .get_mut()
+            .map_err(|_| GscholarError::Config("Cache lock poisoned".to_string()))
+            .and_then(|entries| Ok(serde_json::to_string(&*entries)?))
+            .and_then(|content| write_snapshot(&self.written, &self.path, snapshot, &content));
+        if let Err(e) = written {
+            warn!(path = ?self.path, error = %e, "Failed to write disk cache");
+        }
+    }
+}
+
+/// Disk cache of raw response bodies for one upstream API (or LLM replies)
+///
+/// Entries are keyed by a hash of the request ([`request_key`]) and stored
+/// one file each, since search pages run to megabytes and rewriting a single
+/// JSON file of them like [`DiskCache`] does would not scale. Runs sharing the
+/// directory add entries side by side instead of overwriting each other.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl HttpCache {
+    /// Open a cache directory (created on first insert)
+    pub fn open(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Cache for `api` under the default cache directory (`http/<api>/`)
+    pub fn for_api(api: &str, ttl: Duration) -> Result<Self> {
+        Ok(Self::open(default_cache_dir()?.join(HTTP_CACHE_DIR).join(api), ttl))
+    }
+
+    /// Get a response body if present and not expired
+    pub fn get(&self, key: &str) -> Option<String> {
+        let content = std::fs::read_to_string(self.entry_path(key)).ok()?;
+        let entry: CacheEntry<String> = serde_json::from_str(&content).ok()?;
+        if is_expired(entry.stored_at, self.ttl) {
+            debug!(dir = ?self.dir, "HTTP cache entry expired");
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    /// Store a response body
+    pub fn insert(&self, key: &str, body: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = CacheEntry {
+            value: body,
+            stored_at: chrono::Utc::now().timestamp(),
+        };
+        write_atomic(&self.entry_path(key), &serde_json::to_string(&entry)?)
+    }
+
+    /// [`Self::get`] on a blocking thread, for async tasks
+    pub async fn load(&self, key: &str) -> Option<String> {
+        let (cache, key) = (self.clone(), key.to_string());
+        tokio::task::spawn_blocking(move || cache.get(&key)).await.ok().flatten()
+    }
+
+    /// [`Self::insert`] on a blocking thread, for async tasks
+    pub async fn store(&self, key: &str, body: String) -> Result<()> {
+        let (cache, key) = (self.clone(), key.to_string());
+        tokio::task::spawn_blocking(move || cache.insert(&key, &body))
+            .await
+            .map_err(|e| GscholarError::Config(format!("Cache write task failed: {}", e)))?
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:x}.json", Sha256::digest(key.as_bytes())))
+    }
+}
+
+/// Cache key of an HTTP request: method, full URL (with query) and body
+pub fn request_key(method: &str, url: &str, body: &str) -> String {
+    format!("{} {}\n{}", method, url, body)
+}
+
+/// Size of one cache on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Cache name (`rankings`, `llm`, or `http/<api>`)
+    pub name: String,
+    pub path: PathBuf,
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// Entry counts and sizes of every cache under `cache_dir`
+pub fn stats(cache_dir: &Path) -> Result<Vec<CacheStats>> {
+    let mut all = Vec::new();
+    let path = cache_dir.join(RANKINGS_CACHE_FILE);
+    if let Ok(metadata) = std::fs::metadata(&path) {
+        let entries = load_entries::<serde_json::Value>(&path).len();
+        all.push(CacheStats { name: "rankings".to_string(), path, entries, bytes: metadata.len() });
+    }
+    let llm_dir = cache_dir.join(LLM_CACHE_DIR);
+    if llm_dir.is_dir() {
+        all.push(dir_stats("llm".to_string(), llm_dir)?);
+    }
+
+    let http_dir = cache_dir.join(HTTP_CACHE_DIR);
+    if http_dir.is_dir() {
+        let mut apis: Vec<_> = std::fs::read_dir(&http_dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        apis.sort();
+        for path in apis {
+            let api = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            all.push(dir_stats(format!("{}/{}", HTTP_CACHE_DIR, api), path)?);
+        }
+    }
+    Ok(all)
+}
+
+/// Entry count and size of a one-file-per-entry cache directory
+fn dir_stats(name: String, path: PathBuf) -> Result<CacheStats> {
+    let (mut entries, mut bytes) = (0, 0);
+    for entry in std::fs::read_dir(&path)?.filter_map(|e| e.ok()) {
+        if let Ok(metadata) = entry.metadata() {
+            entries += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok(CacheStats { name, path, entries, bytes })
+}
+
+/// Delete a cache directory (e.g., the HTTP response caches)
+///
+/// Returns true if a directory was removed
+pub fn clear_dir(path: &Path) -> Result<bool> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+        info!("Cleared cache at {:?}", path);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Delete a cache file
+///
+/// Returns true if a file was removed
+pub fn clear(path: &Path) -> Result<bool> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+        info!("Cleared cache at {:?}", path);
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Check whether an entry stored at `stored_at` is older than `ttl`
+fn is_expired(stored_at: i64, ttl: Duration) -> bool {
+    let age = chrono::Utc::now().timestamp() - stored_at;
+    age < 0 || age as u64 > ttl.as_secs()
+}
+
+fn load_entries<V: DeserializeOwned>(path: &Path) -> HashMap<String, CacheEntry<V>> {
+    if !path.exists() {
+        debug!("Cache file not found: {:?}", path);
+        return HashMap::new();
+    }
+
+    match std::fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to parse cache file {:?}: {}", path, e);
+                HashMap::new()
+            }
+        },
+        Err(e) => {
+            warn!("Failed to read cache file {:?}: {}", path, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Write a numbered [`DiskCache`] snapshot unless a newer one is already on disk
+fn write_snapshot(written: &Mutex<u64>, path: &Path, snapshot: u64, content: &str) -> Result<()> {
+    let mut written = written
+        .lock()
+        .map_err(|_| GscholarError::Config("Cache lock poisoned".to_string()))?;
+    if *written > snapshot {
+        return Ok(());
+    }
+    save_entries(path, content)?;
+    *written = snapshot;
+    Ok(())
+}
+
+fn save_entries(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, content)
+}
+
+/// Write a file through a temporary sibling and a rename, so a crash
+/// mid-write never leaves a truncated cache behind
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    // Unique per process and write, so concurrent writers never share one
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp = path.with_file_name(format!("{}.{}.{}.tmp", name, std::process::id(), WRITES.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_insert_persists_across_open() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("nested").join("cache.json");
+
+        let cache: DiskCache<Option<String>> = DiskCache::open(path.clone(), Duration::from_secs(60));
+        cache.insert("Nature", Some("Q1".to_string()))?;
+        cache.insert("Unknown Journal", None)?;
+        // Nothing is written until the cache is saved
+        assert!(!path.exists());
+        cache.save()?;
+
+        let reopened: DiskCache<Option<String>> = DiskCache::open(path.clone(), Duration::from_secs(60));
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.get("Nature"), Some(Some("Q1".to_string())));
+        assert_eq!(reopened.get("Unknown Journal"), Some(None));
+        assert_eq!(reopened.get("Missing"), None);
+        // Written through a temporary file that is renamed into place
+        assert_eq!(std::fs::read_dir(path.parent().expect("cache dir"))?.count(), 1);
+
+        assert!(clear(&path)?);
+        assert!(!clear(&path)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_save_async_and_on_drop() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("cache.json");
+        let open = || DiskCache::<u32>::open(path.clone(), Duration::from_secs(60));
+
+        let cache = open();
+        cache.insert("a", 1)?;
+        cache.save_async().await?;
+        assert_eq!(open().get("a"), Some(1));
+
+        // An older snapshot finishing late does not replace a newer one
+        cache.insert("b", 2)?;
+        let (older, stale) = cache.unsaved_snapshot()?.expect("unsaved entries");
+        cache.insert("c", 3)?;
+        cache.save()?;
+        write_snapshot(&cache.written, &path, older, &stale)?;
+        assert_eq!(open().len(), 3);
+
+        cache.insert("d", 4)?;
+        drop(cache);
+        assert_eq!(open().len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_http_cache() -> Result<()> {
+        let dir = TempDir::new()?;
+        let cache = HttpCache::open(dir.path().join(HTTP_CACHE_DIR).join("crossref"), Duration::from_secs(60));
+        let key = request_key("GET", "https://api.crossref.org/works?query.title=slopes", "");
+        assert_eq!(cache.get(&key), None);
+
+        cache.insert(&key, "{\"message\":{}}")?;
+        assert_eq!(cache.get(&key).as_deref(), Some("{\"message\":{}}"));
+        assert_eq!(cache.get(&request_key("POST", "https://api.crossref.org/works?query.title=slopes", "")), None);
+
+        // Two caches on one directory (e.g., two runs) keep each other's entries
+        let first = HttpCache::open(dir.path().join(LLM_CACHE_DIR), Duration::from_secs(60));
+        let second = HttpCache::open(dir.path().join(LLM_CACHE_DIR), Duration::from_secs(60));
+        first.insert("reply 1", "{}")?;
+        second.insert("reply 2", "{}")?;
+        assert_eq!((first.get("reply 2").as_deref(), second.get("reply 1").as_deref()), (Some("{}"), Some("{}")));
+
+        let all = stats(dir.path())?;
+        assert_eq!(all.len(), 2);
+        assert_eq!((all[0].name.as_str(), all[0].entries), ("llm", 2));
+        assert_eq!((all[1].name.as_str(), all[1].entries), ("http/crossref", 1));
+        assert!(clear_dir(&dir.path().join(LLM_CACHE_DIR))?);
+
+        assert!(clear_dir(&dir.path().join(HTTP_CACHE_DIR))?);
+        assert!(stats(dir.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = chrono::Utc::now().timestamp();
+        assert!(!is_expired(now, Duration::from_secs(60)));
+        assert!(is_expired(now - 120, Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,128 @@
+//! Append-only JSONL checkpoints for long-running stages.
+//!
+//! Each completed item is written as one JSON line and flushed immediately,
+//! so an interrupted run can be resumed by reopening the same file: items
+//! already present are loaded and skipped. A truncated last line (from a
+//! crash mid-write) is ignored.
+
+use crate::error::{GscholarError, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// JSONL checkpoint of completed items, keyed by a caller-provided key
+pub struct JsonlCheckpoint<T> {
+    path: PathBuf,
+    done: HashMap<String, T>,
+    file: Mutex<File>,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> JsonlCheckpoint<T> {
+    /// Open a checkpoint file, loading completed items keyed by `key`
+    pub fn open(path: &Path, key: impl Fn(&T) -> String) -> Result<Self> {
+        let mut done = HashMap::new();
+        let mut needs_newline = false;
+
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            needs_newline = !content.is_empty() && !content.ends_with('\n');
+
+            for (line_no, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<T>(line) {
+                    Ok(item) => {
+                        done.insert(key(&item), item);
+                    }
+                    Err(e) => warn!(path = ?path, line = line_no + 1, error = %e, "Skipping invalid checkpoint line"),
+                }
+            }
+            info!(path = ?path, items = done.len(), "Loaded checkpoint");
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if needs_newline {
+            // Terminate a partial line so new items start on their own line
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            done,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Get the checkpoint file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get a completed item by key
+    pub fn get(&self, key: &str) -> Option<T> {
+        self.done.get(key).cloned()
+    }
+
+    /// Number of items loaded from a previous run
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Whether no items were loaded from a previous run
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty()
+    }
+
+    /// Append a completed item and flush it to disk
+    pub fn append(&self, item: &T) -> Result<()> {
+        let mut line = serde_json::to_string(item)?;
+        line.push('\n');
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| GscholarError::Config("Checkpoint lock poisoned".to_string()))?;
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_reload() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("checkpoint.jsonl");
+        let key = |item: &(String, u32)| item.0.clone();
+
+        let checkpoint = JsonlCheckpoint::open(&path, key)?;
+        assert!(checkpoint.is_empty());
+        checkpoint.append(&("a".to_string(), 1))?;
+        checkpoint.append(&("b".to_string(), 2))?;
+        drop(checkpoint);
+
+        // Simulate a crash mid-write
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(b"[\"c\", ")?;
+
+        let checkpoint = JsonlCheckpoint::open(&path, key)?;
+        assert_eq!(checkpoint.len(), 2);
+        assert_eq!(checkpoint.get("b"), Some(("b".to_string(), 2)));
+        assert_eq!(checkpoint.get("c"), None);
+
+        checkpoint.append(&("d".to_string(), 4))?;
+        drop(checkpoint);
+        assert_eq!(JsonlCheckpoint::open(&path, key)?.len(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+//! Configuration files for the `search` command.
+//!
+//! A review's options (source, pages, keys, rank filters, LLM settings,
+//! criteria and journal list files) can live in a TOML or YAML file instead
+//! of on the command line. The file is grouped into sections; each key maps
+//! to one `search` flag (see [`OPTIONS`]):
+//!
+//! ```toml
+//! [search]
+//! source = "openalex"
+//! pages = "1-5"
+//!
+//! [filters]
+//! sciif = 5.0
+//!
+//! [llm]
+//! model = "gpt-4o-mini"
+//! tags = ["method", "case study"]
+//! ```
+//!
+//! [`load`] turns the file into command-line arguments that are placed
+//! before the ones actually typed, so explicit flags override the file.
+//! `search.keyword` becomes the positional keyword (so it must not also be
+//! typed), and the `[watch]` section applies only to the `watch` command.
+//! An array becomes one flag per item (`--work-types article --work-types
+//! review`), so items may contain commas.
+
+use crate::error::{GscholarError, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// One config key and the `search` flag it sets
+pub struct ConfigOption {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub flag: &'static str,
+    /// Example value written by [`scaffold`]
+    pub example: &'static str,
+    pub help: &'static str,
+}
+
+const fn option(
+    section: &'static str,
+    key: &'static str,
+    flag: &'static str,
+    example: &'static str,
+    help: &'static str,
+) -> ConfigOption {
+    ConfigOption { section, key, flag, example, help }
+}
+
+/// Every supported config key
+pub const OPTIONS: &[ConfigOption] = &[
+    option("search", "keyword", "", "\"landslide susceptibility\"", "Search keyword (instead of the command-line argument)"),
+    option("search", "source", "source", "\"openalex\"", "gscholar, openalex, s2, or a comma-separated list"),
+    option("search", "pages", "pages", "\"1-5\"", "Page range"),
+    option("search", "openalex_per_page", "openalex-per-page", "50", "OpenAlex results per page (1-200)"),
+    option("search", "openalex_page_delay", "openalex-page-delay", "1.0", "Seconds between two OpenAlex page requests"),
+    option("search", "ylo", "ylo", "2020", "Results from this year onwards (default: current year - 5)"),
+    option("search", "auto_ylo", "auto-ylo", "false", "Without ylo, start at the year keeping 90% of the OpenAlex works"),
+    option("search", "proxy", "proxy", "\"http://127.0.0.1:7890\"", "Proxy URL for Google Scholar and every API"),
+    option("search", "mirror", "mirror", "\"https://scholar.example.org\"", "Google Scholar mirror URL"),
+    option("search", "backend", "backend", "\"serpapi\"", "Google Scholar backend: scrape, serpapi or searchapi"),
+    option("search", "serpapi_key", "serpapi-key", "\"...\"", "SerpAPI key for the serpapi backend"),
+    option("search", "searchapi_key", "searchapi-key", "\"...\"", "SearchAPI key for the searchapi backend"),
+    option("search", "sdt", "sdt", "\"0,5\"", "Source data type filter"),
+    option("search", "max_results", "max-results", "500", "Stop after this many unique records across all sources"),
+    option("search", "output", "output", "\"./output\"", "Output directory"),
+    option("search", "stages", "stages", "[1, 2, 3, 6]", "Run only these stages (numbers, ranges or names)"),
+    option("search", "skip_stage", "skip-stage", "[\"semanticscholar\"]", "Skip these stages"),
+    option("easyscholar", "key", "easyscholar-key", "\"your-easyscholar-key\"", "EasyScholar API key (enables Stage 3)"),
+    option("easyscholar", "cache_days", "ranking-cache-days", "30", "Days to keep rankings in the on-disk cache"),
+    option("easyscholar", "journal_include", "journal-include", "\"journals.txt\"", "Journal whitelist file"),
+    option("easyscholar", "journal_exclude", "journal-exclude", "\"blacklist.txt\"", "Journal blacklist file"),
+    option("easyscholar", "journal_aliases", "journal-aliases", "\"aliases.json\"", "Journal name aliases (JSON)"),
+    option("easyscholar", "core_rankings", "core-rankings", "\"CORE.csv\"", "CORE conference rankings CSV"),
+    option("easyscholar", "dblp", "dblp", "true", "Detect conference venues with DBLP"),
+    option("http", "timeout", "http-timeout", "30", "Timeout in seconds of one HTTP request"),
+    option("http", "connect_timeout", "connect-timeout", "10", "Timeout in seconds for opening a connection"),
+    option("http", "pool_size", "http-pool-size", "16", "Idle connections kept open per host"),
+    option("http", "retries", "http-retries", "3", "Retries after a connection error, timeout, 429 or 502-504"),
+    option("http", "http1_only", "http1-only", "false", "Use HTTP/1.1 only"),
+    option("http", "retry_policy", "retry-policy", "[\"s2=5:2000:0.2:90\", \"crossref=3:500\"]", "Retry policy of single upstreams (NAME=RETRIES[:BACKOFF_MS[:JITTER[:TIMEOUT_SECS]]])"),
+    option("http", "proxy_for", "proxy-for", "[\"llm=direct\", \"s2=http://127.0.0.1:3128\"]", "Proxy of single upstreams instead of search.proxy"),
+    option("cache", "http_days", "http-cache-days", "30", "Days to keep Crossref / Semantic Scholar lookups"),
+    option("cache", "search_hours", "search-cache-hours", "24", "Hours to keep OpenAlex / Semantic Scholar search pages"),
+    option("semanticscholar", "key", "s2-key", "\"your-s2-key\"", "Semantic Scholar API key (higher rate limits)"),
+    option("semanticscholar", "title_match_threshold", "title-match-threshold", "0.85", "Title similarity for Crossref / Semantic Scholar title matches"),
+    option("enrich", "enrichers", "enrichers", "[\"crossref\", \"unpaywall\"]", "Enrichers run on the Stage 5 dataset, in order"),
+    option("enrich", "unpaywall_email", "unpaywall-email", "\"you@example.org\"", "Contact email for the Unpaywall enricher"),
+    option("enrich", "abstract_chain", "abstract-chain", "[\"crossref\", \"openalex\", \"landing\"]", "Abstract sources tried for papers still without one"),
+    option("enrich", "download_pdfs", "download-pdfs", "false", "Download open-access PDFs of the Stage 5 papers into pdfs/"),
+    option("enrich", "pdf_delay", "pdf-delay", "2.0", "Seconds between two PDF requests to the same host"),
+    option("enrich", "extract_fulltext", "extract-fulltext", "false", "Extract text, references and affiliations of the downloaded PDFs with GROBID"),
+    option("enrich", "grobid_url", "grobid-url", "\"http://localhost:8070\"", "GROBID server used by extract_fulltext"),
+    option("enrich", "scopus_key", "scopus-key", "\"...\"", "Elsevier API key for the Scopus abstract source"),
+    option("enrich", "altmetric_key", "altmetric-key", "\"...\"", "Altmetric API key for the altmetric enricher (optional)"),
+    option("filters", "min_citations", "min-citations", "50", "Keep papers cited at least this many times"),
+    option("filters", "work_types", "work-types", "[\"article\", \"review\"]", "Keep only these work types"),
+    option("filters", "country", "country", "[\"CN\", \"IT\"]", "Keep papers with an author from these countries"),
+    option("filters", "exclude_reviews", "exclude-reviews", "true", "Drop review articles in Stage 5"),
+    option("filters", "only_reviews", "only-reviews", "true", "Keep only review articles in Stage 5"),
+    option("filters", "sciif", "sciif", "5.0", "Impact Factor >= value"),
+    option("filters", "jci", "jci", "1.0", "JCI >= value"),
+    option("filters", "sci", "sci", "\"Q1\"", "SCI partition"),
+    option("filters", "sci_up_top", "sci-up-top", "\"1区\"", "sciUpTop (substring match)"),
+    option("filters", "sci_base", "sci-base", "\"1区\"", "sciBase (substring match)"),
+    option("filters", "sci_up", "sci-up", "\"1区\"", "sciUp (substring match)"),
+    option("filters", "zky", "zky", "\"1区\"", "CAS partition (substring match)"),
+    option("filters", "pku", "pku", "\"北大核心\"", "北大核心 (substring match)"),
+    option("filters", "cssci", "cssci", "\"CSSCI\"", "CSSCI (substring match)"),
+    option("filters", "cscd", "cscd", "\"CSCD\"", "CSCD (substring match)"),
+    option("filters", "eii", "eii", "\"EI\"", "EI (substring match)"),
+    option("filters", "ccf", "ccf", "\"A\"", "CCF rank (substring match)"),
+    option("filters", "core", "core", "\"A\"", "CORE conference rank (substring match)"),
+    option("filters", "rank_filter", "rank-filter", "\"sciif>=5 && sci~'Q1'\"", "Ranking filter expression"),
+    option("llm", "provider", "llm-provider", "\"openai\"", "openai or ollama"),
+    option("llm", "base_url", "llm-base-url", "\"https://api.openai.com/v1\"", "API base URL (enables Stage 6)"),
+    option("llm", "key", "llm-key", "\"sk-...\"", "API key"),
+    option("llm", "model", "llm-model", "\"gpt-4o-mini\"", "Model name"),
+    option("llm", "json_mode", "llm-json-mode", "\"auto\"", "auto, schema, object, or off"),
+    option("llm", "max_retries", "llm-max-retries", "3", "Retries for transient API errors"),
+    option("llm", "cache_days", "llm-cache-days", "30", "Days to keep cached replies (0 disables)"),
+    option("llm", "concurrency", "llm-concurrency", "10", "Maximum concurrent requests"),
+    option("llm", "timeout", "llm-timeout", "60", "Per-request timeout in seconds"),
+    option("llm", "temperature", "llm-temperature", "0.1", "Sampling temperature"),
+    option("llm", "no_temperature", "llm-no-temperature", "false", "Omit temperature (reasoning models)"),
+    option("llm", "max_tokens", "llm-max-tokens", "20000", "Maximum completion tokens per request"),
+    option("llm", "price", "llm-price", "\"0.15,0.6\"", "USD per 1M input,output tokens (--dry-run cost)"),
+    option("llm", "filter_help", "filter-help", "[\"landslide\", \"slope\"]", "Keywords guiding relevance filtering"),
+    option("llm", "prefilter_include", "prefilter-include", "[\"landslide\", \"slope (failure|stability)\"]", "Papers matching none of these patterns are irrelevant without an LLM call"),
+    option("llm", "prefilter_exclude", "prefilter-exclude", "[\"stock market\"]", "Papers matching one of these patterns are irrelevant without an LLM call"),
+    option("llm", "criteria", "criteria", "\"criteria.yaml\"", "Inclusion/exclusion criteria file (screening mode)"),
+    option("llm", "review_model", "llm-review-model", "\"gpt-4o\"", "Stronger model for uncertain verdicts"),
+    option("llm", "review_below", "llm-review-below", "0.7", "Confidence below which verdicts are re-screened"),
+    option("llm", "min_confidence", "llm-min-confidence", "0.0", "Minimum confidence for Stage 7"),
+    option("llm", "uncertain_policy", "uncertain-policy", "\"drop\"", "keep, drop, or review"),
+    option("llm", "tags", "tags", "[\"method\", \"case study\", \"review\"]", "Topic categories for Stage 8"),
+    option("llm", "study_designs", "study-designs", "[\"experimental\", \"case study\"]", "Study designs for Stage 8"),
+    option("llm", "expand_query", "expand-query", "false", "Suggest query variants before searching"),
+    option("llm", "llm_fulltext", "llm-fulltext", "4000", "Full-text characters given to the LLM for papers with a short or missing abstract"),
+    option("zotero", "collection", "push-zotero", "\"Landslide review\"", "Push relevant papers to this collection"),
+    option("zotero", "key", "zotero-key", "\"your-zotero-key\"", "API key with write access"),
+    option("zotero", "library_id", "zotero-library-id", "\"1234567\"", "User or group library ID"),
+    option("zotero", "group", "zotero-group", "false", "Treat library_id as a group library"),
+    option("output", "format", "output-format", "\"csv\"", "csv, parquet, sqlite, or jsonl"),
+    option("output", "xlsx", "xlsx", "false", "Also write results.xlsx"),
+    option("output", "prisma_svg", "prisma-svg", "false", "Also draw prisma.svg"),
+    option("output", "save_raw", "save-raw", "false", "Keep raw responses under raw/ (gzipped)"),
+    option("watch", "interval", "interval", "\"7d\"", "Time between update runs (s, m, h, d or w)"),
+    option("watch", "notify_webhook", "notify-webhook", "\"https://example.org/hook\"", "POST new papers as JSON"),
+    option("watch", "notify_slack", "notify-slack", "\"https://hooks.slack.com/services/...\"", "Slack incoming webhook"),
+    option("watch", "notify_email", "notify-email", "\"me@example.org\"", "Email new papers (local sendmail)"),
+];
+
+/// Flags taking a single comma-separated list, given an array joined with
+/// commas rather than repeated
+const JOINED_FLAGS: &[&str] = &["filter-help", "tags", "study-designs"];
+
+/// Config file format, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            _ => Err(GscholarError::Config(format!(
+                "Unsupported config file {:?} (expected .toml, .yaml or .yml)",
+                path
+            ))),
+        }
+    }
+}
+
+/// Load a config file as arguments of `command` (e.g., `["--llm-model", "gpt-4o"]`)
+pub fn load(path: &Path, command: &str) -> Result<Vec<String>> {
+    read_document(path).and_then(|value| to_args(&value, command)).map_err(|e| match e {
+        GscholarError::Config(msg) => GscholarError::Config(format!("{:?}: {}", path, msg)),
+        other => other,
+    })
+}
+
+/// Parse a TOML or YAML file (by extension) into a JSON value
+pub(crate) fn read_document(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)?;
+    match ConfigFormat::from_path(path)? {
+        ConfigFormat::Toml => toml::from_str(&content).map_err(|e| GscholarError::Config(format!("invalid TOML: {}", e))),
+        ConfigFormat::Yaml => serde_yaml::from_str(&content).map_err(|e| GscholarError::Config(format!("invalid YAML: {}", e))),
+    }
+}
+
+/// Map a parsed config document to arguments of `command`
+fn to_args(document: &Value, command: &str) -> Result<Vec<String>> {
+    let sections = match document {
+        Value::Object(sections) => sections,
+        Value::Null => return Ok(Vec::new()),
+        _ => return Err(GscholarError::Config("expected sections of key = value pairs".to_string())),
+    };
+
+    let mut args = Vec::new();
+    for (section, keys) in sections {
+        let Value::Object(keys) = keys else {
+            return Err(GscholarError::Config(format!("\"{}\" is not a section", section)));
+        };
+        for (key, value) in keys {
+            let option = OPTIONS
+                .iter()
+                .find(|o| o.section == section && o.key == key)
+                .ok_or_else(|| GscholarError::Config(format!("unknown option {}.{}", section, key)))?;
+            if section == "watch" && command != "watch" {
+                continue;
+            }
+            if option.flag.is_empty() {
+                let Value::String(positional) = value else {
+                    return Err(GscholarError::Config(format!("{}.{}: expected a string", section, key)));
+                };
+                args.insert(0, positional.clone());
+                continue;
+            }
+            let flag = format!("--{}", option.flag);
+            match value {
+                Value::Bool(true) => args.push(flag),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(s) => args.extend([flag, s.clone()]),
+                Value::Number(n) => args.extend([flag, n.to_string()]),
+                Value::Array(items) => {
+                    let items = items
+                        .iter()
+                        .map(|item| match item {
+                            Value::String(s) => Ok(s.clone()),
+                            Value::Number(n) => Ok(n.to_string()),
+                            _ => Err(GscholarError::Config(format!("{}.{}: arrays may hold only strings and numbers", section, key))),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    if JOINED_FLAGS.contains(&option.flag) {
+                        args.extend([flag, items.join(",")]);
+                    } else {
+                        args.extend(items.into_iter().flat_map(|item| [flag.clone(), item]));
+                    }
+                }
+                Value::Object(_) => {
+                    return Err(GscholarError::Config(format!("{}.{}: expected a value, found a table", section, key)));
+                }
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Annotated starter config listing every option, commented out
+pub fn scaffold(format: ConfigFormat) -> String {
+    let mut out = String::from(
+        "# rustgscholar search configuration\n# Uncomment the options you need; flags given on the command line override this file.\n",
+    );
+    let mut section = "";
+    for option in OPTIONS {
+        if option.section != section {
+            section = option.section;
+            out.push_str(&match format {
+                ConfigFormat::Toml => format!("\n[{}]\n", section),
+                ConfigFormat::Yaml => format!("\n{}:\n", section),
+            });
+        }
+        out.push_str(&match format {
+            ConfigFormat::Toml => format!("# {} = {}  # {}\n", option.key, option.example, option.help),
+            ConfigFormat::Yaml => format!("  # {}: {}  # {}\n", option.key, option.example, option.help),
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_toml(content: &str) -> Result<Value> {
+        toml::from_str(content).map_err(|e| GscholarError::Config(e.to_string()))
+    }
+
+    #[test]
+    fn test_toml_to_args() -> Result<()> {
+        let toml = r#"
+# review settings
+[search]
+source = "openalex"   # comment
+pages = '1-3'
+
+[filters]
+sciif = 5.0
+rank_filter = "sci~'Q1' # not a comment"
+
+[llm]
+model = "gpt-4o"
+tags = ["method", "case study",]
+prefilter_include = [
+    'slope.{0,20}failure',
+    "landslide",
+]
+expand_query = true
+no_temperature = false
+"#;
+        let args = to_args(&parse_toml(toml)?, "search")?;
+        assert_eq!(
+            args,
+            vec![
+                "--source", "openalex", "--pages", "1-3", "--sciif", "5.0", "--rank-filter", "sci~'Q1' # not a comment",
+                "--llm-model", "gpt-4o", "--tags", "method,case study", "--prefilter-include", "slope.{0,20}failure",
+                "--prefilter-include", "landslide", "--expand-query",
+            ]
+        );
+
+        assert!(parse_toml("[llm]\nmodel = \"a\"\nmodel = \"b\"").is_err());
+        assert!(parse_toml("[llm]\nmodel = gpt").is_err());
+        assert!(to_args(&parse_toml("[llm]\nmodle = \"gpt\"")?, "search").is_err());
+
+        let watch = parse_toml("[watch]\ninterval = \"7d\"\n[search]\nsource = \"openalex\"\nkeyword = \"landslide\"")?;
+        assert_eq!(to_args(&watch, "search")?, vec!["landslide", "--source", "openalex"]);
+        assert_eq!(to_args(&watch, "watch")?, vec!["landslide", "--interval", "7d", "--source", "openalex"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scaffold_parses() -> Result<()> {
+        // Uncommenting every line of the scaffold gives a valid config
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let uncommented: String = scaffold(format)
+                .lines()
+                .filter(|l| !l.starts_with("# rustgscholar") && !l.starts_with("# Uncomment"))
+                .map(|l| l.replacen("# ", "", 1) + "\n")
+                .collect();
+            let value = match format {
+                ConfigFormat::Toml => parse_toml(&uncommented)?,
+                ConfigFormat::Yaml => serde_yaml::from_str(&uncommented).map_err(|e| GscholarError::Config(e.to_string()))?,
+            };
+            let args = to_args(&value, "watch")?;
+            assert!(args.contains(&"--easyscholar-key".to_string()));
+            assert!(!args.contains(&"--prisma-svg".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+//! Google cookies from a local browser profile (feature "cookie-import").
+//!
+//! `cookies import` reads the cookie store of Chrome, Chromium, Edge or
+//! Firefox directly, so a session the user already has in their browser can
+//! be reused without copying cookies out of the developer tools. Only
+//! cookies of `google.com` and its subdomains are taken.
+//!
+//! Firefox keeps cookie values in plain text. Chromium-based browsers
+//! encrypt them with a key from the OS: a fixed password or the keyring
+//! (Linux), the keychain (macOS) or DPAPI (Windows). The store is copied
+//! before reading, since a running browser keeps it locked.
+
+use crate::cookies::{is_google_host, Cookie};
+use crate::error::{GscholarError, Result};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, info, warn};
+
+/// Seconds between 1601-01-01 (Chromium timestamps) and the Unix epoch
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Cookie store versions from which decrypted values start with a SHA-256
+/// of the host
+const HOST_DIGEST_VERSION: i64 = 24;
+
+/// A browser whose cookie store can be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowserKind {
+    Chrome,
+    Chromium,
+    Edge,
+    Firefox,
+}
+
+impl FromStr for BrowserKind {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "chrome" | "google-chrome" => Ok(Self::Chrome),
+            "chromium" => Ok(Self::Chromium),
+            "edge" | "msedge" | "microsoft-edge" => Ok(Self::Edge),
+            "firefox" => Ok(Self::Firefox),
+            _ => Err(GscholarError::Config(format!(
+                "Unsupported browser '{}' (expected chrome, chromium, edge or firefox)",
+                s
+            ))),
+        }
+    }
+}
+
+impl BrowserKind {
+    /// Name of the browser's entry in the OS keyring / keychain
+    fn keyring_name(self) -> &'static str {
+        match self {
+            Self::Chrome => "Chrome",
+            Self::Chromium => "Chromium",
+            Self::Edge => "Microsoft Edge",
+            Self::Firefox => "Firefox",
+        }
+    }
+
+    /// Folder holding the browser's profiles
+    fn user_data_dir(self) -> Result<PathBuf> {
+        let missing = || GscholarError::Config("Cannot determine the browser profile folder".to_string());
+        let path = if cfg!(windows) {
+            match self {
+                Self::Chrome => dirs::data_local_dir().ok_or_else(missing)?.join("Google/Chrome/User Data"),
+                Self::Chromium => dirs::data_local_dir().ok_or_else(missing)?.join("Chromium/User Data"),
+                Self::Edge => dirs::data_local_dir().ok_or_else(missing)?.join("Microsoft/Edge/User Data"),
+                Self::Firefox => dirs::config_dir().ok_or_else(missing)?.join("Mozilla/Firefox"),
+            }
+        } else if cfg!(target_os = "macos") {
+            let support = dirs::config_dir().ok_or_else(missing)?;
+            match self {
+                Self::Chrome => support.join("Google/Chrome"),
+                Self::Chromium => support.join("Chromium"),
+                Self::Edge => support.join("Microsoft Edge"),
+                Self::Firefox => support.join("Firefox"),
+            }
+        } else {
+            match self {
+                Self::Chrome => dirs::config_dir().ok_or_else(missing)?.join("google-chrome"),
+                Self::Chromium => dirs::config_dir().ok_or_else(missing)?.join("chromium"),
+                Self::Edge => dirs::config_dir().ok_or_else(missing)?.join("microsoft-edge"),
+                Self::Firefox => dirs::home_dir().ok_or_else(missing)?.join(".mozilla/firefox"),
+            }
+        };
+        Ok(path)
+    }
+}
+
+/// Google cookies of a browser profile
+///
+/// `profile` is a profile name (Chromium: `Default`, `Profile 1`; Firefox:
+/// the name in `profiles.ini`) or a profile folder; the default profile
+/// is used without one.
+pub fn import(browser: BrowserKind, profile: Option<&str>) -> Result<Vec<Cookie>> {
+    let profile_dir = profile_dir(browser, profile)?;
+    info!(browser = ?browser, profile = ?profile_dir, "Importing browser cookies");
+    let cookies = match browser {
+        BrowserKind::Firefox => read_firefox(&profile_dir.join("cookies.sqlite"))?,
+        _ => {
+            let store = ["Network/Cookies", "Cookies"]
+                .iter()
+                .map(|name| profile_dir.join(name))
+                .find(|path| path.is_file())
+                .ok_or_else(|| GscholarError::Config(format!("No cookie store in {:?}", profile_dir)))?;
+            read_chromium(&store, &chromium_key(browser, &profile_dir)?)?
+        }
+    };
+    info!(count = cookies.len(), "Imported Google cookies");
+    Ok(cookies)
+}
+
+fn profile_dir(browser: BrowserKind, profile: Option<&str>) -> Result<PathBuf> {
+    if let Some(path) = profile.map(PathBuf::from).filter(|p| p.is_dir()) {
+        return Ok(path);
+    }
+    let base = browser.user_data_dir()?;
+    let dir = match browser {
+        BrowserKind::Firefox => {
+            let ini = std::fs::read_to_string(base.join("profiles.ini")).map_err(|e| {
+                GscholarError::Config(format!("Cannot read Firefox profiles in {:?}: {}", base, e))
+            })?;
+            let path = firefox_profile(&ini, profile)
+                .ok_or_else(|| GscholarError::Config(format!("No Firefox profile {}", profile.unwrap_or("marked default"))))?;
+            base.join(path)
+        }
+        _ => base.join(profile.unwrap_or("Default")),
+    };
+    if !dir.is_dir() {
+        return Err(GscholarError::Config(format!("Browser profile not found: {:?}", dir)));
+    }
+    Ok(dir)
+}
+
+/// Path (relative to the Firefox folder, or absolute) of a profile in `profiles.ini`
+///
+/// Without a name: the default of an install section, else the profile
+/// marked `Default=1`, else the first one.
+fn firefox_profile(ini: &str, name: Option<&str>) -> Option<String> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in ini.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((section.to_string(), Vec::new()));
+        } else if let (Some((key, value)), Some((_, entries))) = (line.split_once('='), sections.last_mut()) {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let get = |entries: &[(String, String)], key: &str| entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    let profiles: Vec<_> = sections.iter().filter(|(s, _)| s.starts_with("Profile")).map(|(_, e)| e).collect();
+
+    if let Some(name) = name {
+        return profiles.iter().find_map(|entries| {
+            let path = get(entries, "Path")?;
+            let matches = get(entries, "Name").as_deref() == Some(name)
+                || Path::new(&path).file_name().and_then(|f| f.to_str()) == Some(name);
+            matches.then_some(path)
+        });
+    }
+    sections
+        .iter()
+        .filter(|(s, _)| s.starts_with("Install"))
+        .find_map(|(_, entries)| get(entries, "Default"))
+        .or_else(|| profiles.iter().find(|e| get(e, "Default").as_deref() == Some("1")).and_then(|e| get(e, "Path")))
+        .or_else(|| profiles.first().and_then(|e| get(e, "Path")))
+}
+
+/// Open a copy of a cookie store (with its write-ahead log), which a
+/// running browser keeps locked
+fn open_copy(store: &Path) -> Result<(Connection, TempCopy)> {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    let dir = std::env::temp_dir().join(format!("rustgscholar-cookies-{}-{}", std::process::id(), nanos));
+    std::fs::create_dir_all(&dir)?;
+    let copy = TempCopy(dir);
+    let target = copy.0.join("cookies.sqlite");
+    std::fs::copy(store, &target)?;
+    let mut wal = store.as_os_str().to_owned();
+    wal.push("-wal");
+    if Path::new(&wal).is_file() {
+        std::fs::copy(&wal, copy.0.join("cookies.sqlite-wal"))?;
+    }
+    let connection = Connection::open(&target).map_err(sqlite_error)?;
+    Ok((connection, copy))
+}
+
+/// Temporary folder removed when dropped
+struct TempCopy(PathBuf);
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            debug!(error = %e, "Failed to remove the cookie store copy");
+        }
+    }
+}
+
+fn sqlite_error(error: rusqlite::Error) -> GscholarError {
+    GscholarError::Parse(format!("Cookie store: {}", error))
+}
+
+// === Firefox ===
+
+fn read_firefox(store: &Path) -> Result<Vec<Cookie>> {
+    if !store.is_file() {
+        return Err(GscholarError::Config(format!("No cookie store at {:?}", store)));
+    }
+    let (connection, _copy) = open_copy(store)?;
+    let mut statement = connection
+        .prepare("SELECT host, name, value, path, expiry, isSecure, isHttpOnly FROM moz_cookies")
+        .map_err(sqlite_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            let expiry: i64 = row.get(4)?;
+            Ok(Cookie {
+                domain: row.get(0)?,
+                name: row.get(1)?,
+                value: row.get(2)?,
+                path: row.get(3)?,
+                // Newer Firefox versions store milliseconds
+                expires: Some(if expiry > 100_000_000_000 { expiry as f64 / 1000.0 } else { expiry as f64 }),
+                secure: row.get::<_, i64>(5)? != 0,
+                http_only: row.get::<_, i64>(6)? != 0,
+            })
+        })
+        .map_err(sqlite_error)?;
+    let mut cookies = Vec::new();
+    for cookie in rows {
+        let cookie = cookie.map_err(sqlite_error)?;
+        if is_google_host(&cookie.domain) {
+            cookies.push(cookie);
+        }
+    }
+    Ok(cookies)
+}
+
+// === Chromium ===
+
+/// Key of a Chromium cookie store
+enum ChromiumKey {
+    /// AES-128-CBC keys of `v10` and `v11` values (Linux, macOS)
+    Cbc { v10: [u8; 16], v11: Option<[u8; 16]> },
+    /// AES-256-GCM key of `v10` values (Windows)
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Gcm([u8; 32]),
+}
+
+/// AES key derived from a keyring password as Chromium does
+fn derive_key(password: &[u8], iterations: u32) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, b"saltysalt", iterations, &mut key);
+    key
+}
+
+#[cfg(target_os = "macos")]
+fn chromium_key(browser: BrowserKind, _profile_dir: &Path) -> Result<ChromiumKey> {
+    let service = format!("{} Safe Storage", browser.keyring_name());
+    let output = std::process::Command::new("security")
+        .args(["find-generic-password", "-w", "-s", &service])
+        .output()?;
+    if !output.status.success() {
+        return Err(GscholarError::Config(format!("Cannot read '{}' from the keychain", service)));
+    }
+    let password = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(ChromiumKey::Cbc { v10: derive_key(password.as_bytes(), 1003), v11: None })
+}
+
+#[cfg(windows)]
+fn chromium_key(_browser: BrowserKind, profile_dir: &Path) -> Result<ChromiumKey> {
+    use base64::Engine;
+
+    let local_state = profile_dir
+        .parent()
+        .map(|dir| dir.join("Local State"))
+        .ok_or_else(|| GscholarError::Config("No Local State next to the profile".to_string()))?;
+    let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&local_state)?)?;
+    let encoded = state["os_crypt"]["encrypted_key"]
+        .as_str()
+        .ok_or_else(|| GscholarError::Parse("No os_crypt.encrypted_key in Local State".to_string()))?;
+    let encrypted = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| GscholarError::Parse(format!("Invalid encrypted_key: {}", e)))?;
+    let encrypted = encrypted
+        .strip_prefix(b"DPAPI")
+        .ok_or_else(|| GscholarError::Parse("encrypted_key is not DPAPI-protected".to_string()))?;
+    let key: [u8; 32] = dpapi_unprotect(encrypted)?
+        .try_into()
+        .map_err(|_| GscholarError::Parse("Unexpected cookie key length".to_string()))?;
+    Ok(ChromiumKey::Gcm(key))
+}
+
+/// Decrypt data protected for the current Windows user
+#[cfg(windows)]
+fn dpapi_unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Cryptography::{CryptUnprotectData, CRYPT_INTEGER_BLOB};
+
+    let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+    let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+    // SAFETY: `input` points to `data`, which outlives the call; the output
+    // buffer is allocated by the call and freed with LocalFree after copying
+    let ok = unsafe {
+        CryptUnprotectData(&input, std::ptr::null_mut(), std::ptr::null(), std::ptr::null(), std::ptr::null(), 0, &mut output)
+    };
+    if ok == 0 || output.pbData.is_null() {
+        return Err(GscholarError::Config("DPAPI could not decrypt the cookie key".to_string()));
+    }
+    // SAFETY: on success `pbData` holds `cbData` bytes
+    let plain = unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+    // SAFETY: the buffer was allocated by CryptUnprotectData
+    unsafe { LocalFree(output.pbData as _) };
+    Ok(plain)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn chromium_key(browser: BrowserKind, _profile_dir: &Path) -> Result<ChromiumKey> {
+    // v11 values use a password from the Secret Service keyring
+    let application = match browser {
+        BrowserKind::Chromium => "chromium",
+        BrowserKind::Edge => "microsoft-edge",
+        _ => "chrome",
+    };
+    let v11 = std::process::Command::new("secret-tool")
+        .args(["lookup", "application", application])
+        .output()
+        .ok()
+        .filter(|output| output.status.success() && !output.stdout.is_empty())
+        .map(|output| derive_key(String::from_utf8_lossy(&output.stdout).trim().as_bytes(), 1));
+    if v11.is_none() {
+        debug!(browser = browser.keyring_name(), "No keyring password; only v10 cookies can be read");
+    }
+    Ok(ChromiumKey::Cbc { v10: derive_key(b"peanuts", 1), v11 })
+}
+
+fn read_chromium(store: &Path, key: &ChromiumKey) -> Result<Vec<Cookie>> {
+    let (connection, _copy) = open_copy(store)?;
+    let version: i64 = connection
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| row.get::<_, String>(0))
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default();
+    let mut statement = connection
+        .prepare(
+            "SELECT host_key, name, value, encrypted_value, path, expires_utc, is_secure, is_httponly, has_expires FROM cookies",
+        )
+        .map_err(sqlite_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, i64>(6)? != 0,
+                row.get::<_, i64>(7)? != 0,
+                row.get::<_, i64>(8)? != 0,
+            ))
+        })
+        .map_err(sqlite_error)?;
+
+    let mut cookies = Vec::new();
+    let mut undecryptable = 0;
+    for row in rows {
+        let (host, name, value, encrypted, path, expires_utc, secure, http_only, has_expires) = row.map_err(sqlite_error)?;
+        if !is_google_host(&host) {
+            continue;
+        }
+        let value = if encrypted.is_empty() {
+            value
+        } else {
+            match decrypt_value(&encrypted, key, version >= HOST_DIGEST_VERSION) {
+                Some(value) => value,
+                None => {
+                    undecryptable += 1;
+                    continue;
+                }
+            }
+        };
+        let expires = (has_expires && expires_utc > 0).then(|| (expires_utc / 1_000_000 - WINDOWS_EPOCH_OFFSET) as f64);
+        cookies.push(Cookie { name, value, domain: host, path, secure, http_only, expires });
+    }
+    if undecryptable > 0 {
+        warn!(count = undecryptable, "Skipped cookies that could not be decrypted");
+    }
+    Ok(cookies)
+}
+
+/// Plain text of an encrypted Chromium cookie value
+fn decrypt_value(encrypted: &[u8], key: &ChromiumKey, host_digest: bool) -> Option<String> {
+    use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+
+    let (prefix, data) = encrypted.split_at_checked(3)?;
+    let plain = match key {
+        ChromiumKey::Cbc { v10, v11 } => {
+            let key = match prefix {
+                b"v10" => v10,
+                b"v11" => v11.as_ref()?,
+                _ => return None,
+            };
+            cbc::Decryptor::<aes::Aes128>::new(key.into(), &[b' '; 16].into())
+                .decrypt_padded_vec_mut::<Pkcs7>(data)
+                .ok()?
+        }
+        #[cfg(windows)]
+        ChromiumKey::Gcm(key) => {
+            use aes_gcm::aead::{Aead, KeyInit};
+            // v20 (app-bound encryption) needs the browser's elevation service
+            if prefix != b"v10" {
+                return None;
+            }
+            let (nonce, ciphertext) = data.split_at_checked(12)?;
+            aes_gcm::Aes256Gcm::new(key.into()).decrypt(nonce.into(), ciphertext).ok()?
+        }
+        #[cfg(not(windows))]
+        ChromiumKey::Gcm(_) => return None,
+    };
+    let plain = if host_digest { plain.get(32..)?.to_vec() } else { plain };
+    String::from_utf8(plain).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+
+    #[test]
+    fn test_read_chromium_store() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let store = dir.path().join("Cookies");
+        let db = Connection::open(&store).map_err(sqlite_error)?;
+        db.execute_batch(
+            "CREATE TABLE meta (key TEXT, value TEXT);
+             INSERT INTO meta VALUES ('version', '24');
+             CREATE TABLE cookies (host_key TEXT, name TEXT, value TEXT, encrypted_value BLOB, path TEXT,
+                 expires_utc INTEGER, is_secure INTEGER, is_httponly INTEGER, has_expires INTEGER);",
+        )
+        .map_err(sqlite_error)?;
+
+        let key = derive_key(b"peanuts", 1);
+        let mut plain = vec![0u8; 32];
+        plain.extend_from_slice(b"secret-nid");
+        let mut encrypted = b"v10".to_vec();
+        encrypted.extend(cbc::Encryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into()).encrypt_padded_vec_mut::<Pkcs7>(&plain));
+        let expires_utc = (1_800_000_000 + WINDOWS_EPOCH_OFFSET) * 1_000_000;
+        db.execute(
+            "INSERT INTO cookies VALUES ('.google.com', 'NID', '', ?1, '/', ?2, 1, 1, 1)",
+            rusqlite::params![encrypted, expires_utc],
+        )
+        .map_err(sqlite_error)?;
+        db.execute("INSERT INTO cookies VALUES ('.example.com', 'x', 'y', x'', '/', 0, 0, 0, 0)", [])
+            .map_err(sqlite_error)?;
+        drop(db);
+
+        let cookies = read_chromium(&store, &ChromiumKey::Cbc { v10: key, v11: None })?;
+        assert_eq!(cookies.len(), 1);
+        assert_eq!((cookies[0].name.as_str(), cookies[0].value.as_str()), ("NID", "secret-nid"));
+        assert_eq!(cookies[0].expires, Some(1_800_000_000.0));
+        assert!(cookies[0].secure && cookies[0].http_only);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_firefox_store() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let store = dir.path().join("cookies.sqlite");
+        let db = Connection::open(&store).map_err(sqlite_error)?;
+        db.execute_batch(
+            "CREATE TABLE moz_cookies (host TEXT, name TEXT, value TEXT, path TEXT, expiry INTEGER, isSecure INTEGER, isHttpOnly INTEGER);
+             INSERT INTO moz_cookies VALUES ('scholar.google.com', 'GSP', 'LM=1', '/', 1800000000000, 0, 0);
+             INSERT INTO moz_cookies VALUES ('notgoogle.com', 'a', 'b', '/', 1800000000, 0, 0);",
+        )
+        .map_err(sqlite_error)?;
+        drop(db);
+
+        let cookies = read_firefox(&store)?;
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].value, "LM=1");
+        assert_eq!(cookies[0].expires, Some(1_800_000_000.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_firefox_profile() {
+        let ini = "[Profile1]\nName=work\nIsRelative=1\nPath=Profiles/abc.work\n\n\
+                   [Profile0]\nName=default\nIsRelative=1\nPath=Profiles/xyz.default\nDefault=1\n\n\
+                   [Install4F96D1932A9F858E]\nDefault=Profiles/def.default-release\n";
+        assert_eq!(firefox_profile(ini, None).as_deref(), Some("Profiles/def.default-release"));
+        assert_eq!(firefox_profile(ini, Some("work")).as_deref(), Some("Profiles/abc.work"));
+        assert_eq!(firefox_profile(ini, Some("xyz.default")).as_deref(), Some("Profiles/xyz.default"));
+        assert_eq!(firefox_profile(ini, Some("missing")), None);
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! This module handles cookie persistence to maintain session state
 //! and avoid rate limiting from Google Scholar.
+//!
+//! Cookie files are read in the formats browser extensions export: a JSON
+//! array (Playwright, EditThisCookie, Cookie-Editor), a Playwright storage
+//! state (`{"cookies": [...]}`) or a Netscape `cookies.txt`. They are always
+//! saved as a JSON array.
 
 use crate::error::{GscholarError, Result};
 use serde::{Deserialize, Serialize};
@@ -17,7 +22,10 @@ fn default_cookie_path() -> Result<PathBuf> {
 }
 
 /// Cookie entry matching Playwright's cookie format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Also reads the field names of EditThisCookie / Cookie-Editor exports
+/// (`httpOnly`, `expirationDate`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Cookie {
     pub name: String,
     pub value: String,
@@ -26,12 +34,73 @@ pub struct Cookie {
     pub path: String,
     #[serde(default)]
     pub secure: bool,
-    #[serde(default)]
+    #[serde(default, alias = "httpOnly")]
     pub http_only: bool,
-    #[serde(default)]
+    #[serde(default, alias = "expirationDate")]
     pub expires: Option<f64>,
 }
 
+/// Playwright storage state, which keeps the cookies next to local storage
+#[derive(Deserialize)]
+struct StorageState {
+    cookies: Vec<Cookie>,
+}
+
+/// Whether a cookie host belongs to google.com
+pub fn is_google_host(host: &str) -> bool {
+    let host = host.trim_start_matches('.').to_ascii_lowercase();
+    host == "google.com" || host.ends_with(".google.com")
+}
+
+/// Parse a cookie file: a JSON array, a storage state or Netscape `cookies.txt`
+pub fn parse_cookies(content: &str) -> Result<Vec<Cookie>> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+    if content.starts_with('[') {
+        Ok(serde_json::from_str(content)?)
+    } else if content.starts_with('{') {
+        Ok(serde_json::from_str::<StorageState>(content)?.cookies)
+    } else {
+        parse_netscape(content)
+    }
+}
+
+/// Parse Netscape `cookies.txt` (curl, wget, yt-dlp and export extensions)
+///
+/// Lines are `domain  include-subdomains  path  secure  expires  name  value`,
+/// tab-separated; `#HttpOnly_` before the domain marks HTTP-only cookies.
+pub fn parse_netscape(content: &str) -> Result<Vec<Cookie>> {
+    let mut cookies = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 7 {
+            return Err(GscholarError::Parse(format!(
+                "cookies.txt line {}: expected 7 tab-separated fields, found {}",
+                number + 1,
+                fields.len()
+            )));
+        }
+        let expires: f64 = fields[4].trim().parse().unwrap_or_default();
+        cookies.push(Cookie {
+            domain: fields[0].to_string(),
+            path: fields[2].to_string(),
+            secure: fields[3].eq_ignore_ascii_case("TRUE"),
+            expires: (expires > 0.0).then_some(expires),
+            name: fields[5].to_string(),
+            // Values may contain tabs
+            value: fields[6..].join("\t").trim_end_matches('\r').to_string(),
+            http_only,
+        });
+    }
+    Ok(cookies)
+}
+
 /// Cookie manager for loading and saving cookies
 pub struct CookieManager {
     path: PathBuf,
@@ -65,7 +134,7 @@ impl CookieManager {
         }
 
         match std::fs::read_to_string(&self.path) {
-            Ok(content) => match serde_json::from_str::<Vec<Cookie>>(&content) {
+            Ok(content) => match parse_cookies(&content) {
                 Ok(cookies) => {
                     info!("Loaded {} cookies from {:?}", cookies.len(), self.path);
                     cookies
@@ -119,7 +188,6 @@ impl Default for CookieManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -128,6 +196,29 @@ mod tests {
         assert!(manager.load().is_empty());
     }
 
+    #[test]
+    fn test_parse_export_formats() -> Result<()> {
+        let netscape = "# Netscape HTTP Cookie File\n\
+                        .google.com\tTRUE\t/\tTRUE\t1800000000\tNID\tabc\n\
+                        #HttpOnly_scholar.google.com\tFALSE\t/\tFALSE\t0\tGSP\tLM=1\n";
+        let cookies = parse_cookies(netscape)?;
+        assert_eq!(cookies.len(), 2);
+        assert_eq!((cookies[0].name.as_str(), cookies[0].expires, cookies[0].secure), ("NID", Some(1800000000.0), true));
+        assert_eq!((cookies[1].domain.as_str(), cookies[1].http_only, cookies[1].expires), ("scholar.google.com", true, None));
+        assert!(parse_netscape(".google.com\tTRUE\t/").is_err());
+
+        let edit_this_cookie = r#"[{"domain": ".google.com", "expirationDate": 1800000000.5, "hostOnly": false,
+            "httpOnly": true, "name": "NID", "path": "/", "sameSite": "no_restriction", "secure": true,
+            "session": false, "storeId": "0", "value": "abc", "id": 1}]"#;
+        let cookies = parse_cookies(edit_this_cookie)?;
+        assert_eq!((cookies[0].http_only, cookies[0].expires), (true, Some(1800000000.5)));
+
+        let storage_state = r#"{"cookies": [{"name": "NID", "value": "abc", "domain": ".google.com"}], "origins": []}"#;
+        assert_eq!(parse_cookies(storage_state)?.len(), 1);
+        assert!(is_google_host(".google.com") && is_google_host("scholar.google.com") && !is_google_host("evilgoogle.com"));
+        Ok(())
+    }
+
     #[test]
     fn test_save_and_load() -> Result<()> {
         let temp = NamedTempFile::new()?;
@@ -3,17 +3,25 @@
 //! This module provides concurrent lookup of article metadata via the Crossref API,
 //! including DOI, journal name, authors, publication date, and abstract.
 
+use crate::cache::{self, HttpCache};
+use crate::doi;
 use crate::error::{GscholarError, Result};
+use crate::http::{self, RetryPolicy};
+use crate::matching::TitleMatcher;
+use crate::ratelimit;
+use crate::raw_archive::{self, BodyKind};
+use crate::throttle::{self, Upstream};
 use futures::future::join_all;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
 /// Crossref API base URL
-const CROSSREF_API_URL: &str = "https://api.crossref.org/works";
+pub const CROSSREF_API_URL: &str = "https://api.crossref.org/works";
 
 /// Polite pool email for Crossref API
 const MAILTO: &str = "gscholar-rust@example.com";
@@ -25,6 +33,8 @@ pub struct CrossrefMetadata {
     pub doi: String,
     /// Journal name
     pub journal: String,
+    /// Journal ISSN (first listed)
+    pub issn: String,
     /// Authors (comma-separated)
     pub authors: String,
     /// Publication date (YYYY-MM-DD or partial)
@@ -33,13 +43,25 @@ pub struct CrossrefMetadata {
     pub abstract_text: String,
     /// Title from Crossref (for verification)
     pub crossref_title: String,
+    /// Work type ("journal-article", "proceedings-article", ...)
+    #[serde(default)]
+    pub work_type: String,
+    /// Funder names ("; "-separated)
+    #[serde(default)]
+    pub funders: String,
+    /// Grant (award) numbers of all funders ("; "-separated)
+    #[serde(default)]
+    pub grants: String,
 }
 
 /// Crossref API client with rate limiting and concurrency control
 pub struct CrossrefClient {
     client: reqwest::Client,
     semaphore: Arc<Semaphore>,
-    max_retries: u32,
+    retry: RetryPolicy,
+    cache: Option<HttpCache>,
+    /// Checks that a title search found the paper searched for
+    title_matcher: TitleMatcher,
 }
 
 impl CrossrefClient {
@@ -49,57 +71,99 @@ impl CrossrefClient {
     ///
     /// * `max_workers` - Maximum concurrent requests (default: 3)
     pub fn new(max_workers: usize) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .user_agent(format!("gscholar-rust/1.0 (mailto:{})", MAILTO))
-            .timeout(Duration::from_secs(15))
-            .build()
-            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
-
+        // The mailto parameter of each request puts it in the polite pool
         Ok(Self {
-            client,
+            client: http::client_for(Upstream::Crossref),
             semaphore: Arc::new(Semaphore::new(max_workers)),
-            max_retries: 3,
+            retry: http::retry_policy(Upstream::Crossref),
+            cache: None,
+            title_matcher: TitleMatcher::default(),
         })
     }
 
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/crossref`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Title similarity a title search result needs to be accepted
+    pub fn with_title_matcher(mut self, matcher: TitleMatcher) -> Self {
+        self.title_matcher = matcher;
+        self
+    }
+
+    /// Store a successful response body, logging (not failing) on errors
+    fn store(&self, key: &str, body: &str) {
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(key, body) {
+                warn!(error = %e, "Failed to write Crossref cache");
+            }
+        }
+    }
+
     /// Lookup article metadata by title
     ///
-    /// Uses exponential backoff for rate limiting
+    /// Uses exponential backoff for rate limiting. The best hit is dropped
+    /// when its title does not match the one searched for.
     pub async fn lookup_by_title(&self, title: &str) -> Option<CrossrefMetadata> {
         let title = title.trim();
         if title.is_empty() {
             return None;
         }
+        let metadata = self.with_retries(title, || self.do_lookup(title)).await?;
+        let score = self.title_matcher.score(title, &metadata.crossref_title);
+        if score < self.title_matcher.threshold {
+            debug!(title = %title, crossref_title = %metadata.crossref_title, score, "Crossref title does not match");
+            return None;
+        }
+        Some(metadata)
+    }
 
+    /// Lookup article metadata by DOI
+    ///
+    /// Uses exponential backoff for rate limiting
+    pub async fn lookup_by_doi(&self, doi: &str) -> Option<CrossrefMetadata> {
+        let doi = doi::normalize(doi);
+        if doi.is_empty() {
+            return None;
+        }
+        self.with_retries(&doi, || self.do_lookup_doi(&doi)).await
+    }
+
+    /// Run a lookup under the concurrency limit, retrying rate limits and errors
+    async fn with_retries<F, Fut>(&self, query: &str, lookup: F) -> Option<CrossrefMetadata>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Option<CrossrefMetadata>>>,
+    {
         let _permit = self.semaphore.acquire().await.ok()?;
 
-        let mut backoff = Duration::from_millis(500);
+        let short: String = query.chars().take(30).collect();
 
-        for attempt in 0..self.max_retries {
-            match self.do_lookup(title).await {
+        for attempt in 0..=self.retry.max_retries {
+            match lookup().await {
                 Ok(Some(metadata)) => return Some(metadata),
                 Ok(None) => return None,
                 Err(GscholarError::RateLimited(secs)) => {
-                    let wait = Duration::from_secs(secs).max(backoff);
+                    let wait = Duration::from_secs(secs).max(self.retry.delay(attempt, None));
                     warn!(
-                        title = &title[..title.len().min(30)],
+                        query = %short,
                         attempt = attempt + 1,
                         wait_secs = wait.as_secs(),
                         "Rate limited, waiting"
                     );
                     tokio::time::sleep(wait).await;
-                    backoff *= 2;
                 }
                 Err(e) => {
                     debug!(
-                        title = &title[..title.len().min(30)],
+                        query = %short,
                         attempt = attempt + 1,
                         error = %e,
                         "Lookup failed"
                     );
-                    if attempt < self.max_retries - 1 {
-                        tokio::time::sleep(backoff).await;
-                        backoff *= 2;
+                    if attempt < self.retry.max_retries {
+                        tokio::time::sleep(self.retry.delay(attempt, None)).await;
                     }
                 }
             }
@@ -110,17 +174,37 @@ impl CrossrefClient {
 
     /// Internal lookup implementation
     async fn do_lookup(&self, title: &str) -> Result<Option<CrossrefMetadata>> {
-        let response = self
+        let request = self
             .client
             .get(CROSSREF_API_URL)
             .query(&[
                 ("query.title", title),
                 ("rows", "1"),
-                ("select", "DOI,title,author,container-title,published,abstract"),
+                ("select", "DOI,title,author,container-title,ISSN,published,abstract,type,funder"),
                 ("mailto", MAILTO),
-            ])
-            .send()
-            .await?;
+            ]);
+        let request = self.retry.apply(request).build()?;
+        let key = cache::request_key("GET", request.url().as_str(), "");
+
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(title = %title, "Crossref cache hit");
+            let data: CrossrefResponse = serde_json::from_str(&body)?;
+            return Ok(data.message.items.into_iter().next().map(parse_crossref_item));
+        }
+
+        let body = self.fetch(request).await?;
+        let data: CrossrefResponse = serde_json::from_str(&body)?;
+        self.store(&key, &body);
+        Ok(data.message.items.into_iter().next().map(parse_crossref_item))
+    }
+
+    /// Send a search request and return the body of a successful response
+    async fn fetch(&self, request: reqwest::Request) -> Result<String> {
+        let url = request.url().clone();
+        ratelimit::acquire(&url).await;
+        let _slot = throttle::acquire(Upstream::Crossref).await;
+        let response = self.client.execute(request).await?;
+        ratelimit::record(&url, response.status(), None);
 
         // Check rate limit headers
         if let Some(limit) = response.headers().get("X-Rate-Limit-Limit") {
@@ -138,13 +222,48 @@ impl CrossrefClient {
             });
         }
 
-        let data: CrossrefResponse = response.json().await?;
+        let body = response.text().await?;
+        raw_archive::record(url.as_str(), BodyKind::Json, &body);
+        Ok(body)
+    }
+
+    /// Internal DOI lookup implementation
+    async fn do_lookup_doi(&self, doi: &str) -> Result<Option<CrossrefMetadata>> {
+        let request = self
+            .client
+            .get(format!("{}/{}", CROSSREF_API_URL, doi))
+            .query(&[("mailto", MAILTO)]);
+        let request = self.retry.apply(request).build()?;
+        let key = cache::request_key("GET", request.url().as_str(), "");
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(doi = %doi, "Crossref cache hit");
+            let data: CrossrefWorkResponse = serde_json::from_str(&body)?;
+            return Ok(Some(parse_crossref_item(data.message)));
+        }
 
-        if let Some(item) = data.message.items.into_iter().next() {
-            Ok(Some(parse_crossref_item(item)))
-        } else {
-            Ok(None)
+        let url = request.url().clone();
+        ratelimit::acquire(&url).await;
+        let _slot = throttle::acquire(Upstream::Crossref).await;
+        let response = self.client.execute(request).await?;
+        ratelimit::record(&url, response.status(), None);
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(None),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(5)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("Crossref API error: {}", status),
+                });
+            }
+            _ => {}
         }
+
+        let body = response.text().await?;
+        raw_archive::record(url.as_str(), BodyKind::Json, &body);
+        let data: CrossrefWorkResponse = serde_json::from_str(&body)?;
+        self.store(&key, &body);
+        Ok(Some(parse_crossref_item(data.message)))
     }
 
     /// Lookup multiple titles concurrently
@@ -174,9 +293,11 @@ impl CrossrefClient {
 impl Default for CrossrefClient {
     fn default() -> Self {
         Self::new(3).unwrap_or_else(|_| Self {
-            client: reqwest::Client::new(),
+            client: http::client_for(Upstream::Crossref),
             semaphore: Arc::new(Semaphore::new(3)),
-            max_retries: 3,
+            retry: http::retry_policy(Upstream::Crossref),
+            cache: None,
+            title_matcher: TitleMatcher::default(),
         })
     }
 }
@@ -188,6 +309,12 @@ struct CrossrefResponse {
     message: CrossrefMessage,
 }
 
+/// Response for a single work (`/works/{doi}`)
+#[derive(Debug, Deserialize)]
+struct CrossrefWorkResponse {
+    message: CrossrefItem,
+}
+
 #[derive(Debug, Deserialize)]
 struct CrossrefMessage {
     #[serde(default)]
@@ -204,10 +331,24 @@ struct CrossrefItem {
     author: Vec<CrossrefAuthor>,
     #[serde(rename = "container-title", default)]
     container_title: Vec<String>,
+    #[serde(rename = "ISSN", default)]
+    issn: Vec<String>,
     #[serde(default)]
     published: Option<CrossrefPublished>,
     #[serde(rename = "abstract", default)]
     abstract_text: Option<String>,
+    #[serde(rename = "type", default)]
+    work_type: String,
+    #[serde(default)]
+    funder: Vec<CrossrefFunder>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossrefFunder {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    award: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -250,6 +391,7 @@ fn parse_crossref_item(item: CrossrefItem) -> CrossrefMetadata {
 
     // Journal
     let journal = item.container_title.into_iter().next().unwrap_or_default();
+    let issn = item.issn.into_iter().next().unwrap_or_default();
 
     // Abstract (strip HTML tags)
     let abstract_text = item
@@ -260,13 +402,32 @@ fn parse_crossref_item(item: CrossrefItem) -> CrossrefMetadata {
     // Title
     let crossref_title = item.title.into_iter().next().unwrap_or_default();
 
+    // Funders and their grant numbers
+    let mut funders: Vec<&str> = Vec::new();
+    let mut grants: Vec<&str> = Vec::new();
+    for funder in &item.funder {
+        let name = funder.name.trim();
+        if !name.is_empty() && !funders.contains(&name) {
+            funders.push(name);
+        }
+        for award in funder.award.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+            if !grants.contains(&award) {
+                grants.push(award);
+            }
+        }
+    }
+
     CrossrefMetadata {
-        doi: item.doi,
+        doi: doi::normalize(&item.doi),
         journal,
+        issn,
         authors,
         date,
         abstract_text,
         crossref_title,
+        work_type: item.work_type,
+        funders: funders.join("; "),
+        grants: grants.join("; "),
     }
 }
 
@@ -300,16 +461,30 @@ mod tests {
                 family: "Doe".to_string(),
             }],
             container_title: vec!["Nature".to_string()],
+            issn: vec!["0028-0836".to_string()],
             published: Some(CrossrefPublished {
                 date_parts: vec![vec![2023, 6, 15]],
             }),
             abstract_text: Some("<p>This is abstract</p>".to_string()),
+            work_type: "journal-article".to_string(),
+            funder: vec![
+                CrossrefFunder {
+                    name: "National Natural Science Foundation of China".to_string(),
+                    award: vec!["41977213".to_string(), " 42077235".to_string()],
+                },
+                CrossrefFunder { name: "National Natural Science Foundation of China".to_string(), award: vec!["41977213".to_string()] },
+                CrossrefFunder { name: "European Research Council".to_string(), award: Vec::new() },
+            ],
         };
 
         let metadata = parse_crossref_item(item);
         assert_eq!(metadata.doi, "10.1234/test");
         assert_eq!(metadata.authors, "John Doe");
+        assert_eq!(metadata.issn, "0028-0836");
         assert_eq!(metadata.date, "2023-6-15");
         assert_eq!(metadata.abstract_text, "This is abstract");
+        assert_eq!(metadata.work_type, "journal-article");
+        assert_eq!(metadata.funders, "National Natural Science Foundation of China; European Research Council");
+        assert_eq!(metadata.grants, "41977213; 42077235");
     }
 }
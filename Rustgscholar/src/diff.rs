@@ -0,0 +1,238 @@
+//! Comparing the results of two runs.
+//!
+//! [`diff_runs`] matches the papers of two unified (or relevant) tables by
+//! DOI, or by normalized title for papers without one, and reports the
+//! papers only the newer table has, those it lost, and those whose metrics
+//! changed (new citations, a different Impact Factor, ...). The result
+//! renders as a Markdown delta ([`RunDiff::render`]) and serializes to JSON.
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::matching;
+use crate::network::{field, read_rows};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Table compared in a run folder
+pub const UNIFIED_FILE: &str = "5_unified.csv";
+
+/// Table compared in a run folder with `--relevant`
+pub const RELEVANT_FILE: &str = "7_relevant.csv";
+
+/// Columns compared between matched papers, when both tables have them
+pub const COMPARED_FIELDS: &[&str] = &[
+    "date", "journal", "citations", "normalized_citations", "if_score", "jci_score",
+    "sci_partition", "altmetric_score", "pdf_url", "label",
+];
+
+/// A paper found in only one of the tables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PaperRef {
+    pub doi: String,
+    pub title: String,
+}
+
+/// One column of a paper that differs between the tables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// A paper in both tables with changed columns
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedPaper {
+    pub doi: String,
+    pub title: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Differences between an older and a newer table
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunDiff {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub added: Vec<PaperRef>,
+    pub removed: Vec<PaperRef>,
+    pub changed: Vec<ChangedPaper>,
+    /// Papers in both tables with no compared column changed
+    pub unchanged: usize,
+}
+
+/// Compare two run folders (their `5_unified.csv`, or `7_relevant.csv` with
+/// `relevant`) or two CSV files
+pub fn diff_runs(old: &Path, new: &Path, relevant: bool) -> Result<RunDiff> {
+    let old = table_path(old, relevant)?;
+    let new = table_path(new, relevant)?;
+    let mut diff = diff_tables(&read_rows(&old)?, &read_rows(&new)?);
+    diff.old = old;
+    diff.new = new;
+    Ok(diff)
+}
+
+fn table_path(path: &Path, relevant: bool) -> Result<PathBuf> {
+    if !path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+    let table = path.join(if relevant { RELEVANT_FILE } else { UNIFIED_FILE });
+    if !table.exists() {
+        return Err(GscholarError::Validation(format!("{:?} has no {}", path, table.file_name().unwrap_or_default().to_string_lossy())));
+    }
+    Ok(table)
+}
+
+/// Compare the rows of two tables; added papers keep the newer table's
+/// order, removed ones the older table's
+pub fn diff_tables(old: &[HashMap<String, String>], new: &[HashMap<String, String>]) -> RunDiff {
+    let old_by_key: HashMap<String, &HashMap<String, String>> = old.iter().map(|row| (paper_key(row), row)).collect();
+    let new_keys: HashSet<String> = new.iter().map(paper_key).collect();
+
+    let mut diff = RunDiff::default();
+    let mut seen = HashSet::new();
+    for row in new {
+        let key = paper_key(row);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        let Some(previous) = old_by_key.get(&key) else {
+            diff.added.push(paper_ref(row));
+            continue;
+        };
+        let changes: Vec<FieldChange> = COMPARED_FIELDS
+            .iter()
+            .filter(|name| previous.contains_key(**name) && row.contains_key(**name))
+            .filter(|name| field(previous, name) != field(row, name))
+            .map(|name| FieldChange { field: name.to_string(), old: field(previous, name).to_string(), new: field(row, name).to_string() })
+            .collect();
+        if changes.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            let PaperRef { doi, title } = paper_ref(row);
+            diff.changed.push(ChangedPaper { doi, title, changes });
+        }
+    }
+    let mut removed = HashSet::new();
+    diff.removed = old
+        .iter()
+        .filter(|row| {
+            let key = paper_key(row);
+            !new_keys.contains(&key) && removed.insert(key)
+        })
+        .map(paper_ref)
+        .collect();
+    diff
+}
+
+/// Normalized DOI, else normalized title
+fn paper_key(row: &HashMap<String, String>) -> String {
+    match doi::normalize(field(row, "doi")) {
+        doi if doi.is_empty() => format!("title:{}", matching::normalize_title(field(row, "title"))),
+        doi => doi,
+    }
+}
+
+fn paper_ref(row: &HashMap<String, String>) -> PaperRef {
+    PaperRef { doi: field(row, "doi").to_string(), title: field(row, "title").to_string() }
+}
+
+impl RunDiff {
+    /// Whether the tables hold the same papers with the same compared columns
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Markdown delta: counts, then the added, removed and changed papers
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Run diff\n");
+        let _ = writeln!(out, "- Old: `{}`", self.old.display());
+        let _ = writeln!(out, "- New: `{}`", self.new.display());
+        let _ = writeln!(
+            out,
+            "\n{} added, {} removed, {} changed, {} unchanged\n",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        );
+        for (heading, papers) in [("Added", &self.added), ("Removed", &self.removed)] {
+            if papers.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "## {} ({})\n", heading, papers.len());
+            for paper in papers {
+                let _ = writeln!(out, "- {}", describe(&paper.title, &paper.doi));
+            }
+            out.push('\n');
+        }
+        if !self.changed.is_empty() {
+            let _ = writeln!(out, "## Changed ({})\n", self.changed.len());
+            for paper in &self.changed {
+                let changes: Vec<String> = paper.changes.iter().map(FieldChange::describe).collect();
+                let _ = writeln!(out, "- {}: {}", describe(&paper.title, &paper.doi), changes.join("; "));
+            }
+        }
+        out
+    }
+}
+
+impl FieldChange {
+    /// "citations 10 → 25 (+15)", the difference only for numbers
+    fn describe(&self) -> String {
+        let shown = |value: &str| if value.is_empty() { "(empty)".to_string() } else { value.to_string() };
+        let mut text = format!("{} {} → {}", self.field, shown(&self.old), shown(&self.new));
+        if let (Ok(old), Ok(new)) = (self.old.replace(',', "").parse::<f64>(), self.new.replace(',', "").parse::<f64>()) {
+            let delta = new - old;
+            let _ = write!(text, " ({}{})", if delta >= 0.0 { "+" } else { "" }, (delta * 100.0).round() / 100.0);
+        }
+        text
+    }
+}
+
+fn describe(title: &str, doi: &str) -> String {
+    match (title, doi) {
+        (title, "") => title.to_string(),
+        ("", doi) => format!("https://doi.org/{}", doi),
+        (title, doi) => format!("{} (https://doi.org/{})", title, doi),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_diff_tables() {
+        let old = vec![
+            row(&[("title", "Slope stability"), ("doi", "10.1/A"), ("citations", "10"), ("if_score", "3.1")]),
+            row(&[("title", "Rainfall thresholds"), ("doi", "10.1/b"), ("citations", "4"), ("if_score", "2.0")]),
+            row(&[("title", "Debris flows in the Alps"), ("doi", ""), ("citations", "1"), ("if_score", "")]),
+        ];
+        let new = vec![
+            row(&[("title", "Slope stability"), ("doi", "https://doi.org/10.1/a"), ("citations", "25"), ("if_score", "3.1"), ("label", "relevant")]),
+            row(&[("title", "Debris Flows in the Alps."), ("doi", ""), ("citations", "1"), ("if_score", "")]),
+            row(&[("title", "InSAR monitoring"), ("doi", "10.1/c"), ("citations", "0"), ("if_score", "")]),
+        ];
+        let diff = diff_tables(&old, &new);
+        assert_eq!(diff.added, vec![PaperRef { doi: "10.1/c".to_string(), title: "InSAR monitoring".to_string() }]);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].doi, "10.1/b");
+        // Matched by DOI; a column only one table has is not a change
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].changes, vec![FieldChange { field: "citations".to_string(), old: "10".to_string(), new: "25".to_string() }]);
+        // Matched by normalized title
+        assert_eq!(diff.unchanged, 1);
+
+        let rendered = diff.render();
+        assert!(rendered.contains("1 added, 1 removed, 1 changed, 1 unchanged"));
+        assert!(rendered.contains("citations 10 → 25 (+15)"));
+        assert!(diff_tables(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+//! Pre-flight checks for `doctor`.
+//!
+//! A long search run can fail hours in because Scholar started showing a
+//! CAPTCHA or an API key expired. `doctor` sends one cheap request per
+//! dependency instead and reports pass/fail: the proxy is reachable, Scholar
+//! answers with results rather than a CAPTCHA, the stored cookies are still
+//! accepted, and each configured API key (EasyScholar, Semantic Scholar, the
+//! LLM) is valid. Unlike the readiness probes of `serve`, a rejected key
+//! counts as a failure here.
+
+use crate::cookies::{is_google_host, Cookie, CookieManager};
+use crate::error::GscholarError;
+use crate::gscholar::{self, QueryOptions, DEFAULT_SCHOLAR_URL};
+use crate::health::LlmEndpoint;
+use crate::http;
+use crate::throttle::Upstream;
+use crate::{rankings, semanticscholar};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Timeout of a single check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Page fetched through the proxy: tiny and always served
+const PROXY_PROBE_URL: &str = "https://www.google.com/generate_204";
+
+/// Query of the Scholar check
+const SCHOLAR_PROBE_QUERY: &str = "machine learning";
+
+/// Venue looked up with the EasyScholar key
+const EASYSCHOLAR_PROBE_VENUE: &str = "Nature";
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but something may go wrong during a run
+    Warn,
+    Fail,
+    /// Not configured
+    Skip,
+}
+
+impl CheckStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+            Self::Skip => "SKIP",
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// Round-trip time of the request, for network checks
+    pub latency: Option<Duration>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into(), latency: None }
+    }
+
+    fn timed(mut self, started: Instant) -> Self {
+        self.latency = Some(started.elapsed());
+        self
+    }
+}
+
+/// What to check
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    /// Proxy used for Google Scholar
+    pub proxy: Option<String>,
+    /// Scholar mirror (default Google Scholar)
+    pub mirror: Option<String>,
+    /// Cookie file (default `~/.gscholar_cookies.json`)
+    pub cookie_path: Option<PathBuf>,
+    pub easyscholar_key: Option<String>,
+    pub s2_api_key: Option<String>,
+    pub llm: Option<LlmEndpoint>,
+    /// Model the run will use; warned about when the endpoint does not list it
+    pub llm_model: Option<String>,
+}
+
+/// Run every check, in table order
+pub async fn run(options: &DoctorOptions) -> crate::error::Result<Vec<Check>> {
+    // Same connection settings (and proxies) as a run
+    let settings = http::HttpSettings { proxy: options.proxy.clone(), ..http::settings().clone() };
+    let client = |upstream: Upstream| {
+        http::builder(&settings.for_upstream(upstream))?
+            .timeout(CHECK_TIMEOUT)
+            .build()
+            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))
+    };
+    let (easyscholar_client, s2_client, llm_client) =
+        (client(Upstream::EasyScholar)?, client(Upstream::SemanticScholar)?, client(Upstream::Llm)?);
+
+    let proxy = check_proxy(options.proxy.as_deref()).await?;
+    let (scholar, captcha) = check_scholar(options).await;
+    let cookie_manager = match &options.cookie_path {
+        Some(path) => CookieManager::with_path(path.clone()),
+        None => CookieManager::new()?,
+    };
+    let cookies = check_cookies(&cookie_manager.load(), chrono::Utc::now().timestamp() as f64, captcha);
+    let (easyscholar, s2, llm) = tokio::join!(
+        check_easyscholar(&easyscholar_client, non_empty(&options.easyscholar_key)),
+        check_s2(&s2_client, non_empty(&options.s2_api_key)),
+        check_llm(&llm_client, options.llm.as_ref(), options.llm_model.as_deref()),
+    );
+    Ok(vec![proxy, scholar, cookies, easyscholar, s2, llm])
+}
+
+/// Whether any check failed
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == CheckStatus::Fail)
+}
+
+/// Plain-text table of the checks
+pub fn render_table(checks: &[Check]) -> String {
+    let rows: Vec<[String; 4]> = checks
+        .iter()
+        .map(|c| {
+            [
+                c.name.to_string(),
+                c.status.label().to_string(),
+                c.latency.map(|l| format!("{} ms", l.as_millis())).unwrap_or_default(),
+                c.detail.clone(),
+            ]
+        })
+        .collect();
+    let header = ["CHECK", "STATUS", "TIME", "DETAIL"].map(String::from);
+    let mut widths = header.clone().map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let line: Vec<String> = row[..3]
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .chain(std::iter::once(row[3].clone()))
+                .collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+async fn check_proxy(proxy: Option<&str>) -> crate::error::Result<Check> {
+    const NAME: &str = "proxy";
+    let Some(proxy_url) = proxy else {
+        return Ok(Check::new(NAME, CheckStatus::Skip, "no --proxy given"));
+    };
+    let settings = http::HttpSettings { proxy: Some(proxy_url.to_string()), ..http::settings().clone() };
+    let client = http::builder(&settings)?
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
+    let started = Instant::now();
+    let check = match client.get(PROXY_PROBE_URL).send().await {
+        Ok(response) => Check::new(NAME, CheckStatus::Pass, format!("{} reachable (HTTP {})", proxy_url, response.status().as_u16())),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, format!("{}: {}", proxy_url, request_error(&e))),
+    };
+    Ok(check.timed(started))
+}
+
+/// Scholar check, and whether Scholar showed a CAPTCHA
+async fn check_scholar(options: &DoctorOptions) -> (Check, bool) {
+    const NAME: &str = "scholar";
+    let query = QueryOptions {
+        proxy: options.proxy.clone(),
+        base_url: options.mirror.clone(),
+        cookie_path: options.cookie_path.clone(),
+        ..Default::default()
+    };
+    let url = options.mirror.as_deref().unwrap_or(DEFAULT_SCHOLAR_URL);
+    let started = Instant::now();
+    let (status, detail, captcha) = match gscholar::count(SCHOLAR_PROBE_QUERY, &query).await {
+        Ok(Some(count)) => (CheckStatus::Pass, format!("{} answers ({} results for a test query)", url, count), false),
+        Ok(None) => (CheckStatus::Warn, format!("{} answers, but the page shows no result count", url), false),
+        Err(GscholarError::Captcha) => (CheckStatus::Fail, "CAPTCHA shown; run `cookies fetch` or change the proxy".to_string(), true),
+        Err(GscholarError::RateLimited(_)) => (CheckStatus::Fail, "rate limited (HTTP 429)".to_string(), false),
+        Err(GscholarError::Network(e)) => (CheckStatus::Fail, request_error(&e), false),
+        Err(e) => (CheckStatus::Fail, e.to_string(), false),
+    };
+    (Check::new(NAME, status, detail).timed(started), captcha)
+}
+
+/// Cookies are judged by their expiry and by whether Scholar still shows a
+/// CAPTCHA although they were sent
+fn check_cookies(cookies: &[Cookie], now: f64, captcha: bool) -> Check {
+    const NAME: &str = "cookies";
+    let google: Vec<&Cookie> = cookies.iter().filter(|c| is_google_host(&c.domain)).collect();
+    if google.is_empty() {
+        return Check::new(NAME, CheckStatus::Warn, "no Google cookies stored; run `cookies fetch` or `cookies import`");
+    }
+    let expired = google.iter().filter(|c| c.expires.is_some_and(|e| e <= now)).count();
+    let summary = format!("{} Google cookies, {} expired", google.len(), expired);
+    if expired == google.len() {
+        Check::new(NAME, CheckStatus::Fail, format!("{}; refresh them", summary))
+    } else if captcha {
+        Check::new(NAME, CheckStatus::Fail, format!("{}; Scholar still shows a CAPTCHA", summary))
+    } else if expired > 0 {
+        Check::new(NAME, CheckStatus::Warn, summary)
+    } else {
+        Check::new(NAME, CheckStatus::Pass, summary)
+    }
+}
+
+#[derive(Deserialize)]
+struct EasyScholarReply {
+    code: i32,
+    msg: Option<String>,
+}
+
+async fn check_easyscholar(client: &reqwest::Client, key: Option<&str>) -> Check {
+    const NAME: &str = "easyscholar";
+    let Some(key) = key else {
+        return Check::new(NAME, CheckStatus::Skip, "EASYSCHOLAR_KEY not set");
+    };
+    let started = Instant::now();
+    let reply = client
+        .get(rankings::EASYSCHOLAR_API_URL)
+        .query(&[("secretKey", key), ("publicationName", EASYSCHOLAR_PROBE_VENUE)])
+        .send()
+        .await;
+    let check = match reply {
+        Ok(response) if !response.status().is_success() => {
+            Check::new(NAME, CheckStatus::Fail, format!("HTTP {}", response.status().as_u16()))
+        }
+        // The API reports errors, a wrong key included, in the body
+        Ok(response) => match response.json::<EasyScholarReply>().await {
+            Ok(reply) if reply.code == 200 => Check::new(NAME, CheckStatus::Pass, "key accepted"),
+            Ok(reply) => Check::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("key rejected: {} ({})", reply.msg.unwrap_or_else(|| "Unknown".to_string()), reply.code),
+            ),
+            Err(e) => Check::new(NAME, CheckStatus::Fail, format!("unexpected reply: {}", e.without_url())),
+        },
+        Err(e) => Check::new(NAME, CheckStatus::Fail, request_error(&e)),
+    };
+    check.timed(started)
+}
+
+async fn check_s2(client: &reqwest::Client, key: Option<&str>) -> Check {
+    const NAME: &str = "s2";
+    let Some(key) = key else {
+        return Check::new(NAME, CheckStatus::Skip, "S2_API_KEY not set (the shared pool is used)");
+    };
+    let started = Instant::now();
+    let reply = client
+        .get(format!("{}/paper/search", semanticscholar::SS_API_BASE))
+        .query(&[("query", "doctor"), ("limit", "1"), ("fields", "title")])
+        .header("x-api-key", key)
+        .send()
+        .await;
+    let check = match reply {
+        Ok(response) => key_check(NAME, response.status().as_u16()),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, request_error(&e)),
+    };
+    check.timed(started)
+}
+
+/// Model listing of an OpenAI-compatible API or Ollama
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<ModelEntry>,
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    #[serde(alias = "name")]
+    id: String,
+}
+
+async fn check_llm(client: &reqwest::Client, llm: Option<&LlmEndpoint>, model: Option<&str>) -> Check {
+    const NAME: &str = "llm";
+    let Some(llm) = llm else {
+        return Check::new(NAME, CheckStatus::Skip, "no --llm-base-url given");
+    };
+    if llm.provider.requires_api_key() && non_empty(&llm.api_key).is_none() {
+        return Check::new(NAME, CheckStatus::Fail, "LLM_API_KEY not set");
+    }
+    let mut request = client.get(llm.provider.models_endpoint(&llm.base_url));
+    if let Some(key) = non_empty(&llm.api_key) {
+        request = request.bearer_auth(key);
+    }
+    let started = Instant::now();
+    let check = match request.send().await {
+        Ok(response) if response.status().is_success() => {
+            // Listings differ between servers: only warn when one parses and lacks the model
+            let listed = response.json::<ModelList>().await.ok().map(|list| {
+                list.data.into_iter().chain(list.models).map(|m| m.id).collect::<Vec<_>>()
+            });
+            match (model, listed) {
+                (Some(model), Some(listed)) if !listed.is_empty() && !listed.iter().any(|m| m == model) => {
+                    Check::new(NAME, CheckStatus::Warn, format!("key accepted, but model '{}' is not listed", model))
+                }
+                _ => Check::new(NAME, CheckStatus::Pass, "key accepted"),
+            }
+        }
+        Ok(response) => key_check(NAME, response.status().as_u16()),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, request_error(&e)),
+    };
+    check.timed(started)
+}
+
+/// Check of an API key from the HTTP status of an authenticated request
+fn key_check(name: &'static str, http_status: u16) -> Check {
+    match http_status {
+        200..=299 => Check::new(name, CheckStatus::Pass, "key accepted"),
+        401 | 403 => Check::new(name, CheckStatus::Fail, format!("key rejected (HTTP {})", http_status)),
+        429 => Check::new(name, CheckStatus::Warn, "key accepted, but rate limited (HTTP 429)"),
+        _ => Check::new(name, CheckStatus::Fail, format!("HTTP {}", http_status)),
+    }
+}
+
+fn request_error(error: &reqwest::Error) -> String {
+    if error.is_timeout() {
+        "timed out".to_string()
+    } else if error.is_connect() {
+        "connection failed".to_string()
+    } else {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(domain: &str, expires: Option<f64>) -> Cookie {
+        Cookie {
+            name: "NID".to_string(),
+            value: "x".to_string(),
+            domain: domain.to_string(),
+            path: "/".to_string(),
+            secure: true,
+            http_only: true,
+            expires,
+        }
+    }
+
+    #[test]
+    fn test_check_cookies() {
+        let now = 1_000.0;
+        assert_eq!(check_cookies(&[cookie(".example.com", None)], now, false).status, CheckStatus::Warn);
+        let fresh = [cookie(".google.com", Some(2_000.0)), cookie("scholar.google.com", None)];
+        assert_eq!(check_cookies(&fresh, now, false).status, CheckStatus::Pass);
+        assert_eq!(check_cookies(&fresh, now, true).status, CheckStatus::Fail);
+        let stale = [cookie(".google.com", Some(500.0)), cookie(".google.com", Some(2_000.0))];
+        let check = check_cookies(&stale, now, false);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.detail, "2 Google cookies, 1 expired");
+        assert_eq!(check_cookies(&stale[..1], now, false).status, CheckStatus::Fail);
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_and_unreachable() -> crate::error::Result<()> {
+        let client = reqwest::Client::new();
+        assert_eq!(check_s2(&client, None).await.status, CheckStatus::Skip);
+        // Nothing listens on port 9
+        let llm = LlmEndpoint {
+            provider: crate::llm_filter::LlmProvider::Ollama,
+            base_url: "http://127.0.0.1:9".to_string(),
+            api_key: None,
+        };
+        assert_eq!(check_llm(&client, Some(&llm), None).await.status, CheckStatus::Fail);
+        assert_eq!(check_proxy(Some("http://127.0.0.1:9")).await?.status, CheckStatus::Fail);
+        assert_eq!(key_check("s2", 403).status, CheckStatus::Fail);
+        assert_eq!(key_check("s2", 429).status, CheckStatus::Warn);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_table() {
+        let checks = [
+            Check::new("proxy", CheckStatus::Skip, "no --proxy given"),
+            Check { latency: Some(Duration::from_millis(42)), ..Check::new("s2", CheckStatus::Pass, "key accepted") },
+        ];
+        assert_eq!(
+            render_table(&checks),
+            "CHECK  STATUS  TIME   DETAIL\nproxy  SKIP           no --proxy given\ns2     PASS    42 ms  key accepted"
+        );
+        assert!(!has_failures(&checks));
+    }
+}
@@ -0,0 +1,72 @@
+//! DOI canonicalization.
+//!
+//! The same DOI reaches the pipeline in many spellings: OpenAlex returns
+//! `https://doi.org/10.1/ABC`, Crossref and Semantic Scholar return the bare
+//! DOI in whatever case the publisher registered, and user lists contain
+//! `DOI: 10.1/abc.`, `https://dx.doi.org/10.1%2Fabc` and the like. DOIs are
+//! case-insensitive, so [`normalize`] reduces all of these to one canonical
+//! form, the lowercase bare DOI, which every source stores and every join
+//! between stages compares.
+
+/// Prefixes stripped in front of the DOI, possibly several in a row
+const PREFIXES: &[&str] = &["https://", "http://", "www.", "dx.doi.org/", "doi.org/", "urn:doi:", "doi:", "doi "];
+
+/// Canonical form of a DOI: URL-decoded, without resolver or `doi:`
+/// prefixes and trailing punctuation, lowercase
+///
+/// Empty input stays empty.
+pub fn normalize(doi: &str) -> String {
+    let doi = doi.trim();
+    let decoded = if doi.contains('%') {
+        urlencoding::decode(doi).map(|d| d.into_owned()).unwrap_or_else(|_| doi.to_string())
+    } else {
+        doi.to_string()
+    };
+    let mut doi = decoded.trim().to_lowercase();
+
+    while let Some(rest) = PREFIXES.iter().find_map(|prefix| doi.strip_prefix(prefix)) {
+        doi = rest.trim_start().to_string();
+    }
+
+    // Sentence punctuation picked up from references and pasted lists;
+    // closing brackets only when they have no opening partner in the DOI
+    while let Some(last) = doi.chars().last() {
+        let unbalanced = |open: char, close: char| doi.matches(close).count() > doi.matches(open).count();
+        let strip = match last {
+            '.' | ',' | ';' | ':' | '"' | '\'' => true,
+            ')' => unbalanced('(', ')'),
+            ']' => unbalanced('[', ']'),
+            _ => false,
+        };
+        if !strip {
+            break;
+        }
+        doi.pop();
+    }
+    doi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        for raw in [
+            "10.1016/J.GEOMORPH.2020.107",
+            " https://doi.org/10.1016/j.geomorph.2020.107 ",
+            "http://dx.doi.org/10.1016/j.Geomorph.2020.107",
+            "doi.org/10.1016/j.geomorph.2020.107",
+            "DOI: 10.1016/j.geomorph.2020.107.",
+            "doi:https://doi.org/10.1016/j.geomorph.2020.107",
+            "https://doi.org/10.1016%2Fj.geomorph.2020.107",
+            "10.1016/j.geomorph.2020.107;",
+        ] {
+            assert_eq!(normalize(raw), "10.1016/j.geomorph.2020.107", "{}", raw);
+        }
+        // Brackets that belong to the DOI are kept
+        assert_eq!(normalize("10.1002/(SICI)1099-1085(199605)10:5<723::AID-HYP314>3.0.CO;2-2"), "10.1002/(sici)1099-1085(199605)10:5<723::aid-hyp314>3.0.co;2-2");
+        assert_eq!(normalize("10.1016/0022-1694(93)90112-T)."), "10.1016/0022-1694(93)90112-t");
+        assert_eq!(normalize("  "), "");
+    }
+}
@@ -0,0 +1,496 @@
+//! Pluggable metadata enrichers.
+//!
+//! An [`Enricher`] fills gaps in a unified paper (abstract, journal, PDF link,
+//! rankings, ...) from one upstream service and reports which fields it set
+//! as [`Provenance`]. The pipeline runs the configured enrichers in order on
+//! the Stage 5 dataset, so an earlier enricher wins: later ones only fill
+//! fields that are still empty.
+//!
+//! The built-in enrichers (Crossref, Semantic Scholar, Unpaywall, EasyScholar
+//! rankings, Altmetric and OpenAlex impact) are created by name with [`builtin`]; third-party
+//! enrichers implement the trait and are added with
+//! [`PipelineBuilder::enricher`](crate::pipeline::PipelineBuilder::enricher).
+
+use crate::altmetric::{self, AltmetricClient};
+use crate::cache::{self, HttpCache};
+use crate::crossref::{self, CrossrefClient};
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::impact::{self, ImpactClient};
+use crate::openalex;
+use crate::rankings::{self, RankingClient};
+use crate::semanticscholar::{self, SemanticScholarClient};
+use crate::paper::{self, Paper};
+use crate::unpaywall::{self, UnpaywallClient};
+use futures::future::BoxFuture;
+use crate::stream;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::warn;
+
+/// Papers enriched concurrently by [`Enricher::enrich_all`]'s default implementation
+pub const MAX_CONCURRENT_PAPERS: usize = 4;
+
+/// Built-in enricher names accepted by [`builtin`]
+pub const BUILTIN_ENRICHERS: &[&str] = &["crossref", "semanticscholar", "unpaywall", "rankings", "altmetric", "impact"];
+
+/// Fields one enricher set on one paper
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub doi: String,
+    pub enricher: String,
+    /// Fields set (e.g., "abstract_text; tldr")
+    pub fields: String,
+}
+
+impl Provenance {
+    pub fn new(enricher: &str, paper: &Paper) -> Self {
+        Self { doi: paper.doi.clone(), enricher: enricher.to_string(), fields: String::new() }
+    }
+
+    /// Whether the enricher set nothing
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Set `slot` to `value` if it is still empty, recording `field`
+    pub fn fill(&mut self, field: &str, slot: &mut String, value: &str) {
+        let value = value.trim();
+        if !slot.trim().is_empty() || value.is_empty() {
+            return;
+        }
+        *slot = value.to_string();
+        self.record(field);
+    }
+
+    /// Record that the enricher set `field`
+    pub fn record(&mut self, field: &str) {
+        if !self.fields.is_empty() {
+            self.fields.push_str("; ");
+        }
+        self.fields.push_str(field);
+    }
+}
+
+/// A metadata source that fills empty fields of unified papers
+///
+/// Implementations must be `Send + Sync`; the futures are boxed so the trait
+/// can be used as `Box<dyn Enricher>`.
+pub trait Enricher: Send + Sync {
+    /// Display name (e.g., "Crossref")
+    fn name(&self) -> &str;
+
+    /// API endpoint, recorded in `manifest.json`
+    fn url(&self) -> &str;
+
+    /// Enrich one paper
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>>;
+
+    /// Enrich every paper, returning the provenance of those that changed
+    ///
+    /// The default runs [`enrich`](Enricher::enrich) on up to
+    /// [`MAX_CONCURRENT_PAPERS`] papers at once and logs (skips) per-paper
+    /// errors. Enrichers with a batch API override it.
+    fn enrich_all<'a>(&'a self, papers: &'a mut [Paper]) -> BoxFuture<'a, Result<Vec<Provenance>>> {
+        Box::pin(async move {
+            let lookups: Vec<_> = papers.iter_mut().map(|paper| self.enrich(paper)).collect();
+            let results = stream::in_order(lookups, MAX_CONCURRENT_PAPERS).await;
+            Ok(results
+                .into_iter()
+                .filter_map(|result| {
+                    result
+                        .map_err(|e| warn!(enricher = self.name(), error = %e, "Enrichment failed"))
+                        .ok()
+                })
+                .filter(|provenance| !provenance.is_empty())
+                .collect())
+        })
+    }
+}
+
+impl Enricher for CrossrefClient {
+    fn name(&self) -> &str {
+        "Crossref"
+    }
+
+    fn url(&self) -> &str {
+        crossref::CROSSREF_API_URL
+    }
+
+    /// Look up by DOI (by title when the paper has none)
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let mut provenance = Provenance::new(self.name(), paper);
+            let metadata = if paper.doi.is_empty() {
+                self.lookup_by_title(&paper.title).await
+            } else {
+                self.lookup_by_doi(&paper.doi).await
+            };
+            if let Some(metadata) = metadata {
+                provenance.fill("doi", &mut paper.doi, &metadata.doi);
+                if paper.authors.is_empty() && !metadata.authors.trim().is_empty() {
+                    paper.set_authors(&metadata.authors);
+                    provenance.record("author");
+                }
+                if paper.date.is_none() && paper::parse_date(&metadata.date).1.is_some() {
+                    paper.set_date(&metadata.date);
+                    provenance.record("date");
+                }
+                provenance.fill("journal", &mut paper.journal, &metadata.journal);
+                provenance.fill("abstract_text", &mut paper.abstract_text, &metadata.abstract_text);
+                if paper.funders.is_empty() && !metadata.funders.trim().is_empty() {
+                    paper.funders = paper::parse_list(&metadata.funders);
+                    paper.grants = paper::parse_list(&metadata.grants);
+                    provenance.record("funders");
+                }
+                provenance.doi = paper.doi.clone();
+            }
+            Ok(provenance)
+        })
+    }
+}
+
+/// Semantic Scholar batch lookup by DOI (abstract, TLDR, open-access PDF)
+pub struct SemanticScholarEnricher {
+    client: SemanticScholarClient,
+    url: String,
+}
+
+impl SemanticScholarEnricher {
+    pub fn new(client: SemanticScholarClient) -> Self {
+        Self { client, url: format!("{}/paper/batch", semanticscholar::SS_API_BASE) }
+    }
+
+    async fn lookup(&self, papers: &mut [Paper]) -> Result<Vec<Provenance>> {
+        let dois: Vec<String> = papers.iter().map(|p| p.doi.clone()).filter(|d| !d.is_empty()).collect();
+        let found = self.client.batch_lookup(&dois).await?;
+        let by_doi: HashMap<String, &semanticscholar::SemanticScholarResult> =
+            found.iter().map(|r| (doi::normalize(&r.doi), r)).collect();
+
+        let mut changed = Vec::new();
+        for paper in papers.iter_mut() {
+            let Some(result) = by_doi.get(&doi::normalize(&paper.doi)) else {
+                continue;
+            };
+            let mut provenance = Provenance::new(self.name(), paper);
+            provenance.fill("abstract_text", &mut paper.abstract_text, &result.ss_abstract);
+            provenance.fill("tldr", &mut paper.tldr, &result.tldr);
+            provenance.fill("pdf_url", &mut paper.pdf_url, &result.oa_pdf_url);
+            if !provenance.is_empty() {
+                changed.push(provenance);
+            }
+        }
+        Ok(changed)
+    }
+}
+
+impl Enricher for SemanticScholarEnricher {
+    fn name(&self) -> &str {
+        "Semantic Scholar"
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let changed = self.lookup(std::slice::from_mut(paper)).await?;
+            Ok(changed.into_iter().next().unwrap_or_else(|| Provenance::new(self.name(), paper)))
+        })
+    }
+
+    /// Batch requests instead of one per paper
+    fn enrich_all<'a>(&'a self, papers: &'a mut [Paper]) -> BoxFuture<'a, Result<Vec<Provenance>>> {
+        Box::pin(self.lookup(papers))
+    }
+}
+
+impl Enricher for UnpaywallClient {
+    fn name(&self) -> &str {
+        "Unpaywall"
+    }
+
+    fn url(&self) -> &str {
+        unpaywall::UNPAYWALL_API_URL
+    }
+
+    /// Fill `pdf_url` with the best open-access location
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let mut provenance = Provenance::new(self.name(), paper);
+            if let Some(location) = self.lookup(&paper.doi).await?.filter(|l| l.is_oa) {
+                provenance.fill("pdf_url", &mut paper.pdf_url, &location.url);
+            }
+            Ok(provenance)
+        })
+    }
+}
+
+fn fill_rankings(enricher: &str, paper: &mut Paper, metrics: Option<&rankings::RankingMetrics>) -> Provenance {
+    let mut provenance = Provenance::new(enricher, paper);
+    if let Some(metrics) = metrics.filter(|_| paper.rankings.is_none()) {
+        paper.rankings = Some(metrics.clone());
+        provenance.record("rankings");
+    }
+    provenance
+}
+
+impl Enricher for RankingClient {
+    fn name(&self) -> &str {
+        "EasyScholar"
+    }
+
+    fn url(&self) -> &str {
+        rankings::EASYSCHOLAR_API_URL
+    }
+
+    /// Fill journal rankings of unranked papers (never drops papers; use Stage 3 to filter)
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let metrics = match paper.journal.trim() {
+                "" => None,
+                journal => self.get_rank_with_issn(journal, None).await,
+            };
+            Ok(fill_rankings(self.name(), paper, metrics.as_ref()))
+        })
+    }
+
+    /// Look up each journal once
+    fn enrich_all<'a>(&'a self, papers: &'a mut [Paper]) -> BoxFuture<'a, Result<Vec<Provenance>>> {
+        Box::pin(async move {
+            let mut seen = HashSet::new();
+            let journals: Vec<(String, Option<String>)> = papers
+                .iter()
+                .map(|p| p.journal.trim().to_string())
+                .filter(|journal| !journal.is_empty() && seen.insert(journal.clone()))
+                .map(|journal| (journal, None))
+                .collect();
+            let ranks = self.get_ranks_batch(&journals, rankings::MAX_CONCURRENT_LOOKUPS).await;
+            Ok(papers
+                .iter_mut()
+                .map(|paper| {
+                    let metrics = ranks.get(paper.journal.trim()).and_then(Option::as_ref);
+                    fill_rankings(self.name(), paper, metrics)
+                })
+                .filter(|provenance| !provenance.is_empty())
+                .collect())
+        })
+    }
+}
+
+impl Enricher for AltmetricClient {
+    fn name(&self) -> &str {
+        "Altmetric"
+    }
+
+    fn url(&self) -> &str {
+        altmetric::ALTMETRIC_API_URL
+    }
+
+    /// Fill the attention score and mention counts
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let mut provenance = Provenance::new(self.name(), paper);
+            if paper.altmetric.is_none() {
+                if let Some(metrics) = self.lookup(&paper.doi).await? {
+                    paper.altmetric = Some(metrics);
+                    provenance.record("altmetric");
+                }
+            }
+            Ok(provenance)
+        })
+    }
+}
+
+impl Enricher for ImpactClient {
+    fn name(&self) -> &str {
+        "OpenAlex impact"
+    }
+
+    fn url(&self) -> &str {
+        openalex::OPENALEX_API_BASE
+    }
+
+    /// Fill missing citation counts and years, then the field-normalized
+    /// citation score
+    fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+        Box::pin(async move {
+            let mut provenance = Provenance::new(self.name(), paper);
+            if paper.normalized_citations.is_some() {
+                return Ok(provenance);
+            }
+            let Some(work) = self.lookup(&paper.doi).await? else {
+                return Ok(provenance);
+            };
+            if paper.citations.is_none() {
+                paper.citations = paper::parse_count(&work.citations);
+                if paper.citations.is_some() {
+                    provenance.record("citations");
+                }
+            }
+            if paper.year.is_none() {
+                paper.set_date(&work.year);
+                if paper.year.is_some() {
+                    provenance.record("year");
+                }
+            }
+            if let (Some(citations), Some(year)) = (paper.citations, paper.year) {
+                if let Some(mean) = self.baseline(&work.primary_topic_id, year).await? {
+                    paper.normalized_citations = impact::normalized_score(citations, mean);
+                    if paper.normalized_citations.is_some() {
+                        provenance.record("normalized_citations");
+                    }
+                }
+            }
+            Ok(provenance)
+        })
+    }
+}
+
+/// Credentials and caching for the built-in enrichers
+#[derive(Debug, Clone, Default)]
+pub struct EnricherSettings {
+    pub easyscholar_key: Option<String>,
+    pub s2_api_key: Option<String>,
+    pub unpaywall_email: Option<String>,
+    /// Elsevier API key for the Scopus abstract source
+    pub scopus_key: Option<String>,
+    /// Altmetric API key (optional; without one requests are spaced out)
+    pub altmetric_key: Option<String>,
+    /// How long API responses stay in the HTTP cache (`None` disables it)
+    pub cache_ttl: Option<Duration>,
+}
+
+impl EnricherSettings {
+    pub(crate) fn http_cache(&self, api: &str) -> Option<HttpCache> {
+        let ttl = self.cache_ttl?;
+        HttpCache::for_api(api, ttl)
+            .map_err(|e| warn!(error = %e, "HTTP cache unavailable"))
+            .ok()
+    }
+
+    /// Semantic Scholar client with its HTTP cache (the key falls back to `S2_API_KEY`)
+    pub(crate) fn s2_client(&self) -> SemanticScholarClient {
+        let client = SemanticScholarClient::new(self.s2_api_key.clone());
+        match self.http_cache("semanticscholar") {
+            Some(cache) => client.with_cache(cache),
+            None => client,
+        }
+    }
+}
+
+/// Create a built-in enricher by name (see [`BUILTIN_ENRICHERS`]; `s2` and
+/// `easyscholar` are accepted as aliases)
+pub fn builtin(name: &str, settings: &EnricherSettings) -> Result<Box<dyn Enricher>> {
+    match name.trim().to_lowercase().as_str() {
+        "crossref" => {
+            let client = CrossrefClient::new(3)?;
+            Ok(Box::new(match settings.http_cache("crossref") {
+                Some(cache) => client.with_cache(cache),
+                None => client,
+            }))
+        }
+        "semanticscholar" | "s2" => Ok(Box::new(SemanticScholarEnricher::new(settings.s2_client()))),
+        "unpaywall" => {
+            let email = settings.unpaywall_email.as_deref().ok_or_else(|| {
+                GscholarError::Config("The unpaywall enricher needs --unpaywall-email".to_string())
+            })?;
+            let client = UnpaywallClient::new(email)?;
+            Ok(Box::new(match settings.http_cache("unpaywall") {
+                Some(cache) => client.with_cache(cache),
+                None => client,
+            }))
+        }
+        "rankings" | "easyscholar" => {
+            let key = settings.easyscholar_key.clone().ok_or_else(|| {
+                GscholarError::Config("The rankings enricher needs --easyscholar-key".to_string())
+            })?;
+            let client = RankingClient::new(key)?;
+            Ok(Box::new(match settings.cache_ttl {
+                Some(ttl) => client.with_disk_cache(cache::default_cache_dir()?.join(cache::RANKINGS_CACHE_FILE), ttl),
+                None => client,
+            }))
+        }
+        "altmetric" => {
+            let client = AltmetricClient::new(settings.altmetric_key.clone())?;
+            Ok(Box::new(match settings.http_cache("altmetric") {
+                Some(cache) => client.with_cache(cache),
+                None => client,
+            }))
+        }
+        "impact" => {
+            let client = ImpactClient::new();
+            Ok(Box::new(match settings.http_cache("openalex") {
+                Some(cache) => client.with_cache(cache),
+                None => client,
+            }))
+        }
+        other => Err(GscholarError::Validation(format!(
+            "Unknown enricher '{}' (expected one of: {})",
+            other,
+            BUILTIN_ENRICHERS.join(", ")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enricher that sets a fixed abstract
+    struct FixedAbstract;
+
+    impl Enricher for FixedAbstract {
+        fn name(&self) -> &str {
+            "Fixed"
+        }
+
+        fn url(&self) -> &str {
+            "https://example.org"
+        }
+
+        fn enrich<'a>(&'a self, paper: &'a mut Paper) -> BoxFuture<'a, Result<Provenance>> {
+            Box::pin(async move {
+                let mut provenance = Provenance::new(self.name(), paper);
+                provenance.fill("abstract_text", &mut paper.abstract_text, "An abstract.");
+                provenance.fill("tldr", &mut paper.tldr, "  ");
+                Ok(provenance)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enrich_all_fills_only_gaps() -> Result<()> {
+        let mut papers = vec![
+            Paper { doi: "10.1/a".to_string(), ..Default::default() },
+            Paper { doi: "10.1/b".to_string(), abstract_text: "Kept.".to_string(), ..Default::default() },
+        ];
+        let provenance = FixedAbstract.enrich_all(&mut papers).await?;
+        assert_eq!(
+            provenance,
+            vec![Provenance { doi: "10.1/a".to_string(), enricher: "Fixed".to_string(), fields: "abstract_text".to_string() }]
+        );
+        assert_eq!(papers[0].abstract_text, "An abstract.");
+        assert_eq!(papers[1].abstract_text, "Kept.");
+        assert_eq!(papers[0].tldr, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin() -> Result<()> {
+        let settings = EnricherSettings::default();
+        assert_eq!(builtin("crossref", &settings)?.name(), "Crossref");
+        assert_eq!(builtin(" S2 ", &settings)?.name(), "Semantic Scholar");
+        assert!(builtin("unpaywall", &settings).is_err());
+        assert!(builtin("rankings", &settings).is_err());
+        assert!(builtin("scopus", &settings).is_err());
+        assert_eq!(builtin("altmetric", &settings)?.name(), "Altmetric");
+        assert_eq!(builtin("impact", &settings)?.name(), "OpenAlex impact");
+
+        let settings = EnricherSettings { unpaywall_email: Some("me@example.org".to_string()), ..Default::default() };
+        assert_eq!(builtin("Unpaywall", &settings)?.name(), "Unpaywall");
+        Ok(())
+    }
+}
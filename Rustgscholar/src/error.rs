@@ -10,7 +10,7 @@ use thiserror::Error;
 /// Uses `thiserror` for ergonomic error handling and automatic `Display` implementation.
 #[derive(Debug, Error)]
 pub enum GscholarError {
-    /// Browser automation error (Playwright)
+    /// Browser automation error (Chrome DevTools)
     #[error("Browser error: {0}")]
     Browser(String),
 
@@ -0,0 +1,344 @@
+//! Dry-run estimates (`--dry-run`).
+//!
+//! A dry run sends one cheap request per source for the hit count of the
+//! query, then projects the pages, API calls, EasyScholar queries, LLM
+//! tokens and cost, and wall-clock time of the full run from it. Later
+//! stages drop records in ways only the run itself can tell (duplicates,
+//! journal and ranking filters, missing DOIs, previously seen records), so
+//! every figure after Stage 1 is an upper bound. Timings are rough averages
+//! of the delays and rate limits of each client, before any cache hits.
+
+use crate::error::{GscholarError, Result};
+use crate::llm_filter::{LlmConfig, LlmProvider};
+use std::fmt::Write as _;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Characters of a typical paper sent to the LLM (title, ~250-word abstract, authors, journal)
+const TYPICAL_PAPER_CHARS: usize = 2000;
+
+/// Rough characters per token of English text
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Completion tokens of a typical verdict (label, confidence and a one-sentence reason)
+const TYPICAL_COMPLETION_TOKENS: u64 = 120;
+
+/// Seconds per Google Scholar page (random 0.5-2s delay plus the request)
+pub const GSCHOLAR_PAGE_SECONDS: f64 = 3.0;
+/// Seconds per OpenAlex page of 200 works
+pub const OPENALEX_PAGE_SECONDS: f64 = 1.5;
+/// Seconds per Semantic Scholar search page (1s rate limit plus the request)
+pub const S2_PAGE_SECONDS: f64 = 2.0;
+/// Seconds per Crossref lookup and worker (3 workers)
+const CROSSREF_LOOKUP_SECONDS: f64 = 1.0;
+const CROSSREF_WORKERS: f64 = 3.0;
+/// Seconds per EasyScholar query (rate limited to one per 0.6s)
+const EASYSCHOLAR_QUERY_SECONDS: f64 = 0.6;
+/// Seconds per Semantic Scholar batch of up to 500 DOIs
+const S2_BATCH_SECONDS: f64 = 3.0;
+/// Seconds per LLM request
+const LLM_CALL_SECONDS: f64 = 4.0;
+
+/// Built-in list prices in USD per 1M input/output tokens (longest prefix wins)
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("o4-mini", 1.10, 4.40),
+    ("o3-mini", 1.10, 4.40),
+    ("deepseek-chat", 0.27, 1.10),
+    ("deepseek-reasoner", 0.55, 2.19),
+];
+
+/// LLM price in USD per 1M tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LlmPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl LlmPrice {
+    /// Built-in price of a model (free for local providers), if known
+    pub fn for_model(provider: LlmProvider, model: &str) -> Option<Self> {
+        if provider == LlmProvider::Ollama {
+            return Some(Self { input: 0.0, output: 0.0 });
+        }
+        // "openai/gpt-4o-mini" on routers is priced like "gpt-4o-mini"
+        let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        MODEL_PRICES
+            .iter()
+            .filter(|(prefix, _, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|&(_, input, output)| Self { input, output })
+    }
+}
+
+/// Parse `input,output` (USD per 1M tokens), e.g. `0.15,0.6`
+impl FromStr for LlmPrice {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || GscholarError::Validation(format!("Invalid LLM price '{}' (expected input,output in USD per 1M tokens)", s));
+        let (input, output) = s.split_once(',').ok_or_else(invalid)?;
+        let parse = |v: &str| v.trim().parse::<f64>().ok().filter(|p| p.is_finite() && *p >= 0.0).ok_or_else(invalid);
+        Ok(Self { input: parse(input)?, output: parse(output)? })
+    }
+}
+
+/// Stage 1 projection for one source
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceEstimate {
+    pub source: String,
+    /// Hit count reported by the source; `None` when it could not be read
+    pub hits: Option<u64>,
+    /// Page requests the search will make
+    pub pages: usize,
+    /// Records those pages return
+    pub records: usize,
+    pub seconds_per_page: f64,
+}
+
+impl SourceEstimate {
+    /// Project the requested 1-indexed `pages` of `page_size` results
+    ///
+    /// Pages past `hits` or past `max_results` (results a source serves at
+    /// most) are not requested. Unknown hits assume full pages.
+    pub fn new(source: &str, hits: Option<u64>, pages: &[i32], page_size: usize, max_results: Option<usize>, seconds_per_page: f64) -> Self {
+        let available = hits.map_or(usize::MAX, |h| usize::try_from(h).unwrap_or(usize::MAX));
+        let available = max_results.map_or(available, |max| available.min(max));
+        let (mut requested, mut records) = (0, 0);
+        for &page in pages {
+            let offset = (page.max(1) as usize - 1).saturating_mul(page_size);
+            if offset < available {
+                requested += 1;
+                records += page_size.min(available - offset);
+            }
+        }
+        Self { source: source.to_string(), hits, pages: requested, records, seconds_per_page }
+    }
+}
+
+/// Stage 6 projection
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmEstimate {
+    pub model: String,
+    pub calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub concurrency: usize,
+    /// `None` for a model without a built-in price and no `--llm-price`
+    pub price: Option<LlmPrice>,
+}
+
+impl LlmEstimate {
+    /// One call per paper with prompts of `prompt_chars` characters (system
+    /// and user prompt without the paper)
+    pub fn new(config: &LlmConfig, calls: usize, prompt_chars: usize, price: Option<LlmPrice>) -> Self {
+        let prompt_per_call = ((prompt_chars + TYPICAL_PAPER_CHARS) / CHARS_PER_TOKEN) as u64;
+        let completion_per_call = TYPICAL_COMPLETION_TOKENS.min(u64::from(config.max_tokens));
+        Self {
+            model: config.model.clone(),
+            calls,
+            prompt_tokens: prompt_per_call * calls as u64,
+            completion_tokens: completion_per_call * calls as u64,
+            concurrency: config.concurrency.max(1),
+            price: price.or_else(|| LlmPrice::for_model(config.provider, &config.model)),
+        }
+    }
+
+    /// Cost in USD, if the price is known
+    pub fn cost(&self) -> Option<f64> {
+        self.price
+            .map(|p| (self.prompt_tokens as f64 * p.input + self.completion_tokens as f64 * p.output) / 1_000_000.0)
+    }
+}
+
+/// What a full run would do
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Estimate {
+    pub keyword: String,
+    pub ylo: i32,
+    pub sources: Vec<SourceEstimate>,
+    /// Records read from an input list instead of searching
+    pub input_records: usize,
+    pub crossref_lookups: usize,
+    /// At most one per record; unique journals are known only after Stage 2
+    pub easyscholar_queries: usize,
+    pub s2_batches: usize,
+    pub llm: Option<LlmEstimate>,
+    /// Notes on what the figures leave out
+    pub notes: Vec<String>,
+}
+
+impl Estimate {
+    /// Records identified in Stage 1
+    pub fn records(&self) -> usize {
+        self.input_records + self.sources.iter().map(|s| s.records).sum::<usize>()
+    }
+
+    /// Requests to every upstream API, the LLM included
+    pub fn api_calls(&self) -> usize {
+        self.sources.iter().map(|s| s.pages).sum::<usize>()
+            + self.crossref_lookups
+            + self.easyscholar_queries
+            + self.s2_batches
+            + self.llm.as_ref().map_or(0, |l| l.calls)
+    }
+
+    /// Wall-clock time of the run
+    pub fn duration(&self) -> Duration {
+        let search: f64 = self.sources.iter().map(|s| s.pages as f64 * s.seconds_per_page).sum();
+        let llm = self
+            .llm
+            .as_ref()
+            .map_or(0.0, |l| l.calls.div_ceil(l.concurrency) as f64 * LLM_CALL_SECONDS);
+        Duration::from_secs_f64(
+            search
+                + self.crossref_lookups as f64 * CROSSREF_LOOKUP_SECONDS / CROSSREF_WORKERS
+                + self.easyscholar_queries as f64 * EASYSCHOLAR_QUERY_SECONDS
+                + self.s2_batches as f64 * S2_BATCH_SECONDS
+                + llm,
+        )
+    }
+
+    /// Plain-text report
+    pub fn render(&self) -> String {
+        let mut out = format!("Dry run: \"{}\" (from {})\n\n", self.keyword, self.ylo);
+        if !self.sources.is_empty() {
+            let _ = writeln!(out, "{:<18} {:>12} {:>6} {:>9}", "Source", "Hits", "Pages", "Records");
+            for s in &self.sources {
+                let hits = s.hits.map_or_else(|| "unknown".to_string(), group);
+                let _ = writeln!(out, "{:<18} {:>12} {:>6} {:>9}", s.source, hits, s.pages, group(s.records as u64));
+            }
+            out.push('\n');
+        }
+        if self.input_records > 0 {
+            let _ = writeln!(out, "Input list: {} records\n", group(self.input_records as u64));
+        }
+
+        let _ = writeln!(out, "Records identified:        {}", group(self.records() as u64));
+        let _ = writeln!(out, "Crossref lookups:          {}", group(self.crossref_lookups as u64));
+        let _ = writeln!(out, "EasyScholar queries:       up to {}", group(self.easyscholar_queries as u64));
+        let _ = writeln!(out, "Semantic Scholar batches:  {}", self.s2_batches);
+        match &self.llm {
+            Some(llm) => {
+                let _ = writeln!(out, "LLM calls:                 up to {} ({})", group(llm.calls as u64), llm.model);
+                let _ = writeln!(
+                    out,
+                    "LLM tokens:                ~{} prompt + ~{} completion",
+                    group(llm.prompt_tokens),
+                    group(llm.completion_tokens)
+                );
+                let cost = match (llm.cost(), llm.price) {
+                    (Some(cost), Some(price)) => {
+                        format!("~${:.2} (${} / ${} per 1M tokens)", cost, price.input, price.output)
+                    }
+                    _ => "unknown (set --llm-price input,output)".to_string(),
+                };
+                let _ = writeln!(out, "LLM cost:                  {}", cost);
+            }
+            None => out.push_str("LLM calls:                 none (Stage 6 will not run)\n"),
+        }
+        let _ = writeln!(out, "API calls:                 ~{}", group(self.api_calls() as u64));
+        let _ = writeln!(out, "Estimated time:            ~{}", format_duration(self.duration()));
+        for note in &self.notes {
+            let _ = writeln!(out, "\nNote: {}", note);
+        }
+        out
+    }
+}
+
+/// `1234567` -> `1,234,567`
+fn group(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `3725s` -> `1h 02m`, `200s` -> `3m 20s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_price() -> Result<()> {
+        assert_eq!(LlmPrice::for_model(LlmProvider::OpenAi, "gpt-4o-mini-2024-07-18"), Some(LlmPrice { input: 0.15, output: 0.60 }));
+        assert_eq!(LlmPrice::for_model(LlmProvider::OpenAi, "openai/gpt-4o"), Some(LlmPrice { input: 2.50, output: 10.00 }));
+        assert_eq!(LlmPrice::for_model(LlmProvider::OpenAi, "my-finetune"), None);
+        assert_eq!(LlmPrice::for_model(LlmProvider::Ollama, "qwen2.5:7b"), Some(LlmPrice { input: 0.0, output: 0.0 }));
+        assert_eq!("0.3, 1.2".parse::<LlmPrice>()?, LlmPrice { input: 0.3, output: 1.2 });
+        assert!("0.3".parse::<LlmPrice>().is_err() && "-1,2".parse::<LlmPrice>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_estimate() {
+        // 250 hits over pages 1-5 of 100: three pages, 250 records
+        let s2 = SourceEstimate::new("Semantic Scholar", Some(250), &[1, 2, 3, 4, 5], 100, Some(1000), S2_PAGE_SECONDS);
+        assert_eq!((s2.pages, s2.records), (3, 250));
+        // Results past the serving limit are never fetched
+        let capped = SourceEstimate::new("Semantic Scholar", Some(50_000), &[10, 11], 100, Some(1000), S2_PAGE_SECONDS);
+        assert_eq!((capped.pages, capped.records), (1, 100));
+        let unknown = SourceEstimate::new("Google Scholar", None, &[1, 2], 10, None, GSCHOLAR_PAGE_SECONDS);
+        assert_eq!((unknown.pages, unknown.records), (2, 20));
+    }
+
+    #[test]
+    fn test_estimate_totals() {
+        let config = LlmConfig {
+            provider: LlmProvider::OpenAi,
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: String::new(),
+            model: "gpt-4o-mini".to_string(),
+            filter_help: String::new(),
+            json_mode: crate::llm_filter::JsonMode::Off,
+            max_retries: 0,
+            cache_dir: None,
+            cache_ttl: Duration::ZERO,
+            concurrency: 10,
+            timeout: None,
+            temperature: None,
+            max_tokens: 1024,
+        };
+        let estimate = Estimate {
+            keyword: "landslide".to_string(),
+            ylo: 2021,
+            sources: vec![SourceEstimate::new("OpenAlex", Some(1_234), &[1, 2, 3], 200, None, OPENALEX_PAGE_SECONDS)],
+            easyscholar_queries: 600,
+            s2_batches: 2,
+            llm: Some(LlmEstimate::new(&config, 600, 2000, None)),
+            ..Default::default()
+        };
+        assert_eq!(estimate.records(), 600);
+        assert_eq!(estimate.api_calls(), 3 + 600 + 2 + 600);
+        // 4.5s search + 360s EasyScholar + 6s Semantic Scholar + 60 rounds of 4s LLM calls
+        assert_eq!(estimate.duration().as_secs(), 610);
+
+        let llm = estimate.llm.as_ref().map(|l| (l.prompt_tokens, l.completion_tokens, l.cost()));
+        assert_eq!(llm, Some((600_000, 72_000, Some((600_000.0 * 0.15 + 72_000.0 * 0.60) / 1_000_000.0))));
+        let text = estimate.render();
+        assert!(text.contains("Records identified:        600"), "{}", text);
+        assert!(text.contains("~$0.13 ($0.15 / $0.6 per 1M tokens)"), "{}", text);
+        assert!(text.contains("Estimated time:            ~10m 10s"), "{}", text);
+    }
+}
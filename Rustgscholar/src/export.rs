@@ -0,0 +1,451 @@
+//! Bibliography export of pipeline CSVs.
+//!
+//! Reads any stage's CSV (`1_openalex.csv`, `5_unified.csv`,
+//! `7_relevant.csv`, ...) by column name and renders the papers as
+//! reference-manager formats (BibTeX for LaTeX, RIS for
+//! EndNote/Zotero/Mendeley, CSL-JSON for pandoc/citeproc). Column names differ between stages, so each
+//! field is looked up under several aliases.
+
+use crate::error::{GscholarError, Result};
+use crate::paper::Paper;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// Output format for `export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Bibtex,
+    Ris,
+    CslJson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bibtex" | "bib" => Ok(Self::Bibtex),
+            "ris" => Ok(Self::Ris),
+            "csl-json" | "csljson" => Ok(Self::CslJson),
+            _ => Err(GscholarError::Config(format!("Invalid export format: {}", s))),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// File extension of the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Bibtex => "bib",
+            Self::Ris => "ris",
+            Self::CslJson => "json",
+        }
+    }
+}
+
+/// A paper in a stage-independent shape
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportRecord {
+    pub title: String,
+    pub authors: Vec<String>,
+    /// Whether the author list was truncated upstream ("A, B, C ...")
+    pub more_authors: bool,
+    pub year: String,
+    pub date: String,
+    pub journal: String,
+    pub doi: String,
+    pub url: String,
+    pub pdf_url: String,
+    pub abstract_text: String,
+    pub keywords: Vec<String>,
+}
+
+impl ExportRecord {
+    /// Build a record from a CSV row keyed by column name
+    pub fn from_row(row: &HashMap<String, String>) -> Self {
+        let field = |aliases: &[&str]| -> String {
+            aliases
+                .iter()
+                .filter_map(|a| row.get(*a))
+                .map(|v| v.trim())
+                .find(|v| !v.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        // Crossref lists every author; OpenAlex keeps the first three
+        let author_field = field(&["crossref_authors", "author", "authors"]);
+        let more_authors = author_field.ends_with("...");
+        let authors = split_list(author_field.trim_end_matches("...").trim_end());
+
+        let date = field(&["date", "publication_date", "crossref_date"]);
+        let year = Some(field(&["year"]))
+            .filter(|y| !y.is_empty())
+            .unwrap_or_else(|| date.chars().take(4).filter(|c| c.is_ascii_digit()).collect());
+
+        Self {
+            title: field(&["title"]),
+            authors,
+            more_authors,
+            year,
+            date,
+            journal: field(&["journal", "venue"]),
+            doi: field(&["doi"]),
+            url: field(&["article_url", "url"]),
+            pdf_url: field(&["pdf_url"]),
+            abstract_text: field(&["abstract_text", "abstract", "snippet"]),
+            keywords: split_list(&field(&["keywords"])),
+        }
+    }
+}
+
+impl From<&Paper> for ExportRecord {
+    fn from(paper: &Paper) -> Self {
+        Self {
+            title: paper.title.clone(),
+            authors: paper.authors.iter().map(|a| a.name.clone()).collect(),
+            more_authors: paper.more_authors,
+            year: paper.year.map(|y| y.to_string()).unwrap_or_default(),
+            date: paper.date_string(),
+            journal: paper.journal.clone(),
+            doi: paper.doi.clone(),
+            url: paper.article_url.clone(),
+            pdf_url: paper.pdf_url.clone(),
+            abstract_text: paper.abstract_text.clone(),
+            keywords: Vec::new(),
+        }
+    }
+}
+
+/// Split a ", " or ";" separated list, dropping empty items
+fn split_list(value: &str) -> Vec<String> {
+    let separator = if value.contains(';') { ';' } else { ',' };
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Read papers from a pipeline CSV
+pub fn read_csv(path: &Path) -> Result<Vec<ExportRecord>> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read CSV header: {}", e)))?
+        .clone();
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(|e| GscholarError::Parse(format!("Failed to read CSV row: {}", e)))?;
+        let map: HashMap<String, String> = headers
+            .iter()
+            .zip(row.iter())
+            .map(|(h, v)| (h.to_string(), v.to_string()))
+            .collect();
+        let record = ExportRecord::from_row(&map);
+        if !record.title.is_empty() {
+            records.push(record);
+        }
+    }
+
+    info!(path = ?path, count = records.len(), "Loaded papers for export");
+    Ok(records)
+}
+
+/// Render records in the given format
+pub fn render(format: ExportFormat, records: &[ExportRecord]) -> String {
+    match format {
+        ExportFormat::Bibtex => to_bibtex(records),
+        ExportFormat::Ris => to_ris(records),
+        ExportFormat::CslJson => to_csl_json(records),
+    }
+}
+
+/// Read a pipeline CSV and write it in the given format; returns the paper count
+pub fn export_file(input: &Path, output: &Path, format: ExportFormat) -> Result<usize> {
+    let records = read_csv(input)?;
+    std::fs::write(output, render(format, &records))?;
+    Ok(records.len())
+}
+
+// === BibTeX ===
+
+/// Render records as BibTeX `@article` entries with unique citation keys
+pub fn to_bibtex(records: &[ExportRecord]) -> String {
+    let mut used_keys = HashSet::new();
+    let mut out = String::new();
+
+    for record in records {
+        let key = unique_key(&citation_key(record), &mut used_keys);
+
+        let mut authors = record.authors.clone();
+        if record.more_authors {
+            authors.push("others".to_string());
+        }
+
+        let mut fields: Vec<(&str, String)> = vec![
+            // Double braces keep the title's capitalization
+            ("title", format!("{{{}}}", escape_bibtex(&record.title))),
+            ("author", escape_bibtex(&authors.join(" and "))),
+            ("journal", escape_bibtex(&record.journal)),
+            ("year", record.year.clone()),
+            // DOI and URL are verbatim fields
+            ("doi", record.doi.clone()),
+            ("url", record.url.clone()),
+            ("abstract", escape_bibtex(&record.abstract_text)),
+            ("keywords", escape_bibtex(&record.keywords.join(", "))),
+        ];
+        fields.retain(|(_, v)| !v.is_empty() && v != "{}");
+
+        out.push_str(&format!("@article{{{},\n", key));
+        for (name, value) in fields {
+            out.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+        out.push_str("}\n\n");
+    }
+
+    out
+}
+
+/// `{family name}{year}{first title word}`, lowercase ASCII
+fn citation_key(record: &ExportRecord) -> String {
+    let ascii_word = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    let family = record
+        .authors
+        .first()
+        .and_then(|a| a.split_whitespace().last())
+        .map(ascii_word)
+        .unwrap_or_default();
+    let word = record
+        .title
+        .split_whitespace()
+        .map(ascii_word)
+        .find(|w| w.len() > 3)
+        .unwrap_or_default();
+
+    let key = format!("{}{}{}", family, record.year, word);
+    if key.is_empty() {
+        "paper".to_string()
+    } else {
+        key
+    }
+}
+
+/// Append a, b, c, ... to a key until it is unused
+fn unique_key(base: &str, used: &mut HashSet<String>) -> String {
+    if used.insert(base.to_string()) {
+        return base.to_string();
+    }
+    (0..)
+        .map(|i| format!("{}{}", base, suffix(i)))
+        .find(|k| used.insert(k.clone()))
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// 0 -> "a", 25 -> "z", 26 -> "aa", ...
+fn suffix(mut i: usize) -> String {
+    let mut s = String::new();
+    loop {
+        s.insert(0, (b'a' + (i % 26) as u8) as char);
+        if i < 26 {
+            return s;
+        }
+        i = i / 26 - 1;
+    }
+}
+
+/// Escape characters with special meaning in BibTeX/LaTeX
+fn escape_bibtex(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '%' | '&' | '$' | '#' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\n' | '\r' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// === RIS ===
+
+/// Render records as RIS `JOUR` records
+pub fn to_ris(records: &[ExportRecord]) -> String {
+    let mut out = String::new();
+
+    for record in records {
+        let mut tags: Vec<(&str, String)> = vec![("TY", "JOUR".to_string()), ("TI", record.title.clone())];
+        tags.extend(record.authors.iter().map(|a| ("AU", a.clone())));
+        tags.push(("PY", record.year.clone()));
+        // RIS dates are YYYY/MM/DD
+        if record.date.len() > 4 {
+            tags.push(("DA", record.date.replace('-', "/")));
+        }
+        tags.push(("T2", record.journal.clone()));
+        tags.push(("DO", record.doi.clone()));
+        tags.push(("UR", record.url.clone()));
+        tags.push(("L1", record.pdf_url.clone()));
+        tags.push(("AB", record.abstract_text.clone()));
+        tags.extend(record.keywords.iter().map(|k| ("KW", k.clone())));
+
+        for (tag, value) in tags {
+            // Every tag must fit on one line
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !value.is_empty() {
+                out.push_str(&format!("{}  - {}\n", tag, value));
+            }
+        }
+        out.push_str("ER  - \n\n");
+    }
+
+    out
+}
+
+// === CSL-JSON ===
+
+/// Render records as a CSL-JSON array of `article-journal` items
+///
+/// Item IDs are the same citation keys as in the BibTeX output, so
+/// `[@smith2021deep]` works with either file in pandoc.
+pub fn to_csl_json(records: &[ExportRecord]) -> String {
+    let mut used_keys = HashSet::new();
+    let items: Vec<serde_json::Value> = records
+        .iter()
+        .map(|record| {
+            let mut item = serde_json::Map::new();
+            item.insert("id".into(), unique_key(&citation_key(record), &mut used_keys).into());
+            item.insert("type".into(), "article-journal".into());
+            item.insert("title".into(), record.title.clone().into());
+
+            let authors: Vec<serde_json::Value> = record.authors.iter().map(|a| csl_name(a)).collect();
+            if !authors.is_empty() {
+                item.insert("author".into(), authors.into());
+            }
+            if let Some(parts) = csl_date_parts(&record.date, &record.year) {
+                item.insert("issued".into(), serde_json::json!({ "date-parts": [parts] }));
+            }
+            for (name, value) in [
+                ("container-title", &record.journal),
+                ("DOI", &record.doi),
+                ("URL", &record.url),
+                ("abstract", &record.abstract_text),
+            ] {
+                if !value.is_empty() {
+                    item.insert(name.into(), value.clone().into());
+                }
+            }
+            if !record.keywords.is_empty() {
+                item.insert("keyword".into(), record.keywords.join(", ").into());
+            }
+            serde_json::Value::Object(item)
+        })
+        .collect();
+
+    format!("{:#}\n", serde_json::Value::Array(items))
+}
+
+/// CSL name: the last word is the family name, unsplittable names are literal
+fn csl_name(author: &str) -> serde_json::Value {
+    match author.trim().rsplit_once(' ') {
+        Some((given, family)) => serde_json::json!({ "family": family, "given": given.trim() }),
+        None => serde_json::json!({ "literal": author.trim() }),
+    }
+}
+
+/// `[year, month, day]` from an ISO date, or `[year]` from the year alone
+fn csl_date_parts(date: &str, year: &str) -> Option<Vec<u32>> {
+    let parts: Vec<u32> = date.split('-').map_while(|p| p.parse().ok()).take(3).collect();
+    if parts.first().is_some_and(|&y| y > 999) {
+        return Some(parts);
+    }
+    year.parse().ok().map(|y| vec![y])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> ExportRecord {
+        let row: HashMap<String, String> = [
+            ("title", "Deep learning for landslide mapping"),
+            ("author", "Jane Smith, Li Wei, Ana Costa ..."),
+            ("publication_date", "2021-05-03"),
+            ("venue", "Landslides & Hazards"),
+            ("doi", "10.1007/s10346_021"),
+            ("keywords", "landslide, CNN"),
+            ("snippet", "We map 50% of slopes."),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        ExportRecord::from_row(&row)
+    }
+
+    #[test]
+    fn test_from_row_aliases() {
+        let r = record();
+        assert_eq!(r.authors, vec!["Jane Smith", "Li Wei", "Ana Costa"]);
+        assert!(r.more_authors);
+        assert_eq!(r.year, "2021");
+        assert_eq!(r.journal, "Landslides & Hazards");
+        assert_eq!(r.keywords, vec!["landslide", "CNN"]);
+    }
+
+    #[test]
+    fn test_to_bibtex() {
+        let bib = to_bibtex(&[record(), record()]);
+        assert!(bib.contains("@article{smith2021deep,\n"));
+        assert!(bib.contains("@article{smith2021deepa,\n"));
+        assert!(bib.contains("  author = {Jane Smith and Li Wei and Ana Costa and others},\n"));
+        assert!(bib.contains("  title = {{Deep learning for landslide mapping}},\n"));
+        assert!(bib.contains("  journal = {Landslides \\& Hazards},\n"));
+        assert!(bib.contains("  doi = {10.1007/s10346_021},\n"));
+        assert!(bib.contains("  abstract = {We map 50\\% of slopes.},\n"));
+        assert!(!bib.contains("url ="));
+        assert_eq!(suffix(26), "aa");
+    }
+
+    #[test]
+    fn test_to_ris() {
+        let ris = to_ris(&[record()]);
+        assert!(ris.starts_with("TY  - JOUR\nTI  - Deep learning for landslide mapping\nAU  - Jane Smith\n"));
+        assert!(ris.contains("AU  - Ana Costa\nPY  - 2021\nDA  - 2021/05/03\n"));
+        assert!(ris.contains("DO  - 10.1007/s10346_021\n"));
+        assert!(ris.contains("AB  - We map 50% of slopes.\n"));
+        assert!(ris.contains("KW  - CNN\n"));
+        assert!(!ris.contains("UR  -"));
+        assert!(ris.ends_with("ER  - \n\n"));
+    }
+
+    #[test]
+    fn test_to_csl_json() -> Result<()> {
+        let items: serde_json::Value = serde_json::from_str(&to_csl_json(&[record(), record()]))?;
+        assert_eq!(items[0]["id"], "smith2021deep");
+        assert_eq!(items[1]["id"], "smith2021deepa");
+        assert_eq!(items[0]["type"], "article-journal");
+        assert_eq!(items[0]["author"][1], serde_json::json!({ "family": "Wei", "given": "Li" }));
+        assert_eq!(items[0]["issued"], serde_json::json!({ "date-parts": [[2021, 5, 3]] }));
+        assert_eq!(items[0]["container-title"], "Landslides & Hazards");
+        assert_eq!(items[0]["DOI"], "10.1007/s10346_021");
+        assert!(items[0].get("URL").is_none());
+        assert_eq!(csl_date_parts("", "2020"), Some(vec![2020]));
+        Ok(())
+    }
+}
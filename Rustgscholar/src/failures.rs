@@ -0,0 +1,114 @@
+//! Per-item failures collected over a run.
+//!
+//! Stages keep going when a single lookup fails (a title Crossref cannot
+//! match, a journal EasyScholar does not rank, an LLM call that errors out).
+//! Each such failure is recorded here instead of only being logged, written
+//! to `errors.csv` at the end of the run and summarized per stage.
+
+use crate::error::{GscholarError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the failure table in the run folder
+pub const ERRORS_FILE: &str = "errors.csv";
+
+/// One failed item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Failure {
+    /// Stage name as accepted by `--stages` (e.g., "crossref", "llm")
+    pub stage: String,
+    /// DOI, title, journal or service the failure concerns
+    pub id: String,
+    pub message: String,
+}
+
+/// Failures recorded while the stages run
+#[derive(Debug, Default)]
+pub struct FailureLog {
+    failures: Mutex<Vec<Failure>>,
+}
+
+impl FailureLog {
+    /// Record a failure
+    pub fn record(&self, stage: &str, id: &str, message: impl ToString) {
+        if let Ok(mut failures) = self.failures.lock() {
+            failures.push(Failure { stage: stage.to_string(), id: id.to_string(), message: message.to_string() });
+        }
+    }
+
+    /// Everything recorded so far, in order
+    pub fn take(&self) -> Vec<Failure> {
+        self.failures.lock().map(|mut f| std::mem::take(&mut *f)).unwrap_or_default()
+    }
+}
+
+/// Write `errors.csv` (just the header when nothing failed)
+pub fn save(folder: &Path, failures: &[Failure]) -> Result<PathBuf> {
+    let path = folder.join(ERRORS_FILE);
+    let csv_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write {}: {}", ERRORS_FILE, e));
+    let mut wtr = csv::Writer::from_path(&path).map_err(csv_err)?;
+    wtr.write_record(["stage", "id", "message"]).map_err(csv_err)?;
+    for failure in failures {
+        wtr.write_record([&failure.stage, &failure.id, &failure.message]).map_err(csv_err)?;
+    }
+    wtr.flush()?;
+    Ok(path)
+}
+
+/// Per-stage table of failure counts with the first failure of each stage
+pub fn summary(failures: &[Failure]) -> String {
+    let mut stages: Vec<(&str, usize, &Failure)> = Vec::new();
+    for failure in failures {
+        match stages.iter_mut().find(|(stage, _, _)| *stage == failure.stage) {
+            Some((_, count, _)) => *count += 1,
+            None => stages.push((&failure.stage, 1, failure)),
+        }
+    }
+
+    let width = stages.iter().map(|(stage, _, _)| stage.len()).max().unwrap_or(0).max("Stage".len());
+    let mut out = format!("{:<width$}  {:>6}  Example\n", "Stage", "Count");
+    for (stage, count, first) in stages {
+        let example = match first.id.as_str() {
+            "" => first.message.clone(),
+            id => format!("{}: {}", truncate(id, 50), first.message),
+        };
+        out.push_str(&format!("{:<width$}  {:>6}  {}\n", stage, count, truncate(&example, 100)));
+    }
+    out
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_and_summary() -> Result<()> {
+        let log = FailureLog::default();
+        log.record("crossref", "Slope stability", "No Crossref match");
+        log.record("easyscholar", "Landslides", "No EasyScholar ranking");
+        log.record("crossref", "Debris flows", "No Crossref match");
+        let failures = log.take();
+        assert_eq!(failures.len(), 3);
+        assert!(log.take().is_empty());
+
+        let table = summary(&failures);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Stage         Count  Example");
+        assert_eq!(lines[1], "crossref          2  Slope stability: No Crossref match");
+        assert_eq!(lines[2], "easyscholar       1  Landslides: No EasyScholar ranking");
+
+        let dir = tempfile::TempDir::new()?;
+        let path = save(dir.path(), &failures)?;
+        let content = std::fs::read_to_string(path)?;
+        assert!(content.starts_with("stage,id,message\ncrossref,Slope stability,No Crossref match\n"));
+        Ok(())
+    }
+}
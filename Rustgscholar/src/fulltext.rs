@@ -0,0 +1,180 @@
+//! Full-text extraction from downloaded PDFs.
+//!
+//! With `--extract-fulltext` every PDF in `pdfs/` is sent to a GROBID server
+//! (see [`grobid`](crate::grobid), e.g. `docker run -p 8070:8070
+//! grobid/grobid`). Its TEI output is kept as `fulltext/*.tei.xml` and
+//! flattened into plain text under `fulltext/*.txt`; the parsed authors and
+//! reference lists are returned for the Stage 5 tables. With `--llm-fulltext`
+//! the start of the text is added to the LLM input of papers whose abstract
+//! is missing or too short to judge.
+
+use crate::error::{GscholarError, Result};
+use crate::grobid::{GrobidClient, TeiDocument};
+use crate::paper::Paper;
+use crate::stream;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Folder of the extracted texts in the run folder
+pub const FULLTEXT_FOLDER: &str = "fulltext";
+
+/// Abstracts shorter than this get the full-text excerpt in LLM stages
+pub const MIN_ABSTRACT_CHARS: usize = 300;
+
+/// PDFs sent to GROBID at once (its default pool handles a few)
+const MAX_CONCURRENT_EXTRACTIONS: usize = 2;
+
+/// What [`extract_all`] did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extraction {
+    pub extracted: usize,
+    /// Already in the folder from an earlier run
+    pub existing: usize,
+    /// (DOI, error) of each failed extraction
+    pub failed: Vec<(String, String)>,
+    /// Parsed TEI of each paper with a PDF, by DOI
+    pub documents: Vec<(String, TeiDocument)>,
+}
+
+/// Row of `5_references.csv`: one reference of a paper with a PDF
+///
+/// The `title`/`doi` columns load with `enrich --input` for backward snowballing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceRow {
+    pub citing_doi: String,
+    pub title: String,
+    pub author: String,
+    pub year: String,
+    pub journal: String,
+    pub doi: String,
+}
+
+/// Row of `5_affiliations.csv`: one author of a paper with a PDF
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AffiliationRow {
+    pub doi: String,
+    pub author: String,
+    /// Affiliations as a "; " separated list
+    pub affiliation: String,
+}
+
+impl Extraction {
+    pub fn reference_rows(&self) -> Vec<ReferenceRow> {
+        self.documents
+            .iter()
+            .flat_map(|(doi, document)| {
+                document.references.iter().map(move |r| ReferenceRow {
+                    citing_doi: doi.clone(),
+                    title: r.title.clone(),
+                    author: r.author.clone(),
+                    year: r.year.clone(),
+                    journal: r.journal.clone(),
+                    doi: r.doi.clone(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn affiliation_rows(&self) -> Vec<AffiliationRow> {
+        self.documents
+            .iter()
+            .flat_map(|(doi, document)| {
+                document.authors.iter().map(move |a| AffiliationRow {
+                    doi: doi.clone(),
+                    author: a.name.clone(),
+                    affiliation: a.affiliations.join("; "),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Run each downloaded PDF (`pdf_path`) through GROBID, writing
+/// `run_folder/fulltext/<name>.tei.xml` and `.txt` (existing TEI files are reused)
+pub async fn extract_all(client: &GrobidClient, papers: &[Paper], run_folder: &Path) -> Result<Extraction> {
+    std::fs::create_dir_all(run_folder.join(FULLTEXT_FOLDER))?;
+    let extractions: Vec<_> = papers
+        .iter()
+        .filter_map(|paper| {
+            let txt = text_path(paper, run_folder)?;
+            let pdf = run_folder.join(&paper.pdf_path);
+            Some(async move { (paper.doi.clone(), extract(client, &pdf, &txt).await) })
+        })
+        .collect();
+    let outcomes = stream::in_order(extractions, MAX_CONCURRENT_EXTRACTIONS).await;
+
+    let mut extraction = Extraction::default();
+    for (doi, outcome) in outcomes {
+        match outcome {
+            Ok((document, fetched)) => {
+                match fetched {
+                    true => extraction.extracted += 1,
+                    false => extraction.existing += 1,
+                }
+                extraction.documents.push((doi, document));
+            }
+            Err(e) => extraction.failed.push((doi, e.to_string())),
+        }
+    }
+    Ok(extraction)
+}
+
+/// Parsed TEI of one PDF and whether GROBID was asked now
+async fn extract(client: &GrobidClient, pdf: &Path, txt: &Path) -> Result<(TeiDocument, bool)> {
+    let tei_path = txt.with_extension("tei.xml");
+    let (tei, fetched) = match std::fs::read_to_string(&tei_path) {
+        Ok(tei) => (tei, false),
+        Err(_) => (client.process_fulltext(pdf).await?, true),
+    };
+    let document = TeiDocument::parse(&tei)?;
+    if document.text.trim().is_empty() {
+        return Err(GscholarError::Parse("GROBID found no text (scanned PDF?)".to_string()));
+    }
+    if fetched {
+        std::fs::write(&tei_path, &tei)?;
+    }
+    if fetched || !txt.exists() {
+        std::fs::write(txt, &document.text)?;
+    }
+    Ok((document, fetched))
+}
+
+/// Where a paper's extracted text lives (`None` without a downloaded PDF)
+pub fn text_path(paper: &Paper, run_folder: &Path) -> Option<PathBuf> {
+    let stem = Path::new(paper.pdf_path.trim()).file_stem()?;
+    Some(run_folder.join(FULLTEXT_FOLDER).join(format!("{}.txt", stem.to_string_lossy())))
+}
+
+/// Start of a paper's extracted text, when its abstract is too short to judge
+///
+/// Returns at most `max_chars` characters, cut at a word boundary.
+pub fn excerpt(paper: &Paper, run_folder: &Path, max_chars: usize) -> Option<String> {
+    if paper.abstract_text.trim().chars().count() >= MIN_ABSTRACT_CHARS {
+        return None;
+    }
+    let text = std::fs::read_to_string(text_path(paper, run_folder)?).ok()?;
+    let text = text.trim();
+    let excerpt = match text.char_indices().nth(max_chars) {
+        Some((end, _)) => text[..end].rsplit_once(char::is_whitespace).map_or(&text[..end], |(head, _)| head),
+        None => text,
+    };
+    (!excerpt.is_empty()).then(|| excerpt.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excerpt() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::create_dir_all(dir.path().join(FULLTEXT_FOLDER))?;
+        std::fs::write(dir.path().join(FULLTEXT_FOLDER).join("10.1_a.txt"), "Landslide inventory mapping methods")?;
+        let mut paper = Paper { pdf_path: "pdfs/10.1_a.pdf".to_string(), ..Default::default() };
+        assert_eq!(excerpt(&paper, dir.path(), 22).as_deref(), Some("Landslide inventory"));
+
+        paper.abstract_text = "A long abstract. ".repeat(30);
+        assert_eq!(excerpt(&paper, dir.path(), 22), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! GROBID client and TEI parsing.
+//!
+//! [GROBID](https://github.com/kermitt2/grobid) turns a PDF into TEI XML
+//! with the paper's header (authors, affiliations), body and bibliography.
+//! [`TeiDocument::parse`] reads the parts the pipeline uses: the plain text
+//! for `fulltext/`, the authors with their affiliations, and the parsed
+//! reference list, which allows backward snowballing for papers whose
+//! references OpenAlex and Crossref do not have.
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::http;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Default GROBID server
+pub const DEFAULT_GROBID_URL: &str = "http://localhost:8070";
+
+/// GROBID client
+pub struct GrobidClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl GrobidClient {
+    /// Create a client for the GROBID server at `url` (e.g., [`DEFAULT_GROBID_URL`])
+    pub fn new(url: &str) -> Result<Self> {
+        // Long timeout for large PDFs; not through --proxy, the server is usually local
+        let client = http::builder(&http::HttpSettings { proxy: None, ..http::settings().clone() })?
+            .timeout(Duration::from_secs(180))
+            .build()
+            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self { client, url: url.trim().trim_end_matches('/').to_string() })
+    }
+
+    /// TEI XML of one PDF (header, body and bibliography)
+    pub async fn process_fulltext(&self, pdf: &Path) -> Result<String> {
+        let bytes = std::fs::read(pdf)?;
+        let name = pdf.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(name)
+            .mime_str("application/pdf")?;
+        let form = reqwest::multipart::Form::new()
+            .part("input", part)
+            .text("includeRawAffiliations", "1");
+        let response = self
+            .client
+            .post(format!("{}/api/processFulltextDocument", self.url))
+            .multipart(form)
+            .send()
+            .await?;
+        match response.status() {
+            // GROBID answers 503 when all its workers are busy
+            reqwest::StatusCode::SERVICE_UNAVAILABLE => return Err(GscholarError::RateLimited(5)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("GROBID error: {}", status),
+                });
+            }
+            _ => {}
+        }
+        Ok(response.text().await?)
+    }
+}
+
+/// One author of the paper itself
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TeiAuthor {
+    pub name: String,
+    pub affiliations: Vec<String>,
+}
+
+/// One entry of the paper's reference list
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TeiReference {
+    pub title: String,
+    /// Authors as a "; " separated list
+    pub author: String,
+    pub year: String,
+    /// Journal, book or proceedings title
+    pub journal: String,
+    pub doi: String,
+}
+
+/// The parts of a GROBID TEI document the pipeline uses
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TeiDocument {
+    pub authors: Vec<TeiAuthor>,
+    pub references: Vec<TeiReference>,
+    /// Abstract, section headings and paragraphs as plain text
+    pub text: String,
+}
+
+impl TeiDocument {
+    pub fn parse(tei: &str) -> Result<Self> {
+        let header = first(&regex(r"(?s)<sourceDesc\b[^>]*>(.*?)</sourceDesc>")?, tei);
+        let author_re = regex(r"(?s)<author\b[^>]*>(.*?)</author>")?;
+        let affiliation_re = regex(r"(?s)<affiliation\b[^>]*>(.*?)</affiliation>")?;
+        let org_re = regex(r"(?s)<orgName\b[^>]*>(.*?)</orgName>")?;
+        let authors = author_re
+            .captures_iter(&header)
+            .filter_map(|author| {
+                let name = pers_name(&author[1]).ok().flatten()?;
+                let affiliations = affiliation_re
+                    .captures_iter(&author[1])
+                    .map(|affiliation| {
+                        let orgs: Vec<String> = org_re.captures_iter(&affiliation[1]).map(|o| plain(&o[1])).collect();
+                        orgs.join(", ")
+                    })
+                    .filter(|affiliation| !affiliation.is_empty())
+                    .collect();
+                Some(TeiAuthor { name, affiliations })
+            })
+            .collect();
+
+        let bibliography = first(&regex(r"(?s)<listBibl\b[^>]*>(.*?)</listBibl>")?, tei);
+        let references = regex(r"(?s)<biblStruct\b[^>]*>(.*?)</biblStruct>")?
+            .captures_iter(&bibliography)
+            .map(|entry| parse_reference(&entry[1]))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|r| !r.title.is_empty() || !r.doi.is_empty())
+            .collect();
+
+        Ok(Self { authors, references, text: tei_to_text(tei)? })
+    }
+}
+
+fn parse_reference(entry: &str) -> Result<TeiReference> {
+    let title = |level: &str| -> Result<String> {
+        let re = regex(&format!(r#"(?s)<title\b[^>]*level="{}"[^>]*>(.*?)</title>"#, level))?;
+        Ok(plain(&first(&re, entry)))
+    };
+    let analytic = title("a")?;
+    let (title_text, journal) = match analytic.is_empty() {
+        // A book or report: its own title is the monograph title
+        true => (title("m")?, String::new()),
+        false => (analytic, [title("j")?, title("m")?].into_iter().find(|t| !t.is_empty()).unwrap_or_default()),
+    };
+    let authors_block = match first(&regex(r"(?s)<analytic\b[^>]*>(.*?)</analytic>")?, entry) {
+        block if block.contains("<author") => block,
+        _ => entry.to_string(),
+    };
+    let authors = regex(r"(?s)<author\b[^>]*>(.*?)</author>")?
+        .captures_iter(&authors_block)
+        .filter_map(|a| pers_name(&a[1]).ok().flatten())
+        .collect::<Vec<_>>();
+    let year = first(&regex(r#"<date\b[^>]*when="(\d{4})"#)?, entry);
+    let doi = plain(&first(&regex(r#"(?s)<idno\b[^>]*type="DOI"[^>]*>(.*?)</idno>"#)?, entry));
+
+    Ok(TeiReference { title: title_text, author: authors.join("; "), year, journal, doi: doi::normalize(&doi) })
+}
+
+/// "Forename Surname" from a `persName` inside `xml`
+fn pers_name(xml: &str) -> Result<Option<String>> {
+    let pers = first(&regex(r"(?s)<persName\b[^>]*>(.*?)</persName>")?, xml);
+    let parts: Vec<String> = regex(r"(?s)<(?:forename|surname)\b[^>]*>(.*?)</(?:forename|surname)>")?
+        .captures_iter(&pers)
+        .map(|part| plain(&part[1]))
+        .filter(|part| !part.is_empty())
+        .collect();
+    Ok((!parts.is_empty()).then(|| parts.join(" ")))
+}
+
+/// Flatten TEI XML: the abstract, then section headings and paragraphs
+pub fn tei_to_text(tei: &str) -> Result<String> {
+    let section = regex(r"(?s)<abstract\b[^>]*>(.*?)</abstract>|<body\b[^>]*>(.*?)</body>")?;
+    let block = regex(r"(?s)<(head|p)\b[^>]*>(.*?)</(?:head|p)>")?;
+
+    let mut paragraphs = Vec::new();
+    for captures in section.captures_iter(tei) {
+        let content = captures.get(1).or_else(|| captures.get(2)).map_or("", |m| m.as_str());
+        for block_captures in block.captures_iter(content) {
+            let text = plain(&block_captures[2]);
+            if !text.is_empty() {
+                paragraphs.push(text);
+            }
+        }
+    }
+    Ok(paragraphs.join("\n\n"))
+}
+
+fn regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| GscholarError::Parse(e.to_string()))
+}
+
+/// First capture group of the first match, or ""
+fn first(re: &Regex, text: &str) -> String {
+    re.captures(text).and_then(|c| c.get(1)).map(|m| m.as_str().to_string()).unwrap_or_default()
+}
+
+/// Text of an XML fragment: tags dropped, entities decoded, whitespace collapsed
+fn plain(xml: &str) -> String {
+    let mut text = String::with_capacity(xml.len());
+    let mut in_tag = false;
+    for c in xml.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEI: &str = r#"<TEI><teiHeader><fileDesc><sourceDesc><biblStruct><analytic>
+        <author role="corresp"><persName><forename type="first">Wei</forename><surname>Li</surname></persName>
+            <affiliation key="aff0"><orgName type="department">Institute of Geology</orgName><orgName type="institution">Tongji University</orgName></affiliation></author>
+        <author><persName><forename>Anna</forename><surname>Rossi</surname></persName></author>
+        <title level="a" type="main">Slope failures</title></analytic></biblStruct></sourceDesc></fileDesc>
+        <profileDesc><abstract><div><p>Rainfall triggers <hi rend="italic">shallow</hi> landslides.</p></div></abstract></profileDesc></teiHeader>
+        <text><body><div><head n="1">Introduction</head><p>Slopes &amp; soils
+        fail.</p></div></body><back><div type="references"><listBibl>
+            <biblStruct xml:id="b0"><analytic><title level="a" type="main">Debris flow runout</title>
+                <author><persName><forename>J</forename><surname>Smith</surname></persName></author>
+                <idno type="DOI">10.1016/J.GEOMORPH.2019.01.002</idno></analytic>
+                <monogr><title level="j">Geomorphology</title><imprint><date type="published" when="2019-03">2019</date></imprint></monogr></biblStruct>
+            <biblStruct xml:id="b1"><monogr><title level="m">Landslides: Analysis and Control</title>
+                <author><persName><surname>Schuster</surname></persName></author><imprint><date when="1978" /></imprint></monogr></biblStruct>
+        </listBibl></div></back></text></TEI>"#;
+
+    #[test]
+    fn test_parse_tei() -> Result<()> {
+        let doc = TeiDocument::parse(TEI)?;
+        assert_eq!(doc.text, "Rainfall triggers shallow landslides.\n\nIntroduction\n\nSlopes & soils fail.");
+        assert_eq!(
+            doc.authors,
+            vec![
+                TeiAuthor { name: "Wei Li".to_string(), affiliations: vec!["Institute of Geology, Tongji University".to_string()] },
+                TeiAuthor { name: "Anna Rossi".to_string(), affiliations: vec![] },
+            ]
+        );
+        assert_eq!(
+            doc.references,
+            vec![
+                TeiReference {
+                    title: "Debris flow runout".to_string(),
+                    author: "J Smith".to_string(),
+                    year: "2019".to_string(),
+                    journal: "Geomorphology".to_string(),
+                    doi: "10.1016/j.geomorph.2019.01.002".to_string(),
+                },
+                TeiReference {
+                    title: "Landslides: Analysis and Control".to_string(),
+                    author: "Schuster".to_string(),
+                    year: "1978".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+        Ok(())
+    }
+}
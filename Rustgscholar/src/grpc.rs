@@ -0,0 +1,305 @@
+//! gRPC API of `serve` (feature "grpc").
+//!
+//! Mirrors the job and paper routes of the REST server with the protobuf
+//! contract in `proto/gscholar.proto`, and adds `WatchJob`, which streams a
+//! job's progress lines and status changes until it finishes. API keys,
+//! tenants and quotas work as for REST (see [`crate::auth`]): the key goes
+//! in the `authorization: Bearer <key>` or `x-api-key` metadata.
+
+use crate::api::ApiError;
+use crate::auth::{ApiKey, KeyStore};
+use crate::jobs::{self, JobQueue};
+use crate::store::{self, ResultStore};
+use futures::stream::{self, Stream, StreamExt};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+/// Generated protobuf messages, server and client
+pub mod proto {
+    tonic::include_proto!("gscholar.v1");
+}
+
+use proto::gscholar_server::{Gscholar, GscholarServer};
+
+/// The gRPC service over the server's job queue and result store
+#[derive(Clone)]
+pub struct GrpcService {
+    jobs: Arc<JobQueue>,
+    results: Arc<ResultStore>,
+    keys: Arc<KeyStore>,
+}
+
+impl GrpcService {
+    pub fn new(jobs: Arc<JobQueue>, results: Arc<ResultStore>, keys: Arc<KeyStore>) -> Self {
+        Self { jobs, results, keys }
+    }
+
+    /// Serve on an already bound listener until the process stops
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> crate::error::Result<()> {
+        tonic::transport::Server::builder()
+            .add_service(GscholarServer::new(self))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .map_err(|e| crate::error::GscholarError::Config(format!("gRPC server error: {}", e)))
+    }
+
+    /// Check the caller's key and limits, returning its tenant
+    fn authorize<T>(&self, request: &Request<T>, method: &str, billable: bool) -> Result<Option<String>, ApiError> {
+        let remote = request.remote_addr().map(|a: SocketAddr| a.to_string()).unwrap_or_default();
+        if self.keys.is_empty() {
+            info!(method, remote = %remote, "gRPC request");
+            return Ok(None);
+        }
+        let headers = request.metadata().clone().into_headers();
+        let key: ApiKey = self.keys.authenticate(&headers)?.clone();
+        self.keys.check(&key, billable)?;
+        info!(method, client = %key.name, tenant = %key.tenant, remote = %remote, "gRPC request");
+        Ok(Some(key.tenant))
+    }
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::JobEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Gscholar for GrpcService {
+    async fn submit_job(&self, request: Request<proto::JobRequest>) -> Result<Response<proto::Job>, Status> {
+        let tenant = self.authorize(&request, "SubmitJob", true)?;
+        let job = self.jobs.submit(request.into_inner().into(), tenant.as_deref()).map_err(ApiError::from)?;
+        info!(id = %job.id, keyword = %job.request.keyword, tenant = ?job.tenant, "Job queued");
+        Ok(Response::new(job.into()))
+    }
+
+    async fn get_job(&self, request: Request<proto::GetJobRequest>) -> Result<Response<proto::Job>, Status> {
+        let tenant = self.authorize(&request, "GetJob", false)?;
+        let id = request.into_inner().id;
+        let job = self.jobs.get(&id, tenant.as_deref()).ok_or_else(|| Status::not_found(format!("No job {}", id)))?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn list_jobs(&self, request: Request<proto::ListJobsRequest>) -> Result<Response<proto::ListJobsResponse>, Status> {
+        let tenant = self.authorize(&request, "ListJobs", false)?;
+        let jobs = self.jobs.list(tenant.as_deref()).into_iter().map(proto::Job::from).collect();
+        Ok(Response::new(proto::ListJobsResponse { jobs }))
+    }
+
+    type WatchJobStream = EventStream;
+
+    async fn watch_job(&self, request: Request<proto::GetJobRequest>) -> Result<Response<Self::WatchJobStream>, Status> {
+        let tenant = self.authorize(&request, "WatchJob", false)?;
+        let id = request.into_inner().id;
+        // Subscribe before the snapshot so no event falls in between
+        let events = self.jobs.subscribe();
+        let job = self.jobs.get(&id, tenant.as_deref()).ok_or_else(|| Status::not_found(format!("No job {}", id)))?;
+
+        let status = proto::JobStatus::from(job.status) as i32;
+        let mut snapshot: Vec<proto::JobEvent> = job
+            .progress
+            .iter()
+            .map(|line| proto::JobEvent { id: id.clone(), status, progress: Some(line.clone()) })
+            .collect();
+        snapshot.push(proto::JobEvent { id: id.clone(), status, progress: None });
+        let snapshot = stream::iter(snapshot.into_iter().map(Ok));
+        if is_finished(job.status) {
+            return Ok(Response::new(Box::pin(snapshot)));
+        }
+
+        let events = BroadcastStream::new(events)
+            // A lagging receiver skips the events it missed
+            .filter_map(move |event| {
+                let event = event.ok().filter(|e| e.id == id && e.tenant == tenant);
+                std::future::ready(event)
+            })
+            .boxed();
+        // Ends right after the final status, without waiting for another event
+        let updates = stream::unfold(Some(events), |events| async move {
+            let mut events = events?;
+            let event = events.next().await?;
+            let finished = is_finished(event.status) && event.progress.is_none();
+            let message = proto::JobEvent {
+                id: event.id,
+                status: proto::JobStatus::from(event.status) as i32,
+                progress: event.progress,
+            };
+            Some((Ok(message), (!finished).then_some(events)))
+        });
+        Ok(Response::new(Box::pin(snapshot.chain(updates))))
+    }
+
+    async fn query_papers(&self, request: Request<proto::PaperQuery>) -> Result<Response<proto::PaperPage>, Status> {
+        let tenant = self.authorize(&request, "QueryPapers", false)?;
+        let query = request.into_inner();
+        let query = store::PaperQuery {
+            query: query.query,
+            min_if: query.min_if,
+            min_citations: query.min_citations,
+            label: query.label,
+            job: query.job,
+            ylo: query.ylo,
+            sort: query.sort,
+            page: query.page.map(|p| p as usize),
+            per_page: query.per_page.map(|p| p as usize),
+        };
+        let page = self.results.query(&query, tenant.as_deref()).map_err(ApiError::from)?;
+        Ok(Response::new(proto::PaperPage {
+            total: page.total as u64,
+            page: page.page as u32,
+            per_page: page.per_page as u32,
+            papers: page
+                .papers
+                .into_iter()
+                .map(|p| proto::Paper {
+                    job_id: p.job_id,
+                    keyword: p.keyword,
+                    label: p.label,
+                    title: p.paper.title,
+                    author: p.paper.author,
+                    date: p.paper.date,
+                    doi: p.paper.doi,
+                    journal: p.paper.journal,
+                    if_score: p.paper.if_score,
+                    abstract_text: p.paper.abstract_text,
+                    article_url: p.paper.article_url,
+                    pdf_url: p.paper.pdf_url,
+                    citations: p.paper.citations,
+                })
+                .collect(),
+        }))
+    }
+}
+
+fn is_finished(status: jobs::JobStatus) -> bool {
+    matches!(status, jobs::JobStatus::Completed | jobs::JobStatus::Failed)
+}
+
+/// gRPC status of an error envelope
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Self {
+        let message = match error.retry_after {
+            Some(secs) => format!("{} (retry after {}s)", error.message, secs),
+            None => error.message,
+        };
+        match error.code.as_str() {
+            "validation" => Status::invalid_argument(message),
+            "unauthorized" => Status::unauthenticated(message),
+            "not_found" => Status::not_found(message),
+            "rate_limited" | "quota_exceeded" => Status::resource_exhausted(message),
+            "captcha" | "upstream" => Status::unavailable(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+impl From<jobs::JobStatus> for proto::JobStatus {
+    fn from(status: jobs::JobStatus) -> Self {
+        match status {
+            jobs::JobStatus::Queued => Self::Queued,
+            jobs::JobStatus::Running => Self::Running,
+            jobs::JobStatus::Completed => Self::Completed,
+            jobs::JobStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<proto::JobRequest> for jobs::JobRequest {
+    fn from(request: proto::JobRequest) -> Self {
+        Self {
+            keyword: request.keyword,
+            sources: request.sources,
+            pages: if request.pages.is_empty() { vec![1] } else { request.pages },
+            ylo: request.ylo,
+            proxy: request.proxy,
+            min_citations: request.min_citations,
+            max_results: request.max_results.map(|n| n as usize),
+        }
+    }
+}
+
+impl From<jobs::Job> for proto::Job {
+    fn from(job: jobs::Job) -> Self {
+        Self {
+            status: proto::JobStatus::from(job.status) as i32,
+            request: Some(proto::JobRequest {
+                keyword: job.request.keyword,
+                sources: job.request.sources,
+                pages: job.request.pages,
+                ylo: job.request.ylo,
+                proxy: job.request.proxy,
+                min_citations: job.request.min_citations,
+                max_results: job.request.max_results.map(|n| n as u64),
+            }),
+            id: job.id,
+            tenant: job.tenant,
+            output_folder: job.output_folder.display().to_string(),
+            created_at: job.created_at.to_rfc3339(),
+            started_at: job.started_at.map(|t| t.to_rfc3339()),
+            finished_at: job.finished_at.map(|t| t.to_rfc3339()),
+            progress: job.progress,
+            result: job.result.map(|r| proto::JobResult {
+                unified: r.unified as u64,
+                relevant: r.relevant as u64,
+                final_table: r.final_table.map(|p| p.display().to_string()),
+                failures: r.failures as u64,
+            }),
+            error: job.error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{GscholarError, Result};
+    use proto::gscholar_client::GscholarClient;
+
+    #[tokio::test]
+    async fn test_submit_and_watch_job() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let runner: jobs::JobRunner = Arc::new(|_request: jobs::JobRequest, context: jobs::JobContext| {
+            Box::pin(async move {
+                (context.progress)("Stage 1: search");
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                (context.progress)("Stage 5: unified");
+                Ok(jobs::JobResult { unified: 3, ..Default::default() })
+            })
+        });
+        let queue = JobQueue::start(dir.path(), 1, runner)?;
+        let results = Arc::new(ResultStore::open(dir.path())?);
+        let keys = Arc::new(KeyStore::new(KeyStore::parse_list(
+            "lab-a:secret-a",
+            crate::auth::Limits { rate_per_minute: 0, daily_quota: 0 },
+        )?)?);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(GrpcService::new(queue, results, keys).serve(listener));
+
+        let network = |e: tonic::transport::Error| GscholarError::Config(format!("gRPC connect failed: {}", e));
+        let rpc = |e: Status| GscholarError::Api { code: e.code() as i32, message: e.message().to_string() };
+        let mut client = GscholarClient::connect(format!("http://{}", addr)).await.map_err(network)?;
+
+        let request = proto::JobRequest { keyword: "landslide".to_string(), ..Default::default() };
+        let denied = client.submit_job(request.clone()).await.err().map(|e| e.code());
+        assert_eq!(denied, Some(tonic::Code::Unauthenticated));
+
+        let mut authed = Request::new(request);
+        authed.metadata_mut().insert("x-api-key", "secret-a".parse().map_err(|_| GscholarError::Parse("key".to_string()))?);
+        let job = client.submit_job(authed).await.map_err(rpc)?.into_inner();
+
+        let mut watch = Request::new(proto::GetJobRequest { id: job.id.clone() });
+        watch.metadata_mut().insert("authorization", "Bearer secret-a".parse().map_err(|_| GscholarError::Parse("key".to_string()))?);
+        let mut events = client.watch_job(watch).await.map_err(rpc)?.into_inner();
+        let mut progress = Vec::new();
+        let mut last = None;
+        while let Some(event) = events.message().await.map_err(rpc)? {
+            progress.extend(event.progress.clone());
+            last = Some(event.status);
+        }
+        assert!(progress.ends_with(&["Stage 1: search".to_string(), "Stage 5: unified".to_string()]));
+        assert_eq!(last, Some(proto::JobStatus::Completed as i32));
+        Ok(())
+    }
+}
@@ -4,16 +4,24 @@
 //! using Playwright for browser automation with anti-detection features.
 
 use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::ratelimit::{self, Rate};
+use crate::raw_archive::{self, BodyKind};
+use crate::stream::{self, Page, PageSender};
+use crate::throttle::{self, Upstream};
 use regex::Regex;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::path::PathBuf;
 use tracing::{debug, error, info, warn};
 use url::Url;
 
 /// Default Google Scholar URL
 pub const DEFAULT_SCHOLAR_URL: &str = "https://scholar.google.com";
 
+/// Results per Google Scholar page
+pub const RESULTS_PER_PAGE: usize = 10;
+
 /// User agent string for requests
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
 
@@ -51,6 +59,10 @@ pub struct QueryOptions {
     pub base_url: Option<String>,
     /// Whether to return all results or just first per page
     pub all_results: bool,
+    /// Cookie file (default `~/.gscholar_cookies.json`)
+    pub cookie_path: Option<PathBuf>,
+    /// Review articles only (`as_rr=1`)
+    pub reviews_only: bool,
 }
 
 impl Default for QueryOptions {
@@ -62,6 +74,8 @@ impl Default for QueryOptions {
             ylo: None,
             base_url: None,
             all_results: true,
+            cookie_path: None,
+            reviews_only: false,
         }
     }
 }
@@ -81,11 +95,18 @@ impl Default for QueryOptions {
 ///
 /// Returns error if browser fails to launch or network error occurs
 pub async fn query(search_str: &str, options: &QueryOptions) -> Result<Vec<ScholarResult>> {
-    let scholar_url = options
-        .base_url
-        .as_ref()
-        .map(|s| s.trim_end_matches('/').to_string())
-        .unwrap_or_else(|| DEFAULT_SCHOLAR_URL.to_string());
+    let (tx, rx) = stream::channel();
+    let all_results = stream::collect(query_pages(search_str, options, tx), rx).await?;
+    info!(total = all_results.len(), "Query complete");
+    Ok(all_results)
+}
+
+/// Query Google Scholar, sending each page down `tx` as soon as it is parsed.
+///
+/// A CAPTCHA stops the query with an error after the pages already sent;
+/// the query also stops early when the receiver is dropped.
+pub async fn query_pages(search_str: &str, options: &QueryOptions, tx: PageSender<ScholarResult>) -> Result<()> {
+    let scholar_url = scholar_url(options);
 
     info!(
         query = search_str,
@@ -94,11 +115,8 @@ pub async fn query(search_str: &str, options: &QueryOptions) -> Result<Vec<Schol
         "Starting Google Scholar query"
     );
 
-    let mut all_results = Vec::new();
-
     // Load cookies from cookie manager
-    let cookie_manager = crate::cookies::CookieManager::default();
-    let cookies = cookie_manager.load();
+    let cookies = cookie_manager(options).load();
     let cookie_header = build_cookie_header(&cookies);
     
     if cookies.is_empty() {
@@ -111,19 +129,15 @@ pub async fn query(search_str: &str, options: &QueryOptions) -> Result<Vec<Schol
     let client = build_http_client(options.proxy.as_deref())?;
 
     for page_num in &options.pages {
-        let start = (page_num - 1) * 10;
-        let url = build_search_url(&scholar_url, search_str, start, &options.sdt, options.ylo)?;
+        let start = (page_num - 1) * RESULTS_PER_PAGE as i32;
+        let url = build_search_url(&scholar_url, search_str, start, &options.sdt, options.ylo, options.reviews_only)?;
 
         debug!(page = page_num, url = %url, "Fetching page");
 
-        // Add random delay to avoid detection
-        let delay = rand::random::<u64>() % 1500 + 500;
-        tokio::time::sleep(Duration::from_millis(delay)).await;
-
         match fetch_page_with_cookies(&client, &url, &cookie_header).await {
             Ok(html) => {
                 // Check for CAPTCHA
-                if html.contains("Solving the above CAPTCHA") || html.contains("unusual traffic") {
+                if is_captcha_page(&html) {
                     warn!(page = page_num, "CAPTCHA detected");
                     return Err(GscholarError::Captcha);
                 }
@@ -141,21 +155,88 @@ pub async fn query(search_str: &str, options: &QueryOptions) -> Result<Vec<Schol
                     }
                 }
 
-                if options.all_results {
-                    all_results.extend(page_results);
-                } else if let Some(first) = page_results.into_iter().next() {
-                    all_results.push(first);
+                let items = if options.all_results {
+                    page_results
+                } else {
+                    page_results.into_iter().take(1).collect()
+                };
+                if tx.send(Page::new(*page_num, items)).await.is_err() {
+                    break;
                 }
             }
             Err(e) => {
                 error!(page = page_num, error = %e, "Failed to fetch page");
                 // Continue with other pages instead of failing completely
+                if tx.send(Page::failed(*page_num, e)).await.is_err() {
+                    break;
+                }
             }
         }
     }
+    Ok(())
+}
 
-    info!(total = all_results.len(), "Query complete");
-    Ok(all_results)
+/// Number of results Google Scholar reports for a query ("About 1,230 results").
+///
+/// Fetches only the first page. Returns `None` when the page shows no count.
+pub async fn count(search_str: &str, options: &QueryOptions) -> Result<Option<u64>> {
+    let url = build_search_url(&scholar_url(options), search_str, 0, &options.sdt, options.ylo, options.reviews_only)?;
+    let cookie_header = build_cookie_header(&cookie_manager(options).load());
+    let client = build_http_client(options.proxy.as_deref())?;
+
+    let html = fetch_page_with_cookies(&client, &url, &cookie_header).await?;
+    if is_captcha_page(&html) {
+        return Err(GscholarError::Captcha);
+    }
+    let count = parse_result_count(&html);
+    debug!(query = search_str, count = ?count, "Google Scholar hit count");
+    Ok(count)
+}
+
+/// Whether Google answered with its CAPTCHA / unusual-traffic page
+pub fn is_captcha_page(html: &str) -> bool {
+    html.contains("Solving the above CAPTCHA") || html.contains("unusual traffic") || html.contains("gs_captcha_f")
+}
+
+/// Parse the result count of a Google Scholar page (English or Chinese UI)
+pub fn parse_result_count(html: &str) -> Option<u64> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("#gs_ab_md div.gs_ab_mdw, div.gs_ab_mdw").ok()?;
+    let count_regex = Regex::new(r"([\d][\d,.\s]*)\s*(?:results?|条结果)").ok()?;
+    document.select(&selector).find_map(|element| {
+        let text = element.text().collect::<String>();
+        let digits: String = count_regex.captures(&text)?.get(1)?.as_str().chars().filter(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    })
+}
+
+/// URL of one result page (1-indexed), as [`query`] requests it
+pub fn search_url(search_str: &str, page: i32, options: &QueryOptions) -> Result<Url> {
+    build_search_url(
+        &scholar_url(options),
+        search_str,
+        (page - 1) * RESULTS_PER_PAGE as i32,
+        &options.sdt,
+        options.ylo,
+        options.reviews_only,
+    )
+}
+
+/// Base URL of Google Scholar or the configured mirror
+fn scholar_url(options: &QueryOptions) -> String {
+    options
+        .base_url
+        .as_ref()
+        .map(|s| s.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| DEFAULT_SCHOLAR_URL.to_string())
+}
+
+/// Cookie jar of a query
+fn cookie_manager(options: &QueryOptions) -> crate::cookies::CookieManager {
+    match &options.cookie_path {
+        Some(path) => crate::cookies::CookieManager::with_path(path.clone()),
+        None => crate::cookies::CookieManager::default(),
+    }
 }
 
 /// Build cookie header string from cookie list
@@ -168,21 +249,16 @@ fn build_cookie_header(cookies: &[crate::cookies::Cookie]) -> String {
         .join("; ")
 }
 
-/// Build HTTP client with optional proxy
+/// Build HTTP client with its own cookie jar, through `proxy` or the
+/// Scholar proxy of the shared settings
 fn build_http_client(proxy: Option<&str>) -> Result<reqwest::Client> {
-    let mut builder = reqwest::Client::builder()
+    let shared = http::settings().for_upstream(Upstream::Scholar);
+    let timeout = http::retry_policy(Upstream::Scholar).timeout.unwrap_or(shared.timeout);
+    let settings = http::HttpSettings { proxy: proxy.map(String::from).or(shared.proxy.clone()), ..shared };
+    http::builder(&settings)?
         .user_agent(USER_AGENT)
-        .timeout(Duration::from_secs(30))
-        .cookie_store(true);
-
-    if let Some(proxy_url) = proxy {
-        let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| {
-            GscholarError::Config(format!("Invalid proxy URL '{}': {}", proxy_url, e))
-        })?;
-        builder = builder.proxy(proxy);
-    }
-
-    builder
+        .timeout(timeout)
+        .cookie_store(true)
         .build()
         .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))
 }
@@ -194,6 +270,7 @@ fn build_search_url(
     start: i32,
     sdt: &str,
     ylo: Option<i32>,
+    reviews_only: bool,
 ) -> Result<Url> {
     let mut url = Url::parse(&format!("{}/scholar", base_url))
         .map_err(|e| GscholarError::Config(format!("Invalid base URL: {}", e)))?;
@@ -207,16 +284,14 @@ fn build_search_url(
         if let Some(year) = ylo {
             params.append_pair("as_ylo", &year.to_string());
         }
+        if reviews_only {
+            params.append_pair("as_rr", "1");
+        }
     }
 
     Ok(url)
 }
 
-/// Fetch page content using HTTP client
-async fn fetch_page(client: &reqwest::Client, url: &Url) -> Result<String> {
-    fetch_page_with_cookies(client, url, "").await
-}
-
 /// Fetch page content using HTTP client with cookies
 async fn fetch_page_with_cookies(client: &reqwest::Client, url: &Url, cookie_header: &str) -> Result<String> {
     let mut request = client
@@ -236,9 +311,16 @@ async fn fetch_page_with_cookies(client: &reqwest::Client, url: &Url, cookie_hea
         request = request.header("Cookie", cookie_header);
     }
 
+    // Paced like a person paging through results, mirrors included
+    let bucket = url.host_str().map(|host| ratelimit::bucket_with(host, Rate::SCHOLAR));
+    if let Some(bucket) = &bucket {
+        bucket.acquire().await;
+    }
+    let _slot = throttle::acquire(Upstream::Scholar).await;
     let response = request.send().await?;
 
     let status = response.status();
+    ratelimit::record(url, status, None);
     if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
         return Err(GscholarError::RateLimited(60));
     }
@@ -250,10 +332,9 @@ async fn fetch_page_with_cookies(client: &reqwest::Client, url: &Url, cookie_hea
         });
     }
 
-    response
-        .text()
-        .await
-        .map_err(|e| GscholarError::Network(e))
+    let html = response.text().await.map_err(GscholarError::Network)?;
+    raw_archive::record(url.as_str(), BodyKind::Html, &html);
+    Ok(html)
 }
 
 /// Parse Google Scholar HTML to extract article information.
@@ -284,7 +365,7 @@ pub fn parse_result_items(html: &str) -> Result<Vec<ScholarResult>> {
 
     let year_regex = Regex::new(r"\b(19|20)\d{2}\b").map_err(|e| GscholarError::Parse(e.to_string()))?;
     // Support both English ("Cited by X") and Chinese ("被引用 X 次") formats
-    let cite_regex = Regex::new(r"(?:Cited by\s*|被引用\s*)(\d+)").map_err(|e| GscholarError::Parse(e.to_string()))?;
+    let cite_regex = Regex::new(r"(?:Cited by\s*|被引用\s*)(\d[\d,]*)").map_err(|e| GscholarError::Parse(e.to_string()))?;
 
     let mut results = Vec::new();
 
@@ -304,25 +385,7 @@ pub fn parse_result_items(html: &str) -> Result<Vec<ScholarResult>> {
 
         // Extract author, year, venue from metadata
         if let Some(meta_elem) = item.select(&meta_selector).next() {
-            let meta_text = meta_elem.text().collect::<String>();
-            let parts: Vec<&str> = meta_text.split(" - ").collect();
-
-            if !parts.is_empty() {
-                data.author = parts[0].trim().to_string();
-            }
-
-            if parts.len() >= 2 {
-                let venue_year = parts[1];
-                if let Some(caps) = year_regex.captures(venue_year) {
-                    if let Some(year_match) = caps.get(0) {
-                        data.year = year_match.as_str().to_string();
-                        let venue = venue_year[..year_match.start()].trim().trim_end_matches(',');
-                        data.venue = venue.to_string();
-                    }
-                } else {
-                    data.venue = venue_year.trim().to_string();
-                }
-            }
+            apply_meta_line(&mut data, &meta_elem.text().collect::<String>(), &year_regex);
         }
 
         // Extract snippet
@@ -354,6 +417,29 @@ pub fn parse_result_items(html: &str) -> Result<Vec<ScholarResult>> {
     Ok(results)
 }
 
+/// Fill author, venue and year from a result's author line
+/// ("W Li, A Rossi - Geomorphology, 2021 - Elsevier")
+pub(crate) fn apply_meta_line(data: &mut ScholarResult, meta_text: &str, year_regex: &Regex) {
+    let parts: Vec<&str> = meta_text.split(" - ").collect();
+
+    if !parts.is_empty() {
+        data.author = parts[0].trim().to_string();
+    }
+
+    if parts.len() >= 2 {
+        let venue_year = parts[1];
+        if let Some(caps) = year_regex.captures(venue_year) {
+            if let Some(year_match) = caps.get(0) {
+                data.year = year_match.as_str().to_string();
+                let venue = venue_year[..year_match.start()].trim().trim_end_matches(',');
+                data.venue = venue.to_string();
+            }
+        } else {
+            data.venue = venue_year.trim().to_string();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -361,10 +447,23 @@ mod tests {
     #[test]
     fn test_build_search_url() {
         let url =
-            build_search_url("https://scholar.google.com", "machine learning", 0, "0,5", Some(2020))
+            build_search_url("https://scholar.google.com", "machine learning", 0, "0,5", Some(2020), false)
                 .expect("Failed to build URL");
         assert!(url.as_str().contains("q=machine+learning"));
         assert!(url.as_str().contains("as_ylo=2020"));
+        assert!(!url.as_str().contains("as_rr"));
+        let reviews = build_search_url("https://scholar.google.com", "machine learning", 0, "0,5", None, true)
+            .expect("Failed to build URL");
+        assert!(reviews.as_str().contains("as_rr=1"));
+    }
+
+    #[test]
+    fn test_parse_result_count() {
+        let html = |text: &str| format!(r#"<div id="gs_ab_md"><div class="gs_ab_mdw">{}</div></div>"#, text);
+        assert_eq!(parse_result_count(&html("About 17,900 results (<b>0.05</b> sec)")), Some(17_900));
+        assert_eq!(parse_result_count(&html("1 result (<b>0.01</b> sec)")), Some(1));
+        assert_eq!(parse_result_count(&html("获得约 1,230 条结果（用时0.04秒）")), Some(1_230));
+        assert_eq!(parse_result_count("<html><body></body></html>"), None);
     }
 
     #[test]
@@ -0,0 +1,255 @@
+//! Readiness probes of the upstream services for `serve`.
+//!
+//! `/health` only says the process is up; `/health/ready` also sends one
+//! cheap request to every upstream the pipeline depends on (Crossref,
+//! OpenAlex, Semantic Scholar, EasyScholar, the LLM endpoint) and reports
+//! each one's status and latency. A dependency is up when it answers with
+//! anything but a server error; the server is ready when all probed
+//! dependencies are up. Results are reused for a few seconds so frequent
+//! orchestrator probes do not hammer the upstream APIs.
+
+use crate::http;
+use crate::throttle::Upstream;
+use crate::llm_filter::LlmProvider;
+use crate::{crossref, openalex, rankings, semanticscholar};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Dependencies probed when none are selected
+pub const DEFAULT_PROBES: &[&str] = &["crossref", "openalex", "s2", "easyscholar", "llm"];
+
+/// Timeout of a single probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a readiness report is reused
+const REPORT_TTL: Duration = Duration::from_secs(15);
+
+/// State of one dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    Up,
+    Down,
+    /// Not configured on this server (no key or base URL)
+    Skipped,
+}
+
+/// Probe result of one dependency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyHealth {
+    pub name: String,
+    pub status: ProbeStatus,
+    /// Round-trip time of the probe
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// HTTP status of the answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Body of `/health/ready`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+/// LLM endpoint to probe
+#[derive(Debug, Clone)]
+pub struct LlmEndpoint {
+    pub provider: LlmProvider,
+    pub base_url: String,
+    pub api_key: Option<String>,
+}
+
+/// One request per dependency
+struct Probe {
+    name: &'static str,
+    /// `None` when the dependency is not configured
+    request: Option<reqwest::RequestBuilder>,
+}
+
+/// Upstream dependencies of the server and their last readiness report
+pub struct HealthChecker {
+    probes: Vec<String>,
+    s2_api_key: Option<String>,
+    easyscholar_key: Option<String>,
+    llm: Option<LlmEndpoint>,
+    last: Mutex<Option<(Instant, Readiness)>>,
+}
+
+impl HealthChecker {
+    /// Probe the given dependencies (names of [`DEFAULT_PROBES`])
+    pub fn new(probes: &[String]) -> crate::error::Result<Self> {
+        if let Some(unknown) = probes.iter().find(|p| !DEFAULT_PROBES.contains(&p.as_str())) {
+            return Err(crate::error::GscholarError::Config(format!(
+                "Unknown readiness probe '{}' (expected one of {})",
+                unknown,
+                DEFAULT_PROBES.join(", ")
+            )));
+        }
+        Ok(Self {
+            probes: probes.to_vec(),
+            s2_api_key: None,
+            easyscholar_key: None,
+            llm: None,
+            last: Mutex::new(None),
+        })
+    }
+
+    /// Semantic Scholar API key sent with its probe
+    pub fn s2_api_key(mut self, key: Option<String>) -> Self {
+        self.s2_api_key = key.filter(|k| !k.trim().is_empty());
+        self
+    }
+
+    /// EasyScholar is only probed when a key is configured
+    pub fn easyscholar_key(mut self, key: Option<String>) -> Self {
+        self.easyscholar_key = key.filter(|k| !k.trim().is_empty());
+        self
+    }
+
+    /// LLM endpoint, probed through its model listing
+    pub fn llm(mut self, llm: Option<LlmEndpoint>) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    /// Probe every dependency, or reuse a report younger than a few seconds
+    pub async fn readiness(&self) -> Readiness {
+        if let Ok(last) = self.last.lock() {
+            if let Some((_, report)) = last.as_ref().filter(|(at, _)| at.elapsed() < REPORT_TTL) {
+                return report.clone();
+            }
+        }
+        let dependencies = join_all(self.probes().into_iter().map(run_probe)).await;
+        let report = Readiness {
+            ready: dependencies.iter().all(|d| d.status != ProbeStatus::Down),
+            checked_at: chrono::Utc::now(),
+            dependencies,
+        };
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((Instant::now(), report.clone()));
+        }
+        report
+    }
+
+    fn probes(&self) -> Vec<Probe> {
+        self.probes
+            .iter()
+            .filter_map(|name| {
+                let name = *DEFAULT_PROBES.iter().find(|p| *p == name)?;
+                // Through the upstream's own proxy, as a run would
+                let client = http::client_for(name.parse::<Upstream>().ok()?);
+                let request = match name {
+                    "crossref" => Some(client.get(crossref::CROSSREF_API_URL).query(&[("rows", "0")])),
+                    "openalex" => Some(
+                        client
+                            .get(format!("{}/works", openalex::OPENALEX_API_BASE))
+                            .query(&[("per-page", "1"), ("select", "id")]),
+                    ),
+                    "s2" => {
+                        let request = client
+                            .get(format!("{}/paper/search", semanticscholar::SS_API_BASE))
+                            .query(&[("query", "health"), ("limit", "1"), ("fields", "title")]);
+                        Some(match &self.s2_api_key {
+                            Some(key) => request.header("x-api-key", key),
+                            None => request,
+                        })
+                    }
+                    // Only checks the server answers: a lookup would use up the daily quota
+                    "easyscholar" => self.easyscholar_key.as_ref().map(|_| client.get(rankings::EASYSCHOLAR_API_URL)),
+                    "llm" => self.llm.as_ref().map(|llm| {
+                        let request = client.get(llm.provider.models_endpoint(&llm.base_url));
+                        match &llm.api_key {
+                            Some(key) => request.bearer_auth(key),
+                            None => request,
+                        }
+                    }),
+                    _ => None,
+                };
+                Some(Probe { name, request: request.map(|r| r.timeout(PROBE_TIMEOUT)) })
+            })
+            .collect()
+    }
+}
+
+async fn run_probe(probe: Probe) -> DependencyHealth {
+    let mut health = DependencyHealth {
+        name: probe.name.to_string(),
+        status: ProbeStatus::Skipped,
+        latency_ms: None,
+        http_status: None,
+        error: None,
+    };
+    let Some(request) = probe.request else {
+        return health;
+    };
+    let started = Instant::now();
+    let result = request.send().await;
+    health.latency_ms = Some(started.elapsed().as_millis() as u64);
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            health.http_status = Some(status.as_u16());
+            health.status = probe_status(status.as_u16());
+            if health.status == ProbeStatus::Down {
+                health.error = Some(format!("Server error: {}", status));
+            }
+        }
+        Err(e) => {
+            warn!(dependency = probe.name, error = %e, "Readiness probe failed");
+            health.status = ProbeStatus::Down;
+            health.error = Some(if e.is_timeout() {
+                "Timed out".to_string()
+            } else if e.is_connect() {
+                "Connection failed".to_string()
+            } else {
+                e.without_url().to_string()
+            });
+        }
+    }
+    health
+}
+
+/// Any answer but a server error means the dependency is reachable; auth
+/// errors and rate limits are the caller's problem, not the service's
+fn probe_status(http_status: u16) -> ProbeStatus {
+    if http_status >= 500 {
+        ProbeStatus::Down
+    } else {
+        ProbeStatus::Up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_readiness() -> crate::error::Result<()> {
+        assert!(HealthChecker::new(&["crossref".to_string(), "scopus".to_string()]).is_err());
+
+        // Nothing listens on port 9: the LLM is down, unconfigured EasyScholar is skipped
+        let checker = HealthChecker::new(&["easyscholar".to_string(), "llm".to_string()])?.llm(Some(LlmEndpoint {
+            provider: LlmProvider::Ollama,
+            base_url: "http://127.0.0.1:9".to_string(),
+            api_key: None,
+        }));
+        let report = checker.readiness().await;
+        assert!(!report.ready);
+        let statuses: Vec<_> = report.dependencies.iter().map(|d| (d.name.as_str(), d.status)).collect();
+        assert_eq!(statuses, vec![("easyscholar", ProbeStatus::Skipped), ("llm", ProbeStatus::Down)]);
+        assert!(report.dependencies[1].latency_ms.is_some());
+
+        assert_eq!(probe_status(429), ProbeStatus::Up);
+        assert_eq!(probe_status(503), ProbeStatus::Down);
+        Ok(())
+    }
+}
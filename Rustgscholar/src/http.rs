@@ -0,0 +1,424 @@
+//! Shared HTTP client.
+//!
+//! API clients used to build a `reqwest::Client` each, so every stage
+//! opened its own connections and only Google Scholar honoured `--proxy`.
+//! [`client`] now hands every API client the same process-wide client,
+//! built once from the [`HttpSettings`] installed by [`configure`] (the
+//! defaults until then): one connection pool, one proxy, common timeouts
+//! and HTTP/2 when the server offers it. Clients that need their own
+//! cookie jar or a browser user agent (Scholar, PDF and landing page
+//! fetches) start from [`builder`] so the same settings apply to them.
+//! [`send`] retries transient failures of a request following a
+//! [`RetryPolicy`]: the shared one, or that of an upstream with
+//! [`send_to`], which may be overridden per upstream. It paces requests
+//! by host through [`crate::ratelimit`], or by the bucket handed to
+//! [`send_paced`].
+//!
+//! An upstream can be given its own proxy (or none) with a proxy override;
+//! [`client_for`] then hands out a separate client for that upstream, built
+//! once with the same settings otherwise.
+
+use crate::error::{GscholarError, Result};
+use crate::ratelimit::{self, TokenBucket};
+use crate::throttle::Upstream;
+use reqwest::{Client, ClientBuilder, Request, RequestBuilder, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// User agent of the shared client
+pub const USER_AGENT: &str = concat!("rustgscholar/", env!("CARGO_PKG_VERSION"), " (+https://github.com/c76d3656e/gscholar)");
+
+/// Proxy value that connects directly, ignoring the environment variables
+pub const DIRECT: &str = "direct";
+
+/// Longest wait before a retry, even when the server asks for more
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Connection settings of the shared client
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpSettings {
+    /// Proxy for every request (`http://`, `https://` or [`DIRECT`]); the
+    /// `HTTPS_PROXY`/`HTTP_PROXY` environment variables apply otherwise
+    pub proxy: Option<String>,
+    /// Proxy of single upstreams instead of `proxy`
+    pub proxy_overrides: HashMap<Upstream, String>,
+    /// Whole-request timeout unless a request sets its own
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Idle connections kept open per host
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Duration,
+    /// Negotiate HTTP/2 with servers that support it (HTTP/1.1 only when off)
+    pub http2: bool,
+    /// Retries of [`send`] for connection errors, timeouts, 429 and 502-504,
+    /// and the base of every upstream's policy
+    pub retry: RetryPolicy,
+    /// Retry policy of single upstreams instead of their default
+    pub retry_overrides: HashMap<Upstream, RetryPolicy>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            proxy_overrides: HashMap::new(),
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            pool_max_idle_per_host: 16,
+            pool_idle_timeout: Duration::from_secs(90),
+            http2: true,
+            retry: RetryPolicy::default(),
+            retry_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Retries, backoff and timeout of requests to one upstream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub base_backoff: Duration,
+    /// Share of each delay (0-1) randomly added or taken off, so clients
+    /// that failed together do not retry together
+    pub jitter: f64,
+    /// Whole-request timeout; None keeps the client's
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 3, base_backoff: Duration::from_secs(1), jitter: 0.2, timeout: None }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `attempt` (from 0); a server's `Retry-After`
+    /// is honoured as is
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| {
+            let backoff = self.base_backoff.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_DELAY);
+            let jitter = self.jitter.clamp(0.0, 1.0) * (2.0 * rand::random::<f64>() - 1.0);
+            backoff.mul_f64(1.0 + jitter)
+        });
+        delay.min(MAX_RETRY_DELAY)
+    }
+
+    /// Set the policy's timeout on a request
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+}
+
+static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static SERVICE_CLIENTS: OnceLock<Mutex<HashMap<Upstream, Client>>> = OnceLock::new();
+
+/// Install the settings of the shared client
+///
+/// Only the first call takes effect, and only before the client is first
+/// used; returns whether this one did.
+pub fn configure(settings: HttpSettings) -> Result<bool> {
+    // Fail on a bad proxy URL here rather than on the first request
+    let _ = builder(&settings)?;
+    for upstream in settings.proxy_overrides.keys() {
+        let _ = builder(&settings.for_upstream(*upstream))?;
+    }
+    let mut installed = false;
+    SETTINGS.get_or_init(|| {
+        installed = true;
+        info!(proxy = settings.proxy.is_some(), timeout_secs = settings.timeout.as_secs(), http2 = settings.http2, "HTTP settings");
+        settings
+    });
+    Ok(installed && CLIENT.get().is_none())
+}
+
+/// Settings in effect
+pub fn settings() -> &'static HttpSettings {
+    SETTINGS.get_or_init(HttpSettings::default)
+}
+
+/// Retry policy in effect for `upstream`
+pub fn retry_policy(upstream: Upstream) -> RetryPolicy {
+    settings().retry_policy(upstream)
+}
+
+/// Client builder with the given settings applied
+pub fn builder(settings: &HttpSettings) -> Result<ClientBuilder> {
+    let mut builder = Client::builder()
+        .timeout(settings.timeout)
+        .connect_timeout(settings.connect_timeout)
+        .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+        .pool_idle_timeout(settings.pool_idle_timeout);
+    if !settings.http2 {
+        builder = builder.http1_only();
+    }
+    match settings.proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(DIRECT) => builder = builder.no_proxy(),
+        Some(proxy_url) => {
+            let proxy = reqwest::Proxy::all(proxy_url.trim())
+                .map_err(|e| GscholarError::Config(format!("Invalid proxy URL '{}': {}", proxy_url, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        None => {}
+    }
+    Ok(builder)
+}
+
+impl HttpSettings {
+    /// Settings of the client for `upstream` (its proxy override applied)
+    pub fn for_upstream(&self, upstream: Upstream) -> HttpSettings {
+        let proxy = self.proxy_overrides.get(&upstream).cloned().or_else(|| self.proxy.clone());
+        HttpSettings { proxy, proxy_overrides: HashMap::new(), ..self.clone() }
+    }
+
+    /// Retry policy of `upstream`: its override, or the shared policy with
+    /// the upstream's own defaults
+    pub fn retry_policy(&self, upstream: Upstream) -> RetryPolicy {
+        if let Some(policy) = self.retry_overrides.get(&upstream) {
+            return *policy;
+        }
+        match upstream {
+            // Crossref asks to back off briefly rather than for long
+            Upstream::Crossref => RetryPolicy { base_backoff: Duration::from_millis(500), ..self.retry },
+            // Batch lookups of 500 DOIs take longer than single requests
+            Upstream::SemanticScholar => RetryPolicy { timeout: Some(Duration::from_secs(60)), ..self.retry },
+            _ => self.retry,
+        }
+    }
+
+    /// Parse a retry policy override
+    /// `NAME=RETRIES[:BACKOFF_MS[:JITTER[:TIMEOUT_SECS]]]`, e.g. `s2=5:2000`;
+    /// omitted fields keep the upstream's policy
+    pub fn parse_retry_override(&self, value: &str) -> Result<(Upstream, RetryPolicy)> {
+        let invalid = || {
+            GscholarError::Config(format!(
+                "Invalid retry policy '{}' (expected NAME=RETRIES[:BACKOFF_MS[:JITTER[:TIMEOUT_SECS]]])",
+                value
+            ))
+        };
+        let (name, fields) = value.split_once('=').ok_or_else(invalid)?;
+        let upstream: Upstream = name.trim().parse()?;
+        let mut policy = self.retry_policy(upstream);
+        let mut fields = fields.split(':').map(str::trim);
+        policy.max_retries = fields.next().and_then(|f| f.parse().ok()).ok_or_else(invalid)?;
+        if let Some(ms) = fields.next() {
+            policy.base_backoff = Duration::from_millis(ms.parse().map_err(|_| invalid())?);
+        }
+        if let Some(jitter) = fields.next() {
+            policy.jitter = jitter.parse().ok().filter(|j| (0.0..=1.0).contains(j)).ok_or_else(invalid)?;
+        }
+        if let Some(secs) = fields.next() {
+            let secs: u64 = secs.parse().map_err(|_| invalid())?;
+            policy.timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+        Ok((upstream, policy))
+    }
+}
+
+/// Parse a proxy override `NAME=URL` or `NAME=direct`, e.g. `llm=direct`
+pub fn parse_proxy_override(value: &str) -> Result<(Upstream, String)> {
+    let (name, proxy) = value.split_once('=').ok_or_else(|| {
+        GscholarError::Config(format!("Invalid proxy override '{}' (expected NAME=URL or NAME={})", value, DIRECT))
+    })?;
+    Ok((name.trim().parse()?, proxy.trim().to_string()))
+}
+
+/// The shared client (cloning it shares the connection pool)
+pub fn client() -> Client {
+    CLIENT
+        .get_or_init(|| {
+            let built = builder(settings()).and_then(|b| {
+                b.user_agent(USER_AGENT)
+                    .build()
+                    .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))
+            });
+            built.unwrap_or_else(|e| {
+                // configure() rejects bad settings, so this is a TLS backend failure
+                warn!(error = %e, "Falling back to a default HTTP client");
+                Client::new()
+            })
+        })
+        .clone()
+}
+
+/// Client for requests to `upstream`: the shared client unless the
+/// upstream has a proxy override
+pub fn client_for(upstream: Upstream) -> Client {
+    if !settings().proxy_overrides.contains_key(&upstream) {
+        return client();
+    }
+    let clients = SERVICE_CLIENTS.get_or_init(Default::default);
+    let mut clients = clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    clients
+        .entry(upstream)
+        .or_insert_with(|| {
+            let built = builder(&settings().for_upstream(upstream)).and_then(|b| {
+                b.user_agent(USER_AGENT)
+                    .build()
+                    .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))
+            });
+            built.unwrap_or_else(|e| {
+                warn!(upstream = upstream.name(), error = %e, "Falling back to the shared HTTP client");
+                client()
+            })
+        })
+        .clone()
+}
+
+/// Send a request, retrying connection errors, timeouts, 429 and 502-504
+/// with exponential backoff (or the server's `Retry-After`)
+///
+/// The last response is returned whatever its status, so callers keep
+/// handling errors themselves. Requests with a streaming body are sent
+/// once.
+pub async fn send(client: &Client, request: Request) -> reqwest::Result<Response> {
+    send_with(client, request, &settings().retry, None).await
+}
+
+/// [`send`] with the retry policy (and timeout) of `upstream`; a timeout
+/// set on the request itself is kept
+pub async fn send_to(upstream: Upstream, client: &Client, mut request: Request) -> reqwest::Result<Response> {
+    let policy = retry_policy(upstream);
+    if let (None, Some(timeout)) = (request.timeout(), policy.timeout) {
+        *request.timeout_mut() = Some(timeout);
+    }
+    send_with(client, request, &policy, None).await
+}
+
+/// [`send_to`] paced by `bucket` instead of the host's shared bucket (e.g.
+/// an API key's [`ratelimit::key_bucket`])
+pub async fn send_paced(upstream: Upstream, client: &Client, mut request: Request, bucket: Arc<TokenBucket>) -> reqwest::Result<Response> {
+    let policy = retry_policy(upstream);
+    if let (None, Some(timeout)) = (request.timeout(), policy.timeout) {
+        *request.timeout_mut() = Some(timeout);
+    }
+    send_with(client, request, &policy, Some(bucket)).await
+}
+
+async fn send_with(client: &Client, request: Request, policy: &RetryPolicy, bucket: Option<Arc<TokenBucket>>) -> reqwest::Result<Response> {
+    let bucket = bucket.or_else(|| request.url().host_str().map(ratelimit::bucket));
+    let acquire = || async {
+        if let Some(bucket) = &bucket {
+            bucket.acquire().await;
+        }
+    };
+    let record = |status, retry_after| {
+        if let Some(bucket) = &bucket {
+            bucket.record(status, retry_after);
+        }
+    };
+    let mut attempt = 0;
+    loop {
+        let Some(copy) = request.try_clone().filter(|_| attempt < policy.max_retries) else {
+            acquire().await;
+            let response = client.execute(request).await?;
+            record(response.status(), retry_after(&response));
+            return Ok(response);
+        };
+        acquire().await;
+        let delay = match client.execute(copy).await {
+            Ok(response) if !is_transient(response.status()) => {
+                record(response.status(), None);
+                return Ok(response);
+            }
+            Ok(response) => {
+                record(response.status(), retry_after(&response));
+                let delay = policy.delay(attempt, retry_after(&response));
+                warn!(url = %request.url(), status = response.status().as_u16(), attempt, delay_secs = delay.as_secs_f64(), "Retrying request");
+                delay
+            }
+            Err(e) if e.is_timeout() || e.is_connect() => {
+                let delay = policy.delay(attempt, None);
+                warn!(url = %request.url(), error = %e, attempt, delay_secs = delay.as_secs_f64(), "Retrying request");
+                delay
+            }
+            Err(e) => return Err(e),
+        };
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Whether a status is worth retrying
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// `Retry-After` in seconds
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy { jitter: 0.0, ..Default::default() };
+        assert_eq!(policy.delay(0, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(3, None), Duration::from_secs(8));
+        assert_eq!(policy.delay(10, None), MAX_RETRY_DELAY);
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(5))), Duration::from_secs(5));
+        let jittered = RetryPolicy { jitter: 0.5, ..policy }.delay(2, None);
+        assert!(jittered >= Duration::from_secs(2) && jittered <= Duration::from_secs(6));
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS) && !is_transient(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_retry_override() -> Result<()> {
+        let settings = HttpSettings::default();
+        assert_eq!(settings.retry_policy(Upstream::Crossref).base_backoff, Duration::from_millis(500));
+        let (upstream, policy) = settings.parse_retry_override("s2=5:2000")?;
+        assert_eq!(upstream, Upstream::SemanticScholar);
+        assert_eq!((policy.max_retries, policy.base_backoff), (5, Duration::from_secs(2)));
+        // Omitted fields keep the upstream's defaults
+        assert_eq!(policy.timeout, Some(Duration::from_secs(60)));
+        let (_, policy) = settings.parse_retry_override("llm=0:500:0:600")?;
+        assert_eq!((policy.max_retries, policy.jitter, policy.timeout), (0, 0.0, Some(Duration::from_secs(600))));
+        for invalid in ["s2", "s2=many", "s2=1:100:2", "s2=1:1:0:1:1", "zotero=1"] {
+            assert!(settings.parse_retry_override(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_checks_proxy() {
+        let settings = HttpSettings { proxy: Some("http://127.0.0.1:7890".to_string()), ..Default::default() };
+        assert!(builder(&settings).is_ok());
+        let settings = HttpSettings { proxy: Some("not a url".to_string()), ..Default::default() };
+        assert!(builder(&settings).is_err());
+        let settings = HttpSettings { proxy: Some(DIRECT.to_string()), ..Default::default() };
+        assert!(builder(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_proxy_override() -> Result<()> {
+        let (upstream, proxy) = parse_proxy_override("s2=http://10.0.0.1:3128")?;
+        assert_eq!(upstream, Upstream::SemanticScholar);
+        let settings = HttpSettings {
+            proxy: Some("http://127.0.0.1:7890".to_string()),
+            proxy_overrides: HashMap::from([(upstream, proxy), parse_proxy_override("llm=direct")?]),
+            ..Default::default()
+        };
+        assert_eq!(settings.for_upstream(Upstream::SemanticScholar).proxy.as_deref(), Some("http://10.0.0.1:3128"));
+        assert_eq!(settings.for_upstream(Upstream::Llm).proxy.as_deref(), Some(DIRECT));
+        assert_eq!(settings.for_upstream(Upstream::Crossref).proxy.as_deref(), Some("http://127.0.0.1:7890"));
+        assert!(parse_proxy_override("zotero=direct").is_err());
+        assert!(parse_proxy_override("http://127.0.0.1:7890").is_err());
+        Ok(())
+    }
+}
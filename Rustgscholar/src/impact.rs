@@ -0,0 +1,97 @@
+//! Citation velocity and field-normalized impact.
+//!
+//! Raw citation counts favour old papers. Citations per year divide the
+//! count by the paper's age, and the field-normalized citation score divides
+//! it by the mean count of the journal articles OpenAlex files under the
+//! same primary topic and publication year (1.0 = field average). Both are
+//! sortable columns of the unified table, so recent high-impact work is not
+//! drowned out by highly cited classics.
+
+use crate::cache::HttpCache;
+use crate::error::Result;
+use crate::http;
+use crate::openalex;
+use crate::throttle::Upstream;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Citations per year of a paper published in `year`, counting the year of
+/// publication as the first (a paper from this year is one year old)
+pub fn citations_per_year(citations: u64, year: u32, current_year: u32) -> f64 {
+    let age = current_year.saturating_sub(year) + 1;
+    citations as f64 / f64::from(age)
+}
+
+/// Citations relative to the field baseline (`None` when the field mean is zero)
+pub fn normalized_score(citations: u64, field_mean: f64) -> Option<f64> {
+    (field_mean > 0.0).then(|| citations as f64 / field_mean)
+}
+
+/// Field-normalized citation scores from OpenAlex topic baselines
+pub struct ImpactClient {
+    client: reqwest::Client,
+    cache: Option<HttpCache>,
+    /// Mean citations by (topic ID, year), shared by all papers of a run
+    baselines: Mutex<HashMap<(String, u32), Option<f64>>>,
+}
+
+impl Default for ImpactClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImpactClient {
+    pub fn new() -> Self {
+        Self {
+            client: http::client_for(Upstream::OpenAlex),
+            cache: None,
+            baselines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/openalex`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Look up a DOI in OpenAlex (for its topic, citation count and year)
+    pub async fn lookup(&self, doi: &str) -> Result<Option<openalex::OpenAlexResult>> {
+        openalex::lookup_by_doi(&self.client, doi, self.cache.as_ref()).await
+    }
+
+    /// Mean citations of the topic's articles published in `year`
+    pub async fn baseline(&self, topic_id: &str, year: u32) -> Result<Option<f64>> {
+        let key = (topic_id.to_string(), year);
+        if let Some(mean) = self.baselines.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&key) {
+            return Ok(*mean);
+        }
+        let mean = openalex::topic_citation_mean(&self.client, topic_id, year, self.cache.as_ref()).await?;
+        debug!(topic = %topic_id, year = year, mean = ?mean, "Field citation baseline");
+        self.baselines.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(key, mean);
+        Ok(mean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_citations_per_year() {
+        assert_eq!(citations_per_year(100, 2016, 2025), 10.0);
+        // Published this year: one year old
+        assert_eq!(citations_per_year(7, 2025, 2025), 7.0);
+        // A year in the future does not divide by zero
+        assert_eq!(citations_per_year(3, 2027, 2025), 3.0);
+    }
+
+    #[test]
+    fn test_normalized_score() {
+        assert_eq!(normalized_score(30, 15.0), Some(2.0));
+        assert_eq!(normalized_score(0, 15.0), Some(0.0));
+        assert_eq!(normalized_score(30, 0.0), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Incremental review updates (`--since <previous-run-folder>`).
+//!
+//! An update run searches again, but only from the year of the previous run
+//! onwards, and drops every record the previous run already identified
+//! before ranking, lookup and screening, so a monthly update screens only
+//! the new papers. Each update writes the keys it has seen (its own plus
+//! those inherited) to `seen.txt`, so updates can be chained, and a
+//! `delta.md` report listing what is new.
+//!
+//! Records are matched by DOI or by normalized title (see
+//! [`prisma::duplicate_key`]), so a paper seen without a DOI is still
+//! recognized once one is known.
+
+use crate::error::{GscholarError, Result};
+use crate::input;
+use crate::prisma::{self, PrismaCounts};
+use crate::spool::Spooled;
+use crate::state::{RunState, Stage, STATE_FILE};
+use crate::paper::Paper;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Keys of every record seen by a run and the runs it updates
+pub const SEEN_FILE: &str = "seen.txt";
+
+/// Report of the records new since the previous run
+pub const DELTA_FILE: &str = "delta.md";
+
+/// What an update run needs from the run it updates
+#[derive(Debug, Clone)]
+pub struct PreviousRun {
+    pub folder: PathBuf,
+    /// Duplicate keys (DOI and title) of every record already seen
+    pub seen: HashSet<String>,
+    /// When the previous run was made
+    pub date: NaiveDate,
+}
+
+/// Title and DOI of a recorded search result
+#[derive(Deserialize)]
+struct SeenRecord {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    doi: String,
+}
+
+impl PreviousRun {
+    /// Load a previous run folder
+    ///
+    /// Seen records come from its `seen.txt`, the search results its
+    /// `state.json` points to, and its `5_unified.csv`, whichever exist.
+    pub fn load(folder: &Path) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let mut dated: Vec<PathBuf> = Vec::new();
+
+        let seen_path = folder.join(SEEN_FILE);
+        if seen_path.exists() {
+            seen.extend(std::fs::read_to_string(&seen_path)?.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from));
+            dated.push(seen_path);
+        }
+        if let Some(state) = RunState::load(folder)? {
+            if let Some(spooled) = state.data::<Spooled>(Stage::Search)? {
+                for record in spooled.reader::<SeenRecord>(folder)? {
+                    let record = record?;
+                    seen.extend(keys(&record.doi, &record.title));
+                }
+            }
+            dated.push(folder.join(STATE_FILE));
+        }
+        let unified_path = folder.join("5_unified.csv");
+        if unified_path.exists() {
+            for record in input::load_unified(&unified_path, &HashMap::new())? {
+                seen.extend(keys(&record.doi, &record.title));
+            }
+            dated.push(unified_path);
+        }
+
+        let Some(newest) = dated.iter().filter_map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok()).max() else {
+            return Err(GscholarError::Validation(format!(
+                "{:?} is not a run folder (no {}, {} or 5_unified.csv)",
+                folder, SEEN_FILE, STATE_FILE
+            )));
+        };
+        let date = DateTime::<Local>::from(newest).date_naive();
+        info!(folder = ?folder, seen = seen.len(), date = %date, "Loaded previous run");
+
+        Ok(Self { folder: folder.to_path_buf(), seen, date })
+    }
+
+    /// Whether a record was already seen, by DOI or by title
+    pub fn contains(&self, doi: &str, title: &str) -> bool {
+        keys(doi, title).iter().any(|key| self.seen.contains(key))
+    }
+}
+
+/// Duplicate keys of a record: its DOI key (if any) and its title key
+fn keys(doi: &str, title: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    if !doi.trim().is_empty() {
+        keys.push(prisma::duplicate_key(doi, ""));
+    }
+    if title.chars().any(char::is_alphanumeric) {
+        keys.push(prisma::duplicate_key("", title));
+    }
+    keys
+}
+
+/// Write `seen.txt`: the previous run's keys plus those of `records`
+pub fn save_seen<'a>(folder: &Path, previous: &PreviousRun, records: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<()> {
+    let mut seen: BTreeSet<String> = previous.seen.iter().cloned().collect();
+    for (doi, title) in records {
+        seen.extend(keys(doi, title));
+    }
+    let mut contents = seen.into_iter().collect::<Vec<_>>().join("\n");
+    contents.push('\n');
+    std::fs::write(folder.join(SEEN_FILE), contents)?;
+    Ok(())
+}
+
+/// Markdown report of an update run: counts and the new relevant papers
+///
+/// `relevant` is `None` when Stage 7 did not run, in which case the new
+/// records of the unified dataset are listed instead.
+pub fn render_delta(previous: &PreviousRun, counts: &PrismaCounts, unified: &[Paper], relevant: Option<&[Paper]>) -> String {
+    let name = previous.folder.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut out = format!("# Update: {}\n\nSince `{}` ({}).\n\n", counts.keyword, name, previous.date);
+    out.push_str("| | Records |\n|---|---:|\n");
+    out.push_str(&format!("| Identified | {} |\n", counts.identified));
+    out.push_str(&format!("| Already in the previous run | {} |\n", counts.previously_seen));
+    out.push_str(&format!("| New after removal | {} |\n", counts.after_removal()));
+    if let Some(included) = counts.included {
+        out.push_str(&format!("| New relevant | {} |\n", included));
+    }
+
+    let (heading, papers) = match relevant {
+        Some(relevant) => ("New relevant papers", relevant),
+        None => ("New papers", unified),
+    };
+    out.push_str(&format!("\n## {}\n\n", heading));
+    if papers.is_empty() {
+        out.push_str("None.\n");
+    }
+    for paper in papers {
+        let year = paper.year.map(|y| y.to_string()).unwrap_or_default();
+        let mut line = format!("- **{}**", paper.title.trim());
+        for detail in [paper.journal.trim(), year.as_str()] {
+            if !detail.is_empty() {
+                line.push_str(&format!(", {}", detail));
+            }
+        }
+        if !paper.doi.is_empty() {
+            line.push_str(&format!(" — https://doi.org/{}", paper.doi));
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_previous_run_chain() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let first = dir.path().join("first");
+        std::fs::create_dir(&first)?;
+        std::fs::write(first.join("5_unified.csv"), "title,doi\nSlope stability,10.1/A\nRainfall thresholds,\n")?;
+        assert!(PreviousRun::load(&dir.path().join("missing")).is_err());
+
+        let previous = PreviousRun::load(&first)?;
+        assert_eq!(previous.date, Local::now().date_naive());
+        assert!(previous.contains("10.1/a", "Another title"));
+        assert!(previous.contains("10.1/new", "Rainfall Thresholds"));
+        assert!(!previous.contains("10.1/b", "Debris flows"));
+
+        // An update inherits everything the run it updates had seen
+        let second = dir.path().join("second");
+        std::fs::create_dir(&second)?;
+        save_seen(&second, &previous, [("10.1/b", "Debris flows")])?;
+        let chained = PreviousRun::load(&second)?;
+        assert!(chained.contains("", "Slope stability") && chained.contains("10.1/B", ""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_delta() {
+        let previous = PreviousRun {
+            folder: PathBuf::from("out/20250101_landslide"),
+            seen: HashSet::new(),
+            date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap_or_default(),
+        };
+        let mut counts = PrismaCounts::new("landslide", "OpenAlex");
+        counts.identified = 50;
+        counts.previously_seen = 45;
+        let paper = Paper {
+            title: "Debris flows".to_string(),
+            journal: "Landslides".to_string(),
+            year: Some(2025),
+            date: NaiveDate::from_ymd_opt(2025, 3, 1),
+            doi: "10.1/b".to_string(),
+            ..Default::default()
+        };
+
+        let md = render_delta(&previous, &counts, std::slice::from_ref(&paper), None);
+        assert!(md.contains("Since `20250101_landslide` (2025-01-01)"));
+        assert!(md.contains("| New after removal | 5 |"));
+        assert!(md.contains("## New papers\n\n- **Debris flows**, Landslides, 2025 — https://doi.org/10.1/b\n"));
+
+        counts.set_screening(5, 0, 0);
+        let md = render_delta(&previous, &counts, &[paper], Some(&[]));
+        assert!(md.contains("| New relevant | 0 |") && md.contains("## New relevant papers\n\nNone.\n"));
+    }
+}
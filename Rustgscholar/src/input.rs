@@ -0,0 +1,286 @@
+//! User-provided record lists for `enrich` and `filter`.
+//!
+//! Instead of searching, Stage 1 can start from a list the user already has:
+//! a CSV exported from a library database (one row per paper, with a title
+//! and/or DOI column) or a plain text file with one DOI per line. Column
+//! names are matched case-insensitively, so exports from Zotero, EndNote or
+//! Web of Science usually work as they are.
+//!
+//! `filter` goes further and reads any CSV straight into unified records
+//! ([`load_unified`]), with `field=column` mappings for headers that are
+//! not recognized.
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::paper::Paper;
+use crate::unified::{UnifiedRow, UNIFIED_COLUMNS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Accepted column names for each field, in order of preference
+const TITLE_COLUMNS: &[&str] = &["title", "article title", "document title", "ti"];
+const DOI_COLUMNS: &[&str] = &["doi", "di"];
+const AUTHOR_COLUMNS: &[&str] = &["author", "authors", "au"];
+const YEAR_COLUMNS: &[&str] = &["year", "publication year", "py"];
+const VENUE_COLUMNS: &[&str] = &["journal", "venue", "source title", "publication title", "so"];
+const ABSTRACT_COLUMNS: &[&str] = &["abstract_text", "abstract", "ab"];
+const CITATION_COLUMNS: &[&str] = &["citations", "cited by", "times cited", "citation count", "tc"];
+const DATE_COLUMNS: &[&str] = &["date", "publication_date", "year", "publication year", "py"];
+
+/// A paper from a user-provided list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub title: String,
+    /// Bare DOI (no `https://doi.org/` prefix)
+    pub doi: String,
+    pub author: String,
+    pub year: String,
+    pub venue: String,
+    /// Citation count as exported (e.g., "1,234")
+    #[serde(default)]
+    pub citations: String,
+}
+
+/// Load records from a CSV with a title and/or DOI column
+///
+/// Rows with neither a title nor a DOI are skipped.
+pub fn load_csv(path: &Path) -> Result<Vec<InputRecord>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+    let column = |names: &[&str]| names.iter().find_map(|name| header.iter().position(|h| h == name));
+
+    let (title, doi) = (column(TITLE_COLUMNS), column(DOI_COLUMNS));
+    if title.is_none() && doi.is_none() {
+        return Err(GscholarError::Validation(format!(
+            "{:?} has no title or DOI column (found: {})",
+            path,
+            header.join(", ")
+        )));
+    }
+    let (author, year, venue) = (column(AUTHOR_COLUMNS), column(YEAR_COLUMNS), column(VENUE_COLUMNS));
+    let citations = column(CITATION_COLUMNS);
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?;
+        let field = |index: Option<usize>| index.and_then(|i| row.get(i)).unwrap_or_default().trim().to_string();
+        let record = InputRecord {
+            title: field(title),
+            doi: doi::normalize(&field(doi)),
+            author: field(author),
+            year: field(year),
+            venue: field(venue),
+            citations: field(citations),
+        };
+        if !record.title.is_empty() || !record.doi.is_empty() {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Parse `field=column` mappings (e.g., `abstract_text=AB`) for [`load_unified`]
+pub fn parse_column_mapping(items: &[String]) -> Result<HashMap<String, String>> {
+    items
+        .iter()
+        .map(|item| {
+            let (field, column) = item
+                .split_once('=')
+                .ok_or_else(|| GscholarError::Validation(format!("Invalid column mapping '{}' (expected field=column)", item)))?;
+            let field = field.trim();
+            if !UNIFIED_COLUMNS.contains(&field) {
+                return Err(GscholarError::Validation(format!(
+                    "Unknown field '{}' (expected one of: {})",
+                    field,
+                    UNIFIED_COLUMNS.join(", ")
+                )));
+            }
+            Ok((field.to_string(), column.trim().to_lowercase()))
+        })
+        .collect()
+}
+
+/// Load any CSV as unified records
+///
+/// Each unified field is read from the column named in `mapping`, else from
+/// a column with the field's own name (so stage tables such as
+/// `5_unified.csv` load as they are) or a common alias. A title column is
+/// required; other fields are left empty when no column matches.
+pub fn load_unified(path: &Path, mapping: &HashMap<String, String>) -> Result<Vec<Paper>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    let mut columns: Vec<(&str, usize)> = Vec::new();
+    for &field in UNIFIED_COLUMNS {
+        let index = match mapping.get(field) {
+            Some(column) => Some(header.iter().position(|h| h == column).ok_or_else(|| {
+                GscholarError::Validation(format!("{:?} has no column '{}' (mapped to {})", path, column, field))
+            })?),
+            None => {
+                let aliases: &[&str] = match field {
+                    "title" => TITLE_COLUMNS,
+                    "doi" => DOI_COLUMNS,
+                    "author" => AUTHOR_COLUMNS,
+                    "journal" => VENUE_COLUMNS,
+                    "abstract_text" => ABSTRACT_COLUMNS,
+                    "date" => DATE_COLUMNS,
+                    "citations" => CITATION_COLUMNS,
+                    _ => &[],
+                };
+                std::iter::once(field)
+                    .chain(aliases.iter().copied())
+                    .find_map(|name| header.iter().position(|h| h == name))
+            }
+        };
+        if let Some(index) = index {
+            columns.push((field, index));
+        }
+    }
+    if !columns.iter().any(|&(field, _)| field == "title") {
+        return Err(GscholarError::Validation(format!(
+            "{:?} has no title column (found: {}); map one with title=<column>",
+            path,
+            header.join(", ")
+        )));
+    }
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?;
+        let get = |field: &str| {
+            columns
+                .iter()
+                .find(|&&(f, _)| f == field)
+                .and_then(|&(_, i)| row.get(i))
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        let record = Paper::from(UnifiedRow {
+            title: get("title"),
+            author: get("author"),
+            institutions: get("institutions"),
+            countries: get("countries"),
+            date: get("date"),
+            doi: doi::normalize(&get("doi")),
+            article_url: get("article_url"),
+            pdf_url: get("pdf_url"),
+            pdf_path: get("pdf_path"),
+            abstract_text: get("abstract_text"),
+            tldr: get("tldr"),
+            journal: get("journal"),
+            venue_type: get("venue_type"),
+            work_type: get("work_type"),
+            is_review: get("is_review"),
+            citations: get("citations"),
+            citations_per_year: String::new(),
+            normalized_citations: get("normalized_citations"),
+            if_score: get("if_score"),
+            jci_score: get("jci_score"),
+            sci_partition: get("sci_partition"),
+            altmetric_score: get("altmetric_score"),
+            altmetric_mentions: get("altmetric_mentions"),
+            altmetric_news: get("altmetric_news"),
+            altmetric_policy: get("altmetric_policy"),
+            funders: get("funders"),
+            grants: get("grants"),
+            sources: get("sources"),
+        });
+        if !record.title.is_empty() {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Load a text file with one DOI per line (`#` starts a comment line)
+pub fn load_dois(path: &Path) -> Result<Vec<InputRecord>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| InputRecord {
+            doi: doi::normalize(line),
+            ..Default::default()
+        })
+        .filter(|record| !record.doi.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_csv() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("library.csv");
+        std::fs::write(
+            &path,
+            "\u{feff}Title,Authors,Publication Year,DOI,Source Title,Cited by\n\
+             Slope stability,A. Li,2021,https://doi.org/10.1/ABC,Landslides,\"1,234\"\n\
+             No DOI here,B. Wu,2020,,,\n\
+             ,,,,,\n",
+        )?;
+        let records = load_csv(&path)?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].doi, "10.1/abc");
+        assert_eq!(records[0].venue, "Landslides");
+        assert_eq!(Paper::from(&records[0]).citations, Some(1234));
+        assert_eq!(records[1].title, "No DOI here");
+        assert_eq!(records[1].year, "2020");
+
+        std::fs::write(&path, "name,notes\nx,y\n")?;
+        assert!(load_csv(&path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_unified() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("wos.csv");
+        std::fs::write(&path, "TI,AB,DI,Notes
+Slope stability,We study slopes.,10.1/a,x
+,No title,,
+")?;
+
+        let mapping = parse_column_mapping(&["tldr=Notes".to_string()])?;
+        let records = load_unified(&path, &mapping)?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].title, "Slope stability");
+        assert_eq!(records[0].abstract_text, "We study slopes.");
+        assert_eq!(records[0].doi, "10.1/a");
+        assert_eq!(records[0].tldr, "x");
+
+        assert!(parse_column_mapping(&["summary=Notes".to_string()]).is_err());
+        assert!(load_unified(&path, &parse_column_mapping(&["title=Name".to_string()])?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dois() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("dois.txt");
+        std::fs::write(&path, "# from Zotero\n10.1/a\n\ndoi:10.1/b\nhttps://dx.doi.org/10.1/c\n")?;
+        let dois: Vec<String> = load_dois(&path)?.into_iter().map(|r| r.doi).collect();
+        assert_eq!(dois, vec!["10.1/a", "10.1/b", "10.1/c"]);
+        Ok(())
+    }
+}
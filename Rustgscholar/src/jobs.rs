@@ -0,0 +1,478 @@
+//! Background pipeline jobs for `serve`.
+//!
+//! A full pipeline run takes far longer than an HTTP client waits, so the
+//! server queues runs as jobs: [`JobQueue::submit`] returns a job ID at once
+//! and a dispatcher runs at most `max_concurrent` jobs in the background.
+//! Every job is kept as `jobs/<id>.json` in the output directory; on start
+//! the queue reloads them and requeues jobs a restart interrupted, which
+//! resume from their run folder's `state.json`.
+//!
+//! Jobs of a tenant run in its workspace, `tenants/<tenant>/` in the output
+//! directory, which also holds the tenant's caches and Google Scholar
+//! cookies; a tenant only sees its own jobs.
+
+use crate::error::{GscholarError, Result};
+use crate::pipeline::{PipelineBuilder, PipelineReport, ProgressFn, Source};
+use crate::state::STATE_FILE;
+use chrono::{DateTime, Local};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tracing::{info, warn};
+
+/// Folder of the job files in the output directory
+pub const JOBS_FOLDER: &str = "jobs";
+
+/// Folder of the tenant workspaces in the output directory
+pub const TENANTS_FOLDER: &str = "tenants";
+
+/// Cache folder in a tenant workspace
+pub const WORKSPACE_CACHE_FOLDER: &str = "cache";
+
+/// Google Scholar cookie file in a tenant workspace
+pub const WORKSPACE_COOKIE_FILE: &str = "cookies.json";
+
+/// Progress lines kept per job
+const MAX_PROGRESS_LINES: usize = 50;
+
+/// Events buffered for slow [`JobQueue::subscribe`] receivers
+const EVENT_BUFFER: usize = 256;
+
+/// Where a job is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Pipeline parameters of a job (body of `POST /jobs`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobRequest {
+    pub keyword: String,
+    /// Search sources (e.g., `["openalex", "s2"]`; default Google Scholar)
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default = "default_pages")]
+    pub pages: Vec<i32>,
+    #[serde(default)]
+    pub ylo: Option<i32>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Keep only papers cited at least this many times
+    #[serde(default)]
+    pub min_citations: Option<u64>,
+    /// Stop searching after this many unique records
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+fn default_pages() -> Vec<i32> {
+    vec![1]
+}
+
+impl JobRequest {
+    /// Pipeline for this request writing into `output_folder` (API keys and
+    /// progress are added by the caller)
+    pub fn pipeline(&self, output_folder: &Path) -> Result<PipelineBuilder> {
+        let sources = self.sources.iter().map(|s| s.trim().parse()).collect::<Result<Vec<Source>>>()?;
+        let mut builder = PipelineBuilder::new(&self.keyword, output_folder)
+            .pages(self.pages.clone())
+            .ylo(self.ylo)
+            .min_citations(self.min_citations)
+            .max_results(self.max_results)
+            .proxy(self.proxy.clone());
+        if !sources.is_empty() {
+            builder = builder.sources(sources);
+        }
+        Ok(builder)
+    }
+}
+
+/// What a finished job produced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobResult {
+    pub unified: usize,
+    pub relevant: usize,
+    /// Most refined stage table written
+    pub final_table: Option<PathBuf>,
+    /// Per-item failures (see `errors.csv` in the run folder)
+    pub failures: usize,
+}
+
+impl From<&PipelineReport> for JobResult {
+    fn from(report: &PipelineReport) -> Self {
+        Self {
+            unified: report.unified.len(),
+            relevant: report.relevant.len(),
+            final_table: report.final_table.clone(),
+            failures: report.failures.len(),
+        }
+    }
+}
+
+/// One queued pipeline run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+    pub request: JobRequest,
+    /// Tenant that submitted the job (none without API keys)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub output_folder: PathBuf,
+    pub created_at: DateTime<Local>,
+    pub started_at: Option<DateTime<Local>>,
+    pub finished_at: Option<DateTime<Local>>,
+    /// Latest progress lines of the run
+    #[serde(default)]
+    pub progress: Vec<String>,
+    pub result: Option<JobResult>,
+    pub error: Option<String>,
+}
+
+/// A progress line or status change of a running job
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobEvent {
+    pub id: String,
+    pub tenant: Option<String>,
+    pub status: JobStatus,
+    pub progress: Option<String>,
+}
+
+/// What a runner gets besides the request
+pub struct JobContext {
+    pub id: String,
+    pub output_folder: PathBuf,
+    pub tenant: Option<String>,
+    /// Workspace of the job's tenant (holds its caches and cookie jar)
+    pub workspace: Option<PathBuf>,
+    /// The run folder holds the state of an interrupted run
+    pub resume: bool,
+    pub progress: ProgressFn,
+}
+
+/// Runs one job's pipeline
+pub type JobRunner = Arc<dyn Fn(JobRequest, JobContext) -> BoxFuture<'static, Result<JobResult>> + Send + Sync>;
+
+/// Persistent job queue with bounded concurrency
+pub struct JobQueue {
+    dir: PathBuf,
+    output_dir: PathBuf,
+    jobs: Mutex<HashMap<String, Job>>,
+    sender: mpsc::UnboundedSender<String>,
+    events: broadcast::Sender<JobEvent>,
+}
+
+impl JobQueue {
+    /// Load the jobs in `output_dir/jobs`, requeue interrupted ones and start
+    /// the dispatcher (must be called inside a Tokio runtime)
+    pub fn start(output_dir: &Path, max_concurrent: usize, runner: JobRunner) -> Result<Arc<Self>> {
+        let dir = output_dir.join(JOBS_FOLDER);
+        std::fs::create_dir_all(&dir)?;
+        let mut jobs = load_jobs(&dir)?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let mut pending: Vec<&mut Job> = jobs
+            .values_mut()
+            .filter(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running))
+            .collect();
+        pending.sort_by_key(|job| job.created_at);
+        for job in pending {
+            job.status = JobStatus::Queued;
+            save_job(&dir, job)?;
+            info!(id = %job.id, "Requeued job");
+            let _ = sender.send(job.id.clone());
+        }
+
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let queue = Arc::new(Self { dir, output_dir: output_dir.to_path_buf(), jobs: Mutex::new(jobs), sender, events });
+        tokio::spawn(dispatch(queue.clone(), receiver, max_concurrent.max(1), runner));
+        Ok(queue)
+    }
+
+    /// Queue a pipeline run for `tenant`
+    pub fn submit(&self, request: JobRequest, tenant: Option<&str>) -> Result<Job> {
+        if request.keyword.trim().is_empty() {
+            return Err(GscholarError::Validation("Job keyword is empty".to_string()));
+        }
+        let id = format!("{}-{:06x}", Local::now().format("%Y%m%d%H%M%S"), rand::random::<u32>() & 0xff_ffff);
+        let folder = match tenant {
+            Some(tenant) => workspace(&self.output_dir, tenant),
+            None => self.output_dir.clone(),
+        };
+        let job = Job {
+            output_folder: folder.join(format!("job_{}", id)),
+            id: id.clone(),
+            status: JobStatus::Queued,
+            request,
+            tenant: tenant.map(String::from),
+            created_at: Local::now(),
+            started_at: None,
+            finished_at: None,
+            progress: Vec::new(),
+            result: None,
+            error: None,
+        };
+        save_job(&self.dir, &job)?;
+        self.lock()?.insert(id.clone(), job.clone());
+        self.sender
+            .send(id)
+            .map_err(|_| GscholarError::Config("The job queue has stopped".to_string()))?;
+        Ok(job)
+    }
+
+    /// A job of `tenant`; other tenants' jobs are not found
+    pub fn get(&self, id: &str, tenant: Option<&str>) -> Option<Job> {
+        self.lock().ok()?.get(id).filter(|job| job.tenant.as_deref() == tenant).cloned()
+    }
+
+    /// All jobs of `tenant`, newest first
+    pub fn list(&self, tenant: Option<&str>) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self
+            .lock()
+            .map(|jobs| jobs.values().filter(|job| job.tenant.as_deref() == tenant).cloned().collect())
+            .unwrap_or_default();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// Progress and status changes of every job from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    /// Tell subscribers about a job's status or a progress line
+    fn notify(&self, job: &Job, progress: Option<String>) {
+        // No subscribers is not an error
+        let _ = self.events.send(JobEvent { id: job.id.clone(), tenant: job.tenant.clone(), status: job.status, progress });
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Job>>> {
+        self.jobs.lock().map_err(|_| GscholarError::Config("Job queue lock poisoned".to_string()))
+    }
+
+    /// Change a job, persisting it when `persist` is set
+    fn update(&self, id: &str, persist: bool, change: impl FnOnce(&mut Job)) -> Option<Job> {
+        let mut jobs = self.lock().ok()?;
+        let job = jobs.get_mut(id)?;
+        change(job);
+        if persist {
+            if let Err(e) = save_job(&self.dir, job) {
+                warn!(id = %id, error = %e, "Failed to save job");
+            }
+        }
+        Some(job.clone())
+    }
+
+    async fn run(self: Arc<Self>, id: String, runner: JobRunner) {
+        let Some(job) = self.update(&id, true, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(Local::now());
+        }) else {
+            return;
+        };
+        self.notify(&job, None);
+        let queue = self.clone();
+        let progress_id = id.clone();
+        let progress: ProgressFn = Arc::new(move |line: &str| {
+            let line = line.trim().to_string();
+            let job = queue.update(&progress_id, false, |job| {
+                job.progress.push(line.clone());
+                let excess = job.progress.len().saturating_sub(MAX_PROGRESS_LINES);
+                job.progress.drain(..excess);
+            });
+            if let Some(job) = job {
+                queue.notify(&job, Some(line));
+            }
+        });
+        let context = JobContext {
+            id: id.clone(),
+            tenant: job.tenant.clone(),
+            workspace: job.tenant.as_deref().map(|tenant| workspace(&self.output_dir, tenant)),
+            resume: job.output_folder.join(STATE_FILE).exists(),
+            output_folder: job.output_folder.clone(),
+            progress,
+        };
+        info!(id = %id, keyword = %job.request.keyword, "Running job");
+        let outcome = runner(job.request, context).await;
+        let finished = self.update(&id, true, |job| {
+            job.finished_at = Some(Local::now());
+            match outcome {
+                Ok(result) => {
+                    job.status = JobStatus::Completed;
+                    job.result = Some(result);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        });
+        if let Some(job) = finished {
+            self.notify(&job, None);
+        }
+    }
+}
+
+/// Workspace of a tenant in the output directory
+pub fn workspace(output_dir: &Path, tenant: &str) -> PathBuf {
+    output_dir.join(TENANTS_FOLDER).join(tenant)
+}
+
+/// Start queued jobs as slots free up
+async fn dispatch(queue: Arc<JobQueue>, mut receiver: mpsc::UnboundedReceiver<String>, max_concurrent: usize, runner: JobRunner) {
+    let slots = Arc::new(Semaphore::new(max_concurrent));
+    while let Some(id) = receiver.recv().await {
+        let Ok(permit) = slots.clone().acquire_owned().await else {
+            break;
+        };
+        let job = queue.clone().run(id, runner.clone());
+        tokio::spawn(async move {
+            job.await;
+            drop(permit);
+        });
+    }
+}
+
+fn load_jobs(dir: &Path) -> Result<HashMap<String, Job>> {
+    let mut jobs = HashMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match std::fs::read_to_string(&path).map_err(GscholarError::from).and_then(|s| Ok(serde_json::from_str::<Job>(&s)?)) {
+            Ok(job) => {
+                jobs.insert(job.id.clone(), job);
+            }
+            Err(e) => warn!(path = ?path, error = %e, "Skipping unreadable job file"),
+        }
+    }
+    Ok(jobs)
+}
+
+fn save_job(dir: &Path, job: &Job) -> Result<()> {
+    let tmp = dir.join(format!("{}.json.tmp", job.id));
+    std::fs::write(&tmp, serde_json::to_string_pretty(job)?)?;
+    std::fs::rename(&tmp, dir.join(format!("{}.json", job.id)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Runner that reports one progress line and counts the keyword's words
+    fn runner() -> JobRunner {
+        Arc::new(|request: JobRequest, context: JobContext| {
+            Box::pin(async move {
+                (context.progress)("Stage 1: search");
+                match request.keyword.as_str() {
+                    "fail" => Err(GscholarError::Validation("bad keyword".to_string())),
+                    keyword => Ok(JobResult { unified: keyword.split_whitespace().count(), ..Default::default() }),
+                }
+            })
+        })
+    }
+
+    async fn wait_finished(queue: &JobQueue, id: &str, tenant: Option<&str>) -> Option<Job> {
+        for _ in 0..200 {
+            match queue.get(id, tenant) {
+                Some(job) if matches!(job.status, JobStatus::Completed | JobStatus::Failed) => return Some(job),
+                _ => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_and_persist() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let queue = JobQueue::start(dir.path(), 1, runner())?;
+        let mut events = queue.subscribe();
+        let ok = queue.submit(JobRequest { keyword: "slope stability".to_string(), ..Default::default() }, None)?;
+        let failed = queue.submit(JobRequest { keyword: "fail".to_string(), ..Default::default() }, None)?;
+        assert!(queue.submit(JobRequest::default(), None).is_err());
+
+        let ok = wait_finished(&queue, &ok.id, None).await.ok_or_else(|| GscholarError::Validation("timeout".to_string()))?;
+        assert_eq!(ok.status, JobStatus::Completed);
+        assert_eq!(ok.result.map(|r| r.unified), Some(2));
+        assert_eq!(ok.progress, vec!["Stage 1: search".to_string()]);
+        let failed = wait_finished(&queue, &failed.id, None).await.ok_or_else(|| GscholarError::Validation("timeout".to_string()))?;
+        assert_eq!(failed.error.as_deref(), Some("Validation error: bad keyword"));
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if event.id == ok.id {
+                seen.push((event.status, event.progress));
+            }
+        }
+        assert_eq!(
+            seen,
+            vec![
+                (JobStatus::Running, None),
+                (JobStatus::Running, Some("Stage 1: search".to_string())),
+                (JobStatus::Completed, None)
+            ]
+        );
+
+        let saved = load_jobs(&dir.path().join(JOBS_FOLDER))?;
+        assert_eq!(saved.get(&ok.id).map(|job| job.status), Some(JobStatus::Completed));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tenant_jobs_are_isolated() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let runner: JobRunner = Arc::new(|_request: JobRequest, context: JobContext| {
+            Box::pin(async move {
+                let workspace = context.workspace.ok_or_else(|| GscholarError::Validation("no workspace".to_string()))?;
+                Ok(JobResult { final_table: Some(workspace), ..Default::default() })
+            })
+        });
+        let queue = JobQueue::start(dir.path(), 1, runner)?;
+        let job = queue.submit(JobRequest { keyword: "landslide".to_string(), ..Default::default() }, Some("lab-a"))?;
+        let workspace = dir.path().join(TENANTS_FOLDER).join("lab-a");
+        assert_eq!(job.output_folder, workspace.join(format!("job_{}", job.id)));
+
+        assert!(queue.get(&job.id, Some("lab-b")).is_none());
+        assert!(queue.get(&job.id, None).is_none());
+        assert!(queue.list(Some("lab-b")).is_empty());
+        let job = wait_finished(&queue, &job.id, Some("lab-a")).await;
+        assert_eq!(job.and_then(|job| job.result).and_then(|r| r.final_table), Some(workspace));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_jobs_are_requeued() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let jobs_dir = dir.path().join(JOBS_FOLDER);
+        std::fs::create_dir_all(&jobs_dir)?;
+        let interrupted = Job {
+            id: "20250101000000-abcdef".to_string(),
+            status: JobStatus::Running,
+            request: JobRequest { keyword: "landslide".to_string(), ..Default::default() },
+            tenant: None,
+            output_folder: dir.path().join("job_20250101000000-abcdef"),
+            created_at: Local::now(),
+            started_at: Some(Local::now()),
+            finished_at: None,
+            progress: Vec::new(),
+            result: None,
+            error: None,
+        };
+        save_job(&jobs_dir, &interrupted)?;
+
+        let queue = JobQueue::start(dir.path(), 2, runner())?;
+        let job = wait_finished(&queue, &interrupted.id, None).await;
+        assert_eq!(job.map(|job| job.status), Some(JobStatus::Completed));
+        Ok(())
+    }
+}
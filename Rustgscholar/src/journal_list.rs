@@ -0,0 +1,120 @@
+//! Journal whitelist/blacklist matching.
+//!
+//! List files contain one entry per line. Plain entries match a journal
+//! name exactly after normalization (case, punctuation, "&" vs "and");
+//! entries wrapped in slashes (`/predatory|hindawi/`) are case-insensitive
+//! regular expressions. Blank lines and lines starting with `#` are ignored.
+
+use crate::error::{GscholarError, Result};
+use crate::rankings::normalize_journal_name;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::info;
+
+/// A set of journal names and patterns
+#[derive(Debug, Default)]
+pub struct JournalList {
+    exact: HashSet<String>,
+    patterns: Vec<Regex>,
+}
+
+impl JournalList {
+    /// Load a journal list from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let list = Self::parse(&content)?;
+        info!(
+            path = ?path,
+            exact = list.exact.len(),
+            patterns = list.patterns.len(),
+            "Loaded journal list"
+        );
+        Ok(list)
+    }
+
+    /// Parse a journal list from text
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut list = Self::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.strip_prefix('/').and_then(|l| l.strip_suffix('/')) {
+                Some(pattern) => {
+                    let regex = RegexBuilder::new(pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| GscholarError::Validation(format!("Invalid journal pattern '{}': {}", pattern, e)))?;
+                    list.patterns.push(regex);
+                }
+                None => {
+                    list.exact.insert(normalize_journal_name(line));
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// Check whether a journal name matches any entry
+    pub fn matches(&self, journal: &str) -> bool {
+        let journal = journal.trim();
+        if journal.is_empty() {
+            return false;
+        }
+        self.exact.contains(&normalize_journal_name(journal)) || self.patterns.iter().any(|p| p.is_match(journal))
+    }
+
+    /// Number of entries in the list
+    pub fn len(&self) -> usize {
+        self.exact.len() + self.patterns.len()
+    }
+
+    /// Whether the list has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Decide whether a journal passes optional include/exclude lists
+///
+/// With an include list, only matching journals pass (papers without a
+/// journal are dropped). Journals matching the exclude list never pass.
+pub fn passes_journal_lists(journal: &str, include: Option<&JournalList>, exclude: Option<&JournalList>) -> bool {
+    include.is_none_or(|list| list.matches(journal)) && !exclude.is_some_and(|list| list.matches(journal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match() -> Result<()> {
+        let list = JournalList::parse(
+            "# Predatory venues\n\nJournal of Rock Mechanics & Geotechnical Engineering\n/^international journal of advanced/\n",
+        )?;
+        assert_eq!(list.len(), 2);
+        assert!(list.matches("journal of rock mechanics and geotechnical engineering"));
+        assert!(list.matches("International Journal of Advanced Research"));
+        assert!(!list.matches("Computers and Geotechnics"));
+        assert!(!list.matches(""));
+        assert!(JournalList::parse("/[unclosed/").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_passes_journal_lists() -> Result<()> {
+        let include = JournalList::parse("Nature\nScience")?;
+        let exclude = JournalList::parse("/science/")?;
+        assert!(passes_journal_lists("Nature", Some(&include), Some(&exclude)));
+        assert!(!passes_journal_lists("Science", Some(&include), Some(&exclude)));
+        assert!(!passes_journal_lists("Cell", Some(&include), None));
+        assert!(!passes_journal_lists("", Some(&include), None));
+        assert!(passes_journal_lists("", None, Some(&exclude)));
+        Ok(())
+    }
+}
@@ -4,10 +4,78 @@
 //!
 //! ## Modules
 //!
+//! - [`pipeline`] - The full search pipeline (builder and report)
+//! - [`paper`] - Typed paper model shared by every stage
+//! - [`stream`] - Bounded page channels between streaming stages
+//! - [`spool`] - Stage 1 records spooled to disk (`records/`)
 //! - [`gscholar`] - Google Scholar scraping with Playwright
+//! - [`scholar_api`] - Google Scholar results through SerpAPI or SearchAPI (`--backend`)
 //! - [`crossref`] - Crossref API client for metadata enrichment
 //! - [`rankings`] - EasyScholar rankings API
+//! - [`venue`] - Journal/conference detection and CORE conference ranks
+//! - [`work_type`] - Work types (`--work-types`) across the sources' taxonomies
+//! - [`review_detection`] - Review-article detection (`--exclude-reviews`, `--only-reviews`)
+//! - [`unpaywall`] - Unpaywall open-access lookup
+//! - [`pdf`] - Open-access PDF downloads
+//! - [`fulltext`] - Full-text extraction with GROBID
+//! - [`grobid`] - GROBID client and TEI parsing
+//! - [`enrich`] - Pluggable metadata enrichers
+//! - [`abstracts`] - Abstract backfill chain
+//! - [`scopus`] - Scopus abstract retrieval
+//! - [`altmetric`] - Altmetric attention scores
+//! - [`impact`] - Citation velocity and field-normalized impact
+//! - [`rank_filter`] - Ranking filter expression language
+//! - [`input`] - User-provided record lists (CSV, DOI lists)
+//! - [`doi`] - DOI canonicalization for joins and deduplication
+//! - [`matching`] - Title normalization and fuzzy matching
+//! - [`journal_list`] - Journal whitelist/blacklist matching
 //! - [`cookies`] - Cookie persistence
+//! - [`browser`] - Google Scholar cookies from a real browser session (feature "browser")
+//! - [`cookie_import`] - Google cookies from Chrome/Edge/Firefox profiles (feature "cookie-import")
+//! - [`cache`] - Persistent on-disk caches
+//! - [`config`] - TOML/YAML configuration files
+//! - [`checkpoint`] - JSONL checkpoints for resumable stages
+//! - [`state`] - Run state for resuming the pipeline
+//! - [`incremental`] - Incremental review updates (`--since`)
+//! - [`watch`] - Scheduled update runs and notifications
+//! - [`jobs`] - Background pipeline jobs for `serve`
+//! - [`api`] - HTTP error envelope and OpenAPI document
+//! - [`auth`] - API-key authentication and quotas for `serve`
+//! - [`health`] - Upstream readiness probes for `serve`
+//! - [`doctor`] - Pre-flight connectivity and API key checks
+//! - [`store`] - Queryable store of `serve` job results
+//! - [`grpc`] - gRPC API of `serve` (feature "grpc")
+//! - [`web`] - Embedded web UI of `serve`
+//! - [`http`] - Shared HTTP client, connection settings and retries
+//! - [`throttle`] - Shared per-upstream request limits for `serve`
+//! - [`ratelimit`] - Per-host token buckets with adaptive 429 handling
+//! - [`estimate`] - Dry-run cost and time estimates
+//! - [`manifest`] - Run provenance (`manifest.json`)
+//! - [`strategy`] - Search strategy report (`search_strategy.md`)
+//! - [`secrets`] - API keys from the environment and log redaction
+//! - [`prefilter`] - Keyword pre-filter ahead of LLM screening
+//! - [`screening`] - Inclusion/exclusion criteria screening
+//! - [`review`] - Human review of screened papers (`review`)
+//! - [`review_tui`] - Terminal UI of `review` (feature "tui")
+//! - [`tagging`] - Multi-label topic tagging
+//! - [`study_design`] - Study design classification for evidence tables
+//! - [`query_expansion`] - LLM query expansion suggestions
+//! - [`export`] - Bibliography export (BibTeX, RIS)
+//! - [`zotero`] - Zotero Web API client
+//! - [`merge`] - Merging unified results across runs
+//! - [`diff`] - Added, removed and changed papers between two runs
+//! - [`network`] - Citation network export (GraphML, GEXF)
+//! - [`topics`] - Keyword co-occurrence and topic clusters (VOSviewer)
+//! - [`orcid`] - Author resolution to ORCID iDs
+//! - [`xlsx`] - Excel workbook writer (`--xlsx`)
+//! - [`output`] - Stage table formats (CSV, Parquet)
+//! - [`parquet`] - Minimal Parquet writer
+//! - [`sqlite`] - SQLite run database writer
+//! - [`report`] - HTML report and Markdown digest
+//! - [`prisma`] - PRISMA 2020 flow counts
+//! - [`failures`] - Per-item failures of a run (`errors.csv`)
+//! - [`run_log`] - JSON-lines log file of a run (`run.log`)
+//! - [`raw_archive`] - Gzipped raw upstream responses (`--save-raw`)
 //! - [`error`] - Custom error types
 //!
 //! ## Usage
@@ -22,16 +90,108 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! The whole pipeline, as the CLI runs it:
+//!
+//! ```rust,no_run
+//! use rustgscholar::pipeline::{PipelineBuilder, RankingOptions, Source};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let report = PipelineBuilder::new("machine learning", "output/ml")
+//!         .source(Source::OpenAlex)
+//!         .pages(vec![1, 2, 3])
+//!         .ranking(RankingOptions::new("your-easyscholar-key"))
+//!         .build()
+//!         .run()
+//!         .await?;
+//!     println!("{} unified papers, {} relevant", report.unified.len(), report.relevant.len());
+//!     Ok(())
+//! }
+//! ```
 
+pub mod abstracts;
+pub mod altmetric;
+pub mod api;
+pub mod auth;
+#[cfg(feature = "browser")]
+pub mod browser;
+pub mod cache;
+pub mod checkpoint;
+pub mod config;
+#[cfg(feature = "cookie-import")]
+pub mod cookie_import;
 pub mod cookies;
 pub mod crossref;
+pub mod diff;
+pub mod doctor;
+pub mod doi;
 pub mod error;
+pub mod enrich;
+pub mod estimate;
+pub mod export;
+pub mod failures;
+pub mod fulltext;
+pub mod grobid;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod http;
+pub mod impact;
 pub mod gscholar;
+pub mod incremental;
+pub mod input;
+pub mod jobs;
+pub mod journal_list;
 pub mod llm_filter;
+pub mod manifest;
+pub mod matching;
+pub mod merge;
+pub mod network;
 pub mod openalex;
+pub mod orcid;
+pub mod output;
+pub mod paper;
+pub mod parquet;
+pub mod pdf;
+pub mod pipeline;
+pub mod prefilter;
+pub mod prisma;
 pub mod prompts;
+pub mod query_expansion;
+pub mod rank_filter;
+pub mod raw_archive;
 pub mod rankings;
+pub mod report;
+pub mod review;
+pub mod review_detection;
+#[cfg(feature = "tui")]
+pub mod review_tui;
+pub mod run_log;
+pub mod scholar_api;
+pub mod scopus;
+pub mod screening;
+pub mod secrets;
 pub mod semanticscholar;
+pub mod spool;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod state;
+pub mod store;
+pub mod strategy;
+pub mod stream;
+pub mod study_design;
+pub mod tagging;
+pub mod throttle;
+pub mod ratelimit;
+pub mod topics;
 pub mod unified;
+pub mod unpaywall;
+pub mod venue;
+pub mod watch;
+pub mod web;
+pub mod work_type;
+pub mod xlsx;
+pub mod zotero;
 
 pub use error::{GscholarError, Result};
@@ -2,31 +2,268 @@
 //!
 //! This module provides concurrent LLM API calls to classify papers
 //! as relevant, irrelevant, or uncertain based on user-provided keywords.
+//!
+//! When the provider supports it, requests use structured output
+//! (`response_format: json_schema` or `json_object`) so responses parse
+//! reliably; `extract_json` remains as a fallback for plain-text replies.
+//!
+//! Replies can be cached on disk keyed by model and a hash of the prompts
+//! (which embed the paper's DOI and metadata), so re-running a pipeline
+//! does not pay again for papers that were already screened.
+//!
+//! Besides OpenAI-compatible endpoints, a local Ollama server can be used
+//! through its native `/api/chat` endpoint, without an API key.
+//!
+//! Reasoning models (DeepSeek-R1, o-series) are supported: `<think>` blocks
+//! are stripped before parsing, reasoning tokens are counted separately, and
+//! the temperature can be omitted for providers that reject it.
 
+use crate::cache::HttpCache;
+use crate::checkpoint::JsonlCheckpoint;
+use crate::doi;
 use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::matching;
 use crate::prompts::relevance_filter::{build_user_prompt, SYSTEM_PROMPT};
-use crate::unified::UnifiedResult;
-use futures::stream::{self, StreamExt};
+use crate::paper::Paper;
+use crate::ratelimit;
+use crate::raw_archive::{self, BodyKind};
+use crate::throttle::{self, Upstream};
+use crate::stream;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
-/// Maximum concurrent LLM API requests
-const MAX_CONCURRENT_REQUESTS: usize = 10;
+/// Default number of concurrent LLM API requests
+pub const DEFAULT_CONCURRENT_REQUESTS: usize = 10;
+
+/// Default sampling temperature (low for consistent classification)
+pub const DEFAULT_TEMPERATURE: f64 = 0.1;
 
-/// Request timeout in seconds
+/// Default completion token limit
+pub const DEFAULT_MAX_TOKENS: u32 = 20000;
+
+/// Default request timeout in seconds
 const REQUEST_TIMEOUT_SECS: u64 = 60;
 
+/// Request timeout for local models, which may load weights on first use
+const LOCAL_REQUEST_TIMEOUT_SECS: u64 = 300;
+
+/// Structured-output mode for LLM requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonMode {
+    /// Try `json_schema`, downgrading to `json_object` and then plain text
+    /// when the provider rejects the request
+    #[default]
+    Auto,
+    /// Always send `response_format: {type: "json_schema"}`
+    Schema,
+    /// Always send `response_format: {type: "json_object"}`
+    Object,
+    /// Plain text; rely on `extract_json`
+    Off,
+}
+
+impl FromStr for JsonMode {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "schema" => Ok(Self::Schema),
+            "object" => Ok(Self::Object),
+            "off" => Ok(Self::Off),
+            _ => Err(GscholarError::Config(format!("Invalid JSON mode: {}", s))),
+        }
+    }
+}
+
+/// LLM API provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LlmProvider {
+    /// OpenAI-compatible `/chat/completions` endpoint
+    #[default]
+    OpenAi,
+    /// Local Ollama server (`/api/chat`), no API key required
+    Ollama,
+}
+
+impl LlmProvider {
+    /// Base URL used when `--llm-base-url` is not given
+    pub fn default_base_url(self) -> Option<&'static str> {
+        match self {
+            Self::OpenAi => None,
+            Self::Ollama => Some("http://localhost:11434"),
+        }
+    }
+
+    /// Whether requests need an API key
+    pub fn requires_api_key(self) -> bool {
+        self == Self::OpenAi
+    }
+
+    fn endpoint(self, base_url: &str) -> String {
+        let base_url = base_url.trim_end_matches('/');
+        match self {
+            Self::OpenAi => format!("{}/chat/completions", base_url),
+            Self::Ollama => format!("{}/api/chat", base_url),
+        }
+    }
+
+    /// Model listing endpoint: a cheap request to check the server is reachable
+    pub fn models_endpoint(self, base_url: &str) -> String {
+        let base_url = base_url.trim_end_matches('/');
+        match self {
+            Self::OpenAi => format!("{}/models", base_url),
+            Self::Ollama => format!("{}/api/tags", base_url),
+        }
+    }
+
+    /// Request timeout used when none is configured
+    pub fn default_timeout(self) -> Duration {
+        match self {
+            Self::OpenAi => Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            Self::Ollama => Duration::from_secs(LOCAL_REQUEST_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl FromStr for LlmProvider {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "openai" => Ok(Self::OpenAi),
+            "ollama" => Ok(Self::Ollama),
+            _ => Err(GscholarError::Config(format!("Invalid LLM provider: {}", s))),
+        }
+    }
+}
+
 /// LLM configuration
 #[derive(Debug, Clone)]
 pub struct LlmConfig {
+    pub provider: LlmProvider,
     pub base_url: String,
+    /// API key; may be empty for providers that do not require one
     pub api_key: String,
     pub model: String,
     pub filter_help: String,
+    pub json_mode: JsonMode,
+    /// Retries for 429/5xx/network errors before a paper is marked uncertain
+    pub max_retries: u32,
+    /// On-disk reply cache directory (one file per reply); None disables caching
+    pub cache_dir: Option<PathBuf>,
+    /// How long cached replies stay valid
+    pub cache_ttl: Duration,
+    /// Maximum requests in flight
+    pub concurrency: usize,
+    /// Per-request timeout; None uses the provider default
+    pub timeout: Option<Duration>,
+    /// Sampling temperature; None omits it (reasoning models reject it)
+    pub temperature: Option<f64>,
+    pub max_tokens: u32,
+}
+
+/// `response_format` level currently in use, shared across concurrent requests
+///
+/// In [`JsonMode::Auto`] the level only ever goes down, so one rejected
+/// request switches every later request to the next supported format.
+struct ResponseFormatState {
+    mode: JsonMode,
+    level: AtomicU8,
+}
+
+const FORMAT_SCHEMA: u8 = 2;
+const FORMAT_OBJECT: u8 = 1;
+const FORMAT_NONE: u8 = 0;
+
+impl ResponseFormatState {
+    fn new(mode: JsonMode) -> Self {
+        let level = match mode {
+            JsonMode::Auto | JsonMode::Schema => FORMAT_SCHEMA,
+            JsonMode::Object => FORMAT_OBJECT,
+            JsonMode::Off => FORMAT_NONE,
+        };
+        Self {
+            mode,
+            level: AtomicU8::new(level),
+        }
+    }
+
+    fn current(&self) -> u8 {
+        self.level.load(Ordering::Relaxed)
+    }
+
+    /// Downgrade from `from` to the next format; returns false if no retry should happen
+    fn downgrade(&self, from: u8) -> bool {
+        if self.mode != JsonMode::Auto || from == FORMAT_NONE {
+            return false;
+        }
+        // Losing the exchange means another request already downgraded; retry at its level
+        if self
+            .level
+            .compare_exchange(from, from - 1, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            warn!(from = from, "Provider rejected response_format, downgrading structured output");
+        }
+        true
+    }
+
+    /// Whether a client error is about `response_format` (rather than,
+    /// say, the context length or the model name)
+    fn rejected(error_text: &str) -> bool {
+        let error_text = error_text.to_lowercase();
+        ["response_format", "json_schema", "json_object"].iter().any(|s| error_text.contains(s))
+    }
+
+    /// `response_format` body for a level, if any
+    fn body(level: u8, name: &str, schema: &serde_json::Value) -> Option<serde_json::Value> {
+        match level {
+            FORMAT_SCHEMA => Some(serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": name,
+                    "strict": true,
+                    "schema": schema
+                }
+            })),
+            FORMAT_OBJECT => Some(serde_json::json!({"type": "json_object"})),
+            _ => None,
+        }
+    }
+
+    /// Ollama `format` value for a level: a JSON schema or `"json"`
+    fn ollama_format(level: u8, schema: &serde_json::Value) -> Option<serde_json::Value> {
+        match level {
+            FORMAT_SCHEMA => Some(schema.clone()),
+            FORMAT_OBJECT => Some(serde_json::json!("json")),
+            _ => None,
+        }
+    }
+}
+
+/// JSON schema for the LLM-produced fields of [`FilterResult`]
+pub fn filter_result_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "label": {"type": "string", "enum": ["relevant", "irrelevant", "uncertain"]},
+            "confidence": {"type": "number", "minimum": 0.0, "maximum": 1.0},
+            "evidence": {"type": "array", "items": {"type": "string"}},
+            "reason": {"type": "string"}
+        },
+        "required": ["label", "confidence", "evidence", "reason"],
+        "additionalProperties": false
+    })
 }
 
 /// Filter result for a single paper
@@ -41,12 +278,158 @@ pub struct FilterResult {
     pub reason: String,
 }
 
+impl FilterResult {
+    /// Checkpoint key for a paper: normalized DOI (see [`doi::normalize`]),
+    /// or lowercase title when there is no DOI
+    pub fn checkpoint_key(id: &str, title: &str) -> String {
+        match doi::normalize(id) {
+            doi if doi.is_empty() => title.trim().to_lowercase(),
+            doi => doi,
+        }
+    }
+}
+
+/// How Stage 7 treats uncertain (and low-confidence relevant) verdicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UncertainPolicy {
+    /// Include them with the relevant papers
+    Keep,
+    /// Leave them out
+    #[default]
+    Drop,
+    /// Write them to a separate file for manual review
+    Review,
+}
+
+impl FromStr for UncertainPolicy {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "drop" => Ok(Self::Drop),
+            "review" => Ok(Self::Review),
+            _ => Err(GscholarError::Config(format!("Invalid uncertain policy: {}", s))),
+        }
+    }
+}
+
+/// Where a paper goes in Stage 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Include,
+    Review,
+    Exclude,
+}
+
+/// Decide where a verdict goes in Stage 7
+///
+/// Relevant verdicts below `min_confidence` are handled like uncertain ones.
+pub fn select(result: &FilterResult, min_confidence: f64, policy: UncertainPolicy) -> Selection {
+    let uncertain = match result.label.as_str() {
+        "relevant" => result.confidence < min_confidence,
+        "uncertain" => true,
+        _ => return Selection::Exclude,
+    };
+    if !uncertain {
+        return Selection::Include;
+    }
+    match policy {
+        UncertainPolicy::Keep => Selection::Include,
+        UncertainPolicy::Drop => Selection::Exclude,
+        UncertainPolicy::Review => Selection::Review,
+    }
+}
+
+/// Verdicts looked up by paper: canonical DOI, else normalized title
+///
+/// The title is only used when the paper or the verdict has no DOI; two
+/// different DOIs are never joined.
+#[derive(Debug, Default)]
+pub struct VerdictIndex<'a> {
+    by_doi: HashMap<String, &'a FilterResult>,
+    by_title: HashMap<String, &'a FilterResult>,
+}
+
+impl<'a> VerdictIndex<'a> {
+    pub fn new(verdicts: &'a [FilterResult]) -> Self {
+        let mut index = Self::default();
+        for verdict in verdicts {
+            let doi = doi::normalize(&verdict.id);
+            if !doi.is_empty() {
+                index.by_doi.entry(doi).or_insert(verdict);
+            }
+            let title = matching::title_key(&verdict.title);
+            if !title.is_empty() {
+                index.by_title.entry(title).or_insert(verdict);
+            }
+        }
+        index
+    }
+
+    /// Verdict of a paper, if it was screened
+    pub fn get(&self, paper: &Paper) -> Option<&'a FilterResult> {
+        let doi = doi::normalize(&paper.doi);
+        if let Some(&verdict) = self.by_doi.get(&doi) {
+            return Some(verdict);
+        }
+        let &verdict = self.by_title.get(&matching::title_key(&paper.title))?;
+        (doi.is_empty() || doi::normalize(&verdict.id).is_empty()).then_some(verdict)
+    }
+}
+
+/// Open a Stage 6 checkpoint file (`6_llm_checkpoint.jsonl`)
+pub fn open_checkpoint(path: &std::path::Path) -> Result<JsonlCheckpoint<FilterResult>> {
+    JsonlCheckpoint::open(path, |r: &FilterResult| FilterResult::checkpoint_key(&r.id, &r.title))
+}
+
 /// Token usage tracking
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub prompt_tokens: u64,
     pub completion_tokens: u64,
     pub total_tokens: u64,
+    /// Hidden reasoning tokens (already included in `completion_tokens`)
+    pub reasoning_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Add another usage to this one
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+    }
+}
+
+/// Per-paper LLM request statistics (one row of `6_llm_details.csv`)
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmCallDetail {
+    pub id: String,
+    pub title: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub reasoning_tokens: u64,
+    /// Wall time from the first attempt to the final response, including retries
+    pub latency_ms: u64,
+    /// HTTP attempts made (retries and format downgrades included)
+    pub attempts: u32,
+    /// "ok" or the error that made the paper fall back to uncertain
+    pub status: String,
+}
+
+/// Statistics recorded by [`LlmSession::complete_json`] for one call
+#[derive(Debug, Clone)]
+struct CallRecord {
+    idx: usize,
+    usage: TokenUsage,
+    latency: Duration,
+    attempts: u32,
+    cached: bool,
+    error: Option<String>,
 }
 
 /// Accumulated token usage with atomic counters
@@ -54,6 +437,7 @@ struct AtomicTokenUsage {
     prompt_tokens: AtomicU64,
     completion_tokens: AtomicU64,
     total_tokens: AtomicU64,
+    reasoning_tokens: AtomicU64,
 }
 
 impl AtomicTokenUsage {
@@ -62,6 +446,7 @@ impl AtomicTokenUsage {
             prompt_tokens: AtomicU64::new(0),
             completion_tokens: AtomicU64::new(0),
             total_tokens: AtomicU64::new(0),
+            reasoning_tokens: AtomicU64::new(0),
         }
     }
 
@@ -69,6 +454,7 @@ impl AtomicTokenUsage {
         self.prompt_tokens.fetch_add(usage.prompt_tokens, Ordering::Relaxed);
         self.completion_tokens.fetch_add(usage.completion_tokens, Ordering::Relaxed);
         self.total_tokens.fetch_add(usage.total_tokens, Ordering::Relaxed);
+        self.reasoning_tokens.fetch_add(usage.reasoning_tokens, Ordering::Relaxed);
     }
 
     fn get(&self) -> TokenUsage {
@@ -76,6 +462,7 @@ impl AtomicTokenUsage {
             prompt_tokens: self.prompt_tokens.load(Ordering::Relaxed),
             completion_tokens: self.completion_tokens.load(Ordering::Relaxed),
             total_tokens: self.total_tokens.load(Ordering::Relaxed),
+            reasoning_tokens: self.reasoning_tokens.load(Ordering::Relaxed),
         }
     }
 }
@@ -94,38 +481,392 @@ struct ChatChoice {
 
 #[derive(Debug, Deserialize)]
 struct ChatMessage {
-    content: String,
+    #[serde(default)]
+    content: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ApiUsage {
+    #[serde(default)]
     prompt_tokens: u64,
+    #[serde(default)]
     completion_tokens: u64,
+    #[serde(default)]
     total_tokens: u64,
+    completion_tokens_details: Option<CompletionTokensDetails>,
 }
 
-/// Paper data for LLM input (subset of UnifiedResult)
+#[derive(Debug, Deserialize)]
+struct CompletionTokensDetails {
+    reasoning_tokens: Option<u64>,
+}
+
+/// Ollama `/api/chat` response (non-streaming); token counts may be absent
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: Option<ChatMessage>,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+impl OllamaChatResponse {
+    fn into_parts(self) -> (String, TokenUsage) {
+        let prompt_tokens = self.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = self.eval_count.unwrap_or(0);
+        let usage = TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            reasoning_tokens: 0,
+        };
+        let content = self.message.and_then(|m| m.content).unwrap_or_default();
+        (content, usage)
+    }
+}
+
+impl ChatCompletionResponse {
+    fn into_parts(self) -> (String, TokenUsage) {
+        let usage = self
+            .usage
+            .map(|u| TokenUsage {
+                prompt_tokens: u.prompt_tokens,
+                completion_tokens: u.completion_tokens,
+                total_tokens: u.total_tokens,
+                reasoning_tokens: u.completion_tokens_details.and_then(|d| d.reasoning_tokens).unwrap_or(0),
+            })
+            .unwrap_or_default();
+        let content = self
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .unwrap_or_default();
+        (content, usage)
+    }
+}
+
+/// Paper data for LLM input (subset of Paper)
 #[derive(Debug, Serialize)]
 struct PaperForLlm {
     id: String,
     title: String,
     abstract_text: String,
     tldr: String,
+    /// Start of the full text, for papers whose abstract is missing or short
+    #[serde(skip_serializing_if = "String::is_empty")]
+    fulltext_excerpt: String,
     journal: String,
     author: String,
     date: String,
 }
 
-impl From<&UnifiedResult> for PaperForLlm {
-    fn from(r: &UnifiedResult) -> Self {
+impl From<&Paper> for PaperForLlm {
+    fn from(r: &Paper) -> Self {
         Self {
             id: r.doi.clone(),
             title: r.title.clone(),
             abstract_text: r.abstract_text.clone(),
             tldr: r.tldr.clone(),
+            fulltext_excerpt: r.fulltext.clone(),
             journal: r.journal.clone(),
-            author: r.author.clone(),
-            date: r.date.clone(),
+            author: r.author_list(),
+            date: r.date_string(),
+        }
+    }
+}
+
+/// Shared state for a batch of LLM requests
+///
+/// Holds the HTTP client, concurrency limit, structured-output negotiation
+/// state, and accumulated token usage. Used by relevance filtering and the
+/// other LLM-backed stages.
+pub struct LlmSession {
+    client: reqwest::Client,
+    config: LlmConfig,
+    semaphore: Semaphore,
+    format_state: ResponseFormatState,
+    token_usage: AtomicTokenUsage,
+    calls: Mutex<Vec<CallRecord>>,
+    cache: Option<HttpCache>,
+}
+
+impl LlmSession {
+    /// Create a new session for the given configuration
+    pub fn new(config: &LlmConfig) -> Result<Self> {
+        Ok(Self {
+            client: http::client_for(Upstream::Llm),
+            config: config.clone(),
+            semaphore: Semaphore::new(config.concurrency.max(1)),
+            format_state: ResponseFormatState::new(config.json_mode),
+            token_usage: AtomicTokenUsage::new(),
+            calls: Mutex::new(Vec::new()),
+            cache: config.cache_dir.clone().map(|dir| HttpCache::open(dir, config.cache_ttl)),
+        })
+    }
+
+    /// The session's configuration
+    pub fn config(&self) -> &LlmConfig {
+        &self.config
+    }
+
+    /// Total token usage so far
+    pub fn usage(&self) -> TokenUsage {
+        self.token_usage.get()
+    }
+
+    /// Per-call statistics so far, in input order
+    ///
+    /// `idx` passed to [`complete_json`](Self::complete_json) is used to look
+    /// up the paper in `papers`.
+    pub fn call_details(&self, papers: &[&Paper]) -> Vec<LlmCallDetail> {
+        let mut calls = self.calls.lock().map(|c| c.clone()).unwrap_or_default();
+        calls.sort_by_key(|c| c.idx);
+        calls
+            .into_iter()
+            .filter_map(|c| {
+                let paper = papers.get(c.idx)?;
+                Some(LlmCallDetail {
+                    id: paper.doi.clone(),
+                    title: paper.title.clone(),
+                    model: self.config.model.clone(),
+                    prompt_tokens: c.usage.prompt_tokens,
+                    completion_tokens: c.usage.completion_tokens,
+                    total_tokens: c.usage.total_tokens,
+                    reasoning_tokens: c.usage.reasoning_tokens,
+                    latency_ms: c.latency.as_millis() as u64,
+                    attempts: c.attempts,
+                    status: match (c.error, c.cached) {
+                        (Some(error), _) => error,
+                        (None, true) => "cached".to_string(),
+                        (None, false) => "ok".to_string(),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Send one chat completion expecting a JSON reply, read by `parse`
+    ///
+    /// Retries transient failures, negotiates `response_format` with the
+    /// provider, and adds the call's token usage to the session total.
+    /// Returns the parsed reply and this call's usage (zero when the reply
+    /// came from the cache). Only replies that parse are cached, so a
+    /// malformed or truncated one is requested again on the next run.
+    pub async fn complete_json<T>(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        schema_name: &str,
+        schema: &serde_json::Value,
+        idx: usize,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<(T, TokenUsage)> {
+        let cache_key = cache_key(&self.config.model, system_prompt, user_prompt, schema);
+        let cached = match &self.cache {
+            Some(cache) => cache.load(&cache_key).await,
+            None => None,
+        };
+        if let Some(content) = cached {
+            match parse(&content) {
+                Ok(reply) => {
+                    debug!(idx = idx, "LLM cache hit");
+                    self.record(CallRecord {
+                        idx,
+                        usage: TokenUsage::default(),
+                        latency: Duration::ZERO,
+                        attempts: 0,
+                        cached: true,
+                        error: None,
+                    });
+                    return Ok((reply, TokenUsage::default()));
+                }
+                Err(e) => debug!(idx = idx, error = %e, "Cached LLM reply does not parse, requesting again"),
+            }
+        }
+
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|e| GscholarError::Config(format!("LLM semaphore closed: {}", e)))?;
+
+        let started = Instant::now();
+        let mut attempts = 0;
+        let sent = self
+            .send_json(system_prompt, user_prompt, schema_name, schema, idx, &mut attempts)
+            .await;
+        let usage = sent.as_ref().map(|(_, u)| u.clone()).unwrap_or_default();
+        let result = sent.and_then(|(content, _)| parse(&content).map(|reply| (reply, content)));
+
+        self.record(CallRecord {
+            idx,
+            usage: usage.clone(),
+            latency: started.elapsed(),
+            attempts,
+            cached: false,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+
+        let (reply, content) = result?;
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.store(&cache_key, content).await {
+                warn!(error = %e, "Failed to write LLM cache");
+            }
+        }
+        Ok((reply, usage))
+    }
+
+    fn record(&self, record: CallRecord) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(record);
+        }
+    }
+
+    /// Request loop behind [`complete_json`](Self::complete_json), counting HTTP attempts
+    async fn send_json(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        schema_name: &str,
+        schema: &serde_json::Value,
+        idx: usize,
+        attempts: &mut u32,
+    ) -> Result<(String, TokenUsage)> {
+        let config = &self.config;
+        let api_url = config.provider.endpoint(&config.base_url);
+        let endpoint = url::Url::parse(&api_url).map_err(|e| GscholarError::Config(format!("Invalid LLM URL '{}': {}", api_url, e)))?;
+
+        // Backoff and timeout of the llm retry policy; retries are counted against the config
+        let policy = http::retry_policy(Upstream::Llm);
+        let mut attempt: u32 = 0;
+
+        let response = loop {
+            let format_level = self.format_state.current();
+
+            let request_body = self.request_body(system_prompt, user_prompt, format_level, schema_name, schema);
+
+            debug!(idx = idx, format_level = format_level, "Sending LLM request");
+            *attempts += 1;
+
+            let mut request = self
+                .client
+                .post(endpoint.clone())
+                .timeout(config.timeout.or(policy.timeout).unwrap_or_else(|| config.provider.default_timeout()))
+                .header("Content-Type", "application/json");
+            if !config.api_key.is_empty() {
+                request = request.header("Authorization", format!("Bearer {}", config.api_key));
+            }
+            // The slot is released before any backoff below
+            ratelimit::acquire(&endpoint).await;
+            let slot = throttle::acquire(Upstream::Llm).await;
+            let result = request.json(&request_body).send().await;
+            drop(slot);
+
+            let response = match result {
+                Ok(response) => response,
+                Err(e) if attempt < config.max_retries => {
+                    warn!(idx = idx, attempt = attempt + 1, error = %e, "LLM request failed, retrying");
+                    tokio::time::sleep(policy.delay(attempt, None)).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(GscholarError::Network(e)),
+            };
+
+            ratelimit::record(&endpoint, response.status(), parse_retry_after(response.headers()));
+            if response.status().is_success() {
+                break response;
+            }
+
+            let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
+            let error_text = response.text().await.unwrap_or_default();
+
+            // Unsupported response_format is reported as a client error naming it
+            if matches!(status.as_u16(), 400 | 422)
+                && ResponseFormatState::rejected(&error_text)
+                && self.format_state.downgrade(format_level)
+            {
+                continue;
+            }
+
+            let transient = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if transient && attempt < config.max_retries {
+                let wait = policy.delay(attempt, retry_after);
+                warn!(
+                    idx = idx,
+                    status = status.as_u16(),
+                    attempt = attempt + 1,
+                    wait_secs = wait.as_secs_f64(),
+                    "Transient LLM API error, retrying"
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+                continue;
+            }
+
+            return Err(GscholarError::Api {
+                code: status.as_u16() as i32,
+                message: format!("LLM API error: {} - {}", status, error_text),
+            });
+        };
+
+        let body = response.text().await?;
+        raw_archive::record(endpoint.as_str(), BodyKind::Json, &body);
+        let parse_error = |e: serde_json::Error| GscholarError::Parse(format!("Failed to parse LLM response: {}", e));
+        let (content, usage) = match config.provider {
+            LlmProvider::OpenAi => serde_json::from_str::<ChatCompletionResponse>(&body).map_err(parse_error)?.into_parts(),
+            LlmProvider::Ollama => serde_json::from_str::<OllamaChatResponse>(&body).map_err(parse_error)?.into_parts(),
+        };
+        self.token_usage.add(&usage);
+
+        Ok((content, usage))
+    }
+
+    /// Build the provider-specific chat request body
+    fn request_body(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        format_level: u8,
+        schema_name: &str,
+        schema: &serde_json::Value,
+    ) -> serde_json::Value {
+        let messages = serde_json::json!([
+            {"role": "system", "content": system_prompt},
+            {"role": "user", "content": user_prompt}
+        ]);
+
+        match self.config.provider {
+            LlmProvider::OpenAi => {
+                let mut body = serde_json::json!({
+                    "model": self.config.model,
+                    "messages": messages,
+                    "max_tokens": self.config.max_tokens
+                });
+                if let Some(temperature) = self.config.temperature {
+                    body["temperature"] = serde_json::json!(temperature);
+                }
+                if let Some(format) = ResponseFormatState::body(format_level, schema_name, schema) {
+                    body["response_format"] = format;
+                }
+                body
+            }
+            LlmProvider::Ollama => {
+                let mut body = serde_json::json!({
+                    "model": self.config.model,
+                    "messages": messages,
+                    "stream": false,
+                    "options": {"num_predict": self.config.max_tokens}
+                });
+                if let Some(temperature) = self.config.temperature {
+                    body["options"]["temperature"] = serde_json::json!(temperature);
+                }
+                if let Some(format) = ResponseFormatState::ollama_format(format_level, schema) {
+                    body["format"] = format;
+                }
+                body
+            }
         }
     }
 }
@@ -133,13 +874,18 @@ impl From<&UnifiedResult> for PaperForLlm {
 /// Filter papers using LLM with concurrent requests.
 ///
 /// Each paper is sent as a separate API request for maximum parallelism.
-/// Results are collected and returned with total token usage.
+/// Results are collected and returned with total token usage and
+/// per-paper request statistics.
+///
+/// With a checkpoint, papers already in it are skipped and every new
+/// result is appended as soon as it completes.
 pub async fn filter_papers(
     config: &LlmConfig,
-    papers: &[UnifiedResult],
-) -> Result<(Vec<FilterResult>, TokenUsage)> {
+    papers: &[Paper],
+    checkpoint: Option<&JsonlCheckpoint<FilterResult>>,
+) -> Result<(Vec<FilterResult>, TokenUsage, Vec<LlmCallDetail>)> {
     if papers.is_empty() {
-        return Ok((Vec::new(), TokenUsage::default()));
+        return Ok((Vec::new(), TokenUsage::default(), Vec::new()));
     }
 
     info!(
@@ -148,58 +894,11 @@ pub async fn filter_papers(
         "Starting LLM relevance filtering"
     );
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .build()
-        .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
-
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
-    let token_usage = Arc::new(AtomicTokenUsage::new());
-    let client = Arc::new(client);
-    let config = Arc::new(config.clone());
-
-    // Process papers concurrently
-    let results: Vec<FilterResult> = stream::iter(papers.iter().enumerate())
-        .map(|(idx, paper)| {
-            let semaphore = Arc::clone(&semaphore);
-            let token_usage = Arc::clone(&token_usage);
-            let client = Arc::clone(&client);
-            let config = Arc::clone(&config);
-
-            async move {
-                let _permit = semaphore.acquire().await.ok()?;
-                
-                match filter_single_paper(&client, &config, paper, idx).await {
-                    Ok((result, usage)) => {
-                        token_usage.add(&usage);
-                        Some(result)
-                    }
-                    Err(e) => {
-                        warn!(
-                            idx = idx,
-                            title = %paper.title.chars().take(50).collect::<String>(),
-                            error = %e,
-                            "Failed to filter paper"
-                        );
-                        // Return uncertain for failed requests
-                        Some(FilterResult {
-                            id: paper.doi.clone(),
-                            title: paper.title.clone(),
-                            label: "uncertain".to_string(),
-                            confidence: 0.0,
-                            evidence: String::new(),
-                            reason: format!("API error: {}", e),
-                        })
-                    }
-                }
-            }
-        })
-        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
-        .filter_map(|r| async { r })
-        .collect()
-        .await;
+    let papers: Vec<&Paper> = papers.iter().collect();
+    let session = LlmSession::new(config)?;
+    let results = filter_with_session(&session, &papers, checkpoint).await;
 
-    let final_usage = token_usage.get();
+    let final_usage = session.usage();
     info!(
         filtered = results.len(),
         prompt_tokens = final_usage.prompt_tokens,
@@ -207,83 +906,222 @@ pub async fn filter_papers(
         "LLM filtering complete"
     );
 
-    Ok((results, final_usage))
+    Ok((results, final_usage, session.call_details(&papers)))
+}
+
+/// Both verdicts of a two-pass run (one row of `6_llm_triage.csv`)
+#[derive(Debug, Clone, Serialize)]
+pub struct TriageRecord {
+    pub id: String,
+    pub title: String,
+    pub triage_label: String,
+    pub triage_confidence: f64,
+    pub escalated: bool,
+    /// Verdict of the review model, empty when not escalated
+    pub review_label: String,
+    pub review_confidence: Option<f64>,
+}
+
+/// Whether a triage verdict should be re-screened by the review model
+pub fn needs_review(result: &FilterResult, min_confidence: f64) -> bool {
+    result.label == "uncertain" || result.confidence < min_confidence
 }
 
-/// Filter a single paper via LLM API
-async fn filter_single_paper(
-    client: &reqwest::Client,
+/// Two-pass filtering: a cheap triage model screens every paper, then
+/// `review_model` re-screens uncertain or low-confidence verdicts.
+///
+/// The review verdict replaces the triage verdict for escalated papers;
+/// both are returned as [`TriageRecord`]s.
+pub async fn filter_papers_two_pass(
     config: &LlmConfig,
-    paper: &UnifiedResult,
-    idx: usize,
-) -> Result<(FilterResult, TokenUsage)> {
-    let paper_data = PaperForLlm::from(paper);
-    let paper_json = serde_json::to_string_pretty(&paper_data)
-        .map_err(|e| GscholarError::Parse(format!("Failed to serialize paper: {}", e)))?;
-
-    let user_prompt = build_user_prompt(&config.filter_help, &paper_json);
-
-    // Build OpenAI-compatible request
-    let request_body = serde_json::json!({
-        "model": config.model,
-        "messages": [
-            {"role": "system", "content": SYSTEM_PROMPT},
-            {"role": "user", "content": user_prompt}
-        ],
-        "temperature": 0.1,
-        "max_tokens": 20000
-    });
-
-    let api_url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
-
-    debug!(idx = idx, "Sending LLM request");
-
-    let response = client
-        .post(&api_url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(GscholarError::Network)?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(GscholarError::Api {
-            code: status.as_u16() as i32,
-            message: format!("LLM API error: {} - {}", status, error_text),
-        });
+    review_model: &str,
+    min_confidence: f64,
+    papers: &[Paper],
+    checkpoint: Option<&JsonlCheckpoint<FilterResult>>,
+) -> Result<(Vec<FilterResult>, TokenUsage, Vec<LlmCallDetail>, Vec<TriageRecord>)> {
+    let (mut results, mut usage, mut details) = filter_papers(config, papers, checkpoint).await?;
+
+    let escalated: Vec<usize> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| needs_review(r, min_confidence))
+        .map(|(i, _)| i)
+        .collect();
+
+    info!(
+        escalated = escalated.len(),
+        total = papers.len(),
+        model = %review_model,
+        "Re-screening borderline papers with review model"
+    );
+
+    let review_config = LlmConfig {
+        model: review_model.to_string(),
+        ..config.clone()
+    };
+    let review_papers: Vec<&Paper> = escalated.iter().map(|&i| &papers[i]).collect();
+    let session = LlmSession::new(&review_config)?;
+    let reviews = filter_with_session(&session, &review_papers, None).await;
+
+    let mut records: Vec<TriageRecord> = results
+        .iter()
+        .map(|r| TriageRecord {
+            id: r.id.clone(),
+            title: r.title.clone(),
+            triage_label: r.label.clone(),
+            triage_confidence: r.confidence,
+            escalated: false,
+            review_label: String::new(),
+            review_confidence: None,
+        })
+        .collect();
+    for (&i, review) in escalated.iter().zip(reviews) {
+        records[i].escalated = true;
+        records[i].review_label = review.label.clone();
+        records[i].review_confidence = Some(review.confidence);
+        results[i] = review;
     }
 
-    let api_response: ChatCompletionResponse = response
-        .json()
-        .await
-        .map_err(|e| GscholarError::Parse(format!("Failed to parse LLM response: {}", e)))?;
+    let review_usage = session.usage();
+    usage.add(&review_usage);
+    details.extend(session.call_details(&review_papers));
 
-    // Extract usage
-    let usage = api_response.usage.map(|u| TokenUsage {
-        prompt_tokens: u.prompt_tokens,
-        completion_tokens: u.completion_tokens,
-        total_tokens: u.total_tokens,
-    }).unwrap_or_default();
+    Ok((results, usage, details, records))
+}
 
-    // Parse LLM output
-    let content = api_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .unwrap_or_default();
+/// Filter papers on an existing session, returning results in input order
+async fn filter_with_session(
+    session: &LlmSession,
+    papers: &[&Paper],
+    checkpoint: Option<&JsonlCheckpoint<FilterResult>>,
+) -> Vec<FilterResult> {
+    let schema = filter_result_schema();
 
-    let result = parse_llm_response(&content, &paper.doi, &paper.title)?;
+    if let Some(checkpoint) = checkpoint.filter(|c| !c.is_empty()) {
+        info!(resumed = checkpoint.len(), path = ?checkpoint.path(), "Resuming from checkpoint");
+    }
 
-    debug!(
-        idx = idx,
-        label = %result.label,
-        "Paper classified"
-    );
+    // Unparsable replies were once checkpointed as "Parse error" verdicts; screen those again
+    let mut results: Vec<Option<FilterResult>> = papers
+        .iter()
+        .map(|paper| checkpoint.and_then(|c| c.get(&FilterResult::checkpoint_key(&paper.doi, &paper.title))))
+        .map(|done| done.filter(|r| !r.reason.starts_with("Parse error:")))
+        .collect();
+    let pending: Vec<(usize, &Paper)> =
+        papers.iter().copied().enumerate().filter(|(idx, _)| results[*idx].is_none()).collect();
+
+    let replies = complete_each(
+        session,
+        SYSTEM_PROMPT,
+        "filter_result",
+        &schema,
+        pending.iter().copied(),
+        |paper| Ok(build_user_prompt(&session.config().filter_help, &paper_to_json(paper)?)),
+        |content, paper| parse_llm_response(content, &paper.doi, &paper.title),
+    )
+    .await;
+
+    for ((idx, paper), reply) in pending.into_iter().zip(replies) {
+        let result = match reply {
+            Ok(result) => {
+                debug!(idx = idx, label = %result.label, "Paper classified");
+                if let Some(checkpoint) = checkpoint {
+                    if let Err(e) = checkpoint.append(&result) {
+                        warn!(idx = idx, error = %e, "Failed to write checkpoint");
+                    }
+                }
+                result
+            }
+            Err(e) => {
+                warn!(
+                    idx = idx,
+                    title = %paper.title.chars().take(50).collect::<String>(),
+                    error = %e,
+                    "Failed to filter paper"
+                );
+                // Return uncertain for failed requests and unparsable replies
+                // (not checkpointed, so a resume retries them)
+                FilterResult {
+                    id: paper.doi.clone(),
+                    title: paper.title.clone(),
+                    label: "uncertain".to_string(),
+                    confidence: 0.0,
+                    evidence: String::new(),
+                    reason: format!("LLM error: {}", e),
+                }
+            }
+        };
+        results[idx] = Some(result);
+    }
+    results.into_iter().flatten().collect()
+}
+
+/// Send one structured-output request per paper, `concurrency` at a time,
+/// and return the parsed replies in the order of `papers`
+///
+/// Each paper comes with its index in the list later given to
+/// [`LlmSession::call_details`]. `prompt` builds the user prompt of a
+/// paper and `parse` reads the reply; a failed request or reply is
+/// returned as an error for the caller to turn into its own fallback.
+pub async fn complete_each<'a, T, B, F>(
+    session: &LlmSession,
+    system_prompt: &str,
+    schema_name: &str,
+    schema: &serde_json::Value,
+    papers: impl IntoIterator<Item = (usize, &'a Paper)>,
+    prompt: B,
+    parse: F,
+) -> Vec<Result<T>>
+where
+    B: Fn(&Paper) -> Result<String> + Sync,
+    F: Fn(&str, &Paper) -> Result<T> + Sync,
+{
+    let (prompt, parse) = (&prompt, &parse);
+    // Build the requests up front so the returned future stays `Send`
+    let requests: Vec<_> = papers
+        .into_iter()
+        .map(|(idx, paper)| async move {
+            let user_prompt = prompt(paper)?;
+            let (reply, _usage) = session
+                .complete_json(system_prompt, &user_prompt, schema_name, schema, idx, |content| parse(content, paper))
+                .await?;
+            Ok(reply)
+        })
+        .collect();
+    stream::in_order(requests, session.config().concurrency).await
+}
+
+/// Serialize the LLM-facing fields of a paper as pretty JSON
+pub fn paper_to_json(paper: &Paper) -> Result<String> {
+    serde_json::to_string_pretty(&PaperForLlm::from(paper))
+        .map_err(|e| GscholarError::Parse(format!("Failed to serialize paper: {}", e)))
+}
+
+/// Cache key for a reply: model plus a SHA-256 of everything sent
+///
+/// The user prompt embeds the paper (DOI, title, abstract), so a changed
+/// prompt, schema, or paper record produces a new key.
+fn cache_key(model: &str, system_prompt: &str, user_prompt: &str, schema: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    for part in [system_prompt, user_prompt, &schema.to_string()] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    let digest: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", model, digest)
+}
 
-    Ok((result, usage))
+/// Parse a `Retry-After` header (delay in seconds or an HTTP date)
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.signed_duration_since(chrono::Utc::now());
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
 }
 
 /// Parse LLM JSON response into FilterResult
@@ -299,39 +1137,26 @@ fn parse_llm_response(content: &str, id: &str, title: &str) -> Result<FilterResu
         reason: String,
     }
 
-    match serde_json::from_str::<LlmOutput>(&json_str) {
-        Ok(output) => Ok(FilterResult {
-            id: id.to_string(),
-            title: title.to_string(),
-            label: output.label,
-            confidence: output.confidence,
-            evidence: output.evidence.join(", "),
-            reason: output.reason,
-        }),
-        Err(e) => {
-            // Log truncated content for debugging (first 200 chars)
-            let preview: String = content.chars().take(200).collect();
-            info!(
-                error = %e,
-                content_preview = %preview,
-                "LLM output parse failed - treating as uncertain"
-            );
-            // Return uncertain for parse failures
-            Ok(FilterResult {
-                id: id.to_string(),
-                title: title.to_string(),
-                label: "uncertain".to_string(),
-                confidence: 0.0,
-                evidence: String::new(),
-                reason: format!("Parse error: {}", e),
-            })
-        }
-    }
+    let output = serde_json::from_str::<LlmOutput>(&json_str).map_err(|e| {
+        // Log truncated content for debugging (first 200 chars)
+        let preview: String = content.chars().take(200).collect();
+        info!(error = %e, content_preview = %preview, "LLM output parse failed");
+        GscholarError::Parse(format!("Failed to parse LLM verdict: {}", e))
+    })?;
+    Ok(FilterResult {
+        id: id.to_string(),
+        title: title.to_string(),
+        label: output.label,
+        confidence: output.confidence,
+        evidence: output.evidence.join(", "),
+        reason: output.reason,
+    })
 }
 
 /// Extract JSON from LLM response (handles markdown code blocks)
-fn extract_json(content: &str) -> String {
-    let trimmed = content.trim();
+pub fn extract_json(content: &str) -> String {
+    let without_thinking = strip_thinking(content);
+    let trimmed = without_thinking.trim();
     
     // Check for markdown code block
     if trimmed.starts_with("```") {
@@ -357,10 +1182,57 @@ fn extract_json(content: &str) -> String {
     trimmed.to_string()
 }
 
+/// Remove `<think>...</think>` reasoning blocks from a model reply
+///
+/// An unterminated block (reply cut off mid-reasoning) removes everything
+/// after `<think>`.
+fn strip_thinking(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("<think>") {
+        result.push_str(&rest[..start]);
+        match rest[start..].find("</think>") {
+            Some(end) => rest = &rest[start + end + "</think>".len()..],
+            None => return result,
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn verdict(label: &str, confidence: f64) -> Result<FilterResult> {
+        parse_llm_response(
+            &format!(r#"{{"label": "{}", "confidence": {}, "evidence": [], "reason": ""}}"#, label, confidence),
+            "10.1/x",
+            "X",
+        )
+    }
+
+    fn test_config() -> LlmConfig {
+        LlmConfig {
+            provider: LlmProvider::OpenAi,
+            // Discard port: any real request would fail
+            base_url: "http://127.0.0.1:9".to_string(),
+            api_key: String::new(),
+            model: "test".to_string(),
+            filter_help: String::new(),
+            json_mode: JsonMode::Off,
+            max_retries: 0,
+            cache_dir: None,
+            cache_ttl: Duration::ZERO,
+            concurrency: DEFAULT_CONCURRENT_REQUESTS,
+            timeout: None,
+            temperature: Some(DEFAULT_TEMPERATURE),
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
     #[test]
     fn test_extract_json_plain() {
         let input = r#"{"label": "relevant", "confidence": 0.9, "evidence": [], "reason": "test"}"#;
@@ -385,13 +1257,245 @@ mod tests {
         assert!(result.ends_with('}'));
     }
 
+    #[test]
+    fn test_extract_json_strips_thinking() {
+        let input = "<think>The paper mentions {slope} stability...</think>\n{\"label\": \"relevant\"}";
+        assert_eq!(extract_json(input), r#"{"label": "relevant"}"#);
+        assert_eq!(strip_thinking("a<think>x</think>b<think>y"), "ab");
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_response_format_downgrade() {
+        let state = ResponseFormatState::new(JsonMode::Auto);
+        assert_eq!(state.current(), FORMAT_SCHEMA);
+        assert!(state.downgrade(FORMAT_SCHEMA));
+        assert_eq!(state.current(), FORMAT_OBJECT);
+        assert!(state.downgrade(FORMAT_OBJECT));
+        assert!(ResponseFormatState::body(state.current(), "filter_result", &filter_result_schema()).is_none());
+        assert!(!state.downgrade(FORMAT_NONE));
+
+        let fixed = ResponseFormatState::new(JsonMode::Object);
+        assert!(!fixed.downgrade(FORMAT_OBJECT));
+        assert_eq!(fixed.current(), FORMAT_OBJECT);
+
+        // A request that lost the race still retries, at the level already lowered
+        let raced = ResponseFormatState::new(JsonMode::Auto);
+        assert!(raced.downgrade(FORMAT_SCHEMA) && raced.downgrade(FORMAT_SCHEMA));
+        assert_eq!(raced.current(), FORMAT_OBJECT);
+
+        assert!(ResponseFormatState::rejected(r#"{"error": {"message": "Invalid parameter: 'response_format' of type 'json_schema' is not supported"}}"#));
+        assert!(!ResponseFormatState::rejected(r#"{"error": {"code": "context_length_exceeded"}}"#));
+        assert!(!ResponseFormatState::rejected("model 'qwen9' not found"));
+    }
+
     #[test]
     fn test_parse_llm_response() {
         let content = r#"{"label": "relevant", "confidence": 0.95, "evidence": ["landslide", "slope"], "reason": "Explicitly involves landslide research"}"#;
-        let result = parse_llm_response(content, "10.1234/test", "Test Paper").unwrap();
+        let result = parse_llm_response(content, "10.1234/test", "Test Paper").expect("Parse failed");
         assert_eq!(result.label, "relevant");
         assert_eq!(result.confidence, 0.95);
         assert!(result.evidence.contains("landslide"));
         assert!(result.evidence.contains("slope"));
+        assert!(parse_llm_response(r#"{"label": "relevant", "confid"#, "10.1234/test", "Test Paper").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cached_reply_skips_request() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let config = LlmConfig {
+            cache_dir: Some(dir.path().join(crate::cache::LLM_CACHE_DIR)),
+            cache_ttl: Duration::from_secs(3600),
+            ..test_config()
+        };
+        let schema = filter_result_schema();
+        let key = cache_key("test", "system", "user", &schema);
+        assert_ne!(key, cache_key("other", "system", "user", &schema));
+
+        let session = LlmSession::new(&config)?;
+        if let Some(cache) = &session.cache {
+            cache.insert(&key, "{}")?;
+        }
+        let (content, usage) =
+            session.complete_json("system", "user", "filter_result", &schema, 0, |c| Ok(c.to_string())).await?;
+        assert_eq!(content, "{}");
+        assert_eq!(usage.total_tokens, 0);
+
+        // A cached reply that no longer parses is requested again (and fails against the discard port)
+        let reply = session.complete_json("system", "user", "filter_result", &schema, 0, |c| parse_llm_response(c, "", "")).await;
+        assert!(reply.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_complete_each_in_order() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let config = LlmConfig {
+            cache_dir: Some(dir.path().join(crate::cache::LLM_CACHE_DIR)),
+            cache_ttl: Duration::from_secs(3600),
+            ..test_config()
+        };
+        let schema = filter_result_schema();
+        let session = LlmSession::new(&config)?;
+        // Only the second paper has a reply; the first request fails against the discard port
+        if let Some(cache) = &session.cache {
+            cache.insert(&cache_key("test", "system", "B", &schema), r#"{"n": 2}"#)?;
+        }
+        let papers: Vec<Paper> =
+            ["A", "B"].iter().map(|title| Paper { title: title.to_string(), ..Default::default() }).collect();
+        let replies = complete_each(
+            &session,
+            "system",
+            "filter_result",
+            &schema,
+            papers.iter().enumerate(),
+            |paper| Ok(paper.title.clone()),
+            |content, paper| Ok(format!("{}: {}", paper.title, content)),
+        )
+        .await;
+        assert_eq!(replies.len(), 2);
+        assert!(replies[0].is_err());
+        assert_eq!(replies[1].as_deref().ok(), Some(r#"B: {"n": 2}"#));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_reply_not_checkpointed() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let config = LlmConfig {
+            cache_dir: Some(dir.path().join(crate::cache::LLM_CACHE_DIR)),
+            cache_ttl: Duration::from_secs(3600),
+            ..test_config()
+        };
+        let schema = filter_result_schema();
+        let session = LlmSession::new(&config)?;
+        let paper = |doi: &str, title: &str| Paper { doi: doi.to_string(), title: title.to_string(), ..Default::default() };
+        let (a, b) = (paper("10.1/a", "A"), paper("https://doi.org/10.1/B", "B"));
+        // A truncated reply for A, a parsed verdict for B
+        for (p, reply) in [(&a, r#"{"label": "relev"#), (&b, r#"{"label": "relevant", "confidence": 0.9, "evidence": [], "reason": ""}"#)] {
+            let user_prompt = build_user_prompt("", &paper_to_json(p)?);
+            if let Some(cache) = &session.cache {
+                cache.insert(&cache_key("test", SYSTEM_PROMPT, &user_prompt, &schema), reply)?;
+            }
+        }
+
+        let path = dir.path().join("6_llm_checkpoint.jsonl");
+        let checkpoint = open_checkpoint(&path)?;
+        let results = filter_with_session(&session, &[&a, &b], Some(&checkpoint)).await;
+        assert_eq!((results[0].label.as_str(), results[1].label.as_str()), ("uncertain", "relevant"));
+
+        let reopened = open_checkpoint(&path)?;
+        assert_eq!(reopened.len(), 1);
+        assert!(reopened.get(&FilterResult::checkpoint_key("doi:10.1/b.", "")).is_some());
+        assert_eq!(FilterResult::checkpoint_key("", " Slope Stability "), "slope stability");
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_policies() -> Result<()> {
+        let drop = UncertainPolicy::default();
+        assert_eq!(select(&verdict("relevant", 0.9)?, 0.7, drop), Selection::Include);
+        assert_eq!(select(&verdict("relevant", 0.5)?, 0.7, drop), Selection::Exclude);
+        assert_eq!(select(&verdict("relevant", 0.5)?, 0.7, "review".parse()?), Selection::Review);
+        assert_eq!(select(&verdict("uncertain", 0.5)?, 0.0, "keep".parse()?), Selection::Include);
+        assert_eq!(select(&verdict("irrelevant", 0.9)?, 0.0, "keep".parse()?), Selection::Exclude);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verdict_index() -> Result<()> {
+        let screened = |id: &str, title: &str| -> Result<FilterResult> {
+            Ok(FilterResult { id: id.to_string(), title: title.to_string(), ..verdict("relevant", 0.9)? })
+        };
+        let paper = |doi: &str, title: &str| Paper { doi: doi.to_string(), title: title.to_string(), ..Default::default() };
+        let verdicts = vec![
+            screened("https://doi.org/10.1/AB", "Slope stability")?,
+            screened("", "Debris flow: a review")?,
+            screened("", "Rockfall hazard")?,
+        ];
+        let index = VerdictIndex::new(&verdicts);
+
+        let found = |p: &Paper| index.get(p).map(|v| v.title.as_str());
+        assert_eq!(found(&paper("10.1/ab", "Other title")), Some("Slope stability"));
+        assert_eq!(found(&paper("", "[PDF] Debris flow - A review")), Some("Debris flow: a review"));
+        assert_eq!(found(&paper("10.1/rock", "Rockfall hazard")), Some("Rockfall hazard"));
+        assert_eq!(found(&paper("", "Slope Stability")), Some("Slope stability"));
+        assert_eq!(found(&paper("10.1/other", "Slope stability")), None);
+        assert_eq!(found(&paper("", "Unscreened")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_needs_review() -> Result<()> {
+        assert!(!needs_review(&verdict("relevant", 0.9)?, 0.7));
+        assert!(needs_review(&verdict("irrelevant", 0.6)?, 0.7));
+        assert!(needs_review(&verdict("uncertain", 0.95)?, 0.7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_details_in_input_order() -> Result<()> {
+        let session = LlmSession::new(&test_config())?;
+        let record = |idx, error: Option<&str>| CallRecord {
+            idx,
+            usage: TokenUsage { prompt_tokens: 10, completion_tokens: 5, total_tokens: 15, reasoning_tokens: 0 },
+            latency: Duration::from_millis(1500),
+            attempts: 2,
+            cached: false,
+            error: error.map(String::from),
+        };
+        if let Ok(mut calls) = session.calls.lock() {
+            calls.push(record(1, Some("timeout")));
+            calls.push(record(0, None));
+        }
+
+        let paper = |doi: &str| Paper {
+            title: format!("Paper {}", doi),
+            doi: doi.to_string(),
+            ..Default::default()
+        };
+        let (a, b) = (paper("10.1/a"), paper("10.1/b"));
+        let details = session.call_details(&[&a, &b]);
+        assert_eq!(details.len(), 2);
+        assert_eq!(details[0].id, "10.1/a");
+        assert_eq!(details[0].status, "ok");
+        assert_eq!(details[0].latency_ms, 1500);
+        assert_eq!(details[1].status, "timeout");
+        Ok(())
+    }
+
+    #[test]
+    fn test_ollama_response_without_usage() -> Result<()> {
+        let response: OllamaChatResponse = serde_json::from_str(r#"{"model": "qwen2.5", "message": {"role": "assistant", "content": "{}"}, "done": true}"#)?;
+        let (content, usage) = response.into_parts();
+        assert_eq!(content, "{}");
+        assert_eq!(usage.total_tokens, 0);
+
+        let response: ChatCompletionResponse = serde_json::from_str(r#"{"choices": [{"message": {"content": null}}]}"#)?;
+        assert_eq!(response.into_parts().0, "");
+
+        let response: ChatCompletionResponse = serde_json::from_str(
+            r#"{"choices": [], "usage": {"prompt_tokens": 10, "completion_tokens": 90, "total_tokens": 100, "completion_tokens_details": {"reasoning_tokens": 80}}}"#,
+        )?;
+        assert_eq!(response.into_parts().1.reasoning_tokens, 80);
+
+        assert_eq!("ollama".parse::<LlmProvider>()?.endpoint("http://localhost:11434/"), "http://localhost:11434/api/chat");
+        Ok(())
     }
 }
@@ -17,19 +17,83 @@
 
 use anyhow::{Context, Result};
 use axum::{
-    extract::State,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Extension, Path as UrlPath, Query, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use chrono::Local;
-use clap::{Parser, Subcommand};
-use rustgscholar::{crossref::CrossrefClient, gscholar, llm_filter, openalex, rankings::RankingClient, semanticscholar, unified};
+use chrono::{Datelike, Local};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use rustgscholar::{
+    abstracts, api, auth, cache, config, diff, doctor, enrich, estimate, export, grobid, gscholar, health, http, incremental, input,
+    jobs, journal_list::JournalList,
+    llm_filter, manifest, matching, merge, network, openalex, orcid, output, pdf,
+    pipeline::{self, PipelineBuilder},
+    prefilter::Prefilter,
+    query_expansion,
+    rank_filter::RankFilter,
+    rankings, review_detection::ReviewPolicy, run_log,
+    scholar_api::{ScholarApiClient, ScholarBackend},
+    screening, secrets, store, study_design, tagging, throttle, topics, unpaywall,
+    venue::CoreRankings,
+    watch, web, work_type, zotero,
+};
 use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
 use std::net::SocketAddr;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tracing::{error, info, Level};
-use tracing_subscriber::{fmt, EnvFilter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tracing::{error, info, warn, Level};
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::{fmt, prelude::*};
+
+/// Set by `search --stdout`: stdout then carries only JSON lines
+static STDOUT_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--quiet` and `--log-format json`: progress is not printed and
+/// logs go to stderr
+static MACHINE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Set when stdout carries a JSON summary of the run at the end
+static SUMMARY_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Known API key values, replaced with `***` in progress and log output
+static REDACTOR: OnceLock<Arc<secrets::Redactor>> = OnceLock::new();
+
+/// `run.log` of the run folder in progress
+static RUN_LOG: OnceLock<run_log::RunLog> = OnceLock::new();
+
+/// Print pipeline progress (to stderr in `--stdout` mode) and log it to `run.log`
+macro_rules! status {
+    ($($arg:tt)*) => {{
+        let line = redact(&format!($($arg)*));
+        info!(target: run_log::TARGET, "{}", line.trim());
+        // --quiet and --log-format json print no plain progress lines
+        if !MACHINE_MODE.load(Ordering::Relaxed) {
+            if STDOUT_MODE.load(Ordering::Relaxed) {
+                eprintln!("{}", line)
+            } else {
+                println!("{}", line)
+            }
+        }
+    }};
+}
+
+fn run_log() -> &'static run_log::RunLog {
+    RUN_LOG.get_or_init(run_log::RunLog::default)
+}
+
+fn redact(text: &str) -> String {
+    match REDACTOR.get() {
+        Some(redactor) => redactor.redact(text),
+        None => text.to_string(),
+    }
+}
 
 // ============================================================================
 // CLI Definition
@@ -38,12 +102,25 @@ use tracing_subscriber::{fmt, EnvFilter};
 /// Google Scholar 3-Stage Literature Pipeline - Rust Microservice
 #[derive(Parser)]
 #[command(name = "rustgscholar")]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_override_self = true)]
 struct Cli {
     /// Enable debug logging
     #[arg(short, long, global = true)]
     debug: bool,
 
+    /// No progress output, only warnings and errors (on stderr); search, enrich and
+    /// filter print a JSON summary of the run on stdout when done
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Console log format: text, or json (JSON lines on stderr, progress included;
+    /// stdout carries only the final JSON summary like --quiet)
+    #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+    log_format: String,
+
+    #[command(flatten)]
+    http: HttpArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -51,103 +128,890 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Search academic literature and run the pipeline
-    Search {
-        /// Search keywords
-        keyword: String,
+    Search(Box<SearchArgs>),
 
-        /// Search source: gscholar or openalex
-        #[arg(long, default_value = "gscholar", value_parser = ["gscholar", "openalex"])]
-        source: String,
+    /// Run Stages 2-8 on an existing title/DOI list instead of searching
+    Enrich(Box<EnrichArgs>),
 
-        /// Page range (e.g., "1", "1-10")
-        #[arg(long, default_value = "1")]
-        pages: String,
+    /// Run only the LLM relevance stage (Stage 6) on an existing CSV
+    Filter(Box<FilterArgs>),
 
-        /// Year filter (results from this year onwards)
-        #[arg(long)]
-        ylo: Option<i32>,
+    /// Re-run a search periodically as incremental updates and notify about new papers
+    Watch(Box<WatchArgs>),
 
-        /// Proxy URL (e.g., http://127.0.0.1:7890)
-        #[arg(long)]
-        proxy: Option<String>,
+    /// Run as HTTP server
+    Serve(ServeArgs),
 
-        /// Mirror site URL
-        #[arg(long)]
-        mirror: Option<String>,
+    /// Manage cookies
+    Cookies {
+        #[command(subcommand)]
+        action: CookieAction,
+    },
+
+    /// Accept or reject LLM-screened papers in a terminal UI and write the final set
+    Review {
+        /// Run folder with 7_review.csv / 7_relevant.csv
+        folder: PathBuf,
+    },
+
+    /// Check proxy, Scholar access, cookies and API keys before a long run
+    Doctor(DoctorArgs),
+
+    /// Suggest synonyms, related terms, and Boolean query variants for a keyword
+    Expand(Box<ExpandArgs>),
 
-        /// Source data type filter (default: 0,5 for articles only, excludes books)
-        #[arg(long, default_value = "0,5")]
-        sdt: String,
+    /// Convert a pipeline CSV to a bibliography format
+    Export {
+        /// Output format
+        #[arg(long, default_value = "bibtex", value_parser = ["bibtex", "ris", "csl-json"])]
+        format: String,
 
-        /// Output directory
-        #[arg(short, long, default_value = "./output")]
+        /// Input CSV from any stage (e.g., 7_relevant.csv)
+        input: PathBuf,
+
+        /// Output file (e.g., refs.bib, refs.ris, refs.json)
         output: PathBuf,
+    },
 
-        // === EasyScholar Filters ===
-        /// EasyScholar API key (required for filtering)
-        #[arg(long)]
-        easyscholar_key: Option<String>,
+    /// Merge the unified results of several run folders, removing duplicates
+    Merge {
+        /// Run folders (a previously merged folder can be given to append to it)
+        #[arg(required = true)]
+        folders: Vec<PathBuf>,
 
-        /// Filter: Impact Factor >= value
-        #[arg(long)]
-        sciif: Option<f64>,
+        /// Output folder for the merged 5_unified.csv
+        #[arg(short, long)]
+        output: PathBuf,
+    },
 
-        /// Filter: JCI >= value
+    /// Compare two runs by DOI: added, removed and changed papers (new citations, changed IF, ...)
+    Diff {
+        /// Compare 7_relevant.csv instead of 5_unified.csv
         #[arg(long)]
-        jci: Option<f64>,
+        relevant: bool,
 
-        /// Filter: SCI partition (e.g., "Q1")
+        /// Also write the differences as JSON to this file ("-" prints JSON instead of Markdown)
         #[arg(long)]
-        sci: Option<String>,
+        json: Option<PathBuf>,
 
-        /// Filter: sciUpTop (substring match)
-        #[arg(long)]
-        sci_up_top: Option<String>,
+        /// Older run folder (or a unified/relevant CSV)
+        old: PathBuf,
 
-        /// Filter: sciBase (substring match)
-        #[arg(long)]
-        sci_base: Option<String>,
+        /// Newer run folder (or a unified/relevant CSV)
+        new: PathBuf,
+    },
 
-        /// Filter: sciUp (substring match)
-        #[arg(long)]
-        sci_up: Option<String>,
+    /// Export the citation network of an OpenAlex run folder for Gephi/Cytoscape
+    Network {
+        /// Output format
+        #[arg(long, default_value = "graphml", value_parser = ["graphml", "gexf"])]
+        format: String,
 
-        // === LLM Filtering (Stage 6) ===
-        /// LLM API base URL (enables Stage 6, e.g., https://api.openai.com/v1)
+        /// Also add cited works outside the result set as nodes
         #[arg(long)]
-        llm_base_url: Option<String>,
+        include_references: bool,
+
+        /// Run folder (e.g., output/20250101_120000_landslide)
+        folder: PathBuf,
 
-        /// LLM API key
+        /// Output file (default: citations.graphml / citations.gexf in the run folder)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Cluster the papers of a run folder by keyword co-occurrence (VOSviewer map and network files)
+    Topics {
+        /// Drop terms found in fewer papers
+        #[arg(long, default_value_t = 2)]
+        min_occurrences: u32,
+
+        /// Keep at most this many terms (the most frequent)
+        #[arg(long, default_value_t = 200)]
+        max_terms: usize,
+
+        /// Use only OpenAlex keywords and topics, not title words
         #[arg(long)]
-        llm_key: Option<String>,
+        no_title_terms: bool,
 
-        /// LLM model name
-        #[arg(long, default_value = "gpt-4o-mini")]
-        llm_model: String,
+        /// Run folder (e.g., output/20250101_120000_landslide)
+        folder: PathBuf,
+    },
 
-        /// Filter keywords/phrases for LLM guidance (e.g., "landslide,slope,边坡")
+    /// Resolve the authors of a run folder's papers to ORCID iDs and write authors.csv
+    /// and authors_summary.csv (per-author h-index within the corpus)
+    Authors {
+        /// Only use OpenAlex authorships, do not search ORCID by DOI
         #[arg(long)]
-        filter_help: Option<String>,
+        no_orcid_search: bool,
+
+        /// Days to keep cached OpenAlex / ORCID responses (0 disables the cache)
+        #[arg(long, default_value = "30")]
+        http_cache_days: u64,
+
+        /// Run folder (e.g., output/20250101_120000_landslide)
+        folder: PathBuf,
     },
 
-    /// Run as HTTP server
-    Serve {
-        /// Port to listen on
-        #[arg(short, long, default_value = "3000")]
-        port: u16,
-
-        /// Host to bind to
-        #[arg(long, default_value = "127.0.0.1")]
-        host: String,
+    /// Write a starter config file for `search --config`
+    InitConfig {
+        /// Config file to create (.toml, .yaml or .yml)
+        #[arg(default_value = "review.toml")]
+        path: PathBuf,
+
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
     },
 
-    /// Manage cookies
-    Cookies {
+    /// Manage on-disk caches
+    Cache {
         #[command(subcommand)]
-        action: CookieAction,
+        action: CacheAction,
     },
 }
 
+/// Arguments for the `search` subcommand
+#[derive(Args, Clone, Serialize)]
+struct SearchArgs {
+    /// Search keywords
+    keyword: String,
+
+    /// TOML/YAML file with search options (see `init-config`); flags given on the command line override it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Search source(s): gscholar, openalex, s2 (Semantic Scholar), or a comma-separated list
+    /// (e.g., "gscholar,openalex") whose results are merged and deduplicated
+    #[arg(long, default_value = "gscholar")]
+    source: String,
+
+    /// Page range (e.g., "1", "1-10")
+    #[arg(long, default_value = "1")]
+    pages: String,
+
+    /// OpenAlex results per page (1-200; larger values are capped)
+    #[arg(long, default_value = "200")]
+    openalex_per_page: usize,
+
+    /// Seconds between two OpenAlex page requests (pages are then fetched one at a time)
+    #[arg(long, default_value = "0")]
+    openalex_page_delay: f64,
+
+    /// Year filter (results from this year onwards; default: current year - 5, with a
+    /// year histogram of the keyword on OpenAlex printed before the search)
+    #[arg(long)]
+    ylo: Option<i32>,
+
+    /// Without --ylo, start at the year that keeps 90% of the keyword's OpenAlex works
+    /// (from the year histogram) instead of five years back
+    #[arg(long, conflicts_with = "ylo")]
+    auto_ylo: bool,
+
+    /// Proxy URL for Google Scholar and every API (e.g., http://127.0.0.1:7890)
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Mirror site URL
+    #[arg(long)]
+    mirror: Option<String>,
+
+    /// How Google Scholar results are fetched: scrape (with the saved cookies), or through the
+    /// paid serpapi / searchapi JSON APIs (no cookies, no CAPTCHAs)
+    #[arg(long, default_value = "scrape", value_parser = ["scrape", "serpapi", "searchapi"])]
+    backend: String,
+
+    /// SerpAPI key for --backend serpapi
+    #[arg(long, env = "SERPAPI_KEY", hide_env_values = true)]
+    serpapi_key: Option<String>,
+
+    /// SearchAPI key for --backend searchapi
+    #[arg(long, env = "SEARCHAPI_KEY", hide_env_values = true)]
+    searchapi_key: Option<String>,
+
+    /// Source data type filter (default: 0,5 for articles only, excludes books)
+    #[arg(long, default_value = "0,5")]
+    sdt: String,
+
+    /// Before searching, ask the LLM for query variants (with OpenAlex hit counts) and save them
+    #[arg(long)]
+    expand_query: bool,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pipeline: PipelineArgs,
+}
+
+/// Arguments for the `serve` subcommand
+#[derive(Args)]
+struct ServeArgs {
+    /// Port to listen on
+    #[arg(short, long, default_value = "3000")]
+    port: u16,
+
+    /// Host to bind to
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Folder for job run folders and job state (jobs/)
+    #[arg(short, long, default_value = "output")]
+    output_dir: PathBuf,
+
+    /// Pipeline jobs run at once
+    #[arg(long, default_value = "1")]
+    max_jobs: usize,
+
+    /// Limit shared by all jobs for one upstream: NAME=CONCURRENCY[:INTERVAL_MS]
+    /// (scholar, crossref, openalex, easyscholar, s2, llm; e.g. s2=1:1500)
+    #[arg(long, value_delimiter = ',')]
+    upstream_limit: Vec<String>,
+
+    /// TOML/YAML file of API keys, one section per client (also: GSCHOLAR_API_KEYS="name:key,...")
+    #[arg(long)]
+    api_keys: Option<PathBuf>,
+
+    /// Requests per minute and key (0 = unlimited; a key's rate_per_minute overrides it)
+    #[arg(long, default_value = "60")]
+    rate_limit: u32,
+
+    /// Searches and jobs per day and key (0 = unlimited; a key's daily_quota overrides it)
+    #[arg(long, default_value = "0")]
+    daily_quota: u32,
+
+    /// Upstream services probed by /health/ready: crossref, openalex, s2, easyscholar, llm
+    #[arg(long, value_delimiter = ',', default_value = "crossref,openalex,s2,easyscholar,llm")]
+    ready_probes: Vec<String>,
+
+    /// LLM provider probed by /health/ready: openai or ollama
+    #[arg(long, default_value = "openai", value_parser = ["openai", "ollama"])]
+    llm_provider: String,
+
+    /// LLM API base URL probed by /health/ready (key from LLM_API_KEY; ollama defaults to http://localhost:11434)
+    #[arg(long)]
+    llm_base_url: Option<String>,
+
+    /// Also serve the gRPC API (proto/gscholar.proto) on this port
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc_port: Option<u16>,
+}
+
+/// Arguments for the `watch` subcommand
+#[derive(Args)]
+struct WatchArgs {
+    #[command(flatten)]
+    search: SearchArgs,
+
+    /// Time between runs: a number with s, m, h, d or w (e.g., "12h", "7d")
+    #[arg(long, default_value = "7d")]
+    interval: String,
+
+    /// Run a single update and exit (for cron or systemd timers)
+    #[arg(long)]
+    once: bool,
+
+    /// POST new papers as JSON to this URL
+    #[arg(long)]
+    notify_webhook: Option<String>,
+
+    /// Slack incoming-webhook URL to post new papers to
+    #[arg(long, env = "SLACK_WEBHOOK_URL", hide_env_values = true)]
+    notify_slack: Option<String>,
+
+    /// Email address to send new papers to (uses the local `sendmail`)
+    #[arg(long)]
+    notify_email: Option<String>,
+}
+
+/// Arguments for the `enrich` subcommand
+#[derive(Args, Serialize)]
+struct EnrichArgs {
+    /// CSV with a title and/or DOI column (e.g., an export from Zotero or Web of Science)
+    #[arg(long, required_unless_present = "dois", conflicts_with = "dois")]
+    input: Option<PathBuf>,
+
+    /// Text file with one DOI per line
+    #[arg(long)]
+    dois: Option<PathBuf>,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pipeline: PipelineArgs,
+}
+
+/// Options for Stages 2-8, shared by `search` and `enrich`
+#[derive(Args, Clone, Serialize)]
+struct PipelineArgs {
+    /// Output directory
+    #[arg(short, long, default_value = "./output")]
+    output: PathBuf,
+
+    /// Run folder name under --output instead of a timestamped one (rerunning with the same name writes to the same folder)
+    #[arg(long)]
+    output_name: Option<String>,
+
+    /// Resume an interrupted run: reuse this run folder and continue after the last stage recorded in its state.json
+    #[arg(long)]
+    resume: Option<PathBuf>,
+
+    /// Start a new run even when --output holds a run with identical options, or the --output-name folder is taken
+    #[arg(long, conflicts_with_all = ["resume", "reuse"])]
+    force: bool,
+
+    /// Continue the run with identical options in --output without asking, reusing its completed stages
+    #[arg(long, conflicts_with = "resume")]
+    reuse: bool,
+
+    /// Update a previous run folder: search from its year onwards and process only papers it has not seen (writes delta.md)
+    #[arg(long)]
+    since: Option<PathBuf>,
+
+    /// Run only these stages, by number, range or name (e.g., "1,2,3,6", "1-5", "semanticscholar")
+    #[arg(long, value_delimiter = ',')]
+    stages: Vec<String>,
+
+    /// Skip these stages (e.g., "semanticscholar" or "4")
+    #[arg(long = "skip-stage", value_delimiter = ',')]
+    skip_stage: Vec<String>,
+
+    /// Only count results and print the estimated API calls, LLM tokens/cost and time, without running
+    #[arg(long)]
+    dry_run: bool,
+
+    /// LLM price for --dry-run as "input,output" USD per 1M tokens (default: built-in list prices)
+    #[arg(long)]
+    llm_price: Option<String>,
+
+    /// Stop searching once this many unique records are collected across all sources,
+    /// and process at most this many in the later stages
+    #[arg(long)]
+    max_results: Option<usize>,
+
+    /// Keep only papers cited at least this many times (papers without a citation count are dropped)
+    #[arg(long)]
+    min_citations: Option<u64>,
+
+    /// Keep only these work types: article, review, preprint, book-chapter, book, dissertation, other
+    /// (searched for where the source supports it; records of unknown type are kept)
+    #[arg(long, value_delimiter = ',')]
+    work_types: Vec<String>,
+
+    /// Keep only papers with an author from these countries (ISO codes, e.g., "CN,IT"; OpenAlex
+    /// is searched for them, records without country data are kept)
+    #[arg(long, value_delimiter = ',')]
+    country: Vec<String>,
+
+    /// Drop review articles in Stage 5 (detected from title, abstract and type cues,
+    /// with the LLM deciding inconclusive papers when configured)
+    #[arg(long)]
+    exclude_reviews: bool,
+
+    /// Keep only review articles in Stage 5
+    #[arg(long, conflicts_with = "exclude_reviews")]
+    only_reviews: bool,
+
+    // === EasyScholar Filters ===
+    /// EasyScholar API key (required for filtering)
+    #[arg(long, env = "EASYSCHOLAR_KEY", hide_env_values = true)]
+    easyscholar_key: Option<String>,
+
+    /// Filter: Impact Factor >= value
+    #[arg(long)]
+    sciif: Option<f64>,
+
+    /// Filter: JCI >= value
+    #[arg(long)]
+    jci: Option<f64>,
+
+    /// Filter: SCI partition (e.g., "Q1")
+    #[arg(long)]
+    sci: Option<String>,
+
+    /// Filter: sciUpTop (substring match)
+    #[arg(long)]
+    sci_up_top: Option<String>,
+
+    /// Filter: sciBase (substring match)
+    #[arg(long)]
+    sci_base: Option<String>,
+
+    /// Filter: sciUp (substring match)
+    #[arg(long)]
+    sci_up: Option<String>,
+
+    /// Filter: CAS partition / zky (substring match, e.g., "1区")
+    #[arg(long)]
+    zky: Option<String>,
+
+    /// Filter: 北大核心 / pku (substring match)
+    #[arg(long)]
+    pku: Option<String>,
+
+    /// Filter: CSSCI (substring match)
+    #[arg(long)]
+    cssci: Option<String>,
+
+    /// Filter: CSCD (substring match)
+    #[arg(long)]
+    cscd: Option<String>,
+
+    /// Filter: EI / eii (substring match)
+    #[arg(long)]
+    eii: Option<String>,
+
+    /// Filter: CCF rank (substring match, e.g., "A")
+    #[arg(long)]
+    ccf: Option<String>,
+
+    /// Filter: CORE conference rank (substring match, e.g., "A"; needs --core-rankings)
+    #[arg(long)]
+    core: Option<String>,
+
+    /// Ranking filter expression, combined with the flags above using AND
+    /// (e.g., "sciif>=5 && (sci~'Q1' || ccf=='A')")
+    #[arg(long)]
+    rank_filter: Option<String>,
+
+    /// Journal whitelist file: keep only papers from these journals (one per line, /regex/ allowed)
+    #[arg(long)]
+    journal_include: Option<PathBuf>,
+
+    /// Journal blacklist file: drop papers from these journals (one per line, /regex/ allowed)
+    #[arg(long)]
+    journal_exclude: Option<PathBuf>,
+
+    /// JSON file mapping journal name aliases to EasyScholar canonical names
+    #[arg(long)]
+    journal_aliases: Option<PathBuf>,
+
+    /// CORE conference rankings CSV (exported from the CORE portal) for ranking conference papers
+    #[arg(long)]
+    core_rankings: Option<PathBuf>,
+
+    /// Ask DBLP whether venues are journals or conferences when their names do not tell
+    #[arg(long)]
+    dblp: bool,
+
+    /// Days to keep EasyScholar results in the on-disk cache (0 disables the cache)
+    #[arg(long, default_value = "30")]
+    ranking_cache_days: u64,
+
+    /// Days to keep cached Crossref / Semantic Scholar lookup responses (0 disables the cache)
+    #[arg(long, default_value = "30")]
+    http_cache_days: u64,
+
+    /// Hours to keep cached OpenAlex / Semantic Scholar search pages (0 disables the cache)
+    #[arg(long, default_value = "24")]
+    search_cache_hours: u64,
+
+    /// Semantic Scholar API key for higher rate limits in Stage 4
+    #[arg(long, env = "S2_API_KEY", hide_env_values = true)]
+    s2_key: Option<String>,
+
+    /// Title similarity (0-1) a Crossref title lookup or a Semantic Scholar title match
+    /// needs to be accepted
+    #[arg(long, default_value_t = matching::DEFAULT_THRESHOLD)]
+    title_match_threshold: f64,
+
+    /// Enrichers to run on the Stage 5 dataset, in order; each fills only empty fields
+    /// (crossref, semanticscholar, unpaywall, rankings, altmetric)
+    #[arg(long, value_delimiter = ',')]
+    enrichers: Vec<String>,
+
+    /// Contact email required by the unpaywall enricher
+    #[arg(long, env = "UNPAYWALL_EMAIL")]
+    unpaywall_email: Option<String>,
+
+    /// Backfill missing abstracts from these sources, in order (crossref, openalex,
+    /// semanticscholar, scopus, landing); without a list, all of them (scopus only with --scopus-key)
+    #[arg(long, num_args = 0.., value_delimiter = ',')]
+    abstract_chain: Option<Vec<String>>,
+
+    /// Elsevier API key for the scopus abstract source
+    #[arg(long, env = "SCOPUS_API_KEY", hide_env_values = true)]
+    scopus_key: Option<String>,
+
+    /// Altmetric API key for the altmetric enricher (optional; without one, one request per second)
+    #[arg(long, env = "ALTMETRIC_API_KEY", hide_env_values = true)]
+    altmetric_key: Option<String>,
+
+    /// Download open-access PDFs of the Stage 5 papers into pdfs/ (pdf_url, else Unpaywall
+    /// with --unpaywall-email); adds a pdf_path column
+    #[arg(long)]
+    download_pdfs: bool,
+
+    /// Seconds between two PDF requests to the same host
+    #[arg(long, default_value_t = 2.0)]
+    pdf_delay: f64,
+
+    /// Run the downloaded PDFs through a GROBID server: text into fulltext/, references
+    /// and author affiliations into 5_references.csv and 5_affiliations.csv
+    #[arg(long, requires = "download_pdfs")]
+    extract_fulltext: bool,
+
+    /// GROBID server used by --extract-fulltext
+    #[arg(long, default_value = grobid::DEFAULT_GROBID_URL)]
+    grobid_url: String,
+
+    /// Give the LLM stages the first CHARS characters of the full text of papers
+    /// whose abstract is missing or short (default 4000 when given without a value)
+    #[arg(long, value_name = "CHARS", num_args = 0..=1, default_missing_value = "4000")]
+    llm_fulltext: Option<usize>,
+
+    // === LLM Filtering (Stage 6) ===
+    #[command(flatten)]
+    #[serde(flatten)]
+    screening: ScreeningArgs,
+
+    /// Minimum confidence for a relevant verdict to reach Stage 7; lower ones are treated as uncertain
+    #[arg(long, default_value_t = 0.0)]
+    llm_min_confidence: f64,
+
+    /// What Stage 7 does with uncertain verdicts: keep, drop, or review (write 7_review.csv)
+    #[arg(long, default_value = "drop", value_parser = ["keep", "drop", "review"])]
+    uncertain_policy: String,
+
+    /// Comma-separated categories for tagging relevant papers (e.g., "method,case study,review")
+    #[arg(long)]
+    tags: Option<String>,
+
+    /// Comma-separated study designs; Stage 8 labels each relevant paper with one of them
+    /// in a study_design column (default list when given without a value)
+    #[arg(long, value_name = "DESIGNS", num_args = 0..=1, default_missing_value = study_design::DEFAULT_DESIGNS)]
+    study_designs: Option<String>,
+
+    /// Push the Stage 7 relevant papers into this Zotero collection (created if missing)
+    #[arg(long, requires_all = ["zotero_key", "zotero_library_id"])]
+    push_zotero: Option<String>,
+
+    /// Zotero API key with write access (https://www.zotero.org/settings/keys)
+    #[arg(long, env = "ZOTERO_API_KEY", hide_env_values = true)]
+    zotero_key: Option<String>,
+
+    /// Zotero library ID (your userID, or the group ID with --zotero-group)
+    #[arg(long)]
+    zotero_library_id: Option<String>,
+
+    /// Treat --zotero-library-id as a group library
+    #[arg(long)]
+    zotero_group: bool,
+
+    /// Also collect every stage CSV into one Excel workbook (results.xlsx, one sheet per stage)
+    #[arg(long)]
+    xlsx: bool,
+
+    /// Also draw the PRISMA 2020 flow diagram (prisma.svg) next to prisma.json/prisma.md
+    #[arg(long)]
+    prisma_svg: bool,
+
+    /// Keep every fetched HTML page and JSON API response, gzipped, under raw/<stage>/ (listed in raw/index.jsonl)
+    #[arg(long, conflicts_with = "stdout")]
+    save_raw: bool,
+
+    /// File format for stage tables: csv, parquet for large harvests, sqlite (CSVs plus one run.db), or jsonl
+    #[arg(long, default_value = "csv", value_parser = ["csv", "parquet", "sqlite", "jsonl"])]
+    output_format: String,
+
+    /// Stream the final records as JSON lines to stdout instead of keeping an output folder
+    /// (progress goes to stderr; --output, --output-format and --xlsx are ignored)
+    #[arg(long, conflicts_with = "resume")]
+    stdout: bool,
+}
+
+/// Arguments for the `filter` subcommand
+#[derive(Args, Serialize)]
+struct FilterArgs {
+    /// CSV to screen (e.g., 5_unified.csv, or any table with a title column)
+    #[arg(long)]
+    input: PathBuf,
+
+    /// Read a unified field from this column (e.g., "abstract_text=Abstract"); repeatable
+    #[arg(long = "column", value_name = "FIELD=COLUMN")]
+    columns: Vec<String>,
+
+    /// Output directory
+    #[arg(short, long, default_value = "./output")]
+    output: PathBuf,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    screening: ScreeningArgs,
+}
+
+/// Connection settings of the HTTP client shared by all upstream APIs
+#[derive(Args, Clone)]
+struct HttpArgs {
+    /// Timeout in seconds of one HTTP request (longer for LLM, batch and PDF requests)
+    #[arg(long, global = true, default_value_t = 30)]
+    http_timeout: u64,
+
+    /// Timeout in seconds for opening a connection
+    #[arg(long, global = true, default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Idle connections kept open per host
+    #[arg(long, global = true, default_value_t = 16)]
+    http_pool_size: usize,
+
+    /// Retries of a request after a connection error, timeout, 429 or 502-504
+    #[arg(long, global = true, default_value_t = 3)]
+    http_retries: u32,
+
+    /// Use HTTP/1.1 only (HTTP/2 is negotiated with servers that support it otherwise)
+    #[arg(long, global = true)]
+    http1_only: bool,
+
+    /// Retry policy of one upstream: NAME=RETRIES[:BACKOFF_MS[:JITTER[:TIMEOUT_SECS]]]
+    /// (scholar, crossref, openalex, easyscholar, s2, llm; e.g. s2=5:2000:0.2:90)
+    #[arg(long, global = true, value_delimiter = ',')]
+    retry_policy: Vec<String>,
+
+    /// Proxy of one upstream instead of --proxy: NAME=URL or NAME=direct
+    /// (scholar, crossref, openalex, easyscholar, s2, llm; e.g. llm=direct)
+    #[arg(long, global = true, value_delimiter = ',')]
+    proxy_for: Vec<String>,
+}
+
+impl HttpArgs {
+    fn settings(&self, proxy: Option<String>) -> Result<http::HttpSettings> {
+        let proxy_overrides = self
+            .proxy_for
+            .iter()
+            .map(|value| http::parse_proxy_override(value))
+            .collect::<std::result::Result<_, _>>()?;
+        let mut settings = http::HttpSettings {
+            proxy,
+            proxy_overrides,
+            timeout: std::time::Duration::from_secs(self.http_timeout),
+            connect_timeout: std::time::Duration::from_secs(self.connect_timeout),
+            pool_max_idle_per_host: self.http_pool_size,
+            http2: !self.http1_only,
+            retry: http::RetryPolicy { max_retries: self.http_retries, ..Default::default() },
+            ..Default::default()
+        };
+        // Overrides start from the upstream's policy under the flags above
+        settings.retry_overrides = self
+            .retry_policy
+            .iter()
+            .map(|value| settings.parse_retry_override(value))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(settings)
+    }
+}
+
+/// Stage 6 options shared by `search`, `enrich` and `filter`
+#[derive(Args, Clone, Serialize)]
+struct ScreeningArgs {
+    #[command(flatten)]
+    #[serde(flatten)]
+    llm: LlmArgs,
+
+    /// Filter keywords/phrases for LLM guidance (e.g., "landslide,slope,边坡")
+    #[arg(long)]
+    filter_help: Option<String>,
+
+    /// Label papers irrelevant without an LLM call unless title or abstract matches one of
+    /// these case-insensitive regular expressions, one per flag (e.g., --prefilter-include
+    /// landslide --prefilter-include "slope.{0,20}failure")
+    #[arg(long)]
+    prefilter_include: Vec<String>,
+
+    /// Label papers irrelevant without an LLM call when title or abstract matches one of
+    /// these case-insensitive regular expressions, one per flag (e.g., --prefilter-exclude
+    /// "stock market" --prefilter-exclude "yield curve")
+    #[arg(long)]
+    prefilter_exclude: Vec<String>,
+
+    /// Inclusion/exclusion criteria file (YAML/JSON); switches Stage 6 to criteria screening
+    #[arg(long)]
+    criteria: Option<PathBuf>,
+
+    /// Stronger model that re-screens uncertain/low-confidence verdicts of --llm-model (two-pass mode)
+    #[arg(long, conflicts_with = "criteria")]
+    llm_review_model: Option<String>,
+
+    /// Triage confidence below which a verdict is re-screened by --llm-review-model
+    #[arg(long, default_value_t = 0.7)]
+    llm_review_below: f64,
+}
+
+impl ScreeningArgs {
+    /// Add the LLM and screening mode to a pipeline
+    fn configure(&self, mut builder: PipelineBuilder) -> Result<PipelineBuilder> {
+        builder = match self.llm.config(self.filter_help.as_deref().unwrap_or_default())? {
+            Ok(llm_config) => builder.llm(llm_config),
+            Err(reason) => builder.llm_unavailable(reason),
+        };
+        builder = builder.prefilter(
+            Prefilter::new(&self.prefilter_include, &self.prefilter_exclude).context("Invalid --prefilter-include/--prefilter-exclude")?,
+        );
+        if let Some(ref path) = self.criteria {
+            builder = builder.criteria(screening::Criteria::load(path).context("Failed to load --criteria")?);
+        }
+        if let Some(ref review_model) = self.llm_review_model {
+            builder = builder.review_model(review_model, self.llm_review_below);
+        }
+        Ok(builder)
+    }
+}
+
+#[derive(Args)]
+struct ExpandArgs {
+    /// Search keyword to expand
+    keyword: String,
+
+    /// Research question or scope to guide the suggestions
+    #[arg(long)]
+    context: Option<String>,
+
+    /// Check each query variant's hit count on OpenAlex
+    #[arg(long)]
+    validate: bool,
+
+    /// Start year for hit counts (default: current year - 5)
+    #[arg(long)]
+    ylo: Option<i32>,
+
+    /// Save suggestions as JSON
+    #[arg(long)]
+    save: Option<PathBuf>,
+
+    #[command(flatten)]
+    llm: LlmArgs,
+}
+
+/// LLM connection options shared by `search` and `expand`
+#[derive(Args, Clone, Serialize)]
+struct LlmArgs {
+    /// LLM provider: openai (any OpenAI-compatible API) or ollama (local, no key)
+    #[arg(long, default_value = "openai", value_parser = ["openai", "ollama"])]
+    llm_provider: String,
+
+    /// LLM API base URL (enables Stage 6, e.g., https://api.openai.com/v1; ollama defaults to http://localhost:11434)
+    #[arg(long)]
+    llm_base_url: Option<String>,
+
+    /// LLM API key (not needed for ollama)
+    #[arg(long, env = "LLM_API_KEY", hide_env_values = true)]
+    llm_key: Option<String>,
+
+    /// LLM model name
+    #[arg(long, default_value = "gpt-4o-mini")]
+    llm_model: String,
+
+    /// Structured output mode: auto (json_schema, downgrading if unsupported), schema, object, or off
+    #[arg(long, default_value = "auto", value_parser = ["auto", "schema", "object", "off"])]
+    llm_json_mode: String,
+
+    /// Retries for transient LLM API errors (429/5xx/network) before marking a paper uncertain
+    /// (default: from the llm retry policy, 3)
+    #[arg(long)]
+    llm_max_retries: Option<u32>,
+
+    /// Days to keep cached LLM replies (keyed by model + prompt hash); 0 disables the cache
+    #[arg(long, default_value_t = 30)]
+    llm_cache_days: u64,
+
+    /// Maximum concurrent LLM requests
+    #[arg(long, default_value_t = llm_filter::DEFAULT_CONCURRENT_REQUESTS)]
+    llm_concurrency: usize,
+
+    /// Per-request timeout in seconds (default: the llm retry policy's, else 60, or 300 for ollama)
+    #[arg(long)]
+    llm_timeout: Option<u64>,
+
+    /// Sampling temperature
+    #[arg(long, default_value_t = llm_filter::DEFAULT_TEMPERATURE)]
+    llm_temperature: f64,
+
+    /// Omit the temperature parameter (for reasoning models that reject it, e.g. o-series)
+    #[arg(long)]
+    llm_no_temperature: bool,
+
+    /// Maximum completion tokens per request
+    #[arg(long, default_value_t = llm_filter::DEFAULT_MAX_TOKENS)]
+    llm_max_tokens: u32,
+}
+
+impl LlmArgs {
+    /// Build the LLM config, or the reason the LLM is not configured
+    ///
+    /// Local providers need neither a base URL nor a key.
+    fn config(&self, filter_help: &str) -> Result<std::result::Result<llm_filter::LlmConfig, &'static str>> {
+        let provider: llm_filter::LlmProvider = self.llm_provider.parse()?;
+
+        let Some(base_url) = self
+            .llm_base_url
+            .clone()
+            .or_else(|| provider.default_base_url().map(String::from))
+        else {
+            return Ok(Err("no --llm-base-url provided"));
+        };
+        let Some(api_key) = self
+            .llm_key
+            .clone()
+            .or_else(|| (!provider.requires_api_key()).then(String::new))
+        else {
+            return Ok(Err("--llm-key not provided"));
+        };
+
+        Ok(Ok(llm_filter::LlmConfig {
+            provider,
+            base_url,
+            api_key,
+            model: self.llm_model.clone(),
+            filter_help: filter_help.to_string(),
+            json_mode: self.llm_json_mode.parse()?,
+            max_retries: self.llm_max_retries.unwrap_or_else(|| http::retry_policy(throttle::Upstream::Llm).max_retries),
+            cache_dir: if self.llm_cache_days > 0 {
+                Some(cache::default_cache_dir()?.join(cache::LLM_CACHE_DIR))
+            } else {
+                None
+            },
+            cache_ttl: std::time::Duration::from_secs(self.llm_cache_days * 24 * 60 * 60),
+            concurrency: self.llm_concurrency,
+            timeout: self.llm_timeout.map(std::time::Duration::from_secs),
+            temperature: (!self.llm_no_temperature).then_some(self.llm_temperature),
+            max_tokens: self.llm_max_tokens,
+        }))
+    }
+}
+
+/// Arguments for the `doctor` subcommand
+#[derive(Args)]
+struct DoctorArgs {
+    /// Proxy URL (e.g., http://127.0.0.1:7890)
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Mirror site URL
+    #[arg(long)]
+    mirror: Option<String>,
+
+    /// EasyScholar API key
+    #[arg(long, env = "EASYSCHOLAR_KEY", hide_env_values = true)]
+    easyscholar_key: Option<String>,
+
+    /// Semantic Scholar API key
+    #[arg(long, env = "S2_API_KEY", hide_env_values = true)]
+    s2_key: Option<String>,
+
+    /// LLM provider: openai (any OpenAI-compatible API) or ollama (local, no key)
+    #[arg(long, default_value = "openai", value_parser = ["openai", "ollama"])]
+    llm_provider: String,
+
+    /// LLM API base URL (ollama defaults to http://localhost:11434)
+    #[arg(long)]
+    llm_base_url: Option<String>,
+
+    /// LLM API key (not needed for ollama)
+    #[arg(long, env = "LLM_API_KEY", hide_env_values = true)]
+    llm_key: Option<String>,
+
+    /// LLM model name, checked against the endpoint's model list
+    #[arg(long, default_value = "gpt-4o-mini")]
+    llm_model: String,
+}
+
 #[derive(Subcommand)]
 enum CookieAction {
     /// Clear stored cookies
@@ -155,610 +1019,785 @@ enum CookieAction {
     /// Show cookie file path
     Path,
     /// Fetch cookies from browser (opens Google Scholar)
-    Fetch,
+    Fetch {
+        /// Run the browser without a window (a CAPTCHA then fails the fetch)
+        #[arg(long)]
+        headless: bool,
+
+        /// Seconds to wait for Scholar to load, including solving a CAPTCHA
+        #[arg(long, default_value = "300")]
+        timeout: u64,
+
+        /// Chrome/Chromium executable (default: found on the PATH)
+        #[arg(long)]
+        browser_path: Option<PathBuf>,
+
+        /// Paste cookies as JSON instead of launching a browser
+        #[arg(long)]
+        manual: bool,
+    },
+    /// Import Google cookies from a local browser profile or an exported file
+    Import {
+        /// Browser to read: chrome, chromium, edge or firefox
+        #[arg(long, default_value = "chrome")]
+        browser: String,
+
+        /// Profile name (e.g., "Profile 1") or folder (default: the browser's default profile)
+        #[arg(long)]
+        profile: Option<PathBuf>,
+
+        /// Cookie export to read instead: Netscape cookies.txt or JSON
+        /// (EditThisCookie, Cookie-Editor, Playwright)
+        #[arg(long, conflicts_with_all = ["browser", "profile"])]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show entries and size of each cache
+    Stats,
+    /// Delete every cache
+    Clear,
+    /// Delete the EasyScholar rankings cache
+    ClearRankings,
+    /// Delete the LLM reply cache
+    ClearLlm,
+    /// Delete the HTTP response caches (Crossref, OpenAlex, Semantic Scholar)
+    ClearHttp,
+    /// Show cache directory path
+    Path,
 }
 
 // ============================================================================
 // Main Entry Point
 // ============================================================================
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<()> {
+    // Keys may also come from a .env file in the working directory. It is
+    // read before the runtime starts: setting variables is only sound while
+    // no other thread may be reading the environment.
+    let dotenv = Path::new(secrets::DOTENV_FILE);
+    if dotenv.exists() {
+        secrets::load_dotenv(dotenv).context("Failed to read .env")?;
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start the async runtime")?
+        .block_on(run())
+}
+
+async fn run() -> Result<()> {
+    let args = with_config_args(std::env::args_os().collect())?;
+    let redactor = Arc::new(secrets::Redactor::from_env_and_args(&args));
+    REDACTOR.get_or_init(|| redactor.clone());
+    let cli = Cli::parse_from(args);
 
     // Initialize logging
-    let log_level = if cli.debug { Level::DEBUG } else { Level::INFO };
+    let json_logs = cli.log_format == "json";
+    let log_level = if cli.debug {
+        Level::DEBUG
+    } else if cli.quiet {
+        Level::WARN
+    } else {
+        Level::INFO
+    };
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(log_level.to_string()));
 
-    fmt()
-        .with_env_filter(filter)
-        .with_target(true)
-        .with_thread_ids(false)
-        .init();
-
-    match cli.command {
-        Commands::Search {
-            keyword,
-            source,
-            pages,
-            ylo,
-            proxy,
-            mirror,
-            sdt,
-            output,
-            easyscholar_key,
-            sciif,
-            jci,
-            sci,
-            sci_up_top,
-            sci_base,
-            sci_up,
-            llm_base_url,
-            llm_key,
-            llm_model,
-            filter_help,
-        } => {
-            run_search_pipeline(
-                keyword,
-                source,
-                pages,
-                ylo,
-                proxy,
-                mirror,
-                sdt,
-                output,
-                easyscholar_key,
-                sciif,
-                jci,
-                sci,
-                sci_up_top,
-                sci_base,
-                sci_up,
-                llm_base_url,
-                llm_key,
-                llm_model,
-                filter_help,
-            )
-            .await
-        }
-        Commands::Serve { port, host } => run_server(host, port).await,
-        Commands::Cookies { action } => handle_cookies(action),
+    match &cli.command {
+        Commands::Search(args) => STDOUT_MODE.store(args.pipeline.stdout, Ordering::Relaxed),
+        Commands::Enrich(args) => STDOUT_MODE.store(args.pipeline.stdout, Ordering::Relaxed),
+        _ => {}
+    }
+    let machine = cli.quiet || json_logs;
+    MACHINE_MODE.store(machine, Ordering::Relaxed);
+    // --stdout already gives stdout to the records
+    let summarized = matches!(cli.command, Commands::Search(_) | Commands::Enrich(_) | Commands::Filter(_));
+    SUMMARY_MODE.store(machine && summarized && !STDOUT_MODE.load(Ordering::Relaxed), Ordering::Relaxed);
+
+    // Logs share stderr with progress when stdout carries records or the summary
+    let console_redactor = redactor.clone();
+    let console_writer = move || -> Box<dyn std::io::Write> {
+        if STDOUT_MODE.load(Ordering::Relaxed) || MACHINE_MODE.load(Ordering::Relaxed) {
+            Box::new(secrets::RedactingWriter::new(std::io::stderr(), console_redactor.clone()))
+        } else {
+            Box::new(secrets::RedactingWriter::new(std::io::stdout(), console_redactor.clone()))
+        }
+    };
+    let (console_text, console_json) = if json_logs {
+        (None, Some(fmt::layer().json().with_writer(console_writer).with_filter(filter)))
+    } else {
+        let layer = fmt::layer()
+            .with_target(true)
+            .with_thread_ids(false)
+            .with_writer(console_writer)
+            .with_filter(filter)
+            .with_filter(filter_fn(|metadata| metadata.target() != run_log::TARGET));
+        (Some(layer), None)
+    };
+    // run.log gets everything down to this crate's debug events, whatever the console shows
+    let file = fmt::layer()
+        .json()
+        .with_writer(move || secrets::RedactingWriter::new(run_log().clone(), redactor.clone()))
+        .with_filter(EnvFilter::new("info,rustgscholar=debug"));
+    tracing_subscriber::registry().with(console_text).with(console_json).with(file).init();
+
+    // One client (pool, proxy, timeouts) for every upstream API
+    let proxy = match &cli.command {
+        Commands::Search(args) => args.proxy.clone(),
+        Commands::Watch(args) => args.search.proxy.clone(),
+        Commands::Doctor(args) => args.proxy.clone(),
+        _ => None,
+    };
+    let settings = cli.http.settings(proxy).context("Invalid --proxy-for or --retry-policy")?;
+    http::configure(settings).context("Invalid HTTP settings")?;
+
+    let result = match cli.command {
+        Commands::Search(args) => run_search_pipeline(*args).await,
+        Commands::Enrich(args) => run_enrich(*args).await,
+        Commands::Filter(args) => run_filter(*args).await,
+        Commands::Watch(args) => run_watch(*args).await,
+        Commands::Serve(args) => run_server(args).await,
+        Commands::Cookies { action } => handle_cookies(action).await,
+        Commands::Doctor(args) => run_doctor(args).await,
+        Commands::Review { folder } => run_review(&folder),
+        Commands::Expand(args) => run_expand(*args).await,
+        Commands::Export { format, input, output } => run_export(&format, &input, &output),
+        Commands::Merge { folders, output } => run_merge(&folders, &output),
+        Commands::Diff { relevant, json, old, new } => run_diff(&old, &new, relevant, json.as_deref()),
+        Commands::Network { format, include_references, folder, output } => {
+            run_network(&format, include_references, &folder, output)
+        }
+        Commands::Topics { min_occurrences, max_terms, no_title_terms, folder } => {
+            let options = topics::TopicOptions { min_occurrences, max_terms, title_terms: !no_title_terms };
+            run_topics(&options, &folder)
+        }
+        Commands::Authors { no_orcid_search, http_cache_days, folder } => {
+            run_authors(!no_orcid_search, http_cache_days, &folder).await
+        }
+        Commands::InitConfig { path, force } => run_init_config(&path, force),
+        Commands::Cache { action } => handle_cache(action),
+    };
+
+    // Errors can quote request URLs that carry a key
+    result.map_err(|e| {
+        let message = redact(&format!("{:?}", e));
+        error!(target: run_log::TARGET, error = %message, "Run failed");
+        if SUMMARY_MODE.load(Ordering::Relaxed) {
+            print_summary(&RunOutcome { status: "error", summary: None, error: Some(redact(&format!("{:#}", e))) });
+        }
+        anyhow::anyhow!(message)
+    })
+}
+
+/// Insert the options of `search --config <file>` (or `watch --config`)
+/// right after the subcommand, so flags typed on the command line (parsed
+/// later) override the file
+fn with_config_args(mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let Some(search) = subcommand_index(&args) else {
+        return Ok(args);
+    };
+    let command = args[search].to_string_lossy().into_owned();
+    if command != "search" && command != "watch" {
+        return Ok(args);
+    }
+
+    let path = args[search + 1..].iter().enumerate().find_map(|(i, arg)| {
+        let arg = arg.to_string_lossy();
+        if arg == "--config" {
+            args.get(search + 2 + i).map(PathBuf::from)
+        } else {
+            arg.strip_prefix("--config=").map(PathBuf::from)
+        }
+    });
+    let Some(path) = path else {
+        return Ok(args);
+    };
+
+    let file_args = config::load(&path, &command).with_context(|| format!("Failed to load --config {:?}", path))?;
+    args.splice(search + 1..search + 1, file_args.into_iter().map(OsString::from));
+    Ok(args)
+}
+
+/// Position of the subcommand: the first argument that is neither a global
+/// flag nor the value of one (`--log-format json`, `--http-timeout 60`)
+fn subcommand_index(args: &[OsString]) -> Option<usize> {
+    let command = Cli::command();
+    let takes_value = |flag: &str| {
+        let arg = match flag.strip_prefix("--") {
+            Some(long) => command.get_arguments().find(|a| a.get_long() == Some(long)),
+            None => flag.chars().last().and_then(|short| command.get_arguments().find(|a| a.get_short() == Some(short))),
+        };
+        arg.is_some_and(|a| a.get_action().takes_values())
+    };
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+        if !arg.contains('=') && takes_value(&arg) {
+            i += 1;
+        }
+        i += 1;
     }
+    None
 }
 
 // ============================================================================
 // Search Pipeline
 // ============================================================================
 
-#[allow(clippy::too_many_arguments)]
-async fn run_search_pipeline(
-    keyword: String,
-    source: String,
-    pages_str: String,
-    ylo: Option<i32>,
-    proxy: Option<String>,
-    mirror: Option<String>,
-    sdt: String,
-    output_dir: PathBuf,
-    easyscholar_key: Option<String>,
-    sciif: Option<f64>,
-    jci: Option<f64>,
-    sci: Option<String>,
-    sci_up_top: Option<String>,
-    sci_base: Option<String>,
-    sci_up: Option<String>,
-    llm_base_url: Option<String>,
-    llm_key: Option<String>,
-    llm_model: String,
-    filter_help: Option<String>,
-) -> Result<()> {
-    // Parse pages
-    let pages = parse_pages(&pages_str).context("Invalid --pages format")?;
+async fn run_search_pipeline(args: SearchArgs) -> Result<()> {
+    let report = search_pipeline(args).await?;
+    summarize(&report);
+    Ok(())
+}
 
-    // Calculate year filter (default: current year - 5)
-    let ylo_val = ylo.unwrap_or_else(|| Local::now().format("%Y").to_string().parse().unwrap_or(2020) - 5);
+/// Final line on stdout with `--quiet` / `--log-format json`
+#[derive(Serialize)]
+struct RunOutcome {
+    status: &'static str,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    summary: Option<pipeline::RunSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
 
-    // Create output folder
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let safe_keyword: String = keyword
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
-        .collect::<String>()
-        .trim()
-        .replace(' ', "_");
-    let output_folder = output_dir.join(format!("{}_{}", timestamp, safe_keyword));
-    std::fs::create_dir_all(&output_folder).context("Failed to create output directory")?;
-
-    println!("Output folder: {}", output_folder.display());
-
-    // ===========================================
-    // STAGE 1: Google Scholar Scrape
-    // ===========================================
-    // ===========================================
-    // STAGE 1 & 2: Search & Enrichment
-    // ===========================================
-    
-    let mut enriched_list: Vec<EnrichedResult> = Vec::new();
-
-    if source == "bs" || source == "gscholar" {
-        println!("\n--- Stage 1: Google Scholar Search ---");
-
-        let query_options = gscholar::QueryOptions {
-            proxy: proxy.clone(),
-            pages: pages.clone(),
-            sdt,
-            ylo: Some(ylo_val),
-            base_url: mirror,
-            all_results: true,
-        };
+/// Print the run summary when stdout is reserved for it
+fn summarize(report: &pipeline::PipelineReport) {
+    if SUMMARY_MODE.load(Ordering::Relaxed) {
+        print_summary(&RunOutcome { status: "ok", summary: Some(report.into()), error: None });
+    }
+}
 
-        let gs_results = gscholar::query(&keyword, &query_options).await?;
+fn print_summary(outcome: &RunOutcome) {
+    match serde_json::to_string(outcome) {
+        Ok(line) => println!("{}", redact(&line)),
+        Err(e) => error!("Failed to serialize the run summary: {}", e),
+    }
+}
 
-        if gs_results.is_empty() {
-            println!("No results from Google Scholar.");
-            return Ok(());
+async fn search_pipeline(args: SearchArgs) -> Result<pipeline::PipelineReport> {
+    let options = effective_options(&args)?;
+    let SearchArgs {
+        keyword,
+        config: _,
+        source,
+        pages: pages_str,
+        openalex_per_page,
+        openalex_page_delay,
+        ylo,
+        auto_ylo,
+        proxy,
+        mirror,
+        backend,
+        serpapi_key,
+        searchapi_key,
+        sdt,
+        expand_query,
+        pipeline,
+    } = args;
+
+    // Parse pages
+    let pages = parse_pages(&pages_str).context("Invalid --pages format")?;
+    let sources = pipeline::Source::parse_list(&source)?;
+    let backend: ScholarBackend = backend.parse()?;
+    let scholar_api = match backend {
+        ScholarBackend::Scrape => None,
+        ScholarBackend::SerpApi => {
+            let key = serpapi_key.context("--backend serpapi needs --serpapi-key (or SERPAPI_KEY)")?;
+            Some(ScholarApiClient::new(backend, &key)?)
+        }
+        ScholarBackend::SearchApi => {
+            let key = searchapi_key.context("--backend searchapi needs --searchapi-key (or SEARCHAPI_KEY)")?;
+            Some(ScholarApiClient::new(backend, &key)?)
         }
+    };
 
-        println!("Found {} results from Google Scholar.", gs_results.len());
-
-        // Save Stage 1 CSV
-        let gs_path = output_folder.join("1_gscholar.csv");
-        save_csv(&gs_path, &gs_results, &["title", "author", "year", "venue", "article_url", "citations", "snippet"])?;
-
-        // ===========================================
-        // STAGE 2: Crossref Enrichment
-        // ===========================================
-        println!("\n--- Stage 2: Crossref Enrichment ---");
-
-        let crossref_client = CrossrefClient::new(3)?;
-        let titles: Vec<String> = gs_results.iter().map(|r| r.title.clone()).collect();
-
-        println!("Looking up {} titles (concurrent, 3 workers)...", titles.len());
-        let crossref_results: Vec<Option<rustgscholar::crossref::CrossrefMetadata>> = crossref_client.lookup_batch(&titles).await;
-
-        // Merge results
-        enriched_list = Vec::with_capacity(gs_results.len());
-        for (gs, cr) in gs_results.iter().zip(crossref_results.iter()) {
-            let enriched = EnrichedResult {
-                title: gs.title.clone(),
-                author: gs.author.clone(),
-                year: gs.year.clone(),
-                publication_date: cr.as_ref().map(|c| c.date.clone()).unwrap_or_default(), // Use crossref date
-                venue: gs.venue.clone(),
-                article_url: gs.article_url.clone(),
-                citations: gs.citations.clone(),
-                snippet: gs.snippet.clone(),
-                doi: cr.as_ref().map(|c| c.doi.clone()).unwrap_or_default(),
-                journal: cr.as_ref().map(|c| c.journal.clone()).unwrap_or_default(),
-                crossref_authors: cr.as_ref().map(|c| c.authors.clone()).unwrap_or_default(),
-                crossref_date: cr.as_ref().map(|c| c.date.clone()).unwrap_or_default(),
-                abstract_text: cr.as_ref().map(|c| c.abstract_text.clone()).unwrap_or_default(),
-                // Rankings (to be filled in Stage 3)
-                if_score: String::new(),
-                jci_score: String::new(),
-                sci_partition: String::new(),
-                sci_up_top: String::new(),
-                sci_base: String::new(),
-                sci_up: String::new(),
-            };
-            enriched_list.push(enriched);
+    run_pipeline(&keyword, pipeline, options, |builder| {
+        builder
+            .sources(sources)
+            .pages(pages)
+            .openalex_paging(openalex_per_page, std::time::Duration::from_secs_f64(openalex_page_delay.max(0.0)))
+            .ylo(ylo)
+            .auto_ylo(auto_ylo)
+            .proxy(proxy)
+            .mirror(mirror)
+            .scholar_api(scholar_api)
+            .sdt(&sdt)
+            .expand_query(expand_query)
+    })
+    .await
+}
+
+async fn run_enrich(args: EnrichArgs) -> Result<()> {
+    let options = effective_options(&args)?;
+    let EnrichArgs { input: csv_path, dois, pipeline } = args;
+    let (path, records) = match (csv_path, dois) {
+        (Some(path), _) => {
+            let records = input::load_csv(&path).context("Failed to load --input")?;
+            (path, records)
         }
+        (None, Some(path)) => {
+            let records = input::load_dois(&path).context("Failed to load --dois")?;
+            (path, records)
+        }
+        (None, None) => anyhow::bail!("--input or --dois is required"),
+    };
 
-        let matched = crossref_results.iter().filter(|r| r.is_some()).count();
-        println!("Crossref: {} / {} matched", matched, titles.len());
+    // The list's file name stands in for the search keyword
+    let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "input".to_string());
+    let report = run_pipeline(&name, pipeline, options, |builder| builder.input(records)).await?;
+    summarize(&report);
+    Ok(())
+}
 
-        // Save Stage 2 CSV
-        let cr_path = output_folder.join("2_crossref.csv");
-        save_csv(&cr_path, &enriched_list, &["title", "doi", "journal", "author", "crossref_authors", "crossref_date", "abstract_text", "article_url", "citations"])?;
+/// Options as parsed (after --config and the environment), secrets redacted, for manifest.json
+fn effective_options(args: &impl Serialize) -> Result<serde_json::Value> {
+    let mut options = serde_json::to_value(args).context("Failed to record options")?;
+    manifest::redact_options(&mut options, &REDACTOR.get().cloned().unwrap_or_default());
+    Ok(options)
+}
 
-    } else if source == "openalex" {
-        println!("\n--- Stage 1: OpenAlex Search (Enriched) ---");
+/// Run the pipeline with the options shared by `search` and `enrich`;
+/// `configure` sets up where Stage 1 gets its records, `options` are
+/// recorded in manifest.json
+async fn run_pipeline(
+    keyword: &str,
+    args: PipelineArgs,
+    options: serde_json::Value,
+    configure: impl FnOnce(PipelineBuilder) -> PipelineBuilder,
+) -> Result<pipeline::PipelineReport> {
+    let PipelineArgs {
+        output: output_dir,
+        output_name,
+        resume,
+        force,
+        reuse,
+        since,
+        stages,
+        skip_stage,
+        dry_run,
+        llm_price,
+        max_results,
+        min_citations,
+        work_types,
+        country,
+        exclude_reviews,
+        only_reviews,
+        easyscholar_key,
+        sciif,
+        jci,
+        sci,
+        sci_up_top,
+        sci_base,
+        sci_up,
+        zky,
+        pku,
+        cssci,
+        cscd,
+        eii,
+        ccf,
+        core,
+        rank_filter,
+        journal_include,
+        journal_exclude,
+        journal_aliases,
+        core_rankings,
+        dblp,
+        ranking_cache_days,
+        http_cache_days,
+        search_cache_hours,
+        s2_key,
+        title_match_threshold,
+        enrichers,
+        unpaywall_email,
+        abstract_chain,
+        scopus_key,
+        altmetric_key,
+        download_pdfs,
+        pdf_delay,
+        extract_fulltext,
+        grobid_url,
+        llm_fulltext,
+        screening,
+        llm_min_confidence,
+        uncertain_policy,
+        tags,
+        study_designs,
+        push_zotero,
+        zotero_key,
+        zotero_library_id,
+        zotero_group,
+        xlsx,
+        prisma_svg,
+        save_raw,
+        output_format,
+        stdout,
+    } = args;
+
+    // --stdout runs in a scratch folder with JSONL tables, then prints the final one
+    let output_format: output::OutputFormat = if stdout { output::OutputFormat::Jsonl } else { output_format.parse()? };
+    let llm_price: Option<estimate::LlmPrice> = llm_price.as_deref().map(str::parse).transpose().context("Invalid --llm-price")?;
 
-        let query_options = openalex::QueryOptions {
-            pages: pages.clone(),
-            ylo: Some(ylo_val),
-            yhi: None,
-            all_results: true,
-        };
+    // Create output folder
+    let (output_folder, resume) = match resume {
+        Some(folder) => (folder, true),
+        None if stdout => (
+            std::env::temp_dir().join(format!("rustgscholar_{}_{}", Local::now().format("%Y%m%d_%H%M%S"), std::process::id())),
+            false,
+        ),
+        None if dry_run => (output_dir.join(run_folder_name(keyword)), false),
+        None => {
+            let fingerprint = manifest::fingerprint(keyword, &options);
+            choose_run_folder(&output_dir, output_name.as_deref(), keyword, &fingerprint, force, reuse)?
+        }
+    };
 
-        let oa_results = openalex::query(&keyword, &query_options).await?;
+    let mut stage_selection = if stages.is_empty() {
+        pipeline::StageSelection::default()
+    } else {
+        pipeline::StageSelection::only(&pipeline::StageSelection::parse_list(&stages).context("Invalid --stages")?)
+    };
+    stage_selection = stage_selection.skip(&pipeline::StageSelection::parse_list(&skip_stage).context("Invalid --skip-stage")?);
+
+    let http_cache_ttl = (http_cache_days > 0).then(|| std::time::Duration::from_secs(http_cache_days * 24 * 60 * 60));
+    let enricher_settings = enrich::EnricherSettings {
+        easyscholar_key: easyscholar_key.clone(),
+        s2_api_key: s2_key.clone(),
+        unpaywall_email,
+        scopus_key,
+        altmetric_key,
+        cache_ttl: http_cache_ttl,
+    };
+    let mut builder = configure(PipelineBuilder::new(keyword, &output_folder))
+        .options(options)
+        .resume(resume)
+        .stages(stage_selection)
+        .max_results(max_results)
+        .min_citations(min_citations)
+        .work_types(work_type::parse_list(&work_types).context("Invalid --work-types")?)
+        .countries(openalex::parse_country_codes(&country).context("Invalid --country")?)
+        .reviews(match (exclude_reviews, only_reviews) {
+            (true, _) => ReviewPolicy::Exclude,
+            (_, true) => ReviewPolicy::Only,
+            _ => ReviewPolicy::Keep,
+        })
+        .s2_api_key(s2_key)
+        .http_cache(
+            http_cache_ttl,
+            (search_cache_hours > 0).then(|| std::time::Duration::from_secs(search_cache_hours * 60 * 60)),
+        )
+        .title_match_threshold(title_match_threshold)
+        .min_confidence(llm_min_confidence)
+        .uncertain_policy(uncertain_policy.parse()?)
+        .output_format(output_format)
+        .xlsx(xlsx && !stdout)
+        .prisma_svg(prisma_svg)
+        .save_raw(save_raw)
+        .on_progress(|line| status!("{}", line));
+
+    for name in &enrichers {
+        builder = builder.enricher(enrich::builtin(name, &enricher_settings).context("Invalid --enrichers")?);
+    }
+    if let Some(ref names) = abstract_chain {
+        let sources = abstracts::AbstractSource::parse_list(names, &enricher_settings).context("Invalid --abstract-chain")?;
+        let chain = abstracts::AbstractChain::new(sources, &enricher_settings).context("Invalid --abstract-chain")?;
+        builder = builder.enricher(Box::new(chain));
+    }
+    if download_pdfs {
+        let mut downloader = pdf::PdfDownloader::new()?.with_domain_interval(std::time::Duration::from_secs_f64(pdf_delay.max(0.0)));
+        if let Some(ref email) = enricher_settings.unpaywall_email {
+            downloader = downloader.with_unpaywall(unpaywall::UnpaywallClient::new(email).context("Invalid --unpaywall-email")?);
+        }
+        builder = builder.download_pdfs(downloader);
+    }
+    if extract_fulltext {
+        builder = builder.extract_fulltext(grobid::GrobidClient::new(&grobid_url)?);
+    }
+    builder = builder.llm_fulltext(llm_fulltext);
 
-        if oa_results.is_empty() {
-            println!("No results from OpenAlex.");
-            return Ok(());
+    if let Some(ref folder) = since {
+        builder = builder.since(incremental::PreviousRun::load(folder).context("Failed to load the --since run folder")?);
+    }
+
+    if let Some(key) = easyscholar_key {
+        let mut ranking = pipeline::RankingOptions::new(&key);
+        if ranking_cache_days > 0 {
+            ranking.cache_ttl = Some(std::time::Duration::from_secs(ranking_cache_days * 24 * 60 * 60));
         }
+        if let Some(ref path) = journal_aliases {
+            ranking.aliases = rankings::load_aliases(path).context("Failed to load --journal-aliases")?;
+        }
+        if let Some(ref path) = core_rankings {
+            ranking.core_rankings = Some(CoreRankings::load(path).context("Failed to load --core-rankings")?);
+        }
+        ranking.dblp = dblp;
 
-        println!("Found {} results from OpenAlex.", oa_results.len());
-
-        // Save Stage 1 CSV with all OpenAlex fields
-        let oa_path = output_folder.join("1_openalex.csv");
-        save_csv(&oa_path, &oa_results, &[
-            "title", "author", "year", "publication_date", "venue", "source_type", "doi",
-            "article_url", "pdf_url", "citations", "is_oa", "oa_status", "oa_url",
-            "language", "work_type", "keywords", "primary_topic",
-            "referenced_works", "related_works",
-            "referenced_works_count", "related_works_count", "locations_count",
-            "snippet", "openalex_id"
-        ])?;
-
-        // Convert to EnrichedResult for Stage 3
-        enriched_list = oa_results.into_iter().map(|oa| {
-            EnrichedResult {
-                title: oa.title,
-                author: oa.author,
-                year: oa.year,
-                publication_date: oa.publication_date, // ISO date from OpenAlex
-                venue: oa.venue.clone(),
-                article_url: oa.article_url,
-                citations: oa.citations,
-                snippet: oa.snippet.clone(),
-                doi: oa.doi,
-                journal: oa.venue, // Map venue to journal for ranking lookup
-                crossref_authors: String::new(),
-                crossref_date: String::new(),
-                abstract_text: oa.snippet, // Use snippet as abstract
-                if_score: String::new(),
-                jci_score: String::new(),
-                sci_partition: String::new(),
-                sci_up_top: String::new(),
-                sci_base: String::new(),
-                sci_up: String::new(),
+        // Individual filter flags are sugar for an AND of comparisons
+        let mut filters: Vec<RankFilter> = Vec::new();
+        if let Some(threshold) = sciif {
+            filters.push(RankFilter::at_least("sciif", threshold));
+        }
+        if let Some(threshold) = jci {
+            filters.push(RankFilter::at_least("jci", threshold));
+        }
+        for (key, pattern) in [
+            ("sci", sci),
+            ("sciUpTop", sci_up_top),
+            ("sciBase", sci_base),
+            ("sciUp", sci_up),
+            ("zky", zky),
+            ("pku", pku),
+            ("cssci", cssci),
+            ("cscd", cscd),
+            ("eii", eii),
+            ("ccf", ccf),
+            ("core", core),
+        ] {
+            if let Some(pattern) = pattern {
+                filters.push(RankFilter::contains(key, &pattern));
             }
-        }).collect();
+        }
+        if let Some(ref expr) = rank_filter {
+            filters.push(RankFilter::parse(expr).context("Invalid --rank-filter expression")?);
+        }
+        ranking.filter = RankFilter::all(filters);
+
+        ranking.journal_include = journal_include
+            .as_deref()
+            .map(JournalList::load)
+            .transpose()
+            .context("Failed to load --journal-include")?;
+        ranking.journal_exclude = journal_exclude
+            .as_deref()
+            .map(JournalList::load)
+            .transpose()
+            .context("Failed to load --journal-exclude")?;
+
+        builder = builder.ranking(ranking);
+    }
 
-    } else {
-        anyhow::bail!("Invalid source: {}", source);
+    builder = screening.configure(builder)?;
+    if let Some(ref tags) = tags {
+        builder = builder.tags(tagging::parse_categories(tags));
+    }
+    if let Some(ref designs) = study_designs {
+        builder = builder.study_designs(tagging::parse_categories(designs));
+    }
+    if let (Some(collection), Some(key), Some(library_id)) = (&push_zotero, &zotero_key, &zotero_library_id) {
+        let client = zotero::ZoteroClient::new(key, library_id, zotero_group).context("Failed to create Zotero client")?;
+        builder = builder.zotero(client, collection);
     }
 
-    // ===========================================
-    // STAGE 3: EasyScholar Ranking Enrichment
-    // ===========================================
-    if let Some(key) = easyscholar_key {
-        println!("\n--- Stage 3: EasyScholar Ranking ---");
+    let pipeline = builder.build();
+    if dry_run {
+        let estimate = pipeline.estimate(llm_price).await?;
+        status!("\n{}", estimate.render());
+        return Ok(pipeline::PipelineReport::default());
+    }
+    run_log().open(&output_folder).context("Failed to open run.log")?;
+    let report = pipeline.run().await?;
+
+    if stdout {
+        if let Some(path) = &report.final_table {
+            status!("\n✓ Pipeline complete. Streaming {:?}", path.file_name().unwrap_or_default());
+            let mut file = std::fs::File::open(path).context("Failed to open final records")?;
+            std::io::copy(&mut file, &mut std::io::stdout().lock()).context("Failed to write records to stdout")?;
+        } else {
+            status!("\n✓ Pipeline complete. No records to stream.");
+        }
+        run_log().close();
+        std::fs::remove_dir_all(&output_folder).context("Failed to remove scratch folder")?;
+        return Ok(report);
+    }
 
-        let ranking_client = RankingClient::new(key)?;
+    status!("\n✓ Pipeline complete. Results in: {}", output_folder.display());
+    run_log().close();
+    Ok(report)
+}
 
-        let filter_active = sciif.is_some()
-            || jci.is_some()
-            || sci.is_some()
-            || sci_up_top.is_some()
-            || sci_base.is_some()
-            || sci_up.is_some();
+/// Run folder of a new run and whether it continues an earlier one
+///
+/// A run with identical options already in `output_dir` (in the named
+/// folder only, when `output_name` is given) is continued with `reuse`,
+/// started over with `force`, else the user is asked (outside a terminal
+/// the run stops instead).
+fn choose_run_folder(
+    output_dir: &Path,
+    output_name: Option<&str>,
+    keyword: &str,
+    fingerprint: &str,
+    force: bool,
+    reuse: bool,
+) -> Result<(PathBuf, bool)> {
+    let folder = match output_name {
+        Some(name) if safe_keyword(name).is_empty() => anyhow::bail!("Invalid --output-name {:?}", name),
+        Some(name) => output_dir.join(safe_keyword(name)),
+        None => output_dir.join(run_folder_name(keyword)),
+    };
+    if force {
+        return Ok((folder, false));
+    }
 
-        // Step 1: Collect unique journal names
-        let unique_journals: std::collections::HashSet<String> = enriched_list
-            .iter()
-            .map(|item| item.journal.trim().to_string())
-            .filter(|j| !j.is_empty())
-            .collect();
-
-        println!("Found {} unique journals to query", unique_journals.len());
-
-        // Step 2: Batch query all unique journals
-        use std::collections::HashMap;
-        let mut journal_rankings: HashMap<String, Option<rustgscholar::rankings::RankingMetrics>> = HashMap::new();
-        
-        for (idx, journal) in unique_journals.iter().enumerate() {
-            if (idx + 1) % 50 == 0 {
-                println!("  Queried {}/{} journals...", idx + 1, unique_journals.len());
-            }
-            let metrics = ranking_client.get_rank(journal).await;
-            journal_rankings.insert(journal.clone(), metrics);
+    let identical = match output_name {
+        // An explicit name is honoured: only a run in that folder is continued
+        Some(_) => manifest::Manifest::load(&folder)
+            .context("Failed to read the run manifest")?
+            .filter(|manifest| !fingerprint.is_empty() && manifest.fingerprint == fingerprint)
+            .map(|manifest| (folder.clone(), manifest)),
+        None => manifest::find_identical(output_dir, fingerprint).context("Failed to look for identical runs")?,
+    };
+    if let Some((previous, manifest)) = identical {
+        let state = if manifest.finished_at.is_some() { "finished" } else { "interrupted" };
+        if reuse || confirm_reuse(&previous, state)? {
+            status!("Continuing the identical {} run in {}", state, previous.display());
+            return Ok((previous, true));
         }
+    }
+    if folder.join(manifest::MANIFEST_FILE).exists() {
+        anyhow::bail!("{} already holds a different run (use --force to overwrite it, or another --output-name)", folder.display());
+    }
+    Ok((folder, false))
+}
 
-        println!("Completed querying {} journals", unique_journals.len());
-
-        // Step 3: Assign rankings to all articles
-        let mut result_list: Vec<EnrichedResult> = Vec::new();
-
-        for mut item in enriched_list {
-            let journal = item.journal.trim().to_string();
-            
-            if journal.is_empty() {
-                if !filter_active {
-                    result_list.push(item);
-                }
-                continue;
-            }
+/// Ask whether to continue an identical earlier run (`Ok(false)`: start a new one)
+fn confirm_reuse(previous: &Path, state: &str) -> Result<bool> {
+    use std::io::{BufRead, IsTerminal, Write};
 
-            let metrics = match journal_rankings.get(&journal).cloned().flatten() {
-                Some(m) => m,
-                None => {
-                    if !filter_active {
-                        result_list.push(item);
-                    }
-                    continue;
-                }
-            };
+    let hint = "use --reuse to continue it or --force to start a new run";
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("An identical {} run already exists in {} ({})", state, previous.display(), hint);
+    }
+    eprint!(
+        "An identical {} run already exists in {}.\n[r]euse its completed stages, start a [n]ew run, or [a]bort? ",
+        state,
+        previous.display()
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "r" | "reuse" => Ok(true),
+        "n" | "new" => Ok(false),
+        _ => anyhow::bail!("Aborted ({})", hint),
+    }
+}
 
-            // Check filters
-            let mut keep = true;
+/// Run folder name: timestamp plus the keyword reduced to safe characters
+fn run_folder_name(keyword: &str) -> String {
+    format!("{}_{}", Local::now().format("%Y%m%d_%H%M%S"), safe_keyword(keyword))
+}
 
-            if filter_active {
-                if let Some(threshold) = sciif {
-                    if !RankingClient::passes_numeric_filter(metrics.sciif.as_deref(), threshold) {
-                        keep = false;
-                    }
-                }
+fn safe_keyword(keyword: &str) -> String {
+    keyword
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect::<String>()
+        .trim()
+        .replace(' ', "_")
+}
 
-                if keep {
-                    if let Some(threshold) = jci {
-                        if !RankingClient::passes_numeric_filter(metrics.jci.as_deref(), threshold) {
-                            keep = false;
-                        }
-                    }
-                }
+// ============================================================================
+// Watch
+// ============================================================================
 
-                if keep {
-                    if let Some(ref pattern) = sci {
-                        if !RankingClient::passes_string_filter(metrics.sci.as_deref(), pattern) {
-                            keep = false;
-                        }
-                    }
-                }
+async fn run_watch(args: WatchArgs) -> Result<()> {
+    let WatchArgs { search, interval, once, notify_webhook, notify_slack, notify_email } = args;
+    let interval = watch::parse_interval(&interval).context("Invalid --interval")?;
+    if search.pipeline.resume.is_some() || search.pipeline.stdout || search.pipeline.dry_run || search.pipeline.output_name.is_some() {
+        anyhow::bail!("watch does not support --resume, --stdout, --dry-run or --output-name");
+    }
+    let notifiers: Vec<watch::Notifier> = [
+        notify_webhook.map(watch::Notifier::Webhook),
+        notify_slack.map(watch::Notifier::Slack),
+        notify_email.map(watch::Notifier::Email),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // Continue from the latest run of this keyword, so restarts and --once runs chain
+    let mut previous = match &search.pipeline.since {
+        Some(folder) => Some(folder.clone()),
+        None => watch::latest_run(&search.pipeline.output, &safe_keyword(&search.keyword))?,
+    };
 
-                if keep {
-                    if let Some(ref pattern) = sci_up_top {
-                        if !RankingClient::passes_string_filter(metrics.sci_up_top.as_deref(), pattern) {
-                            keep = false;
-                        }
-                    }
-                }
+    loop {
+        let mut args = search.clone();
+        args.pipeline.since = previous.clone();
+        // An update that found nothing repeats the same options next time
+        args.pipeline.force = true;
+        match &previous {
+            Some(folder) => status!("\n=== Update of {} ===", folder.display()),
+            None => status!("\n=== Baseline run (later runs report new papers) ==="),
+        }
 
-                if keep {
-                    if let Some(ref pattern) = sci_base {
-                        if !RankingClient::passes_string_filter(metrics.sci_base.as_deref(), pattern) {
-                            keep = false;
+        match search_pipeline(args).await {
+            Ok(report) => {
+                let update = watch::Update::from_report(&search.keyword, &report);
+                if previous.is_some() && !update.papers.is_empty() {
+                    status!("{}", update.subject());
+                    for notifier in &notifiers {
+                        if let Err(e) = notifier.send(&update).await {
+                            status!("Notification failed ({:?}): {}", notifier, e);
                         }
                     }
+                } else if previous.is_some() {
+                    status!("No new papers.");
                 }
-
-                if keep {
-                    if let Some(ref pattern) = sci_up {
-                        if !RankingClient::passes_string_filter(metrics.sci_up.as_deref(), pattern) {
-                            keep = false;
-                        }
-                    }
+                // A run that found nothing has no records for the next update to compare against
+                if watch::has_records(&report.output_folder) {
+                    previous = Some(report.output_folder);
                 }
             }
-
-            if keep {
-                item.if_score = metrics.sciif.unwrap_or_default();
-                item.jci_score = metrics.jci.unwrap_or_default();
-                item.sci_partition = metrics.sci.unwrap_or_default();
-                item.sci_up_top = metrics.sci_up_top.unwrap_or_default();
-                item.sci_base = metrics.sci_base.unwrap_or_default();
-                item.sci_up = metrics.sci_up.unwrap_or_default();
-                result_list.push(item);
-            }
+            Err(e) if once => return Err(e),
+            Err(e) => status!("Run failed, retrying at the next interval: {:#}", e),
         }
 
-        if filter_active {
-            println!("Filtered: {} results", result_list.len());
-        } else {
-            println!("Enriched: {} results with ranking data", result_list.len());
+        if once {
+            return Ok(());
         }
+        let next = Local::now() + chrono::Duration::from_std(interval).unwrap_or_else(|_| chrono::Duration::days(7));
+        status!("\nNext run at {}", next.format("%Y-%m-%d %H:%M"));
+        tokio::time::sleep(interval).await;
+    }
+}
 
-        // Save Stage 3 CSV
-        let es_path = output_folder.join("3_easyscholar.csv");
-        save_csv(&es_path, &result_list, &["title", "if_score", "jci_score", "sci_partition", "journal", "doi", "author", "abstract_text", "article_url"])?;
-
-        // ===========================================
-        // STAGE 4: Semantic Scholar Enrichment
-        // ===========================================
-        if !result_list.is_empty() {
-            println!("\n--- Stage 4: Semantic Scholar Lookup ---");
-
-            // Extract DOIs from result_list
-            let dois: Vec<String> = result_list
-                .iter()
-                .map(|r| r.doi.clone())
-                .filter(|d: &String| !d.is_empty())
-                .collect();
+// ============================================================================
+// Filter
+// ============================================================================
 
-            if dois.is_empty() {
-                println!("No DOIs found in filtered results, skipping Semantic Scholar.");
-            } else {
-                println!("Looking up {} papers by DOI...", dois.len());
-
-                // Batch lookup (no API key for now - can be added later)
-                match semanticscholar::batch_lookup(&dois, None).await {
-                    Ok(ss_results) => {
-                        println!("Found {} papers in Semantic Scholar.", ss_results.len());
-
-                        // Save Stage 4 CSV with DOI as key for cross-filtering
-                        let ss_path = output_folder.join("4_semanticscholar.csv");
-                        save_csv(&ss_path, &ss_results, &[
-                            "doi", "title", "ss_abstract", "tldr", "ss_url", "is_oa", "oa_pdf_url", "paper_id", "embedding"
-                        ])?;
-
-                        // ===========================================
-                        // STAGE 5: Unified CSV Generation
-                        // ===========================================
-                        println!("\n--- Stage 5: Creating Unified Dataset ---");
-
-                        // Convert result_list to EnrichedInput for unified module
-                        let enriched_inputs: Vec<unified::EnrichedInput> = result_list.iter()
-                            .map(|r| unified::EnrichedInput {
-                                title: r.title.clone(),
-                                author: r.author.clone(),
-                                year: r.year.clone(),
-                                publication_date: r.publication_date.clone(),
-                                doi: r.doi.clone(),
-                                article_url: r.article_url.clone(),
-                                abstract_text: r.abstract_text.clone(),
-                                journal: r.journal.clone(),
-                                if_score: r.if_score.clone(),
-                                jci_score: r.jci_score.clone(),
-                                sci_partition: r.sci_partition.clone(),
-                            })
-                            .collect();
-
-                        // Generate unified results using the module
-                        let unified_results = unified::generate_unified(&enriched_inputs, &ss_results);
-
-                        // Save Stage 5 CSV
-                        let unified_path = output_folder.join("5_unified.csv");
-                        save_csv(&unified_path, &unified_results, unified::UNIFIED_COLUMNS)?;
-                        println!("Created unified dataset: {} papers", unified_results.len());
-
-                        // ===========================================
-                        // STAGE 6: LLM Relevance Filtering
-                        // ===========================================
-                        if let Some(ref base_url) = llm_base_url {
-                            if let Some(ref api_key) = llm_key {
-                                println!("\n--- Stage 6: LLM Relevance Filtering ---");
-                                
-                                let llm_config = llm_filter::LlmConfig {
-                                    base_url: base_url.clone(),
-                                    api_key: api_key.clone(),
-                                    model: llm_model.clone(),
-                                    filter_help: filter_help.clone().unwrap_or_default(),
-                                };
-
-                                println!(
-                                    "Filtering {} papers with {} (max 10 concurrent requests)...",
-                                    unified_results.len(),
-                                    llm_config.model
-                                );
-
-                                match llm_filter::filter_papers(&llm_config, &unified_results).await {
-                                    Ok((filter_results, usage)) => {
-                                        // Save filtered results
-                                        let filtered_path = output_folder.join("6_llm_filtered.csv");
-                                        save_csv(&filtered_path, &filter_results, &[
-                                            "id", "title", "label", "confidence", "evidence", "reason"
-                                        ])?;
-
-                                        // Count by label
-                                        let relevant = filter_results.iter().filter(|r| r.label == "relevant").count();
-                                        let irrelevant = filter_results.iter().filter(|r| r.label == "irrelevant").count();
-                                        let uncertain = filter_results.iter().filter(|r| r.label == "uncertain").count();
-
-                                        println!(
-                                            "LLM filtering complete: {} relevant, {} irrelevant, {} uncertain",
-                                            relevant, irrelevant, uncertain
-                                        );
-
-                                        // Log token usage
-                                        let usage_path = output_folder.join("6_token_usage.log");
-                                        let usage_line = format!(
-                                            "{},{},{},{}",
-                                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                                            usage.prompt_tokens,
-                                            usage.completion_tokens,
-                                            usage.total_tokens
-                                        );
-                                        std::fs::write(&usage_path, &usage_line)
-                                            .context("Failed to write token usage log")?;
-                                        println!(
-                                            "Token usage: {} prompt + {} completion = {} total",
-                                            usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
-                                        );
-
-                                        // ===========================================
-                                        // STAGE 7: Relevant Papers Only
-                                        // ===========================================
-                                        println!("\n--- Stage 7: Extracting Relevant Papers ---");
-
-                                        // Get DOIs of relevant papers
-                                        let relevant_dois: std::collections::HashSet<String> = filter_results
-                                            .iter()
-                                            .filter(|r| r.label == "relevant")
-                                            .map(|r| r.id.to_lowercase())
-                                            .collect();
-
-                                        // Filter unified_results to only keep relevant papers
-                                        let relevant_papers: Vec<&unified::UnifiedResult> = unified_results
-                                            .iter()
-                                            .filter(|u| relevant_dois.contains(&u.doi.to_lowercase()))
-                                            .collect();
-
-                                        if !relevant_papers.is_empty() {
-                                            // Create a new struct for CSV output with full data
-                                            #[derive(serde::Serialize)]
-                                            struct RelevantPaper {
-                                                title: String,
-                                                author: String,
-                                                date: String,
-                                                doi: String,
-                                                article_url: String,
-                                                pdf_url: String,
-                                                abstract_text: String,
-                                                tldr: String,
-                                                journal: String,
-                                                if_score: String,
-                                                jci_score: String,
-                                                sci_partition: String,
-                                                confidence: f64,
-                                                evidence: String,
-                                                reason: String,
-                                            }
-
-                                            // Join filter_results with unified_results
-                                            let filter_map: std::collections::HashMap<String, &llm_filter::FilterResult> = 
-                                                filter_results.iter()
-                                                    .filter(|r| r.label == "relevant")
-                                                    .map(|r| (r.id.to_lowercase(), r))
-                                                    .collect();
-
-                                            let relevant_output: Vec<RelevantPaper> = relevant_papers
-                                                .iter()
-                                                .filter_map(|u| {
-                                                    filter_map.get(&u.doi.to_lowercase()).map(|f| RelevantPaper {
-                                                        title: u.title.clone(),
-                                                        author: u.author.clone(),
-                                                        date: u.date.clone(),
-                                                        doi: u.doi.clone(),
-                                                        article_url: u.article_url.clone(),
-                                                        pdf_url: u.pdf_url.clone(),
-                                                        abstract_text: u.abstract_text.clone(),
-                                                        tldr: u.tldr.clone(),
-                                                        journal: u.journal.clone(),
-                                                        if_score: u.if_score.clone(),
-                                                        jci_score: u.jci_score.clone(),
-                                                        sci_partition: u.sci_partition.clone(),
-                                                        confidence: f.confidence,
-                                                        evidence: f.evidence.clone(),
-                                                        reason: f.reason.clone(),
-                                                    })
-                                                })
-                                                .collect();
-
-                                            let relevant_path = output_folder.join("7_relevant.csv");
-                                            save_csv(&relevant_path, &relevant_output, &[
-                                                "title", "author", "date", "doi", "article_url", "pdf_url",
-                                                "abstract_text", "tldr", "journal", "if_score", "jci_score", 
-                                                "sci_partition", "confidence", "evidence", "reason"
-                                            ])?;
-                                            println!("Saved {} relevant papers to 7_relevant.csv", relevant_output.len());
-                                        } else {
-                                            println!("No relevant papers found.");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        println!("LLM filtering failed: {}", e);
-                                    }
-                                }
-                            } else {
-                                println!("\n--- Stage 6: Skipped (--llm-key not provided) ---");
-                            }
-                        } else {
-                            println!("\n--- Stage 6: Skipped (no --llm-base-url provided) ---");
-                        }
-                    }
-                    Err(e) => {
-                        println!("Semantic Scholar lookup failed: {}", e);
-                    }
-                }
-            }
-        }
-    } else {
-        println!("\n--- Stage 3: Skipped (no --easyscholar-key provided) ---");
+async fn run_filter(args: FilterArgs) -> Result<()> {
+    let options = effective_options(&args)?;
+    let FilterArgs { input: csv_path, columns, output, screening } = args;
+
+    let mapping = input::parse_column_mapping(&columns).context("Invalid --column")?;
+    let records = input::load_unified(&csv_path, &mapping).context("Failed to load --input")?;
+    let name = csv_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "input".to_string());
+    let output_folder = output.join(run_folder_name(&format!("filter {}", name)));
+    status!("Loaded {} papers from {}", records.len(), csv_path.display());
+
+    let builder = PipelineBuilder::new(&name, &output_folder)
+        .unified(records)
+        .stages(pipeline::StageSelection::only(&[6]))
+        .options(options)
+        .on_progress(|line| status!("{}", line));
+    let builder = screening.configure(builder)?;
+    if let Some(reason) = builder.llm_unavailable_reason() {
+        anyhow::bail!("filter needs an LLM ({})", reason);
     }
 
-    println!("\n✓ Pipeline complete. Results in: {}", output_folder.display());
+    run_log().open(&output_folder).context("Failed to open run.log")?;
+    let report = builder.build().run().await?;
+    status!("\n✓ Screening complete: {} verdicts in {}", report.verdicts.len(), output_folder.display());
+    run_log().close();
+    summarize(&report);
     Ok(())
 }
 
@@ -778,65 +1817,70 @@ fn parse_pages(pages_str: &str) -> Result<Vec<i32>> {
     }
 }
 
-/// Enriched result combining Google Scholar and Crossref data
-#[derive(Debug, Serialize, Deserialize)]
-struct EnrichedResult {
-    title: String,
-    author: String,
-    year: String,
-    publication_date: String,  // ISO date (YYYY-MM-DD) from OpenAlex
-    venue: String,
-    article_url: String,
-    citations: String,
-    snippet: String,
-    doi: String,
-    journal: String,
-    crossref_authors: String,
-    crossref_date: String,
-    abstract_text: String,
-    if_score: String,
-    jci_score: String,
-    sci_partition: String,
-    sci_up_top: String,
-    sci_base: String,
-    sci_up: String,
-}
-
-/// Save data to CSV file
-fn save_csv<T: Serialize>(path: &std::path::Path, data: &[T], _priority_fields: &[&str]) -> Result<()> {
-    if data.is_empty() {
-        println!("No data to save to {:?}", path);
-        return Ok(());
-    }
-
-    let mut wtr = csv::WriterBuilder::new()
-        .has_headers(true)
-        .from_path(path)
-        .context("Failed to create CSV writer")?;
-
-    for item in data {
-        wtr.serialize(item).context("Failed to write CSV record")?;
-    }
-
-    wtr.flush().context("Failed to flush CSV")?;
-    println!("Saved: {:?}", path);
-    Ok(())
-}
-
 // ============================================================================
 // HTTP Server
 // ============================================================================
 
-async fn run_server(host: String, port: u16) -> Result<()> {
+async fn run_server(args: ServeArgs) -> Result<()> {
+    let ServeArgs { port, ref host, ref output_dir, max_jobs, .. } = args;
     info!(host = %host, port = port, "Starting HTTP server");
     println!("Starting server at http://{}:{}", host, port);
 
-    // Shared state (could add database connections, etc.)
-    let app_state = Arc::new(AppState::default());
+    let keys = Arc::new(load_api_keys(&args)?);
+    if keys.is_empty() {
+        let loopback = host.parse::<std::net::IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(host == "localhost");
+        if !loopback {
+            warn!(host = %host, "No API keys configured: the server is open to anyone who can reach it");
+        }
+    } else {
+        info!("API key authentication enabled");
+    }
+
+    // Concurrent jobs share one request budget per upstream service
+    let overrides = args
+        .upstream_limit
+        .iter()
+        .map(|value| throttle::parse_override(value))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    throttle::configure(&overrides);
+
+    let results = Arc::new(
+        store::ResultStore::open(output_dir)
+            .with_context(|| format!("Failed to index job results in {:?}", output_dir))?,
+    );
+    let jobs = jobs::JobQueue::start(output_dir, max_jobs, job_runner(results.clone()))
+        .with_context(|| format!("Failed to load jobs from {:?}", output_dir.join(jobs::JOBS_FOLDER)))?;
+    let health = health_checker(&args)?;
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = args.grpc_port {
+        let addr: SocketAddr = format!("{}:{}", host, grpc_port).parse().context("Invalid host:grpc-port")?;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        println!("gRPC listening on {}", addr);
+        let service = rustgscholar::grpc::GrpcService::new(jobs.clone(), results.clone(), keys.clone());
+        tokio::spawn(async move {
+            if let Err(e) = service.serve(listener).await {
+                error!(error = %e, "gRPC server stopped");
+            }
+        });
+    }
+
+    let app_state = Arc::new(AppState { jobs, health, results, output_dir: output_dir.clone() });
 
     let app = Router::new()
+        .route("/", get(ui_handler))
+        .route("/assets/{*path}", get(asset_handler))
         .route("/health", get(health_handler))
+        .route("/health/ready", get(ready_handler))
         .route("/search", post(search_handler))
+        .route("/jobs", post(submit_job_handler).get(list_jobs_handler))
+        .route("/jobs/{id}", get(job_handler))
+        .route("/jobs/{id}/files", get(job_files_handler))
+        .route("/jobs/{id}/files/{name}", get(job_file_handler))
+        .route("/jobs/{id}/export", get(job_export_handler))
+        .route("/papers", get(papers_handler))
+        .route("/openapi.json", get(openapi_handler))
+        .layer(axum::middleware::from_fn_with_state(keys, auth::middleware))
         .with_state(app_state);
 
     let addr: SocketAddr = format!("{}:{}", host, port)
@@ -853,9 +1897,69 @@ async fn run_server(host: String, port: u16) -> Result<()> {
     Ok(())
 }
 
-#[derive(Default)]
 struct AppState {
-    // Add shared state here (e.g., rate limiters, caches)
+    jobs: Arc<jobs::JobQueue>,
+    health: health::HealthChecker,
+    results: Arc<store::ResultStore>,
+    output_dir: PathBuf,
+}
+
+/// Tenant of an authenticated request (none without API keys)
+fn tenant(key: &Option<Extension<auth::ApiKey>>) -> Option<&str> {
+    key.as_ref().map(|Extension(key)| key.tenant.as_str())
+}
+
+/// Readiness probes, with the API keys job runs use
+fn health_checker(args: &ServeArgs) -> Result<health::HealthChecker> {
+    let provider: llm_filter::LlmProvider = args.llm_provider.parse()?;
+    let llm = args
+        .llm_base_url
+        .clone()
+        .or_else(|| provider.default_base_url().map(String::from))
+        .map(|base_url| health::LlmEndpoint { provider, base_url, api_key: std::env::var("LLM_API_KEY").ok() });
+    Ok(health::HealthChecker::new(&args.ready_probes)?
+        .s2_api_key(std::env::var("S2_API_KEY").ok())
+        .easyscholar_key(std::env::var("EASYSCHOLAR_KEY").ok())
+        .llm(llm))
+}
+
+/// API keys of `--api-keys` and `GSCHOLAR_API_KEYS`
+fn load_api_keys(args: &ServeArgs) -> Result<auth::KeyStore> {
+    let defaults = auth::Limits { rate_per_minute: args.rate_limit, daily_quota: args.daily_quota };
+    let mut keys = match &args.api_keys {
+        Some(path) => auth::KeyStore::load_file(path, defaults)
+            .with_context(|| format!("Failed to load API keys from {:?}", path))?,
+        None => Vec::new(),
+    };
+    if let Ok(value) = std::env::var(auth::API_KEYS_ENV) {
+        keys.extend(auth::KeyStore::parse_list(&value, defaults)?);
+    }
+    Ok(auth::KeyStore::new(keys)?)
+}
+
+/// Pipeline runs of `POST /jobs`, with API keys from the server's environment;
+/// finished runs are added to the result store
+fn job_runner(results: Arc<store::ResultStore>) -> jobs::JobRunner {
+    Arc::new(move |request: jobs::JobRequest, context: jobs::JobContext| {
+        let results = results.clone();
+        Box::pin(async move {
+            let progress = context.progress.clone();
+            let mut builder = request
+                .pipeline(&context.output_folder)?
+                .resume(context.resume)
+                .cache_dir(context.workspace.as_ref().map(|w| w.join(jobs::WORKSPACE_CACHE_FOLDER)))
+                .cookie_path(context.workspace.as_ref().map(|w| w.join(jobs::WORKSPACE_COOKIE_FILE)))
+                .on_progress(move |line| progress(line));
+            if let Ok(key) = std::env::var("EASYSCHOLAR_KEY") {
+                builder = builder.ranking(pipeline::RankingOptions::new(&key));
+            }
+            let report = builder.build().run().await?;
+            if let Err(e) = results.add_run(&context.output_folder, context.tenant.as_deref()) {
+                warn!(id = %context.id, error = %e, "Failed to add job results to the store");
+            }
+            Ok(jobs::JobResult::from(&report))
+        })
+    })
 }
 
 /// Health check endpoint
@@ -863,6 +1967,13 @@ async fn health_handler() -> &'static str {
     "OK"
 }
 
+/// Readiness: 200 when every probed upstream service answers, 503 otherwise
+async fn ready_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<health::Readiness>) {
+    let report = state.health.readiness().await;
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+
 /// Search request body
 #[derive(Debug, Deserialize)]
 struct SearchRequest {
@@ -885,42 +1996,439 @@ struct SearchResponse {
     results: Vec<gscholar::ScholarResult>,
 }
 
+/// OpenAPI 3 document of these routes
+async fn openapi_handler() -> Json<serde_json::Value> {
+    Json(api::openapi())
+}
+
+/// A JSON body, or a validation error envelope when it does not parse
+fn json_body<T>(payload: std::result::Result<Json<T>, JsonRejection>) -> std::result::Result<T, api::ApiError> {
+    payload
+        .map(|Json(body)| body)
+        .map_err(|rejection| api::ApiError::new(rejection.status(), "validation", rejection.body_text()))
+}
+
 /// Search endpoint handler
 async fn search_handler(
-    State(_state): State<Arc<AppState>>,
-    Json(req): Json<SearchRequest>,
-) -> Json<SearchResponse> {
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    payload: std::result::Result<Json<SearchRequest>, JsonRejection>,
+) -> std::result::Result<Json<SearchResponse>, api::ApiError> {
+    let req = json_body(payload)?;
     info!(keyword = %req.keyword, pages = ?req.pages, "Search request");
 
+    // Tenants search with the cookie jar of their workspace
     let options = gscholar::QueryOptions {
         proxy: req.proxy,
         pages: req.pages,
         ylo: req.ylo,
+        cookie_path: tenant(&key).map(|t| jobs::workspace(&state.output_dir, t).join(jobs::WORKSPACE_COOKIE_FILE)),
         ..Default::default()
     };
 
     match gscholar::query(&req.keyword, &options).await {
-        Ok(results) => Json(SearchResponse {
+        Ok(results) => Ok(Json(SearchResponse {
             status: "success".to_string(),
             count: results.len(),
             results,
-        }),
+        })),
         Err(e) => {
             error!(error = %e, "Search failed");
-            Json(SearchResponse {
-                status: format!("error: {}", e),
-                count: 0,
-                results: vec![],
-            })
+            Err(e.into())
+        }
+    }
+}
+
+/// Queue a pipeline run; answers 202 with the job at once
+async fn submit_job_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    payload: std::result::Result<Json<jobs::JobRequest>, JsonRejection>,
+) -> std::result::Result<(StatusCode, Json<jobs::Job>), api::ApiError> {
+    let job = state.jobs.submit(json_body(payload)?, tenant(&key))?;
+    info!(id = %job.id, keyword = %job.request.keyword, tenant = ?job.tenant, "Job queued");
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// The tenant's jobs, newest first
+async fn list_jobs_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+) -> Json<Vec<jobs::Job>> {
+    Json(state.jobs.list(tenant(&key)))
+}
+
+/// One of the tenant's jobs, or 404
+fn find_job(state: &AppState, key: &Option<Extension<auth::ApiKey>>, id: &str) -> std::result::Result<jobs::Job, api::ApiError> {
+    state.jobs.get(id, tenant(key)).ok_or_else(|| api::ApiError::not_found(format!("No job {}", id)))
+}
+
+/// Status, progress and result of one of the tenant's jobs
+async fn job_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    UrlPath(id): UrlPath<String>,
+) -> std::result::Result<Json<jobs::Job>, api::ApiError> {
+    find_job(&state, &key, &id).map(Json)
+}
+
+/// Files in a job's run folder
+async fn job_files_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    UrlPath(id): UrlPath<String>,
+) -> std::result::Result<Json<Vec<web::RunFile>>, api::ApiError> {
+    let job = find_job(&state, &key, &id)?;
+    Ok(Json(web::run_files(&job.output_folder)?))
+}
+
+/// Download one file of a job's run folder
+async fn job_file_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    UrlPath((id, name)): UrlPath<(String, String)>,
+) -> std::result::Result<Response, api::ApiError> {
+    let job = find_job(&state, &key, &id)?;
+    let path = web::run_file(&job.output_folder, &name)
+        .ok_or_else(|| api::ApiError::not_found(format!("No file {} in job {}", name, id)))?;
+    let data = tokio::fs::read(&path).await.map_err(rustgscholar::error::GscholarError::from)?;
+    Ok(attachment(&name, data))
+}
+
+/// Export format of `GET /jobs/{id}/export`
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    format: String,
+}
+
+/// The papers of a finished job's final table as BibTeX, RIS or CSL-JSON
+async fn job_export_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    UrlPath(id): UrlPath<String>,
+    query: std::result::Result<Query<ExportQuery>, QueryRejection>,
+) -> std::result::Result<Response, api::ApiError> {
+    let Query(query) =
+        query.map_err(|rejection| api::ApiError::new(StatusCode::BAD_REQUEST, "validation", rejection.body_text()))?;
+    let format: export::ExportFormat = query.format.parse()?;
+    let job = find_job(&state, &key, &id)?;
+    let table = job
+        .result
+        .and_then(|r| r.final_table)
+        .ok_or_else(|| api::ApiError::not_found(format!("Job {} has no results yet", id)))?;
+    let records = export::read_csv(&table)?;
+    let name = format!("{}.{}", id, format.extension());
+    Ok(attachment(&name, export::render(format, &records).into_bytes()))
+}
+
+/// A download response named `name`
+fn attachment(name: &str, data: Vec<u8>) -> Response {
+    let disposition = format!("attachment; filename=\"{}\"", name.replace('"', ""));
+    ([(header::CONTENT_TYPE, web::content_type(name).to_string()), (header::CONTENT_DISPOSITION, disposition)], data)
+        .into_response()
+}
+
+/// The web UI's page
+async fn ui_handler() -> Response {
+    asset_response("index.html")
+}
+
+/// Static files of the web UI
+async fn asset_handler(UrlPath(path): UrlPath<String>) -> Response {
+    asset_response(&path)
+}
+
+fn asset_response(path: &str) -> Response {
+    match web::asset(path) {
+        Some(asset) => ([(header::CONTENT_TYPE, asset.content_type)], asset.data).into_response(),
+        None => api::ApiError::not_found(format!("No asset {}", path)).into_response(),
+    }
+}
+
+/// Papers of the tenant's finished jobs, filtered, sorted and paged
+async fn papers_handler(
+    State(state): State<Arc<AppState>>,
+    key: Option<Extension<auth::ApiKey>>,
+    query: std::result::Result<Query<store::PaperQuery>, QueryRejection>,
+) -> std::result::Result<Json<store::PaperPage>, api::ApiError> {
+    let Query(query) =
+        query.map_err(|rejection| api::ApiError::new(StatusCode::BAD_REQUEST, "validation", rejection.body_text()))?;
+    Ok(Json(state.results.query(&query, tenant(&key))?))
+}
+
+// ============================================================================
+// Query Expansion
+// ============================================================================
+
+async fn run_expand(args: ExpandArgs) -> Result<()> {
+    let ExpandArgs {
+        keyword,
+        context,
+        validate,
+        ylo,
+        save,
+        llm,
+    } = args;
+
+    let llm_config = match llm.config("")? {
+        Ok(config) => config,
+        Err(reason) => anyhow::bail!("LLM is not configured: {}", reason),
+    };
+
+    let count_options = validate.then(|| openalex::QueryOptions {
+        ylo: Some(ylo.unwrap_or_else(|| Local::now().year() - 5)),
+        ..Default::default()
+    });
+
+    let expansion = print_expansion(&llm_config, &keyword, context.as_deref().unwrap_or_default(), count_options.as_ref()).await?;
+
+    if let Some(path) = save {
+        std::fs::write(&path, serde_json::to_string_pretty(&expansion)?).context("Failed to write query expansion")?;
+        println!("\nSaved: {:?}", path);
+    }
+
+    Ok(())
+}
+
+/// Run query expansion, optionally count hits on OpenAlex, and print the suggestions
+async fn print_expansion(
+    llm_config: &llm_filter::LlmConfig,
+    keyword: &str,
+    context: &str,
+    count_options: Option<&openalex::QueryOptions>,
+) -> Result<query_expansion::QueryExpansion> {
+    let (mut expansion, usage) = query_expansion::expand_query(llm_config, keyword, context).await?;
+
+    let baseline = match count_options {
+        Some(options) => query_expansion::validate(&mut expansion, options).await,
+        None => None,
+    };
+    status!("{}", expansion.summary(count_options.map(|_| baseline)));
+    status!("\nToken usage: {} total", usage.total_tokens);
+
+    Ok(expansion)
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+fn run_export(format: &str, input: &Path, output: &Path) -> Result<()> {
+    let format: export::ExportFormat = format.parse()?;
+    let count = export::export_file(input, output, format).with_context(|| format!("Failed to export {:?}", input))?;
+    println!("Exported {} papers to {:?}", count, output);
+    Ok(())
+}
+
+fn run_init_config(path: &Path, force: bool) -> Result<()> {
+    let format = config::ConfigFormat::from_path(path)?;
+    if path.exists() && !force {
+        anyhow::bail!("{:?} already exists (use --force to overwrite)", path);
+    }
+    std::fs::write(path, config::scaffold(format)).with_context(|| format!("Failed to write {:?}", path))?;
+    println!("Wrote {:?}", path);
+    println!("Edit it, then run: rustgscholar search \"<keyword>\" --config {}", path.display());
+    Ok(())
+}
+
+fn run_merge(folders: &[PathBuf], output: &Path) -> Result<()> {
+    let summary = merge::merge_runs(folders, output).context("Failed to merge runs")?;
+    println!(
+        "Merged {} runs: {} records, {} after removing duplicates -> {:?}",
+        summary.runs, summary.records, summary.unique, summary.path
+    );
+    Ok(())
+}
+
+fn run_diff(old: &Path, new: &Path, relevant: bool, json: Option<&Path>) -> Result<()> {
+    let diff = diff::diff_runs(old, new, relevant).context("Failed to compare runs")?;
+    let encoded = serde_json::to_string_pretty(&diff).context("Failed to encode the differences")?;
+    match json {
+        Some(path) if path == Path::new("-") => println!("{}", encoded),
+        Some(path) => {
+            std::fs::write(path, encoded).with_context(|| format!("Failed to write {:?}", path))?;
+            print!("{}", diff.render());
+        }
+        None => print!("{}", diff.render()),
+    }
+    Ok(())
+}
+
+fn run_network(format: &str, include_references: bool, folder: &Path, output: Option<PathBuf>) -> Result<()> {
+    let format: network::GraphFormat = format.parse()?;
+    let graph = network::CitationGraph::from_run_folder(folder, include_references)
+        .with_context(|| format!("Failed to build citation network from {:?}", folder))?;
+    let output = output.unwrap_or_else(|| folder.join("citations").with_extension(format.extension()));
+    std::fs::write(&output, graph.render(format)).with_context(|| format!("Failed to write {:?}", output))?;
+    println!("Exported {} nodes and {} citations to {:?}", graph.nodes.len(), graph.edges.len(), output);
+    Ok(())
+}
+
+fn run_topics(options: &topics::TopicOptions, folder: &Path) -> Result<()> {
+    let map = topics::TopicMap::from_run_folder(folder, options)
+        .with_context(|| format!("Failed to build the term network of {:?}", folder))?;
+    let written = map.save(folder).with_context(|| format!("Failed to write topic files to {:?}", folder))?;
+    println!("{} terms, {} links, {} clusters", map.terms.len(), map.links.len(), map.cluster_count());
+    for cluster in 1..=map.cluster_count() {
+        let papers = map.papers.iter().filter(|p| p.cluster == Some(cluster)).count();
+        println!("  Cluster {}: {} papers - {}", cluster, papers, map.top_terms(cluster, 5).join(", "));
+    }
+    for path in written {
+        println!("Saved: {:?}", path);
+    }
+    Ok(())
+}
+
+async fn run_authors(orcid_search: bool, http_cache_days: u64, folder: &Path) -> Result<()> {
+    let papers = orcid::papers_from_run_folder(folder)?;
+    let mut resolver = orcid::OrcidResolver::new()?.with_orcid_search(orcid_search);
+    if http_cache_days > 0 {
+        let ttl = std::time::Duration::from_secs(http_cache_days * 24 * 60 * 60);
+        match cache::HttpCache::for_api("orcid", ttl) {
+            Ok(cache) => resolver = resolver.with_cache(cache),
+            Err(e) => status!("HTTP cache unavailable: {}", e),
+        }
+    }
+    println!("Resolving the authors of {} papers...", papers.len());
+    let (resolved, failed) = resolver.resolve_all(&papers).await;
+    for (doi, error) in &failed {
+        status!("Author lookup failed for {}: {}", doi, error);
+    }
+    let rows = orcid::author_table(&resolved);
+    let path = orcid::save_author_table(&rows, folder).with_context(|| format!("Failed to write authors.csv to {:?}", folder))?;
+    let with_orcid = rows.iter().filter(|r| !r.orcid.is_empty()).count();
+    println!("{} authors, {} with an ORCID iD", rows.len(), with_orcid);
+    println!("Saved: {:?}", path);
+
+    let citations = orcid::citations_from_run_folder(folder)?;
+    let summary = orcid::author_summary(&rows, &citations);
+    let path = orcid::save_author_summary(&summary, folder)
+        .with_context(|| format!("Failed to write authors_summary.csv to {:?}", folder))?;
+    if let Some(top) = summary.first() {
+        println!("Highest h-index in the corpus: {} ({})", top.author, top.h_index);
+    }
+    println!("Saved: {:?}", path);
+    Ok(())
+}
+
+// ============================================================================
+// Cache Management
+// ============================================================================
+
+fn handle_cache(action: CacheAction) -> Result<()> {
+    let cache_dir = cache::default_cache_dir()?;
+
+    match action {
+        CacheAction::Stats => {
+            let stats = cache::stats(&cache_dir)?;
+            if stats.is_empty() {
+                println!("No caches in {:?}", cache_dir);
+            }
+            for entry in &stats {
+                println!("{:<24} {:>8} entries {:>10.1} KB", entry.name, entry.entries, entry.bytes as f64 / 1024.0);
+            }
+        }
+        CacheAction::Clear => {
+            let mut cleared = cache::clear(&cache_dir.join(cache::RANKINGS_CACHE_FILE))?;
+            cleared |= cache::clear_dir(&cache_dir.join(cache::LLM_CACHE_DIR))?;
+            cleared |= cache::clear_dir(&cache_dir.join(cache::HTTP_CACHE_DIR))?;
+            println!("{}", if cleared { "All caches cleared." } else { "No caches found." });
+        }
+        CacheAction::ClearHttp => {
+            if cache::clear_dir(&cache_dir.join(cache::HTTP_CACHE_DIR))? {
+                println!("HTTP response cache cleared.");
+            } else {
+                println!("No HTTP response cache found.");
+            }
+        }
+        CacheAction::ClearRankings => {
+            if cache::clear(&cache_dir.join(cache::RANKINGS_CACHE_FILE))? {
+                println!("EasyScholar rankings cache cleared.");
+            } else {
+                println!("No EasyScholar rankings cache found.");
+            }
+        }
+        CacheAction::ClearLlm => {
+            if cache::clear_dir(&cache_dir.join(cache::LLM_CACHE_DIR))? {
+                println!("LLM reply cache cleared.");
+            } else {
+                println!("No LLM reply cache found.");
+            }
+        }
+        CacheAction::Path => {
+            println!("Cache directory: {:?}", cache_dir);
         }
     }
+
+    Ok(())
+}
+
+// ============================================================================
+// Review TUI
+// ============================================================================
+
+/// Review the screened papers of a run folder in the terminal UI
+#[cfg(feature = "tui")]
+fn run_review(folder: &Path) -> Result<()> {
+    use rustgscholar::review;
+
+    let mut session = review::ReviewSession::load(folder)?;
+    if session.candidates.is_empty() {
+        println!("No papers to review in {:?}", folder);
+        return Ok(());
+    }
+
+    rustgscholar::review_tui::run(&mut session).context("Terminal UI failed")?;
+    let included = session.save()?;
+    println!(
+        "Reviewed {} of {} papers; {} papers in {:?}",
+        session.reviewed(),
+        session.candidates.len(),
+        included,
+        folder.join(review::FINAL_FILE)
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_review(_folder: &Path) -> Result<()> {
+    anyhow::bail!("This build has no terminal UI (rebuild with the \"tui\" feature)")
+}
+
+// ============================================================================
+// Doctor
+// ============================================================================
+
+async fn run_doctor(args: DoctorArgs) -> Result<()> {
+    let provider: llm_filter::LlmProvider = args.llm_provider.parse()?;
+    let llm = args
+        .llm_base_url
+        .or_else(|| provider.default_base_url().map(String::from))
+        .map(|base_url| health::LlmEndpoint { provider, base_url, api_key: args.llm_key });
+    let options = doctor::DoctorOptions {
+        proxy: args.proxy,
+        mirror: args.mirror,
+        cookie_path: None,
+        easyscholar_key: args.easyscholar_key,
+        s2_api_key: args.s2_key,
+        llm,
+        llm_model: Some(args.llm_model),
+    };
+
+    let checks = doctor::run(&options).await?;
+    println!("{}", doctor::render_table(&checks));
+    if doctor::has_failures(&checks) {
+        let failed = checks.iter().filter(|c| c.status == doctor::CheckStatus::Fail).count();
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
 }
 
 // ============================================================================
 // Cookie Management
 // ============================================================================
 
-fn handle_cookies(action: CookieAction) -> Result<()> {
+async fn handle_cookies(action: CookieAction) -> Result<()> {
     use rustgscholar::cookies::CookieManager;
 
     let manager = CookieManager::new()?;
@@ -933,30 +2441,79 @@ fn handle_cookies(action: CookieAction) -> Result<()> {
         CookieAction::Path => {
             println!("Cookie file: {:?}", manager.path());
         }
-        CookieAction::Fetch => {
-            println!("Opening Google Scholar in browser to fetch cookies...");
-            println!("Please complete any CAPTCHA or login, then the cookies will be saved.");
-            println!();
-            println!("Cookie file will be saved to: {:?}", manager.path());
-            println!();
-            
-            // Run async cookie fetch
-            tokio::runtime::Runtime::new()?
-                .block_on(fetch_cookies_from_browser(&manager))?;
+        CookieAction::Fetch { headless, timeout, browser_path, manual } => {
+            #[cfg(feature = "browser")]
+            if !manual {
+                println!("Opening Google Scholar in the browser to fetch cookies...");
+                if !headless {
+                    println!("Please complete any CAPTCHA in the browser window; it closes once Scholar loads.");
+                }
+                let options = rustgscholar::browser::BrowserOptions {
+                    headless,
+                    timeout: std::time::Duration::from_secs(timeout),
+                    executable: browser_path,
+                    url: None,
+                };
+                let cookies = rustgscholar::browser::fetch_scholar_cookies(&options)
+                    .await
+                    .context("Failed to fetch cookies from the browser (use --manual to paste them instead)")?;
+                manager.save(&cookies)?;
+                println!("Saved {} cookies to {:?}", cookies.len(), manager.path());
+                return Ok(());
+            }
+            #[cfg(not(feature = "browser"))]
+            {
+                let _ = (headless, timeout, browser_path, manual);
+                println!("This build has no browser support (feature \"browser\"); paste the cookies instead.");
+                println!();
+            }
+            paste_cookies(&manager)?;
+        }
+        CookieAction::Import { browser, profile, file } => {
+            let cookies = match file {
+                Some(file) => {
+                    let content = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {:?}", file))?;
+                    let cookies = rustgscholar::cookies::parse_cookies(&content)
+                        .with_context(|| format!("Failed to parse cookies in {:?}", file))?;
+                    cookies.into_iter().filter(|c| rustgscholar::cookies::is_google_host(&c.domain)).collect()
+                }
+                None => import_browser_cookies(&browser, profile.as_deref())?,
+            };
+            if cookies.is_empty() {
+                println!("No Google cookies found; open https://scholar.google.com in that browser first.");
+                return Ok(());
+            }
+            manager.save(&cookies)?;
+            println!("Imported {} Google cookies to {:?}", cookies.len(), manager.path());
         }
     }
 
     Ok(())
 }
 
-/// Fetch cookies from browser using headless-chrome or browser subagent
-async fn fetch_cookies_from_browser(manager: &rustgscholar::cookies::CookieManager) -> Result<()> {
+/// Google cookies of a local browser profile
+#[cfg(feature = "cookie-import")]
+fn import_browser_cookies(browser: &str, profile: Option<&Path>) -> Result<Vec<rustgscholar::cookies::Cookie>> {
+    let browser: rustgscholar::cookie_import::BrowserKind = browser.parse()?;
+    let profile = profile.map(|p| p.to_string_lossy());
+    rustgscholar::cookie_import::import(browser, profile.as_deref())
+        .with_context(|| format!("Failed to import cookies from {:?}", browser))
+}
+
+#[cfg(not(feature = "cookie-import"))]
+fn import_browser_cookies(_browser: &str, _profile: Option<&Path>) -> Result<Vec<rustgscholar::cookies::Cookie>> {
+    anyhow::bail!("This build cannot read browser profiles (feature \"cookie-import\"); use --file")
+}
+
+/// Read cookies exported from the browser's developer tools as JSON
+fn paste_cookies(manager: &rustgscholar::cookies::CookieManager) -> Result<()> {
     use std::io::{self, Write};
     
     println!("=== Manual Cookie Export Instructions ===");
     println!();
-    println!("Since automated browser cookie fetching requires additional setup,");
-    println!("please follow these steps to export cookies manually:");
+    println!("Cookie file will be saved to: {:?}", manager.path());
+    println!();
+    println!("Follow these steps to export cookies manually:");
     println!();
     println!("1. Open Google Chrome and go to: https://scholar.google.com");
     println!("2. Complete any CAPTCHA if prompted");
@@ -964,7 +2521,8 @@ async fn fetch_cookies_from_browser(manager: &rustgscholar::cookies::CookieManag
     println!("4. Go to 'Application' tab -> 'Cookies' -> 'https://scholar.google.com'");
     println!("5. Right-click and copy all cookies, or use a cookie export extension");
     println!();
-    println!("Alternatively, paste cookies in JSON format below (or press Enter to skip):");
+    println!("Alternatively, paste cookies as a JSON array below (or press Enter to skip),");
+    println!("or import an exported cookies.txt / JSON file with `cookies import --file`:");
     println!("Format: [{{\"name\":\"NID\",\"value\":\"xxx\",\"domain\":\".google.com\"}},...]");
     println!();
     print!("> ");
@@ -981,7 +2539,7 @@ async fn fetch_cookies_from_browser(manager: &rustgscholar::cookies::CookieManag
     }
     
     // Try to parse as JSON
-    match serde_json::from_str::<Vec<rustgscholar::cookies::Cookie>>(input) {
+    match rustgscholar::cookies::parse_cookies(input) {
         Ok(cookies) => {
             manager.save(&cookies)?;
             println!("Successfully saved {} cookies!", cookies.len());
@@ -994,3 +2552,30 @@ async fn fetch_cookies_from_browser(manager: &rustgscholar::cookies::CookieManag
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_args_after_global_flags() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("review.toml");
+        std::fs::write(&path, "[llm]\nmodel = \"gpt-4o\"\n")?;
+        let args = |typed: &[&str]| -> Vec<OsString> {
+            std::iter::once("rustgscholar").chain(typed.iter().copied()).map(OsString::from).collect()
+        };
+        let config = path.to_string_lossy();
+
+        let typed = ["--log-format", "json", "--http-timeout", "60", "-q", "search", "landslide", "--config", &config];
+        let expanded = with_config_args(args(&typed))?;
+        assert_eq!(&expanded[6..9], &args(&["search", "--llm-model", "gpt-4o"])[1..]);
+        let cli = Cli::try_parse_from(expanded)?;
+        assert_eq!(cli.log_format, "json");
+
+        let typed = ["--log-format=json", "watch", "landslide", "--config", &config];
+        assert_eq!(subcommand_index(&args(&typed)), Some(2));
+        assert_eq!(subcommand_index(&args(&["--debug"])), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! Title normalization and fuzzy matching.
+//!
+//! Titles of the same paper rarely agree character for character: Google
+//! Scholar prefixes `[PDF]`/`[HTML]` tags and cuts long titles with `…`,
+//! Crossref keeps `<i>`/`<sub>` markup and drops subtitles, publishers mix
+//! ligatures, full-width characters, accents, dashes and case.
+//! [`normalize_title`] reduces a title to lowercase ASCII-folded words
+//! without punctuation or stop words; [`TitleMatcher`] scores two titles on
+//! that form (Levenshtein or Jaro-Winkler) and accepts them above a
+//! threshold. Crossref title lookups, deduplication and the Semantic
+//! Scholar title fallback all go through here.
+
+use crate::error::{GscholarError, Result};
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Similarity at or above which two titles name the same paper
+pub const DEFAULT_THRESHOLD: f64 = 0.85;
+
+/// A shorter title this many words long that starts the other one matches
+/// it (a missing subtitle or a truncated Scholar title)
+const MIN_PREFIX_WORDS: usize = 4;
+
+/// Words ignored when comparing titles
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "into", "is", "its", "of", "on", "or", "the", "to", "via",
+    "with",
+];
+
+/// String similarity used to score two normalized titles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Metric {
+    /// Normalized Levenshtein distance: strict, for long titles
+    #[default]
+    Levenshtein,
+    /// Jaro-Winkler: lenient, favours a common beginning
+    JaroWinkler,
+}
+
+impl FromStr for Metric {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "levenshtein" => Ok(Self::Levenshtein),
+            "jaro-winkler" | "jaro_winkler" => Ok(Self::JaroWinkler),
+            _ => Err(GscholarError::Config(format!(
+                "Unknown title metric '{}' (expected levenshtein or jaro-winkler)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Decides whether two titles name the same paper
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TitleMatcher {
+    pub metric: Metric,
+    /// Minimum score in `0.0..=1.0`
+    pub threshold: f64,
+}
+
+impl Default for TitleMatcher {
+    fn default() -> Self {
+        Self { metric: Metric::default(), threshold: DEFAULT_THRESHOLD }
+    }
+}
+
+impl TitleMatcher {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold, ..Default::default() }
+    }
+
+    /// Similarity of two raw titles in `0.0..=1.0`
+    pub fn score(&self, a: &str, b: &str) -> f64 {
+        let (a_truncated, b_truncated) = (is_truncated(a), is_truncated(b));
+        let mut a_words = title_words(a);
+        let mut b_words = title_words(b);
+        // The last word of a cut title may itself be cut
+        if a_truncated {
+            a_words.pop();
+        }
+        if b_truncated {
+            b_words.pop();
+        }
+        if a_words.is_empty() || b_words.is_empty() {
+            return 0.0;
+        }
+
+        let (shorter, longer) = if a_words.len() <= b_words.len() { (&a_words, &b_words) } else { (&b_words, &a_words) };
+        if shorter.len() >= MIN_PREFIX_WORDS && longer.starts_with(shorter) {
+            return 1.0;
+        }
+        // A truncated title is compared with the same number of words of the other
+        let longer = if a_truncated || b_truncated { &longer[..shorter.len()] } else { &longer[..] };
+
+        let (a, b) = (shorter.join(" "), longer.join(" "));
+        match self.metric {
+            Metric::Levenshtein => strsim::normalized_levenshtein(&a, &b),
+            Metric::JaroWinkler => strsim::jaro_winkler(&a, &b),
+        }
+    }
+
+    /// Whether two raw titles name the same paper
+    pub fn matches(&self, a: &str, b: &str) -> bool {
+        self.score(a, b) >= self.threshold
+    }
+}
+
+/// Title as space-separated lowercase words, without markup, accents,
+/// punctuation and stop words
+pub fn normalize_title(title: &str) -> String {
+    title_words(title).join(" ")
+}
+
+/// Compact key of a title for exact-match deduplication
+pub fn title_key(title: &str) -> String {
+    title_words(title).concat()
+}
+
+/// Whether Scholar cut the title short
+fn is_truncated(title: &str) -> bool {
+    let title = title.trim_end();
+    title.ends_with('…') || title.ends_with("...")
+}
+
+fn title_words(title: &str) -> Vec<String> {
+    static NOISE: OnceLock<Option<Regex>> = OnceLock::new();
+    // Scholar result tags ([PDF], [HTML], [BOOK][B], [CITATION][C]), HTML/MathML markup, entities
+    let noise = NOISE.get_or_init(|| Regex::new(r"^\s*(?:\[[A-Za-z]{1,10}\]\s*)+|<[^>]{1,100}>|&[a-z]{2,6};|&#\d{1,5};").ok());
+    let title = match noise {
+        Some(noise) => noise.replace_all(title, " "),
+        None => title.into(),
+    };
+
+    let folded: String = title
+        .nfkc()
+        .collect::<String>()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    folded.split_whitespace().filter(|w| !STOP_WORDS.contains(w)).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("[PDF][PDF] Rainfall-induced landslides: A review of the <i>in situ</i> evidence"),
+            "rainfall induced landslides review situ evidence"
+        );
+        assert_eq!(normalize_title("Évaluation de la susceptibilité aux ﬂux de débris"), "evaluation de la susceptibilite aux flux de debris");
+        assert_eq!(normalize_title("ＧＩＳ‐based mapping &amp; analysis"), "gis based mapping analysis");
+        assert_eq!(title_key("Slope Stability: A Review"), title_key("slope stability - a review"));
+    }
+
+    #[test]
+    fn test_matches_noisy_scholar_titles() {
+        let matcher = TitleMatcher::default();
+        // Scholar title next to the Crossref title of the same paper
+        for (scholar, crossref) in [
+            (
+                "[HTML][HTML] Landslide susceptibility mapping using GIS-based multi-criteria decision analysis, support vector machines, and logistic regression",
+                "Landslide susceptibility mapping using GIS-based multi-criteria decision analysis, support vector machines, and logistic regression",
+            ),
+            (
+                "A review of the application of machine learning in landslide susceptibility …",
+                "A Review of the Application of Machine Learning in Landslide Susceptibility Assessment: Progress and Challenges",
+            ),
+            ("Deep learning for landslide detection: a comparison of CNN architectures", "Deep learning for landslide detection"),
+            ("Rainfall thresholds for the initiation of landslides in central and southern Europe", "Rainfall thresholds for the initiation of landslides in central and southern europe"),
+            ("Debris-flow runout prediction with a depth-averaged model", "Debris flow run-out prediction with a depth averaged model"),
+        ] {
+            assert!(matcher.matches(scholar, crossref), "{} / {} scored {}", scholar, crossref, matcher.score(scholar, crossref));
+        }
+
+        for (a, b) in [
+            ("Landslide susceptibility mapping using random forest", "Landslide susceptibility mapping using support vector machines"),
+            ("Slope stability", "Slope stability analysis of open pit mines under seismic loading"),
+        ] {
+            assert!(!matcher.matches(a, b), "{} / {} scored {}", a, b, matcher.score(a, b));
+        }
+        assert_eq!(matcher.score("", "Landslides"), 0.0);
+    }
+
+    #[test]
+    fn test_metrics_and_threshold() -> Result<()> {
+        let (a, b) = ("Landslide early warning systems", "Landslide early warning system");
+        let levenshtein = TitleMatcher::new(0.99);
+        assert!(!levenshtein.matches(a, b));
+        assert!(TitleMatcher::new(0.9).matches(a, b));
+        let jaro_winkler = TitleMatcher { metric: "jaro-winkler".parse()?, threshold: 0.9 };
+        assert!(jaro_winkler.score(a, b) > levenshtein.score(a, b));
+        assert!("cosine".parse::<Metric>().is_err());
+        Ok(())
+    }
+}
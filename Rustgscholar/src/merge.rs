@@ -0,0 +1,239 @@
+//! Merging unified results across runs.
+//!
+//! Multi-keyword reviews run the pipeline once per query; [`merge_runs`]
+//! concatenates the `5_unified.csv` of each run folder into one table,
+//! removes duplicates (same DOI, or same normalized title without one) and
+//! records provenance in two extra columns: `runs` (run folder names) and
+//! `sources` (search source of each run), `; `-joined for merged records.
+//! A merged folder can itself be merged again, which appends new runs while
+//! keeping the provenance already recorded.
+
+use crate::error::{GscholarError, Result};
+use crate::output::{self, OutputFormat};
+use crate::prisma;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Stage table merged from each run
+pub const UNIFIED_FILE: &str = "5_unified.csv";
+
+/// Provenance columns appended to the merged table
+const PROVENANCE_COLUMNS: [&str; 2] = ["runs", "sources"];
+
+/// Outcome of a merge
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub path: PathBuf,
+    pub runs: usize,
+    /// Records read across all runs
+    pub records: usize,
+    /// Records after removing duplicates
+    pub unique: usize,
+}
+
+/// One run's unified table
+struct RunTable {
+    name: String,
+    source: String,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl RunTable {
+    fn load(folder: &Path) -> Result<Self> {
+        let path = folder.join(UNIFIED_FILE);
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_path(&path)
+            .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+        let header = reader
+            .headers()
+            .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?
+            .iter()
+            .map(String::from)
+            .collect();
+        let rows = reader
+            .records()
+            .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+            .collect::<std::result::Result<Vec<Vec<String>>, _>>()
+            .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?;
+
+        let name = folder
+            .canonicalize()
+            .unwrap_or_else(|_| folder.to_path_buf())
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source = if folder.join("1_openalex.csv").exists() {
+            "openalex"
+        } else if folder.join("1_gscholar.csv").exists() {
+            "gscholar"
+        } else if folder.join("1_semanticscholar.csv").exists() {
+            "s2"
+        } else if folder.join("1_input.csv").exists() {
+            "input"
+        } else {
+            ""
+        };
+
+        Ok(Self { name, source: source.to_string(), header, rows })
+    }
+}
+
+/// Merge the unified results of several run folders into `output/5_unified.csv`
+pub fn merge_runs(folders: &[PathBuf], output: &Path) -> Result<MergeSummary> {
+    if folders.is_empty() {
+        return Err(GscholarError::Validation("No run folders to merge".to_string()));
+    }
+    let tables = folders.iter().map(|f| RunTable::load(f)).collect::<Result<Vec<_>>>()?;
+    let records = tables.iter().map(|t| t.rows.len()).sum();
+    let (header, rows) = merge_tables(&tables);
+
+    std::fs::create_dir_all(output)?;
+    let path = output::write_table(&output.join(UNIFIED_FILE), OutputFormat::Csv, &header, &rows)?;
+    info!(runs = tables.len(), records, unique = rows.len(), "Merged runs");
+
+    Ok(MergeSummary { path, runs: tables.len(), records, unique: rows.len() })
+}
+
+/// Union of the columns (in order of first appearance) plus provenance;
+/// the first copy of a duplicate wins, with empty cells filled from later copies
+fn merge_tables(tables: &[RunTable]) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut header: Vec<String> = Vec::new();
+    for table in tables {
+        for column in &table.header {
+            if !header.contains(column) && !PROVENANCE_COLUMNS.contains(&column.as_str()) {
+                header.push(column.clone());
+            }
+        }
+    }
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let (doi_col, title_col) = (column("doi"), column("title"));
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut provenance: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for table in tables {
+        let previous = |row: &[String], name: &str| -> Vec<String> {
+            table
+                .header
+                .iter()
+                .position(|c| c == name)
+                .and_then(|i| row.get(i))
+                .map(|v| v.split(';').map(str::trim).filter(|v| !v.is_empty()).map(String::from).collect())
+                .unwrap_or_default()
+        };
+
+        for row in &table.rows {
+            let mut merged = vec![String::new(); header.len()];
+            for (name, value) in table.header.iter().zip(row) {
+                if let Some(i) = column(name) {
+                    merged[i] = value.clone();
+                }
+            }
+
+            // Records from an earlier merge keep their provenance
+            let mut row_runs = previous(row, "runs");
+            if row_runs.is_empty() {
+                row_runs.push(table.name.clone());
+            }
+            let mut row_sources = previous(row, "sources");
+            if row_sources.is_empty() && !table.source.is_empty() {
+                row_sources.push(table.source.clone());
+            }
+
+            let cell = |col: Option<usize>| col.map(|i| merged[i].as_str()).unwrap_or_default();
+            let key = prisma::duplicate_key(cell(doi_col), cell(title_col));
+            match index.get(&key) {
+                Some(&i) => {
+                    for (existing, value) in rows[i].iter_mut().zip(merged) {
+                        if existing.trim().is_empty() {
+                            *existing = value;
+                        }
+                    }
+                    let (runs, sources) = &mut provenance[i];
+                    for run in row_runs {
+                        if !runs.contains(&run) {
+                            runs.push(run);
+                        }
+                    }
+                    for source in row_sources {
+                        if !sources.contains(&source) {
+                            sources.push(source);
+                        }
+                    }
+                }
+                None => {
+                    index.insert(key, rows.len());
+                    rows.push(merged);
+                    provenance.push((row_runs, row_sources));
+                }
+            }
+        }
+    }
+
+    for (row, (runs, sources)) in rows.iter_mut().zip(provenance) {
+        row.push(runs.join("; "));
+        row.push(sources.join("; "));
+    }
+    header.extend(PROVENANCE_COLUMNS.iter().map(|c| c.to_string()));
+    (header, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str, source: &str, header: &[&str], rows: &[&[&str]]) -> RunTable {
+        RunTable {
+            name: name.to_string(),
+            source: source.to_string(),
+            header: header.iter().map(|s| s.to_string()).collect(),
+            rows: rows.iter().map(|r| r.iter().map(|s| s.to_string()).collect()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_tables() {
+        let a = table("run_a", "openalex", &["title", "doi", "tldr"], &[&["Slopes", "10.1/X", ""], &["No DOI: a study", "", "t"]]);
+        let b = table("run_b", "gscholar", &["title", "doi", "journal"], &[
+            &["Slopes", "10.1/x", "Landslides"],
+            &["no doi - A study", "", "J"],
+            &["Rain", "10.1/y", ""],
+        ]);
+
+        let (header, rows) = merge_tables(&[a, b]);
+        assert_eq!(header, vec!["title", "doi", "tldr", "journal", "runs", "sources"]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], vec!["Slopes", "10.1/X", "", "Landslides", "run_a; run_b", "openalex; gscholar"]);
+        assert_eq!(rows[1][3], "J");
+        assert_eq!(rows[2], vec!["Rain", "10.1/y", "", "", "run_b", "gscholar"]);
+
+        // Appending to an earlier merge keeps its provenance
+        let combined = table("combined", "", &["title", "doi", "runs", "sources"], &[&["Slopes", "10.1/x", "run_a; run_b", "openalex"]]);
+        let c = table("run_c", "openalex", &["title", "doi"], &[&["Slopes", "10.1/x"]]);
+        let (header, rows) = merge_tables(&[combined, c]);
+        assert_eq!(header, vec!["title", "doi", "runs", "sources"]);
+        assert_eq!(rows, vec![vec!["Slopes", "10.1/x", "run_a; run_b; run_c", "openalex"]]);
+    }
+
+    #[test]
+    fn test_merge_runs() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        for (name, body) in [("a", "title,doi\nSlopes,10.1/x\n"), ("b", "title,doi\nSlopes,10.1/X\nRain,10.1/y\n")] {
+            std::fs::create_dir(dir.path().join(name))?;
+            std::fs::write(dir.path().join(name).join(UNIFIED_FILE), body)?;
+        }
+
+        let out = dir.path().join("combined");
+        let summary = merge_runs(&[dir.path().join("a"), dir.path().join("b")], &out)?;
+        assert_eq!((summary.runs, summary.records, summary.unique), (2, 3, 2));
+        assert_eq!(
+            std::fs::read_to_string(out.join(UNIFIED_FILE))?,
+            "title,doi,runs,sources\nSlopes,10.1/x,a; b,\nRain,10.1/y,b,\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,370 @@
+//! Citation-network export for Gephi/Cytoscape.
+//!
+//! Builds a directed citation graph from a run folder: nodes are the works in
+//! `1_openalex.csv`, edges follow their OpenAlex `referenced_works` (citing ->
+//! cited). Node attributes are joined by DOI from the later stages: IF from
+//! `5_unified.csv` (or `3_easyscholar.csv`) and the relevance label from
+//! `6_llm_filtered.csv`. Cited works outside the result set can be added as
+//! bare nodes to surface frequently cited foundational papers.
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// Output format for `network`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Graphml,
+    Gexf,
+}
+
+impl FromStr for GraphFormat {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "graphml" => Ok(Self::Graphml),
+            "gexf" => Ok(Self::Gexf),
+            _ => Err(GscholarError::Config(format!("Invalid graph format: {}", s))),
+        }
+    }
+}
+
+impl GraphFormat {
+    /// File extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Graphml => "graphml",
+            Self::Gexf => "gexf",
+        }
+    }
+}
+
+/// A work in the citation graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Node {
+    /// Short OpenAlex ID (e.g., "W2741809807")
+    pub id: String,
+    pub title: String,
+    pub doi: String,
+    pub year: Option<i64>,
+    pub journal: String,
+    pub citations: Option<i64>,
+    pub if_score: Option<f64>,
+    /// LLM relevance label (Stage 6), empty when not screened
+    pub label: String,
+    /// Cited work outside the result set (no metadata)
+    pub external: bool,
+}
+
+/// Directed citation graph; edges are (citing, cited) node indices
+#[derive(Debug, Clone, Default)]
+pub struct CitationGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+/// Node attributes: (key, type name in GraphML/GEXF)
+const ATTRIBUTES: [(&str, &str); 8] = [
+    ("title", "string"),
+    ("doi", "string"),
+    ("year", "int"),
+    ("journal", "string"),
+    ("citations", "int"),
+    ("if_score", "double"),
+    ("label", "string"),
+    ("external", "boolean"),
+];
+
+impl CitationGraph {
+    /// Build the graph from a run folder's stage CSVs
+    pub fn from_run_folder(folder: &Path, include_external: bool) -> Result<Self> {
+        let works_path = folder.join("1_openalex.csv");
+        if !works_path.exists() {
+            return Err(GscholarError::Validation(format!(
+                "{:?} not found: citation data comes from OpenAlex runs (--source openalex)",
+                works_path
+            )));
+        }
+        let works = read_rows(&works_path)?;
+
+        let mut if_scores: HashMap<String, f64> = HashMap::new();
+        for stage in ["3_easyscholar.csv", "5_unified.csv"] {
+            let path = folder.join(stage);
+            if path.exists() {
+                for row in read_rows(&path)? {
+                    if let Ok(score) = field(&row, "if_score").parse() {
+                        if_scores.insert(doi::normalize(field(&row, "doi")), score);
+                    }
+                }
+            }
+        }
+        let labels_path = folder.join("6_llm_filtered.csv");
+        let labels: HashMap<String, String> = if labels_path.exists() {
+            read_rows(&labels_path)?
+                .iter()
+                .map(|row| (doi::normalize(field(row, "id")), field(row, "label").to_string()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self::build(&works, &if_scores, &labels, include_external))
+    }
+
+    fn build(
+        works: &[HashMap<String, String>],
+        if_scores: &HashMap<String, f64>,
+        labels: &HashMap<String, String>,
+        include_external: bool,
+    ) -> Self {
+        let mut graph = Self::default();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut seen_edges = HashSet::new();
+
+        for row in works {
+            let id = short_id(field(row, "openalex_id"));
+            if id.is_empty() || index.contains_key(id) {
+                continue;
+            }
+            let doi = doi::normalize(field(row, "doi"));
+            index.insert(id.to_string(), graph.nodes.len());
+            graph.nodes.push(Node {
+                id: id.to_string(),
+                title: field(row, "title").to_string(),
+                year: field(row, "year").parse().ok(),
+                journal: field(row, "venue").to_string(),
+                citations: field(row, "citations").parse().ok(),
+                if_score: if_scores.get(&doi).copied(),
+                label: labels.get(&doi).cloned().unwrap_or_default(),
+                doi,
+                external: false,
+            });
+        }
+
+        for row in works {
+            let Some(&citing) = index.get(short_id(field(row, "openalex_id"))) else {
+                continue;
+            };
+            for cited in field(row, "referenced_works").split(',').map(|w| short_id(w.trim())) {
+                if cited.is_empty() {
+                    continue;
+                }
+                let target = match index.get(cited) {
+                    Some(&target) => target,
+                    None if include_external => {
+                        index.insert(cited.to_string(), graph.nodes.len());
+                        graph.nodes.push(Node { id: cited.to_string(), external: true, ..Default::default() });
+                        graph.nodes.len() - 1
+                    }
+                    None => continue,
+                };
+                if target != citing && seen_edges.insert((citing, target)) {
+                    graph.edges.push((citing, target));
+                }
+            }
+        }
+
+        info!(nodes = graph.nodes.len(), edges = graph.edges.len(), "Built citation graph");
+        graph
+    }
+
+    /// Attribute values of a node, in [`ATTRIBUTES`] order (empty = missing)
+    fn attribute_values(node: &Node) -> [String; 8] {
+        let optional = |v: Option<String>| v.unwrap_or_default();
+        [
+            node.title.clone(),
+            node.doi.clone(),
+            optional(node.year.map(|y| y.to_string())),
+            node.journal.clone(),
+            optional(node.citations.map(|c| c.to_string())),
+            optional(node.if_score.map(|s| s.to_string())),
+            node.label.clone(),
+            node.external.to_string(),
+        ]
+    }
+
+    /// Render as GraphML
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+            "\n"
+        ));
+        for (key, kind) in ATTRIBUTES {
+            out.push_str(&format!(
+                "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>\n",
+                key, kind
+            ));
+        }
+        out.push_str("  <graph id=\"citations\" edgedefault=\"directed\">\n");
+        for node in &self.nodes {
+            out.push_str(&format!("    <node id=\"{}\">\n", escape_xml(&node.id)));
+            for ((key, _), value) in ATTRIBUTES.iter().zip(Self::attribute_values(node)) {
+                if !value.is_empty() {
+                    out.push_str(&format!("      <data key=\"{}\">{}</data>\n", key, escape_xml(&value)));
+                }
+            }
+            out.push_str("    </node>\n");
+        }
+        for (i, (source, target)) in self.edges.iter().enumerate() {
+            out.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"/>\n",
+                i,
+                escape_xml(&self.nodes[*source].id),
+                escape_xml(&self.nodes[*target].id)
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// Render as GEXF 1.3
+    pub fn to_gexf(&self) -> String {
+        let mut out = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#,
+            "\n",
+            "  <meta><creator>rustgscholar</creator></meta>\n",
+            r#"  <graph defaultedgetype="directed" mode="static">"#,
+            "\n",
+            r#"    <attributes class="node">"#,
+            "\n"
+        ));
+        for (i, (key, kind)) in ATTRIBUTES.iter().enumerate() {
+            let kind = if *kind == "int" { "integer" } else { kind };
+            out.push_str(&format!("      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>\n", i, key, kind));
+        }
+        out.push_str("    </attributes>\n    <nodes>\n");
+        for node in &self.nodes {
+            let label = if node.title.is_empty() { &node.id } else { &node.title };
+            out.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\">\n        <attvalues>\n",
+                escape_xml(&node.id),
+                escape_xml(label)
+            ));
+            for (i, value) in Self::attribute_values(node).iter().enumerate() {
+                if !value.is_empty() {
+                    out.push_str(&format!("          <attvalue for=\"{}\" value=\"{}\"/>\n", i, escape_xml(value)));
+                }
+            }
+            out.push_str("        </attvalues>\n      </node>\n");
+        }
+        out.push_str("    </nodes>\n    <edges>\n");
+        for (i, (source, target)) in self.edges.iter().enumerate() {
+            out.push_str(&format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\"/>\n",
+                i,
+                escape_xml(&self.nodes[*source].id),
+                escape_xml(&self.nodes[*target].id)
+            ));
+        }
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        out
+    }
+
+    /// Render in the given format
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Graphml => self.to_graphml(),
+            GraphFormat::Gexf => self.to_gexf(),
+        }
+    }
+}
+
+/// "https://openalex.org/W123" -> "W123"
+fn short_id(id: &str) -> &str {
+    id.rsplit('/').next().unwrap_or_default().trim()
+}
+
+pub(crate) fn field<'a>(row: &'a HashMap<String, String>, name: &str) -> &'a str {
+    row.get(name).map(|v| v.trim()).unwrap_or_default()
+}
+
+pub(crate) fn read_rows(path: &Path) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?
+        .clone();
+    reader
+        .records()
+        .map(|r| {
+            r.map(|r| headers.iter().zip(r.iter()).map(|(h, v)| (h.to_string(), v.to_string())).collect())
+                .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))
+        })
+        .collect()
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(include_external: bool) -> CitationGraph {
+        let work = |id: &str, title: &str, doi: &str, refs: &str| -> HashMap<String, String> {
+            [
+                ("openalex_id", format!("https://openalex.org/{}", id)),
+                ("title", title.to_string()),
+                ("doi", doi.to_string()),
+                ("year", "2022".to_string()),
+                ("referenced_works", refs.to_string()),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+        };
+        let works = vec![
+            work("W1", "Rock & soil", "10.1/A", "https://openalex.org/W2,https://openalex.org/W9"),
+            work("W2", "Slopes", "https://doi.org/10.1/B", ""),
+            work("W2", "Slopes (duplicate)", "10.1/b", ""),
+        ];
+        let if_scores = HashMap::from([("10.1/a".to_string(), 4.5)]);
+        let labels = HashMap::from([("10.1/b".to_string(), "relevant".to_string())]);
+        CitationGraph::build(&works, &if_scores, &labels, include_external)
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let g = graph(false);
+        assert_eq!(g.nodes.len(), 2);
+        assert_eq!(g.edges, vec![(0, 1)]);
+        assert_eq!(g.nodes[0].if_score, Some(4.5));
+        assert_eq!(g.nodes[1].label, "relevant");
+
+        let g = graph(true);
+        assert_eq!(g.nodes.len(), 3);
+        assert!(g.nodes[2].external && g.nodes[2].id == "W9");
+        assert_eq!(g.edges, vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let g = graph(false);
+        let graphml = g.to_graphml();
+        assert!(graphml.contains(r#"<key id="if_score" for="node" attr.name="if_score" attr.type="double"/>"#));
+        assert!(graphml.contains("<data key=\"title\">Rock &amp; soil</data>"));
+        assert!(graphml.contains(r#"<edge id="e0" source="W1" target="W2"/>"#));
+
+        let gexf = g.to_gexf();
+        assert!(gexf.contains(r#"<attribute id="2" title="year" type="integer"/>"#));
+        assert!(gexf.contains(r#"<node id="W2" label="Slopes">"#));
+        assert!(gexf.contains(r#"<attvalue for="6" value="relevant"/>"#));
+        assert!(gexf.contains(r#"<edge id="0" source="W1" target="W2"/>"#));
+    }
+}
@@ -5,21 +5,35 @@
 //!
 //! API Best Practices (per OpenAlex docs):
 //! - Use `mailto:email` parameter for polite pool (10 req/s vs 1 req/s)
-//! - Use `per-page=200` for maximum results per page
-//! - Implement exponential backoff for retries
+//! - Use `per-page=200` for maximum results per page (lower with
+//!   [`QueryOptions::per_page`] for small exploratory queries)
+//! - Implement exponential backoff for retries (see [`crate::http::send_to`])
 
+use crate::cache::{self, HttpCache};
+use crate::doi;
 use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use crate::stream::{self, Page, PageSender};
+use crate::throttle::{self, Upstream};
+use crate::work_type::{self, WorkType};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info, warn};
 use std::time::Duration;
+use tracing::{debug, info, warn};
 use urlencoding; // Ensure crate is linked
 
 /// OpenAlex API base URL
-const OPENALEX_API_BASE: &str = "https://api.openalex.org";
+pub const OPENALEX_API_BASE: &str = "https://api.openalex.org";
 
 /// Maximum results per page (OpenAlex limit)
-const MAX_PER_PAGE: usize = 200;
+pub const MAX_PER_PAGE: usize = 200;
+
+/// Works sampled per topic and year by [`topic_citation_mean`]
+pub const TOPIC_SAMPLE_SIZE: usize = 200;
+
+/// Share of a query's works that the year [`YearCounts::suggest_ylo`] suggests keeps
+pub const YLO_COVERAGE: f64 = 0.9;
 
 /// Email for polite pool access
 const POLITE_EMAIL: &str = "c76d@c.com";
@@ -29,10 +43,23 @@ const POLITE_EMAIL: &str = "c76d@c.com";
 pub struct OpenAlexResult {
     pub title: String,
     pub author: String,
+    /// Institutions of all authors, "; "-separated, in authorship order
+    #[serde(default)]
+    pub institutions: String,
+    /// ISO 3166-1 alpha-2 codes of the authors' countries, "; "-separated
+    #[serde(default)]
+    pub countries: String,
+    /// Funder names from `grants`, "; "-separated
+    #[serde(default)]
+    pub funders: String,
+    /// Grant (award) numbers from `grants`, "; "-separated
+    #[serde(default)]
+    pub grants: String,
     pub year: String,
     pub publication_date: String,  // ISO 8601 date
     pub venue: String,             // Journal/source name
     pub source_type: String,       // journal, repository, etc.
+    pub issn: String,              // Linking ISSN (ISSN-L) of the source
     pub doi: String,
     pub article_url: String,       // Landing page URL
     pub pdf_url: String,           // Direct PDF URL (if available)
@@ -48,6 +75,7 @@ pub struct OpenAlexResult {
     pub work_type: String,         // article, book, etc.
     pub keywords: String,          // Comma-separated keywords
     pub primary_topic: String,     // Primary research topic
+    pub primary_topic_id: String,  // OpenAlex topic ID (e.g., https://openalex.org/T10017)
     // Reference info (full lists as comma-separated OpenAlex IDs)
     pub referenced_works: String,  // Works this paper cites
     pub related_works: String,     // Algorithmically related works
@@ -68,6 +96,18 @@ pub struct QueryOptions {
     pub yhi: Option<i32>,
     /// Whether to return all results or just first per page
     pub all_results: bool,
+    /// Results per page, 1 to [`MAX_PER_PAGE`]
+    pub per_page: usize,
+    /// Wait between two page requests; pages are then fetched one at a
+    /// time instead of concurrently
+    pub page_delay: Duration,
+    /// Reuse responses from the HTTP cache (`None` always queries the API)
+    pub cache: Option<HttpCache>,
+    /// Work types to search for (journal articles when empty)
+    pub work_types: Vec<WorkType>,
+    /// Only works with an author from one of these countries (ISO 3166-1
+    /// alpha-2 codes; any country when empty)
+    pub countries: Vec<String>,
 }
 
 impl Default for QueryOptions {
@@ -77,6 +117,11 @@ impl Default for QueryOptions {
             ylo: None,
             yhi: None,
             all_results: true,
+            per_page: MAX_PER_PAGE,
+            page_delay: Duration::ZERO,
+            cache: None,
+            work_types: Vec::new(),
+            countries: Vec::new(),
         }
     }
 }
@@ -84,14 +129,12 @@ impl Default for QueryOptions {
 /// OpenAlex API response structures
 #[derive(Debug, Deserialize)]
 struct OpenAlexResponse {
-    #[allow(dead_code)]
     meta: OpenAlexMeta,
     results: Vec<OpenAlexWork>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAlexMeta {
-    #[allow(dead_code)]
     count: i64,
     #[allow(dead_code)]
     per_page: i32,
@@ -99,6 +142,68 @@ struct OpenAlexMeta {
     page: i32,
 }
 
+/// Response of a `group_by` request
+#[derive(Debug, Deserialize)]
+struct GroupByResponse {
+    group_by: Vec<GroupByEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroupByEntry {
+    key: String,
+    count: u64,
+}
+
+/// Works per publication year, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YearCounts(pub Vec<(i32, u64)>);
+
+impl YearCounts {
+    /// Works of all years
+    pub fn total(&self) -> u64 {
+        self.0.iter().map(|&(_, count)| count).sum()
+    }
+
+    /// Works published in `year` or later
+    pub fn since(&self, year: i32) -> u64 {
+        self.0.iter().filter(|&&(y, _)| y >= year).map(|&(_, count)| count).sum()
+    }
+
+    /// Latest start year that still keeps `coverage` (0-1) of the works
+    pub fn suggest_ylo(&self, coverage: f64) -> Option<i32> {
+        let wanted = (self.total() as f64 * coverage.clamp(0.0, 1.0)).ceil() as u64;
+        let mut kept = 0;
+        for &(year, count) in self.0.iter().rev() {
+            kept += count;
+            if kept >= wanted && kept > 0 {
+                return Some(year);
+            }
+        }
+        None
+    }
+
+    /// Text histogram, one bar per year; years beyond the last `max_rows`
+    /// are summed into a first "before" row
+    pub fn histogram(&self, max_rows: usize, width: usize) -> String {
+        let split = self.0.len().saturating_sub(max_rows.max(1));
+        let mut rows: Vec<(String, u64)> = Vec::new();
+        if let Some(&(first_shown, _)) = self.0.get(split).filter(|_| split > 0) {
+            rows.push((format!("before {}", first_shown), self.0[..split].iter().map(|&(_, count)| count).sum()));
+        }
+        rows.extend(self.0[split..].iter().map(|&(year, count)| (year.to_string(), count)));
+
+        let max = rows.iter().map(|&(_, count)| count).max().unwrap_or_default().max(1);
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or_default();
+        rows.iter()
+            .map(|(label, count)| {
+                let bar = ((*count as f64 / max as f64) * width as f64).round() as usize;
+                let bar = if *count > 0 { bar.max(1) } else { 0 };
+                format!("{:>label_width$} | {} {}\n", label, "█".repeat(bar), count)
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAlexWork {
     id: Option<String>,
@@ -123,11 +228,26 @@ struct OpenAlexWork {
     referenced_works_count: Option<i64>,
     related_works: Option<Vec<String>>,
     locations_count: Option<i64>,
+    grants: Option<Vec<OpenAlexGrant>>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAlexAuthorship {
     author: Option<OpenAlexAuthor>,
+    institutions: Option<Vec<OpenAlexInstitution>>,
+    countries: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexGrant {
+    funder_display_name: Option<String>,
+    award_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexInstitution {
+    display_name: Option<String>,
+    country_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -173,10 +293,20 @@ struct OpenAlexKeyword {
 #[derive(Debug, Deserialize)]
 struct OpenAlexTopic {
     display_name: Option<String>,
-    #[allow(dead_code)]
     id: Option<String>,
 }
 
+/// Citation counts of a sample of works
+#[derive(Debug, Deserialize)]
+struct CitationSample {
+    results: Vec<CitationCount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CitationCount {
+    cited_by_count: Option<i64>,
+}
+
 /// Query OpenAlex for academic works.
 ///
 /// # Arguments
@@ -188,12 +318,21 @@ struct OpenAlexTopic {
 ///
 /// List of search results
 pub async fn query(search_query: &str, options: &QueryOptions) -> Result<Vec<OpenAlexResult>> {
+    let (tx, rx) = stream::channel();
+    let all_results = stream::collect(query_pages(search_query, options, tx), rx).await?;
+    info!(total = all_results.len(), "OpenAlex query complete");
+    Ok(all_results)
+}
+
+/// Query OpenAlex, sending each page down `tx` as soon as it is parsed.
+///
+/// Pages are fetched concurrently and sent in the order they complete
+/// (one at a time, `page_delay` apart, when a delay is set); failed pages
+/// are logged and skipped.
+pub async fn query_pages(search_query: &str, options: &QueryOptions, tx: PageSender<OpenAlexResult>) -> Result<()> {
     use futures::stream::{self, StreamExt};
     
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .user_agent("rustgscholar/1.0 (mailto:c76d@c.com)")
-        .build()?;
+    let client = http::client_for(Upstream::OpenAlex);
 
     info!(
         query = search_query,
@@ -212,123 +351,231 @@ pub async fn query(search_query: &str, options: &QueryOptions) -> Result<Vec<Ope
         .collect();
 
     // Concurrent fetching with 5 parallel requests (polite pool allows 10/s)
-    let concurrent_limit = 5;
-    
-    let results: Vec<(i32, std::result::Result<Vec<OpenAlexResult>, GscholarError>)> = stream::iter(urls)
-        .map(|(page, url)| {
+    let concurrent_limit = if options.page_delay.is_zero() { 5 } else { 1 };
+
+    let mut pages = stream::iter(urls.into_iter().enumerate())
+        .map(|(index, (page, url))| {
             let client = client.clone();
+            let cache = options.cache.as_ref();
             async move {
+                if index > 0 && !options.page_delay.is_zero() {
+                    tokio::time::sleep(options.page_delay).await;
+                }
                 debug!(url = %url, page = page, "Fetching OpenAlex page");
-                match fetch_page(&client, &url).await {
+                match fetch_cached(&client, &url, cache).await {
                     Ok(response) => {
                         let works = parse_response(&response);
                         (page, works)
                     }
-                    Err(e) => {
-                        warn!(page = page, error = %e, "Failed to fetch page");
-                        (page, Ok(Vec::new()))
-                    }
+                    Err(e) => (page, Err(e)),
                 }
             }
         })
-        .buffer_unordered(concurrent_limit)
-        .collect()
-        .await;
-
-    // Combine results in page order
-    let mut all_results = Vec::new();
-    let mut sorted_results: Vec<_> = results.into_iter().collect();
-    sorted_results.sort_by_key(|(page, _)| *page);
-    
-    for (page, result) in sorted_results {
+        // Pages arrive as they finish; receivers put them back by page number
+        .buffer_unordered(concurrent_limit);
+
+    while let Some((page, result)) = pages.next().await {
         match result {
             Ok(works) => {
-                let count = works.len();
-                info!(page = page, count = count, "Parsed OpenAlex results");
-                if options.all_results {
-                    all_results.extend(works);
-                } else if let Some(first) = works.into_iter().next() {
-                    all_results.push(first);
+                info!(page = page, count = works.len(), "Parsed OpenAlex results");
+                let items = if options.all_results {
+                    works
+                } else {
+                    works.into_iter().take(1).collect()
+                };
+                if tx.send(Page::new(page, items)).await.is_err() {
+                    break;
                 }
             }
             Err(e) => {
-                warn!(page = page, error = %e, "Parse error");
+                warn!(page = page, error = %e, "Failed to fetch page");
+                if tx.send(Page::failed(page, e)).await.is_err() {
+                    break;
+                }
             }
         }
     }
+    Ok(())
+}
 
-    info!(total = all_results.len(), "OpenAlex query complete");
-    Ok(all_results)
+/// Look up one work by DOI (`None` when OpenAlex does not know it)
+pub async fn lookup_by_doi(client: &Client, doi: &str, cache: Option<&HttpCache>) -> Result<Option<OpenAlexResult>> {
+    let doi = doi.trim();
+    if doi.is_empty() {
+        return Ok(None);
+    }
+    let url = format!(
+        "{}/works?filter=doi:{}&per-page=1&mailto={}",
+        OPENALEX_API_BASE,
+        urlencoding::encode(doi),
+        POLITE_EMAIL
+    );
+    let body = fetch_cached(client, &url, cache).await?;
+    Ok(parse_response(&body)?.into_iter().next())
+}
+
+/// Mean citation count of the journal articles of one primary topic and
+/// publication year, estimated from a fixed random sample of
+/// [`TOPIC_SAMPLE_SIZE`] works (`None` when there are none)
+pub async fn topic_citation_mean(client: &Client, topic_id: &str, year: u32, cache: Option<&HttpCache>) -> Result<Option<f64>> {
+    let topic = topic_id.trim().rsplit('/').next().unwrap_or_default();
+    if topic.is_empty() {
+        return Ok(None);
+    }
+    // A fixed seed draws the same sample every time, so the mean is stable and cacheable
+    let url = format!(
+        "{}/works?filter=primary_topic.id:{},publication_year:{},type:article&sample={}&seed=1&per-page={}&select=cited_by_count&mailto={}",
+        OPENALEX_API_BASE,
+        urlencoding::encode(topic),
+        year,
+        TOPIC_SAMPLE_SIZE,
+        TOPIC_SAMPLE_SIZE,
+        POLITE_EMAIL
+    );
+    let body = fetch_cached(client, &url, cache).await?;
+    let sample: CitationSample = serde_json::from_str(&body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse OpenAlex response: {}", e)))?;
+    Ok(mean_citations(&sample))
+}
+
+fn mean_citations(sample: &CitationSample) -> Option<f64> {
+    let counts: Vec<i64> = sample.results.iter().map(|w| w.cited_by_count.unwrap_or(0).max(0)).collect();
+    (!counts.is_empty()).then(|| counts.iter().sum::<i64>() as f64 / counts.len() as f64)
 }
 
 /// Build OpenAlex API search URL
-fn build_search_url(query: &str, page: i32, options: &QueryOptions) -> Result<String> {
+pub fn build_search_url(query: &str, page: i32, options: &QueryOptions) -> Result<String> {
     let mut url = format!(
         "{}/works?search={}&per-page={}&page={}&mailto={}",
         OPENALEX_API_BASE,
         urlencoding::encode(query),
-        MAX_PER_PAGE,
+        options.per_page.clamp(1, MAX_PER_PAGE),
         page,
         POLITE_EMAIL
     );
 
-    // Add year filters
+    url.push_str(&format!("&filter={}", build_filters(options).join(",")));
+
+    // Select all needed fields
+    url.push_str("&select=id,title,display_name,publication_year,publication_date,doi,cited_by_count,abstract_inverted_index,authorships,primary_location,best_oa_location,open_access,language,type,keywords,primary_topic,referenced_works_count,related_works,locations_count,grants");
+
+    Ok(url)
+}
+
+/// Build the `filter` clauses shared by search and count requests
+fn build_filters(options: &QueryOptions) -> Vec<String> {
     let mut filters = Vec::new();
-    
+
+    // Add year filters
     if let Some(ylo) = options.ylo {
         filters.push(format!("publication_year:>{}", ylo - 1));
     }
-    
+
     if let Some(yhi) = options.yhi {
         filters.push(format!("publication_year:<{}", yhi + 1));
     }
 
-    // Filter for journal articles only (type:article)
-    filters.push("type:article".to_string());
+    // Filter for the wanted work types (journal articles by default)
+    filters.push(work_type::openalex_filter(&options.work_types));
 
-    if !filters.is_empty() {
-        url.push_str(&format!("&filter={}", filters.join(",")));
+    if !options.countries.is_empty() {
+        filters.push(format!("institutions.country_code:{}", options.countries.join("|")));
     }
 
-    // Select all needed fields
-    url.push_str("&select=id,title,display_name,publication_year,publication_date,doi,cited_by_count,abstract_inverted_index,authorships,primary_location,best_oa_location,open_access,language,type,keywords,primary_topic,referenced_works_count,related_works,locations_count");
+    filters
+}
 
-    Ok(url)
+/// Count the works matching a search query without fetching them.
+///
+/// Uses the same filters as [`query`] (year range, work types), so
+/// the count is comparable across query variants.
+pub async fn count(search_query: &str, options: &QueryOptions) -> Result<i64> {
+    let client = http::client_for(Upstream::OpenAlex);
+
+    let url = format!(
+        "{}/works?search={}&per-page=1&select=id&filter={}&mailto={}",
+        OPENALEX_API_BASE,
+        urlencoding::encode(search_query),
+        build_filters(options).join(","),
+        POLITE_EMAIL
+    );
+
+    let body = fetch_cached(&client, &url, options.cache.as_ref()).await?;
+    let response: OpenAlexResponse = serde_json::from_str(&body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse OpenAlex response: {}", e)))?;
+
+    debug!(query = search_query, count = response.meta.count, "OpenAlex hit count");
+    Ok(response.meta.count)
 }
 
-/// Fetch page content from OpenAlex API
-async fn fetch_page(client: &Client, url: &str) -> Result<String> {
-    let mut retries = 0;
-    let max_retries = 3;
+/// Works per publication year matching a search query (`group_by=publication_year`)
+///
+/// Uses the same filters as [`count`]; leave [`QueryOptions::ylo`] unset to
+/// see the whole history of the topic.
+pub async fn year_counts(search_query: &str, options: &QueryOptions) -> Result<YearCounts> {
+    let client = http::client_for(Upstream::OpenAlex);
 
-    loop {
-        let response = client.get(url).send().await?;
-        let status = response.status();
+    let url = format!(
+        "{}/works?search={}&filter={}&group_by=publication_year&mailto={}",
+        OPENALEX_API_BASE,
+        urlencoding::encode(search_query),
+        build_filters(options).join(","),
+        POLITE_EMAIL
+    );
 
-        if status.is_success() {
-            return response.text().await.map_err(|e| GscholarError::Network(e));
-        }
+    let body = fetch_cached(&client, &url, options.cache.as_ref()).await?;
+    parse_year_counts(&body)
+}
 
-        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            if retries < max_retries {
-                let backoff = Duration::from_secs(2u64.pow(retries));
-                warn!(
-                    retries = retries,
-                    backoff_secs = backoff.as_secs(),
-                    "Rate limited, backing off"
-                );
-                tokio::time::sleep(backoff).await;
-                retries += 1;
-                continue;
-            }
-            return Err(GscholarError::RateLimited(60));
+fn parse_year_counts(body: &str) -> Result<YearCounts> {
+    let response: GroupByResponse = serde_json::from_str(body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse OpenAlex response: {}", e)))?;
+    let mut years: Vec<(i32, u64)> = response
+        .group_by
+        .into_iter()
+        .filter_map(|entry| entry.key.parse().ok().map(|year| (year, entry.count)))
+        .collect();
+    years.sort_unstable();
+    Ok(YearCounts(years))
+}
+
+/// Fetch page content, from the HTTP cache when possible
+async fn fetch_cached(client: &Client, url: &str, cache: Option<&HttpCache>) -> Result<String> {
+    let key = cache::request_key("GET", url, "");
+    if let Some(body) = cache.and_then(|c| c.get(&key)) {
+        debug!(url = %url, "OpenAlex cache hit");
+        return Ok(body);
+    }
+
+    let body = fetch_page(client, url).await?;
+    if let Some(cache) = cache {
+        if let Err(e) = cache.insert(&key, &body) {
+            warn!(error = %e, "Failed to write OpenAlex cache");
         }
+    }
+    Ok(body)
+}
 
-        return Err(GscholarError::Api {
-            code: status.as_u16() as i32,
-            message: format!("OpenAlex API error: {}", status),
-        });
+/// Fetch page content from OpenAlex API
+async fn fetch_page(client: &Client, url: &str) -> Result<String> {
+    let response = {
+        let _slot = throttle::acquire(Upstream::OpenAlex).await;
+        http::send_to(Upstream::OpenAlex, client, client.get(url).build()?).await?
+    };
+    let status = response.status();
+
+    if status.is_success() {
+        let body = response.text().await.map_err(GscholarError::Network)?;
+        raw_archive::record(url, BodyKind::Json, &body);
+        return Ok(body);
+    }
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(GscholarError::RateLimited(60));
     }
+    Err(GscholarError::Api {
+        code: status.as_u16() as i32,
+        message: format!("OpenAlex API error: {}", status),
+    })
 }
 
 /// Parse OpenAlex API response
@@ -339,12 +586,11 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
     let mut results = Vec::new();
 
     for work in response.results {
-        let mut result = OpenAlexResult::default();
-
         // Title
-        result.title = work.display_name
-            .or(work.title)
-            .unwrap_or_default();
+        let mut result = OpenAlexResult {
+            title: work.display_name.or(work.title).unwrap_or_default(),
+            ..Default::default()
+        };
 
         // OpenAlex ID
         result.openalex_id = work.id.unwrap_or_default();
@@ -354,9 +600,9 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
             result.year = year.to_string();
         }
 
-        // DOI (clean format without https://doi.org/ prefix)
+        // DOI (canonical form, without the https://doi.org/ prefix)
         if let Some(doi) = work.doi {
-            result.doi = doi.replace("https://doi.org/", "");
+            result.doi = doi::normalize(&doi);
         }
 
         // Citations
@@ -376,6 +622,28 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
             if authorships.len() > 3 {
                 result.author.push_str(" ...");
             }
+
+            // Institutions and countries of all authors
+            let mut institutions: Vec<String> = Vec::new();
+            let mut countries: Vec<String> = Vec::new();
+            for authorship in &authorships {
+                for institution in authorship.institutions.iter().flatten() {
+                    if let Some(name) = institution.display_name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+                        if !institutions.iter().any(|i| i == name) {
+                            institutions.push(name.to_string());
+                        }
+                    }
+                }
+                let codes = authorship.countries.iter().flatten().map(String::as_str);
+                let institution_codes = authorship.institutions.iter().flatten().filter_map(|i| i.country_code.as_deref());
+                for code in codes.chain(institution_codes).map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty()) {
+                    if !countries.contains(&code) {
+                        countries.push(code);
+                    }
+                }
+            }
+            result.institutions = institutions.join("; ");
+            result.countries = countries.join("; ");
         }
 
         // Venue (journal/source name) and source type
@@ -383,6 +651,7 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
             if let Some(source) = &location.source {
                 result.venue = source.display_name.clone().unwrap_or_default();
                 result.source_type = source.source_type.clone().unwrap_or_default();
+                result.issn = source.issn_l.clone().unwrap_or_default();
             }
             // Article URL
             result.article_url = location.landing_page_url.clone().unwrap_or_default();
@@ -440,6 +709,7 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
         // Primary topic
         if let Some(topic) = work.primary_topic {
             result.primary_topic = topic.display_name.unwrap_or_default();
+            result.primary_topic_id = topic.id.unwrap_or_default();
         }
 
         // Referenced works (full list as comma-separated)
@@ -456,6 +726,24 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
             result.related_works_count = related.len() as i64;
         }
 
+        // Funders and grant numbers
+        if let Some(grants) = work.grants {
+            let mut funders: Vec<String> = Vec::new();
+            let mut awards: Vec<String> = Vec::new();
+            for grant in grants {
+                let funder = grant.funder_display_name.unwrap_or_default().trim().to_string();
+                if !funder.is_empty() && !funders.contains(&funder) {
+                    funders.push(funder);
+                }
+                let award = grant.award_id.unwrap_or_default().trim().to_string();
+                if !award.is_empty() && !awards.contains(&award) {
+                    awards.push(award);
+                }
+            }
+            result.funders = funders.join("; ");
+            result.grants = awards.join("; ");
+        }
+
         // Locations count
         result.locations_count = work.locations_count.unwrap_or(0);
 
@@ -468,6 +756,25 @@ fn parse_response(json_str: &str) -> Result<Vec<OpenAlexResult>> {
     Ok(results)
 }
 
+/// Parse `--country` codes (ISO 3166-1 alpha-2, e.g., "CN", "us"),
+/// uppercased and without duplicates
+pub fn parse_country_codes(codes: &[String]) -> Result<Vec<String>> {
+    let mut countries: Vec<String> = Vec::new();
+    for code in codes.iter().map(|c| c.trim()).filter(|c| !c.is_empty()) {
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(GscholarError::Validation(format!(
+                "Invalid country code '{}' (expected a two-letter ISO 3166-1 code such as CN or US)",
+                code
+            )));
+        }
+        let code = code.to_uppercase();
+        if !countries.contains(&code) {
+            countries.push(code);
+        }
+    }
+    Ok(countries)
+}
+
 /// Reconstruct abstract text from inverted index
 /// OpenAlex provides abstract as inverted index for legal reasons.
 /// This function reconstructs the full plaintext abstract.
@@ -500,17 +807,74 @@ mod tests {
 
     #[test]
     fn test_build_search_url() {
-        let options = QueryOptions {
+        let mut options = QueryOptions {
             pages: vec![1],
             ylo: Some(2020),
             yhi: None,
             all_results: true,
+            cache: None,
+            ..Default::default()
         };
         
-        let url = build_search_url("machine learning", 1, &options).unwrap();
+        let url = build_search_url("machine learning", 1, &options).expect("Failed to build URL");
         assert!(url.contains("search=machine%20learning"));
         assert!(url.contains("per-page=200"));
         assert!(url.contains("mailto="));
-        assert!(url.contains("publication_year:>2019"));
+        assert!(url.contains("publication_year:>2019,type:article&"));
+
+        options.per_page = 25;
+        assert!(build_search_url("machine learning", 2, &options).expect("Failed to build URL").contains("per-page=25&page=2"));
+        options.per_page = 1000;
+        assert!(build_search_url("machine learning", 1, &options).expect("Failed to build URL").contains("per-page=200"));
+        options.work_types = vec![WorkType::Review, WorkType::Preprint];
+        assert!(build_search_url("machine learning", 1, &options).expect("Failed to build URL").contains("type:review|preprint"));
+        options.countries = vec!["CN".to_string(), "IT".to_string()];
+        assert!(build_search_url("machine learning", 1, &options)
+            .expect("Failed to build URL")
+            .contains("type:review|preprint,institutions.country_code:CN|IT&"));
+    }
+
+    #[test]
+    fn test_authorships_and_grants() -> Result<()> {
+        let body = r#"{"meta": {"count": 1, "per_page": 25, "page": 1}, "results": [{
+            "display_name": "Landslide mapping",
+            "authorships": [
+                {"author": {"display_name": "Wei Li"}, "countries": ["CN"],
+                 "institutions": [{"display_name": "Tongji University", "country_code": "CN"}]},
+                {"author": {"display_name": "Anna Rossi"}, "countries": [],
+                 "institutions": [{"display_name": "University of Florence", "country_code": "IT"},
+                                  {"display_name": "Tongji University", "country_code": "CN"}]}
+            ],
+            "grants": [{"funder_display_name": "European Commission", "award_id": "101003534"},
+                       {"funder_display_name": "European Commission", "award_id": null}]}]}"#;
+        let results = parse_response(body)?;
+        assert_eq!(results[0].institutions, "Tongji University; University of Florence");
+        assert_eq!(results[0].countries, "CN; IT");
+        assert_eq!((results[0].funders.as_str(), results[0].grants.as_str()), ("European Commission", "101003534"));
+        assert_eq!(parse_country_codes(&["cn".to_string(), " CN".to_string(), "us".to_string()])?, vec!["CN", "US"]);
+        assert!(parse_country_codes(&["China".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_year_counts() -> Result<()> {
+        let body = r#"{"meta": {"count": 100}, "results": [], "group_by": [
+            {"key": "2024", "key_display_name": "2024", "count": 40},
+            {"key": "2020", "key_display_name": "2020", "count": 10},
+            {"key": "2023", "key_display_name": "2023", "count": 30},
+            {"key": "1998", "key_display_name": "1998", "count": 5},
+            {"key": "2022", "key_display_name": "2022", "count": 15}]}"#;
+        let counts = parse_year_counts(body)?;
+        assert_eq!(counts.0.first(), Some(&(1998, 5)));
+        assert_eq!((counts.total(), counts.since(2022)), (100, 85));
+        assert_eq!(counts.suggest_ylo(YLO_COVERAGE), Some(2020));
+        assert_eq!(counts.suggest_ylo(0.5), Some(2023));
+        assert_eq!(YearCounts::default().suggest_ylo(YLO_COVERAGE), None);
+
+        let histogram = counts.histogram(3, 8);
+        assert_eq!(histogram.lines().count(), 4);
+        assert!(histogram.starts_with("before 2022 | ███ 15\n"));
+        assert!(histogram.ends_with("       2024 | ████████ 40\n"));
+        Ok(())
     }
 }
@@ -0,0 +1,523 @@
+//! ORCID author resolution.
+//!
+//! Resolves the authors of a run folder's papers to stable identifiers: the
+//! OpenAlex authorships of each DOI give the OpenAlex author ID, the ORCID iD
+//! when OpenAlex knows it, and the institutions; authors still without an
+//! iD are matched by name against the public ORCID records that list the
+//! DOI. [`author_table`] merges the results into one row per person, so
+//! "Li, W." and "Wei Li" count as one author in bibliometric analyses, and
+//! [`author_summary`] ranks those people by their h-index within the corpus
+//! to point out the key researchers of the field.
+
+use crate::cache::{self, HttpCache};
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::network::{field, read_rows};
+use crate::openalex;
+use crate::paper::{self, Author};
+use crate::raw_archive::{self, BodyKind};
+use crate::stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Public ORCID API base URL
+pub const ORCID_API_URL: &str = "https://pub.orcid.org/v3.0";
+
+/// Papers resolved at once
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+/// One author of one paper
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuthorRecord {
+    pub name: String,
+    /// Bare ORCID iD (e.g., "0000-0002-1825-0097")
+    pub orcid: String,
+    /// OpenAlex author ID (e.g., "A5023888391")
+    pub openalex_id: String,
+    pub affiliations: Vec<String>,
+}
+
+/// Row of `authors.csv`: one person across all papers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthorRow {
+    /// Most frequent spelling of the name
+    pub author: String,
+    pub orcid: String,
+    pub openalex_id: String,
+    /// Affiliations as a "; " separated list
+    pub affiliations: String,
+    /// Every spelling found, "; " separated
+    pub name_variants: String,
+    pub papers: usize,
+    pub dois: String,
+}
+
+/// Row of `authors_summary.csv`: one person's output within the corpus
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthorSummaryRow {
+    pub author: String,
+    pub orcid: String,
+    pub openalex_id: String,
+    pub papers: usize,
+    /// Citations of those papers, summed
+    pub citations: u64,
+    /// Largest h such that h of the author's papers here have at least h citations each
+    pub h_index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexWork {
+    #[serde(default)]
+    authorships: Vec<OpenAlexAuthorship>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexAuthorship {
+    author: Option<OpenAlexAuthor>,
+    #[serde(default)]
+    institutions: Vec<OpenAlexInstitution>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexAuthor {
+    id: Option<String>,
+    display_name: Option<String>,
+    orcid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAlexInstitution {
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrcidSearch {
+    #[serde(rename = "expanded-result", default)]
+    results: Option<Vec<OrcidPerson>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrcidPerson {
+    #[serde(rename = "orcid-id", default)]
+    orcid: String,
+    #[serde(rename = "given-names", default)]
+    given_names: Option<String>,
+    #[serde(rename = "family-names", default)]
+    family_names: Option<String>,
+    #[serde(rename = "institution-name", default)]
+    institutions: Option<Vec<String>>,
+}
+
+/// Author resolver (OpenAlex authorships, then ORCID search)
+pub struct OrcidResolver {
+    client: reqwest::Client,
+    cache: Option<HttpCache>,
+    orcid_search: bool,
+}
+
+impl OrcidResolver {
+    pub fn new() -> Result<Self> {
+        Ok(Self { client: http::client(), cache: None, orcid_search: true })
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/orcid`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Whether to search ORCID for authors OpenAlex has no iD for (default on)
+    pub fn with_orcid_search(mut self, enabled: bool) -> Self {
+        self.orcid_search = enabled;
+        self
+    }
+
+    /// Resolve the authors of each (DOI, authors) pair; the records by DOI
+    /// and the (DOI, error) of each failed lookup
+    pub async fn resolve_all(
+        &self,
+        papers: &[(String, Vec<Author>)],
+    ) -> (Vec<(String, Vec<AuthorRecord>)>, Vec<(String, String)>) {
+        let lookups: Vec<_> = papers
+            .iter()
+            .map(|(doi, authors)| async move { (doi.clone(), self.resolve(doi, authors).await) })
+            .collect();
+        let outcomes = stream::in_order(lookups, MAX_CONCURRENT_LOOKUPS).await;
+
+        let mut resolved = Vec::new();
+        let mut failed = Vec::new();
+        for (doi, outcome) in outcomes {
+            match outcome {
+                Ok(records) => resolved.push((doi, records)),
+                Err(e) => failed.push((doi, e.to_string())),
+            }
+        }
+        (resolved, failed)
+    }
+
+    /// Authors of one paper; `authors` (as the CSV lists them) is used when
+    /// OpenAlex does not know the DOI
+    pub async fn resolve(&self, doi: &str, authors: &[Author]) -> Result<Vec<AuthorRecord>> {
+        let doi = doi.trim();
+        let mut records = match doi {
+            "" => Vec::new(),
+            doi => self.openalex_authors(doi).await?,
+        };
+        if records.is_empty() {
+            records = authors
+                .iter()
+                .map(|a| AuthorRecord { name: a.name.clone(), ..Default::default() })
+                .collect();
+        }
+        if self.orcid_search && !doi.is_empty() && records.iter().any(|r| r.orcid.is_empty()) {
+            let people = self.orcid_people(doi).await?;
+            match_orcid(&mut records, &people);
+        }
+        Ok(records)
+    }
+
+    async fn openalex_authors(&self, doi: &str) -> Result<Vec<AuthorRecord>> {
+        let url = format!(
+            "{}/works/doi:{}?select=authorships",
+            openalex::OPENALEX_API_BASE,
+            urlencoding::encode(&doi.to_lowercase())
+        );
+        match self.get(&url, "OpenAlex").await? {
+            Some(body) => parse_openalex(&body),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn orcid_people(&self, doi: &str) -> Result<Vec<OrcidPerson>> {
+        let query = format!("doi-self:\"{}\"", doi.to_lowercase());
+        let url = format!("{}/expanded-search/?q={}&rows=50", ORCID_API_URL, urlencoding::encode(&query));
+        match self.get(&url, "ORCID").await? {
+            Some(body) => parse_orcid(&body),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// GET a JSON body through the cache (`None` on 404)
+    async fn get(&self, url: &str, api: &str) -> Result<Option<String>> {
+        let key = cache::request_key("GET", url, "");
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(url = %url, "{} cache hit", api);
+            return Ok(Some(body));
+        }
+        let request = self.client.get(url).header(reqwest::header::ACCEPT, "application/json").build()?;
+        let response = http::send(&self.client, request).await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(None),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(10)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("{} API error: {}", api, status),
+                });
+            }
+            _ => {}
+        }
+        let body = response.text().await?;
+        raw_archive::record(url, BodyKind::Json, &body);
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write {} cache", api);
+            }
+        }
+        Ok(Some(body))
+    }
+}
+
+/// A run folder's `7_relevant.csv`, else its `5_unified.csv`
+fn paper_table(folder: &Path) -> Result<std::path::PathBuf> {
+    ["7_relevant.csv", "5_unified.csv"]
+        .iter()
+        .map(|name| folder.join(name))
+        .find(|path| path.exists())
+        .ok_or_else(|| GscholarError::Validation(format!("{:?} has no 7_relevant.csv or 5_unified.csv", folder)))
+}
+
+/// (DOI, authors) of each paper in a run folder's `7_relevant.csv`, else its
+/// `5_unified.csv`
+pub fn papers_from_run_folder(folder: &Path) -> Result<Vec<(String, Vec<Author>)>> {
+    Ok(read_rows(&paper_table(folder)?)?
+        .iter()
+        .map(|row| (field(row, "doi").to_string(), paper::parse_authors(field(row, "author")).0))
+        .filter(|(doi, authors)| !doi.is_empty() || !authors.is_empty())
+        .collect())
+}
+
+/// Citation count by normalized DOI of the same papers (papers without a
+/// DOI or a count are left out)
+pub fn citations_from_run_folder(folder: &Path) -> Result<HashMap<String, u64>> {
+    Ok(read_rows(&paper_table(folder)?)?
+        .iter()
+        .filter_map(|row| Some((doi::normalize(field(row, "doi")), paper::parse_count(field(row, "citations"))?)))
+        .filter(|(doi, _)| !doi.is_empty())
+        .collect())
+}
+
+/// Merge the records of all papers into one row per person: by ORCID iD,
+/// else OpenAlex ID, else normalized name. Rows are sorted by paper count.
+pub fn author_table(resolved: &[(String, Vec<AuthorRecord>)]) -> Vec<AuthorRow> {
+    struct Person {
+        names: Vec<(String, usize)>,
+        orcid: String,
+        openalex_id: String,
+        affiliations: Vec<String>,
+        dois: Vec<String>,
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let mut people: HashMap<String, Person> = HashMap::new();
+    for (doi, records) in resolved {
+        for record in records {
+            let key = match (record.orcid.as_str(), record.openalex_id.as_str()) {
+                ("", "") => format!("name:{}", normalize_name(&record.name)),
+                ("", id) => format!("openalex:{}", id),
+                (orcid, _) => format!("orcid:{}", orcid),
+            };
+            let person = people.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                Person { names: Vec::new(), orcid: String::new(), openalex_id: String::new(), affiliations: Vec::new(), dois: Vec::new() }
+            });
+            match person.names.iter_mut().find(|(name, _)| *name == record.name) {
+                Some((_, count)) => *count += 1,
+                None => person.names.push((record.name.clone(), 1)),
+            }
+            if person.orcid.is_empty() {
+                person.orcid = record.orcid.clone();
+            }
+            if person.openalex_id.is_empty() {
+                person.openalex_id = record.openalex_id.clone();
+            }
+            for affiliation in &record.affiliations {
+                if !person.affiliations.contains(affiliation) {
+                    person.affiliations.push(affiliation.clone());
+                }
+            }
+            if !doi.is_empty() && !person.dois.contains(doi) {
+                person.dois.push(doi.clone());
+            }
+        }
+    }
+
+    let mut rows: Vec<AuthorRow> = order
+        .iter()
+        .filter_map(|key| people.remove(key))
+        .map(|person| {
+            let author = person
+                .names
+                .iter()
+                .fold(None::<&(String, usize)>, |best, name| match best {
+                    Some(best) if best.1 >= name.1 => Some(best),
+                    _ => Some(name),
+                })
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default();
+            let mut variants: Vec<&str> = person.names.iter().map(|(name, _)| name.as_str()).collect();
+            variants.sort_unstable();
+            AuthorRow {
+                author,
+                orcid: person.orcid,
+                openalex_id: person.openalex_id,
+                affiliations: person.affiliations.join("; "),
+                name_variants: variants.join("; "),
+                papers: person.names.iter().map(|(_, count)| count).sum(),
+                dois: person.dois.join("; "),
+            }
+        })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.papers));
+    rows
+}
+
+/// Per-person paper and citation counts and h-index within the corpus,
+/// from the rows of [`author_table`] and the citation counts by DOI.
+/// Papers without a known count count as uncited. Rows are sorted by
+/// h-index, then citations.
+pub fn author_summary(rows: &[AuthorRow], citations: &HashMap<String, u64>) -> Vec<AuthorSummaryRow> {
+    let mut summary: Vec<AuthorSummaryRow> = rows
+        .iter()
+        .map(|row| {
+            let counts: Vec<u64> = row
+                .dois
+                .split("; ")
+                .filter(|doi| !doi.trim().is_empty())
+                .map(|doi| citations.get(&doi::normalize(doi)).copied().unwrap_or(0))
+                .collect();
+            AuthorSummaryRow {
+                author: row.author.clone(),
+                orcid: row.orcid.clone(),
+                openalex_id: row.openalex_id.clone(),
+                papers: row.papers,
+                citations: counts.iter().sum(),
+                h_index: h_index(counts),
+            }
+        })
+        .collect();
+    summary.sort_by_key(|row| std::cmp::Reverse((row.h_index, row.citations)));
+    summary
+}
+
+/// h-index of a list of citation counts
+pub fn h_index(mut counts: Vec<u64>) -> usize {
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts.iter().enumerate().take_while(|(rank, &count)| count > *rank as u64).count()
+}
+
+/// Write `authors.csv` into `folder`
+pub fn save_author_table(rows: &[AuthorRow], folder: &Path) -> Result<std::path::PathBuf> {
+    save_rows(rows, folder.join("authors.csv"))
+}
+
+/// Write `authors_summary.csv` into `folder`
+pub fn save_author_summary(rows: &[AuthorSummaryRow], folder: &Path) -> Result<std::path::PathBuf> {
+    save_rows(rows, folder.join("authors_summary.csv"))
+}
+
+fn save_rows<T: Serialize>(rows: &[T], path: std::path::PathBuf) -> Result<std::path::PathBuf> {
+    let csv_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write {:?}: {}", path, e));
+    let mut wtr = csv::Writer::from_path(&path).map_err(csv_err)?;
+    for row in rows {
+        wtr.serialize(row).map_err(csv_err)?;
+    }
+    wtr.flush()?;
+    Ok(path)
+}
+
+fn parse_openalex(body: &str) -> Result<Vec<AuthorRecord>> {
+    let work: OpenAlexWork = serde_json::from_str(body)?;
+    Ok(work
+        .authorships
+        .into_iter()
+        .filter_map(|authorship| {
+            let author = authorship.author?;
+            let name = author.display_name.filter(|n| !n.trim().is_empty())?;
+            Some(AuthorRecord {
+                name: name.trim().to_string(),
+                orcid: author.orcid.as_deref().map(bare_orcid).unwrap_or_default(),
+                openalex_id: author.id.as_deref().map(|id| id.rsplit('/').next().unwrap_or(id).to_string()).unwrap_or_default(),
+                affiliations: authorship.institutions.into_iter().filter_map(|i| i.display_name).collect(),
+            })
+        })
+        .collect())
+}
+
+fn parse_orcid(body: &str) -> Result<Vec<OrcidPerson>> {
+    let search: OrcidSearch = serde_json::from_str(body)?;
+    Ok(search.results.unwrap_or_default())
+}
+
+/// Give each record without an iD the ORCID record with the same family name
+/// and first initial
+fn match_orcid(records: &mut [AuthorRecord], people: &[OrcidPerson]) {
+    for record in records.iter_mut().filter(|r| r.orcid.is_empty()) {
+        let author = Author::new(&record.name);
+        let family = normalize_name(author.family_name());
+        let initial = given_initial(&author);
+        let found = people.iter().find(|person| {
+            let given = person.given_names.as_deref().unwrap_or_default();
+            normalize_name(person.family_names.as_deref().unwrap_or_default()) == family
+                && initial.is_some_and(|i| normalize_name(given).starts_with(i))
+        });
+        if let Some(person) = found {
+            record.orcid = bare_orcid(&person.orcid);
+            if record.affiliations.is_empty() {
+                record.affiliations = person.institutions.clone().unwrap_or_default();
+            }
+        }
+    }
+}
+
+/// First letter of the given names ("Li, Wei" and "Wei Li" both give 'w')
+fn given_initial(author: &Author) -> Option<char> {
+    let given = match author.name.split_once(',') {
+        Some((_, given)) => given.trim(),
+        None => author.name.split_whitespace().next().unwrap_or_default(),
+    };
+    normalize_name(given).chars().next()
+}
+
+/// "https://orcid.org/0000-..." -> "0000-..."
+fn bare_orcid(value: &str) -> String {
+    value.trim().rsplit('/').next().unwrap_or_default().to_uppercase()
+}
+
+/// Lowercase letters and single spaces only
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match() -> Result<()> {
+        let openalex = r#"{"authorships": [
+            {"author": {"id": "https://openalex.org/A5023888391", "display_name": "Wei Li", "orcid": "https://orcid.org/0000-0002-1825-009x"},
+             "institutions": [{"display_name": "Tongji University"}]},
+            {"author": {"id": "https://openalex.org/A5000000002", "display_name": "Anna Rossi", "orcid": null}, "institutions": []}
+        ]}"#;
+        let mut records = parse_openalex(openalex)?;
+        assert_eq!(records[0].orcid, "0000-0002-1825-009X");
+        assert_eq!(records[0].openalex_id, "A5023888391");
+        assert_eq!(records[0].affiliations, vec!["Tongji University".to_string()]);
+
+        let orcid = r#"{"expanded-result": [
+            {"orcid-id": "0000-0001-5109-3700", "given-names": "Anna Maria", "family-names": "Rossi", "institution-name": ["University of Padova"]},
+            {"orcid-id": "0000-0003-0000-0001", "given-names": "Bruno", "family-names": "Rossi"}
+        ], "num-found": 2}"#;
+        match_orcid(&mut records, &parse_orcid(orcid)?);
+        assert_eq!(records[1].orcid, "0000-0001-5109-3700");
+        assert_eq!(records[1].affiliations, vec!["University of Padova".to_string()]);
+        assert!(parse_orcid(r#"{"expanded-result": null, "num-found": 0}"#)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_author_table_merges_by_identifier() {
+        let record = |name: &str, orcid: &str, affiliation: &str| AuthorRecord {
+            name: name.to_string(),
+            orcid: orcid.to_string(),
+            affiliations: vec![affiliation.to_string()],
+            ..Default::default()
+        };
+        let resolved = vec![
+            ("10.1/a".to_string(), vec![record("Wei Li", "0000-0001", "Tongji University"), record("J. Smith", "", "UCL")]),
+            ("10.1/b".to_string(), vec![record("W. Li", "0000-0001", "Tongji University")]),
+            ("10.1/c".to_string(), vec![record("Wei Li", "0000-0001", "Delft University of Technology"), record("J Smith", "", "UCL")]),
+        ];
+        let rows = author_table(&resolved);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].author, "Wei Li");
+        assert_eq!(rows[0].name_variants, "W. Li; Wei Li");
+        assert_eq!(rows[0].papers, 3);
+        assert_eq!(rows[0].affiliations, "Tongji University; Delft University of Technology");
+        assert_eq!(rows[0].dois, "10.1/a; 10.1/b; 10.1/c");
+        // Without an iD only identical normalized names merge
+        assert_eq!(rows[1].name_variants, "J Smith; J. Smith");
+        assert_eq!(rows[1].papers, 2);
+
+        let citations = HashMap::from([("10.1/a".to_string(), 10), ("10.1/b".to_string(), 1), ("10.1/c".to_string(), 4)]);
+        let summary = author_summary(&rows, &citations);
+        assert_eq!((summary[0].author.as_str(), summary[0].citations, summary[0].h_index), ("Wei Li", 15, 2));
+        assert_eq!((summary[1].author.as_str(), summary[1].citations, summary[1].h_index), ("J. Smith", 14, 2));
+    }
+
+    #[test]
+    fn test_h_index() {
+        assert_eq!(h_index(vec![10, 8, 5, 4, 3]), 4);
+        assert_eq!(h_index(vec![25, 8, 5, 3, 3]), 3);
+        assert_eq!(h_index(vec![0, 0]), 0);
+        assert_eq!(h_index(Vec::new()), 0);
+    }
+}
@@ -0,0 +1,399 @@
+//! Table output formats for stage results.
+//!
+//! Every stage produces a header plus string rows; [`write_table`] writes
+//! them in the format chosen with `--output-format`, replacing the `.csv`
+//! extension of the stage file name as needed. `jsonl` writes one JSON
+//! object per row, with numeric columns as numbers and empty cells as null.
+//! With `sqlite`, stage tables
+//! are kept as CSV and collected into `run.db` when the run finishes (see
+//! [`crate::sqlite::write_run_database`]).
+//!
+//! Stage 1 tables (a harvest of many OpenAlex pages) are written with a
+//! [`TableWriter`] instead: rows are appended to a CSV file and flushed
+//! page by page, so the file grows as pages arrive and a crashed run keeps
+//! what it had fetched. Only these writes are streamed; the search still
+//! collects its records in memory for the later stages, which write their
+//! tables whole. Parquet and JSONL tables are converted from that CSV once
+//! it is complete, since both need every value of a column to pick its
+//! type.
+
+use crate::error::{GscholarError, Result};
+use crate::parquet;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File format for stage tables
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    Parquet,
+    Sqlite,
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "parquet" => Ok(Self::Parquet),
+            "sqlite" if cfg!(feature = "sqlite") => Ok(Self::Sqlite),
+            "sqlite" => Err(GscholarError::Config("This build cannot write SQLite (feature \"sqlite\")".to_string())),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => Err(GscholarError::Config(format!("Invalid output format: {}", s))),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// File extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv | Self::Sqlite => "csv",
+            Self::Parquet => "parquet",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Write a table, returning the path actually written
+pub fn write_table(path: &Path, format: OutputFormat, header: &[String], rows: &[Vec<String>]) -> Result<PathBuf> {
+    let path = path.with_extension(format.extension());
+
+    match format {
+        OutputFormat::Csv | OutputFormat::Sqlite => {
+            let mut wtr = csv::Writer::from_path(&path)
+                .map_err(|e| GscholarError::Parse(format!("Failed to create CSV writer: {}", e)))?;
+            let write_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write CSV record: {}", e));
+            wtr.write_record(header).map_err(write_err)?;
+            for row in rows {
+                wtr.write_record(row).map_err(write_err)?;
+            }
+            wtr.flush()?;
+        }
+        OutputFormat::Parquet => parquet::write_table(&path, header, rows)?,
+        OutputFormat::Jsonl => {
+            let mut out = String::new();
+            for object in json_rows(header, rows) {
+                out.push_str(&serde_json::to_string(&object)?);
+                out.push('\n');
+            }
+            std::fs::write(&path, out)?;
+        }
+    }
+
+    Ok(path)
+}
+
+/// Serialize records into a header and string rows, with serde's CSV column
+/// layout (nested fields flattened, `None` as an empty cell)
+pub fn serialize_rows<T: Serialize>(data: &[T]) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let csv_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write CSV record: {}", e));
+    let mut wtr = csv::WriterBuilder::new().has_headers(true).from_writer(Vec::new());
+    for item in data {
+        wtr.serialize(item).map_err(csv_err)?;
+    }
+    let bytes = wtr
+        .into_inner()
+        .map_err(|e| GscholarError::Parse(format!("Failed to flush CSV: {}", e)))?;
+
+    let mut records = csv::ReaderBuilder::new().has_headers(false).from_reader(bytes.as_slice()).into_records();
+    let header: Vec<String> = match records.next() {
+        Some(record) => record.map_err(csv_err)?.iter().map(String::from).collect(),
+        None => Vec::new(),
+    };
+    let rows = records
+        .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+        .collect::<std::result::Result<Vec<Vec<String>>, _>>()
+        .map_err(csv_err)?;
+    Ok((header, rows))
+}
+
+/// Table written batch by batch as its rows are produced
+///
+/// The file is created with the first non-empty batch, so a table without
+/// rows leaves no file behind, as with [`write_table`].
+pub struct TableWriter {
+    path: PathBuf,
+    format: OutputFormat,
+    csv: Option<csv::Writer<File>>,
+    rows: usize,
+}
+
+impl TableWriter {
+    /// Writer for the table `path` (a `.csv` stage file name) in `format`
+    pub fn new(path: &Path, format: OutputFormat) -> Self {
+        Self { path: path.to_path_buf(), format, csv: None, rows: 0 }
+    }
+
+    /// CSV file the rows go to: the table itself, or a spool file for
+    /// formats converted at the end
+    fn csv_path(&self) -> PathBuf {
+        match self.format {
+            OutputFormat::Csv | OutputFormat::Sqlite => self.path.with_extension("csv"),
+            OutputFormat::Parquet | OutputFormat::Jsonl => self.path.with_extension("partial.csv"),
+        }
+    }
+
+    /// Rows written so far
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Append records (the header is written before the first batch)
+    pub fn serialize<T: Serialize>(&mut self, data: &[T]) -> Result<()> {
+        let (header, rows) = serialize_rows(data)?;
+        self.write(&header, &rows)
+    }
+
+    /// Append rows and flush them to disk
+    pub fn write(&mut self, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let write_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write CSV record: {}", e));
+        let wtr = match &mut self.csv {
+            Some(wtr) => wtr,
+            None => {
+                let mut wtr = csv::Writer::from_path(self.csv_path())
+                    .map_err(|e| GscholarError::Parse(format!("Failed to create CSV writer: {}", e)))?;
+                wtr.write_record(header).map_err(write_err)?;
+                self.csv.insert(wtr)
+            }
+        };
+        for row in rows {
+            wtr.write_record(row).map_err(write_err)?;
+        }
+        wtr.flush()?;
+        self.rows += rows.len();
+        Ok(())
+    }
+
+    /// Complete the table, returning the path written (`None` without rows)
+    pub fn finish(mut self) -> Result<Option<PathBuf>> {
+        let csv_path = self.csv_path();
+        let Some(mut wtr) = self.csv.take() else {
+            return Ok(None);
+        };
+        wtr.flush()?;
+        drop(wtr);
+        if matches!(self.format, OutputFormat::Csv | OutputFormat::Sqlite) {
+            return Ok(Some(csv_path));
+        }
+
+        let path = convert_csv(&csv_path, &self.path.with_extension(self.format.extension()), self.format)?;
+        std::fs::remove_file(&csv_path)?;
+        Ok(Some(path))
+    }
+}
+
+/// Rows read back at a time while converting a streamed table
+const CONVERT_CHUNK_ROWS: usize = 10_000;
+
+/// Convert a streamed CSV table to Parquet or JSONL without loading it: one
+/// pass infers the column types, a second converts the rows chunk by chunk
+fn convert_csv(csv_path: &Path, path: &Path, format: OutputFormat) -> Result<PathBuf> {
+    let read_err = |e: csv::Error| GscholarError::Parse(format!("Failed to read {:?}: {}", csv_path, e));
+    let open = || csv::ReaderBuilder::new().flexible(true).from_path(csv_path).map_err(read_err);
+
+    let mut reader = open()?;
+    let header: Vec<String> = reader.headers().map_err(read_err)?.iter().map(String::from).collect();
+    let mut inference = vec![TypeInference::default(); header.len()];
+    for record in reader.records() {
+        let record = record.map_err(read_err)?;
+        for (col, column) in inference.iter_mut().enumerate() {
+            column.add(record.get(col).unwrap_or_default());
+        }
+    }
+    let types: Vec<ColumnType> = inference.into_iter().map(TypeInference::finish).collect();
+
+    let mut records = open()?.into_records();
+    let mut next_chunk = || -> Result<Vec<Vec<String>>> {
+        records
+            .by_ref()
+            .take(CONVERT_CHUNK_ROWS)
+            .map(|r| r.map(|r| r.iter().map(String::from).collect()).map_err(read_err))
+            .collect()
+    };
+    match format {
+        OutputFormat::Parquet => {
+            let mut writer = parquet::ParquetWriter::create(path, &header, &types)?;
+            loop {
+                let chunk = next_chunk()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                writer.write(&chunk)?;
+            }
+            writer.close()?;
+        }
+        OutputFormat::Jsonl => {
+            let mut out = std::io::BufWriter::new(File::create(path)?);
+            loop {
+                let chunk = next_chunk()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                for row in &chunk {
+                    serde_json::to_writer(&mut out, &json_row(&header, &types, row))?;
+                    out.write_all(b"\n")?;
+                }
+            }
+            out.flush()?;
+        }
+        OutputFormat::Csv | OutputFormat::Sqlite => std::fs::copy(csv_path, path).map(drop)?,
+    }
+    Ok(path.to_path_buf())
+}
+
+/// Rows as JSON objects keyed by column name, with inferred value types
+pub fn json_rows(header: &[String], rows: &[Vec<String>]) -> Vec<serde_json::Map<String, serde_json::Value>> {
+    let types: Vec<ColumnType> = (0..header.len())
+        .map(|col| infer_type(rows.iter().map(|r| cell(r, col))))
+        .collect();
+    rows.iter().map(|row| json_row(header, &types, row)).collect()
+}
+
+/// One row as a JSON object, with the column types given
+fn json_row(header: &[String], types: &[ColumnType], row: &[String]) -> serde_json::Map<String, serde_json::Value> {
+    header
+        .iter()
+        .zip(types)
+        .enumerate()
+        .map(|(col, (name, column_type))| {
+            let value = cell(row, col);
+            let json = match column_type {
+                _ if value.is_empty() => serde_json::Value::Null,
+                ColumnType::Int64 => value.parse::<i64>().map(Into::into).unwrap_or_default(),
+                ColumnType::Double => value.parse::<f64>().map(Into::into).unwrap_or_default(),
+                ColumnType::Utf8 => value.into(),
+            };
+            (name.clone(), json)
+        })
+        .collect()
+}
+
+/// Column type inferred from string cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Int64,
+    Double,
+    Utf8,
+}
+
+/// Cell value, treating short rows as empty
+pub(crate) fn cell(row: &[String], col: usize) -> &str {
+    row.get(col).map(String::as_str).unwrap_or_default()
+}
+
+/// Narrowest type that holds every non-empty value: INT64 when every value
+/// is an integer, DOUBLE when every value is a number, UTF8 otherwise
+pub(crate) fn infer_type<'a>(values: impl Iterator<Item = &'a str>) -> ColumnType {
+    let mut inference = TypeInference::default();
+    values.for_each(|value| inference.add(value));
+    inference.finish()
+}
+
+/// [`infer_type`] fed one value at a time, for tables read row by row
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TypeInference(Option<ColumnType>);
+
+impl TypeInference {
+    pub(crate) fn add(&mut self, value: &str) {
+        if value.is_empty() || self.0 == Some(ColumnType::Utf8) {
+            return;
+        }
+        let numeric = value.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+            && value.chars().any(|c| c.is_ascii_digit());
+        // IDs with leading zeros ("0123") must keep their text form
+        let digits = value.trim_start_matches(['-', '+']);
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+
+        let value_type = if !numeric || leading_zero {
+            ColumnType::Utf8
+        } else if value.parse::<i64>().is_ok() {
+            ColumnType::Int64
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            ColumnType::Double
+        } else {
+            ColumnType::Utf8
+        };
+
+        self.0 = match (self.0, value_type) {
+            (_, ColumnType::Utf8) => Some(ColumnType::Utf8),
+            (Some(ColumnType::Double), _) | (_, ColumnType::Double) => Some(ColumnType::Double),
+            _ => Some(ColumnType::Int64),
+        };
+    }
+
+    pub(crate) fn finish(self) -> ColumnType {
+        self.0.unwrap_or(ColumnType::Utf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_table_extension() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let header = vec!["title".to_string()];
+        let rows = vec![vec!["A, B".to_string()]];
+
+        let csv_path = write_table(&dir.path().join("1_openalex.csv"), OutputFormat::Csv, &header, &rows)?;
+        assert_eq!(std::fs::read_to_string(&csv_path)?, "title\n\"A, B\"\n");
+
+        let parquet_path = write_table(&dir.path().join("1_openalex.csv"), "parquet".parse()?, &header, &rows)?;
+        assert_eq!(parquet_path, dir.path().join("1_openalex.parquet"));
+
+        let header = vec!["title".to_string(), "citations".to_string()];
+        let rows = vec![vec!["A".to_string(), "3".to_string()], vec!["B".to_string(), String::new()]];
+        let jsonl_path = write_table(&dir.path().join("5_unified.csv"), OutputFormat::Jsonl, &header, &rows)?;
+        assert_eq!(
+            std::fs::read_to_string(&jsonl_path)?,
+            "{\"title\":\"A\",\"citations\":3}\n{\"title\":\"B\",\"citations\":null}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_writer() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("1_openalex.csv");
+        let header = vec!["title".to_string(), "year".to_string()];
+
+        let mut writer = TableWriter::new(&path, OutputFormat::Csv);
+        writer.write(&header, &[vec!["A".to_string(), "2020".to_string()]])?;
+        // Each batch is on disk before the table is finished
+        assert_eq!(std::fs::read_to_string(&path)?, "title,year\nA,2020\n");
+        writer.write(&header, &[vec!["B".to_string(), String::new()]])?;
+        assert_eq!(writer.rows(), 2);
+        assert_eq!(writer.finish()?, Some(path.clone()));
+        assert_eq!(std::fs::read_to_string(&path)?, "title,year\nA,2020\nB,\n");
+
+        let mut writer = TableWriter::new(&path, OutputFormat::Jsonl);
+        writer.write(&header, &[vec!["A".to_string(), "2020".to_string()]])?;
+        let jsonl_path = writer.finish()?.ok_or_else(|| GscholarError::Parse("no table".to_string()))?;
+        assert_eq!(std::fs::read_to_string(&jsonl_path)?, "{\"title\":\"A\",\"year\":2020}\n");
+        assert!(!dir.path().join("1_openalex.partial.csv").exists());
+
+        assert_eq!(TableWriter::new(&dir.path().join("2_crossref.csv"), OutputFormat::Csv).finish()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_type() {
+        assert_eq!(infer_type(["12", "", "-3"].into_iter()), ColumnType::Int64);
+        assert_eq!(infer_type(["12", "4.5"].into_iter()), ColumnType::Double);
+        assert_eq!(infer_type(["0123"].into_iter()), ColumnType::Utf8);
+        assert_eq!(infer_type(["10.1/x", "3"].into_iter()), ColumnType::Utf8);
+        assert_eq!(infer_type(["inf"].into_iter()), ColumnType::Utf8);
+        assert_eq!(infer_type(["", ""].into_iter()), ColumnType::Utf8);
+    }
+}
@@ -0,0 +1,436 @@
+//! Central paper model.
+//!
+//! Every search source (Google Scholar, OpenAlex, Semantic Scholar) and the
+//! user-provided lists convert their raw results into a [`Paper`], which the
+//! later stages deduplicate, rank, enrich, screen and export. Years, dates,
+//! citation counts, author lists and rankings keep their types, so no stage
+//! re-parses strings. Stage tables are written through flat rows (e.g.
+//! [`UnifiedRow`](crate::unified::UnifiedRow)); the run state stores papers
+//! as they are.
+
+use crate::altmetric::AltmetricMetrics;
+use crate::gscholar::ScholarResult;
+use crate::impact;
+use crate::input::InputRecord;
+use crate::openalex::OpenAlexResult;
+use crate::rankings::RankingMetrics;
+use crate::semanticscholar::SemanticScholarPaper;
+use crate::venue::VenueType;
+use crate::work_type::WorkType;
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// One author as the source named them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+}
+
+impl Author {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.trim().to_string() }
+    }
+
+    /// Family name: the part before a comma ("Li, Wei"), else the last word
+    pub fn family_name(&self) -> &str {
+        match self.name.split_once(',') {
+            Some((family, _)) => family.trim(),
+            None => self.name.split_whitespace().last().unwrap_or_default(),
+        }
+    }
+}
+
+/// A paper, from whichever source found it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Paper {
+    pub title: String,
+    pub authors: Vec<Author>,
+    /// Whether the source truncated the author list (OpenAlex keeps the first three)
+    #[serde(default)]
+    pub more_authors: bool,
+    /// Institutions of all authors (OpenAlex `authorships`)
+    #[serde(default)]
+    pub institutions: Vec<String>,
+    /// ISO 3166-1 alpha-2 codes of the authors' countries (e.g., "CN")
+    #[serde(default)]
+    pub countries: Vec<String>,
+    /// Funders named in the funding data (Crossref `funder`, OpenAlex `grants`)
+    #[serde(default)]
+    pub funders: Vec<String>,
+    /// Grant (award) numbers from the funding data
+    #[serde(default)]
+    pub grants: Vec<String>,
+    pub year: Option<u32>,
+    /// Full publication date, when the source gives one
+    pub date: Option<NaiveDate>,
+    pub doi: String,
+    pub article_url: String,
+    pub pdf_url: String,
+    /// Downloaded PDF, relative to the run folder (`--download-pdfs`)
+    #[serde(default)]
+    pub pdf_path: String,
+    /// Start of the extracted full text, set only on the LLM stages' input
+    /// (`--llm-fulltext`)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub fulltext: String,
+    pub abstract_text: String,
+    pub tldr: String,
+    /// Venue as the search source reported it
+    pub venue: String,
+    /// Journal or conference proceedings (see [`VenueType`])
+    #[serde(default)]
+    pub venue_type: VenueType,
+    /// Article, review, preprint, ... (see [`WorkType`])
+    #[serde(default)]
+    pub work_type: WorkType,
+    /// Whether the paper is a review article (see [`crate::review_detection`]);
+    /// `None` until checked
+    #[serde(default)]
+    pub is_review: Option<bool>,
+    /// Journal used for ranking lookups (Crossref's name when known)
+    pub journal: String,
+    pub issn: String,
+    pub citations: Option<u64>,
+    /// Citations relative to the mean of the paper's OpenAlex topic and
+    /// year (`impact` enricher)
+    #[serde(default)]
+    pub normalized_citations: Option<f64>,
+    /// Search result snippet (Google Scholar, OpenAlex)
+    pub snippet: String,
+    /// EasyScholar metrics (Stage 3)
+    pub rankings: Option<RankingMetrics>,
+    /// Altmetric attention (`altmetric` enricher)
+    #[serde(default)]
+    pub altmetric: Option<AltmetricMetrics>,
+    /// Search sources that found the paper (e.g., `["gscholar", "openalex"]`)
+    pub sources: Vec<String>,
+}
+
+impl Paper {
+    /// Set the authors from a ", " or ";" separated list
+    pub fn set_authors(&mut self, value: &str) {
+        (self.authors, self.more_authors) = parse_authors(value);
+    }
+
+    /// Authors as a ", " separated list, ending in " ..." when truncated
+    pub fn author_list(&self) -> String {
+        let mut list = self.authors.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", ");
+        if self.more_authors {
+            list.push_str(" ...");
+        }
+        list
+    }
+
+    /// Set the year and date from "YYYY-MM-DD", "YYYY-MM" or "YYYY" (empty or
+    /// unparsable values leave them unchanged)
+    pub fn set_date(&mut self, value: &str) {
+        let (year, date) = parse_date(value);
+        if year.is_some() {
+            self.year = year;
+            self.date = date;
+        }
+    }
+
+    /// "YYYY-MM-DD" when the full date is known, else "YYYY", else ""
+    pub fn date_string(&self) -> String {
+        match (self.date, self.year) {
+            (Some(date), _) => date.format("%Y-%m-%d").to_string(),
+            (None, Some(year)) => year.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Citations per year since publication (see [`impact::citations_per_year`])
+    pub fn citations_per_year(&self) -> Option<f64> {
+        let current_year = u32::try_from(Local::now().year()).ok()?;
+        Some(impact::citations_per_year(self.citations?, self.year?, current_year))
+    }
+
+    /// Impact Factor
+    pub fn if_score(&self) -> Option<f64> {
+        parse_metric(self.rankings.as_ref()?.sciif.as_deref()?)
+    }
+
+    /// Journal Citation Indicator
+    pub fn jci_score(&self) -> Option<f64> {
+        parse_metric(self.rankings.as_ref()?.jci.as_deref()?)
+    }
+
+    /// SCI partition (e.g., "Q1"), empty when unranked
+    pub fn sci_partition(&self) -> &str {
+        self.rankings.as_ref().and_then(|r| r.sci.as_deref()).unwrap_or_default()
+    }
+
+    /// Sources as a "; " separated list
+    pub fn source_list(&self) -> String {
+        self.sources.join("; ")
+    }
+
+    /// Set the sources from a ";" separated list
+    pub fn set_sources(&mut self, value: &str) {
+        self.sources.clear();
+        for source in value.split(';') {
+            self.add_source(source);
+        }
+    }
+
+    /// Record another source that found the paper
+    pub fn add_source(&mut self, source: &str) {
+        let source = source.trim();
+        if !source.is_empty() && !self.sources.iter().any(|s| s == source) {
+            self.sources.push(source.to_string());
+        }
+    }
+
+    /// Take in a duplicate found again (possibly by another source): fill
+    /// empty fields from it and add its sources
+    pub fn absorb(&mut self, other: Paper) {
+        for (field, value) in [
+            (&mut self.doi, other.doi),
+            (&mut self.venue, other.venue),
+            (&mut self.article_url, other.article_url),
+            (&mut self.pdf_url, other.pdf_url),
+            (&mut self.journal, other.journal),
+            (&mut self.issn, other.issn),
+            (&mut self.abstract_text, other.abstract_text),
+        ] {
+            if field.trim().is_empty() {
+                *field = value;
+            }
+        }
+        if self.date.is_none() && other.date.is_some() {
+            self.year = other.year;
+            self.date = other.date;
+        }
+        self.year = self.year.or(other.year);
+        self.citations = self.citations.or(other.citations);
+        if self.venue_type == VenueType::Unknown {
+            self.venue_type = other.venue_type;
+        }
+        if self.work_type == WorkType::Unknown {
+            self.work_type = other.work_type;
+        }
+        self.is_review = self.is_review.or(other.is_review);
+        if self.institutions.is_empty() {
+            self.institutions = other.institutions;
+        }
+        if self.countries.is_empty() {
+            self.countries = other.countries;
+        }
+        if self.funders.is_empty() {
+            self.funders = other.funders;
+        }
+        if self.grants.is_empty() {
+            self.grants = other.grants;
+        }
+        for source in &other.sources {
+            self.add_source(source);
+        }
+    }
+}
+
+impl From<ScholarResult> for Paper {
+    fn from(result: ScholarResult) -> Self {
+        let mut paper = Self {
+            title: result.title,
+            year: parse_date(&result.year).0,
+            venue: result.venue,
+            article_url: result.article_url,
+            citations: parse_count(&result.citations),
+            snippet: result.snippet,
+            ..Default::default()
+        };
+        paper.set_authors(&result.author);
+        paper
+    }
+}
+
+impl From<OpenAlexResult> for Paper {
+    fn from(result: OpenAlexResult) -> Self {
+        let mut paper = Self {
+            title: result.title,
+            year: parse_date(&result.year).0,
+            doi: result.doi,
+            article_url: result.article_url,
+            pdf_url: result.pdf_url,
+            // OpenAlex's snippet is the reconstructed abstract
+            abstract_text: result.snippet.clone(),
+            journal: result.venue.clone(),
+            venue: result.venue,
+            venue_type: VenueType::from_openalex(&result.source_type),
+            work_type: WorkType::from_openalex(&result.work_type),
+            issn: result.issn,
+            citations: parse_count(&result.citations),
+            snippet: result.snippet,
+            institutions: parse_list(&result.institutions),
+            countries: parse_list(&result.countries),
+            funders: parse_list(&result.funders),
+            grants: parse_list(&result.grants),
+            ..Default::default()
+        };
+        paper.set_authors(&result.author);
+        paper.set_date(&result.publication_date);
+        paper
+    }
+}
+
+impl From<SemanticScholarPaper> for Paper {
+    fn from(result: SemanticScholarPaper) -> Self {
+        let mut paper = Self {
+            title: result.title,
+            year: parse_date(&result.year).0,
+            doi: result.doi,
+            article_url: result.ss_url,
+            pdf_url: result.oa_pdf_url,
+            abstract_text: result.ss_abstract,
+            journal: result.venue.clone(),
+            venue: result.venue,
+            work_type: WorkType::from_semantic_scholar(
+                &result.publication_types.split(';').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect::<Vec<_>>(),
+            ),
+            citations: parse_count(&result.citations),
+            ..Default::default()
+        };
+        paper.set_authors(&result.author);
+        paper.set_date(&result.publication_date);
+        paper
+    }
+}
+
+impl From<&InputRecord> for Paper {
+    fn from(record: &InputRecord) -> Self {
+        let mut paper = Self {
+            title: record.title.clone(),
+            year: parse_date(&record.year).0,
+            doi: record.doi.clone(),
+            venue: record.venue.clone(),
+            journal: record.venue.clone(),
+            citations: parse_count(&record.citations),
+            ..Default::default()
+        };
+        paper.set_authors(&record.author);
+        paper
+    }
+}
+
+/// Parse a ", " or ";" separated author list; a trailing "..." or "…"
+/// marks a truncated list
+pub fn parse_authors(value: &str) -> (Vec<Author>, bool) {
+    let value = value.trim();
+    let trimmed = value.trim_end_matches("...").trim_end_matches('…').trim_end();
+    let separator = if trimmed.contains(';') { ';' } else { ',' };
+    let authors = trimmed
+        .split(separator)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(Author::new)
+        .collect();
+    (authors, trimmed.len() < value.len())
+}
+
+/// Parse "YYYY-MM-DD" (month and day may be unpadded), "YYYY-MM" or "YYYY"
+/// into a year and, when complete, a date
+///
+/// A range such as "2019-2020" or "2019–20" gives its first year.
+pub fn parse_date(value: &str) -> (Option<u32>, Option<NaiveDate>) {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return (u32::try_from(date.year()).ok(), Some(date));
+    }
+    let year = value
+        .get(..4)
+        .filter(|y| y.chars().all(|c| c.is_ascii_digit()))
+        .filter(|_| value[4..].chars().next().is_none_or(|c| matches!(c, '-' | '–' | '/' | ' ')))
+        .and_then(|y| y.parse().ok());
+    (year, None)
+}
+
+/// Parse a count such as "1,234" or "Cited by 1,234" (empty or invalid
+/// counts are unknown)
+pub fn parse_count(value: &str) -> Option<u64> {
+    let start = value.find(|c: char| c.is_ascii_digit())?;
+    if value[..start].trim_end().ends_with('-') {
+        return None;
+    }
+    value[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// Split a ";" separated list such as "CN; IT", dropping empty items
+pub fn parse_list(value: &str) -> Vec<String> {
+    value.split(';').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+}
+
+/// Parse a numeric ranking value such as "5.2"
+fn parse_metric(value: &str) -> Option<f64> {
+    value.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        let (authors, more) = parse_authors("A. Li, B. Wu ...");
+        assert_eq!(authors, vec![Author::new("A. Li"), Author::new("B. Wu")]);
+        assert!(more);
+        let (authors, more) = parse_authors("Li, Wei; Chen, Bo");
+        assert_eq!(authors.iter().map(Author::family_name).collect::<Vec<_>>(), vec!["Li", "Chen"]);
+        assert!(!more);
+
+        assert_eq!(parse_date("2021-3-1"), (Some(2021), NaiveDate::from_ymd_opt(2021, 3, 1)));
+        assert_eq!(parse_date("2021-03"), (Some(2021), None));
+        assert_eq!(parse_date("2021"), (Some(2021), None));
+        assert_eq!(parse_date("20210"), (None, None));
+        assert_eq!(parse_date(""), (None, None));
+        assert_eq!(parse_date("2019-2020"), (Some(2019), None));
+        assert_eq!(parse_date("2019–20"), (Some(2019), None));
+        assert_eq!(parse_count("1,234"), Some(1234));
+        assert_eq!(parse_count("Cited by 1,234"), Some(1234));
+        assert_eq!(parse_count("被引用 12 次"), Some(12));
+        assert_eq!(parse_count(" 7 "), Some(7));
+        assert_eq!(parse_count("-3"), None);
+        assert_eq!(parse_count(""), None);
+    }
+
+    #[test]
+    fn test_from_openalex() {
+        let paper = Paper::from(OpenAlexResult {
+            title: "Slope stability".to_string(),
+            author: "A. Li, B. Wu, C. Xu ...".to_string(),
+            year: "2023".to_string(),
+            publication_date: "2023-05-02".to_string(),
+            venue: "Landslides".to_string(),
+            citations: "12".to_string(),
+            snippet: "Abstract".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(paper.year, Some(2023));
+        assert_eq!(paper.date_string(), "2023-05-02");
+        assert_eq!(paper.citations, Some(12));
+        assert_eq!(paper.author_list(), "A. Li, B. Wu, C. Xu ...");
+        assert_eq!((paper.journal.as_str(), paper.abstract_text.as_str()), ("Landslides", "Abstract"));
+    }
+
+    #[test]
+    fn test_absorb_and_rankings() {
+        let mut first = Paper { title: "Slope stability".to_string(), sources: vec!["gscholar".to_string()], ..Default::default() };
+        let mut second = Paper { doi: "10.1/a".to_string(), year: Some(2021), ..Default::default() };
+        second.set_sources("openalex; gscholar");
+        first.absorb(second);
+        assert_eq!(first.doi, "10.1/a");
+        assert_eq!(first.year, Some(2021));
+        assert_eq!(first.source_list(), "gscholar; openalex");
+
+        assert_eq!(first.if_score(), None);
+        first.rankings = Some(RankingMetrics { sciif: Some("5.2".to_string()), sci: Some("Q1".to_string()), ..Default::default() });
+        assert_eq!(first.if_score(), Some(5.2));
+        assert_eq!(first.sci_partition(), "Q1");
+    }
+}
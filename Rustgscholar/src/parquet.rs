@@ -0,0 +1,142 @@
+//! Parquet stage tables.
+//!
+//! Writes string tables (as produced for the CSVs) as Snappy-compressed
+//! Parquet through the arrow writer, so pandas/polars can load large
+//! harvests without parsing CSV. Each column's type is inferred from its
+//! values: Int64 when every non-empty value is an integer, Float64 when
+//! every value is a number, and Utf8 otherwise. Empty cells are written as
+//! nulls.
+
+use crate::error::{GscholarError, Result};
+use crate::output::{cell, infer_type, ColumnType};
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Rows per row group
+const ROW_GROUP_SIZE: usize = 50_000;
+
+fn data_type(column_type: ColumnType) -> DataType {
+    match column_type {
+        ColumnType::Int64 => DataType::Int64,
+        ColumnType::Double => DataType::Float64,
+        ColumnType::Utf8 => DataType::Utf8,
+    }
+}
+
+/// Write a table (header + string rows) to a Parquet file
+pub fn write_table(path: &Path, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+    let types: Vec<ColumnType> = (0..header.len())
+        .map(|col| infer_type(rows.iter().map(|r| cell(r, col))))
+        .collect();
+    let mut writer = ParquetWriter::create(path, header, &types)?;
+    for chunk in rows.chunks(ROW_GROUP_SIZE) {
+        writer.write(chunk)?;
+    }
+    writer.close()
+}
+
+/// Parquet file written batch by batch, with column types known up front
+/// (a streamed table converted without loading it, see [`crate::output`])
+pub(crate) struct ParquetWriter {
+    path: PathBuf,
+    schema: SchemaRef,
+    types: Vec<ColumnType>,
+    writer: ArrowWriter<File>,
+    rows: usize,
+}
+
+impl ParquetWriter {
+    pub(crate) fn create(path: &Path, header: &[String], types: &[ColumnType]) -> Result<Self> {
+        if header.is_empty() {
+            return Err(GscholarError::Validation("Parquet table needs at least one column".to_string()));
+        }
+        let schema: SchemaRef = Arc::new(Schema::new(
+            header.iter().zip(types).map(|(name, &t)| Field::new(name, data_type(t), true)).collect::<Vec<_>>(),
+        ));
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_row_count(Some(ROW_GROUP_SIZE))
+            .set_created_by(concat!("rustgscholar ", env!("CARGO_PKG_VERSION")).to_string())
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(properties)).map_err(write_error)?;
+        Ok(Self { path: path.to_path_buf(), schema, types: types.to_vec(), writer, rows: 0 })
+    }
+
+    /// Append rows as one record batch
+    pub(crate) fn write(&mut self, rows: &[Vec<String>]) -> Result<()> {
+        let columns: Vec<ArrayRef> = self
+            .types
+            .iter()
+            .enumerate()
+            .map(|(col, &t)| column(t, rows.iter().map(|r| cell(r, col))))
+            .collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(write_error)?;
+        self.writer.write(&batch).map_err(write_error)?;
+        self.rows += rows.len();
+        Ok(())
+    }
+
+    pub(crate) fn close(self) -> Result<()> {
+        self.writer.close().map_err(write_error)?;
+        info!(path = ?self.path, rows = self.rows, columns = self.types.len(), "Saved Parquet table");
+        Ok(())
+    }
+}
+
+/// Arrow array of one column's cells (empty or unparsable cells are null)
+fn column<'a>(column_type: ColumnType, values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    let present = |v: &'a str| Some(v).filter(|v| !v.is_empty());
+    match column_type {
+        ColumnType::Int64 => Arc::new(values.map(|v| present(v).and_then(|v| v.parse().ok())).collect::<Int64Array>()),
+        ColumnType::Double => Arc::new(values.map(|v| present(v).and_then(|v| v.parse().ok())).collect::<Float64Array>()),
+        ColumnType::Utf8 => Arc::new(values.map(present).collect::<StringArray>()),
+    }
+}
+
+fn write_error(error: impl std::fmt::Display) -> GscholarError {
+    GscholarError::Io(std::io::Error::other(format!("Failed to write Parquet: {}", error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::file::reader::FileReader;
+    use parquet::file::serialized_reader::SerializedFileReader;
+
+    #[test]
+    fn test_write_table() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("table.parquet");
+        let header = vec!["title".to_string(), "citations".to_string(), "if_score".to_string(), "id".to_string()];
+        let rows = vec![
+            vec!["A".to_string(), "3".to_string(), "5.2".to_string(), "0123".to_string()],
+            vec!["B".to_string(), String::new(), "7".to_string(), String::new()],
+        ];
+        write_table(&path, &header, &rows)?;
+
+        let metadata = SerializedFileReader::new(File::open(&path)?)?.metadata().clone();
+        assert_eq!(metadata.row_group(0).column(0).compression(), Compression::SNAPPY);
+        assert!(metadata.file_metadata().created_by().is_some_and(|c| c.starts_with("rustgscholar")));
+
+        let mut reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path)?)?.build()?;
+        let batch = reader.next().ok_or("no batch")??;
+        let types: Vec<&DataType> = batch.schema_ref().fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(types, [&DataType::Utf8, &DataType::Int64, &DataType::Float64, &DataType::Utf8]);
+        let citations = batch.column(1).as_any().downcast_ref::<Int64Array>().ok_or("not Int64")?;
+        assert_eq!((citations.value(0), citations.is_null(1)), (3, true));
+        let scores = batch.column(2).as_any().downcast_ref::<Float64Array>().ok_or("not Float64")?;
+        assert_eq!(scores.value(1), 7.0);
+        let ids = batch.column(3).as_any().downcast_ref::<StringArray>().ok_or("not Utf8")?;
+        assert_eq!((ids.value(0), ids.is_null(1)), ("0123", true));
+        Ok(())
+    }
+}
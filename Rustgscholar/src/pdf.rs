@@ -0,0 +1,275 @@
+//! Open-access PDF downloads.
+//!
+//! With `--download-pdfs` the pipeline saves the PDF of every unified paper
+//! that has an open-access copy (its `pdf_url`, else the best Unpaywall
+//! location) to `pdfs/` in the run folder and records the file in the
+//! `pdf_path` column. Requests to one host are spaced out by a fixed
+//! interval, downloads go to a `.part` file that a later run resumes with an
+//! HTTP range request, and finished files are never fetched again.
+
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::paper::Paper;
+use crate::unpaywall::UnpaywallClient;
+use crate::stream;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Folder of the downloaded PDFs in the run folder
+pub const PDF_FOLDER: &str = "pdfs";
+
+/// Default pause between two requests to the same host
+pub const DEFAULT_DOMAIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Downloads in flight at once (to different hosts, or queued on one)
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Browser-like user agent; some publishers refuse unknown clients
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// Earliest next request per host
+struct DomainThrottle {
+    interval: Duration,
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl DomainThrottle {
+    /// Wait for this host's next slot
+    async fn wait(&self, host: &str) {
+        let slot = {
+            let Ok(mut next) = self.next.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let slot = next.get(host).copied().filter(|t| *t > now).unwrap_or(now);
+            next.insert(host.to_string(), slot + self.interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// What [`PdfDownloader::download_all`] did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DownloadSummary {
+    pub downloaded: usize,
+    /// Already in the folder from an earlier run
+    pub existing: usize,
+    /// No open-access PDF known
+    pub unavailable: usize,
+    /// (DOI, error) of each failed download
+    pub failed: Vec<(String, String)>,
+}
+
+/// Open-access PDF downloader
+pub struct PdfDownloader {
+    client: reqwest::Client,
+    unpaywall: Option<UnpaywallClient>,
+    throttle: DomainThrottle,
+}
+
+impl PdfDownloader {
+    pub fn new() -> Result<Self> {
+        // Publishers get a browser user agent, so their own client
+        let client = http::builder(http::settings())?
+            .timeout(Duration::from_secs(120))
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self {
+            client,
+            unpaywall: None,
+            throttle: DomainThrottle { interval: DEFAULT_DOMAIN_INTERVAL, next: Mutex::new(HashMap::new()) },
+        })
+    }
+
+    /// Ask Unpaywall for papers without a `pdf_url`
+    pub fn with_unpaywall(mut self, client: UnpaywallClient) -> Self {
+        self.unpaywall = Some(client);
+        self
+    }
+
+    /// Pause between two requests to the same host (default [`DEFAULT_DOMAIN_INTERVAL`])
+    pub fn with_domain_interval(mut self, interval: Duration) -> Self {
+        self.throttle.interval = interval;
+        self
+    }
+
+    /// Download the PDF of each paper into `run_folder/pdfs`, setting
+    /// `pdf_path` (relative to `run_folder`) of those that have one
+    pub async fn download_all(&self, papers: &mut [Paper], run_folder: &Path) -> Result<DownloadSummary> {
+        let folder = run_folder.join(PDF_FOLDER);
+        std::fs::create_dir_all(&folder)?;
+
+        // Build the downloads up front so the returned future stays `Send`
+        let downloads: Vec<_> = papers
+            .iter_mut()
+            .filter(|paper| !paper.doi.trim().is_empty())
+            .map(|paper| {
+                let folder = &folder;
+                async move {
+                    let outcome = self.download(paper, folder).await;
+                    if let Ok(Some((ref path, _))) = outcome {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        paper.pdf_path = format!("{}/{}", PDF_FOLDER, name);
+                    }
+                    (paper.doi.clone(), outcome)
+                }
+            })
+            .collect();
+        let outcomes = stream::in_order(downloads, MAX_CONCURRENT_DOWNLOADS).await;
+
+        let mut summary = DownloadSummary::default();
+        for (doi, outcome) in outcomes {
+            match outcome {
+                Ok(Some((_, true))) => summary.downloaded += 1,
+                Ok(Some((_, false))) => summary.existing += 1,
+                Ok(None) => summary.unavailable += 1,
+                Err(e) => summary.failed.push((doi, e.to_string())),
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Download one paper's PDF into `folder`; the path and whether it was
+    /// fetched now, or `None` when no open-access PDF is known
+    pub async fn download(&self, paper: &Paper, folder: &Path) -> Result<Option<(PathBuf, bool)>> {
+        let path = folder.join(file_name(&paper.doi));
+        if path.exists() {
+            return Ok(Some((path, false)));
+        }
+        let Some(url) = self.pdf_url(paper).await? else {
+            return Ok(None);
+        };
+        self.fetch(&url, &path).await?;
+        Ok(Some((path, true)))
+    }
+
+    /// The paper's `pdf_url`, else Unpaywall's best open-access location
+    async fn pdf_url(&self, paper: &Paper) -> Result<Option<String>> {
+        if !paper.pdf_url.trim().is_empty() {
+            return Ok(Some(paper.pdf_url.trim().to_string()));
+        }
+        let Some(unpaywall) = &self.unpaywall else {
+            return Ok(None);
+        };
+        Ok(unpaywall
+            .lookup(&paper.doi)
+            .await?
+            .filter(|location| location.is_oa && !location.url.is_empty())
+            .map(|location| location.url))
+    }
+
+    /// Fetch `url` into `path`, resuming `path.part` when an earlier run left one
+    async fn fetch(&self, url: &str, path: &Path) -> Result<()> {
+        let host = reqwest::Url::parse(url)
+            .map_err(|e| GscholarError::Validation(format!("Invalid PDF URL '{}': {}", url, e)))?
+            .host_str()
+            .unwrap_or_default()
+            .to_string();
+        let part = path.with_extension("pdf.part");
+        let offset = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+        self.throttle.wait(&host).await;
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            debug!(url = %url, offset, "Resuming PDF download");
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await?;
+        let append = match response.status() {
+            reqwest::StatusCode::PARTIAL_CONTENT => true,
+            // The part file already holds the whole PDF
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return finish(&part, path),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(30)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("PDF download failed ({}): {}", host, status),
+                });
+            }
+            // The server ignored the range: start over
+            _ => false,
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&part)?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk)?;
+        }
+        file.flush()?;
+        finish(&part, path)
+    }
+}
+
+/// Move a complete download into place, refusing anything that is not a PDF
+/// (e.g., a publisher's login page)
+fn finish(part: &Path, path: &Path) -> Result<()> {
+    let mut magic = [0u8; 5];
+    let is_pdf = std::fs::File::open(part)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok_and(|_| &magic == b"%PDF-");
+    if !is_pdf {
+        std::fs::remove_file(part)?;
+        return Err(GscholarError::Parse("Response is not a PDF (likely a landing or login page)".to_string()));
+    }
+    std::fs::rename(part, path)?;
+    Ok(())
+}
+
+/// File name of a paper's PDF, derived from its DOI
+pub fn file_name(doi: &str) -> String {
+    let stem: String = doi
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.pdf", stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("10.1016/J.Geomorph.2020.107(3)"), "10.1016_j.geomorph.2020.107_3_.pdf");
+    }
+
+    #[test]
+    fn test_finish_checks_pdf() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let part = dir.path().join("a.pdf.part");
+        let path = dir.path().join("a.pdf");
+        std::fs::write(&part, "<html>Sign in</html>")?;
+        assert!(finish(&part, &path).is_err());
+        assert!(!part.exists() && !path.exists());
+
+        std::fs::write(&part, "%PDF-1.7\n...")?;
+        finish(&part, &path)?;
+        assert!(path.exists() && !part.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_throttle_spaces_requests_per_host() {
+        let interval = Duration::from_millis(200);
+        let throttle = DomainThrottle { interval, next: Mutex::new(HashMap::new()) };
+        let start = Instant::now();
+        throttle.wait("a.org").await;
+        throttle.wait("b.org").await;
+        assert!(start.elapsed() < interval);
+        throttle.wait("a.org").await;
+        assert!(start.elapsed() >= interval);
+    }
+}
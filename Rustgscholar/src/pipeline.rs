@@ -0,0 +1,3474 @@
+//! The search pipeline as a library API.
+//!
+//! [`PipelineBuilder`] configures a run (source, filters, LLM stages, output
+//! sinks) and [`Pipeline::run`] executes the stages, writing every stage
+//! table into the output folder and returning a typed [`PipelineReport`]:
+//!
+//! - Stage 1: search (Google Scholar, OpenAlex and/or Semantic Scholar; the
+//!   records of several sources are merged and deduplicated by DOI or title)
+//! - Stage 2: Crossref enrichment (of the Google Scholar records only)
+//! - Stage 3: EasyScholar rankings and filters
+//! - Stage 4: Semantic Scholar lookup
+//! - Stage 5: unified dataset
+//! - Stage 6: LLM relevance filtering or criteria screening
+//! - Stage 7: relevant papers (plus RIS, digest, Zotero push)
+//! - Stage 8: topic tagging
+//!
+//! Stages 3 and 6-8 run only when configured. Progress lines go to the
+//! callback set with [`PipelineBuilder::on_progress`] (tracing by default).
+//!
+//! ```rust,no_run
+//! use rustgscholar::pipeline::{PipelineBuilder, Source};
+//!
+//! # async fn run() -> rustgscholar::Result<()> {
+//! let report = PipelineBuilder::new("landslide susceptibility", "output/landslide")
+//!     .source(Source::OpenAlex)
+//!     .pages(vec![1, 2])
+//!     .build()
+//!     .run()
+//!     .await?;
+//! println!("{} papers identified", report.prisma.identified);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::{self, HttpCache};
+use crate::checkpoint::JsonlCheckpoint;
+use crate::crossref::{self, CrossrefClient, CrossrefMetadata};
+use crate::enrich::{Enricher, Provenance};
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::estimate::{self, Estimate, LlmEstimate, LlmPrice, SourceEstimate};
+use crate::export;
+use crate::failures::{self, Failure, FailureLog};
+use crate::fulltext::{self, FULLTEXT_FOLDER};
+use crate::grobid::GrobidClient;
+use crate::gscholar;
+use crate::incremental::{self, PreviousRun};
+use crate::input::InputRecord;
+use crate::journal_list::{self, JournalList};
+use crate::llm_filter::{self, FilterResult, LlmCallDetail, LlmConfig, TokenUsage, UncertainPolicy};
+use crate::manifest::{self, Manifest, SearchParameters, Service, SourceQuery, StageCount};
+use crate::matching::TitleMatcher;
+use crate::openalex;
+use crate::output::{self, OutputFormat, TableWriter};
+use crate::paper::{self, Paper};
+use crate::pdf::{PdfDownloader, PDF_FOLDER};
+use crate::prefilter::{self, Prefilter};
+use crate::prisma::{self, PrismaCounts};
+use crate::prompts::{criteria_screening, relevance_filter};
+use crate::query_expansion;
+use crate::rank_filter::RankFilter;
+use crate::rankings::{self, RankingClient, RankingMetrics};
+use crate::raw_archive::{self, RawArchive};
+use crate::report;
+use crate::review_detection::{self, ReviewPolicy, Verdict};
+use crate::scholar_api::ScholarApiClient;
+use crate::screening::{self, Criteria};
+use crate::semanticscholar::{self, SemanticScholarClient, SemanticScholarResult};
+use crate::spool::{Spool, Spooled};
+use crate::state::{RunState, Stage, STATE_FILE};
+use crate::stream::{self, Page, PageOrder, PageReceiver, PageSender};
+use crate::strategy;
+use crate::study_design;
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+use crate::tagging;
+use crate::unified::{self, UnifiedRow};
+use crate::venue::{CoreRankings, DblpClient, VenueType};
+use crate::work_type::{self, WorkType};
+use crate::xlsx;
+use crate::zotero::ZoteroClient;
+use chrono::{Datelike, Local};
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Report a progress line through the pipeline's callback
+macro_rules! status {
+    ($pipeline:expr, $($arg:tt)*) => {
+        ($pipeline.progress)(&format!($($arg)*))
+    };
+}
+
+/// Stage outputs from most to least refined; the first one present is the
+/// report's [`PipelineReport::final_table`]
+pub const FINAL_STAGE_FILES: &[&str] = &[
+    "9_final",
+    "8_tagged",
+    "8_study_design",
+    "7_relevant",
+    "5_unified",
+    "3_easyscholar",
+    "2_crossref",
+    "1_openalex",
+    "1_gscholar",
+    "1_semanticscholar",
+    "1_input",
+];
+
+/// Source name recorded for runs that start from an input list
+const INPUT_SOURCE: &str = "Input list";
+
+/// Years shown one by one in the year histogram, and its widest bar
+const HISTOGRAM_YEARS: usize = 20;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Search source for Stage 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Source {
+    #[default]
+    GoogleScholar,
+    OpenAlex,
+    SemanticScholar,
+}
+
+impl FromStr for Source {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gscholar" | "bs" => Ok(Self::GoogleScholar),
+            "openalex" => Ok(Self::OpenAlex),
+            "s2" | "semanticscholar" => Ok(Self::SemanticScholar),
+            _ => Err(GscholarError::Config(format!("Invalid source: {}", s))),
+        }
+    }
+}
+
+impl Source {
+    /// Display name (as used in PRISMA counts)
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GoogleScholar => "Google Scholar",
+            Self::OpenAlex => "OpenAlex",
+            Self::SemanticScholar => "Semantic Scholar",
+        }
+    }
+
+    /// Identifier used on the command line and in the `sources` column
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::GoogleScholar => "gscholar",
+            Self::OpenAlex => "openalex",
+            Self::SemanticScholar => "s2",
+        }
+    }
+
+    /// Parse a comma-separated list of sources (e.g., "gscholar,openalex,s2")
+    pub fn parse_list(s: &str) -> Result<Vec<Self>> {
+        let mut sources = Vec::new();
+        for source in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let source: Self = source.parse()?;
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        if sources.is_empty() {
+            return Err(GscholarError::Config("No search source given".to_string()));
+        }
+        Ok(sources)
+    }
+}
+
+/// Names of Stages 1-8, in order
+pub const STAGE_NAMES: [&str; 8] = [
+    "search",
+    "crossref",
+    "easyscholar",
+    "semanticscholar",
+    "unified",
+    "llm",
+    "relevant",
+    "tagging",
+];
+
+/// Which of Stages 1-8 run
+///
+/// Stages are given by number, range or name (e.g., `1,2,3,6`, `1-5`,
+/// `semanticscholar`). A skipped stage passes its input through unchanged
+/// where it can: without Stage 3 papers go on unranked, without Stage 4 the
+/// unified dataset has no Semantic Scholar fields. Stages 6-8 need the
+/// unified dataset, and Stage 8 classifies and tags the papers selected in
+/// Stage 7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageSelection {
+    enabled: [bool; 8],
+}
+
+impl Default for StageSelection {
+    fn default() -> Self {
+        Self { enabled: [true; 8] }
+    }
+}
+
+impl StageSelection {
+    /// Only the listed stages
+    pub fn only(stages: &[usize]) -> Self {
+        let mut enabled = [false; 8];
+        for &stage in stages {
+            if let Some(slot) = stage.checked_sub(1).and_then(|i| enabled.get_mut(i)) {
+                *slot = true;
+            }
+        }
+        Self { enabled }
+    }
+
+    /// The same selection without the listed stages
+    pub fn skip(mut self, stages: &[usize]) -> Self {
+        for &stage in stages {
+            if let Some(slot) = stage.checked_sub(1).and_then(|i| self.enabled.get_mut(i)) {
+                *slot = false;
+            }
+        }
+        self
+    }
+
+    pub fn contains(&self, stage: usize) -> bool {
+        stage.checked_sub(1).and_then(|i| self.enabled.get(i)).copied().unwrap_or(false)
+    }
+
+    /// Stage numbers from a list of numbers, ranges and names
+    pub fn parse_list(items: &[String]) -> Result<Vec<usize>> {
+        let mut stages = Vec::new();
+        for item in items.iter().flat_map(|i| i.split(',')).map(str::trim).filter(|i| !i.is_empty()) {
+            let invalid = || {
+                GscholarError::Config(format!(
+                    "Invalid stage '{}' (expected 1-8 or one of: {})",
+                    item,
+                    STAGE_NAMES.join(", ")
+                ))
+            };
+            match item.split_once('-') {
+                Some((start, end)) => {
+                    let start = stage_number(start.trim()).ok_or_else(invalid)?;
+                    let end = stage_number(end.trim()).ok_or_else(invalid)?;
+                    stages.extend(start..=end);
+                }
+                None => stages.push(stage_number(item).ok_or_else(invalid)?),
+            }
+        }
+        Ok(stages)
+    }
+}
+
+fn stage_number(name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    if let Ok(n) = name.parse::<usize>() {
+        return (1..=8).contains(&n).then_some(n);
+    }
+    let alias = match name.as_str() {
+        "gscholar" | "openalex" => "search",
+        "rankings" | "ranking" => "easyscholar",
+        "s2" => "semanticscholar",
+        "screening" | "filter" => "llm",
+        "tags" => "tagging",
+        other => other,
+    };
+    STAGE_NAMES.iter().position(|s| *s == alias).map(|i| i + 1)
+}
+
+/// Stage 3 settings: EasyScholar key, filters and journal lists
+#[derive(Debug)]
+pub struct RankingOptions {
+    pub api_key: String,
+    /// Papers must pass this filter (papers without rankings are dropped when set)
+    pub filter: Option<RankFilter>,
+    /// Keep only papers from these journals
+    pub journal_include: Option<JournalList>,
+    /// Drop papers from these journals
+    pub journal_exclude: Option<JournalList>,
+    /// Journal name aliases mapped to EasyScholar canonical names
+    pub aliases: HashMap<String, String>,
+    /// How long EasyScholar results stay in the on-disk cache (`None` disables it)
+    pub cache_ttl: Option<Duration>,
+    /// CORE conference ranks for conference papers (`--core-rankings`)
+    pub core_rankings: Option<CoreRankings>,
+    /// Ask DBLP for the type of venues their names do not reveal (`--dblp`)
+    pub dblp: bool,
+}
+
+impl RankingOptions {
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            filter: None,
+            journal_include: None,
+            journal_exclude: None,
+            aliases: HashMap::new(),
+            cache_ttl: None,
+            core_rankings: None,
+            dblp: false,
+        }
+    }
+}
+
+/// What a finished run produced
+#[derive(Debug, Clone, Default)]
+pub struct PipelineReport {
+    pub output_folder: PathBuf,
+    /// Record counts at every stage
+    pub prisma: PrismaCounts,
+    /// Stage 5 unified papers
+    pub unified: Vec<Paper>,
+    /// Stage 6 verdicts
+    pub verdicts: Vec<FilterResult>,
+    /// Stage 7 papers selected as relevant
+    pub relevant: Vec<Paper>,
+    /// LLM tokens spent across all stages
+    pub token_usage: TokenUsage,
+    /// Most refined stage table written (see [`FINAL_STAGE_FILES`])
+    pub final_table: Option<PathBuf>,
+    /// Per-item failures of the stages run this time (also in `errors.csv`)
+    pub failures: Vec<Failure>,
+}
+
+/// Outcome of a run in numbers, for tools driving the CLI (`--quiet`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub output_folder: PathBuf,
+    /// Most refined stage table written
+    pub final_table: Option<PathBuf>,
+    pub prisma: PrismaCounts,
+    pub unified: usize,
+    pub verdicts: usize,
+    pub relevant: usize,
+    /// Per-item failures (see `errors.csv`)
+    pub failures: usize,
+    pub token_usage: TokenUsage,
+}
+
+impl From<&PipelineReport> for RunSummary {
+    fn from(report: &PipelineReport) -> Self {
+        Self {
+            output_folder: report.output_folder.clone(),
+            final_table: report.final_table.clone(),
+            prisma: report.prisma.clone(),
+            unified: report.unified.len(),
+            verdicts: report.verdicts.len(),
+            relevant: report.relevant.len(),
+            failures: report.failures.len(),
+            token_usage: report.token_usage.clone(),
+        }
+    }
+}
+
+/// Progress callback
+pub type ProgressFn = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Builder for a [`Pipeline`]
+pub struct PipelineBuilder {
+    pipeline: Pipeline,
+}
+
+impl PipelineBuilder {
+    /// Start a pipeline for a keyword, writing into `output_folder` (created if missing)
+    pub fn new(keyword: &str, output_folder: impl Into<PathBuf>) -> Self {
+        Self {
+            pipeline: Pipeline {
+                keyword: keyword.to_string(),
+                output_folder: output_folder.into(),
+                resume: false,
+                sources: vec![Source::default()],
+                pages: vec![1],
+                openalex_per_page: openalex::MAX_PER_PAGE,
+                openalex_page_delay: Duration::ZERO,
+                ylo: None,
+                auto_ylo: false,
+                proxy: None,
+                mirror: None,
+                scholar_api: None,
+                sdt: "0,5".to_string(),
+                cookie_path: None,
+                input: None,
+                unified_input: None,
+                stages: StageSelection::default(),
+                since: None,
+                min_citations: None,
+                work_types: Vec::new(),
+                countries: Vec::new(),
+                reviews: ReviewPolicy::default(),
+                max_results: None,
+                ranking: None,
+                s2: SemanticScholarClient::new(None),
+                cache_dir: None,
+                lookup_cache_ttl: None,
+                search_cache_ttl: None,
+                llm: Err("no LLM configured".to_string()),
+                expand_query: false,
+                criteria: None,
+                prefilter: Prefilter::default(),
+                review_model: None,
+                review_below: 0.7,
+                min_confidence: 0.0,
+                title_matcher: TitleMatcher::default(),
+                uncertain_policy: UncertainPolicy::default(),
+                tags: Vec::new(),
+                study_designs: Vec::new(),
+                zotero: None,
+                output_format: OutputFormat::default(),
+                xlsx: false,
+                prisma_svg: false,
+                save_raw: false,
+                enrichers: Vec::new(),
+                pdfs: None,
+                grobid: None,
+                llm_fulltext: None,
+                options: serde_json::Value::Null,
+                failures: FailureLog::default(),
+                stage_clock: Mutex::new(Instant::now()),
+                progress: Arc::new(|line: &str| info!("{}", line.trim())),
+            },
+        }
+    }
+
+    /// Continue after the last stage recorded in the output folder's `state.json`
+    pub fn resume(mut self, enabled: bool) -> Self {
+        self.pipeline.resume = enabled;
+        self
+    }
+
+    pub fn source(mut self, source: Source) -> Self {
+        self.pipeline.sources = vec![source];
+        self
+    }
+
+    /// Search several sources in one run; their results are merged and
+    /// deduplicated, and each record lists the sources that found it
+    pub fn sources(mut self, sources: Vec<Source>) -> Self {
+        if !sources.is_empty() {
+            self.pipeline.sources = sources;
+        }
+        self
+    }
+
+    /// Result pages to fetch
+    pub fn pages(mut self, pages: Vec<i32>) -> Self {
+        self.pipeline.pages = pages;
+        self
+    }
+
+    /// OpenAlex results per page (at most 200) and wait between two page
+    /// requests, which then go out one at a time
+    pub fn openalex_paging(mut self, per_page: usize, delay: Duration) -> Self {
+        self.pipeline.openalex_per_page = per_page;
+        self.pipeline.openalex_page_delay = delay;
+        self
+    }
+
+    /// Earliest publication year (`None`: five years back)
+    pub fn ylo(mut self, year: Option<i32>) -> Self {
+        self.pipeline.ylo = year;
+        self
+    }
+
+    /// Without [`Self::ylo`], start at the year covering
+    /// [`openalex::YLO_COVERAGE`] of the keyword's OpenAlex works instead of
+    /// five years back
+    pub fn auto_ylo(mut self, auto: bool) -> Self {
+        self.pipeline.auto_ylo = auto;
+        self
+    }
+
+    /// Proxy for Google Scholar requests
+    pub fn proxy(mut self, proxy: Option<String>) -> Self {
+        self.pipeline.proxy = proxy;
+        self
+    }
+
+    /// Google Scholar mirror base URL
+    pub fn mirror(mut self, mirror: Option<String>) -> Self {
+        self.pipeline.mirror = mirror;
+        self
+    }
+
+    /// Fetch Google Scholar results through SerpAPI or SearchAPI instead of
+    /// scraping (the mirror, proxy and cookies are then unused)
+    pub fn scholar_api(mut self, client: Option<ScholarApiClient>) -> Self {
+        self.pipeline.scholar_api = client;
+        self
+    }
+
+    /// Google Scholar source data type filter (e.g., "0,5")
+    pub fn sdt(mut self, sdt: &str) -> Self {
+        self.pipeline.sdt = sdt.to_string();
+        self
+    }
+
+    /// Google Scholar cookie file (default `~/.gscholar_cookies.json`)
+    pub fn cookie_path(mut self, path: Option<PathBuf>) -> Self {
+        self.pipeline.cookie_path = path;
+        self
+    }
+
+    /// Start from these records instead of searching (Stage 1 loads them,
+    /// Stage 2 looks them up on Crossref by DOI, or by title without one)
+    pub fn input(mut self, records: Vec<InputRecord>) -> Self {
+        self.pipeline.input = Some(records);
+        self
+    }
+
+    /// Start from an existing unified dataset (Stages 1-5 are not run)
+    pub fn unified(mut self, records: Vec<Paper>) -> Self {
+        self.pipeline.unified_input = Some(records);
+        self
+    }
+
+    /// Stages to run (all by default)
+    pub fn stages(mut self, stages: StageSelection) -> Self {
+        self.pipeline.stages = stages;
+        self
+    }
+
+    /// Update a previous run: search from its year onwards and skip the
+    /// records it already identified (see [`incremental`])
+    pub fn since(mut self, previous: PreviousRun) -> Self {
+        self.pipeline.since = Some(previous);
+        self
+    }
+
+    /// Keep only papers cited at least `min` times, before Stage 3 (papers
+    /// whose source reports no count are dropped too)
+    pub fn min_citations(mut self, min: Option<u64>) -> Self {
+        self.pipeline.min_citations = min;
+        self
+    }
+
+    /// Search for these work types only (OpenAlex: journal articles when
+    /// empty), and drop records of other known types before Stage 3
+    pub fn work_types(mut self, types: Vec<WorkType>) -> Self {
+        self.pipeline.work_types = types;
+        self
+    }
+
+    /// Keep only papers with an author from one of these countries (ISO
+    /// 3166-1 alpha-2 codes): OpenAlex is searched for them, and records of
+    /// other known countries are dropped before Stage 3
+    pub fn countries(mut self, countries: Vec<String>) -> Self {
+        self.pipeline.countries = countries;
+        self
+    }
+
+    /// Keep, drop or keep only review articles in Stage 5 (every paper
+    /// gets an `is_review` column either way)
+    pub fn reviews(mut self, policy: ReviewPolicy) -> Self {
+        self.pipeline.reviews = policy;
+        self
+    }
+
+    /// Stop searching once `max` unique records are collected across all
+    /// sources, and pass at most `max` records on to the later stages
+    pub fn max_results(mut self, max: Option<usize>) -> Self {
+        self.pipeline.max_results = max;
+        self
+    }
+
+    /// EasyScholar settings for Stage 3
+    pub fn ranking(mut self, options: RankingOptions) -> Self {
+        self.pipeline.ranking = Some(options);
+        self
+    }
+
+    /// Semantic Scholar API key for Stage 4 (higher rate limits); without
+    /// one the key in `S2_API_KEY` is used, if set
+    pub fn s2_api_key(mut self, key: Option<String>) -> Self {
+        self.pipeline.s2 = SemanticScholarClient::new(key);
+        self
+    }
+
+    /// Reuse API responses from the on-disk HTTP cache (`~/.cache/rustgscholar/http/`)
+    ///
+    /// `lookups` applies to Crossref and Semantic Scholar metadata lookups,
+    /// `searches` to OpenAlex and Semantic Scholar search pages, which go
+    /// stale sooner; `None` disables either.
+    pub fn http_cache(mut self, lookups: Option<Duration>, searches: Option<Duration>) -> Self {
+        self.pipeline.lookup_cache_ttl = lookups;
+        self.pipeline.search_cache_ttl = searches;
+        self
+    }
+
+    /// Keep the HTTP and rankings caches under `dir` instead of `~/.cache/rustgscholar`
+    pub fn cache_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.pipeline.cache_dir = dir;
+        self
+    }
+
+    /// Enable the LLM stages (query expansion, Stages 6-8)
+    pub fn llm(mut self, config: LlmConfig) -> Self {
+        self.pipeline.llm = Ok(config);
+        self
+    }
+
+    /// Leave the LLM stages off, with the reason shown when they are skipped
+    pub fn llm_unavailable(mut self, reason: &str) -> Self {
+        self.pipeline.llm = Err(reason.to_string());
+        self
+    }
+
+    /// Why the LLM stages will be skipped, if no LLM is configured
+    pub fn llm_unavailable_reason(&self) -> Option<&str> {
+        self.pipeline.llm.as_ref().err().map(String::as_str)
+    }
+
+    /// Ask the LLM for query variants before searching
+    pub fn expand_query(mut self, enabled: bool) -> Self {
+        self.pipeline.expand_query = enabled;
+        self
+    }
+
+    /// Screen against inclusion/exclusion criteria instead of relevance filtering
+    pub fn criteria(mut self, criteria: Criteria) -> Self {
+        self.pipeline.criteria = Some(criteria);
+        self
+    }
+
+    /// Label papers irrelevant by keyword before the LLM sees them (Stage 6)
+    pub fn prefilter(mut self, prefilter: Prefilter) -> Self {
+        self.pipeline.prefilter = prefilter;
+        self
+    }
+
+    /// Re-screen verdicts below `below` confidence with a stronger model
+    pub fn review_model(mut self, model: &str, below: f64) -> Self {
+        self.pipeline.review_model = Some(model.to_string());
+        self.pipeline.review_below = below;
+        self
+    }
+
+    /// Minimum confidence for a relevant verdict to reach Stage 7
+    pub fn min_confidence(mut self, min_confidence: f64) -> Self {
+        self.pipeline.min_confidence = min_confidence;
+        self
+    }
+
+    /// Title similarity (0-1) a Crossref or Semantic Scholar title match needs
+    pub fn title_match_threshold(mut self, threshold: f64) -> Self {
+        self.pipeline.title_matcher.threshold = threshold;
+        self
+    }
+
+    pub fn uncertain_policy(mut self, policy: UncertainPolicy) -> Self {
+        self.pipeline.uncertain_policy = policy;
+        self
+    }
+
+    /// Stage 8 topic categories (empty disables tagging)
+    pub fn tags(mut self, categories: Vec<String>) -> Self {
+        self.pipeline.tags = categories;
+        self
+    }
+
+    /// Stage 8 study designs, one of which labels each relevant paper
+    /// (empty disables the classification)
+    pub fn study_designs(mut self, designs: Vec<String>) -> Self {
+        self.pipeline.study_designs = designs;
+        self
+    }
+
+    /// Push Stage 7 papers into this Zotero collection
+    pub fn zotero(mut self, client: ZoteroClient, collection: &str) -> Self {
+        self.pipeline.zotero = Some((client, collection.to_string()));
+        self
+    }
+
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.pipeline.output_format = format;
+        self
+    }
+
+    /// Also collect the stage CSVs into results.xlsx
+    pub fn xlsx(mut self, enabled: bool) -> Self {
+        self.pipeline.xlsx = enabled;
+        self
+    }
+
+    /// Also draw prisma.svg
+    pub fn prisma_svg(mut self, enabled: bool) -> Self {
+        self.pipeline.prisma_svg = enabled;
+        self
+    }
+
+    /// Keep every fetched page and API response, gzipped, under `raw/`
+    pub fn save_raw(mut self, enabled: bool) -> Self {
+        self.pipeline.save_raw = enabled;
+        self
+    }
+
+    /// Append an enricher to run on the Stage 5 dataset, after those added before it
+    pub fn enricher(mut self, enricher: Box<dyn Enricher>) -> Self {
+        self.pipeline.enrichers.push(enricher);
+        self
+    }
+
+    /// Download open-access PDFs of the Stage 5 papers into `pdfs/`
+    pub fn download_pdfs(mut self, downloader: PdfDownloader) -> Self {
+        self.pipeline.pdfs = Some(downloader);
+        self
+    }
+
+    /// Run the downloaded PDFs through GROBID: text into `fulltext/`, parsed
+    /// references and affiliations into `5_references.csv` / `5_affiliations.csv`
+    pub fn extract_fulltext(mut self, client: GrobidClient) -> Self {
+        self.pipeline.grobid = Some(client);
+        self
+    }
+
+    /// Give LLM stages the first `max_chars` of the full text of papers
+    /// whose abstract is missing or short
+    pub fn llm_fulltext(mut self, max_chars: Option<usize>) -> Self {
+        self.pipeline.llm_fulltext = max_chars;
+        self
+    }
+
+    /// Effective options to record in `manifest.json` (secrets already redacted)
+    pub fn options(mut self, options: serde_json::Value) -> Self {
+        self.pipeline.options = options;
+        self
+    }
+
+    /// Receive progress lines (stage headers, counts, saved files)
+    pub fn on_progress(mut self, progress: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.pipeline.progress = Arc::new(progress);
+        self
+    }
+
+    pub fn build(self) -> Pipeline {
+        self.pipeline
+    }
+}
+
+/// A configured pipeline run
+pub struct Pipeline {
+    keyword: String,
+    output_folder: PathBuf,
+    resume: bool,
+    sources: Vec<Source>,
+    pages: Vec<i32>,
+    openalex_per_page: usize,
+    openalex_page_delay: Duration,
+    ylo: Option<i32>,
+    /// Without `ylo`, start at the year the OpenAlex year histogram suggests
+    auto_ylo: bool,
+    proxy: Option<String>,
+    mirror: Option<String>,
+    scholar_api: Option<ScholarApiClient>,
+    sdt: String,
+    cookie_path: Option<PathBuf>,
+    input: Option<Vec<InputRecord>>,
+    unified_input: Option<Vec<Paper>>,
+    stages: StageSelection,
+    since: Option<PreviousRun>,
+    min_citations: Option<u64>,
+    work_types: Vec<WorkType>,
+    countries: Vec<String>,
+    reviews: ReviewPolicy,
+    max_results: Option<usize>,
+    ranking: Option<RankingOptions>,
+    s2: SemanticScholarClient,
+    cache_dir: Option<PathBuf>,
+    lookup_cache_ttl: Option<Duration>,
+    search_cache_ttl: Option<Duration>,
+    llm: std::result::Result<LlmConfig, String>,
+    expand_query: bool,
+    criteria: Option<Criteria>,
+    /// Keyword patterns that settle obviously irrelevant papers before Stage 6
+    prefilter: Prefilter,
+    review_model: Option<String>,
+    review_below: f64,
+    min_confidence: f64,
+    /// Verifies Crossref title lookups and the Semantic Scholar title fallback
+    title_matcher: TitleMatcher,
+    uncertain_policy: UncertainPolicy,
+    tags: Vec<String>,
+    study_designs: Vec<String>,
+    zotero: Option<(ZoteroClient, String)>,
+    output_format: OutputFormat,
+    xlsx: bool,
+    prisma_svg: bool,
+    save_raw: bool,
+    enrichers: Vec<Box<dyn Enricher>>,
+    pdfs: Option<PdfDownloader>,
+    grobid: Option<GrobidClient>,
+    llm_fulltext: Option<usize>,
+    options: serde_json::Value,
+    failures: FailureLog,
+    /// Start of the stage in progress, for the stage timings in the log
+    stage_clock: Mutex<Instant>,
+    progress: ProgressFn,
+}
+
+impl Pipeline {
+    /// Run every configured stage
+    pub async fn run(mut self) -> Result<PipelineReport> {
+        let started = Instant::now();
+        *self.stage_clock.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = started;
+        std::fs::create_dir_all(&self.output_folder)?;
+        status!(self, "Output folder: {}", self.output_folder.display());
+        let mut state = self.load_state()?;
+        let archive = self.save_raw.then(|| RawArchive::new(self.output_folder.join(raw_archive::RAW_DIR)));
+
+        // A resumed run keeps the start year --auto-ylo chose
+        if self.ylo.is_none() {
+            self.ylo = match state.ylo {
+                Some(year) => Some(year),
+                None if !state.is_completed(Stage::Search) => {
+                    raw_archive::scope(archive.clone(), raw_archive::in_stage("year_histogram", self.year_histogram())).await
+                }
+                None => None,
+            };
+            state.ylo = self.ylo;
+        }
+        let mut manifest = self.manifest()?;
+        manifest.save(&self.output_folder)?;
+
+        let mut report = PipelineReport {
+            output_folder: self.output_folder.clone(),
+            prisma: state.prisma.clone(),
+            token_usage: state.token_usage.clone(),
+            ..Default::default()
+        };
+
+        let outcome = raw_archive::scope(archive.clone(), self.run_stages(&mut state, &mut report)).await;
+        if let Some(archive) = &archive {
+            archive.flush().await;
+        }
+        outcome?;
+        if let Some(archive) = archive.filter(|a| a.saved() > 0) {
+            status!(self, "Saved {} raw responses to {}/", archive.saved(), raw_archive::RAW_DIR);
+        }
+        self.finish(&mut report)?;
+        manifest.finish(&report.prisma, self.stage_counts(&report));
+        manifest.save(&self.output_folder)?;
+        std::fs::write(self.output_folder.join(strategy::SEARCH_STRATEGY_FILE), strategy::render(&manifest))?;
+        info!(elapsed_secs = started.elapsed().as_secs_f64(), failures = report.failures.len(), "Pipeline finished");
+        Ok(report)
+    }
+
+    /// Manifest of this run: options, exact queries and services used
+    ///
+    /// A resumed run keeps the start time of the manifest it continues.
+    fn manifest(&self) -> Result<Manifest> {
+        let ylo = self.start_year();
+        let search = SearchParameters {
+            from_year: ylo,
+            pages: self.pages.clone(),
+            stages: (1..=8).filter(|&stage| self.stages.contains(stage)).collect(),
+            input_records: self.input.as_ref().map(Vec::len).or(self.unified_input.as_ref().map(Vec::len)),
+            since: self.since.as_ref().map(|previous| previous.folder.clone()),
+            filters: self.filter_descriptions(),
+        };
+        let mut manifest = Manifest::new(&self.keyword, search);
+        if self.resume {
+            if let Some(previous) = Manifest::load(&self.output_folder)? {
+                manifest.started_at = previous.started_at;
+                manifest.resumed_at = previous.resumed_at;
+                manifest.resumed_at.push(Local::now().to_rfc3339());
+            }
+        }
+        manifest.options = self.options.clone();
+        manifest.fingerprint = manifest::fingerprint(&self.keyword, &self.options);
+
+        let searched = self.input.is_none() && self.unified_input.is_none() && self.stages.contains(1);
+        for &source in self.sources.iter().filter(|_| searched) {
+            let url = match source {
+                Source::GoogleScholar => {
+                    let query_options = gscholar::QueryOptions {
+                        sdt: self.sdt.clone(),
+                        ylo: Some(ylo),
+                        base_url: self.mirror.clone(),
+                        reviews_only: work_type::scholar_reviews_only(&self.work_types),
+                        ..Default::default()
+                    };
+                    match &self.scholar_api {
+                        Some(api) => api.search_url(&self.keyword, 1, &query_options)?.to_string(),
+                        None => gscholar::search_url(&self.keyword, 1, &query_options)?.to_string(),
+                    }
+                }
+                Source::OpenAlex => {
+                    let query_options = openalex::QueryOptions {
+                        ylo: Some(ylo),
+                        work_types: self.work_types.clone(),
+                        countries: self.countries.clone(),
+                        ..Default::default()
+                    };
+                    openalex::build_search_url(&self.keyword, 1, &query_options)?
+                }
+                Source::SemanticScholar => semanticscholar::search_url(&self.keyword, 1, Some(ylo), &self.work_types)?,
+            };
+            manifest.queries.push(SourceQuery { source: source.name().to_string(), query: self.keyword.clone(), url });
+        }
+
+        let service = |stage: &str, name: &str, url: &str, model: Option<&str>| Service {
+            stage: stage.to_string(),
+            name: name.to_string(),
+            url: url.to_string(),
+            model: model.map(String::from),
+        };
+        let looked_up = self.input.is_some() || (searched && self.sources.contains(&Source::GoogleScholar));
+        if looked_up && self.stages.contains(2) {
+            manifest.services.push(service("2", "Crossref", crossref::CROSSREF_API_URL, None));
+        }
+        if self.unified_input.is_none() {
+            if self.ranking.is_some() && self.stages.contains(3) {
+                manifest.services.push(service("3", "EasyScholar", rankings::EASYSCHOLAR_API_URL, None));
+            }
+            if self.stages.contains(4) {
+                let url = format!("{}/paper/batch", semanticscholar::SS_API_BASE);
+                manifest.services.push(service("4", "Semantic Scholar", &url, None));
+            }
+            if self.stages.contains(5) {
+                for enricher in &self.enrichers {
+                    manifest.services.push(service("5 (enrich)", enricher.name(), enricher.url(), None));
+                }
+            }
+        }
+        if let Ok(llm_config) = &self.llm {
+            let name = match llm_config.provider {
+                llm_filter::LlmProvider::OpenAi => "OpenAI-compatible LLM",
+                llm_filter::LlmProvider::Ollama => "Ollama",
+            };
+            if self.expand_query {
+                manifest.services.push(service("query expansion", name, &llm_config.base_url, Some(&llm_config.model)));
+            }
+            if self.stages.contains(6) {
+                manifest.services.push(service("6", name, &llm_config.base_url, Some(&llm_config.model)));
+                if let Some(review_model) = &self.review_model {
+                    manifest.services.push(service("6 (review)", name, &llm_config.base_url, Some(review_model)));
+                }
+            }
+            if (!self.tags.is_empty() || !self.study_designs.is_empty()) && self.stages.contains(8) {
+                manifest.services.push(service("8", name, &llm_config.base_url, Some(&llm_config.model)));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Filters of the selected stages, in words, for the manifest and the
+    /// search strategy report
+    fn filter_descriptions(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(previous) = &self.since {
+            filters.push(format!("Only records not seen by the previous run ({}, {})", previous.folder.display(), previous.date));
+        }
+        if let Some(max) = self.max_results {
+            filters.push(format!("Search stopped after {} unique records", max));
+        }
+        if !self.work_types.is_empty() {
+            let wanted: Vec<&str> = self.work_types.iter().map(|t| t.as_str()).collect();
+            filters.push(format!("Work types: {} (records of other known types removed)", wanted.join(", ")));
+        }
+        if !self.countries.is_empty() {
+            filters.push(format!(
+                "Author countries: {} (records without country data kept)",
+                self.countries.join(", ")
+            ));
+        }
+        if let Some(min) = self.min_citations {
+            filters.push(format!("At least {} citations", min));
+        }
+        if let Some(ranking) = self.ranking.as_ref().filter(|_| self.stages.contains(3)) {
+            if let Some(list) = &ranking.journal_include {
+                filters.push(format!("Journal whitelist ({} entries)", list.len()));
+            }
+            if let Some(list) = &ranking.journal_exclude {
+                filters.push(format!("Journal blacklist ({} entries)", list.len()));
+            }
+            if let Some(filter) = &ranking.filter {
+                filters.push(format!("EasyScholar ranking filter: `{}`", filter));
+            }
+        }
+        if self.stages.contains(5) {
+            filters.push("Records without a DOI removed".to_string());
+            match self.reviews {
+                ReviewPolicy::Keep => {}
+                ReviewPolicy::Exclude => filters.push("Review articles removed".to_string()),
+                ReviewPolicy::Only => filters.push("Only review articles kept".to_string()),
+            }
+        }
+        if self.llm.is_ok() && self.stages.contains(6) {
+            if !self.prefilter.is_empty() {
+                filters.push(format!("Keyword pre-filter of titles and abstracts: {}", self.prefilter.describe()));
+            }
+            let uncertain = match self.uncertain_policy {
+                UncertainPolicy::Keep => "included",
+                UncertainPolicy::Drop => "excluded",
+                UncertainPolicy::Review => "set aside for manual review",
+            };
+            let method = match &self.criteria {
+                Some(criteria) => format!("LLM screening against {} inclusion/exclusion criteria", criteria.criteria.len()),
+                None => "LLM relevance screening of titles and abstracts".to_string(),
+            };
+            filters.push(format!("{} (minimum confidence {}; uncertain papers {})", method, self.min_confidence, uncertain));
+        }
+        filters
+    }
+
+    /// Records left after each stage that produced output
+    fn stage_counts(&self, report: &PipelineReport) -> Vec<StageCount> {
+        let prisma = &report.prisma;
+        let count = |stage: &str, records: usize| StageCount { stage: stage.to_string(), records };
+        let mut counts = Vec::new();
+        if prisma.identified > 0 {
+            counts.push(count("1-2 search", prisma.identified));
+            counts.push(count("deduplicated", prisma.identified - prisma.duplicates_removed - prisma.previously_seen));
+        }
+        if self.ranking.is_some() && self.stages.contains(3) && self.unified_input.is_none() {
+            counts.push(count("3 easyscholar", prisma.after_removal() + prisma.no_doi_excluded + prisma.review_excluded));
+        }
+        if !report.unified.is_empty() {
+            counts.push(count("5 unified", report.unified.len()));
+        }
+        if !report.verdicts.is_empty() {
+            counts.push(count("6 screening", report.verdicts.len()));
+        }
+        if let Some(included) = prisma.included {
+            counts.push(count("7 relevant", included));
+        }
+        counts
+    }
+
+    /// Project the calls, tokens, cost and duration of [`run`](Self::run)
+    /// from one hit-count request per source, without writing anything
+    pub async fn estimate(&self, llm_price: Option<LlmPrice>) -> Result<Estimate> {
+        let ylo = match self.year_histogram().await {
+            Some(year) => year,
+            None => self.start_year(),
+        };
+        let mut estimate = Estimate { keyword: self.keyword.clone(), ylo, ..Default::default() };
+
+        if let Some(unified_results) = &self.unified_input {
+            estimate.input_records = unified_results.len();
+        } else if let Some(input) = &self.input {
+            estimate.input_records = input.len();
+            if self.stages.contains(2) {
+                estimate.crossref_lookups = input.len();
+            }
+        } else if self.stages.contains(1) {
+            for &source in &self.sources {
+                let source_estimate = self.count_source(source, ylo).await;
+                if source == Source::GoogleScholar && self.stages.contains(2) {
+                    estimate.crossref_lookups += source_estimate.records;
+                }
+                estimate.sources.push(source_estimate);
+            }
+        }
+
+        let mut records = estimate.records();
+        if let Some(max) = self.max_results.filter(|&max| max < records) {
+            records = max;
+            estimate.notes.push(format!("The search stops at --max-results {}, so Stage 1 fetches fewer pages than listed.", max));
+        }
+        if self.unified_input.is_none() {
+            if self.ranking.is_some() && self.stages.contains(3) {
+                estimate.easyscholar_queries = records;
+            }
+            if self.stages.contains(4) && self.stages.contains(5) {
+                estimate.s2_batches = records.div_ceil(semanticscholar::MAX_BATCH_SIZE);
+            }
+        }
+
+        let unified = self.unified_input.is_some() || self.stages.contains(5);
+        if let Ok(llm_config) = &self.llm {
+            if unified && self.stages.contains(6) {
+                let prompt_chars = match &self.criteria {
+                    Some(criteria) => {
+                        criteria_screening::SYSTEM_PROMPT.len()
+                            + criteria_screening::build_user_prompt(&criteria.to_prompt(), "").len()
+                    }
+                    None => {
+                        relevance_filter::SYSTEM_PROMPT.len()
+                            + relevance_filter::build_user_prompt(&llm_config.filter_help, "").len()
+                    }
+                };
+                estimate.llm = Some(LlmEstimate::new(llm_config, records, prompt_chars, llm_price));
+                if !self.prefilter.is_empty() {
+                    estimate.notes.push("The keyword pre-filter labels some papers irrelevant without an LLM call, so Stage 6 makes fewer calls.".to_string());
+                }
+                if self.review_model.is_some() {
+                    estimate.notes.push("The second pass adds one call to the review model per uncertain or low-confidence verdict.".to_string());
+                }
+                if !self.tags.is_empty() && self.stages.contains(8) {
+                    estimate.notes.push("Stage 8 adds one tagging call per relevant paper.".to_string());
+                }
+                if !self.study_designs.is_empty() && self.stages.contains(8) {
+                    estimate.notes.push("Stage 8 adds one study design call per relevant paper.".to_string());
+                }
+            }
+            if self.expand_query {
+                estimate.notes.push("Query expansion adds one LLM call and a hit count per suggestion.".to_string());
+            }
+        }
+        if self.since.is_some() {
+            estimate.notes.push("Records already in the previous run are dropped after Stage 1, so later stages see fewer.".to_string());
+        }
+        Ok(estimate)
+    }
+
+    /// Stage 1 projection for one source; a failed hit count assumes full pages
+    async fn count_source(&self, source: Source, ylo: i32) -> SourceEstimate {
+        status!(self, "Counting {} results...", source.name());
+        let (hits, page_size, max_results, seconds_per_page) = match source {
+            Source::GoogleScholar => {
+                let query_options = gscholar::QueryOptions {
+                    proxy: self.proxy.clone(),
+                    pages: vec![1],
+                    sdt: self.sdt.clone(),
+                    ylo: Some(ylo),
+                    base_url: self.mirror.clone(),
+                    all_results: true,
+                    cookie_path: self.cookie_path.clone(),
+                    reviews_only: work_type::scholar_reviews_only(&self.work_types),
+                };
+                let hits = match &self.scholar_api {
+                    Some(api) => api.count(&self.keyword, &query_options).await,
+                    None => gscholar::count(&self.keyword, &query_options).await,
+                };
+                (hits, gscholar::RESULTS_PER_PAGE, None, estimate::GSCHOLAR_PAGE_SECONDS)
+            }
+            Source::OpenAlex => {
+                let query_options = openalex::QueryOptions {
+                    ylo: Some(ylo),
+                    cache: self.http_cache("openalex", self.search_cache_ttl),
+                    work_types: self.work_types.clone(),
+                    countries: self.countries.clone(),
+                    ..Default::default()
+                };
+                let hits = openalex::count(&self.keyword, &query_options).await.map(|c| u64::try_from(c).ok());
+                (
+                    hits,
+                    self.openalex_per_page.clamp(1, openalex::MAX_PER_PAGE),
+                    None,
+                    estimate::OPENALEX_PAGE_SECONDS + self.openalex_page_delay.as_secs_f64(),
+                )
+            }
+            Source::SemanticScholar => {
+                let hits = self.s2.count(&self.keyword, Some(ylo), &self.work_types).await.map(Some);
+                (
+                    hits,
+                    semanticscholar::SEARCH_PAGE_SIZE,
+                    Some(semanticscholar::MAX_SEARCH_RESULTS),
+                    estimate::S2_PAGE_SECONDS,
+                )
+            }
+        };
+        let hits = hits.unwrap_or_else(|e| {
+            status!(self, "{} hit count failed: {} (assuming full pages)", source.name(), e);
+            None
+        });
+        SourceEstimate::new(source.name(), hits, &self.pages, page_size, max_results, seconds_per_page)
+    }
+
+    /// Print how the keyword's OpenAlex works spread over the years before a
+    /// search without `--ylo`; returns the suggested start year under
+    /// `--auto-ylo`
+    async fn year_histogram(&self) -> Option<i32> {
+        if self.ylo.is_some()
+            || self.since.is_some()
+            || self.input.is_some()
+            || self.unified_input.is_some()
+            || !self.stages.contains(1)
+        {
+            return None;
+        }
+        let query_options = openalex::QueryOptions {
+            cache: self.http_cache("openalex", self.search_cache_ttl),
+            work_types: self.work_types.clone(),
+            countries: self.countries.clone(),
+            ..Default::default()
+        };
+        let counts = match openalex::year_counts(&self.keyword, &query_options).await {
+            Ok(counts) if counts.total() > 0 => counts,
+            Ok(_) => return None,
+            Err(e) => {
+                status!(self, "Year histogram unavailable: {}", e);
+                return None;
+            }
+        };
+
+        status!(self, "Publication years of \"{}\" on OpenAlex ({} works):", self.keyword, counts.total());
+        for line in counts.histogram(HISTOGRAM_YEARS, HISTOGRAM_WIDTH).lines() {
+            status!(self, "  {}", line);
+        }
+        let share = |year: i32| counts.since(year) as f64 * 100.0 / counts.total() as f64;
+        let suggested = counts.suggest_ylo(openalex::YLO_COVERAGE);
+        if let Some(year) = suggested.filter(|_| self.auto_ylo) {
+            status!(self, "Searching from {} onwards (--auto-ylo): {:.0}% of the works", year, share(year));
+            return Some(year);
+        }
+        let ylo = self.start_year();
+        status!(self, "Searching from {} onwards (default: five years back): {:.0}% of the works", ylo, share(ylo));
+        if let Some(year) = suggested.filter(|&year| year < ylo) {
+            status!(self, "--ylo {} would cover {:.0}% (or pass --auto-ylo)", year, share(year));
+        }
+        None
+    }
+
+    /// First year searched: `--ylo` (default five years back), or the year
+    /// of the run `--since` updates if later
+    fn start_year(&self) -> i32 {
+        let ylo = self.ylo.unwrap_or_else(|| Local::now().year() - 5);
+        self.since.as_ref().map_or(ylo, |previous| ylo.max(previous.date.year()))
+    }
+
+    async fn run_stages(&self, state: &mut RunState, report: &mut PipelineReport) -> Result<()> {
+        let ylo = self.start_year();
+        if let Some(previous) = self.since.as_ref().filter(|p| p.date.year() == ylo) {
+            status!(self, "Updating {}: searching from {} onwards", previous.folder.display(), ylo);
+        }
+
+        if self.expand_query && !state.is_completed(Stage::QueryExpansion) {
+            raw_archive::in_stage("query_expansion", self.expand(ylo, report)).await?;
+            self.checkpoint(state, Stage::QueryExpansion, &(), report)?;
+        }
+
+        let unified_results = match &self.unified_input {
+            Some(unified_results) => {
+                status!(self, "\n--- Stages 1-5: Skipped ({} records from the input table) ---", unified_results.len());
+                report.prisma.identified = unified_results.len();
+                let mut unified_results = self.drop_uncited(unified_results.clone(), report);
+                if let Some(max) = self.max_results {
+                    unified_results.truncate(max);
+                }
+                if unified_results.is_empty() {
+                    return Ok(());
+                }
+                unified_results
+            }
+            None => match self.collect(ylo, state, report).await? {
+                Some(unified_results) => unified_results,
+                None => return Ok(()),
+            },
+        };
+        report.unified = unified_results.clone();
+
+        if !self.stages.contains(6) && !state.is_completed(Stage::Screening) {
+            status!(self, "\n--- Stage 6: Skipped ---");
+            return Ok(());
+        }
+        let llm_config = match &self.llm {
+            Ok(llm_config) => llm_config,
+            Err(reason) => {
+                status!(self, "\n--- Stage 6: Skipped ({}) ---", reason);
+                return Ok(());
+            }
+        };
+        let verdicts: Vec<FilterResult> = match self.resumed(state, Stage::Screening, "Stage 6")? {
+            Some(verdicts) => verdicts,
+            None => {
+                let Some(verdicts) = raw_archive::in_stage("6_screening", self.screen(llm_config, &unified_results, report)).await?
+                else {
+                    return Ok(());
+                };
+                self.checkpoint(state, Stage::Screening, &verdicts, report)?;
+                verdicts
+            }
+        };
+
+        report.verdicts = verdicts.clone();
+        if !self.stages.contains(7) {
+            status!(self, "\n--- Stage 7: Skipped ---");
+            return Ok(());
+        }
+        raw_archive::in_stage("7_relevant", self.select(llm_config, &unified_results, &verdicts, state, report)).await?;
+        Ok(())
+    }
+
+    /// Stages 1-5; `None` when there is no unified dataset to screen
+    async fn collect(&self, ylo: i32, state: &mut RunState, report: &mut PipelineReport) -> Result<Option<Vec<Paper>>> {
+        // Shared by the lookups during the search and Stage 3, which then
+        // finds the journals seen while searching in the client's cache
+        let mut ranking = match &self.ranking {
+            Some(options) if self.stages.contains(3) && !state.is_completed(Stage::Rankings) => {
+                Some((options, self.ranking_client(options)?))
+            }
+            _ => None,
+        };
+
+        let spooled: Spooled = match self.resumed(state, Stage::Search, "Stages 1-2")? {
+            Some(spooled) => spooled,
+            None if !self.stages.contains(1) && self.input.is_none() => {
+                return Err(GscholarError::Config(format!(
+                    "Stage 1 (search) can only be skipped when resuming a run whose {} has search results",
+                    STATE_FILE
+                )));
+            }
+            None => {
+                let records = match &self.input {
+                    Some(input) => raw_archive::in_stage("2_crossref", self.load_input(input)).await?,
+                    None => self.search(ylo, ranking.as_ref().map(|(options, client)| (*options, client))).await?,
+                };
+                let Some(records) = records else {
+                    return Ok(None);
+                };
+                self.checkpoint(state, Stage::Search, &records, report)?;
+                records
+            }
+        };
+        let records: Vec<Paper> = spooled.read_all(&self.output_folder)?;
+
+        // Drop repeated records, keeping every source that found them
+        report.prisma.identified = records.len();
+        let mut records = merge_duplicates(records);
+        guess_venue_types(&mut records);
+        report.prisma.duplicates_removed = report.prisma.identified - records.len();
+        if let Some(max) = self.max_results.filter(|&max| records.len() > max) {
+            // A list given with --input, or records both sources counted once
+            status!(self, "Keeping the first {} of {} records (--max-results)", max, records.len());
+            records.truncate(max);
+        }
+        if report.prisma.duplicates_removed > 0 {
+            status!(self, "Removed {} duplicate records", report.prisma.duplicates_removed);
+        }
+        let records = match &self.since {
+            Some(previous) => self.drop_seen(previous, records, report)?,
+            None => records,
+        };
+        if records.is_empty() {
+            status!(self, "No new records since the previous run.");
+            return Ok(None);
+        }
+        let records = self.drop_other_work_types(records, report);
+        let records = self.drop_other_countries(records, report);
+        let records = self.drop_uncited(records, report);
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        let ranked: Vec<Paper> = match self.resumed(state, Stage::Rankings, "Stage 3")? {
+            Some(ranked) => ranked,
+            None => match &self.ranking {
+                Some(options) if self.stages.contains(3) => {
+                    let client = match ranking.take() {
+                        Some((_, client)) => client,
+                        None => self.ranking_client(options)?,
+                    };
+                    let ranked = raw_archive::in_stage("3_easyscholar", self.rank(options, &client, records, report)).await?;
+                    self.checkpoint(state, Stage::Rankings, &ranked, report)?;
+                    ranked
+                }
+                Some(_) => {
+                    status!(self, "\n--- Stage 3: Skipped ---");
+                    records
+                }
+                None => {
+                    status!(self, "\n--- Stage 3: Skipped (no EasyScholar key provided) ---");
+                    records
+                }
+            },
+        };
+        if ranked.is_empty() {
+            return Ok(None);
+        }
+
+        let unified_results: Vec<Paper> = match self.resumed(state, Stage::Unified, "Stages 4-5")? {
+            Some(unified_results) => unified_results,
+            None if !self.stages.contains(5) => {
+                status!(self, "\n--- Stage 5: Skipped (Stages 6-8 need the unified dataset) ---");
+                return Ok(None);
+            }
+            None => {
+                let unified_results = raw_archive::in_stage("5_unified", self.unify(&ranked, report)).await?;
+                if unified_results.is_empty() {
+                    status!(self, "No papers with a DOI for the unified dataset.");
+                    return Ok(None);
+                }
+                self.checkpoint(state, Stage::Unified, &unified_results, report)?;
+                unified_results
+            }
+        };
+        Ok(Some(unified_results))
+    }
+
+    /// Drop the records whose known work type is not one of `--work-types`
+    /// (records of unknown type are kept)
+    fn drop_other_work_types(&self, records: Vec<Paper>, report: &mut PipelineReport) -> Vec<Paper> {
+        if self.work_types.is_empty() {
+            return records;
+        }
+        let total = records.len();
+        let records: Vec<Paper> =
+            records.into_iter().filter(|r| work_type::is_wanted(r.work_type, &self.work_types)).collect();
+        report.prisma.work_type_excluded = total - records.len();
+        let wanted: Vec<&str> = self.work_types.iter().map(|t| t.as_str()).collect();
+        status!(self, "Work types: kept {} / {} results ({})", records.len(), total, wanted.join(", "));
+        records
+    }
+
+    /// Drop the records whose authors are all from countries other than
+    /// `--country` (records without country data are kept)
+    fn drop_other_countries(&self, records: Vec<Paper>, report: &mut PipelineReport) -> Vec<Paper> {
+        if self.countries.is_empty() {
+            return records;
+        }
+        let total = records.len();
+        let unknown = records.iter().filter(|r| r.countries.is_empty()).count();
+        let records: Vec<Paper> = records
+            .into_iter()
+            .filter(|r| r.countries.is_empty() || r.countries.iter().any(|c| self.countries.contains(c)))
+            .collect();
+        report.prisma.country_excluded = total - records.len();
+        status!(
+            self,
+            "Countries: kept {} / {} results ({}; {} without country data)",
+            records.len(),
+            total,
+            self.countries.join(", "),
+            unknown
+        );
+        records
+    }
+
+    /// Drop the records cited fewer than `--min-citations` times
+    fn drop_uncited(&self, records: Vec<Paper>, report: &mut PipelineReport) -> Vec<Paper> {
+        let Some(min) = self.min_citations else {
+            return records;
+        };
+        let total = records.len();
+        let records: Vec<Paper> = records.into_iter().filter(|r| r.citations.is_some_and(|c| c >= min)).collect();
+        report.prisma.citation_excluded = total - records.len();
+        status!(self, "Citations: kept {} / {} results cited at least {} times", records.len(), total, min);
+        records
+    }
+
+    /// Drop the records a previous run already identified, recording every
+    /// key seen so far in `seen.txt` for the next update
+    fn drop_seen(&self, previous: &PreviousRun, records: Vec<Paper>, report: &mut PipelineReport) -> Result<Vec<Paper>> {
+        incremental::save_seen(&self.output_folder, previous, records.iter().map(|r| (r.doi.as_str(), r.title.as_str())))?;
+        let total = records.len();
+        let records: Vec<Paper> = records.into_iter().filter(|r| !previous.contains(&r.doi, &r.title)).collect();
+        report.prisma.previously_seen = total - records.len();
+        status!(
+            self,
+            "Skipped {} records already in {} ({} new)",
+            report.prisma.previously_seen,
+            previous.folder.display(),
+            records.len()
+        );
+        Ok(records)
+    }
+
+    /// Fresh run state, or the folder's `state.json` when resuming
+    fn load_state(&self) -> Result<RunState> {
+        let fresh = || RunState::new(&self.keyword, &self.source_name());
+        if !self.resume {
+            return Ok(fresh());
+        }
+        let Some(state) = RunState::load(&self.output_folder)? else {
+            status!(self, "No {} in the run folder, starting from Stage 1", STATE_FILE);
+            return Ok(fresh());
+        };
+
+        if state.keyword != self.keyword || state.source != self.source_name() {
+            return Err(GscholarError::Config(format!(
+                "{} was started for \"{}\" on {}, not \"{}\" on {}",
+                self.output_folder.display(),
+                state.keyword,
+                state.source,
+                self.keyword,
+                self.source_name()
+            )));
+        }
+        if let Some(stage) = state.last_completed() {
+            status!(self, "Resuming after {:?} ({} stages completed)", stage, state.completed.len());
+        }
+        Ok(state)
+    }
+
+    /// Source recorded in the run state
+    fn source_name(&self) -> String {
+        if self.input.is_some() || self.unified_input.is_some() {
+            INPUT_SOURCE.to_string()
+        } else {
+            self.sources.iter().map(|s| s.name()).collect::<Vec<_>>().join(" + ")
+        }
+    }
+
+    /// Output of a stage completed before the run was resumed
+    fn resumed<T: DeserializeOwned>(&self, state: &RunState, stage: Stage, label: &str) -> Result<Option<T>> {
+        let data = state.data(stage)?;
+        if data.is_some() {
+            status!(self, "\n--- {}: Reusing results from {} ---", label, STATE_FILE);
+        }
+        Ok(data)
+    }
+
+    /// Record a completed stage (with the counts so far) in `state.json`
+    fn checkpoint<T: Serialize>(&self, state: &mut RunState, stage: Stage, data: &T, report: &PipelineReport) -> Result<()> {
+        state.prisma = report.prisma.clone();
+        state.token_usage = report.token_usage.clone();
+        state.complete(stage, data)?;
+        let mut clock = self.stage_clock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        info!(stage = ?stage, elapsed_secs = clock.elapsed().as_secs_f64(), "Stage completed");
+        *clock = Instant::now();
+        state.save(&self.output_folder)
+    }
+
+    // ===========================================
+    // PRE-STAGE: Query Expansion
+    // ===========================================
+    async fn expand(&self, ylo: i32, report: &mut PipelineReport) -> Result<()> {
+        status!(self, "\n--- Pre-Stage: Query Expansion ---");
+        let llm_config = match &self.llm {
+            Ok(config) => config,
+            Err(reason) => {
+                status!(self, "Skipped ({})", reason);
+                return Ok(());
+            }
+        };
+
+        let outcome = async {
+            let (mut expansion, usage) =
+                query_expansion::expand_query(llm_config, &self.keyword, &llm_config.filter_help).await?;
+            let count_options = openalex::QueryOptions {
+                ylo: Some(ylo),
+                work_types: self.work_types.clone(),
+                countries: self.countries.clone(),
+                ..Default::default()
+            };
+            let baseline = query_expansion::validate(&mut expansion, &count_options).await;
+            Ok::<_, GscholarError>((expansion, usage, baseline))
+        };
+
+        match outcome.await {
+            Ok((expansion, usage, baseline)) => {
+                status!(self, "{}", expansion.summary(Some(baseline)));
+                status!(self, "\nToken usage: {} total", usage.total_tokens);
+                report.token_usage.add(&usage);
+
+                let path = self.output_folder.join("0_query_expansion.json");
+                std::fs::write(&path, serde_json::to_string_pretty(&expansion)?)?;
+                status!(self, "Saved: {:?}", path);
+            }
+            Err(e) => status!(self, "Query expansion failed: {}", e),
+        }
+        Ok(())
+    }
+
+    // ===========================================
+    // STAGE 1 & 2: Search & Enrichment
+    // ===========================================
+
+    /// Stage 1 over every source (and Stage 2 for Google Scholar); `None` when nothing was found
+    ///
+    /// Pages stream through bounded channels: each page goes through
+    /// Crossref and the EasyScholar journal lookups (when `ranking` is set)
+    /// while the next one is fetched, and a source that fails after some
+    /// pages keeps them.
+    async fn search(&self, ylo: i32, ranking: Option<(&RankingOptions, &RankingClient)>) -> Result<Option<Spooled>> {
+        let (tx, rx) = stream::channel();
+        let searches = async move {
+            let mut harvest = Harvest {
+                budget: ResultBudget::new(self.max_results),
+                records: Spool::create(&self.output_folder, SEARCH_SPOOL)?,
+            };
+            for &source in &self.sources {
+                if harvest.budget.is_full() {
+                    status!(self, "\n--- Stage 1: {} skipped (--max-results reached) ---", source.name());
+                    continue;
+                }
+                match self.search_source(source, ylo, &tx, &mut harvest).await {
+                    Ok(_) => {}
+                    // One failing source does not sink the others
+                    Err(e) if self.sources.len() > 1 => {
+                        status!(self, "{} search failed: {} (continuing with the other sources)", source.name(), e);
+                        self.failures.record("search", source.id(), &e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            harvest.records.finish()
+        };
+        let (records, ()) = tokio::join!(
+            raw_archive::in_stage("1_search", searches),
+            raw_archive::in_stage("3_easyscholar", self.prefetch_rankings(rx, ranking))
+        );
+        let records = records?;
+        if self.sources.len() > 1 {
+            status!(self, "\n{} records from {} sources", records.len(), self.sources.len());
+        }
+        Ok((!records.is_empty()).then_some(records))
+    }
+
+    /// Stage 1 for one source, adding its records to the harvest and
+    /// sending every page on to the next stage; returns the records found
+    async fn search_source(
+        &self,
+        source: Source,
+        ylo: i32,
+        tx: &PageSender<Paper>,
+        harvest: &mut Harvest,
+    ) -> Result<usize> {
+        match source {
+            Source::GoogleScholar => {
+                match &self.scholar_api {
+                    Some(api) => status!(self, "\n--- Stage 1: Google Scholar Search (via {}) ---", api.backend().name()),
+                    None => status!(self, "\n--- Stage 1: Google Scholar Search ---"),
+                }
+
+                let query_options = gscholar::QueryOptions {
+                    proxy: self.proxy.clone(),
+                    pages: self.pages.clone(),
+                    sdt: self.sdt.clone(),
+                    ylo: Some(ylo),
+                    base_url: self.mirror.clone(),
+                    all_results: true,
+                    cookie_path: self.cookie_path.clone(),
+                    reviews_only: work_type::scholar_reviews_only(&self.work_types),
+                };
+
+                let crossref_client = if self.stages.contains(2) {
+                    status!(self, "Stage 2: Crossref lookups run on each page as it arrives");
+                    Some(self.crossref_client()?)
+                } else {
+                    status!(self, "\n--- Stage 2: Skipped ---");
+                    None
+                };
+
+                // Stage 1 and 2 tables are written page by page
+                let mut gs_table = self.table_writer("1_gscholar.csv");
+                let mut crossref_table = crossref_client.is_some().then(|| self.table_writer("2_crossref.csv"));
+                let (page_tx, page_rx) = stream::channel();
+                let search_cache = self.http_cache("scholar_api", self.search_cache_ttl);
+                let fetch = async {
+                    match &self.scholar_api {
+                        Some(api) => api.query_pages(&self.keyword, &query_options, search_cache.as_ref(), page_tx).await,
+                        None => gscholar::query_pages(&self.keyword, &query_options, page_tx).await,
+                    }
+                };
+                let crossref =
+                    self.crossref_pages(crossref_client.as_ref(), page_rx, tx, harvest, &mut gs_table, crossref_table.as_mut());
+                let (fetched, crossref) = tokio::join!(fetch, raw_archive::in_stage("2_crossref", crossref));
+                crossref?;
+                self.source_outcome(source, fetched, gs_table.rows())?;
+
+                if gs_table.rows() == 0 {
+                    status!(self, "No results from Google Scholar.");
+                    return Ok(0);
+                }
+
+                status!(self, "Found {} results from Google Scholar.", gs_table.rows());
+                let found = gs_table.rows();
+                self.finish_table(gs_table)?;
+                if let Some(table) = crossref_table {
+                    self.finish_table(table)?;
+                }
+                Ok(found)
+            }
+            Source::OpenAlex => {
+                status!(self, "\n--- Stage 1: OpenAlex Search (Enriched) ---");
+
+                let query_options = openalex::QueryOptions {
+                    pages: self.pages.clone(),
+                    ylo: Some(ylo),
+                    yhi: None,
+                    all_results: true,
+                    per_page: self.openalex_per_page,
+                    page_delay: self.openalex_page_delay,
+                    cache: self.http_cache("openalex", self.search_cache_ttl),
+                    work_types: self.work_types.clone(),
+                    countries: self.countries.clone(),
+                };
+
+                // Stage 1 table with all OpenAlex fields, written page by page
+                let mut table = self.table_writer("1_openalex.csv");
+                let (page_tx, page_rx) = stream::channel();
+                let fetch = openalex::query_pages(&self.keyword, &query_options, page_tx);
+                let (fetched, found) =
+                    tokio::join!(fetch, self.forward_pages(source, page_rx, tx, harvest, &mut table));
+                let found = found?;
+                self.source_outcome(source, fetched, found)?;
+
+                if found == 0 {
+                    status!(self, "No results from OpenAlex.");
+                    return Ok(0);
+                }
+
+                status!(self, "Found {} results from OpenAlex.", found);
+                self.finish_table(table)?;
+                Ok(found)
+            }
+            Source::SemanticScholar => {
+                status!(self, "\n--- Stage 1: Semantic Scholar Search ---");
+
+                let client = self.s2_client(self.search_cache_ttl);
+                let mut table = self.table_writer("1_semanticscholar.csv");
+                let (page_tx, page_rx) = stream::channel();
+                let fetch = client.search_pages(&self.keyword, &self.pages, Some(ylo), &self.work_types, page_tx);
+                let (fetched, found) =
+                    tokio::join!(fetch, self.forward_pages(source, page_rx, tx, harvest, &mut table));
+                let found = found?;
+                self.source_outcome(source, fetched, found)?;
+
+                if found == 0 {
+                    status!(self, "No results from Semantic Scholar.");
+                    return Ok(0);
+                }
+
+                status!(self, "Found {} results from Semantic Scholar.", found);
+                self.finish_table(table)?;
+                Ok(found)
+            }
+        }
+    }
+
+    /// HTTP response cache for `api`, if enabled; cache errors never fail a run
+    fn http_cache(&self, api: &str, ttl: Option<Duration>) -> Option<HttpCache> {
+        let ttl = ttl?;
+        self.cache_root()
+            .map(|dir| HttpCache::open(dir.join(cache::HTTP_CACHE_DIR).join(api), ttl))
+            .map_err(|e| warn!(error = %e, "HTTP cache unavailable"))
+            .ok()
+    }
+
+    /// Cache directory of this run (see [`PipelineBuilder::cache_dir`])
+    fn cache_root(&self) -> Result<PathBuf> {
+        match &self.cache_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => cache::default_cache_dir(),
+        }
+    }
+
+    /// Semantic Scholar client with the HTTP cache of `ttl` attached
+    fn s2_client(&self, ttl: Option<Duration>) -> SemanticScholarClient {
+        match self.http_cache("semanticscholar", ttl) {
+            Some(cache) => self.s2.clone().with_cache(cache),
+            None => self.s2.clone(),
+        }
+    }
+
+    /// Crossref client with the lookup cache attached
+    fn crossref_client(&self) -> Result<CrossrefClient> {
+        let client = CrossrefClient::new(3)?.with_title_matcher(self.title_matcher);
+        Ok(match self.http_cache("crossref", self.lookup_cache_ttl) {
+            Some(cache) => client.with_cache(cache),
+            None => client,
+        })
+    }
+
+    /// Stage 2: Crossref enrichment of each Google Scholar page as it
+    /// arrives (just the merge when `client` is `None`), appending the raw
+    /// and the enriched results to their tables and the enriched ones to
+    /// the harvest, in page order
+    async fn crossref_pages(
+        &self,
+        client: Option<&CrossrefClient>,
+        mut rx: PageReceiver<gscholar::ScholarResult>,
+        tx: &PageSender<Paper>,
+        harvest: &mut Harvest,
+        gs_table: &mut TableWriter,
+        mut crossref_table: Option<&mut TableWriter>,
+    ) -> Result<()> {
+        let mut matched = 0;
+        while let Some(mut page) = rx.recv().await {
+            if self.page_failed(Source::GoogleScholar, &page) {
+                continue;
+            }
+            let mut crossref_results: Vec<Option<CrossrefMetadata>> = match client {
+                Some(client) => {
+                    let titles: Vec<String> = page.items.iter().map(|r| r.title.clone()).collect();
+                    client.lookup_batch(&titles).await
+                }
+                None => Vec::new(),
+            };
+            let mut papers = merge_crossref(&page.items, &crossref_results);
+            for paper in &mut papers {
+                paper.sources = vec![Source::GoogleScholar.id().to_string()];
+            }
+            // Records past --max-results are dropped with the rest of the page
+            let keep = harvest.budget.take(&papers);
+            papers.truncate(keep);
+            page.items.truncate(keep);
+            crossref_results.truncate(keep);
+
+            let page_matched = crossref_results.iter().filter(|r| r.is_some()).count();
+            matched += page_matched;
+            for (result, crossref) in page.items.iter().zip(&crossref_results) {
+                if crossref.is_none() {
+                    self.failures.record("crossref", &result.title, "No Crossref match");
+                }
+            }
+            if client.is_some() {
+                status!(self, "Page {}: {} results, {} matched on Crossref", page.number, papers.len(), page_matched);
+            } else {
+                status!(self, "Page {}: {} results", page.number, papers.len());
+            }
+
+            gs_table.serialize(&page.items)?;
+            if let Some(table) = crossref_table.as_deref_mut() {
+                table.serialize(&papers.iter().map(RecordRow::from).collect::<Vec<_>>())?;
+            }
+            harvest.records.extend(&papers)?;
+            if tx.send(Page::new(page.number, papers)).await.is_err() {
+                break;
+            }
+            if harvest.budget.is_full() {
+                status!(self, "Collected {} unique records, stopping the search (--max-results)", harvest.budget.len());
+                break;
+            }
+        }
+        if client.is_some() {
+            status!(self, "Crossref: {} / {} matched", matched, gs_table.rows());
+        }
+        Ok(())
+    }
+
+    /// Pass a source's pages on to the next stage as they arrive, appending
+    /// the raw results to the Stage 1 `table` and the papers to `records`,
+    /// both in page order; returns the number of papers
+    ///
+    /// The budget is counted in page order as well, so the records kept at
+    /// `--max-results` do not depend on which page arrived first, and only
+    /// the kept records reach the next stage.
+    async fn forward_pages<T: Clone + Serialize + Into<Paper>>(
+        &self,
+        source: Source,
+        mut rx: PageReceiver<T>,
+        tx: &PageSender<Paper>,
+        harvest: &mut Harvest,
+        table: &mut TableWriter,
+    ) -> Result<usize> {
+        let mut order = PageOrder::new(self.pages.iter().copied());
+        let start = harvest.records.len();
+        let mut open = true;
+        while let Some(page) = rx.recv().await {
+            if !self.page_failed(source, &page) {
+                status!(self, "Page {}: {} results", page.number, page.items.len());
+            }
+            // Later pages are not held back waiting for a failed one
+            open = self.forward_released(source, order.push(page), tx, harvest, table).await?;
+            if !open || harvest.budget.is_full() {
+                break;
+            }
+        }
+        if harvest.budget.is_full() {
+            status!(self, "Collected {} unique records, stopping the search (--max-results)", harvest.budget.len());
+        } else if open {
+            self.forward_released(source, order.finish(), tx, harvest, table).await?;
+        }
+        Ok(harvest.records.len() - start)
+    }
+
+    /// Count pages released in page order against the budget, then write
+    /// the kept records to `table`, add them to the harvest and send them on;
+    /// `false` once the next stage stopped listening
+    async fn forward_released<T: Clone + Serialize + Into<Paper>>(
+        &self,
+        source: Source,
+        pages: Vec<Page<T>>,
+        tx: &PageSender<Paper>,
+        harvest: &mut Harvest,
+        table: &mut TableWriter,
+    ) -> Result<bool> {
+        for mut page in pages {
+            let mut page_papers: Vec<Paper> = page.items.iter().cloned().map(Into::into).collect();
+            let keep = harvest.budget.take(&page_papers);
+            page.items.truncate(keep);
+            page_papers.truncate(keep);
+            for paper in &mut page_papers {
+                paper.sources = vec![source.id().to_string()];
+            }
+            table.serialize(&page.items)?;
+            harvest.records.extend(&page_papers)?;
+            if page.error.is_none() && tx.send(Page::new(page.number, page_papers)).await.is_err() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Report and record a page that could not be fetched
+    fn page_failed<T>(&self, source: Source, page: &Page<T>) -> bool {
+        let Some(error) = &page.error else {
+            return false;
+        };
+        status!(self, "Page {}: failed ({})", page.number, error);
+        self.failures.record("search", &format!("{} page {}", source.id(), page.number), error);
+        true
+    }
+
+    /// A source that fails after some pages keeps them; one that fails
+    /// before the first page fails the search
+    fn source_outcome(&self, source: Source, fetched: Result<()>, found: usize) -> Result<()> {
+        match fetched {
+            Err(e) if found > 0 => {
+                status!(self, "{} search stopped early: {} (keeping the {} results already fetched)", source.name(), e, found);
+                self.failures.record("search", source.id(), &e);
+                Ok(())
+            }
+            fetched => fetched,
+        }
+    }
+
+    /// Stages 1-2 for a user-provided list; `None` when it is empty
+    async fn load_input(&self, input: &[InputRecord]) -> Result<Option<Spooled>> {
+        status!(self, "\n--- Stage 1: Input List ---");
+        if input.is_empty() {
+            status!(self, "No records in the input list.");
+            return Ok(None);
+        }
+        status!(self, "Loaded {} records ({} with a DOI).", input.len(), input.iter().filter(|r| !r.doi.is_empty()).count());
+        self.save_csv(&self.output_folder.join("1_input.csv"), input)?;
+
+        if !self.stages.contains(2) {
+            status!(self, "\n--- Stage 2: Skipped ---");
+            return self.spool_input(&merge_input(input, &[])).map(Some);
+        }
+        status!(self, "\n--- Stage 2: Crossref Enrichment ---");
+
+        let crossref_client = self.crossref_client()?;
+        status!(self, "Looking up {} records by DOI or title (concurrent, 3 workers)...", input.len());
+        let crossref_results: Vec<Option<CrossrefMetadata>> = join_all(input.iter().map(|record| async {
+            // Fall back to the title when the DOI is unknown to Crossref
+            match crossref_client.lookup_by_doi(&record.doi).await {
+                Some(metadata) => Some(metadata),
+                None => crossref_client.lookup_by_title(&record.title).await,
+            }
+        }))
+        .await;
+
+        let enriched_list = merge_input(input, &crossref_results);
+        for (record, crossref) in input.iter().zip(&crossref_results) {
+            if crossref.is_none() {
+                let id = if record.doi.is_empty() { &record.title } else { &record.doi };
+                self.failures.record("crossref", id, "No Crossref match");
+            }
+        }
+
+        let matched = crossref_results.iter().filter(|r| r.is_some()).count();
+        status!(self, "Crossref: {} / {} matched", matched, input.len());
+
+        // Save Stage 2 CSV
+        self.save_records(&self.output_folder.join("2_crossref.csv"), &enriched_list)?;
+
+        self.spool_input(&enriched_list).map(Some)
+    }
+
+    /// Spool the records of an input list for the later stages
+    fn spool_input(&self, records: &[Paper]) -> Result<Spooled> {
+        let mut spool = Spool::create(&self.output_folder, SEARCH_SPOOL)?;
+        spool.extend(records)?;
+        spool.finish()
+    }
+
+    // ===========================================
+    // STAGE 3: EasyScholar Ranking Enrichment
+    // ===========================================
+
+    /// EasyScholar client with the disk cache and journal aliases attached
+    fn ranking_client(&self, options: &RankingOptions) -> Result<RankingClient> {
+        let mut ranking_client = RankingClient::new(options.api_key.clone())?;
+        if let Some(ttl) = options.cache_ttl {
+            let cache_path = self.cache_root()?.join(cache::RANKINGS_CACHE_FILE);
+            ranking_client = ranking_client.with_disk_cache(cache_path, ttl);
+        }
+        if !options.aliases.is_empty() {
+            ranking_client = ranking_client.with_aliases(options.aliases.clone());
+        }
+        Ok(ranking_client)
+    }
+
+    /// Look up the journals of each searched page as it arrives, so that
+    /// Stage 3 finds them in the client's cache. Journals the journal lists
+    /// drop, and records a previous run already saw, cost no API calls.
+    async fn prefetch_rankings(&self, mut rx: PageReceiver<Paper>, ranking: Option<(&RankingOptions, &RankingClient)>) {
+        let mut looked_up: HashSet<String> = HashSet::new();
+        while let Some(page) = rx.recv().await {
+            let Some((options, ranking_client)) = ranking else {
+                continue;
+            };
+            let wanted: Vec<Paper> = page
+                .items
+                .into_iter()
+                .filter(|item| {
+                    journal_list::passes_journal_lists(
+                        &item.journal,
+                        options.journal_include.as_ref(),
+                        options.journal_exclude.as_ref(),
+                    )
+                })
+                .filter(|item| self.since.as_ref().is_none_or(|previous| !previous.contains(&item.doi, &item.title)))
+                .collect();
+            let journal_keys: Vec<(String, Option<String>)> = unique_journals(&wanted)
+                .into_iter()
+                .filter(|(journal, _)| looked_up.insert(journal.clone()))
+                .collect();
+            if !journal_keys.is_empty() {
+                ranking_client.get_ranks_batch(&journal_keys, rankings::MAX_CONCURRENT_LOOKUPS).await;
+            }
+        }
+        if !looked_up.is_empty() {
+            status!(self, "Looked up {} journals on EasyScholar while searching", looked_up.len());
+        }
+    }
+
+    async fn rank(
+        &self,
+        options: &RankingOptions,
+        ranking_client: &RankingClient,
+        mut enriched_list: Vec<Paper>,
+        report: &mut PipelineReport,
+    ) -> Result<Vec<Paper>> {
+        status!(self, "\n--- Stage 3: EasyScholar Ranking ---");
+
+        let filter_active = options.filter.is_some();
+
+        // Step 0: Apply journal whitelist/blacklist before spending any API calls
+        if options.journal_include.is_some() || options.journal_exclude.is_some() {
+            let before = enriched_list.len();
+            enriched_list.retain(|item| {
+                journal_list::passes_journal_lists(
+                    &item.journal,
+                    options.journal_include.as_ref(),
+                    options.journal_exclude.as_ref(),
+                )
+            });
+            report.prisma.journal_list_excluded = before - enriched_list.len();
+            status!(
+                self,
+                "Journal lists: kept {} / {} results ({} dropped)",
+                enriched_list.len(),
+                before,
+                report.prisma.journal_list_excluded
+            );
+        }
+
+        // Step 1: Tell conference proceedings from journals, then collect
+        // unique journal names (with the first ISSN seen for each journal)
+        if options.dblp {
+            self.dblp_venue_types(&mut enriched_list).await;
+        }
+        let journal_keys = unique_journals(&enriched_list);
+        status!(self, "Found {} unique journals to query", journal_keys.len());
+
+        // Step 2: Batch query all unique journals (concurrent, rate limited;
+        // journals looked up while searching come from the client's cache)
+        let journal_rankings = ranking_client
+            .get_ranks_batch(&journal_keys, rankings::MAX_CONCURRENT_LOOKUPS)
+            .await;
+
+        status!(self, "Completed querying {} journals", journal_keys.len());
+
+        // Step 3: Assign rankings to all articles (conferences also get
+        // their CORE rank)
+        let ranked_count = enriched_list.len();
+        let mut result_list: Vec<Paper> = Vec::new();
+        let mut unranked: BTreeMap<String, &str> = BTreeMap::new();
+
+        for mut item in enriched_list {
+            let journal = item.journal.trim().to_string();
+            let conference = item.venue_type == VenueType::Conference;
+            let core = match &options.core_rankings {
+                Some(core_rankings) if conference => core_rankings.rank(venue_name(&item)),
+                _ => None,
+            };
+
+            let metrics = match (journal_rankings.get(&journal).cloned().flatten().filter(|_| !journal.is_empty()), core) {
+                (Some(m), core) => RankingMetrics { core, ..m },
+                (None, Some(core)) => RankingMetrics { core: Some(core), ..Default::default() },
+                (None, None) => {
+                    let venue = venue_name(&item).trim();
+                    if !venue.is_empty() {
+                        let message = if conference { "No CCF/CORE ranking" } else { "No EasyScholar ranking" };
+                        unranked.insert(venue.to_string(), message);
+                    }
+                    if !filter_active {
+                        result_list.push(item);
+                    }
+                    continue;
+                }
+            };
+
+            // Check filters
+            let keep = options.filter.as_ref().is_none_or(|f| f.evaluate(&metrics));
+
+            if keep {
+                item.rankings = Some(metrics);
+                result_list.push(item);
+            }
+        }
+
+        for (venue, message) in &unranked {
+            self.failures.record("easyscholar", venue, message);
+        }
+        report.prisma.rank_excluded = ranked_count - result_list.len();
+        if filter_active {
+            status!(self, "Filtered: {} results", result_list.len());
+        } else {
+            status!(self, "Enriched: {} results with ranking data", result_list.len());
+        }
+
+        // Save Stage 3 CSV
+        self.save_records(&self.output_folder.join("3_easyscholar.csv"), &result_list)?;
+
+        Ok(result_list)
+    }
+
+    /// Ask DBLP for the type of the venues still unknown after
+    /// [`guess_venue_types`] (failed lookups leave them unknown)
+    async fn dblp_venue_types(&self, records: &mut [Paper]) {
+        let dblp = match self.http_cache("dblp", self.lookup_cache_ttl) {
+            Some(cache) => DblpClient::new().with_cache(cache),
+            None => DblpClient::new(),
+        };
+        let mut venues: Vec<String> = records
+            .iter()
+            .filter(|item| item.venue_type == VenueType::Unknown)
+            .map(|item| venue_name(item).trim().to_string())
+            .filter(|venue| !venue.is_empty())
+            .collect();
+        venues.sort();
+        venues.dedup();
+        if venues.is_empty() {
+            return;
+        }
+        status!(self, "Looking up {} venues on DBLP...", venues.len());
+        let mut types: HashMap<String, VenueType> = HashMap::new();
+        for venue in venues {
+            match dblp.venue_type(&venue).await {
+                Ok(venue_type) => {
+                    types.insert(venue, venue_type);
+                }
+                Err(e) => self.failures.record("dblp", &venue, &e),
+            }
+        }
+        for item in records.iter_mut().filter(|item| item.venue_type == VenueType::Unknown) {
+            if let Some(venue_type) = types.get(venue_name(item).trim()) {
+                item.venue_type = *venue_type;
+            }
+        }
+        let conferences = types.values().filter(|t| **t == VenueType::Conference).count();
+        status!(self, "DBLP: {} of {} venues are conferences", conferences, types.len());
+    }
+
+    // ===========================================
+    // STAGE 4 & 5: Semantic Scholar & Unified Dataset
+    // ===========================================
+    /// Stages 4-5 (the unified dataset is built even without Semantic Scholar data)
+    async fn unify(&self, result_list: &[Paper], report: &mut PipelineReport) -> Result<Vec<Paper>> {
+        let ss_results = if self.stages.contains(4) {
+            raw_archive::in_stage("4_semanticscholar", self.semantic_scholar(result_list)).await?
+        } else {
+            status!(self, "\n--- Stage 4: Skipped ---");
+            Vec::new()
+        };
+
+        status!(self, "\n--- Stage 5: Creating Unified Dataset ---");
+
+        let mut unified_results = unified::generate_unified(result_list, &ss_results);
+        report.prisma.no_doi_excluded = result_list.len() - unified_results.len();
+        self.enrich(&mut unified_results).await?;
+        self.detect_reviews(&mut unified_results, report).await;
+        let mut unified_results = self.apply_review_policy(unified_results, report);
+        self.download_pdfs(&mut unified_results).await?;
+        self.extract_fulltext(&unified_results).await?;
+
+        // Save Stage 5 CSV
+        let rows: Vec<UnifiedRow> = unified_results.iter().map(UnifiedRow::from).collect();
+        self.save_csv(&self.output_folder.join("5_unified.csv"), &rows)?;
+        status!(self, "Created unified dataset: {} papers", unified_results.len());
+        self.save_report(&unified_results, &[])?;
+
+        Ok(unified_results)
+    }
+
+    /// Run the configured enrichers in order, saving which fields each set
+    /// to `5_provenance.csv` (a failing enricher is reported and skipped)
+    async fn enrich(&self, unified_results: &mut [Paper]) -> Result<()> {
+        if self.enrichers.is_empty() || unified_results.is_empty() {
+            return Ok(());
+        }
+        let mut provenance: Vec<Provenance> = Vec::new();
+        for enricher in &self.enrichers {
+            status!(self, "Enriching {} papers with {}...", unified_results.len(), enricher.name());
+            match enricher.enrich_all(unified_results).await {
+                Ok(changed) => {
+                    status!(self, "{}: filled fields of {} papers", enricher.name(), changed.len());
+                    provenance.extend(changed);
+                }
+                Err(e) => {
+                    status!(self, "{} enrichment failed: {} (continuing without it)", enricher.name(), e);
+                    self.failures.record("unified", enricher.name(), &e);
+                }
+            }
+        }
+        let missing = unified_results.iter().filter(|p| p.abstract_text.trim().is_empty()).count();
+        if missing > 0 {
+            status!(self, "{} papers still have no abstract", missing);
+        }
+        self.save_csv(&self.output_folder.join("5_provenance.csv"), &provenance)
+    }
+
+    /// Set `is_review` from the title, abstract and type cues, asking the
+    /// LLM about inconclusive papers when reviews are excluded or selected
+    async fn detect_reviews(&self, unified_results: &mut [Paper], report: &mut PipelineReport) {
+        let mut uncertain = Vec::new();
+        for (idx, paper) in unified_results.iter_mut().enumerate() {
+            let verdict = review_detection::heuristic(paper);
+            paper.is_review = Some(verdict == Verdict::Review);
+            if verdict == Verdict::Uncertain {
+                uncertain.push(idx);
+            }
+        }
+        let Ok(llm_config) = &self.llm else {
+            return;
+        };
+        if self.reviews == ReviewPolicy::Keep || uncertain.is_empty() {
+            return;
+        }
+
+        status!(self, "Asking the LLM whether {} papers with inconclusive cues are reviews...", uncertain.len());
+        let papers: Vec<Paper> = uncertain.iter().map(|&idx| unified_results[idx].clone()).collect();
+        let llm_input = self.llm_input(&papers);
+        let llm_papers: Vec<&Paper> = llm_input.iter().collect();
+        match review_detection::classify(llm_config, &llm_papers).await {
+            Ok((verdicts, usage)) => {
+                report.token_usage.add(&usage);
+                for (&idx, verdict) in uncertain.iter().zip(verdicts) {
+                    match verdict {
+                        Some(is_review) => unified_results[idx].is_review = Some(is_review),
+                        None => self.failures.record("reviews", &unified_results[idx].title, "LLM review detection failed"),
+                    }
+                }
+            }
+            Err(e) => {
+                status!(self, "LLM review detection failed: {} (keeping the heuristic verdicts)", e);
+                self.failures.record("reviews", "", &e);
+            }
+        }
+    }
+
+    /// Drop review articles (`--exclude-reviews`) or everything else
+    /// (`--only-reviews`)
+    fn apply_review_policy(&self, unified_results: Vec<Paper>, report: &mut PipelineReport) -> Vec<Paper> {
+        let reviews = unified_results.iter().filter(|p| p.is_review == Some(true)).count();
+        if self.reviews == ReviewPolicy::Keep {
+            status!(self, "Detected {} review articles", reviews);
+            return unified_results;
+        }
+        let total = unified_results.len();
+        let kept: Vec<Paper> = unified_results.into_iter().filter(|p| self.reviews.keeps(p.is_review)).collect();
+        report.prisma.review_excluded = total - kept.len();
+        let removed = if self.reviews == ReviewPolicy::Exclude { "review articles" } else { "papers that are not reviews" };
+        status!(self, "Detected {} review articles; removed {} {}", reviews, total - kept.len(), removed);
+        kept
+    }
+
+    /// Download open-access PDFs, recording each file in `pdf_path`
+    async fn download_pdfs(&self, unified_results: &mut [Paper]) -> Result<()> {
+        let Some(downloader) = &self.pdfs else {
+            return Ok(());
+        };
+        status!(self, "Downloading open-access PDFs into {}/...", PDF_FOLDER);
+        let summary = downloader.download_all(unified_results, &self.output_folder).await?;
+        status!(
+            self,
+            "PDFs: {} downloaded, {} already present, {} without an open-access copy, {} failed",
+            summary.downloaded,
+            summary.existing,
+            summary.unavailable,
+            summary.failed.len()
+        );
+        for (doi, error) in &summary.failed {
+            self.failures.record("unified", doi, error);
+        }
+        Ok(())
+    }
+
+    /// Extract the text, references and affiliations of the downloaded PDFs
+    async fn extract_fulltext(&self, unified_results: &[Paper]) -> Result<()> {
+        let Some(grobid) = &self.grobid else {
+            return Ok(());
+        };
+        status!(self, "Extracting full text into {}/...", FULLTEXT_FOLDER);
+        let extraction = fulltext::extract_all(grobid, unified_results, &self.output_folder).await?;
+        status!(
+            self,
+            "Full text: {} extracted, {} already present, {} failed",
+            extraction.extracted,
+            extraction.existing,
+            extraction.failed.len()
+        );
+        for (doi, error) in &extraction.failed {
+            self.failures.record("unified", doi, error);
+        }
+
+        let references = extraction.reference_rows();
+        self.save_csv(&self.output_folder.join("5_references.csv"), &references)?;
+        self.save_csv(&self.output_folder.join("5_affiliations.csv"), &extraction.affiliation_rows())?;
+        status!(
+            self,
+            "Saved {} references ({} with a DOI) of {} papers to 5_references.csv",
+            references.len(),
+            references.iter().filter(|r| !r.doi.is_empty()).count(),
+            extraction.documents.len()
+        );
+        Ok(())
+    }
+
+    /// Papers left for the LLM (Stage 6), and irrelevant verdicts for the
+    /// papers the keyword pre-filter rules out
+    fn prefilter_papers<'a>(&self, papers: &'a [Paper]) -> (std::borrow::Cow<'a, [Paper]>, Vec<(usize, FilterResult)>) {
+        if self.prefilter.is_empty() {
+            return (std::borrow::Cow::Borrowed(papers), Vec::new());
+        }
+        let (kept, excluded) = self.prefilter.split(papers);
+        status!(
+            self,
+            "Keyword pre-filter: {} of {} papers labeled irrelevant without an LLM call",
+            excluded.len(),
+            papers.len()
+        );
+        (std::borrow::Cow::Owned(kept), excluded)
+    }
+
+    /// Papers as the LLM stages see them: with a full-text excerpt where the
+    /// abstract is missing or short (`--llm-fulltext`)
+    fn llm_input<'a>(&self, papers: &'a [Paper]) -> std::borrow::Cow<'a, [Paper]> {
+        let Some(max_chars) = self.llm_fulltext else {
+            return std::borrow::Cow::Borrowed(papers);
+        };
+        let mut with_text = 0;
+        let papers: Vec<Paper> = papers
+            .iter()
+            .map(|paper| {
+                let mut paper = paper.clone();
+                if let Some(excerpt) = fulltext::excerpt(&paper, &self.output_folder, max_chars) {
+                    paper.fulltext = excerpt;
+                    with_text += 1;
+                }
+                paper
+            })
+            .collect();
+        status!(self, "Adding full-text excerpts to {} papers with a short or missing abstract", with_text);
+        std::borrow::Cow::Owned(papers)
+    }
+
+    /// Stage 4: Semantic Scholar lookup by DOI, then by title for the papers
+    /// not found (empty when unavailable)
+    async fn semantic_scholar(&self, result_list: &[Paper]) -> Result<Vec<SemanticScholarResult>> {
+        status!(self, "\n--- Stage 4: Semantic Scholar Lookup ---");
+
+        // Extract DOIs from result_list
+        let dois: Vec<String> = result_list
+            .iter()
+            .map(|r| r.doi.clone())
+            .filter(|d| !d.is_empty())
+            .collect();
+
+        if dois.is_empty() {
+            status!(self, "No DOIs found in filtered results, skipping Semantic Scholar.");
+            return Ok(Vec::new());
+        }
+        status!(self, "Looking up {} papers by DOI...", dois.len());
+
+        let client = self.s2_client(self.lookup_cache_ttl);
+        let mut ss_results = match client.batch_lookup(&dois).await {
+            Ok(ss_results) => ss_results,
+            Err(e) => {
+                status!(self, "Semantic Scholar lookup failed: {} (continuing without it)", e);
+                self.failures.record("semanticscholar", "", &e);
+                return Ok(Vec::new());
+            }
+        };
+        status!(self, "Found {} papers in Semantic Scholar.", ss_results.len());
+
+        // Papers S2 does not know by DOI are searched by title
+        let mut found: HashSet<String> = ss_results.iter().map(|r| doi::normalize(&r.doi)).collect();
+        let missing: Vec<(String, String)> = result_list
+            .iter()
+            .filter(|r| !r.doi.is_empty() && !r.title.is_empty() && !found.contains(&doi::normalize(&r.doi)))
+            .map(|r| (doi::normalize(&r.doi), r.title.clone()))
+            .collect();
+        if !missing.is_empty() {
+            status!(self, "Searching {} papers by title...", missing.len());
+            match client.match_titles(&missing, &self.title_matcher).await {
+                Ok(matched) => {
+                    status!(self, "Matched {} papers by title.", matched.len());
+                    found.extend(matched.iter().map(|r| r.doi.clone()));
+                    ss_results.extend(matched);
+                }
+                Err(e) => status!(self, "Semantic Scholar title search failed: {}", e),
+            }
+        }
+        for doi in dois.iter().filter(|d| !found.contains(&doi::normalize(d))) {
+            self.failures.record("semanticscholar", doi, "Not found in Semantic Scholar");
+        }
+
+        // Save Stage 4 CSV with DOI as key for cross-filtering
+        self.save_csv(&self.output_folder.join("4_semanticscholar.csv"), &ss_results)?;
+        Ok(ss_results)
+    }
+
+    // ===========================================
+    // STAGE 6: LLM Relevance Filtering
+    // ===========================================
+    /// Stage 6 verdicts; `None` when filtering failed
+    async fn screen(
+        &self,
+        llm_config: &LlmConfig,
+        unified_results: &[Paper],
+        report: &mut PipelineReport,
+    ) -> Result<Option<Vec<FilterResult>>> {
+        status!(self, "\n--- Stage 6: LLM Relevance Filtering ---");
+
+        let (to_screen, prefiltered) = self.prefilter_papers(unified_results);
+        status!(
+            self,
+            "Filtering {} papers with {} (max {} concurrent requests)...",
+            to_screen.len(),
+            llm_config.model,
+            llm_config.concurrency
+        );
+
+        let llm_input = self.llm_input(&to_screen);
+        let papers = llm_input.as_ref();
+
+        // Results are appended as they complete; --resume picks them up
+        let checkpoint = match self.criteria {
+            Some(_) => None,
+            None => Some(llm_filter::open_checkpoint(&self.output_folder.join("6_llm_checkpoint.jsonl"))?),
+        };
+        if let Some(resumed) = checkpoint.as_ref().map(|c| c.len()).filter(|&n| n > 0) {
+            status!(self, "Resuming: {} papers already classified in checkpoint", resumed);
+        }
+
+        let llm_outcome = match (&self.criteria, &self.review_model) {
+            _ if papers.is_empty() => Ok((Vec::new(), TokenUsage::default(), Vec::new())),
+            (Some(criteria), _) => self.run_screening(llm_config, criteria, papers).await,
+            (None, Some(review_model)) => self.run_two_pass(llm_config, review_model, papers, checkpoint.as_ref()).await,
+            (None, None) => llm_filter::filter_papers(llm_config, papers, checkpoint.as_ref()).await,
+        };
+
+        let (filter_results, usage, details) = match llm_outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                status!(self, "LLM filtering failed: {}", e);
+                self.failures.record("llm", "", &e);
+                return Ok(None);
+            }
+        };
+        for detail in details.iter().filter(|d| d.status != "ok") {
+            self.failures.record("llm", &detail.id, &detail.status);
+        }
+
+        report.prisma.prefilter_excluded = prefiltered.len();
+        let filter_results = prefilter::merge(filter_results, prefiltered);
+
+        // Save filtered results
+        self.save_csv(&self.output_folder.join("6_llm_filtered.csv"), &filter_results)?;
+        self.save_report(unified_results, &filter_results)?;
+
+        // Count by label
+        let relevant = filter_results.iter().filter(|r| r.label == "relevant").count();
+        let irrelevant = filter_results.iter().filter(|r| r.label == "irrelevant").count();
+        let uncertain = filter_results.iter().filter(|r| r.label == "uncertain").count();
+
+        status!(
+            self,
+            "LLM filtering complete: {} relevant, {} irrelevant, {} uncertain",
+            relevant,
+            irrelevant,
+            uncertain
+        );
+
+        // Log token usage
+        let usage_line = format!(
+            "{},{},{},{}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens
+        );
+        std::fs::write(self.output_folder.join("6_token_usage.log"), &usage_line)?;
+
+        // Per-paper tokens and latency
+        self.save_csv(&self.output_folder.join("6_llm_details.csv"), &details)?;
+        if let Some(slowest) = details.iter().max_by_key(|d| d.latency_ms) {
+            status!(
+                self,
+                "Slowest request: {:.1}s ({} tokens) - {}",
+                slowest.latency_ms as f64 / 1000.0,
+                slowest.total_tokens,
+                slowest.title.chars().take(60).collect::<String>()
+            );
+        }
+        status!(
+            self,
+            "Token usage: {} prompt + {} completion = {} total",
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens
+        );
+        if usage.reasoning_tokens > 0 {
+            status!(self, "  (including {} reasoning tokens)", usage.reasoning_tokens);
+        }
+        report.token_usage.add(&usage);
+
+        Ok(Some(filter_results))
+    }
+
+    /// Two-pass relevance filtering (Stage 6 triage mode)
+    ///
+    /// Saves both verdicts to `6_llm_triage.csv` and returns the final verdicts.
+    async fn run_two_pass(
+        &self,
+        llm_config: &LlmConfig,
+        review_model: &str,
+        papers: &[Paper],
+        checkpoint: Option<&JsonlCheckpoint<FilterResult>>,
+    ) -> Result<(Vec<FilterResult>, TokenUsage, Vec<LlmCallDetail>)> {
+        let (results, usage, details, records) =
+            llm_filter::filter_papers_two_pass(llm_config, review_model, self.review_below, papers, checkpoint).await?;
+
+        let escalated = records.iter().filter(|r| r.escalated).count();
+        status!(
+            self,
+            "Triage with {}: {} of {} papers re-screened by {}",
+            llm_config.model,
+            escalated,
+            records.len(),
+            review_model
+        );
+        self.save_csv(&self.output_folder.join("6_llm_triage.csv"), &records)?;
+
+        Ok((results, usage, details))
+    }
+
+    /// Criteria screening (Stage 6 screening mode)
+    ///
+    /// Saves per-criterion verdicts to `6_llm_screening.csv` and returns the
+    /// decisions as relevance results so Stage 7 works unchanged.
+    async fn run_screening(
+        &self,
+        llm_config: &LlmConfig,
+        criteria: &Criteria,
+        papers: &[Paper],
+    ) -> Result<(Vec<FilterResult>, TokenUsage, Vec<LlmCallDetail>)> {
+        status!(self, "Screening against {} criteria", criteria.criteria.len());
+
+        let (results, usage, details) = screening::screen_papers(llm_config, criteria, papers).await?;
+
+        let rows: Vec<Vec<String>> = results.iter().map(|r| r.csv_row(criteria)).collect();
+        self.save_rows(
+            &self.output_folder.join("6_llm_screening.csv"),
+            &screening::ScreeningResult::csv_header(criteria),
+            &rows,
+        )?;
+
+        Ok((results.iter().map(|r| r.to_filter_result()).collect(), usage, details))
+    }
+
+    // ===========================================
+    // STAGE 7: Relevant Papers Only
+    // ===========================================
+    async fn select(
+        &self,
+        llm_config: &LlmConfig,
+        unified_results: &[Paper],
+        filter_results: &[FilterResult],
+        state: &mut RunState,
+        report: &mut PipelineReport,
+    ) -> Result<()> {
+        status!(self, "\n--- Stage 7: Extracting Relevant Papers ---");
+
+        // Join filter_results with unified_results (by DOI, else by title),
+        // then route each verdict by label, confidence and --uncertain-policy
+        let verdicts = llm_filter::VerdictIndex::new(filter_results);
+        let joined: Vec<(&Paper, &FilterResult)> =
+            unified_results.iter().filter_map(|u| verdicts.get(u).map(|f| (u, f))).collect();
+        let selected = |selection: llm_filter::Selection| -> Vec<(&Paper, &FilterResult)> {
+            joined
+                .iter()
+                .filter(|(_, f)| llm_filter::select(f, self.min_confidence, self.uncertain_policy) == selection)
+                .copied()
+                .collect()
+        };
+        let included = selected(llm_filter::Selection::Include);
+        let for_review = selected(llm_filter::Selection::Review);
+        report.prisma.set_screening(filter_results.len(), included.len(), for_review.len());
+
+        let uncertain: Vec<RelevantPaper> =
+            joined.iter().filter(|(_, f)| f.label == "uncertain").map(|(u, f)| RelevantPaper::new(u, f)).collect();
+        if !uncertain.is_empty() {
+            self.save_csv(&self.output_folder.join("7_uncertain.csv"), &uncertain)?;
+            status!(self, "Saved {} uncertain papers to 7_uncertain.csv", uncertain.len());
+        }
+
+        if !for_review.is_empty() {
+            let review_output: Vec<RelevantPaper> = for_review.iter().map(|(u, f)| RelevantPaper::new(u, f)).collect();
+            self.save_csv(&self.output_folder.join("7_review.csv"), &review_output)?;
+            status!(self, "Saved {} papers for manual review to 7_review.csv", review_output.len());
+        }
+
+        let relevant_papers: Vec<&Paper> = included.iter().map(|(u, _)| *u).collect();
+        if relevant_papers.is_empty() {
+            status!(self, "No relevant papers found.");
+            return Ok(());
+        }
+
+        let mut relevant_output: Vec<RelevantPaper> = included.iter().map(|(u, f)| RelevantPaper::new(u, f)).collect();
+        self.save_csv(&self.output_folder.join("7_relevant.csv"), &relevant_output)?;
+        status!(self, "Saved {} relevant papers to 7_relevant.csv", relevant_output.len());
+
+        let records: Vec<export::ExportRecord> = relevant_papers.iter().map(|u| export::ExportRecord::from(*u)).collect();
+        std::fs::write(self.output_folder.join("7_relevant.ris"), export::to_ris(&records))?;
+        status!(self, "Saved RIS export to 7_relevant.ris");
+        self.save_digest(&relevant_papers, &HashMap::new())?;
+
+        match &self.zotero {
+            // Papers were already pushed before the run was resumed
+            Some(_) if state.is_completed(Stage::Relevant) => status!(self, "Zotero: already pushed, skipping"),
+            Some((client, collection)) => match client.push_papers(collection, &relevant_papers).await {
+                Ok(summary) => {
+                    status!(
+                        self,
+                        "Pushed {} papers ({} PDF links) to Zotero collection \"{}\"{}",
+                        summary.created,
+                        summary.attachments,
+                        collection,
+                        if summary.failed > 0 { format!(", {} failed", summary.failed) } else { String::new() }
+                    );
+                    if summary.failed > 0 {
+                        self.failures.record("relevant", collection, format!("{} papers failed to push to Zotero", summary.failed));
+                    }
+                }
+                Err(e) => {
+                    status!(self, "Zotero push failed: {}", e);
+                    self.failures.record("relevant", collection, &e);
+                }
+            },
+            None => {}
+        }
+        self.checkpoint(state, Stage::Relevant, &(), report)?;
+
+        if !self.study_designs.is_empty() {
+            if self.stages.contains(8) {
+                let designs = self.classify_designs(llm_config, &relevant_papers, &mut relevant_output, state, report);
+                raw_archive::in_stage("8_study_design", designs).await?;
+            } else {
+                status!(self, "\n--- Stage 8: Skipped ---");
+            }
+        }
+        if !self.tags.is_empty() {
+            if self.stages.contains(8) {
+                let tagging = self.tag(llm_config, &relevant_papers, &mut relevant_output, state, report);
+                raw_archive::in_stage("8_tagging", tagging).await?;
+            } else {
+                status!(self, "\n--- Stage 8: Skipped ---");
+            }
+        }
+
+        report.relevant = relevant_papers.into_iter().cloned().collect();
+        Ok(())
+    }
+
+    // ===========================================
+    // STAGE 8: Study Design Classification
+    // ===========================================
+    async fn classify_designs(
+        &self,
+        llm_config: &LlmConfig,
+        relevant_papers: &[&Paper],
+        relevant_output: &mut [RelevantPaper],
+        state: &mut RunState,
+        report: &mut PipelineReport,
+    ) -> Result<()> {
+        let designs = &self.study_designs;
+        let design_map: HashMap<String, String> = match self.resumed(state, Stage::StudyDesign, "Stage 8")? {
+            Some(design_map) => design_map,
+            None => {
+                status!(self, "\n--- Stage 8: Study Design Classification ({} designs) ---", designs.len());
+
+                let papers: Vec<Paper> = relevant_papers.iter().map(|p| (*p).clone()).collect();
+                let llm_input = self.llm_input(&papers);
+                let llm_papers: Vec<&Paper> = llm_input.iter().collect();
+                let (design_results, usage) = match study_design::classify_papers(llm_config, designs, &llm_papers).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        status!(self, "Study design classification failed: {}", e);
+                        self.failures.record("tagging", "", &e);
+                        return Ok(());
+                    }
+                };
+                status!(
+                    self,
+                    "Study design token usage: {} prompt + {} completion = {} total",
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                    usage.total_tokens
+                );
+                report.token_usage.add(&usage);
+
+                let design_map = design_results.into_iter().map(|d| (doi::normalize(&d.id), d.design)).collect();
+                self.checkpoint(state, Stage::StudyDesign, &design_map, report)?;
+                design_map
+            }
+        };
+
+        for paper in relevant_output.iter_mut() {
+            paper.study_design = Some(design_map.get(&doi::normalize(&paper.doi)).cloned().unwrap_or_default());
+        }
+        for design in designs {
+            let count = relevant_output.iter().filter(|p| p.study_design.as_ref() == Some(design)).count();
+            status!(self, "  {}: {} papers", design, count);
+        }
+        let unclear = relevant_output.iter().filter(|p| p.study_design.as_ref().is_some_and(String::is_empty)).count();
+        if unclear > 0 {
+            status!(self, "  (unclear): {} papers", unclear);
+        }
+        self.save_csv(&self.output_folder.join("8_study_design.csv"), relevant_output)
+    }
+
+    // ===========================================
+    // STAGE 8: Topic Tagging
+    // ===========================================
+    async fn tag(
+        &self,
+        llm_config: &LlmConfig,
+        relevant_papers: &[&Paper],
+        relevant_output: &mut [RelevantPaper],
+        state: &mut RunState,
+        report: &mut PipelineReport,
+    ) -> Result<()> {
+        let categories = &self.tags;
+        let tag_map: HashMap<String, Vec<String>> = match self.resumed(state, Stage::Tagging, "Stage 8")? {
+            Some(tag_map) => tag_map,
+            None => {
+                status!(self, "\n--- Stage 8: Topic Tagging ({} categories) ---", categories.len());
+
+                let papers: Vec<Paper> = relevant_papers.iter().map(|p| (*p).clone()).collect();
+                let llm_input = self.llm_input(&papers);
+                let llm_papers: Vec<&Paper> = llm_input.iter().collect();
+                let (tag_results, usage) = match tagging::tag_papers(llm_config, categories, &llm_papers).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        status!(self, "Topic tagging failed: {}", e);
+                        self.failures.record("tagging", "", &e);
+                        return Ok(());
+                    }
+                };
+                status!(
+                    self,
+                    "Tagging token usage: {} prompt + {} completion = {} total",
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                    usage.total_tokens
+                );
+                report.token_usage.add(&usage);
+
+                let tag_map = tag_results.into_iter().map(|t| (doi::normalize(&t.id), t.tags)).collect();
+                self.checkpoint(state, Stage::Tagging, &tag_map, report)?;
+                tag_map
+            }
+        };
+        let paper_tags = |d: &str| tag_map.get(&doi::normalize(d));
+
+        for paper in relevant_output.iter_mut() {
+            paper.tags = Some(paper_tags(&paper.doi).map(|t| t.join("; ")).unwrap_or_default());
+        }
+        self.save_csv(&self.output_folder.join("8_tagged.csv"), relevant_output)?;
+        self.save_digest(relevant_papers, &tag_map)?;
+
+        for category in categories {
+            let subset: Vec<&RelevantPaper> = relevant_output
+                .iter()
+                .filter(|p| paper_tags(&p.doi).is_some_and(|t| t.contains(category)))
+                .collect();
+            status!(self, "  {}: {} papers", category, subset.len());
+            let tag_path = self.output_folder.join(format!("8_tag_{}.csv", tagging::file_slug(category)));
+            self.save_csv(&tag_path, &subset)?;
+        }
+        Ok(())
+    }
+
+    // ===========================================
+    // Run outputs
+    // ===========================================
+
+    /// PRISMA counts, failures, run database, workbook, and the final table
+    fn finish(&self, report: &mut PipelineReport) -> Result<()> {
+        self.save_prisma(&report.prisma)?;
+        self.save_failures(report)?;
+
+        if let Some(previous) = &self.since {
+            let relevant = report.prisma.included.map(|_| report.relevant.as_slice());
+            let path = self.output_folder.join(incremental::DELTA_FILE);
+            std::fs::write(&path, incremental::render_delta(previous, &report.prisma, &report.unified, relevant))?;
+            status!(self, "Saved: {:?}", path);
+        }
+
+        #[cfg(feature = "sqlite")]
+        if self.output_format == OutputFormat::Sqlite {
+            let db_path = sqlite::write_run_database(&self.output_folder)?;
+            status!(self, "Saved: {:?}", db_path);
+        }
+
+        if self.xlsx {
+            self.save_workbook()?;
+        }
+
+        report.final_table = FINAL_STAGE_FILES
+            .iter()
+            .map(|stem| self.output_folder.join(stem).with_extension(self.output_format.extension()))
+            .find(|p| p.exists());
+        Ok(())
+    }
+
+    /// Write `errors.csv` and print the per-stage failure summary
+    fn save_failures(&self, report: &mut PipelineReport) -> Result<()> {
+        report.failures = self.failures.take();
+        let path = failures::save(&self.output_folder, &report.failures)?;
+        if report.failures.is_empty() {
+            status!(self, "\nNo per-item failures");
+            return Ok(());
+        }
+        status!(self, "\n{} per-item failures (details in {:?}):", report.failures.len(), path);
+        status!(self, "{}", failures::summary(&report.failures).trim_end());
+        Ok(())
+    }
+
+    /// Streamed table for a stage file in the configured output format
+    fn table_writer(&self, file: &str) -> TableWriter {
+        TableWriter::new(&self.output_folder.join(file), self.output_format)
+    }
+
+    /// Complete a streamed stage table
+    fn finish_table(&self, table: TableWriter) -> Result<()> {
+        if let Some(path) = table.finish()? {
+            status!(self, "Saved: {:?}", path);
+        }
+        Ok(())
+    }
+
+    /// Save papers of Stages 1-3 with their full ranking columns
+    fn save_records(&self, path: &Path, papers: &[Paper]) -> Result<()> {
+        let rows: Vec<RecordRow> = papers.iter().map(RecordRow::from).collect();
+        self.save_csv(path, &rows)
+    }
+
+    /// Save a stage table in the configured output format
+    fn save_csv<T: Serialize>(&self, path: &Path, data: &[T]) -> Result<()> {
+        if data.is_empty() {
+            status!(self, "No data to save to {:?}", path);
+            return Ok(());
+        }
+
+        // Serialize through an in-memory CSV so every format shares serde's column layout
+        let (header, rows) = output::serialize_rows(data)?;
+        self.save_rows(path, &header, &rows)
+    }
+
+    /// Save rows with a dynamic header in the configured output format
+    fn save_rows(&self, path: &Path, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+        if rows.is_empty() {
+            status!(self, "No data to save to {:?}", path);
+            return Ok(());
+        }
+
+        let path = output::write_table(path, self.output_format, header, rows)?;
+        status!(self, "Saved: {:?}", path);
+        Ok(())
+    }
+
+    /// Collect every stage CSV in the run folder into results.xlsx, one sheet per file
+    fn save_workbook(&self) -> Result<()> {
+        let mut csv_paths: Vec<PathBuf> = std::fs::read_dir(&self.output_folder)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
+            .collect();
+        csv_paths.sort();
+
+        let sheets = csv_paths
+            .iter()
+            .map(|p| {
+                let name = p.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+                xlsx::Sheet::from_csv(&name, p)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if sheets.is_empty() {
+            status!(self, "No CSVs to collect into results.xlsx");
+            return Ok(());
+        }
+
+        let path = self.output_folder.join("results.xlsx");
+        xlsx::write_workbook(&path, &sheets)?;
+        status!(self, "Saved: {:?} ({} sheets)", path, sheets.len());
+        Ok(())
+    }
+
+    /// Write the shareable HTML report, replacing any earlier one in the run folder
+    fn save_report(&self, papers: &[Paper], verdicts: &[FilterResult]) -> Result<()> {
+        let path = self.output_folder.join(report::REPORT_FILE);
+        std::fs::write(&path, report::render_html(&self.keyword, papers, verdicts)?)?;
+        status!(self, "Saved: {:?}", path);
+        Ok(())
+    }
+
+    /// Write the Markdown digest of relevant papers, replacing any earlier one
+    fn save_digest(&self, papers: &[&Paper], tags: &HashMap<String, Vec<String>>) -> Result<()> {
+        let path = self.output_folder.join(report::DIGEST_FILE);
+        std::fs::write(&path, report::render_markdown(&self.keyword, papers, tags))?;
+        status!(self, "Saved: {:?}", path);
+        Ok(())
+    }
+
+    /// Write the PRISMA counts (prisma.json, prisma.md and optionally prisma.svg)
+    fn save_prisma(&self, counts: &PrismaCounts) -> Result<()> {
+        let mut files = vec![
+            (prisma::JSON_FILE, serde_json::to_string_pretty(counts)?),
+            (prisma::MARKDOWN_FILE, counts.to_markdown()),
+        ];
+        if self.prisma_svg {
+            files.push((prisma::SVG_FILE, counts.to_svg()));
+        }
+        for (name, contents) in files {
+            std::fs::write(self.output_folder.join(name), contents)?;
+        }
+        status!(
+            self,
+            "PRISMA: {} identified, {} removed before screening, {} included",
+            counts.identified,
+            counts.removed_before_screening(),
+            counts.included.map_or_else(|| "-".to_string(), |n| n.to_string())
+        );
+        Ok(())
+    }
+}
+
+/// Row of the Stage 1-3 tables (`2_crossref.csv`, `3_easyscholar.csv`)
+#[derive(Serialize)]
+struct RecordRow {
+    title: String,
+    author: String,
+    institutions: String,
+    countries: String,
+    year: Option<u32>,
+    publication_date: String,
+    venue: String,
+    article_url: String,
+    citations: Option<u64>,
+    snippet: String,
+    doi: String,
+    journal: String,
+    venue_type: String,
+    work_type: String,
+    issn: String,
+    abstract_text: String,
+    if_score: String,
+    jci_score: String,
+    sci_partition: String,
+    sci_up_top: String,
+    sci_base: String,
+    sci_up: String,
+    sciif5: String,
+    ssci: String,
+    esi: String,
+    sci_warn: String,
+    zky: String,
+    pku: String,
+    cssci: String,
+    cscd: String,
+    eii: String,
+    ccf: String,
+    core: String,
+    funders: String,
+    grants: String,
+    sources: String,
+}
+
+impl From<&Paper> for RecordRow {
+    fn from(paper: &Paper) -> Self {
+        let metrics = paper.rankings.clone().unwrap_or_default();
+        Self {
+            title: paper.title.clone(),
+            author: paper.author_list(),
+            institutions: paper.institutions.join("; "),
+            countries: paper.countries.join("; "),
+            year: paper.year,
+            publication_date: paper.date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+            venue: paper.venue.clone(),
+            article_url: paper.article_url.clone(),
+            citations: paper.citations,
+            snippet: paper.snippet.clone(),
+            doi: paper.doi.clone(),
+            journal: paper.journal.clone(),
+            venue_type: paper.venue_type.to_string(),
+            work_type: paper.work_type.to_string(),
+            issn: paper.issn.clone(),
+            abstract_text: paper.abstract_text.clone(),
+            if_score: metrics.sciif.unwrap_or_default(),
+            jci_score: metrics.jci.unwrap_or_default(),
+            sci_partition: metrics.sci.unwrap_or_default(),
+            sci_up_top: metrics.sci_up_top.unwrap_or_default(),
+            sci_base: metrics.sci_base.unwrap_or_default(),
+            sci_up: metrics.sci_up.unwrap_or_default(),
+            sciif5: metrics.sciif5.unwrap_or_default(),
+            ssci: metrics.ssci.unwrap_or_default(),
+            esi: metrics.esi.unwrap_or_default(),
+            sci_warn: metrics.sci_warn.unwrap_or_default(),
+            zky: metrics.zky.unwrap_or_default(),
+            pku: metrics.pku.unwrap_or_default(),
+            cssci: metrics.cssci.unwrap_or_default(),
+            cscd: metrics.cscd.unwrap_or_default(),
+            eii: metrics.eii.unwrap_or_default(),
+            ccf: metrics.ccf.unwrap_or_default(),
+            core: metrics.core.unwrap_or_default(),
+            funders: paper.funders.join("; "),
+            grants: paper.grants.join("; "),
+            sources: paper.source_list(),
+        }
+    }
+}
+
+/// Journal name used for ranking lookups, else the venue as the source reported it
+fn venue_name(paper: &Paper) -> &str {
+    if paper.journal.trim().is_empty() {
+        &paper.venue
+    } else {
+        &paper.journal
+    }
+}
+
+/// Set the venue types the search sources left unknown from the venue names
+fn guess_venue_types(records: &mut [Paper]) {
+    for item in records.iter_mut().filter(|item| item.venue_type == VenueType::Unknown) {
+        item.venue_type = VenueType::guess(venue_name(item));
+    }
+}
+
+/// Unique journal names of the records, with the first ISSN seen for each
+/// (none for conferences: a proceedings ISSN is the series', e.g. LNCS)
+fn unique_journals(records: &[Paper]) -> Vec<(String, Option<String>)> {
+    let mut unique_journals: HashMap<String, Option<String>> = HashMap::new();
+    for item in records {
+        let journal = item.journal.trim();
+        if journal.is_empty() {
+            continue;
+        }
+        let issn = Some(item.issn.trim().to_string())
+            .filter(|i| !i.is_empty() && item.venue_type != VenueType::Conference);
+        let entry = unique_journals.entry(journal.to_string()).or_default();
+        if entry.is_none() {
+            *entry = issn;
+        }
+    }
+    unique_journals.into_iter().collect()
+}
+
+/// Spool of the Stage 1-2 records, `records/1_search.jsonl`
+const SEARCH_SPOOL: &str = "1_search";
+
+/// Records of the search so far, spooled in the order found
+struct Harvest {
+    budget: ResultBudget,
+    records: Spool<Paper>,
+}
+
+/// Unique records collected by the search so far, against `--max-results`
+///
+/// A record counts once however many sources find it, matched like
+/// [`merge_duplicates`] does: by DOI, or by title when either has no DOI.
+struct ResultBudget {
+    max: Option<usize>,
+    dois: HashSet<String>,
+    titles: HashSet<String>,
+    /// Titles of the counted records without a DOI
+    doiless_titles: HashSet<String>,
+    unique: usize,
+}
+
+impl ResultBudget {
+    fn new(max: Option<usize>) -> Self {
+        Self { max, dois: HashSet::new(), titles: HashSet::new(), doiless_titles: HashSet::new(), unique: 0 }
+    }
+
+    /// How many of the leading `papers` fit: all of them until the budget
+    /// is used up, duplicates of counted records included
+    fn take(&mut self, papers: &[Paper]) -> usize {
+        let Some(max) = self.max else {
+            return papers.len();
+        };
+        for (i, paper) in papers.iter().enumerate() {
+            let title = prisma::duplicate_key("", &paper.title);
+            let doi = (!paper.doi.trim().is_empty()).then(|| prisma::duplicate_key(&paper.doi, ""));
+            let seen = match &doi {
+                Some(doi) if self.dois.contains(doi) => true,
+                // The counted record takes this DOI on merging
+                Some(doi) if self.doiless_titles.remove(&title) => {
+                    self.dois.insert(doi.clone());
+                    true
+                }
+                Some(_) => false,
+                None => self.titles.contains(&title),
+            };
+            if seen {
+                continue;
+            }
+            if self.unique >= max {
+                return i;
+            }
+            self.unique += 1;
+            match doi {
+                Some(doi) => {
+                    self.dois.insert(doi);
+                }
+                None => {
+                    self.doiless_titles.insert(title.clone());
+                }
+            }
+            self.titles.insert(title);
+        }
+        papers.len()
+    }
+
+    /// Unique records counted
+    fn len(&self) -> usize {
+        self.unique
+    }
+
+    fn is_full(&self) -> bool {
+        self.max.is_some_and(|max| self.unique >= max)
+    }
+}
+
+/// Merge repeated records: the same DOI, or the same title when either one
+/// has no DOI. The first copy wins; later copies fill its gaps.
+fn merge_duplicates(records: Vec<Paper>) -> Vec<Paper> {
+    let mut merged: Vec<Paper> = Vec::new();
+    let mut by_doi: HashMap<String, usize> = HashMap::new();
+    let mut by_title: HashMap<String, usize> = HashMap::new();
+
+    for record in records {
+        let title_key = prisma::duplicate_key("", &record.title);
+        let existing = if record.doi.trim().is_empty() {
+            by_title.get(&title_key).copied()
+        } else {
+            by_doi
+                .get(&prisma::duplicate_key(&record.doi, ""))
+                .copied()
+                .or_else(|| by_title.get(&title_key).copied().filter(|&i| merged[i].doi.trim().is_empty()))
+        };
+        let i = match existing {
+            Some(i) => {
+                merged[i].absorb(record);
+                i
+            }
+            None => {
+                merged.push(record);
+                merged.len() - 1
+            }
+        };
+        if !merged[i].doi.trim().is_empty() {
+            by_doi.entry(prisma::duplicate_key(&merged[i].doi, "")).or_insert(i);
+        }
+        by_title.entry(title_key).or_insert(i);
+    }
+    merged
+}
+
+/// Join Google Scholar results with their Crossref matches (missing matches leave the Crossref fields empty)
+fn merge_crossref(gs_results: &[gscholar::ScholarResult], crossref_results: &[Option<CrossrefMetadata>]) -> Vec<Paper> {
+    gs_results
+        .iter()
+        .enumerate()
+        .map(|(i, gs)| {
+            let mut paper = Paper::from(gs.clone());
+            if let Some(cr) = crossref_results.get(i).and_then(Option::as_ref) {
+                paper.doi = cr.doi.clone();
+                paper.journal = cr.journal.clone();
+                paper.issn = cr.issn.clone();
+                paper.abstract_text = cr.abstract_text.clone();
+                paper.venue_type = VenueType::from_crossref(&cr.work_type);
+                paper.work_type = WorkType::from_crossref(&cr.work_type);
+                paper.funders = paper::parse_list(&cr.funders);
+                paper.grants = paper::parse_list(&cr.grants);
+                paper.set_date(&cr.date);
+                // Google Scholar truncates long author lists; Crossref has them all
+                if !cr.authors.trim().is_empty() {
+                    paper.set_authors(&cr.authors);
+                }
+            }
+            paper
+        })
+        .collect()
+}
+
+/// Fill gaps in user-provided records with their Crossref matches
+fn merge_input(input: &[InputRecord], crossref_results: &[Option<CrossrefMetadata>]) -> Vec<Paper> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let mut paper = Paper::from(record);
+            let Some(cr) = crossref_results.get(i).and_then(Option::as_ref) else {
+                return paper;
+            };
+            if paper.title.is_empty() {
+                paper.title = cr.crossref_title.clone();
+            }
+            if paper.authors.is_empty() {
+                paper.set_authors(&cr.authors);
+            }
+            if paper.doi.is_empty() {
+                paper.doi = cr.doi.clone();
+            }
+            paper.set_date(&cr.date);
+            // Crossref's journal name is the one EasyScholar knows best
+            if !cr.journal.is_empty() {
+                paper.journal = cr.journal.clone();
+            }
+            paper.issn = cr.issn.clone();
+            paper.abstract_text = cr.abstract_text.clone();
+            paper.venue_type = VenueType::from_crossref(&cr.work_type);
+            paper.work_type = WorkType::from_crossref(&cr.work_type);
+            if paper.funders.is_empty() {
+                paper.funders = paper::parse_list(&cr.funders);
+            }
+            if paper.grants.is_empty() {
+                paper.grants = paper::parse_list(&cr.grants);
+            }
+            paper
+        })
+        .collect()
+}
+
+/// Stage 7 output row: unified paper data joined with its LLM verdict
+#[derive(Serialize)]
+struct RelevantPaper {
+    title: String,
+    author: String,
+    institutions: String,
+    countries: String,
+    date: String,
+    doi: String,
+    article_url: String,
+    pdf_url: String,
+    pdf_path: String,
+    abstract_text: String,
+    tldr: String,
+    journal: String,
+    if_score: String,
+    jci_score: String,
+    sci_partition: String,
+    altmetric_score: String,
+    altmetric_mentions: String,
+    altmetric_news: String,
+    altmetric_policy: String,
+    funders: String,
+    grants: String,
+    sources: String,
+    label: String,
+    confidence: f64,
+    evidence: String,
+    reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    study_design: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<String>,
+}
+
+impl RelevantPaper {
+    fn new(u: &Paper, f: &FilterResult) -> Self {
+        let u = UnifiedRow::from(u);
+        Self {
+            title: u.title,
+            author: u.author,
+            institutions: u.institutions,
+            countries: u.countries,
+            date: u.date,
+            doi: u.doi,
+            article_url: u.article_url,
+            pdf_url: u.pdf_url,
+            pdf_path: u.pdf_path,
+            abstract_text: u.abstract_text,
+            tldr: u.tldr,
+            journal: u.journal,
+            if_score: u.if_score,
+            jci_score: u.jci_score,
+            sci_partition: u.sci_partition,
+            altmetric_score: u.altmetric_score,
+            altmetric_mentions: u.altmetric_mentions,
+            altmetric_news: u.altmetric_news,
+            altmetric_policy: u.altmetric_policy,
+            funders: u.funders,
+            grants: u.grants,
+            sources: u.sources,
+            label: f.label.clone(),
+            confidence: f.confidence,
+            evidence: f.evidence.clone(),
+            reason: f.reason.clone(),
+            study_design: None,
+            tags: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() -> Result<()> {
+        let pipeline = PipelineBuilder::new("landslide", "out")
+            .source("openalex".parse()?)
+            .pages(vec![1, 2])
+            .build();
+        assert_eq!(pipeline.sources, vec![Source::OpenAlex]);
+        assert_eq!(pipeline.pages, vec![1, 2]);
+        assert!(pipeline.ranking.is_none());
+        assert!(pipeline.llm.is_err());
+        assert_eq!(pipeline.output_format, OutputFormat::Csv);
+        assert!("scopus".parse::<Source>().is_err());
+        assert_eq!(
+            Source::parse_list("gscholar, openalex,s2,openalex")?,
+            vec![Source::GoogleScholar, Source::OpenAlex, Source::SemanticScholar]
+        );
+        assert!(Source::parse_list(" ,").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_selection() -> Result<()> {
+        let stages = StageSelection::parse_list(&["1-3".to_string(), "6,Tags".to_string()])?;
+        assert_eq!(stages, vec![1, 2, 3, 6, 8]);
+        let selection = StageSelection::only(&stages);
+        assert!(selection.contains(3) && selection.contains(8));
+        assert!(!selection.contains(4) && !selection.contains(0) && !selection.contains(9));
+
+        let skipped = StageSelection::default().skip(&StageSelection::parse_list(&["s2".to_string()])?);
+        assert!(!skipped.contains(4) && skipped.contains(5));
+        assert!(StageSelection::parse_list(&["9".to_string()]).is_err());
+        assert!(StageSelection::parse_list(&["scopus".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_duplicates() {
+        let record = |title: &str, doi: &str, source: &str, abstract_text: &str| Paper {
+            title: title.to_string(),
+            doi: doi.to_string(),
+            sources: vec![source.to_string()],
+            abstract_text: abstract_text.to_string(),
+            ..Default::default()
+        };
+        let merged = merge_duplicates(vec![
+            record("Slope stability", "", "gscholar", ""),
+            record("Slope Stability.", "10.1/a", "openalex", "Abstract"),
+            record("Other", "10.1/A", "s2", ""),
+            record("Same title", "10.1/b", "gscholar", ""),
+            record("Same title", "10.1/c", "openalex", ""),
+        ]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].doi, "10.1/a");
+        assert_eq!(merged[0].abstract_text, "Abstract");
+        assert_eq!(merged[0].source_list(), "gscholar; openalex; s2");
+        assert_eq!(merged[2].doi, "10.1/c");
+    }
+
+    #[test]
+    fn test_result_budget() {
+        let record = |title: &str, doi: &str| Paper { title: title.to_string(), doi: doi.to_string(), ..Default::default() };
+        let mut budget = ResultBudget::new(Some(2));
+        let page = vec![record("Slope stability", ""), record("Slope Stability.", "10.1/a"), record("Debris flow", "10.1/b")];
+        assert_eq!(budget.take(&page), 3);
+        assert!(budget.is_full());
+        // Duplicates of counted records still fit, new records do not
+        assert_eq!(budget.take(&[record("Other", "10.1/A"), record("Rockfall", "10.1/c"), record("Debris flow", "10.1/b")]), 1);
+        assert_eq!(merge_duplicates(page).len(), budget.len());
+
+        assert_eq!(ResultBudget::new(None).take(&[record("A", ""), record("B", "")]), 2);
+    }
+
+    #[tokio::test]
+    async fn test_forward_pages_budget_in_page_order() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let pipeline = PipelineBuilder::new("landslide", dir.path())
+            .pages(vec![1, 2])
+            .max_results(Some(2))
+            .on_progress(|_| {})
+            .build();
+        let result = |title: &str| gscholar::ScholarResult { title: title.to_string(), ..Default::default() };
+        let (page_tx, page_rx) = stream::channel();
+        // Page 2 arrives first but must not use up the budget
+        page_tx.send(Page::new(2, vec![result("Rockfall"), result("Debris flow")])).await.map_err(|e| GscholarError::Parse(e.to_string()))?;
+        page_tx.send(Page::new(1, vec![result("Landslide"), result("Slope stability")])).await.map_err(|e| GscholarError::Parse(e.to_string()))?;
+        drop(page_tx);
+
+        let (tx, mut rx) = stream::channel();
+        let mut harvest = Harvest { budget: ResultBudget::new(Some(2)), records: Spool::create(dir.path(), SEARCH_SPOOL)? };
+        let mut table = TableWriter::new(&dir.path().join("1_gscholar.csv"), OutputFormat::Csv);
+        let found = pipeline.forward_pages(Source::GoogleScholar, page_rx, &tx, &mut harvest, &mut table).await?;
+        drop(tx);
+
+        assert_eq!(found, 2);
+        let papers: Vec<Paper> = harvest.records.finish()?.read_all(dir.path())?;
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), ["Landslide", "Slope stability"]);
+        assert_eq!(papers[0].sources, ["gscholar"]);
+        let mut sent = Vec::new();
+        while let Some(page) = rx.recv().await {
+            sent.extend(page.items.into_iter().map(|p| p.title));
+        }
+        assert_eq!(sent, ["Landslide", "Slope stability"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_input() {
+        let input = vec![
+            InputRecord { title: "Own title".to_string(), doi: "10.1/a".to_string(), ..Default::default() },
+            InputRecord { doi: "10.1/b".to_string(), venue: "Landslides".to_string(), ..Default::default() },
+        ];
+        let crossref = CrossrefMetadata {
+            doi: "10.1/a".to_string(),
+            crossref_title: "Crossref title".to_string(),
+            authors: "A. Li".to_string(),
+            date: "2021-3-1".to_string(),
+            journal: "Engineering Geology".to_string(),
+            ..Default::default()
+        };
+        let merged = merge_input(&input, &[Some(crossref), None]);
+        assert_eq!(merged[0].title, "Own title");
+        assert_eq!(merged[0].author_list(), "A. Li");
+        assert_eq!(merged[0].date_string(), "2021-03-01");
+        assert_eq!(merged[0].journal, "Engineering Geology");
+        assert_eq!(merged[1].doi, "10.1/b");
+        assert_eq!(merged[1].journal, "Landslides");
+    }
+
+    #[tokio::test]
+    async fn test_resume_reuses_search() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let record = |title: &str, doi: &str| Paper {
+            title: title.to_string(),
+            doi: doi.to_string(),
+            ..Default::default()
+        };
+        let mut spool = Spool::create(dir.path(), SEARCH_SPOOL)?;
+        spool.extend(&[record("A", "10.1/a"), record("A again", "10.1/A"), record("B", "")])?;
+        let mut state = RunState::new("landslide", "OpenAlex");
+        state.complete(Stage::Search, &spool.finish()?)?;
+        state.save(dir.path())?;
+
+        // No network access: Stage 1 comes from the spool in state.json, Stage 3 is off and Stage 4 skipped
+        let report = PipelineBuilder::new("landslide", dir.path())
+            .source(Source::OpenAlex)
+            .resume(true)
+            .stages(StageSelection::default().skip(&[4]))
+            .on_progress(|_| {})
+            .build()
+            .run()
+            .await?;
+        assert_eq!((report.prisma.identified, report.prisma.duplicates_removed), (3, 1));
+        assert!(dir.path().join(prisma::JSON_FILE).exists());
+
+        let mismatch = PipelineBuilder::new("rockfall", dir.path()).source(Source::OpenAlex).resume(true).build();
+        assert!(mismatch.run().await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Keyword pre-filter ahead of Stage 6 (`--prefilter-include`, `--prefilter-exclude`).
+//!
+//! Many harvested papers are plainly off-topic: a title and abstract that
+//! never mention the subject, or that are about a homonym ("slope" in an
+//! economics paper). Those are settled by case-insensitive regular
+//! expressions over title and abstract and labeled irrelevant without an
+//! LLM call; only the remaining papers are screened by the LLM.
+
+use crate::error::{GscholarError, Result};
+use crate::llm_filter::FilterResult;
+use crate::paper::Paper;
+use regex::{Regex, RegexBuilder};
+
+/// Compiled include/exclude patterns
+#[derive(Debug, Clone, Default)]
+pub struct Prefilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl Prefilter {
+    /// Compile the patterns (regular expressions, matched case-insensitively)
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Whether no pattern is set (every paper goes to the LLM)
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Irrelevant verdict for a paper the patterns rule out; `None` when it
+    /// is left to the LLM
+    ///
+    /// A paper is ruled out when it matches an exclude pattern, or when
+    /// include patterns are set and it matches none of them.
+    pub fn check(&self, paper: &Paper) -> Option<FilterResult> {
+        let text = format!("{}\n{}", paper.title, paper.abstract_text);
+        let verdict = |evidence: &str, reason: String| FilterResult {
+            id: paper.doi.clone(),
+            title: paper.title.clone(),
+            label: "irrelevant".to_string(),
+            confidence: 1.0,
+            evidence: evidence.to_string(),
+            reason,
+        };
+        if let Some((pattern, found)) = self.exclude.iter().find_map(|re| re.find(&text).map(|m| (re, m.as_str()))) {
+            return Some(verdict(found, format!("Keyword pre-filter: matches exclude pattern /{}/", pattern)));
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(&text)) {
+            return Some(verdict("", "Keyword pre-filter: matches no include pattern".to_string()));
+        }
+        None
+    }
+
+    /// Papers left to the LLM, and the verdicts of the papers ruled out
+    /// with their positions in `papers`
+    pub fn split(&self, papers: &[Paper]) -> (Vec<Paper>, Vec<(usize, FilterResult)>) {
+        let mut kept = Vec::new();
+        let mut excluded = Vec::new();
+        for (index, paper) in papers.iter().enumerate() {
+            match self.check(paper) {
+                Some(verdict) => excluded.push((index, verdict)),
+                None => kept.push(paper.clone()),
+            }
+        }
+        (kept, excluded)
+    }
+
+    /// Human-readable summary for the manifest
+    pub fn describe(&self) -> String {
+        let list = |patterns: &[Regex]| patterns.iter().map(|re| format!("/{}/", re)).collect::<Vec<_>>().join(", ");
+        match (self.include.is_empty(), self.exclude.is_empty()) {
+            (false, false) => format!("include {}; exclude {}", list(&self.include), list(&self.exclude)),
+            (false, true) => format!("include {}", list(&self.include)),
+            _ => format!("exclude {}", list(&self.exclude)),
+        }
+    }
+}
+
+/// Verdicts in the order of the papers [`Prefilter::split`] was given: the
+/// pre-filter's at their positions, the LLM's (one per kept paper, in
+/// order) filling the rest
+pub fn merge(screened: Vec<FilterResult>, excluded: Vec<(usize, FilterResult)>) -> Vec<FilterResult> {
+    let mut merged = Vec::with_capacity(screened.len() + excluded.len());
+    let mut screened = screened.into_iter();
+    let mut excluded = excluded.into_iter().peekable();
+    loop {
+        let next = match excluded.peek() {
+            Some((index, _)) if *index <= merged.len() => excluded.next().map(|(_, verdict)| verdict),
+            _ => screened.next().or_else(|| excluded.next().map(|(_, verdict)| verdict)),
+        };
+        match next {
+            Some(verdict) => merged.push(verdict),
+            None => return merged,
+        }
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            RegexBuilder::new(p)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| GscholarError::Validation(format!("invalid pattern {:?}: {}", p, e)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_excluded() -> GscholarError {
+        GscholarError::Validation("expected the paper to be excluded".to_string())
+    }
+
+    fn paper(title: &str, abstract_text: &str) -> Paper {
+        Paper {
+            doi: "10.1/x".to_string(),
+            title: title.to_string(),
+            abstract_text: abstract_text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_include_and_exclude() -> Result<()> {
+        let prefilter = Prefilter::new(
+            &["landslide".to_string(), r"slope\s{1,3}(failure|stability)".to_string()],
+            &["stock market".to_string()],
+        )?;
+
+        assert!(prefilter.check(&paper("Landslide susceptibility mapping", "")).is_none());
+        assert!(prefilter.check(&paper("A new method", "We study Slope  Stability in Alps.")).is_none());
+
+        let excluded = prefilter.check(&paper("Landslide of the Stock Market", "")).ok_or_else(not_excluded)?;
+        assert_eq!(excluded.label, "irrelevant");
+        assert_eq!(excluded.evidence, "Stock Market");
+        assert_eq!(excluded.id, "10.1/x");
+
+        let unmatched = prefilter.check(&paper("Slope of the yield curve", "Bond pricing.")).ok_or_else(not_excluded)?;
+        assert!(unmatched.reason.contains("no include pattern"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_keeps_paper_order() -> Result<()> {
+        let prefilter = Prefilter::new(&[], &["stock market".to_string()])?;
+        let papers: Vec<Paper> = ["Stock market slopes", "Landslide inventory", "Stock market crash", "Debris flow", "Rockfall"]
+            .iter()
+            .map(|title| Paper { doi: title.to_lowercase(), ..paper(title, "") })
+            .collect();
+        let (kept, excluded) = prefilter.split(&papers);
+        assert_eq!(excluded.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 2]);
+
+        // The LLM answers for the kept papers only, in their order
+        let screened = kept
+            .iter()
+            .map(|p| FilterResult {
+                id: p.doi.clone(),
+                title: p.title.clone(),
+                label: "relevant".to_string(),
+                confidence: 0.9,
+                evidence: String::new(),
+                reason: String::new(),
+            })
+            .collect();
+        let merged = merge(screened, excluded);
+        let ids: Vec<&str> = merged.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, papers.iter().map(|p| p.doi.as_str()).collect::<Vec<_>>());
+        assert_eq!(merged.iter().map(|r| r.label.as_str()).collect::<Vec<_>>(), ["irrelevant", "relevant", "irrelevant", "relevant", "relevant"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_and_empty_patterns() -> Result<()> {
+        assert!(Prefilter::new(&["(unclosed".to_string()], &[]).is_err());
+        let prefilter = Prefilter::new(&[" ".to_string()], &[])?;
+        assert!(prefilter.is_empty());
+        assert!(prefilter.check(&paper("Anything", "")).is_none());
+        Ok(())
+    }
+}
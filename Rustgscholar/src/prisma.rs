@@ -0,0 +1,315 @@
+//! PRISMA 2020 flow-diagram counts.
+//!
+//! The search pipeline records how many papers enter and leave each stage;
+//! [`PrismaCounts`] holds those numbers and renders them as `prisma.json`,
+//! a `prisma.md` table, and optionally a `prisma.svg` flow diagram laid out
+//! like the PRISMA 2020 template (identification, screening, included, with
+//! exclusions on the right).
+
+use serde::{Deserialize, Serialize};
+
+/// JSON counts file name inside a run folder
+pub const JSON_FILE: &str = "prisma.json";
+/// Markdown table file name inside a run folder
+pub const MARKDOWN_FILE: &str = "prisma.md";
+/// SVG flow diagram file name inside a run folder
+pub const SVG_FILE: &str = "prisma.svg";
+
+/// Record counts at every pipeline stage
+///
+/// Screening counts are `None` when Stage 6 did not run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrismaCounts {
+    pub keyword: String,
+    /// Database searched (e.g., "OpenAlex")
+    pub source: String,
+    /// Records identified by the search (Stage 1)
+    pub identified: usize,
+    /// Duplicate records removed (same DOI or normalized title)
+    pub duplicates_removed: usize,
+    /// Records already seen by the previous run of an update (--since)
+    #[serde(default)]
+    pub previously_seen: usize,
+    /// Records dropped by --journal-include/--journal-exclude
+    pub journal_list_excluded: usize,
+    /// Records dropped by the ranking filter (Stage 3)
+    pub rank_excluded: usize,
+    /// Records dropped by --min-citations
+    #[serde(default)]
+    pub citation_excluded: usize,
+    /// Records dropped by --work-types
+    #[serde(default)]
+    pub work_type_excluded: usize,
+    /// Records dropped by --country
+    #[serde(default)]
+    pub country_excluded: usize,
+    /// Records dropped for having no DOI (Stage 5)
+    pub no_doi_excluded: usize,
+    /// Records dropped by --exclude-reviews/--only-reviews (Stage 5)
+    #[serde(default)]
+    pub review_excluded: usize,
+    /// Records screened by the LLM (Stage 6)
+    pub screened: Option<usize>,
+    /// Screened records excluded by the keyword pre-filter, without an LLM call
+    #[serde(default)]
+    pub prefilter_excluded: usize,
+    /// Records excluded by LLM screening
+    pub llm_excluded: Option<usize>,
+    /// Records left for manual review (--uncertain-policy review)
+    pub awaiting_review: Option<usize>,
+    /// Studies included (Stage 7)
+    pub included: Option<usize>,
+}
+
+impl PrismaCounts {
+    pub fn new(keyword: &str, source: &str) -> Self {
+        Self {
+            keyword: keyword.to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Records removed before screening, all reasons together
+    pub fn removed_before_screening(&self) -> usize {
+        self.duplicates_removed + self.previously_seen + self.journal_list_excluded
+            + self.rank_excluded
+            + self.citation_excluded
+            + self.work_type_excluded
+            + self.country_excluded
+            + self.no_doi_excluded
+            + self.review_excluded
+    }
+
+    /// Records left after duplicate and automated removals
+    pub fn after_removal(&self) -> usize {
+        self.identified.saturating_sub(self.removed_before_screening())
+    }
+
+    /// Record the Stage 6/7 outcome
+    pub fn set_screening(&mut self, screened: usize, included: usize, awaiting_review: usize) {
+        self.screened = Some(screened);
+        self.included = Some(included);
+        self.awaiting_review = Some(awaiting_review);
+        self.llm_excluded = Some(screened.saturating_sub(included + awaiting_review + self.prefilter_excluded));
+    }
+
+    /// Markdown table of the counts
+    pub fn to_markdown(&self) -> String {
+        let optional = |n: Option<usize>| n.map_or_else(|| "not performed".to_string(), |n| n.to_string());
+        let mut rows = vec![
+            (format!("Records identified from {}", self.source), self.identified.to_string()),
+            ("Duplicate records removed".to_string(), self.duplicates_removed.to_string()),
+            ("Records excluded by journal lists".to_string(), self.journal_list_excluded.to_string()),
+            ("Records excluded by ranking filter".to_string(), self.rank_excluded.to_string()),
+            ("Records removed without DOI".to_string(), self.no_doi_excluded.to_string()),
+            ("Records after removal".to_string(), self.after_removal().to_string()),
+            ("Records screened (LLM, title/abstract)".to_string(), optional(self.screened)),
+            ("Records excluded by LLM screening".to_string(), optional(self.llm_excluded)),
+            ("Records awaiting manual review".to_string(), optional(self.awaiting_review)),
+            ("Studies included in review".to_string(), optional(self.included)),
+        ];
+        if self.prefilter_excluded > 0 {
+            rows.insert(7, ("Records excluded by keyword pre-filter".to_string(), self.prefilter_excluded.to_string()));
+        }
+        if self.review_excluded > 0 {
+            rows.insert(5, ("Records excluded as (non-)review articles".to_string(), self.review_excluded.to_string()));
+        }
+        if self.citation_excluded > 0 {
+            rows.insert(4, ("Records excluded by citation count".to_string(), self.citation_excluded.to_string()));
+        }
+        if self.country_excluded > 0 {
+            rows.insert(2, ("Records excluded by author country".to_string(), self.country_excluded.to_string()));
+        }
+        if self.work_type_excluded > 0 {
+            rows.insert(2, ("Records excluded by work type".to_string(), self.work_type_excluded.to_string()));
+        }
+        if self.previously_seen > 0 {
+            rows.insert(2, ("Records in the previous run".to_string(), self.previously_seen.to_string()));
+        }
+
+        let mut out = format!("# PRISMA 2020 flow: {}\n\n| Stage | Records |\n|---|---:|\n", self.keyword);
+        for (stage, count) in rows {
+            out.push_str(&format!("| {} | {} |\n", stage, count));
+        }
+        out
+    }
+
+    /// PRISMA 2020 style flow diagram
+    pub fn to_svg(&self) -> String {
+        let mut removed = vec![
+            "Records removed before screening:".to_string(),
+            format!("Duplicate records (n = {})", self.duplicates_removed),
+            format!("Excluded by journal lists (n = {})", self.journal_list_excluded),
+            format!("Excluded by ranking filter (n = {})", self.rank_excluded),
+            format!("Without DOI (n = {})", self.no_doi_excluded),
+        ];
+        if self.review_excluded > 0 {
+            removed.push(format!("Excluded as (non-)review articles (n = {})", self.review_excluded));
+        }
+        if self.citation_excluded > 0 {
+            removed.insert(4, format!("Excluded by citation count (n = {})", self.citation_excluded));
+        }
+        if self.country_excluded > 0 {
+            removed.insert(2, format!("Excluded by author country (n = {})", self.country_excluded));
+        }
+        if self.work_type_excluded > 0 {
+            removed.insert(2, format!("Excluded by work type (n = {})", self.work_type_excluded));
+        }
+        if self.previously_seen > 0 {
+            removed.insert(2, format!("In the previous run (n = {})", self.previously_seen));
+        }
+        let mut boxes = vec![
+            FlowBox::new(40, 40, vec![format!("Records identified from {}", self.source), format!("(n = {})", self.identified)]),
+            FlowBox::new(400, 20, removed),
+        ];
+        let mut arrows = vec![(200, 100, 200, 180), (360, 70, 400, 70)];
+
+        match (self.screened, self.included) {
+            (Some(screened), Some(included)) => {
+                boxes.push(FlowBox::new(40, 180, vec!["Records screened".to_string(), format!("(n = {})", screened)]));
+                let mut excluded = vec![format!("Records excluded by LLM (n = {})", self.llm_excluded.unwrap_or_default())];
+                if self.prefilter_excluded > 0 {
+                    excluded.insert(0, format!("Excluded by keyword pre-filter (n = {})", self.prefilter_excluded));
+                }
+                if let Some(review) = self.awaiting_review.filter(|&n| n > 0) {
+                    excluded.push(format!("Awaiting manual review (n = {})", review));
+                }
+                boxes.push(FlowBox::new(400, 180, excluded));
+                boxes.push(FlowBox::new(40, 320, vec!["Studies included in review".to_string(), format!("(n = {})", included)]));
+                arrows.extend([(360, 210, 400, 210), (200, 240, 200, 320)]);
+            }
+            _ => {
+                boxes.push(FlowBox::new(40, 180, vec!["Records after removal".to_string(), format!("(n = {})", self.after_removal())]));
+                boxes.push(FlowBox::new(400, 180, vec!["LLM screening not performed".to_string()]));
+            }
+        }
+
+        let height = boxes.iter().map(|b| b.y + b.height()).max().unwrap_or_default() + 40;
+        let mut svg = format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="760" height="{}" font-family="Arial, sans-serif" font-size="13">"#,
+                "\n",
+                r#"<defs><marker id="arrow" markerWidth="10" markerHeight="10" refX="9" refY="5" orient="auto"><path d="M0,0 L10,5 L0,10 z"/></marker></defs>"#,
+                "\n"
+            ),
+            height
+        );
+        for b in &boxes {
+            svg.push_str(&b.render());
+        }
+        for (x1, y1, x2, y2) in arrows {
+            svg.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" marker-end=\"url(#arrow)\"/>\n",
+                x1, y1, x2, y2
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// One diagram box with centered lines of text
+struct FlowBox {
+    x: usize,
+    y: usize,
+    lines: Vec<String>,
+}
+
+impl FlowBox {
+    const WIDTH: usize = 320;
+    const LINE_HEIGHT: usize = 18;
+
+    fn new(x: usize, y: usize, lines: Vec<String>) -> Self {
+        Self { x, y, lines }
+    }
+
+    fn height(&self) -> usize {
+        (self.lines.len() + 1) * Self::LINE_HEIGHT
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\" stroke=\"black\"/>\n",
+            self.x,
+            self.y,
+            Self::WIDTH,
+            self.height()
+        );
+        for (i, line) in self.lines.iter().enumerate() {
+            out.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                self.x + Self::WIDTH / 2,
+                self.y + (i + 1) * Self::LINE_HEIGHT + 4,
+                escape_xml(line)
+            ));
+        }
+        out
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Key under which two records count as duplicates: the canonical DOI, or
+/// the normalized title (see [`crate::matching::title_key`]) when there is no DOI
+pub fn duplicate_key(doi: &str, title: &str) -> String {
+    let doi = crate::doi::normalize(doi);
+    if doi.is_empty() {
+        format!("title:{}", crate::matching::title_key(title))
+    } else {
+        format!("doi:{}", doi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_and_markdown() {
+        let mut counts = PrismaCounts::new("landslide", "OpenAlex");
+        counts.identified = 100;
+        counts.duplicates_removed = 4;
+        counts.rank_excluded = 50;
+        counts.no_doi_excluded = 6;
+        assert_eq!(counts.after_removal(), 40);
+        assert!(counts.to_markdown().contains("| Records screened (LLM, title/abstract) | not performed |"));
+        assert!(counts.to_svg().contains("LLM screening not performed"));
+
+        counts.set_screening(40, 12, 3);
+        assert_eq!(counts.llm_excluded, Some(25));
+        let md = counts.to_markdown();
+        assert!(md.contains("| Records identified from OpenAlex | 100 |"));
+        assert!(md.contains("| Studies included in review | 12 |"));
+        let svg = counts.to_svg();
+        assert!(svg.contains("Records excluded by LLM (n = 25)") && svg.contains("Awaiting manual review (n = 3)"));
+        assert!(!md.contains("previous run"));
+
+        counts.previously_seen = 10;
+        assert_eq!(counts.after_removal(), 30);
+        assert!(counts.to_markdown().contains("| Records in the previous run | 10 |"));
+        assert!(counts.to_svg().contains("In the previous run (n = 10)"));
+
+        counts.citation_excluded = 5;
+        assert_eq!(counts.after_removal(), 25);
+        assert!(counts.to_markdown().contains("| Records excluded by citation count | 5 |\n| Records removed without DOI |"));
+
+        counts.prefilter_excluded = 20;
+        counts.set_screening(40, 12, 3);
+        assert_eq!(counts.llm_excluded, Some(5));
+        assert!(counts
+            .to_markdown()
+            .contains("| Records excluded by keyword pre-filter | 20 |\n| Records excluded by LLM screening | 5 |"));
+        assert!(counts.to_svg().contains("Excluded by keyword pre-filter (n = 20)"));
+    }
+
+    #[test]
+    fn test_duplicate_key() {
+        assert_eq!(duplicate_key(" 10.1/AB ", "x"), duplicate_key("10.1/ab", "y"));
+        assert_eq!(duplicate_key("", "Slope Stability: A Review"), duplicate_key("", "slope stability - a review"));
+        assert_ne!(duplicate_key("", "Slope stability"), duplicate_key("", "Slope stability II"));
+        assert_eq!(duplicate_key("", "[PDF] Pente stabilité"), duplicate_key("", "Pente stabilite"));
+    }
+}
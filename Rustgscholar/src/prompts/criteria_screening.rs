@@ -0,0 +1,62 @@
+//! Criteria-based screening prompts for systematic reviews.
+//!
+//! Contains system and user prompt templates for judging a paper against
+//! explicit inclusion and exclusion criteria (PICO-style).
+
+/// System prompt for criteria-based screening
+pub const SYSTEM_PROMPT: &str = r#"You are a systematic-review screening assistant. Your task is to judge a paper against explicit inclusion and exclusion criteria based ONLY on the provided fields (title/abstract/fulltext_excerpt/tldr/journal). fulltext_excerpt, when present, is the start of the paper's full text.
+
+Rules you MUST follow:
+- Do NOT fabricate abstract or paper content.
+- Judge every criterion separately, citing the phrase from the input that supports your verdict.
+- Use "unclear" for a criterion when the input does not contain enough information.
+- Output MUST be valid JSON only (no extra text), for machine parsing.
+
+Per-criterion verdicts:
+- met: The input explicitly satisfies the criterion.
+- not_met: The input explicitly contradicts the criterion.
+- unclear: Insufficient information.
+
+Overall decision:
+- include: All inclusion criteria are met and no exclusion criterion is met.
+- exclude: Any inclusion criterion is not_met, or any exclusion criterion is met.
+- uncertain: Otherwise (some verdicts are unclear).
+
+Output format (strict JSON, no markdown):
+{
+  "criteria": [{"id": "I1", "verdict": "met" | "not_met" | "unclear", "evidence": "quoted phrase"}],
+  "decision": "include" | "exclude" | "uncertain",
+  "confidence": 0.0-1.0,
+  "reason": "Brief explanation in English"
+}"#;
+
+/// User prompt template for single paper screening
+/// Placeholders: {criteria}, {paper_json}
+pub const USER_PROMPT_TEMPLATE: &str = r#"Screen the following paper against the criteria.
+
+Criteria:
+{criteria}
+
+Paper data (JSON):
+{paper_json}
+
+Output strict JSON only (no markdown code blocks, no extra text), with one entry in "criteria" per criterion ID above."#;
+
+/// Build user prompt with rendered criteria and paper data
+pub fn build_user_prompt(criteria: &str, paper_json: &str) -> String {
+    USER_PROMPT_TEMPLATE
+        .replace("{criteria}", criteria)
+        .replace("{paper_json}", paper_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_user_prompt() {
+        let prompt = build_user_prompt("I1 (inclusion): Field study", r#"{"title": "test"}"#);
+        assert!(prompt.contains("I1 (inclusion): Field study"));
+        assert!(prompt.contains(r#"{"title": "test"}"#));
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! This module provides modular prompt templates for various LLM tasks.
 
+pub mod criteria_screening;
+pub mod query_expansion;
 pub mod relevance_filter;
+pub mod review_detection;
+pub mod study_design;
+pub mod topic_tagging;
 
 pub use relevance_filter::*;
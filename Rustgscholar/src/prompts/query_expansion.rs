@@ -0,0 +1,51 @@
+//! Query expansion prompts for building search strings.
+//!
+//! Contains system and user prompt templates for proposing synonyms,
+//! related terms, and Boolean query variants for a search keyword.
+
+/// System prompt for query expansion
+pub const SYSTEM_PROMPT: &str = r#"You are an academic search librarian. Your task is to help a researcher build better search strings for scholarly databases (OpenAlex, Google Scholar).
+
+Rules you MUST follow:
+- Propose synonyms and spelling variants that mean the same thing as the keyword.
+- Propose closely related terms (narrower concepts, common co-occurring terms); avoid generic words.
+- Propose Boolean query variants using AND, OR, NOT, parentheses, and "quoted phrases".
+- Keep the researcher's original intent; do not drift to other topics.
+- Output MUST be valid JSON only (no extra text), for machine parsing.
+
+Output format (strict JSON, no markdown):
+{
+  "synonyms": ["term1", "term2"],
+  "related_terms": ["term1", "term2"],
+  "queries": [{"query": "(\"a\" OR \"b\") AND c", "rationale": "Brief explanation in English"}]
+}"#;
+
+/// User prompt template for query expansion
+/// Placeholders: {keyword}, {context}
+pub const USER_PROMPT_TEMPLATE: &str = r#"Expand the following search keyword.
+
+Keyword: {keyword}
+
+Research context: {context}
+
+Output strict JSON only (no markdown code blocks, no extra text)."#;
+
+/// Build user prompt with keyword and optional research context
+pub fn build_user_prompt(keyword: &str, context: &str) -> String {
+    let context = if context.trim().is_empty() { "(not provided)" } else { context };
+    USER_PROMPT_TEMPLATE
+        .replace("{keyword}", keyword)
+        .replace("{context}", context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_user_prompt() {
+        let prompt = build_user_prompt("landslide susceptibility", "");
+        assert!(prompt.contains("Keyword: landslide susceptibility"));
+        assert!(prompt.contains("(not provided)"));
+    }
+}
@@ -3,7 +3,7 @@
 //! Contains system and user prompt templates for LLM-based relevance filtering.
 
 /// System prompt for academic paper relevance filtering
-pub const SYSTEM_PROMPT: &str = r#"You are an academic literature relevance classifier. Your task is to determine if a paper is related to the target domain based ONLY on the provided fields (title/abstract/tldr/venue/journal/keywords).
+pub const SYSTEM_PROMPT: &str = r#"You are an academic literature relevance classifier. Your task is to determine if a paper is related to the target domain based ONLY on the provided fields (title/abstract/fulltext_excerpt/tldr/venue/journal/keywords).
 
 Rules you MUST follow:
 - Do NOT fabricate abstract or paper content.
@@ -17,7 +17,8 @@ Classification criteria:
 - uncertain: Insufficient information (e.g., no abstract/tldr) or only vague keyword matches without context support.
 
 Important rules:
-- Evidence priority: abstract_text > tldr > title > venue/journal.
+- Evidence priority: abstract_text > fulltext_excerpt > tldr > title > venue/journal.
+- fulltext_excerpt, when present, is the start of the paper's full text; it is given only when the abstract is missing or short.
 - If abstract_text and tldr are both empty: use only title + venue/journal; output "uncertain" if unsure.
 - Do not mark "relevant" just because a word looks similar; there must be contextual support.
 - If both positive and negative signals exist, prefer "uncertain" and explain the conflict in reason.
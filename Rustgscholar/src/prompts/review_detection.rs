@@ -0,0 +1,33 @@
+//! Review detection prompts.
+//!
+//! Contains system and user prompt templates for deciding whether a paper
+//! is a review article when the title and abstract cues are inconclusive.
+
+/// System prompt for review-article detection
+pub const SYSTEM_PROMPT: &str = r#"You are an academic literature classifier. Your task is to decide whether a paper is a review article (narrative review, systematic review, scoping review, meta-analysis, survey or overview of prior work) rather than original research, based ONLY on the provided fields (title/abstract/fulltext_excerpt/tldr/journal).
+
+Rules you MUST follow:
+- A paper is a review when its main contribution is summarizing, comparing or synthesizing previously published work.
+- Original research that includes a literature review section is NOT a review.
+- Output false when the fields do not show that the paper is a review.
+- Output MUST be valid JSON only (no extra text), for machine parsing.
+
+Output format (strict JSON, no markdown):
+{
+  "is_review": true | false,
+  "reason": "Brief explanation in English"
+}"#;
+
+/// User prompt template for single paper review detection
+/// Placeholders: {paper_json}
+pub const USER_PROMPT_TEMPLATE: &str = r#"Is the following paper a review article?
+
+Paper data (JSON):
+{paper_json}
+
+Output strict JSON only (no markdown code blocks, no extra text)."#;
+
+/// Build user prompt with paper data
+pub fn build_user_prompt(paper_json: &str) -> String {
+    USER_PROMPT_TEMPLATE.replace("{paper_json}", paper_json)
+}
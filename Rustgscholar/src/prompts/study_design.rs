@@ -0,0 +1,43 @@
+//! Study design classification prompts.
+//!
+//! Contains system and user prompt templates for labeling a paper with one
+//! study design from a user-defined list.
+
+/// System prompt for single-label study design classification
+pub const SYSTEM_PROMPT: &str = r#"You are an academic evidence synthesis assistant. Your task is to label a paper with the ONE study design from the given list that best describes how its evidence was produced, based ONLY on the provided fields (title/abstract/fulltext_excerpt/tldr/journal).
+
+Rules you MUST follow:
+- Use only a design name from the provided list, spelled exactly as given.
+- Choose the design of the paper's main contribution, not of the work it cites.
+- Return an empty string when the fields do not show the design; do not guess.
+- Output MUST be valid JSON only (no extra text), for machine parsing.
+
+Output format (strict JSON, no markdown):
+{
+  "design": "design name",
+  "reason": "Brief explanation in English"
+}"#;
+
+/// User prompt template for single paper study design classification
+/// Placeholders: {designs}, {paper_json}
+pub const USER_PROMPT_TEMPLATE: &str = r#"Which of the following study designs does this paper use?
+
+Study designs:
+{designs}
+
+Paper data (JSON):
+{paper_json}
+
+Output strict JSON only (no markdown code blocks, no extra text)."#;
+
+/// Build user prompt with design list and paper data
+pub fn build_user_prompt(designs: &[String], paper_json: &str) -> String {
+    let designs = designs
+        .iter()
+        .map(|d| format!("- {}", d))
+        .collect::<Vec<_>>()
+        .join("\n");
+    USER_PROMPT_TEMPLATE
+        .replace("{designs}", &designs)
+        .replace("{paper_json}", paper_json)
+}
@@ -0,0 +1,55 @@
+//! Topic tagging prompts for organizing relevant papers.
+//!
+//! Contains system and user prompt templates for multi-label classification
+//! of papers into user-defined categories.
+
+/// System prompt for multi-label topic tagging
+pub const SYSTEM_PROMPT: &str = r#"You are an academic literature organizer. Your task is to assign a paper to one or more of the given categories based ONLY on the provided fields (title/abstract/fulltext_excerpt/tldr/journal).
+
+Rules you MUST follow:
+- Use only category names from the provided list, spelled exactly as given.
+- Assign every category that clearly applies; a paper may have several.
+- Return an empty list when no category applies; do not guess.
+- Output MUST be valid JSON only (no extra text), for machine parsing.
+
+Output format (strict JSON, no markdown):
+{
+  "tags": ["category1", "category2"],
+  "reason": "Brief explanation in English"
+}"#;
+
+/// User prompt template for single paper tagging
+/// Placeholders: {categories}, {paper_json}
+pub const USER_PROMPT_TEMPLATE: &str = r#"Assign the following paper to the matching categories.
+
+Categories:
+{categories}
+
+Paper data (JSON):
+{paper_json}
+
+Output strict JSON only (no markdown code blocks, no extra text)."#;
+
+/// Build user prompt with category list and paper data
+pub fn build_user_prompt(categories: &[String], paper_json: &str) -> String {
+    let categories = categories
+        .iter()
+        .map(|c| format!("- {}", c))
+        .collect::<Vec<_>>()
+        .join("\n");
+    USER_PROMPT_TEMPLATE
+        .replace("{categories}", &categories)
+        .replace("{paper_json}", paper_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_user_prompt() {
+        let prompt = build_user_prompt(&["method".to_string(), "review".to_string()], r#"{"title": "test"}"#);
+        assert!(prompt.contains("- method\n- review"));
+        assert!(prompt.contains(r#"{"title": "test"}"#));
+    }
+}
@@ -0,0 +1,171 @@
+//! LLM-driven query expansion.
+//!
+//! Asks the LLM for synonyms, related terms, and Boolean query variants of
+//! a search keyword, and optionally checks each variant's hit count on
+//! OpenAlex so users can compare search strings before running the pipeline.
+
+use crate::error::Result;
+use crate::llm_filter::{extract_json, LlmConfig, LlmSession, TokenUsage};
+use crate::openalex::{self, QueryOptions};
+use crate::prompts::query_expansion::{build_user_prompt, SYSTEM_PROMPT};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// A suggested search string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryVariant {
+    pub query: String,
+    #[serde(default)]
+    pub rationale: String,
+    /// OpenAlex hit count, when validated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hits: Option<i64>,
+}
+
+/// Expansion suggestions for one keyword
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryExpansion {
+    #[serde(default)]
+    pub keyword: String,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    #[serde(default)]
+    pub related_terms: Vec<String>,
+    #[serde(default)]
+    pub queries: Vec<QueryVariant>,
+}
+
+/// JSON schema for the expansion response
+fn response_schema() -> serde_json::Value {
+    let strings = serde_json::json!({"type": "array", "items": {"type": "string"}});
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "synonyms": strings,
+            "related_terms": strings,
+            "queries": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "query": {"type": "string"},
+                        "rationale": {"type": "string"}
+                    },
+                    "required": ["query", "rationale"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["synonyms", "related_terms", "queries"],
+        "additionalProperties": false
+    })
+}
+
+/// Ask the LLM for expansion suggestions for a keyword
+///
+/// `context` is an optional description of the research question.
+pub async fn expand_query(config: &LlmConfig, keyword: &str, context: &str) -> Result<(QueryExpansion, TokenUsage)> {
+    info!(keyword = keyword, model = %config.model, "Expanding query");
+
+    let session = LlmSession::new(config)?;
+    let (mut expansion, usage) = session
+        .complete_json(SYSTEM_PROMPT, &build_user_prompt(keyword, context), "query_expansion", &response_schema(), 0, parse_expansion)
+        .await?;
+
+    expansion.keyword = keyword.to_string();
+    Ok((expansion, usage))
+}
+
+/// Fill in OpenAlex hit counts for the original keyword and every variant
+///
+/// Returns the keyword's own hit count. Variants whose count request fails
+/// are left without a count.
+pub async fn validate(expansion: &mut QueryExpansion, options: &QueryOptions) -> Option<i64> {
+    let baseline = match openalex::count(&expansion.keyword, options).await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            warn!(error = %e, "Failed to count keyword hits");
+            None
+        }
+    };
+
+    // Sequential: a handful of requests, well within the polite pool limit
+    for variant in &mut expansion.queries {
+        match openalex::count(&variant.query, options).await {
+            Ok(count) => variant.hits = Some(count),
+            Err(e) => warn!(query = %variant.query, error = %e, "Failed to count query hits"),
+        }
+    }
+
+    baseline
+}
+
+impl QueryExpansion {
+    /// Printable listing of the suggestions
+    ///
+    /// `baseline` is the keyword's own hit count from [`validate`]; pass
+    /// `None` when the variants were not validated to omit the count column.
+    pub fn summary(&self, baseline: Option<Option<i64>>) -> String {
+        let hits = |count: Option<i64>| count.map(|c| format!("{:>9}", c)).unwrap_or_else(|| format!("{:>9}", "-"));
+
+        let mut lines = vec![
+            format!("Synonyms:      {}", self.synonyms.join(", ")),
+            format!("Related terms: {}", self.related_terms.join(", ")),
+            "\nQuery variants:".to_string(),
+        ];
+        if let Some(baseline) = baseline {
+            lines.push(format!("  {}  {} (original)", hits(baseline), self.keyword));
+        }
+        for variant in &self.queries {
+            if baseline.is_some() {
+                lines.push(format!("  {}  {}", hits(variant.hits), variant.query));
+            } else {
+                lines.push(format!("  - {}", variant.query));
+            }
+            if !variant.rationale.is_empty() {
+                lines.push(format!("  {:>9}  ↳ {}", "", variant.rationale));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Parse the expansion JSON, dropping empty and duplicate suggestions
+fn parse_expansion(content: &str) -> Result<QueryExpansion> {
+    let mut expansion: QueryExpansion = serde_json::from_str(&extract_json(content))?;
+
+    let dedup = |terms: &mut Vec<String>| {
+        let mut seen = std::collections::HashSet::new();
+        terms.retain(|t| !t.trim().is_empty() && seen.insert(t.trim().to_lowercase()));
+    };
+    dedup(&mut expansion.synonyms);
+    dedup(&mut expansion.related_terms);
+
+    let mut seen = std::collections::HashSet::new();
+    expansion
+        .queries
+        .retain(|q| !q.query.trim().is_empty() && seen.insert(q.query.trim().to_string()));
+
+    Ok(expansion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expansion() -> Result<()> {
+        let content = r#"```json
+{"synonyms": ["mass movement", "Mass Movement", ""],
+ "related_terms": ["debris flow"],
+ "queries": [{"query": "landslide AND InSAR", "rationale": "remote sensing"}, {"query": "landslide AND InSAR", "rationale": "dup"}]}
+```"#;
+        let expansion = parse_expansion(content)?;
+        assert_eq!(expansion.synonyms, vec!["mass movement"]);
+        assert_eq!(expansion.related_terms, vec!["debris flow"]);
+        assert_eq!(expansion.queries.len(), 1);
+        assert!(expansion.queries[0].hits.is_none());
+        assert!(parse_expansion("not json").is_err());
+        Ok(())
+    }
+}
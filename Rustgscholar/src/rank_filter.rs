@@ -0,0 +1,419 @@
+//! Ranking filter expression language.
+//!
+//! Parses expressions such as `sciif>=5 && (sci~'Q1' || ccf=='A')` and
+//! evaluates them against [`RankingMetrics`] for each paper in Stage 3.
+//!
+//! Grammar:
+//!
+//! ```text
+//! expr       := and ( "||" and )*
+//! and        := unary ( "&&" unary )*
+//! unary      := "!" unary | "(" expr ")" | comparison
+//! comparison := KEY [ op VALUE ]
+//! op         := ">=" | ">" | "<=" | "<" | "==" | "!=" | "~"
+//! ```
+//!
+//! `KEY` is any EasyScholar metric name in [`METRIC_KEYS`]. A bare key is
+//! true when the journal has a value for that metric. `~` is a substring
+//! match; ordering operators compare numerically.
+
+use crate::error::{GscholarError, Result};
+use crate::rankings::{RankingClient, RankingMetrics, METRIC_KEYS};
+use std::fmt;
+use std::str::FromStr;
+
+/// Comparison operator in a filter expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+    Ne,
+    Contains,
+}
+
+/// Parsed ranking filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum RankFilter {
+    And(Box<RankFilter>, Box<RankFilter>),
+    Or(Box<RankFilter>, Box<RankFilter>),
+    Not(Box<RankFilter>),
+    /// `key op value`
+    Compare {
+        key: String,
+        op: CompareOp,
+        value: String,
+    },
+    /// Bare `key`: the metric is present
+    Exists(String),
+}
+
+impl RankFilter {
+    /// Parse a filter expression
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(GscholarError::Validation(format!(
+                "Unexpected token '{}' in rank filter",
+                token
+            )));
+        }
+        Ok(expr)
+    }
+
+    /// `key >= threshold` (sugar for the `--sciif`/`--jci` flags)
+    pub fn at_least(key: &str, threshold: f64) -> Self {
+        Self::Compare {
+            key: key.to_string(),
+            op: CompareOp::Ge,
+            value: threshold.to_string(),
+        }
+    }
+
+    /// `key ~ pattern` (sugar for the substring flags such as `--sci`)
+    pub fn contains(key: &str, pattern: &str) -> Self {
+        Self::Compare {
+            key: key.to_string(),
+            op: CompareOp::Contains,
+            value: pattern.to_string(),
+        }
+    }
+
+    /// Combine two filters with logical AND
+    pub fn and(self, other: RankFilter) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    /// Combine a list of filters with AND, returning None for an empty list
+    pub fn all(filters: impl IntoIterator<Item = RankFilter>) -> Option<Self> {
+        filters.into_iter().reduce(RankFilter::and)
+    }
+
+    /// Evaluate the filter against a journal's ranking metrics
+    pub fn evaluate(&self, metrics: &RankingMetrics) -> bool {
+        match self {
+            Self::And(a, b) => a.evaluate(metrics) && b.evaluate(metrics),
+            Self::Or(a, b) => a.evaluate(metrics) || b.evaluate(metrics),
+            Self::Not(a) => !a.evaluate(metrics),
+            Self::Exists(key) => RankingClient::get_metric(metrics, key).is_some_and(|v| !v.is_empty()),
+            Self::Compare { key, op, value } => {
+                let actual = RankingClient::get_metric(metrics, key);
+                compare(actual.as_deref(), *op, value)
+            }
+        }
+    }
+}
+
+impl FromStr for RankFilter {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ge => ">=",
+            Self::Gt => ">",
+            Self::Le => "<=",
+            Self::Lt => "<",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Contains => "~",
+        })
+    }
+}
+
+/// The filter as an expression [`RankFilter::parse`] reads back (e.g., for
+/// the search strategy report)
+impl fmt::Display for RankFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grouped = |filter: &RankFilter, f: &mut fmt::Formatter<'_>, group_or: bool| match filter {
+            Self::Or(..) if group_or => write!(f, "({})", filter),
+            Self::And(..) | Self::Or(..) if !group_or => write!(f, "({})", filter),
+            _ => write!(f, "{}", filter),
+        };
+        match self {
+            Self::And(a, b) => {
+                grouped(a, f, true)?;
+                f.write_str(" && ")?;
+                grouped(b, f, true)
+            }
+            Self::Or(a, b) => write!(f, "{} || {}", a, b),
+            Self::Not(a) => {
+                f.write_str("!")?;
+                grouped(a, f, false)
+            }
+            Self::Exists(key) => f.write_str(key),
+            Self::Compare { key, op, value } if value.parse::<f64>().is_ok() => write!(f, "{} {} {}", key, op, value),
+            Self::Compare { key, op, value } if value.contains('\'') => write!(f, "{} {} \"{}\"", key, op, value),
+            Self::Compare { key, op, value } => write!(f, "{} {} '{}'", key, op, value),
+        }
+    }
+}
+
+/// Compare a metric value against a literal
+fn compare(actual: Option<&str>, op: CompareOp, expected: &str) -> bool {
+    let Some(actual) = actual else {
+        // Missing metrics only satisfy "not equal"
+        return op == CompareOp::Ne;
+    };
+
+    let numeric = || -> Option<(f64, f64)> {
+        Some((actual.trim().parse().ok()?, expected.trim().parse().ok()?))
+    };
+
+    match op {
+        CompareOp::Contains => RankingClient::passes_string_filter(Some(actual), expected),
+        CompareOp::Ge => numeric().is_some_and(|(a, b)| a >= b),
+        CompareOp::Gt => numeric().is_some_and(|(a, b)| a > b),
+        CompareOp::Le => numeric().is_some_and(|(a, b)| a <= b),
+        CompareOp::Lt => numeric().is_some_and(|(a, b)| a < b),
+        CompareOp::Eq => numeric().map_or(actual.trim() == expected, |(a, b)| a == b),
+        CompareOp::Ne => numeric().map_or(actual.trim() != expected, |(a, b)| a != b),
+    }
+}
+
+// === Tokenizer ===
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(String),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(s) | Self::Literal(s) => write!(f, "{}", s),
+            Self::Op(op) => write!(f, "{:?}", op),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+            Self::Not => write!(f, "!"),
+            Self::LParen => write!(f, "("),
+            Self::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let two = |i: usize| -> String { chars[i..(i + 2).min(chars.len())].iter().collect() };
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '&' if two(i) == "&&" => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if two(i) == "||" => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '>' | '<' | '=' | '!' => {
+                let (token, len) = match two(i).as_str() {
+                    ">=" => (Token::Op(CompareOp::Ge), 2),
+                    "<=" => (Token::Op(CompareOp::Le), 2),
+                    "==" => (Token::Op(CompareOp::Eq), 2),
+                    "!=" => (Token::Op(CompareOp::Ne), 2),
+                    _ => match c {
+                        '>' => (Token::Op(CompareOp::Gt), 1),
+                        '<' => (Token::Op(CompareOp::Lt), 1),
+                        '=' => (Token::Op(CompareOp::Eq), 1),
+                        _ => (Token::Not, 1),
+                    },
+                };
+                tokens.push(token);
+                i += len;
+            }
+            '~' => {
+                tokens.push(Token::Op(CompareOp::Contains));
+                i += 1;
+            }
+            '\'' | '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .ok_or_else(|| GscholarError::Validation("Unterminated string in rank filter".to_string()))?;
+                tokens.push(Token::Literal(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !is_delimiter(chars[i]) {
+                    i += 1;
+                }
+                if start == i {
+                    return Err(GscholarError::Validation(format!(
+                        "Unexpected character '{}' in rank filter",
+                        c
+                    )));
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "()&|><=!~'\"".contains(c)
+}
+
+// === Recursive-descent parser ===
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<RankFilter> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = RankFilter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<RankFilter> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = RankFilter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<RankFilter> {
+        match self.next() {
+            Some(Token::Not) => Ok(RankFilter::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(GscholarError::Validation("Missing ')' in rank filter".to_string())),
+                }
+            }
+            Some(Token::Ident(key)) => self.parse_comparison(key),
+            Some(token) => Err(GscholarError::Validation(format!(
+                "Expected metric name, found '{}' in rank filter",
+                token
+            ))),
+            None => Err(GscholarError::Validation("Unexpected end of rank filter".to_string())),
+        }
+    }
+
+    fn parse_comparison(&mut self, key: String) -> Result<RankFilter> {
+        if !METRIC_KEYS.contains(&key.as_str()) {
+            return Err(GscholarError::Validation(format!(
+                "Unknown metric '{}' in rank filter (expected one of: {})",
+                key,
+                METRIC_KEYS.join(", ")
+            )));
+        }
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Ok(RankFilter::Exists(key)),
+        };
+        self.pos += 1;
+
+        match self.next() {
+            Some(Token::Literal(value)) | Some(Token::Ident(value)) => Ok(RankFilter::Compare { key, op, value }),
+            _ => Err(GscholarError::Validation(format!(
+                "Expected value after operator for '{}' in rank filter",
+                key
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> RankingMetrics {
+        RankingMetrics {
+            sciif: Some("6.2".to_string()),
+            sci: Some("Q2".to_string()),
+            ccf: Some("A".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_and_evaluate() -> Result<()> {
+        let m = metrics();
+        assert!(RankFilter::parse("sciif>=5 && (sci~'Q1' || ccf=='A')")?.evaluate(&m));
+        assert!(!RankFilter::parse("sciif>=5 && sci~\"Q1\"")?.evaluate(&m));
+        assert!(RankFilter::parse("!(jci > 1) && ccf")?.evaluate(&m));
+        assert!(!RankFilter::parse("pku")?.evaluate(&m));
+        assert!(RankFilter::parse("sciif < 10 && sci == Q2")?.evaluate(&m));
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_round_trip() -> Result<()> {
+        for expr in ["sciif >= 5 && (sci ~ 'Q1' || ccf == 'A')", "!(jci > 1 && pku) || sci != 'Q4'", "!ccf"] {
+            let filter = RankFilter::parse(expr)?;
+            assert_eq!(filter.to_string(), expr);
+            assert_eq!(RankFilter::parse(&filter.to_string())?, filter);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(RankFilter::parse("impact>=5").is_err());
+        assert!(RankFilter::parse("sciif>=5 &&").is_err());
+        assert!(RankFilter::parse("(sciif>=5").is_err());
+        assert!(RankFilter::parse("sci~'Q1").is_err());
+    }
+
+    #[test]
+    fn test_flag_sugar() {
+        let filter = RankFilter::all([RankFilter::at_least("sciif", 5.0), RankFilter::contains("sci", "Q2")]);
+        assert!(filter.is_some_and(|f| f.evaluate(&metrics())));
+        assert!(RankFilter::all(Vec::new()).is_none());
+    }
+}
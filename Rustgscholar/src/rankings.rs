@@ -1,23 +1,35 @@
 //! EasyScholar publication rankings API client.
 //!
 //! This module provides access to EasyScholar's ranking data,
-//! including Impact Factor (IF), JCI, and SCI partitions.
+//! including Impact Factor (IF), JCI, SCI/CAS partitions, and Chinese
+//! core-journal lists (北大核心, CSSCI, CSCD), EI and CCF.
+//!
+//! Lookups try the ISSN first, then the venue name through an alias and
+//! normalization layer, since Scholar/OpenAlex names often differ from
+//! EasyScholar's canonical names ("Comput. Geotech." vs "Computers and Geotechnics").
 
+use crate::cache::DiskCache;
 use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use crate::throttle::{self, Upstream};
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
 /// EasyScholar API base URL
-const EASYSCHOLAR_API_URL: &str = "https://www.easyscholar.cc/open/getPublicationRank";
+pub const EASYSCHOLAR_API_URL: &str = "https://www.easyscholar.cc/open/getPublicationRank";
 
-/// Minimum interval between requests (slightly more than 0.5s to be safe)
-const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(600);
+/// Maximum journal lookups in flight at once in [`RankingClient::get_ranks_batch`]
+pub const MAX_CONCURRENT_LOOKUPS: usize = 4;
 
 /// Ranking metrics from EasyScholar
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct RankingMetrics {
     /// Impact Factor
     pub sciif: Option<String>,
@@ -31,14 +43,50 @@ pub struct RankingMetrics {
     pub sci_base: Option<String>,
     /// SCI Up
     pub sci_up: Option<String>,
+    /// 5-year Impact Factor
+    pub sciif5: Option<String>,
+    /// SSCI partition
+    pub ssci: Option<String>,
+    /// ESI subject
+    pub esi: Option<String>,
+    /// CAS early-warning journal list (预警)
+    pub sci_warn: Option<String>,
+    /// CAS partition (中科院分区)
+    pub zky: Option<String>,
+    /// Peking University core journals (北大核心)
+    pub pku: Option<String>,
+    /// Chinese Social Sciences Citation Index
+    pub cssci: Option<String>,
+    /// Chinese Science Citation Database
+    pub cscd: Option<String>,
+    /// Engineering Index (EI)
+    pub eii: Option<String>,
+    /// China Computer Federation rank (A/B/C)
+    pub ccf: Option<String>,
+    /// CORE conference rank (A*/A/B/C), from `--core-rankings` rather than EasyScholar
+    #[serde(default)]
+    pub core: Option<String>,
 }
 
+/// EasyScholar keys exposed by [`RankingMetrics`], as accepted by [`RankingClient::get_metric`]
+pub const METRIC_KEYS: &[&str] = &[
+    "sciif", "jci", "sci", "sciUpTop", "sciBase", "sciUp", "sciif5", "ssci", "esi", "sciwarn",
+    "zky", "pku", "cssci", "cscd", "eii", "ccf", "core",
+];
+
+/// Words left out of abbreviated titles ("J. Hydrol." for "Journal of Hydrology")
+const CONNECTIVES: &[&str] = &["a", "an", "and", "at", "for", "in", "of", "on", "the", "to"];
+
 /// EasyScholar API client with caching and rate limiting
 pub struct RankingClient {
     secret_key: String,
     client: reqwest::Client,
     cache: Mutex<HashMap<String, Option<RankingMetrics>>>,
-    last_request: Mutex<Option<Instant>>,
+    disk_cache: Option<DiskCache<Option<RankingMetrics>>>,
+    /// Normalized alias -> canonical EasyScholar name
+    aliases: HashMap<String, String>,
+    /// Unabbreviated venue names looked up so far, for matching abbreviated ones
+    full_names: Mutex<Vec<String>>,
 }
 
 impl RankingClient {
@@ -48,19 +96,114 @@ impl RankingClient {
     ///
     /// * `secret_key` - EasyScholar API key
     pub fn new(secret_key: String) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .map_err(|e| GscholarError::Config(format!("Failed to build HTTP client: {}", e)))?;
-
         Ok(Self {
             secret_key,
-            client,
+            client: http::client_for(Upstream::EasyScholar),
             cache: Mutex::new(HashMap::new()),
-            last_request: Mutex::new(None),
+            disk_cache: None,
+            aliases: HashMap::new(),
+            full_names: Mutex::new(Vec::new()),
         })
     }
 
+    /// Enable the persistent on-disk cache
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Cache file (e.g., `~/.cache/rustgscholar/rankings.json`)
+    /// * `ttl` - Maximum age of a cached entry before it is re-queried
+    pub fn with_disk_cache(mut self, path: PathBuf, ttl: Duration) -> Self {
+        let disk_cache = DiskCache::open(path, ttl);
+        info!(
+            path = ?disk_cache.path(),
+            entries = disk_cache.len(),
+            "Loaded EasyScholar disk cache"
+        );
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Register journal name aliases
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - Map of alias (any spelling) to canonical EasyScholar name
+    pub fn with_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.aliases.extend(
+            aliases
+                .into_iter()
+                .map(|(alias, canonical)| (normalize_journal_name(&alias), canonical)),
+        );
+        self
+    }
+
+    /// Get ranking info for a journal, trying ISSN first
+    ///
+    /// Falls back to the venue name, a registered alias, and normalized
+    /// name variants before giving up.
+    pub async fn get_rank_with_issn(&self, venue_name: &str, issn: Option<&str>) -> Option<RankingMetrics> {
+        for candidate in self.lookup_candidates(venue_name, issn) {
+            if let Some(metrics) = self.get_rank(&candidate).await {
+                if candidate != venue_name.trim() {
+                    debug!(venue = venue_name, matched = %candidate, "Matched via alternate key");
+                }
+                return Some(metrics);
+            }
+        }
+        None
+    }
+
+    /// Build the ordered list of lookup keys for a venue
+    fn lookup_candidates(&self, venue_name: &str, issn: Option<&str>) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::new();
+        let mut push = |c: String| {
+            if !c.is_empty() && !candidates.contains(&c) {
+                candidates.push(c);
+            }
+        };
+
+        if let Some(issn) = issn.and_then(normalize_issn) {
+            push(issn);
+        }
+        if let Some(canonical) = self.aliases.get(&normalize_journal_name(venue_name)) {
+            push(canonical.clone());
+        }
+        if let Some(full_name) = self.full_name(venue_name) {
+            push(full_name);
+        }
+        for variant in name_variants(venue_name) {
+            push(variant);
+        }
+
+        candidates
+    }
+
+    /// Remember the unabbreviated names among `names` for [`Self::full_name`]
+    fn remember_full_names<'a>(&self, names: impl IntoIterator<Item = &'a str>) {
+        let Ok(mut full_names) = self.full_names.lock() else {
+            return;
+        };
+        for name in names.into_iter().map(str::trim).filter(|n| !n.is_empty() && !n.contains('.')) {
+            if !full_names.iter().any(|known| known == name) {
+                full_names.push(name.to_string());
+            }
+        }
+    }
+
+    /// Full name of an abbreviated venue ("Comput. Geotech."), if an alias
+    /// target or a venue of this run spells it out
+    fn full_name(&self, venue_name: &str) -> Option<String> {
+        if !venue_name.contains('.') {
+            return None;
+        }
+        let full_names = self.full_names.lock().ok()?;
+        self.aliases
+            .values()
+            .chain(full_names.iter())
+            .find(|full| abbreviates(venue_name, full))
+            .cloned()
+    }
+
     /// Get ranking info for a journal/venue
     ///
     /// Returns None if not found or error
@@ -79,13 +222,32 @@ impl RankingClient {
             }
         }
 
-        // Rate limiting
-        self.wait_for_rate_limit().await;
+        // Check disk cache
+        if let Some(cached) = self.disk_cache.as_ref().and_then(|c| c.get(venue_name)) {
+            debug!(venue = venue_name, "Disk cache hit");
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.insert(venue_name.to_string(), cached.clone());
+            }
+            return cached;
+        }
 
-        // Make request
-        let result = self.do_request(venue_name).await;
+        // Make request (paced by the shared EasyScholar bucket)
+        let result = match self.do_request(venue_name).await {
+            Ok(result) => {
+                // Only definitive answers are persisted; transient failures are retried next run
+                if let Some(disk_cache) = &self.disk_cache {
+                    if let Err(e) = disk_cache.insert(venue_name, result.clone()) {
+                        warn!(venue = venue_name, error = %e, "Failed to update disk cache");
+                    }
+                }
+                result
+            }
+            Err(e) => {
+                warn!(venue = venue_name, error = %e, "EasyScholar request failed");
+                None
+            }
+        };
 
-        // Note: The block below handles caching properly
         // Update cache
         {
             if let Ok(mut cache) = self.cache.lock() {
@@ -96,37 +258,74 @@ impl RankingClient {
         result
     }
 
-    /// Wait for rate limit interval
-    async fn wait_for_rate_limit(&self) {
-        let should_wait = {
-            let last = self.last_request.lock().ok();
-            last.and_then(|l| *l).map(|t| t.elapsed() < MIN_REQUEST_INTERVAL)
-        };
-
-        if should_wait == Some(true) {
-            tokio::time::sleep(MIN_REQUEST_INTERVAL).await;
+    /// Get rankings for many journals concurrently
+    ///
+    /// Lookups share the EasyScholar token bucket of [`crate::ratelimit`],
+    /// so several requests can be in flight while request starts still
+    /// honor the API rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `journals` - Venue names with an optional ISSN for each
+    /// * `concurrency` - Maximum lookups in flight
+    pub async fn get_ranks_batch(
+        &self,
+        journals: &[(String, Option<String>)],
+        concurrency: usize,
+    ) -> HashMap<String, Option<RankingMetrics>> {
+        info!(count = journals.len(), concurrency = concurrency, "Starting batch EasyScholar lookup");
+        self.remember_full_names(journals.iter().map(|(journal, _)| journal.as_str()));
+
+        let completed = AtomicUsize::new(0);
+        let total = journals.len();
+
+        // Build the lookups up front so the returned future stays `Send`
+        let lookups: Vec<_> = journals
+            .iter()
+            .map(|(journal, issn)| {
+                let completed = &completed;
+                async move {
+                    let metrics = self.get_rank_with_issn(journal, issn.as_deref()).await;
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    if done.is_multiple_of(50) {
+                        info!(done = done, total = total, "Queried journals");
+                    }
+                    (journal.clone(), metrics)
+                }
+            })
+            .collect();
+        let results: HashMap<String, Option<RankingMetrics>> =
+            stream::iter(lookups).buffer_unordered(concurrency.max(1)).collect().await;
+
+        let found = results.values().filter(|m| m.is_some()).count();
+        info!(total = total, found = found, "Batch EasyScholar lookup complete");
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Err(e) = disk_cache.save_async().await {
+                warn!(path = ?disk_cache.path(), error = %e, "Failed to write disk cache");
+            }
         }
 
-        // Update last request time
-        if let Ok(mut last) = self.last_request.lock() {
-            *last = Some(Instant::now());
-        }
+        results
     }
 
     /// Internal request implementation
-    async fn do_request(&self, venue_name: &str) -> Option<RankingMetrics> {
+    ///
+    /// Returns `Ok(None)` when EasyScholar has no data for the venue, and
+    /// `Err` for network/API failures that should not be cached.
+    async fn do_request(&self, venue_name: &str) -> Result<Option<RankingMetrics>> {
         debug!(venue = venue_name, "Querying EasyScholar");
 
-        let response = self
+        let _slot = throttle::acquire(Upstream::EasyScholar).await;
+        let request = self
             .client
             .get(EASYSCHOLAR_API_URL)
             .query(&[
                 ("secretKey", self.secret_key.as_str()),
                 ("publicationName", venue_name),
             ])
-            .send()
-            .await
-            .ok()?;
+            .build()?;
+        let request_url = request.url().clone();
+        let response = http::send_to(Upstream::EasyScholar, &self.client, request).await?;
 
         if !response.status().is_success() {
             warn!(
@@ -134,16 +333,16 @@ impl RankingClient {
                 status = response.status().as_u16(),
                 "EasyScholar API error"
             );
-            return None;
+            return Err(GscholarError::Api {
+                code: response.status().as_u16() as i32,
+                message: format!("EasyScholar API error: {}", response.status()),
+            });
         }
 
-        let data: EasyScholarResponse = match response.json().await {
-            Ok(d) => d,
-            Err(e) => {
-                warn!(venue = venue_name, error = %e, "Failed to parse response");
-                return None;
-            }
-        };
+        let body = response.text().await?;
+        raw_archive::record(request_url.as_str(), BodyKind::Json, &body);
+        let data: EasyScholarResponse = serde_json::from_str(&body)
+            .map_err(|e| GscholarError::Parse(format!("Failed to parse EasyScholar response: {}", e)))?;
 
         if data.code != 200 {
             warn!(
@@ -152,7 +351,10 @@ impl RankingClient {
                 msg = data.msg.as_deref().unwrap_or("Unknown"),
                 "EasyScholar API returned error"
             );
-            return None;
+            return Err(GscholarError::Api {
+                code: data.code,
+                message: data.msg.unwrap_or_else(|| "Unknown".to_string()),
+            });
         }
 
         let result = data.data.map(|d| extract_metrics(&d));
@@ -163,7 +365,7 @@ impl RankingClient {
             debug!(venue = venue_name, "No ranking data found");
         }
         
-        result
+        Ok(result)
     }
 
     /// Get a specific metric from ranking data
@@ -180,6 +382,17 @@ impl RankingClient {
             "sciUpTop" => metrics.sci_up_top.clone(),
             "sciBase" => metrics.sci_base.clone(),
             "sciUp" => metrics.sci_up.clone(),
+            "sciif5" => metrics.sciif5.clone(),
+            "ssci" => metrics.ssci.clone(),
+            "esi" => metrics.esi.clone(),
+            "sciwarn" => metrics.sci_warn.clone(),
+            "zky" => metrics.zky.clone(),
+            "pku" => metrics.pku.clone(),
+            "cssci" => metrics.cssci.clone(),
+            "cscd" => metrics.cscd.clone(),
+            "eii" => metrics.eii.clone(),
+            "ccf" => metrics.ccf.clone(),
+            "core" => metrics.core.clone(),
             _ => None,
         }
     }
@@ -210,6 +423,97 @@ impl RankingClient {
     }
 }
 
+/// Load journal aliases from a JSON object file
+///
+/// Format: `{"Comput. Geotech.": "Computers and Geotechnics", ...}`
+pub fn load_aliases(path: &Path) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path)?;
+    let aliases: HashMap<String, String> = serde_json::from_str(&content)?;
+    info!(count = aliases.len(), path = ?path, "Loaded journal aliases");
+    Ok(aliases)
+}
+
+/// Normalize a journal name into a comparison key
+///
+/// Lowercases, replaces "&" with "and", drops punctuation and a leading
+/// "the", and collapses whitespace.
+pub fn normalize_journal_name(name: &str) -> String {
+    let replaced = name.to_lowercase().replace('&', " and ");
+    let cleaned: String = replaced
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+    let words = match words.first() {
+        Some(&"the") if words.len() > 1 => &words[1..],
+        _ => &words[..],
+    };
+    words.join(" ")
+}
+
+/// Normalize an ISSN to the `NNNN-NNNX` form
+///
+/// Returns None if the input is not a valid-looking ISSN
+pub fn normalize_issn(issn: &str) -> Option<String> {
+    let compact: String = issn
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    let valid = compact.len() == 8
+        && compact[..7].chars().all(|c| c.is_ascii_digit())
+        && compact[7..].chars().all(|c| c.is_ascii_digit() || c == 'X');
+    valid.then(|| format!("{}-{}", &compact[..4], &compact[4..]))
+}
+
+/// Generate spelling variants of a venue name to try in order
+///
+/// Includes the trimmed original, "&"/"and" swaps and the name without a
+/// leading "The". Abbreviated names are matched to full ones separately
+/// (see [`abbreviates`]), since expanding them word by word does not give
+/// a name EasyScholar knows.
+pub fn name_variants(name: &str) -> Vec<String> {
+    let original = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut variants = vec![original.clone()];
+
+    if original.contains('&') {
+        variants.push(original.replace('&', "and"));
+    } else if original.contains(" and ") {
+        variants.push(original.replace(" and ", " & "));
+    }
+
+    if let Some(rest) = original.strip_prefix("The ").or_else(|| original.strip_prefix("the ")) {
+        variants.push(rest.to_string());
+    }
+
+    variants.dedup();
+    variants
+}
+
+/// Whether `abbreviated` is an ISO 4 style abbreviation of `full`
+/// ("Comput. Geotech." of "Computers and Geotechnics")
+///
+/// Connectives are ignored on both sides; every other word must match in
+/// order, an abbreviated word ("Geotech.") as a prefix of the full one.
+pub fn abbreviates(abbreviated: &str, full: &str) -> bool {
+    let words = |name: &str| -> Vec<(String, bool)> {
+        name.replace('&', " and ")
+            .split(|c: char| c.is_whitespace() || c == '-' || c == ',' || c == ':')
+            .filter_map(|word| {
+                let bare: String = word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+                (!bare.is_empty() && !CONNECTIVES.contains(&bare.as_str())).then(|| (bare, word.ends_with('.')))
+            })
+            .collect()
+    };
+    let (short, long) = (words(abbreviated), words(full));
+    short.len() == long.len()
+        && short.iter().any(|(_, abbreviation)| *abbreviation)
+        && short
+            .iter()
+            .zip(&long)
+            .all(|((word, abbreviation), (full_word, _))| word == full_word || (*abbreviation && full_word.starts_with(word.as_str())))
+}
+
 // === EasyScholar API Response Types ===
 
 #[derive(Debug, Deserialize)]
@@ -250,6 +554,16 @@ fn extract_metrics(data: &EasyScholarData) -> RankingMetrics {
         metrics.sci_up_top = get_value(select, all, "sciUpTop");
         metrics.sci_base = get_value(select, all, "sciBase");
         metrics.sci_up = get_value(select, all, "sciUp");
+        metrics.sciif5 = get_value(select, all, "sciif5");
+        metrics.ssci = get_value(select, all, "ssci");
+        metrics.esi = get_value(select, all, "esi");
+        metrics.sci_warn = get_value(select, all, "sciwarn");
+        metrics.zky = get_value(select, all, "zky");
+        metrics.pku = get_value(select, all, "pku");
+        metrics.cssci = get_value(select, all, "cssci");
+        metrics.cscd = get_value(select, all, "cscd");
+        metrics.eii = get_value(select, all, "eii");
+        metrics.ccf = get_value(select, all, "ccf");
     }
 
     metrics
@@ -307,4 +621,69 @@ mod tests {
         assert!(!RankingClient::passes_string_filter(Some("Q2"), "Q1"));
         assert!(!RankingClient::passes_string_filter(None, "Q1"));
     }
+
+    #[test]
+    fn test_extract_metrics_extended_fields() -> Result<()> {
+        let data: EasyScholarData = serde_json::from_str(
+            r#"{"officialRank": {"select": {"sciif": "6.3", "pku": "北大核心"},
+                "all": {"sciif": "6.1", "eii": "EI", "ccf": "A", "cssci": "CSSCI"}}}"#,
+        )?;
+        let metrics = extract_metrics(&data);
+        assert_eq!(metrics.sciif.as_deref(), Some("6.3"));
+        assert_eq!(metrics.pku.as_deref(), Some("北大核心"));
+        assert_eq!(metrics.eii.as_deref(), Some("EI"));
+        assert_eq!(RankingClient::get_metric(&metrics, "ccf").as_deref(), Some("A"));
+        assert_eq!(RankingClient::get_metric(&metrics, "cssci").as_deref(), Some("CSSCI"));
+        assert_eq!(RankingClient::get_metric(&metrics, "zky"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_journal_name() {
+        assert_eq!(
+            normalize_journal_name("The Journal of Rock Mechanics & Geotechnical Engineering"),
+            "journal of rock mechanics and geotechnical engineering"
+        );
+        assert_eq!(
+            normalize_journal_name("  IEEE  Trans. Geosci. Remote-Sens. "),
+            "ieee trans geosci remote sens"
+        );
+        assert_eq!(normalize_issn("0266352x"), Some("0266-352X".to_string()));
+        assert_eq!(normalize_issn("0266-352X"), Some("0266-352X".to_string()));
+        assert_eq!(normalize_issn("not an issn"), None);
+    }
+
+    #[test]
+    fn test_lookup_candidates() -> Result<()> {
+        let mut aliases = HashMap::new();
+        aliases.insert("Comput. Geotech.".to_string(), "Computers and Geotechnics".to_string());
+        let client = RankingClient::new("key".to_string())?.with_aliases(aliases);
+
+        let candidates = client.lookup_candidates("comput geotech", Some("0266352X"));
+        assert_eq!(candidates[0], "0266-352X");
+        assert_eq!(candidates[1], "Computers and Geotechnics");
+        assert_eq!(candidates[2], "comput geotech");
+
+        let variants = name_variants("J. Hydrol. & Earth");
+        assert_eq!(variants, ["J. Hydrol. & Earth", "J. Hydrol. and Earth"]);
+
+        // Abbreviated names resolve to a full name seen in the same run
+        client.remember_full_names(["Journal of Hydrology", "Engineering Geology", "Eng. Geol."]);
+        assert_eq!(client.lookup_candidates("J. Hydrol.", None), ["Journal of Hydrology", "J. Hydrol."]);
+        assert_eq!(client.lookup_candidates("Comput. Geotech.", None)[0], "Computers and Geotechnics");
+        assert_eq!(client.lookup_candidates("Landslides", None), ["Landslides"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abbreviates() {
+        assert!(abbreviates("Comput. Geotech.", "Computers and Geotechnics"));
+        assert!(abbreviates("J. Hydrol.", "Journal of Hydrology"));
+        assert!(abbreviates("Q. J. Eng. Geol. Hydrogeol.", "Quarterly Journal of Engineering Geology and Hydrogeology"));
+        assert!(abbreviates("Int. J. Rock Mech. Min. Sci.", "International Journal of Rock Mechanics and Mining Sciences"));
+        assert!(!abbreviates("J. Hydrol.", "Hydrological Processes"));
+        assert!(!abbreviates("J. Hydrol.", "Journal of Hydrology: Regional Studies"));
+        assert!(!abbreviates("Eng. Geol.", "Engineering"));
+        assert!(!abbreviates("Engineering Geology", "Engineering Geology"));
+    }
 }
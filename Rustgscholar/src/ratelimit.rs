@@ -0,0 +1,274 @@
+//! Per-host request pacing.
+//!
+//! Every host gets one [`TokenBucket`] for the whole process, so all
+//! clients (and all tasks of one client) that talk to it share its rate:
+//! up to `burst` requests at once, refilled at one token per `interval`,
+//! plus an optional random `jitter` before each request. Known upstreams
+//! start at the rate they document (see [`Rate::for_host`]); other hosts
+//! are not paced until they answer 429.
+//!
+//! Pacing adapts to the server: a 429 pauses the host for its
+//! `Retry-After` (or the new interval) and doubles the interval, and each
+//! successful response then brings it back towards the starting rate.
+//! [`crate::http::send`] does both for every request it sends; clients with
+//! their own request loop call [`acquire`] and [`record`] themselves. An
+//! API key with its own limit gets a separate [`key_bucket`] on the host.
+//!
+//! [`crate::throttle`] only adds a cap on how many requests the jobs of
+//! `serve` have in flight per upstream, taken from the same [`Rate`]s.
+
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Interval a 429 raises an unpaced host to at least
+const MIN_ADAPTIVE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest interval after repeated 429s
+const MAX_ADAPTIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Share of the raised interval kept after each successful response
+const RECOVERY: f64 = 0.9;
+
+/// Starting rate of a host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    /// Requests that may start at once
+    pub burst: u32,
+    /// Time to refill one token; zero for no pacing
+    pub interval: Duration,
+    /// Random extra wait (up to this long) before each request
+    pub jitter: Duration,
+}
+
+impl Rate {
+    /// No pacing until the host answers 429
+    pub const UNLIMITED: Rate = Rate { burst: 1, interval: Duration::ZERO, jitter: Duration::ZERO };
+
+    /// Google Scholar: one page every 0.5-2 s, like a person paging through results
+    pub const SCHOLAR: Rate = Rate::every(Duration::from_millis(500)).with_jitter(Duration::from_millis(1500));
+
+    /// Crossref: the polite pool asks for no more than 50 requests per second
+    pub const CROSSREF: Rate = Rate::every(Duration::from_millis(50)).with_burst(5);
+
+    /// OpenAlex: 10 requests per second
+    pub const OPENALEX: Rate = Rate::every(Duration::from_millis(100)).with_burst(10);
+
+    /// EasyScholar: slightly more than the 0.5 s the API asks for, to be safe
+    pub const EASYSCHOLAR: Rate = Rate::every(Duration::from_millis(600));
+
+    /// Semantic Scholar: 1 request per second in the shared pool; a key's
+    /// rate is set by the client
+    pub const SEMANTIC_SCHOLAR: Rate = Rate::every(Duration::from_secs(1));
+
+    /// One request per `interval`
+    pub const fn every(interval: Duration) -> Self {
+        Rate { burst: 1, interval, jitter: Duration::ZERO }
+    }
+
+    pub const fn with_burst(self, burst: u32) -> Self {
+        Rate { burst, ..self }
+    }
+
+    pub const fn with_jitter(self, jitter: Duration) -> Self {
+        Rate { jitter, ..self }
+    }
+
+    /// Documented rate of a known upstream host
+    pub fn for_host(host: &str) -> Rate {
+        match host {
+            "scholar.google.com" => Rate::SCHOLAR,
+            "api.crossref.org" => Rate::CROSSREF,
+            "api.openalex.org" => Rate::OPENALEX,
+            "www.easyscholar.cc" => Rate::EASYSCHOLAR,
+            "api.semanticscholar.org" => Rate::SEMANTIC_SCHOLAR,
+            _ => Rate::UNLIMITED,
+        }
+    }
+}
+
+/// Token-bucket rate limiter with adaptive 429 handling
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: Rate,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+    /// Current refill interval (the rate's, or more after a 429)
+    interval: Duration,
+    /// No request starts before this after a 429
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: Rate) -> Self {
+        let burst = rate.burst.max(1) as f64;
+        Self {
+            rate,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                last: Instant::now(),
+                interval: rate.interval,
+                paused_until: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Current refill interval
+    pub fn interval(&self) -> Duration {
+        self.lock().interval
+    }
+
+    /// Wait until a token is available and take it
+    pub async fn acquire(&self) {
+        let burst = self.rate.burst.max(1) as f64;
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                let now = Instant::now();
+                match state.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ => {
+                        state.tokens = if state.interval.is_zero() {
+                            burst
+                        } else {
+                            let refill = (now - state.last).as_secs_f64() / state.interval.as_secs_f64();
+                            (state.tokens + refill).min(burst)
+                        };
+                        state.last = now;
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            break;
+                        }
+                        state.interval.mul_f64(1.0 - state.tokens)
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+        if !self.rate.jitter.is_zero() {
+            tokio::time::sleep(self.rate.jitter.mul_f64(rand::random::<f64>())).await;
+        }
+    }
+
+    /// The server answered 429: pause for `retry_after` (or the new
+    /// interval) and halve the rate
+    pub fn throttled(&self, retry_after: Option<Duration>) {
+        let mut state = self.lock();
+        state.interval = (state.interval * 2).clamp(MIN_ADAPTIVE_INTERVAL, MAX_ADAPTIVE_INTERVAL);
+        state.tokens = 0.0;
+        let pause = retry_after.unwrap_or(state.interval).min(MAX_ADAPTIVE_INTERVAL);
+        let until = Instant::now() + pause;
+        state.paused_until = Some(until);
+        state.last = until;
+        warn!(interval_ms = state.interval.as_millis() as u64, pause_secs = pause.as_secs_f64(), "Rate limited, slowing down");
+    }
+
+    /// Adapt to a response status: slow down on 429, recover on success
+    pub fn record(&self, status: StatusCode, retry_after: Option<Duration>) {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            self.throttled(retry_after);
+        } else if !status.is_server_error() {
+            self.succeeded();
+        }
+    }
+
+    /// A request went through: move back towards the starting rate
+    pub fn succeeded(&self) {
+        let mut state = self.lock();
+        if state.interval > self.rate.interval {
+            let next = state.interval.mul_f64(RECOVERY);
+            state.interval = if next < self.rate.interval.max(MIN_ADAPTIVE_INTERVAL) { self.rate.interval } else { next };
+        }
+    }
+}
+
+static BUCKETS: OnceLock<Mutex<HashMap<String, Arc<TokenBucket>>>> = OnceLock::new();
+
+/// Shared bucket of `host`, created at the host's known rate
+pub fn bucket(host: &str) -> Arc<TokenBucket> {
+    bucket_with(host, Rate::for_host(host))
+}
+
+/// Shared bucket of `host`, created at `rate` if it does not exist yet
+/// (e.g. a Scholar mirror paced like Scholar)
+pub fn bucket_with(host: &str, rate: Rate) -> Arc<TokenBucket> {
+    let buckets = BUCKETS.get_or_init(Default::default);
+    let mut buckets = buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    buckets
+        .entry(host.to_ascii_lowercase())
+        .or_insert_with(|| {
+            debug!(host, interval_ms = rate.interval.as_millis() as u64, burst = rate.burst, "Pacing host");
+            Arc::new(TokenBucket::new(rate))
+        })
+        .clone()
+}
+
+/// Bucket of one API key on `host`, created at `rate` (the key's own
+/// limit): clients with the same key share it, and neither the host's
+/// shared bucket nor other keys are affected
+pub fn key_bucket(host: &str, key: &str, rate: Rate) -> Arc<TokenBucket> {
+    bucket_with(&format!("{}#{:x}", host, Sha256::digest(key.as_bytes())), rate)
+}
+
+/// Wait for a token of the URL's host
+pub async fn acquire(url: &Url) {
+    if let Some(host) = url.host_str() {
+        bucket(host).acquire().await;
+    }
+}
+
+/// Adapt the pacing of the URL's host to a response status
+pub fn record(url: &Url, status: StatusCode, retry_after: Option<Duration>) {
+    let Some(host) = url.host_str() else {
+        return;
+    };
+    bucket(host).record(status, retry_after);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_bucket_spacing() {
+        let bucket = TokenBucket::new(Rate::every(Duration::from_millis(50)));
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            bucket.acquire().await;
+        }
+        // First token is immediate, the next two wait one interval each
+        assert!(start.elapsed() >= Duration::from_millis(95));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_interval() {
+        let bucket = TokenBucket::new(Rate::UNLIMITED);
+        bucket.throttled(Some(Duration::from_millis(30)));
+        assert_eq!(bucket.interval(), MIN_ADAPTIVE_INTERVAL);
+        let start = std::time::Instant::now();
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        bucket.throttled(None);
+        assert_eq!(bucket.interval(), MIN_ADAPTIVE_INTERVAL * 2);
+        // Successes bring the interval back to the starting rate
+        for _ in 0..10 {
+            bucket.succeeded();
+        }
+        assert_eq!(bucket.interval(), Duration::ZERO);
+        assert_eq!(Rate::for_host("api.openalex.org").burst, 10);
+    }
+}
@@ -0,0 +1,279 @@
+//! Raw upstream responses of a run (`--save-raw`).
+//!
+//! With `--save-raw` every HTML page and JSON API response a run fetches
+//! is kept, gzipped, under `raw/<stage>/` in the output folder, and
+//! listed in `raw/index.jsonl` with its URL and fetch time, so a parsing
+//! bug can be reproduced from the exact bytes received and a review can
+//! archive its evidence trail. Responses served from the HTTP cache were
+//! not fetched by the run and are not saved again.
+//!
+//! The archive travels with the run as a task-local value: the pipeline
+//! enters it with [`scope`] and names the stage with [`in_stage`], and the
+//! API clients hand each body to [`record`], which does nothing outside a
+//! scope. Concurrent `serve` jobs thus never write into each other's
+//! folders, and Stage 2 lookups running alongside the Stage 1 search land
+//! in their own stage folder.
+//!
+//! [`record`] only queues the body: one writer on a blocking thread owns
+//! the folder, gzips each body (flate2) and appends its line to
+//! `index.jsonl`, so the async workers never wait on the disk and index
+//! lines never interleave. A failed write is logged and never fails the
+//! run; [`RawArchive::flush`] waits until everything queued is written.
+
+use crate::error::Result;
+use chrono::Local;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+/// Directory of the archive in the output folder
+pub const RAW_DIR: &str = "raw";
+
+/// Index of the saved responses, one JSON object per line
+pub const INDEX_FILE: &str = "index.jsonl";
+
+/// Query parameters that carry API keys, left out of the saved URLs
+const KEY_PARAMS: &[&str] = &["api_key", "apikey", "secretKey", "key"];
+
+tokio::task_local! {
+    static ARCHIVE: RawArchive;
+}
+
+/// Format of a response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Html,
+    Json,
+}
+
+impl BodyKind {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Archive folder of a run, and the stage responses are saved under
+#[derive(Debug, Clone)]
+pub struct RawArchive {
+    stage: String,
+    /// Shared by every stage, so file names sort in fetch order
+    saved: Arc<AtomicUsize>,
+    writer: mpsc::UnboundedSender<Job>,
+}
+
+/// Line of `index.jsonl`
+#[derive(Debug, Serialize)]
+struct IndexEntry {
+    file: String,
+    stage: String,
+    url: String,
+    fetched_at: String,
+    bytes: usize,
+}
+
+/// Work for the writer thread
+#[derive(Debug)]
+enum Job {
+    Save { entry: IndexEntry, body: String },
+    Flush(oneshot::Sender<()>),
+}
+
+impl RawArchive {
+    /// Archive under `root` (created on the first save), with its writer
+    /// on a blocking thread of the current runtime
+    pub fn new(root: PathBuf) -> Self {
+        let (writer, jobs) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || write_jobs(&root, jobs));
+        Self { stage: "other".to_string(), saved: Arc::new(AtomicUsize::new(0)), writer }
+    }
+
+    /// Number of responses saved so far (queued ones included)
+    pub fn saved(&self) -> usize {
+        self.saved.load(Ordering::Relaxed)
+    }
+
+    /// Wait until every response queued so far is written
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        if self.writer.send(Job::Flush(done)).is_ok() {
+            let _ = written.await;
+        }
+    }
+
+    fn for_stage(&self, stage: &str) -> Self {
+        Self { stage: stage.to_string(), ..self.clone() }
+    }
+
+    /// Queue one body for the writer
+    fn save(&self, url: &str, kind: BodyKind, body: &str) {
+        let number = self.saved.fetch_add(1, Ordering::Relaxed) + 1;
+        let url = strip_keys(url);
+        let host = url::Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from))
+            .unwrap_or_else(|| "response".to_string());
+        let entry = IndexEntry {
+            file: format!("{}/{:05}_{}.{}.gz", self.stage, number, host, kind.extension()),
+            stage: self.stage.clone(),
+            url,
+            fetched_at: Local::now().to_rfc3339(),
+            bytes: body.len(),
+        };
+        if self.writer.send(Job::Save { entry, body: body.to_string() }).is_err() {
+            warn!("Raw response writer stopped, response not saved");
+        }
+    }
+}
+
+/// Write queued responses until every [`RawArchive`] clone is dropped
+fn write_jobs(root: &Path, mut jobs: mpsc::UnboundedReceiver<Job>) {
+    while let Some(job) = jobs.blocking_recv() {
+        match job {
+            Job::Save { entry, body } => match write_response(root, &entry, &body) {
+                Ok(path) => debug!(path = %path.display(), "Saved raw response"),
+                Err(e) => warn!(url = %entry.url, error = %e, "Failed to save raw response"),
+            },
+            Job::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Gzip one body into the archive and add it to the index
+fn write_response(root: &Path, entry: &IndexEntry, body: &str) -> Result<PathBuf> {
+    let path = root.join(&entry.file);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut encoder = GzEncoder::new(std::fs::File::create(&path)?, Compression::default());
+    encoder.write_all(body.as_bytes())?;
+    encoder.finish()?;
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut index = std::fs::OpenOptions::new().create(true).append(true).open(root.join(INDEX_FILE))?;
+    index.write_all(line.as_bytes())?;
+    Ok(path)
+}
+
+/// Run `future` with `archive` (without one when `None`)
+pub async fn scope<F: Future>(archive: Option<RawArchive>, future: F) -> F::Output {
+    match archive {
+        Some(archive) => ARCHIVE.scope(archive, future).await,
+        None => future.await,
+    }
+}
+
+/// Run `future` saving its responses under `stage`, when in a [`scope`]
+pub async fn in_stage<F: Future>(stage: &str, future: F) -> F::Output {
+    match ARCHIVE.try_with(|archive| archive.for_stage(stage)) {
+        Ok(archive) => ARCHIVE.scope(archive, future).await,
+        Err(_) => future.await,
+    }
+}
+
+/// Save a response body fetched from `url`, when in a [`scope`]
+pub fn record(url: &str, kind: BodyKind, body: &str) {
+    let _ = ARCHIVE.try_with(|archive| archive.save(url, kind, body));
+}
+
+/// `url` without its API key parameters
+fn strip_keys(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !KEY_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_and_stages() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        // Outside a scope nothing is written
+        record("https://api.openalex.org/works", BodyKind::Json, "{}");
+        assert!(!dir.path().join(RAW_DIR).exists());
+
+        let archive = RawArchive::new(dir.path().join(RAW_DIR));
+        scope(Some(archive.clone()), async {
+            in_stage("1_search", async {
+                record("https://serpapi.com/search.json?q=landslide&api_key=secret", BodyKind::Json, "{}");
+            })
+            .await;
+            in_stage("2_crossref", async { record("https://api.crossref.org/works?rows=1", BodyKind::Json, "{}") })
+                .await;
+        })
+        .await;
+        archive.flush().await;
+
+        assert_eq!(archive.saved(), 2);
+        let root = dir.path().join(RAW_DIR);
+        assert!(root.join("1_search/00001_serpapi.com.json.gz").is_file());
+        assert!(root.join("2_crossref/00002_api.crossref.org.json.gz").is_file());
+        let index = std::fs::read_to_string(root.join(INDEX_FILE))?;
+        assert_eq!(index.lines().count(), 2);
+        assert!(index.contains("q=landslide") && !index.contains("secret"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gzip_round_trip() -> Result<()> {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir()?;
+        let archive = RawArchive::new(dir.path().to_path_buf());
+        let body = r#"{"results": [{"title": "Landslide susceptibility"}, {"title": "Débris"}]}"#.repeat(500);
+        // Many concurrent tasks append to one index
+        let tasks: Vec<_> = (0..20)
+            .map(|i| {
+                let archive = archive.clone();
+                let body = format!("{}{}", body, i);
+                tokio::spawn(scope(Some(archive), async move { record("https://api.openalex.org/works", BodyKind::Json, &body) }))
+            })
+            .collect();
+        for task in tasks {
+            task.await.map_err(std::io::Error::other)?;
+        }
+        archive.flush().await;
+
+        let index = std::fs::read_to_string(dir.path().join(INDEX_FILE))?;
+        assert_eq!(index.lines().count(), 20);
+        for line in index.lines() {
+            let entry: serde_json::Value = serde_json::from_str(line)?;
+            let file = entry["file"].as_str().unwrap_or_default();
+            let compressed = std::fs::read(dir.path().join(file))?;
+            assert!(compressed.len() < body.len() / 10);
+            let mut decoded = String::new();
+            flate2::read::GzDecoder::new(compressed.as_slice()).read_to_string(&mut decoded)?;
+            assert!(decoded.starts_with(&body));
+            assert_eq!(entry["bytes"].as_u64(), Some(decoded.len() as u64));
+        }
+        Ok(())
+    }
+}
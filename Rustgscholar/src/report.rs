@@ -0,0 +1,443 @@
+//! Shareable reports of the pipeline results.
+//!
+//! [`render_html`] produces one self-contained HTML file with the papers
+//! embedded as JSON and a small script for sorting, filtering (label,
+//! journal, IF, free text), and expanding rows to show abstracts, so results
+//! can be shared with collaborators who won't open CSVs. No external assets
+//! are loaded.
+//!
+//! [`render_markdown`] produces a literature digest of the relevant papers,
+//! grouped by topic and year, for pasting into Obsidian/Notion or a lab wiki.
+
+use crate::doi;
+use crate::error::Result;
+use crate::llm_filter::{FilterResult, VerdictIndex};
+use crate::paper::Paper;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Report file name inside a run folder
+pub const REPORT_FILE: &str = "report.html";
+
+/// Markdown digest file name inside a run folder
+pub const DIGEST_FILE: &str = "REPORT.md";
+
+/// Authors listed in the digest before "et al."
+const DIGEST_AUTHORS: usize = 3;
+
+/// One table row: a unified paper with its LLM verdict, if any
+#[derive(Debug, Serialize)]
+struct ReportRow<'a> {
+    title: &'a str,
+    author: String,
+    date: String,
+    journal: &'a str,
+    if_score: Option<f64>,
+    jci_score: Option<f64>,
+    sci_partition: &'a str,
+    doi: &'a str,
+    article_url: &'a str,
+    pdf_url: &'a str,
+    abstract_text: &'a str,
+    tldr: &'a str,
+    label: &'a str,
+    confidence: Option<f64>,
+    reason: &'a str,
+}
+
+/// Render the report for a search keyword
+///
+/// Verdicts are matched to papers by DOI (or title when there is no DOI).
+pub fn render_html(keyword: &str, papers: &[Paper], verdicts: &[FilterResult]) -> Result<String> {
+    let verdict_index = VerdictIndex::new(verdicts);
+
+    let rows: Vec<ReportRow> = papers
+        .iter()
+        .map(|p| {
+            let verdict = verdict_index.get(p);
+            ReportRow {
+                title: &p.title,
+                author: p.author_list(),
+                date: p.date_string(),
+                journal: &p.journal,
+                if_score: p.if_score(),
+                jci_score: p.jci_score(),
+                sci_partition: p.sci_partition(),
+                doi: &p.doi,
+                article_url: &p.article_url,
+                pdf_url: &p.pdf_url,
+                abstract_text: &p.abstract_text,
+                tldr: &p.tldr,
+                label: verdict.map(|v| v.label.as_str()).unwrap_or_default(),
+                confidence: verdict.map(|v| v.confidence),
+                reason: verdict.map(|v| v.reason.as_str()).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    // "</" inside the JSON would end the <script> element early
+    let data = serde_json::to_string(&rows)?.replace("</", "<\\/");
+
+    Ok(TEMPLATE
+        .replace("{{TITLE}}", &escape_html(keyword))
+        .replace("{{GENERATED}}", &chrono::Local::now().format("%Y-%m-%d %H:%M").to_string())
+        .replace("{{DATA}}", &data))
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render the Markdown digest of relevant papers
+///
+/// `tags` maps lowercase DOIs to Stage 8 topics. When any paper is tagged,
+/// the digest has one section per topic (papers with several topics appear
+/// under each, untagged ones under "Other") with year subsections;
+/// otherwise it is grouped by year only. Newest years come first, and papers
+/// within a year are ordered by IF.
+pub fn render_markdown(keyword: &str, papers: &[&Paper], tags: &HashMap<String, Vec<String>>) -> String {
+    let mut out = format!(
+        "# {}\n\n{} relevant papers · generated {}\n",
+        escape_markdown(keyword),
+        papers.len(),
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+
+    let paper_tags = |p: &Paper| tags.get(&doi::normalize(&p.doi)).filter(|t| !t.is_empty());
+    if papers.iter().any(|p| paper_tags(p).is_some()) {
+        let mut topics: Vec<&str> = tags.values().flatten().map(String::as_str).collect();
+        topics.sort_unstable();
+        topics.dedup();
+        topics.push("Other");
+
+        for topic in topics {
+            let members: Vec<&Paper> = papers
+                .iter()
+                .copied()
+                .filter(|p| match paper_tags(p) {
+                    Some(t) => t.iter().any(|t| t == topic),
+                    None => topic == "Other",
+                })
+                .collect();
+            if !members.is_empty() {
+                out.push_str(&format!("\n## {} ({})\n", escape_markdown(topic), members.len()));
+                push_years(&mut out, &members, "###");
+            }
+        }
+    } else {
+        push_years(&mut out, papers, "##");
+    }
+
+    out
+}
+
+/// Append year sections (newest first) with one entry per paper
+fn push_years(out: &mut String, papers: &[&Paper], heading: &str) {
+    let year = |p: &Paper| p.year.map(|y| y.to_string()).unwrap_or_default();
+    let if_score = |p: &Paper| p.if_score().unwrap_or(-1.0);
+
+    let mut sorted = papers.to_vec();
+    sorted.sort_by(|a, b| {
+        year(b)
+            .cmp(&year(a))
+            .then(if_score(b).total_cmp(&if_score(a)))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+
+    let mut current = None;
+    for paper in sorted {
+        let paper_year = year(paper);
+        if current.as_ref() != Some(&paper_year) {
+            let label = if paper_year.is_empty() { "Unknown year" } else { &paper_year };
+            out.push_str(&format!("\n{} {}\n\n", heading, label));
+            current = Some(paper_year);
+        }
+        out.push_str(&digest_entry(paper));
+    }
+}
+
+/// One list item: title, authors, venue, IF, DOI link and TL;DR
+fn digest_entry(paper: &Paper) -> String {
+    let mut parts = vec![format!("**{}**", escape_markdown(paper.title.trim()))];
+
+    let authors: Vec<&str> = paper.authors.iter().map(|a| a.name.as_str()).collect();
+    if !authors.is_empty() {
+        let mut names = authors[..authors.len().min(DIGEST_AUTHORS)].join(", ");
+        if authors.len() > DIGEST_AUTHORS || paper.more_authors {
+            names.push_str(" et al.");
+        }
+        parts.push(escape_markdown(&names));
+    }
+    if !paper.journal.trim().is_empty() {
+        parts.push(format!("*{}*", escape_markdown(paper.journal.trim())));
+    }
+    if let Some(if_score) = paper.if_score() {
+        parts.push(format!("IF {}", if_score));
+    }
+    let doi = paper.doi.trim();
+    if !doi.is_empty() {
+        parts.push(format!("[{}](https://doi.org/{})", escape_markdown(doi), doi.replace(' ', "%20").replace(')', "%29")));
+    }
+
+    let mut entry = format!("- {}\n", parts.join(" · "));
+    if !paper.tldr.trim().is_empty() {
+        entry.push_str(&format!("  > {}\n", escape_markdown(paper.tldr.trim())));
+    }
+    entry
+}
+
+/// Escape characters that would otherwise be read as Markdown formatting
+fn escape_markdown(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' | '\r' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{TITLE}} - rustgscholar report</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 1.5rem; color: #222; }
+h1 { font-size: 1.4rem; margin-bottom: 0.2rem; }
+.meta { color: #666; margin-bottom: 1rem; }
+.filters { display: flex; flex-wrap: wrap; gap: 0.8rem; margin-bottom: 1rem; align-items: center; }
+.filters input, .filters select { padding: 0.3rem; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9rem; }
+th { background: #f3f3f3; cursor: pointer; user-select: none; position: sticky; top: 0; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4rem; text-align: left; vertical-align: top; }
+th.sorted::after { content: attr(data-dir); }
+tr.paper { cursor: pointer; }
+tr.paper:hover { background: #fafafa; }
+tr.detail td { background: #fcfcf7; white-space: pre-wrap; }
+.label { padding: 0.1rem 0.4rem; border-radius: 3px; font-size: 0.8rem; }
+.label-relevant { background: #d4edda; }
+.label-irrelevant { background: #f8d7da; }
+.label-uncertain { background: #fff3cd; }
+a { color: #0645ad; }
+</style>
+</head>
+<body>
+<h1>{{TITLE}}</h1>
+<div class="meta">Generated {{GENERATED}} · <span id="count"></span> papers</div>
+<div class="filters">
+  <input id="q" type="search" placeholder="Search title/abstract">
+  <label>Label <select id="label"><option value="">All</option></select></label>
+  <label>Journal <input id="journal" list="journals" placeholder="Any"></label>
+  <datalist id="journals"></datalist>
+  <label>IF ≥ <input id="minIf" type="number" step="0.1" min="0" style="width:5rem"></label>
+</div>
+<table>
+  <thead><tr>
+    <th data-key="title">Title</th>
+    <th data-key="journal">Journal</th>
+    <th data-key="date">Date</th>
+    <th data-key="if_score">IF</th>
+    <th data-key="sci_partition">SCI</th>
+    <th data-key="label">Label</th>
+    <th data-key="confidence">Conf.</th>
+    <th>Links</th>
+  </tr></thead>
+  <tbody id="rows"></tbody>
+</table>
+<script id="data" type="application/json">{{DATA}}</script>
+<script>
+const papers = JSON.parse(document.getElementById("data").textContent);
+const $ = (id) => document.getElementById(id);
+let sortKey = "if_score", sortDir = -1;
+
+for (const label of [...new Set(papers.map((p) => p.label).filter(Boolean))].sort()) {
+  $("label").append(new Option(label, label));
+}
+for (const journal of [...new Set(papers.map((p) => p.journal).filter(Boolean))].sort()) {
+  $("journals").append(new Option(journal));
+}
+
+function cell(row, content) {
+  const td = document.createElement("td");
+  if (content instanceof Node) td.append(content); else td.textContent = content ?? "";
+  row.append(td);
+  return td;
+}
+
+function link(text, href) {
+  const a = document.createElement("a");
+  a.textContent = text;
+  a.href = href;
+  a.target = "_blank";
+  a.rel = "noopener";
+  return a;
+}
+
+function compare(a, b) {
+  if (a == null || a === "") return 1;
+  if (b == null || b === "") return -1;
+  return typeof a === "number" ? a - b : String(a).localeCompare(String(b));
+}
+
+function render() {
+  const q = $("q").value.toLowerCase();
+  const label = $("label").value;
+  const journal = $("journal").value.toLowerCase();
+  const minIf = parseFloat($("minIf").value);
+
+  const rows = papers.filter((p) =>
+    (!q || (p.title + " " + p.abstract_text).toLowerCase().includes(q)) &&
+    (!label || p.label === label) &&
+    (!journal || p.journal.toLowerCase().includes(journal)) &&
+    (isNaN(minIf) || (p.if_score ?? -1) >= minIf));
+  rows.sort((a, b) => {
+    const empty = a[sortKey] == null || a[sortKey] === "" || b[sortKey] == null || b[sortKey] === "";
+    return empty ? compare(a[sortKey], b[sortKey]) : compare(a[sortKey], b[sortKey]) * sortDir;
+  });
+
+  const body = $("rows");
+  body.replaceChildren();
+  for (const p of rows) {
+    const tr = document.createElement("tr");
+    tr.className = "paper";
+    cell(tr, p.title);
+    cell(tr, p.journal);
+    cell(tr, p.date);
+    cell(tr, p.if_score);
+    cell(tr, p.sci_partition);
+    const badge = document.createElement("span");
+    badge.className = "label label-" + p.label;
+    badge.textContent = p.label;
+    cell(tr, badge);
+    cell(tr, p.confidence == null ? "" : p.confidence.toFixed(2));
+    const links = cell(tr, "");
+    if (p.doi) links.append(link("DOI", "https://doi.org/" + p.doi), " ");
+    if (/^https?:\/\//.test(p.pdf_url)) links.append(link("PDF", p.pdf_url), " ");
+    else if (/^https?:\/\//.test(p.article_url)) links.append(link("Web", p.article_url));
+    for (const a of links.querySelectorAll("a")) a.addEventListener("click", (e) => e.stopPropagation());
+
+    const detail = document.createElement("tr");
+    detail.className = "detail";
+    detail.hidden = true;
+    const text = [p.author, p.tldr && "TL;DR: " + p.tldr, p.abstract_text, p.reason && "LLM: " + p.reason]
+      .filter(Boolean).join("\n\n");
+    cell(detail, text).colSpan = 8;
+    tr.addEventListener("click", () => { detail.hidden = !detail.hidden; });
+
+    body.append(tr, detail);
+  }
+  $("count").textContent = rows.length + " / " + papers.length;
+
+  for (const th of document.querySelectorAll("th[data-key]")) {
+    th.classList.toggle("sorted", th.dataset.key === sortKey);
+    th.dataset.dir = sortDir > 0 ? " ▲" : " ▼";
+  }
+}
+
+for (const th of document.querySelectorAll("th[data-key]")) {
+  th.addEventListener("click", () => {
+    sortDir = th.dataset.key === sortKey ? -sortDir : 1;
+    sortKey = th.dataset.key;
+    render();
+  });
+}
+for (const id of ["q", "label", "journal", "minIf"]) $(id).addEventListener("input", render);
+render();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unified::UnifiedRow;
+
+    #[test]
+    fn test_render_html() -> Result<()> {
+        let paper = Paper::from(UnifiedRow {
+            title: "Landslides </script><b>".to_string(),
+            author: String::new(),
+            date: "2024".to_string(),
+            doi: "10.1/X".to_string(),
+            article_url: String::new(),
+            pdf_url: String::new(),
+            pdf_path: String::new(),
+            abstract_text: String::new(),
+            tldr: String::new(),
+            journal: "Landslides".to_string(),
+            if_score: "6.1".to_string(),
+            jci_score: String::new(),
+            sci_partition: "Q1".to_string(),
+            sources: String::new(),
+            ..Default::default()
+        });
+        let verdict = FilterResult {
+            id: "10.1/x".to_string(),
+            title: paper.title.clone(),
+            label: "relevant".to_string(),
+            confidence: 0.9,
+            evidence: String::new(),
+            reason: "On topic".to_string(),
+        };
+
+        let html = render_html("slope <failure>", &[paper], &[verdict])?;
+        assert!(html.contains("<h1>slope &lt;failure&gt;</h1>"));
+        assert!(html.contains(r#""if_score":6.1"#));
+        assert!(html.contains(r#""label":"relevant","confidence":0.9"#));
+        assert!(html.contains(r#"Landslides <\/script><b>"#));
+        assert_eq!(html.matches("</script>").count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let paper = |title: &str, date: &str, doi: &str, if_score: &str| Paper::from(UnifiedRow {
+            title: title.to_string(),
+            author: "Ann Lee; Bo Chen; Cy Diaz; Di Evans".to_string(),
+            date: date.to_string(),
+            doi: doi.to_string(),
+            article_url: String::new(),
+            pdf_url: String::new(),
+            pdf_path: String::new(),
+            abstract_text: String::new(),
+            tldr: "Short summary".to_string(),
+            journal: "Landslides".to_string(),
+            if_score: if_score.to_string(),
+            jci_score: String::new(),
+            sci_partition: String::new(),
+            sources: String::new(),
+            ..Default::default()
+        });
+        let a = paper("Rainfall *triggers*", "2023-05-01", "10.1/A", "6.1");
+        let b = paper("Slope stability", "2024", "10.1/b", "");
+        let c = paper("InSAR monitoring", "2023", "10.1/c", "7.5");
+
+        let md = render_markdown("landslide", &[&a, &b, &c], &HashMap::new());
+        assert!(md.starts_with("# landslide\n\n3 relevant papers"));
+        let order: Vec<usize> = ["## 2024", "## 2023", "InSAR", "Rainfall"]
+            .iter()
+            .filter_map(|s| md.find(s))
+            .collect();
+        assert!(order.len() == 4 && order.windows(2).all(|w| w[0] < w[1]));
+        assert!(md.contains(
+            "- **Rainfall \\*triggers\\*** · Ann Lee, Bo Chen, Cy Diaz et al. · *Landslides* · IF 6.1 · [10.1/A](https://doi.org/10.1/A)\n  > Short summary\n"
+        ));
+
+        let tags = HashMap::from([("10.1/a".to_string(), vec!["Rainfall".to_string()])]);
+        let md = render_markdown("landslide", &[&a, &b, &c], &tags);
+        assert!(md.contains("## Rainfall (1)\n\n### 2023\n"));
+        assert!(md.contains("## Other (2)\n\n### 2024\n"));
+    }
+}
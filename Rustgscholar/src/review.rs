@@ -0,0 +1,291 @@
+//! Human review of LLM-screened papers (`review`).
+//!
+//! Stage 7 writes the papers the LLM included to `7_relevant.csv` and the
+//! ones it was unsure about to `7_review.csv`. A [`ReviewSession`] loads
+//! both (papers awaiting review first), records an accept/reject decision
+//! per paper and saves them to `human_review.csv`, so a review can be
+//! interrupted and resumed. The final set, `9_final.csv`, holds the
+//! accepted papers plus the LLM's relevant papers nobody rejected. The
+//! terminal UI on top of the session lives in [`crate::review_tui`].
+
+use crate::error::{GscholarError, Result};
+use crate::doi;
+use crate::output::{self, OutputFormat};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Papers the LLM was unsure about
+pub const REVIEW_INPUT_FILE: &str = "7_review.csv";
+
+/// Papers the LLM included
+pub const RELEVANT_INPUT_FILE: &str = "7_relevant.csv";
+
+/// Decisions of the reviewer
+pub const HUMAN_REVIEW_FILE: &str = "human_review.csv";
+
+/// Relevant papers after human review
+pub const FINAL_FILE: &str = "9_final.csv";
+
+/// Columns of [`HUMAN_REVIEW_FILE`]
+const DECISION_COLUMNS: [&str; 6] = ["doi", "title", "llm_label", "llm_confidence", "decision", "reviewed_at"];
+
+/// Table row keyed by column name
+type Row = HashMap<String, String>;
+
+/// Reviewer's verdict on one paper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Accept => "accept",
+            Self::Reject => "reject",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "accept" | "include" => Some(Self::Accept),
+            "reject" | "exclude" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// One paper to review, with its row of the stage table
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub title: String,
+    pub doi: String,
+    pub journal: String,
+    pub date: String,
+    pub abstract_text: String,
+    pub label: String,
+    pub confidence: String,
+    pub reason: String,
+    pub evidence: String,
+    /// Whether the LLM included the paper (`7_relevant.csv`)
+    pub llm_included: bool,
+    pub decision: Option<Decision>,
+    reviewed_at: String,
+    row: Row,
+}
+
+impl Candidate {
+    /// DOI, or the title for papers without one
+    fn key(&self) -> String {
+        review_key(&self.doi, &self.title)
+    }
+
+    /// Whether the paper belongs to the final set
+    pub fn is_included(&self) -> bool {
+        match self.decision {
+            Some(decision) => decision == Decision::Accept,
+            None => self.llm_included,
+        }
+    }
+}
+
+fn review_key(doi: &str, title: &str) -> String {
+    let doi = doi::normalize(doi);
+    if doi.is_empty() {
+        title.trim().to_lowercase()
+    } else {
+        doi
+    }
+}
+
+/// Papers of a run folder and the decisions taken so far
+#[derive(Debug)]
+pub struct ReviewSession {
+    folder: PathBuf,
+    /// Column order of the final table
+    header: Vec<String>,
+    pub candidates: Vec<Candidate>,
+}
+
+impl ReviewSession {
+    /// Load the stage 7 tables of a run folder and any earlier decisions
+    pub fn load(folder: &Path) -> Result<Self> {
+        let mut header: Vec<String> = Vec::new();
+        let mut candidates = Vec::new();
+        for (file, llm_included) in [(REVIEW_INPUT_FILE, false), (RELEVANT_INPUT_FILE, true)] {
+            let path = folder.join(file);
+            if !path.exists() {
+                continue;
+            }
+            let (file_header, rows) = read_table(&path)?;
+            for column in &file_header {
+                if !header.contains(column) {
+                    header.push(column.clone());
+                }
+            }
+            candidates.extend(rows.into_iter().map(|row| candidate(row, llm_included)));
+        }
+        if header.is_empty() {
+            return Err(GscholarError::Config(format!(
+                "No {} or {} in {:?}; run the pipeline with LLM filtering first",
+                REVIEW_INPUT_FILE, RELEVANT_INPUT_FILE, folder
+            )));
+        }
+
+        let mut session = Self { folder: folder.to_path_buf(), header, candidates };
+        let decisions_path = folder.join(HUMAN_REVIEW_FILE);
+        if decisions_path.exists() {
+            let (_, rows) = read_table(&decisions_path)?;
+            let decisions: HashMap<String, (Decision, String)> = rows
+                .into_iter()
+                .filter_map(|row| {
+                    let decision = Decision::parse(row.get("decision")?)?;
+                    let key = review_key(row.get("doi")?, row.get("title")?);
+                    Some((key, (decision, row.get("reviewed_at").cloned().unwrap_or_default())))
+                })
+                .collect();
+            for candidate in &mut session.candidates {
+                if let Some((decision, reviewed_at)) = decisions.get(&candidate.key()) {
+                    candidate.decision = Some(*decision);
+                    candidate.reviewed_at = reviewed_at.clone();
+                }
+            }
+        }
+        info!(folder = ?folder, papers = session.candidates.len(), reviewed = session.reviewed(), "Loaded review session");
+        Ok(session)
+    }
+
+    /// Record (or clear) the decision on a paper
+    pub fn decide(&mut self, index: usize, decision: Option<Decision>) {
+        if let Some(candidate) = self.candidates.get_mut(index) {
+            candidate.decision = decision;
+            candidate.reviewed_at = match decision {
+                Some(_) => chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                None => String::new(),
+            };
+        }
+    }
+
+    /// Number of papers with a decision
+    pub fn reviewed(&self) -> usize {
+        self.candidates.iter().filter(|c| c.decision.is_some()).count()
+    }
+
+    /// First paper without a decision at or after `from`, wrapping around
+    pub fn next_undecided(&self, from: usize) -> Option<usize> {
+        let len = self.candidates.len();
+        (0..len).map(|offset| (from + offset) % len).find(|&i| self.candidates[i].decision.is_none())
+    }
+
+    /// Write the decisions and the final set; returns the size of the final set
+    pub fn save(&self) -> Result<usize> {
+        let decisions: Vec<Vec<String>> = self
+            .candidates
+            .iter()
+            .filter_map(|c| {
+                let decision = c.decision?;
+                Some(vec![
+                    c.doi.clone(),
+                    c.title.clone(),
+                    c.label.clone(),
+                    c.confidence.clone(),
+                    decision.as_str().to_string(),
+                    c.reviewed_at.clone(),
+                ])
+            })
+            .collect();
+        let decision_header: Vec<String> = DECISION_COLUMNS.iter().map(|c| c.to_string()).collect();
+        output::write_table(&self.folder.join(HUMAN_REVIEW_FILE), OutputFormat::Csv, &decision_header, &decisions)?;
+
+        let mut header = self.header.clone();
+        header.push("human_decision".to_string());
+        let rows: Vec<Vec<String>> = self
+            .candidates
+            .iter()
+            .filter(|c| c.is_included())
+            .map(|c| {
+                let mut row: Vec<String> = self.header.iter().map(|h| c.row.get(h).cloned().unwrap_or_default()).collect();
+                row.push(c.decision.map(|d| d.as_str().to_string()).unwrap_or_default());
+                row
+            })
+            .collect();
+        output::write_table(&self.folder.join(FINAL_FILE), OutputFormat::Csv, &header, &rows)?;
+        debug!(decisions = decisions.len(), included = rows.len(), "Saved human review");
+        Ok(rows.len())
+    }
+}
+
+fn candidate(row: Row, llm_included: bool) -> Candidate {
+    let field = |name: &str| row.get(name).cloned().unwrap_or_default();
+    Candidate {
+        title: field("title"),
+        doi: field("doi"),
+        journal: field("journal"),
+        date: field("date"),
+        abstract_text: field("abstract_text"),
+        label: field("label"),
+        confidence: field("confidence"),
+        reason: field("reason"),
+        evidence: field("evidence"),
+        llm_included,
+        decision: None,
+        reviewed_at: String::new(),
+        row,
+    }
+}
+
+/// Header and rows of a CSV, each row keyed by column name
+fn read_table(path: &Path) -> Result<(Vec<String>, Vec<Row>)> {
+    let read_err = |e: csv::Error| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e));
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path).map_err(read_err)?;
+    let header: Vec<String> = reader.headers().map_err(read_err)?.iter().map(String::from).collect();
+    let rows = reader
+        .records()
+        .map(|r| r.map(|r| header.iter().cloned().zip(r.iter().map(String::from)).collect()))
+        .collect::<std::result::Result<Vec<Row>, _>>()
+        .map_err(read_err)?;
+    Ok((header, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_session() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join(REVIEW_INPUT_FILE),
+            "title,doi,label,confidence,reason\nMaybe,10.1/B,uncertain,0.5,Unclear scope\nNo DOI,,uncertain,0.4,Short abstract\n",
+        )?;
+        std::fs::write(
+            dir.path().join(RELEVANT_INPUT_FILE),
+            "title,doi,label,confidence,reason,tags\nYes,10.1/A,relevant,0.9,On topic,field\nAlso,10.1/C,relevant,0.8,On topic,\n",
+        )?;
+
+        let mut session = ReviewSession::load(dir.path())?;
+        let titles: Vec<&str> = session.candidates.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Maybe", "No DOI", "Yes", "Also"]);
+        session.decide(0, Some(Decision::Accept));
+        session.decide(3, Some(Decision::Reject));
+        assert_eq!(session.next_undecided(3), Some(1));
+        assert_eq!(session.save()?, 2);
+
+        let final_table = std::fs::read_to_string(dir.path().join(FINAL_FILE))?;
+        assert_eq!(
+            final_table,
+            "title,doi,label,confidence,reason,tags,human_decision\n\
+             Maybe,10.1/B,uncertain,0.5,Unclear scope,,accept\n\
+             Yes,10.1/A,relevant,0.9,On topic,field,\n"
+        );
+
+        // Resuming picks up the saved decisions
+        let session = ReviewSession::load(dir.path())?;
+        let decisions: Vec<_> = session.candidates.iter().map(|c| c.decision).collect();
+        assert_eq!(decisions, vec![Some(Decision::Accept), None, None, Some(Decision::Reject)]);
+        assert!(ReviewSession::load(&dir.path().join("missing")).is_err());
+        Ok(())
+    }
+}
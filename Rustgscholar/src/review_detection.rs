@@ -0,0 +1,201 @@
+//! Review-article detection (`is_review`).
+//!
+//! Whether to include review articles is one of the most common screening
+//! criteria. Every Stage 5 paper is checked against cheap cues first: a
+//! source that types it as a review, review words in the title ("A
+//! review of", "survey", "meta-analysis") and review phrasing in the
+//! abstract ("In this review", "we summarize"), weighed against phrasing
+//! typical of original research ("we propose", "our results"). Papers the
+//! cues leave inconclusive are put to the LLM, when one is configured and
+//! `--exclude-reviews` or `--only-reviews` makes the answer matter.
+
+use crate::error::{GscholarError, Result};
+use crate::llm_filter::{self, extract_json, paper_to_json, LlmConfig, LlmSession, TokenUsage};
+use crate::paper::Paper;
+use crate::prompts::review_detection::{build_user_prompt, SYSTEM_PROMPT};
+use crate::work_type::WorkType;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Title words that mark a review
+const TITLE_STRONG: &[&str] = &["review", "reviews", "survey", "meta analysis", "meta analyses", "metaanalysis"];
+
+/// Title words common in reviews but also in research papers
+const TITLE_WEAK: &[&str] = &[
+    "overview", "state of the art", "advances", "progress", "perspective", "perspectives", "trends", "bibliometric",
+];
+
+/// Abstract phrases that mark a review
+const ABSTRACT_STRONG: &[&str] = &[
+    "this review", "we review", "this paper reviews", "this article reviews", "this study reviews", "this survey",
+    "we survey", "systematic review", "scoping review", "meta analysis", "this overview",
+];
+
+/// Abstract phrases common in reviews but also in research papers
+const ABSTRACT_WEAK: &[&str] = &[
+    "literature review", "we summarize", "we summarise", "summarizes", "summarises", "recent advances",
+    "recent progress", "state of the art", "an overview", "comprehensive overview",
+];
+
+/// Abstract phrases of original research
+const ABSTRACT_RESEARCH: &[&str] = &[
+    "we propose", "we present a novel", "we developed", "we develop", "our results", "experiments show",
+    "experimental results", "we collected", "were collected",
+];
+
+/// What to do with review articles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewPolicy {
+    /// Keep every paper (only the `is_review` column is written)
+    #[default]
+    Keep,
+    /// Drop review articles (`--exclude-reviews`)
+    Exclude,
+    /// Keep review articles only (`--only-reviews`)
+    Only,
+}
+
+impl ReviewPolicy {
+    /// Whether a paper passes (papers not checked pass)
+    pub fn keeps(self, is_review: Option<bool>) -> bool {
+        match (self, is_review) {
+            (Self::Keep, _) | (_, None) => true,
+            (Self::Exclude, Some(review)) => !review,
+            (Self::Only, Some(review)) => review,
+        }
+    }
+}
+
+/// Outcome of the cue check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Review,
+    NotReview,
+    /// A single weak cue, or review and research cues that cancel out
+    Uncertain,
+}
+
+/// Check a paper against the type, title and abstract cues
+pub fn heuristic(paper: &Paper) -> Verdict {
+    if paper.work_type == WorkType::Review {
+        return Verdict::Review;
+    }
+    let title = phrases(&paper.title);
+    let abstract_text = phrases(&paper.abstract_text);
+    let hits = |text: &str, list: &[&str]| list.iter().filter(|phrase| text.contains(&format!(" {} ", phrase))).count() as i32;
+
+    let score = 2 * hits(&title, TITLE_STRONG).min(1)
+        + hits(&title, TITLE_WEAK).min(1)
+        + 2 * hits(&abstract_text, ABSTRACT_STRONG).min(1)
+        + hits(&abstract_text, ABSTRACT_WEAK).min(1)
+        - hits(&abstract_text, ABSTRACT_RESEARCH).min(2);
+    match score {
+        s if s >= 2 => Verdict::Review,
+        1 => Verdict::Uncertain,
+        _ => Verdict::NotReview,
+    }
+}
+
+/// Lowercase words separated by single spaces, padded with a space at both
+/// ends so that phrases match whole words only
+fn phrases(text: &str) -> String {
+    let cleaned: String = text.to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }).collect();
+    format!(" {} ", cleaned.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// JSON schema for the review detection response
+fn response_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "is_review": {"type": "boolean"},
+            "reason": {"type": "string"}
+        },
+        "required": ["is_review", "reason"],
+        "additionalProperties": false
+    })
+}
+
+/// Ask the LLM whether each paper is a review, with concurrent requests;
+/// `None` for papers whose request failed
+pub async fn classify(config: &LlmConfig, papers: &[&Paper]) -> Result<(Vec<Option<bool>>, TokenUsage)> {
+    if papers.is_empty() {
+        return Ok((Vec::new(), TokenUsage::default()));
+    }
+    info!(count = papers.len(), model = %config.model, "Starting LLM review detection");
+
+    let session = LlmSession::new(config)?;
+    let schema = response_schema();
+
+    let replies = llm_filter::complete_each(
+        &session,
+        SYSTEM_PROMPT,
+        "review_result",
+        &schema,
+        papers.iter().copied().enumerate(),
+        |paper| Ok(build_user_prompt(&paper_to_json(paper)?)),
+        |content, _| parse_response(content),
+    )
+    .await;
+    let verdicts: Vec<Option<bool>> = replies
+        .into_iter()
+        .enumerate()
+        .map(|(idx, reply)| reply.map_err(|e| warn!(idx = idx, error = %e, "Failed to classify paper")).ok())
+        .collect();
+
+    info!(
+        reviews = verdicts.iter().filter(|v| **v == Some(true)).count(),
+        total = verdicts.len(),
+        "LLM review detection complete"
+    );
+    Ok((verdicts, session.usage()))
+}
+
+fn parse_response(content: &str) -> Result<bool> {
+    #[derive(Deserialize)]
+    struct ReviewOutput {
+        is_review: bool,
+    }
+
+    serde_json::from_str::<ReviewOutput>(&extract_json(content))
+        .map(|output| output.is_review)
+        .map_err(|e| GscholarError::Parse(format!("Invalid review detection reply: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(title: &str, abstract_text: &str) -> Paper {
+        Paper { title: title.to_string(), abstract_text: abstract_text.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_heuristic() {
+        assert_eq!(heuristic(&paper("Landslide susceptibility mapping: a review", "")), Verdict::Review);
+        assert_eq!(
+            heuristic(&paper("Deep learning for landslide detection", "In this review, we summarize recent work.")),
+            Verdict::Review
+        );
+        assert_eq!(
+            heuristic(&paper("Recent advances in InSAR monitoring", "We propose a new phase unwrapping method.")),
+            Verdict::NotReview
+        );
+        assert_eq!(heuristic(&paper("Recent advances in InSAR monitoring", "")), Verdict::Uncertain);
+        // "reviewer" is not "review"
+        assert_eq!(heuristic(&paper("Reviewer agreement in peer assessment", "")), Verdict::NotReview);
+        let typed = Paper { work_type: WorkType::Review, ..paper("Slope stability", "") };
+        assert_eq!(heuristic(&typed), Verdict::Review);
+    }
+
+    #[test]
+    fn test_policy_and_reply() -> Result<()> {
+        assert!(ReviewPolicy::Exclude.keeps(Some(false)) && !ReviewPolicy::Exclude.keeps(Some(true)));
+        assert!(ReviewPolicy::Only.keeps(Some(true)) && !ReviewPolicy::Only.keeps(Some(false)));
+        assert!(ReviewPolicy::Only.keeps(None));
+        assert!(parse_response(r#"{"is_review": true, "reason": "synthesizes 120 studies"}"#)?);
+        assert!(parse_response("not json").is_err());
+        Ok(())
+    }
+}
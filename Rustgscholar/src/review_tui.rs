@@ -0,0 +1,140 @@
+//! Terminal UI of `review` (feature "tui").
+//!
+//! Shows one paper at a time (title, venue, abstract and the LLM's label,
+//! confidence and reason) and takes a decision per keystroke. Every
+//! decision is saved right away, so quitting never loses work.
+
+use crate::error::Result;
+use crate::review::{Decision, ReviewSession};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+const HELP: &str = "a accept · r reject · c clear · ←/→ previous/next · u next undecided · ↑/↓ scroll · q quit";
+
+/// Review the session's papers until the user quits
+pub fn run(session: &mut ReviewSession) -> Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = App::new(session).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct App<'a> {
+    session: &'a mut ReviewSession,
+    index: usize,
+    scroll: u16,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(session: &'a mut ReviewSession) -> Self {
+        let index = session.next_undecided(0).unwrap_or(0);
+        Self { session, index, scroll: 0, status: String::new() }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Char('a') | KeyCode::Char('y') => self.decide(Some(Decision::Accept))?,
+                KeyCode::Char('r') | KeyCode::Char('n') => self.decide(Some(Decision::Reject))?,
+                KeyCode::Char('c') | KeyCode::Backspace => self.decide(None)?,
+                KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => self.go_to(self.index + 1),
+                KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => self.go_to(self.index.saturating_sub(1)),
+                KeyCode::Char('u') => match self.session.next_undecided(self.index + 1) {
+                    Some(index) => self.go_to(index),
+                    None => self.status = "All papers reviewed".to_string(),
+                },
+                KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+                KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                _ => {}
+            }
+        }
+    }
+
+    fn go_to(&mut self, index: usize) {
+        if index < self.session.candidates.len() {
+            self.index = index;
+            self.scroll = 0;
+        }
+    }
+
+    /// Record a decision, save, and move on to the next undecided paper
+    fn decide(&mut self, decision: Option<Decision>) -> Result<()> {
+        self.session.decide(self.index, decision);
+        let included = self.session.save()?;
+        self.status = format!("Saved · {} papers in the final set", included);
+        if decision.is_some() {
+            match self.session.next_undecided(self.index + 1) {
+                Some(index) => self.go_to(index),
+                None => self.status.push_str(" · all papers reviewed, press q to quit"),
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, footer] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+
+        let total = self.session.candidates.len();
+        let Some(paper) = self.session.candidates.get(self.index) else {
+            frame.render_widget(Paragraph::new("No papers to review"), body);
+            return;
+        };
+
+        let decision = match paper.decision {
+            Some(Decision::Accept) => Span::styled(" ACCEPTED ", Style::new().bg(Color::Green).fg(Color::Black)),
+            Some(Decision::Reject) => Span::styled(" REJECTED ", Style::new().bg(Color::Red).fg(Color::Black)),
+            None => Span::styled(" UNDECIDED ", Style::new().bg(Color::DarkGray)),
+        };
+        let summary = Line::from(vec![
+            decision,
+            Span::raw(format!(
+                " Paper {}/{} · {} reviewed · LLM: {}{}",
+                self.index + 1,
+                total,
+                self.session.reviewed(),
+                paper.label,
+                if paper.confidence.is_empty() { String::new() } else { format!(" ({})", paper.confidence) },
+            )),
+        ]);
+        frame.render_widget(Paragraph::new(summary), header);
+
+        let venue: Vec<&str> =
+            [paper.journal.as_str(), paper.date.as_str(), paper.doi.as_str()].into_iter().filter(|s| !s.is_empty()).collect();
+        let mut text = Text::from(vec![
+            Line::from(paper.title.as_str()).bold(),
+            Line::from(venue.join(" · ")).fg(Color::Gray),
+        ]);
+        for (heading, content) in [("Abstract", &paper.abstract_text), ("LLM reason", &paper.reason), ("Evidence", &paper.evidence)] {
+            if content.is_empty() {
+                continue;
+            }
+            text.push_line(Line::default());
+            text.push_line(Line::from(heading).add_modifier(Modifier::UNDERLINED));
+            text.push_line(Line::from(content.as_str()));
+        }
+        let details = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(Block::bordered().title(if paper.llm_included { " Included by the LLM " } else { " Awaiting review " }));
+        frame.render_widget(details, body);
+
+        frame.render_widget(Paragraph::new(vec![Line::from(HELP).fg(Color::Gray), Line::from(self.status.as_str())]), footer);
+    }
+}
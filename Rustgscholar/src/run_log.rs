@@ -0,0 +1,94 @@
+//! Log file of a run folder (`run.log`).
+//!
+//! Console output is gone once the terminal is closed, so every CLI run
+//! also writes its log as JSON lines (one `tracing` event per line, with
+//! timestamp, level, target and fields) to `run.log` in the output folder:
+//! progress lines, per-stage timings, warnings and the final error. The
+//! `tracing` layer writing it is installed once at startup with a
+//! [`RunLog`] as its writer; lines are dropped until [`RunLog::open`] points
+//! it at a run folder.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Log file in each run folder
+pub const RUN_LOG_FILE: &str = "run.log";
+
+/// Target of events meant for `run.log` only (progress lines already
+/// printed to the console, the final error)
+pub const TARGET: &str = "run";
+
+/// Shared, switchable `run.log` sink
+#[derive(Debug, Clone, Default)]
+pub struct RunLog {
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl RunLog {
+    /// Append to the `run.log` of `folder` (created if missing); a resumed
+    /// run continues the log of the runs before it
+    pub fn open(&self, folder: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(folder)?;
+        let file = OpenOptions::new().create(true).append(true).open(folder.join(RUN_LOG_FILE))?;
+        *self.lock() = Some(file);
+        Ok(())
+    }
+
+    /// Stop writing (before the folder is removed or the next run starts)
+    pub fn close(&self) {
+        *self.lock() = None;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<File>> {
+        // A panic while writing leaves the file usable
+        self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<'a> MakeWriter<'a> for RunLog {
+    type Writer = RunLog;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+impl Write for RunLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(file) = self.lock().as_mut() {
+            file.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.lock().as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_log() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut log = RunLog::default();
+        log.write_all(b"dropped\n")?;
+        log.open(dir.path())?;
+        log.make_writer().write_all(b"{\"n\":1}\n")?;
+        log.close();
+        log.write_all(b"dropped\n")?;
+        // Reopening appends
+        log.open(dir.path())?;
+        log.write_all(b"{\"n\":2}\n")?;
+        assert_eq!(std::fs::read_to_string(dir.path().join(RUN_LOG_FILE))?, "{\"n\":1}\n{\"n\":2}\n");
+        Ok(())
+    }
+}
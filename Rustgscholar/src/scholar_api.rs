@@ -0,0 +1,346 @@
+//! Google Scholar results through a paid search API (`--backend`).
+//!
+//! Scraping Scholar needs fresh cookies and still runs into CAPTCHAs on
+//! long searches. SerpAPI and SearchAPI run the same Scholar query on
+//! their side and return the result list as JSON, for a fee per request.
+//! [`ScholarApiClient`] turns those results into the [`ScholarResult`]s
+//! the scraper produces, so Stage 2 onwards cannot tell the difference.
+
+use crate::cache::{self, HttpCache};
+use crate::error::{GscholarError, Result};
+use crate::gscholar::{self, QueryOptions, ScholarResult, RESULTS_PER_PAGE};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use crate::stream::{Page, PageSender};
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+/// SerpAPI search endpoint
+pub const SERPAPI_URL: &str = "https://serpapi.com/search.json";
+
+/// SearchAPI search endpoint
+pub const SEARCHAPI_URL: &str = "https://www.searchapi.io/api/v1/search";
+
+/// How Google Scholar results are fetched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScholarBackend {
+    /// Scrape scholar.google.com (or a mirror) with the saved cookies
+    #[default]
+    Scrape,
+    SerpApi,
+    SearchApi,
+}
+
+impl ScholarBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Scrape => "scrape",
+            Self::SerpApi => "serpapi",
+            Self::SearchApi => "searchapi",
+        }
+    }
+
+    /// Display name of the provider
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Scrape => "Google Scholar",
+            Self::SerpApi => "SerpAPI",
+            Self::SearchApi => "SearchAPI",
+        }
+    }
+}
+
+impl fmt::Display for ScholarBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ScholarBackend {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "scrape" | "scraper" => Ok(Self::Scrape),
+            "serpapi" => Ok(Self::SerpApi),
+            "searchapi" => Ok(Self::SearchApi),
+            other => Err(GscholarError::Config(format!(
+                "Unknown Scholar backend '{}' (expected scrape, serpapi or searchapi)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Client for SerpAPI or SearchAPI Google Scholar searches
+#[derive(Debug, Clone)]
+pub struct ScholarApiClient {
+    backend: ScholarBackend,
+    api_key: String,
+    client: Client,
+}
+
+impl ScholarApiClient {
+    pub fn new(backend: ScholarBackend, api_key: &str) -> Result<Self> {
+        if backend == ScholarBackend::Scrape {
+            return Err(GscholarError::Config("The scrape backend does not use a search API".to_string()));
+        }
+        let api_key = api_key.trim();
+        if api_key.is_empty() {
+            return Err(GscholarError::Config(format!("The {} backend needs an API key", backend)));
+        }
+        Ok(Self { backend, api_key: api_key.to_string(), client: http::client() })
+    }
+
+    pub fn backend(&self) -> ScholarBackend {
+        self.backend
+    }
+
+    /// Search URL of one result page, without the API key
+    pub fn search_url(&self, search_str: &str, page: i32, options: &QueryOptions) -> Result<Url> {
+        let base = match self.backend {
+            ScholarBackend::SearchApi => SEARCHAPI_URL,
+            _ => SERPAPI_URL,
+        };
+        let mut url = Url::parse(base).map_err(|e| GscholarError::Parse(e.to_string()))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("engine", "google_scholar");
+            query.append_pair("q", search_str);
+            match self.backend {
+                ScholarBackend::SearchApi => query.append_pair("page", &page.max(1).to_string()),
+                _ => query.append_pair("start", &((page.max(1) - 1) * RESULTS_PER_PAGE as i32).to_string()),
+            };
+            query.append_pair("num", &RESULTS_PER_PAGE.to_string());
+            query.append_pair("as_sdt", &options.sdt);
+            if let Some(ylo) = options.ylo {
+                query.append_pair("as_ylo", &ylo.to_string());
+            }
+            if options.reviews_only {
+                query.append_pair("as_rr", "1");
+            }
+        }
+        Ok(url)
+    }
+
+    /// Fetch the pages of `options` into `tx`, like [`gscholar::query_pages`]
+    pub async fn query_pages(
+        &self,
+        search_str: &str,
+        options: &QueryOptions,
+        cache: Option<&HttpCache>,
+        tx: PageSender<ScholarResult>,
+    ) -> Result<()> {
+        info!(query = search_str, backend = %self.backend, pages = ?options.pages, "Starting Google Scholar API query");
+
+        for &page_num in &options.pages {
+            let outcome = async {
+                let url = self.search_url(search_str, page_num, options)?;
+                parse_response(&self.fetch(&url, cache).await?)
+            }
+            .await;
+            match outcome {
+                Ok(response) => {
+                    let mut items = response.results()?;
+                    info!(page = page_num, count = items.len(), "Parsed results");
+                    if !options.all_results {
+                        items.truncate(1);
+                    }
+                    if tx.send(Page::new(page_num, items)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e @ (GscholarError::Config(_) | GscholarError::RateLimited(_))) => return Err(e),
+                Err(e) => {
+                    error!(page = page_num, error = %e, "Failed to fetch page");
+                    if tx.send(Page::failed(page_num, e)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of results reported for a query (first page only)
+    pub async fn count(&self, search_str: &str, options: &QueryOptions) -> Result<Option<u64>> {
+        let url = self.search_url(search_str, 1, options)?;
+        let count = parse_response(&self.fetch(&url, None).await?)?.total_results();
+        debug!(query = search_str, count = ?count, "Google Scholar API hit count");
+        Ok(count)
+    }
+
+    /// Fetch a search page, from the HTTP cache when possible (keyed
+    /// without the API key)
+    async fn fetch(&self, url: &Url, cache: Option<&HttpCache>) -> Result<String> {
+        let key = cache::request_key("GET", url.as_str(), "");
+        if let Some(body) = cache.and_then(|c| c.get(&key)) {
+            debug!(url = %url, "Scholar API cache hit");
+            return Ok(body);
+        }
+
+        let request = match self.backend {
+            ScholarBackend::SearchApi => self.client.get(url.clone()).bearer_auth(&self.api_key),
+            _ => self.client.get(url.clone()).query(&[("api_key", self.api_key.as_str())]),
+        };
+        let response = http::send(&self.client, request.build()?).await?;
+        let status = response.status();
+        let body = response.text().await?;
+        match status.as_u16() {
+            200..=299 => {}
+            401 | 403 => {
+                return Err(GscholarError::Config(format!("{} rejected the API key ({})", self.backend.name(), status)));
+            }
+            429 => return Err(GscholarError::RateLimited(60)),
+            code => {
+                return Err(GscholarError::Api {
+                    code: code as i32,
+                    message: format!("{} API error: {}", self.backend.name(), error_message(&body).unwrap_or_default()),
+                })
+            }
+        }
+        raw_archive::record(url.as_str(), BodyKind::Json, &body);
+
+        if let Some(cache) = cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write Scholar API cache");
+            }
+        }
+        Ok(body)
+    }
+}
+
+/// Google Scholar search response, as SerpAPI and SearchAPI return it
+#[derive(Debug, Default, Deserialize)]
+struct ApiResponse {
+    #[serde(default)]
+    organic_results: Vec<ApiResult>,
+    search_information: Option<SearchInformation>,
+    /// Set instead of results when the search failed
+    error: Option<String>,
+}
+
+impl ApiResponse {
+    fn total_results(&self) -> Option<u64> {
+        self.search_information.as_ref().and_then(|info| info.total_results)
+    }
+
+    /// The organic results as scraped results; "no results" is not an error
+    fn results(self) -> Result<Vec<ScholarResult>> {
+        if let Some(error) = self.error.filter(|e| !e.to_lowercase().contains("hasn't returned any results")) {
+            return Err(GscholarError::Api { code: 200, message: error });
+        }
+        let year_regex = Regex::new(r"\b(19|20)\d{2}\b").map_err(|e| GscholarError::Parse(e.to_string()))?;
+        Ok(self.organic_results.into_iter().filter(|r| !r.title.trim().is_empty()).map(|r| r.into_result(&year_regex)).collect())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SearchInformation {
+    total_results: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiResult {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    link: String,
+    #[serde(default)]
+    snippet: String,
+    /// SerpAPI: the author/venue line as `publication_info.summary`
+    publication_info: Option<PublicationInfo>,
+    /// SearchAPI: the author/venue line as `publication`
+    #[serde(default)]
+    publication: String,
+    inline_links: Option<InlineLinks>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PublicationInfo {
+    #[serde(default)]
+    summary: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct InlineLinks {
+    cited_by: Option<CitedBy>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CitedBy {
+    total: Option<u64>,
+}
+
+impl ApiResult {
+    fn into_result(self, year_regex: &Regex) -> ScholarResult {
+        let mut result = ScholarResult {
+            title: self.title.trim().to_string(),
+            article_url: self.link,
+            snippet: self.snippet.trim().to_string(),
+            citations: self.inline_links.and_then(|l| l.cited_by).and_then(|c| c.total).map(|c| c.to_string()).unwrap_or_default(),
+            ..Default::default()
+        };
+        let meta = self.publication_info.map(|p| p.summary).filter(|s| !s.is_empty()).unwrap_or(self.publication);
+        gscholar::apply_meta_line(&mut result, &meta, year_regex);
+        result
+    }
+}
+
+fn parse_response(body: &str) -> Result<ApiResponse> {
+    serde_json::from_str(body).map_err(|e| GscholarError::Parse(format!("Invalid Scholar API response: {}", e)))
+}
+
+/// Error message of a failed request, when the body carries one
+fn error_message(body: &str) -> Option<String> {
+    serde_json::from_str::<ApiResponse>(body).ok().and_then(|r| r.error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_url() -> Result<()> {
+        let options = QueryOptions { ylo: Some(2020), reviews_only: true, ..Default::default() };
+        let serpapi = ScholarApiClient::new(ScholarBackend::SerpApi, "secret")?;
+        let url = serpapi.search_url("landslide susceptibility", 3, &options)?;
+        assert!(url.as_str().starts_with(SERPAPI_URL));
+        assert!(url.as_str().contains("engine=google_scholar&q=landslide+susceptibility&start=20&num=10"));
+        assert!(url.as_str().contains("as_ylo=2020&as_rr=1"));
+        assert!(!url.as_str().contains("secret"));
+        let searchapi = ScholarApiClient::new("searchapi".parse()?, "secret")?;
+        assert!(searchapi.search_url("landslide", 3, &options)?.as_str().contains("&page=3&"));
+        assert!(ScholarApiClient::new(ScholarBackend::SerpApi, " ").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_response() -> Result<()> {
+        let serpapi = r#"{"search_information": {"total_results": 17900}, "organic_results": [{
+            "title": "Landslide susceptibility mapping using machine learning",
+            "link": "https://example.org/paper",
+            "snippet": "We compare random forests ...",
+            "publication_info": {"summary": "W Li, A Rossi - Geomorphology, 2021 - Elsevier"},
+            "inline_links": {"cited_by": {"total": 123}}}]}"#;
+        let response = parse_response(serpapi)?;
+        assert_eq!(response.total_results(), Some(17_900));
+        let results = response.results()?;
+        assert_eq!(results[0].author, "W Li, A Rossi");
+        assert_eq!((results[0].venue.as_str(), results[0].year.as_str()), ("Geomorphology", "2021"));
+        assert_eq!(results[0].citations, "123");
+
+        let searchapi = r#"{"organic_results": [{"title": "Debris flows", "publication": "J Smith - Nature, 2019 - nature.com"}]}"#;
+        let results = parse_response(searchapi)?.results()?;
+        assert_eq!((results[0].author.as_str(), results[0].year.as_str()), ("J Smith", "2019"));
+
+        assert!(parse_response(r#"{"error": "Google hasn't returned any results for this query."}"#)?.results()?.is_empty());
+        assert!(parse_response(r#"{"error": "Your account has run out of searches."}"#)?.results().is_err());
+        Ok(())
+    }
+}
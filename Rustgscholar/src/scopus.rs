@@ -0,0 +1,130 @@
+//! Scopus Abstract Retrieval API client.
+//!
+//! Fetches the abstract of a DOI from Elsevier's Scopus API, which needs an
+//! API key (free for institutional subscribers at https://dev.elsevier.com).
+
+use crate::cache::{self, HttpCache};
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+/// Scopus Abstract Retrieval API base URL (by DOI)
+pub const SCOPUS_API_URL: &str = "https://api.elsevier.com/content/abstract/doi";
+
+#[derive(Debug, Deserialize)]
+struct ScopusResponse {
+    #[serde(rename = "abstracts-retrieval-response")]
+    response: Option<ScopusRetrieval>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScopusRetrieval {
+    coredata: Option<ScopusCoredata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScopusCoredata {
+    #[serde(rename = "dc:description")]
+    description: Option<String>,
+}
+
+/// Scopus API client
+pub struct ScopusClient {
+    client: reqwest::Client,
+    api_key: String,
+    cache: Option<HttpCache>,
+}
+
+impl ScopusClient {
+    /// Create a new ScopusClient
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Elsevier API key, sent as `X-ELS-APIKey`
+    pub fn new(api_key: &str) -> Result<Self> {
+        Ok(Self { client: http::client(), api_key: api_key.trim().to_string(), cache: None })
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/scopus`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Abstract of a DOI (`None` when Scopus has no record or no abstract)
+    pub async fn abstract_by_doi(&self, doi: &str) -> Result<Option<String>> {
+        let doi = doi.trim();
+        if doi.is_empty() {
+            return Ok(None);
+        }
+        let request = self
+            .client
+            .get(format!("{}/{}", SCOPUS_API_URL, doi))
+            .query(&[("field", "dc:description")])
+            .header("X-ELS-APIKey", &self.api_key)
+            .header("Accept", "application/json")
+            .build()?;
+        let key = cache::request_key("GET", request.url().as_str(), "");
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(doi = %doi, "Scopus cache hit");
+            return parse_abstract(&body);
+        }
+
+        let url = request.url().to_string();
+        let response = http::send(&self.client, request).await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(None),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(5)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("Scopus API error: {}", status),
+                });
+            }
+            _ => {}
+        }
+
+        let body = response.text().await?;
+        raw_archive::record(&url, BodyKind::Json, &body);
+        let abstract_text = parse_abstract(&body)?;
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write Scopus cache");
+            }
+        }
+        Ok(abstract_text)
+    }
+}
+
+/// Abstract from a retrieval response, without the trailing copyright line
+fn parse_abstract(body: &str) -> Result<Option<String>> {
+    let data: ScopusResponse = serde_json::from_str(body)?;
+    let description = data
+        .response
+        .and_then(|r| r.coredata)
+        .and_then(|c| c.description)
+        .unwrap_or_default();
+    let text = match description.find('©') {
+        Some(i) if i > 0 => &description[..i],
+        _ => description.as_str(),
+    };
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_abstract() -> Result<()> {
+        let body = r#"{"abstracts-retrieval-response": {"coredata": {
+            "dc:description": "Rainfall triggers shallow landslides. © 2021 Elsevier B.V."
+        }}}"#;
+        assert_eq!(parse_abstract(body)?.as_deref(), Some("Rainfall triggers shallow landslides."));
+        assert_eq!(parse_abstract(r#"{"abstracts-retrieval-response": {"coredata": {}}}"#)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+//! Structured inclusion/exclusion criteria screening.
+//!
+//! For systematic reviews a single keyword list is too coarse. This module
+//! lets the user supply explicit inclusion and exclusion criteria (YAML or
+//! JSON) and asks the LLM for a verdict per criterion plus an overall
+//! include/exclude/uncertain decision.
+//!
+//! Criteria file format:
+//!
+//! ```yaml
+//! inclusion:
+//!   - id: I1
+//!     description: Field or laboratory study of rainfall-induced landslides
+//!   - Reports slope stability results      # ID assigned automatically (I2)
+//! exclusion:
+//!   - id: E1
+//!     description: Review or survey article
+//! ```
+
+use crate::error::{GscholarError, Result};
+use crate::llm_filter::{self, extract_json, paper_to_json, FilterResult, LlmCallDetail, LlmConfig, LlmSession, TokenUsage};
+use crate::prompts::criteria_screening::{build_user_prompt, SYSTEM_PROMPT};
+use crate::paper::Paper;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Whether a criterion must be met or must not be met
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CriterionKind {
+    Inclusion,
+    Exclusion,
+}
+
+/// A single screening criterion
+#[derive(Debug, Clone)]
+pub struct Criterion {
+    pub id: String,
+    pub kind: CriterionKind,
+    pub description: String,
+}
+
+/// Inclusion and exclusion criteria for a review
+#[derive(Debug, Clone)]
+pub struct Criteria {
+    pub criteria: Vec<Criterion>,
+}
+
+/// Criteria entry as written in the file: plain text or `{id, description}`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CriterionSpec {
+    Text(String),
+    Full {
+        #[serde(default)]
+        id: Option<String>,
+        description: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct CriteriaFile {
+    #[serde(default)]
+    inclusion: Vec<CriterionSpec>,
+    #[serde(default)]
+    exclusion: Vec<CriterionSpec>,
+}
+
+impl Criteria {
+    /// Load criteria from a YAML or JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let criteria = Self::parse(&content)?;
+        info!(path = ?path, count = criteria.criteria.len(), "Loaded screening criteria");
+        Ok(criteria)
+    }
+
+    /// Parse criteria from YAML (or JSON, which is valid YAML)
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: CriteriaFile = serde_yaml::from_str(content)
+            .map_err(|e| GscholarError::Config(format!("Invalid criteria file: {}", e)))?;
+
+        let mut criteria = Vec::new();
+        for (kind, prefix, specs) in [
+            (CriterionKind::Inclusion, "I", file.inclusion),
+            (CriterionKind::Exclusion, "E", file.exclusion),
+        ] {
+            for (idx, spec) in specs.into_iter().enumerate() {
+                let (id, description) = match spec {
+                    CriterionSpec::Text(description) => (None, description),
+                    CriterionSpec::Full { id, description } => (id, description),
+                };
+                criteria.push(Criterion {
+                    id: id.unwrap_or_else(|| format!("{}{}", prefix, idx + 1)),
+                    kind,
+                    description: description.trim().to_string(),
+                });
+            }
+        }
+
+        if criteria.is_empty() {
+            return Err(GscholarError::Config("Criteria file has no inclusion or exclusion criteria".to_string()));
+        }
+
+        let mut seen = HashSet::new();
+        for c in &criteria {
+            if !seen.insert(c.id.as_str()) {
+                return Err(GscholarError::Config(format!("Duplicate criterion ID: {}", c.id)));
+            }
+        }
+
+        Ok(Self { criteria })
+    }
+
+    /// Render the criteria as prompt text, one per line
+    pub fn to_prompt(&self) -> String {
+        self.criteria
+            .iter()
+            .map(|c| {
+                let kind = match c.kind {
+                    CriterionKind::Inclusion => "inclusion",
+                    CriterionKind::Exclusion => "exclusion",
+                };
+                format!("- {} ({}): {}", c.id, kind, c.description)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// JSON schema for the screening response
+    pub fn response_schema(&self) -> serde_json::Value {
+        let ids: Vec<&str> = self.criteria.iter().map(|c| c.id.as_str()).collect();
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "criteria": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": {"type": "string", "enum": ids},
+                            "verdict": {"type": "string", "enum": ["met", "not_met", "unclear"]},
+                            "evidence": {"type": "string"}
+                        },
+                        "required": ["id", "verdict", "evidence"],
+                        "additionalProperties": false
+                    }
+                },
+                "decision": {"type": "string", "enum": ["include", "exclude", "uncertain"]},
+                "confidence": {"type": "number", "minimum": 0.0, "maximum": 1.0},
+                "reason": {"type": "string"}
+            },
+            "required": ["criteria", "decision", "confidence", "reason"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Verdict for one criterion
+#[derive(Debug, Clone, Default)]
+pub struct CriterionVerdict {
+    /// "met", "not_met", or "unclear"
+    pub verdict: String,
+    pub evidence: String,
+}
+
+/// Screening result for a single paper
+#[derive(Debug, Clone)]
+pub struct ScreeningResult {
+    pub id: String,
+    pub title: String,
+    /// "include", "exclude", or "uncertain"
+    pub decision: String,
+    pub confidence: f64,
+    pub reason: String,
+    /// Criterion ID -> verdict
+    pub verdicts: HashMap<String, CriterionVerdict>,
+}
+
+impl ScreeningResult {
+    fn uncertain(paper: &Paper, reason: String) -> Self {
+        Self {
+            id: paper.doi.clone(),
+            title: paper.title.clone(),
+            decision: "uncertain".to_string(),
+            confidence: 0.0,
+            reason,
+            verdicts: HashMap::new(),
+        }
+    }
+
+    /// Convert to a relevance [`FilterResult`] so later stages can treat
+    /// both modes alike (include -> relevant, exclude -> irrelevant)
+    pub fn to_filter_result(&self) -> FilterResult {
+        let label = match self.decision.as_str() {
+            "include" => "relevant",
+            "exclude" => "irrelevant",
+            _ => "uncertain",
+        };
+        let mut evidence: Vec<String> = self
+            .verdicts
+            .iter()
+            .map(|(id, v)| format!("{}={}", id, v.verdict))
+            .collect();
+        evidence.sort();
+
+        FilterResult {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            label: label.to_string(),
+            confidence: self.confidence,
+            evidence: evidence.join(", "),
+            reason: self.reason.clone(),
+        }
+    }
+
+    /// CSV header: fixed columns followed by `<id>` and `<id>_evidence` per criterion
+    pub fn csv_header(criteria: &Criteria) -> Vec<String> {
+        let mut header: Vec<String> = ["id", "title", "decision", "confidence", "reason"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        for c in &criteria.criteria {
+            header.push(c.id.clone());
+            header.push(format!("{}_evidence", c.id));
+        }
+        header
+    }
+
+    /// CSV row matching [`ScreeningResult::csv_header`]
+    pub fn csv_row(&self, criteria: &Criteria) -> Vec<String> {
+        let mut row = vec![
+            self.id.clone(),
+            self.title.clone(),
+            self.decision.clone(),
+            self.confidence.to_string(),
+            self.reason.clone(),
+        ];
+        for c in &criteria.criteria {
+            let verdict = self.verdicts.get(&c.id).cloned().unwrap_or_default();
+            row.push(verdict.verdict);
+            row.push(verdict.evidence);
+        }
+        row
+    }
+}
+
+/// Screen papers against criteria using concurrent LLM requests.
+pub async fn screen_papers(
+    config: &LlmConfig,
+    criteria: &Criteria,
+    papers: &[Paper],
+) -> Result<(Vec<ScreeningResult>, TokenUsage, Vec<LlmCallDetail>)> {
+    if papers.is_empty() {
+        return Ok((Vec::new(), TokenUsage::default(), Vec::new()));
+    }
+
+    info!(
+        count = papers.len(),
+        criteria = criteria.criteria.len(),
+        model = %config.model,
+        "Starting LLM criteria screening"
+    );
+
+    let session = LlmSession::new(config)?;
+    let schema = criteria.response_schema();
+    let criteria_prompt = criteria.to_prompt();
+
+    let papers: Vec<&Paper> = papers.iter().collect();
+    let replies = llm_filter::complete_each(
+        &session,
+        SYSTEM_PROMPT,
+        "screening_result",
+        &schema,
+        papers.iter().copied().enumerate(),
+        |paper| Ok(build_user_prompt(&criteria_prompt, &paper_to_json(paper)?)),
+        |content, paper| parse_screening_response(content, paper, criteria),
+    )
+    .await;
+    let results: Vec<ScreeningResult> = replies
+        .into_iter()
+        .zip(&papers)
+        .enumerate()
+        .map(|(idx, (reply, paper))| {
+            reply.unwrap_or_else(|e| {
+                warn!(idx = idx, error = %e, "Failed to screen paper");
+                ScreeningResult::uncertain(paper, format!("LLM error: {}", e))
+            })
+        })
+        .collect();
+
+    let usage = session.usage();
+    info!(
+        screened = results.len(),
+        included = results.iter().filter(|r| r.decision == "include").count(),
+        prompt_tokens = usage.prompt_tokens,
+        completion_tokens = usage.completion_tokens,
+        "LLM screening complete"
+    );
+
+    Ok((results, usage, session.call_details(&papers)))
+}
+
+/// Parse the LLM screening JSON, defaulting missing/invalid parts to "unclear"/"uncertain"
+fn parse_screening_response(content: &str, paper: &Paper, criteria: &Criteria) -> Result<ScreeningResult> {
+    #[derive(Deserialize)]
+    struct VerdictOutput {
+        id: String,
+        verdict: String,
+        #[serde(default)]
+        evidence: String,
+    }
+
+    #[derive(Deserialize)]
+    struct ScreeningOutput {
+        #[serde(default)]
+        criteria: Vec<VerdictOutput>,
+        decision: String,
+        #[serde(default)]
+        confidence: f64,
+        #[serde(default)]
+        reason: String,
+    }
+
+    let output = serde_json::from_str::<ScreeningOutput>(&extract_json(content)).map_err(|e| {
+        let preview: String = content.chars().take(200).collect();
+        info!(error = %e, content_preview = %preview, "Screening output parse failed");
+        GscholarError::Parse(format!("Failed to parse screening verdict: {}", e))
+    })?;
+
+    let mut verdicts: HashMap<String, CriterionVerdict> = output
+        .criteria
+        .into_iter()
+        .map(|v| {
+            let verdict = match v.verdict.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+                "met" => "met",
+                "not_met" => "not_met",
+                _ => "unclear",
+            };
+            (
+                v.id.trim().to_string(),
+                CriterionVerdict {
+                    verdict: verdict.to_string(),
+                    evidence: v.evidence,
+                },
+            )
+        })
+        .collect();
+
+    for c in &criteria.criteria {
+        verdicts.entry(c.id.clone()).or_insert_with(|| CriterionVerdict {
+            verdict: "unclear".to_string(),
+            evidence: String::new(),
+        });
+    }
+
+    let decision = match output.decision.trim().to_lowercase().as_str() {
+        "include" => "include",
+        "exclude" => "exclude",
+        _ => "uncertain",
+    };
+
+    Ok(ScreeningResult {
+        id: paper.doi.clone(),
+        title: paper.title.clone(),
+        decision: decision.to_string(),
+        confidence: output.confidence,
+        reason: output.reason,
+        verdicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper() -> Paper {
+        Paper {
+            title: "Rainfall-induced landslides in Sichuan".to_string(),
+            doi: "10.1234/test".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_criteria() -> Result<()> {
+        let criteria = Criteria::parse(
+            "inclusion:\n  - id: P\n    description: Field study\n  - Reports slope stability\nexclusion:\n  - Review article\n",
+        )?;
+        let ids: Vec<&str> = criteria.criteria.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["P", "I2", "E1"]);
+        assert!(criteria.to_prompt().contains("- E1 (exclusion): Review article"));
+
+        let json = Criteria::parse(r#"{"inclusion": ["A"], "exclusion": [{"id": "I1", "description": "B"}]}"#);
+        assert!(json.is_err(), "duplicate IDs must be rejected");
+        assert!(Criteria::parse("inclusion: []").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_screening_response() -> Result<()> {
+        let criteria = Criteria::parse("inclusion: [Field study]\nexclusion: [Review article]")?;
+        let content = r#"{"criteria": [{"id": "I1", "verdict": "Met", "evidence": "field monitoring"}],
+            "decision": "include", "confidence": 0.8, "reason": "Field study"}"#;
+        let result = parse_screening_response(content, &paper(), &criteria)?;
+
+        assert_eq!(result.decision, "include");
+        assert_eq!(result.verdicts["I1"].verdict, "met");
+        assert_eq!(result.verdicts["E1"].verdict, "unclear");
+
+        let row = result.csv_row(&criteria);
+        assert_eq!(row.len(), ScreeningResult::csv_header(&criteria).len());
+        assert_eq!(result.to_filter_result().label, "relevant");
+
+        assert!(parse_screening_response("not json", &paper(), &criteria).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+//! API keys from the environment, and keeping them out of logs.
+//!
+//! Every key flag of `search` falls back to an environment variable (see
+//! [`SECRET_VARS`]), which may also be set in a `.env` file in the working
+//! directory. Variables already set in the environment win over the file.
+//!
+//! A [`Redactor`] knows the key values in use and replaces them with `***`;
+//! [`RedactingWriter`] applies it to log output, since request URLs (e.g.
+//! EasyScholar's `secretKey` query parameter) can end up in error messages.
+
+use crate::error::Result;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Dotenv file read from the working directory
+pub const DOTENV_FILE: &str = ".env";
+
+/// Environment variable and the `search` flag it stands in for
+pub struct SecretVar {
+    pub var: &'static str,
+    pub flag: &'static str,
+}
+
+/// Every secret that can come from the environment
+pub const SECRET_VARS: &[SecretVar] = &[
+    SecretVar { var: "ALTMETRIC_API_KEY", flag: "--altmetric-key" },
+    SecretVar { var: "EASYSCHOLAR_KEY", flag: "--easyscholar-key" },
+    SecretVar { var: "LLM_API_KEY", flag: "--llm-key" },
+    SecretVar { var: "S2_API_KEY", flag: "--s2-key" },
+    SecretVar { var: "SCOPUS_API_KEY", flag: "--scopus-key" },
+    SecretVar { var: "SEARCHAPI_KEY", flag: "--searchapi-key" },
+    SecretVar { var: "SERPAPI_KEY", flag: "--serpapi-key" },
+    SecretVar { var: "ZOTERO_API_KEY", flag: "--zotero-key" },
+    SecretVar { var: "SLACK_WEBHOOK_URL", flag: "--notify-slack" },
+];
+
+/// Whether an option holds a secret, by flag (`--llm-key`) or field name (`llm_key`)
+pub fn is_secret_option(name: &str) -> bool {
+    let name = name.trim_start_matches('-').replace('_', "-");
+    SECRET_VARS.iter().any(|s| s.flag.trim_start_matches('-') == name)
+}
+
+/// Shorter values are not redacted (they would mangle ordinary text)
+const MIN_SECRET_LEN: usize = 6;
+
+/// Set variables from a dotenv file that are not already in the environment
+///
+/// Returns the names that were set. Lines are `KEY=value` (optionally
+/// prefixed with `export`); values may be single- or double-quoted, and
+/// `#` starts a comment line. Call it before any other thread starts.
+pub fn load_dotenv(path: &Path) -> Result<Vec<String>> {
+    let mut set = Vec::new();
+    for (key, value) in parse_dotenv(&std::fs::read_to_string(path)?) {
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(&key, value);
+            set.push(key);
+        }
+    }
+    Ok(set)
+}
+
+fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return None;
+            }
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+                .unwrap_or(value);
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Replaces known secret values with `***`
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(secrets: impl IntoIterator<Item = String>) -> Self {
+        let mut secrets: Vec<String> = secrets.into_iter().filter(|s| s.len() >= MIN_SECRET_LEN).collect();
+        // Longest first, so a key containing another is replaced whole
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    /// Secrets from [`SECRET_VARS`] in the environment and from key flags in `args`
+    pub fn from_env_and_args(args: &[OsString]) -> Self {
+        let from_env = SECRET_VARS.iter().filter_map(|s| std::env::var(s.var).ok());
+        let from_args = args.iter().enumerate().filter_map(|(i, arg)| {
+            let arg = arg.to_string_lossy();
+            SECRET_VARS.iter().find_map(|s| {
+                if arg == s.flag {
+                    args.get(i + 1).map(|v| v.to_string_lossy().into_owned())
+                } else {
+                    arg.strip_prefix(s.flag).and_then(|v| v.strip_prefix('=')).map(String::from)
+                }
+            })
+        });
+        Self::new(from_env.chain(from_args).collect::<Vec<_>>())
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), "***"))
+    }
+}
+
+/// Writer that redacts secrets from everything written through it
+///
+/// Each `write` call is redacted on its own, which matches log formatters
+/// that emit one event per call.
+pub struct RedactingWriter<W> {
+    inner: W,
+    redactor: Arc<Redactor>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    pub fn new(inner: W, redactor: Arc<Redactor>) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(self.redactor.redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dotenv() {
+        let parsed = parse_dotenv("# keys\nEASYSCHOLAR_KEY=abc123\nexport LLM_API_KEY = \"sk-x y\"\nBAD LINE\nS2_API_KEY='s2'\n");
+        assert_eq!(
+            parsed,
+            vec![
+                ("EASYSCHOLAR_KEY".to_string(), "abc123".to_string()),
+                ("LLM_API_KEY".to_string(), "sk-x y".to_string()),
+                ("S2_API_KEY".to_string(), "s2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact() -> std::io::Result<()> {
+        let args: Vec<OsString> = ["rustgscholar", "search", "kw", "--easyscholar-key", "secret-key-1", "--llm-key=sk-abcdef", "--zotero-key", "zz"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let redactor = Arc::new(Redactor::from_env_and_args(&args));
+        assert_eq!(
+            redactor.redact("GET https://x/api?secretKey=secret-key-1 (auth sk-abcdef, zz)"),
+            "GET https://x/api?secretKey=*** (auth ***, zz)"
+        );
+
+        let mut writer = RedactingWriter::new(Vec::new(), redactor);
+        writer.write_all(b"key secret-key-1\n")?;
+        assert_eq!(writer.inner, b"key ***\n");
+        Ok(())
+    }
+}
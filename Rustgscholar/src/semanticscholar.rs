@@ -1,24 +1,87 @@
 //! Semantic Scholar API Client
 //!
-//! Provides batch lookup of papers by DOI to retrieve abstracts and PDF links.
+//! [`SemanticScholarClient`] serves every Semantic Scholar call: batch
+//! lookup of papers by DOI to retrieve abstracts and PDF links, the title
+//! match fallback, keyword search as a Stage 1 source, and the citations
+//! and recommendations of a paper. It takes its API key from `--s2-key` or
+//! `S2_API_KEY` and, when it has one, sends requests faster and with more
+//! in flight through a bucket of that key alone.
 //!
 //! API Details:
 //! - Batch endpoint: POST /graph/v1/paper/batch
 //! - Max 500 papers per request
 //! - 10MB data limit per response
-//! - Rate limit: 1 req/s (unauthenticated), higher with API key
-
+//! - Search endpoint: GET /graph/v1/paper/search (100 per page, first 1000 results)
+//! - Title match endpoint: GET /graph/v1/paper/search/match (fallback for unknown DOIs)
+//! - Citations endpoint: GET /graph/v1/paper/{id}/citations (up to 1000 per request)
+//! - Recommendations endpoint: GET /recommendations/v1/papers/forpaper/{id} (up to 500)
+//! - Rate limit: 1 req/s in the shared pool, 4 req/s per key (lowered on
+//!   429), paced by [`crate::ratelimit`]; 429 honours `Retry-After`
+
+use crate::cache::{self, HttpCache};
+use crate::doi;
 use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::matching::TitleMatcher;
+use crate::ratelimit::{self, Rate, TokenBucket};
+use crate::raw_archive::{self, BodyKind};
+use crate::stream::{self, Page, PageSender};
+use crate::throttle::{self, Upstream};
+use crate::work_type::{self, WorkType};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// Fields requested for DOI and title lookups (with tldr and the SPECTER v2 embedding)
+const LOOKUP_FIELDS: &str = "title,abstract,url,isOpenAccess,openAccessPdf,externalIds,tldr,embedding.specter_v2";
+
+/// Fields requested for search results, citations and recommendations
+const SEARCH_FIELDS: &str = "title,abstract,authors,year,venue,publicationDate,externalIds,url,citationCount,isOpenAccess,openAccessPdf,publicationTypes";
+
 /// Semantic Scholar API base URL
-const SS_API_BASE: &str = "https://api.semanticscholar.org/graph/v1";
+pub const SS_API_BASE: &str = "https://api.semanticscholar.org/graph/v1";
+
+/// Semantic Scholar recommendations API base URL
+pub const RECOMMENDATIONS_API_BASE: &str = "https://api.semanticscholar.org/recommendations/v1";
+
+/// Host whose requests a key's bucket paces
+const API_HOST: &str = "api.semanticscholar.org";
+
+/// Environment variable holding the API key
+pub const API_KEY_VAR: &str = "S2_API_KEY";
 
 /// Maximum papers per batch request
-const MAX_BATCH_SIZE: usize = 500;
+pub const MAX_BATCH_SIZE: usize = 500;
+
+/// Papers per search page
+pub const SEARCH_PAGE_SIZE: usize = 100;
+
+/// Search results beyond this offset are not served
+pub const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// Most citations returned by one request
+const MAX_CITATIONS: usize = 1000;
+
+/// Most recommendations returned by one request
+const MAX_RECOMMENDATIONS: usize = 500;
+
+/// Requests in flight in the shared unauthenticated pool
+const SHARED_CONCURRENCY: usize = 1;
+
+/// Requests in flight with an API key
+pub const KEYED_CONCURRENCY: usize = 4;
+
+/// Time between two request starts with an API key (the shared pool gets
+/// one per second); a 429 slows it down to the key's actual limit
+pub const KEYED_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Pace of one API key
+pub const KEYED_RATE: Rate = Rate::every(KEYED_INTERVAL).with_burst(KEYED_CONCURRENCY as u32);
+
+/// Wait reported for a 429 without `Retry-After`
+const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
 
 /// Result from Semantic Scholar lookup
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -34,6 +97,52 @@ pub struct SemanticScholarResult {
     pub embedding: String,     // Specter v2 embedding (comma-separated floats)
 }
 
+/// A paper from a Semantic Scholar keyword search
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SemanticScholarPaper {
+    pub title: String,
+    pub author: String,
+    pub year: String,
+    pub publication_date: String,
+    pub venue: String,
+    pub doi: String,
+    pub ss_url: String,
+    pub citations: String,
+    pub ss_abstract: String,
+    pub is_oa: bool,
+    pub oa_pdf_url: String,
+    pub paper_id: String,
+    /// Publication types ("Review; JournalArticle")
+    #[serde(default)]
+    pub publication_types: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SSSearchResponse {
+    #[serde(default)]
+    total: u64,
+    #[serde(default)]
+    data: Vec<SSPaper>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SSCitationsResponse {
+    #[serde(default)]
+    data: Vec<SSCitation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SSCitation {
+    #[serde(rename = "citingPaper")]
+    citing_paper: Option<SSPaper>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SSRecommendationsResponse {
+    #[serde(rename = "recommendedPapers", default)]
+    recommended_papers: Vec<SSPaper>,
+}
+
 #[derive(Debug, Deserialize)]
 struct SSPaper {
     #[serde(rename = "paperId")]
@@ -50,6 +159,21 @@ struct SSPaper {
     #[serde(rename = "externalIds")]
     external_ids: Option<SSExternalIds>,
     embedding: Option<SSEmbedding>,
+    #[serde(default)]
+    authors: Vec<SSAuthor>,
+    year: Option<i32>,
+    venue: Option<String>,
+    #[serde(rename = "publicationDate")]
+    publication_date: Option<String>,
+    #[serde(rename = "citationCount")]
+    citation_count: Option<i64>,
+    #[serde(rename = "publicationTypes", default)]
+    publication_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SSAuthor {
+    name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,170 +197,405 @@ struct SSExternalIds {
     doi: Option<String>,
 }
 
-/// Batch lookup papers by DOI using Semantic Scholar API.
-///
-/// # Arguments
-///
-/// * `dois` - List of DOI strings (without "DOI:" prefix)
-/// * `api_key` - Optional API key for higher rate limits
-///
-/// # Returns
+/// API key in `S2_API_KEY`, if set and not blank
+pub fn env_key() -> Option<String> {
+    std::env::var(API_KEY_VAR).ok().filter(|k| !k.trim().is_empty())
+}
+
+/// Semantic Scholar API client
 ///
-/// List of results for papers found
-pub async fn batch_lookup(dois: &[String], api_key: Option<&str>) -> Result<Vec<SemanticScholarResult>> {
-    if dois.is_empty() {
-        return Ok(Vec::new());
+/// Every request goes through [`Self::send`]: it adds the `x-api-key`
+/// header, serves and stores responses through the HTTP cache when one is
+/// attached, and is paced like every other request (one start per second
+/// for the host; four in a bucket of its own with a key, see
+/// [`crate::ratelimit::key_bucket`]). A 429 is retried after the
+/// server's `Retry-After` by [`http::send_to`]; one that persists becomes
+/// [`GscholarError::RateLimited`] carrying that wait.
+#[derive(Debug, Clone)]
+pub struct SemanticScholarClient {
+    client: Client,
+    api_key: Option<String>,
+    /// Bucket of the API key, shared by every client with that key
+    key_bucket: Option<Arc<TokenBucket>>,
+    cache: Option<HttpCache>,
+}
+
+impl SemanticScholarClient {
+    /// Client with `api_key`, or the key in `S2_API_KEY` when `None` (blank
+    /// keys count as none)
+    pub fn new(api_key: Option<String>) -> Self {
+        let api_key = api_key.or_else(|| std::env::var(API_KEY_VAR).ok()).filter(|k| !k.trim().is_empty());
+        let key_bucket = api_key
+            .as_deref()
+            .map(|key| ratelimit::key_bucket(API_HOST, key, KEYED_RATE));
+        Self { client: http::client_for(Upstream::SemanticScholar), api_key, key_bucket, cache: None }
     }
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()?;
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/semanticscholar`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
 
-    // Filter out empty DOIs
-    let valid_dois: Vec<&String> = dois.iter().filter(|d| !d.is_empty()).collect();
-    
-    if valid_dois.is_empty() {
-        return Ok(Vec::new());
+    pub fn has_key(&self) -> bool {
+        self.api_key.is_some()
     }
 
-    info!(total = valid_dois.len(), "Starting Semantic Scholar batch lookup");
-
-    // Calculate optimal chunk size
-    let total = valid_dois.len();
-    let batch_count = if total <= MAX_BATCH_SIZE {
-        1
-    } else {
-        (total + MAX_BATCH_SIZE - 1) / MAX_BATCH_SIZE // ceil division
-    };
-    let chunk_size = (total + batch_count - 1) / batch_count; // ceil division
-
-    info!(
-        batches = batch_count,
-        chunk_size = chunk_size,
-        "Chunking DOIs for batch requests"
-    );
-
-    let mut all_results = Vec::new();
-
-    for (batch_idx, chunk) in valid_dois.chunks(chunk_size).enumerate() {
-        info!(
-            batch = batch_idx + 1,
-            total_batches = batch_count,
-            papers = chunk.len(),
-            "Processing batch"
-        );
-
-        match fetch_batch(&client, chunk, api_key).await {
-            Ok(papers) => {
-                info!(
-                    batch = batch_idx + 1,
-                    found = papers.len(),
-                    "Batch completed"
-                );
-                all_results.extend(papers);
-            }
-            Err(e) => {
-                warn!(batch = batch_idx + 1, error = %e, "Batch failed");
-                // Continue with other batches
-            }
+    /// Requests kept in flight by batch lookups and title matches: one in
+    /// the shared pool, more with a key, whose limit is the key's own
+    pub fn concurrency(&self) -> usize {
+        if self.has_key() {
+            KEYED_CONCURRENCY
+        } else {
+            SHARED_CONCURRENCY
         }
+    }
 
-        // Rate limiting: 1 second between requests (unauthenticated)
-        if batch_idx < batch_count - 1 {
-            tokio::time::sleep(Duration::from_secs(1)).await;
+    /// Batch lookup papers by DOI (without "DOI:" prefix), in requests of
+    /// up to 500 papers; a failed batch is logged and skipped
+    pub async fn batch_lookup(&self, dois: &[String]) -> Result<Vec<SemanticScholarResult>> {
+        let valid_dois: Vec<&String> = dois.iter().filter(|d| !d.is_empty()).collect();
+        if valid_dois.is_empty() {
+            return Ok(Vec::new());
         }
+
+        // Spread the DOIs evenly over the fewest batches
+        let total = valid_dois.len();
+        let batch_count = total.div_ceil(MAX_BATCH_SIZE);
+        let chunk_size = total.div_ceil(batch_count);
+        info!(total, batches = batch_count, chunk_size, keyed = self.has_key(), "Starting Semantic Scholar batch lookup");
+
+        let batches: Vec<_> = valid_dois
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(batch_idx, chunk)| async move {
+                match self.fetch_batch(chunk).await {
+                    Ok(papers) => {
+                        info!(batch = batch_idx + 1, papers = chunk.len(), found = papers.len(), "Batch completed");
+                        papers
+                    }
+                    Err(e) => {
+                        warn!(batch = batch_idx + 1, error = %e, "Batch failed");
+                        Vec::new()
+                    }
+                }
+            })
+            .collect();
+        let all_results: Vec<SemanticScholarResult> =
+            stream::in_order(batches, self.concurrency()).await.into_iter().flatten().collect();
+
+        info!(total_found = all_results.len(), "Semantic Scholar lookup complete");
+        Ok(all_results)
     }
 
-    info!(
-        total_found = all_results.len(),
-        "Semantic Scholar lookup complete"
-    );
+    /// Fetch a single batch of papers
+    async fn fetch_batch(&self, dois: &[&String]) -> Result<Vec<SemanticScholarResult>> {
+        // Request tldr and embedding.specter_v2 along with other fields
+        let url = format!("{}/paper/batch?fields={}", SS_API_BASE, LOOKUP_FIELDS);
+        let ids: Vec<String> = dois.iter().map(|d| format!("DOI:{}", d)).collect();
+        debug!(url = %url, count = ids.len(), "Sending batch request");
 
-    Ok(all_results)
-}
+        let body = self.send(self.client.post(&url).json(&serde_json::json!({ "ids": ids }))).await?;
+        let papers: Vec<Option<SSPaper>> = serde_json::from_str(&body)
+            .map_err(|e| GscholarError::Parse(format!("Failed to parse Semantic Scholar response: {}", e)))?;
 
-/// Fetch a single batch of papers
-async fn fetch_batch(
-    client: &Client,
-    dois: &[&String],
-    api_key: Option<&str>,
-) -> Result<Vec<SemanticScholarResult>> {
-    // Request tldr and embedding.specter_v2 along with other fields
-    let url = format!(
-        "{}/paper/batch?fields=title,abstract,url,isOpenAccess,openAccessPdf,externalIds,tldr,embedding.specter_v2",
-        SS_API_BASE
-    );
+        Ok(papers.into_iter().flatten().map(lookup_result).collect())
+    }
 
-    // Format DOIs with prefix
-    let ids: Vec<String> = dois.iter().map(|d| format!("DOI:{}", d)).collect();
+    /// Look up papers by title when Semantic Scholar does not know their DOI
+    ///
+    /// `papers` holds `(doi, title)` pairs. Each title goes to the
+    /// `/paper/search/match` endpoint, which returns the closest title match;
+    /// it is kept only when `matcher` accepts it, and under the paper's DOI so
+    /// it joins with the paper like a DOI hit.
+    pub async fn match_titles(
+        &self,
+        papers: &[(String, String)],
+        matcher: &TitleMatcher,
+    ) -> Result<Vec<SemanticScholarResult>> {
+        let url = format!("{}/paper/search/match", SS_API_BASE);
+        let matches: Vec<_> = papers
+            .iter()
+            .map(|(paper_doi, title)| {
+                let url = &url;
+                async move {
+                    let request = self.client.get(url).query(&[("query", title.as_str()), ("fields", LOOKUP_FIELDS)]);
+                    let body = match self.send(request).await {
+                        Ok(body) => body,
+                        // No paper with a similar title
+                        Err(GscholarError::Api { code: 404, .. }) => return None,
+                        Err(e) => {
+                            warn!(title = %title, error = %e, "Semantic Scholar title match failed");
+                            return None;
+                        }
+                    };
+                    let paper = match serde_json::from_str::<SSSearchResponse>(&body) {
+                        Ok(response) => response.data.into_iter().next()?,
+                        Err(e) => {
+                            warn!(title = %title, error = %e, "Failed to parse Semantic Scholar response");
+                            return None;
+                        }
+                    };
+                    let mut result = lookup_result(paper);
+                    if !matcher.matches(title, &result.title) {
+                        debug!(title = %title, found = %result.title, "Semantic Scholar title match rejected");
+                        return None;
+                    }
+                    result.doi = paper_doi.clone();
+                    Some(result)
+                }
+            })
+            .collect();
+        let results: Vec<SemanticScholarResult> =
+            stream::in_order(matches, self.concurrency()).await.into_iter().flatten().collect();
+        info!(searched = papers.len(), found = results.len(), "Semantic Scholar title fallback complete");
+        Ok(results)
+    }
 
-    let body = serde_json::json!({ "ids": ids });
+    /// Search papers by keyword (1-indexed pages of 100 results).
+    ///
+    /// Pages past the first 1000 results are skipped, and a failed page is
+    /// logged and skipped like in the OpenAlex client.
+    pub async fn search(
+        &self,
+        query: &str,
+        pages: &[i32],
+        ylo: Option<i32>,
+        work_types: &[WorkType],
+    ) -> Result<Vec<SemanticScholarPaper>> {
+        let (tx, rx) = stream::channel();
+        let all_results = stream::collect(self.search_pages(query, pages, ylo, work_types, tx), rx).await?;
+        info!(total = all_results.len(), "Semantic Scholar search complete");
+        Ok(all_results)
+    }
 
-    debug!(url = %url, count = ids.len(), "Sending batch request");
+    /// Search papers by keyword, sending each page down `tx` as soon as it is
+    /// parsed (see [`Self::search`])
+    pub async fn search_pages(
+        &self,
+        query: &str,
+        pages: &[i32],
+        ylo: Option<i32>,
+        work_types: &[WorkType],
+        tx: PageSender<SemanticScholarPaper>,
+    ) -> Result<()> {
+        info!(query = query, pages = ?pages, ylo = ?ylo, "Starting Semantic Scholar search");
+
+        for &page in pages {
+            let offset = (page.max(1) as usize - 1) * SEARCH_PAGE_SIZE;
+            if offset + SEARCH_PAGE_SIZE > MAX_SEARCH_RESULTS {
+                warn!(page = page, "Semantic Scholar serves only the first 1000 search results, skipping page");
+                continue;
+            }
 
-    let mut request = client.post(&url).json(&body);
+            let request =
+                self.client.get(format!("{}/paper/search", SS_API_BASE)).query(&search_params(query, offset, ylo, work_types));
+            match self.send(request).await.and_then(|body| parse_search_response(&body)) {
+                Ok(papers) => {
+                    info!(page = page, count = papers.len(), "Parsed Semantic Scholar results");
+                    let last_page = papers.len() < SEARCH_PAGE_SIZE;
+                    if tx.send(Page::new(page, papers)).await.is_err() || last_page {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!(page = page, error = %e, "Failed to fetch page");
+                    if tx.send(Page::failed(page, e)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
 
-    // Add API key header if provided
-    if let Some(key) = api_key {
-        request = request.header("x-api-key", key);
+        Ok(())
     }
 
-    let response = request.send().await?;
-    let status = response.status();
+    /// Number of search results for a query (a single one-result request)
+    pub async fn count(&self, query: &str, ylo: Option<i32>, work_types: &[WorkType]) -> Result<u64> {
+        let mut params = vec![("query", query.to_string()), ("limit", "1".to_string()), ("fields", "title".to_string())];
+        if let Some(year) = ylo {
+            params.push(("year", format!("{}-", year)));
+        }
+        if let Some(types) = work_type::semantic_scholar_filter(work_types) {
+            params.push(("publicationTypes", types));
+        }
+        let body = self.send(self.client.get(format!("{}/paper/search", SS_API_BASE)).query(&params)).await?;
+        let response: SSSearchResponse = serde_json::from_str(&body)
+            .map_err(|e| GscholarError::Parse(format!("Failed to parse Semantic Scholar response: {}", e)))?;
+        debug!(query = query, count = response.total, "Semantic Scholar hit count");
+        Ok(response.total)
+    }
 
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        warn!(status = status.as_u16(), error = %error_text, "API error");
-        return Err(GscholarError::Api {
-            code: status.as_u16() as i32,
-            message: format!("Semantic Scholar API error: {} - {}", status, error_text),
-        });
+    /// Papers citing a paper, up to `limit` (at most 1000)
+    ///
+    /// `paper_id` is a Semantic Scholar ID or a prefixed external ID such
+    /// as `DOI:10.1016/j.geomorph.2020.107124`.
+    pub async fn citations(&self, paper_id: &str, limit: usize) -> Result<Vec<SemanticScholarPaper>> {
+        let request = self
+            .client
+            .get(format!("{}/paper/{}/citations", SS_API_BASE, paper_id))
+            .query(&[("fields", SEARCH_FIELDS.to_string()), ("limit", limit.clamp(1, MAX_CITATIONS).to_string())]);
+        parse_citations_response(&self.send(request).await?)
     }
 
-    let papers: Vec<Option<SSPaper>> = response.json().await.map_err(|e| {
-        GscholarError::Parse(format!("Failed to parse Semantic Scholar response: {}", e))
-    })?;
-
-    // Convert to our result format
-    let results: Vec<SemanticScholarResult> = papers
-        .into_iter()
-        .filter_map(|p| p)
-        .map(|paper| {
-            let doi = paper
-                .external_ids
-                .and_then(|ids| ids.doi)
-                .unwrap_or_default();
-
-            // Extract tldr text
-            let tldr = paper.tldr.and_then(|t| t.text).unwrap_or_default();
-
-            // Convert embedding vector to comma-separated string
-            let embedding = paper
-                .embedding
-                .and_then(|e| e.vector)
-                .map(|v| {
-                    v.iter()
-                        .map(|f| format!("{:.6}", f))
-                        .collect::<Vec<_>>()
-                        .join(",")
-                })
-                .unwrap_or_default();
-
-            SemanticScholarResult {
-                title: paper.title.unwrap_or_default(),
-                doi,
-                ss_abstract: paper.abstract_text.unwrap_or_default(),
-                tldr,
-                ss_url: paper.url.unwrap_or_default(),
-                is_oa: paper.is_open_access.unwrap_or(false),
-                oa_pdf_url: paper.oa_pdf.and_then(|p| p.url).unwrap_or_default(),
-                paper_id: paper.paper_id.unwrap_or_default(),
-                embedding,
+    /// Papers Semantic Scholar recommends for a paper, up to `limit` (at most 500)
+    pub async fn recommendations(&self, paper_id: &str, limit: usize) -> Result<Vec<SemanticScholarPaper>> {
+        let request = self
+            .client
+            .get(format!("{}/papers/forpaper/{}", RECOMMENDATIONS_API_BASE, paper_id))
+            .query(&[("fields", SEARCH_FIELDS.to_string()), ("limit", limit.clamp(1, MAX_RECOMMENDATIONS).to_string())]);
+        parse_recommendations_response(&self.send(request).await?)
+    }
+
+    /// Send a request and return the body of a successful response, from the
+    /// HTTP cache when possible (keyed without the API key)
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String> {
+        let mut request = request.build()?;
+        let body = request.body().and_then(|b| b.as_bytes()).map(String::from_utf8_lossy).unwrap_or_default();
+        let key = cache::request_key(request.method().as_str(), request.url().as_str(), &body);
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(url = %request.url(), "Semantic Scholar cache hit");
+            return Ok(body);
+        }
+        if let Some(api_key) = &self.api_key {
+            let value = reqwest::header::HeaderValue::from_str(api_key)
+                .map_err(|_| GscholarError::Config("Invalid Semantic Scholar API key".to_string()))?;
+            request.headers_mut().insert("x-api-key", value);
+        }
+
+        let url = request.url().to_string();
+        let _slot = throttle::acquire(Upstream::SemanticScholar).await;
+        let response = match &self.key_bucket {
+            Some(bucket) => http::send_paced(Upstream::SemanticScholar, &self.client, request, bucket.clone()).await?,
+            None => http::send_to(Upstream::SemanticScholar, &self.client, request).await?,
+        };
+        let status = response.status();
+
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let wait = http::retry_after(&response).map_or(DEFAULT_RETRY_AFTER_SECS, |d| d.as_secs().max(1));
+            warn!(retry_after_secs = wait, keyed = self.has_key(), "Semantic Scholar rate limit reached");
+            return Err(GscholarError::RateLimited(wait));
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            warn!(status = status.as_u16(), error = %error_text, "API error");
+            return Err(GscholarError::Api {
+                code: status.as_u16() as i32,
+                message: format!("Semantic Scholar API error: {} - {}", status, error_text),
+            });
+        }
+
+        let body = response.text().await?;
+        raw_archive::record(&url, BodyKind::Json, &body);
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write Semantic Scholar cache");
             }
-        })
-        .collect();
+        }
+        Ok(body)
+    }
+}
+
+/// URL of one search page (1-indexed), as [`SemanticScholarClient::search`] requests it
+pub fn search_url(query: &str, page: i32, ylo: Option<i32>, work_types: &[WorkType]) -> Result<String> {
+    let offset = (page.max(1) as usize - 1) * SEARCH_PAGE_SIZE;
+    let params = search_params(query, offset, ylo, work_types);
+    reqwest::Url::parse_with_params(&format!("{}/paper/search", SS_API_BASE), params)
+        .map(String::from)
+        .map_err(|e| GscholarError::Config(format!("Invalid search URL: {}", e)))
+}
 
-    Ok(results)
+/// Query parameters of a search page
+fn search_params(query: &str, offset: usize, ylo: Option<i32>, work_types: &[WorkType]) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        ("query", query.to_string()),
+        ("offset", offset.to_string()),
+        ("limit", SEARCH_PAGE_SIZE.to_string()),
+        ("fields", SEARCH_FIELDS.to_string()),
+    ];
+    if let Some(year) = ylo {
+        params.push(("year", format!("{}-", year)));
+    }
+    if let Some(types) = work_type::semantic_scholar_filter(work_types) {
+        params.push(("publicationTypes", types));
+    }
+    params
+}
+
+/// Parse a `/paper/search` response body
+fn parse_search_response(body: &str) -> Result<Vec<SemanticScholarPaper>> {
+    let response: SSSearchResponse = serde_json::from_str(body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse Semantic Scholar response: {}", e)))?;
+    Ok(response.data.into_iter().map(search_result).collect())
+}
+
+/// Parse a `/paper/{id}/citations` response body
+fn parse_citations_response(body: &str) -> Result<Vec<SemanticScholarPaper>> {
+    let response: SSCitationsResponse = serde_json::from_str(body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse Semantic Scholar citations: {}", e)))?;
+    Ok(response.data.into_iter().filter_map(|c| c.citing_paper).map(search_result).collect())
+}
+
+/// Parse a recommendations response body
+fn parse_recommendations_response(body: &str) -> Result<Vec<SemanticScholarPaper>> {
+    let response: SSRecommendationsResponse = serde_json::from_str(body)
+        .map_err(|e| GscholarError::Parse(format!("Failed to parse Semantic Scholar recommendations: {}", e)))?;
+    Ok(response.recommended_papers.into_iter().map(search_result).collect())
+}
+
+/// Search result of a paper record
+fn search_result(paper: SSPaper) -> SemanticScholarPaper {
+    SemanticScholarPaper {
+        title: paper.title.unwrap_or_default(),
+        author: paper.authors.into_iter().filter_map(|a| a.name).collect::<Vec<_>>().join(", "),
+        year: paper.year.map(|y| y.to_string()).unwrap_or_default(),
+        publication_date: paper.publication_date.unwrap_or_default(),
+        venue: paper.venue.unwrap_or_default(),
+        doi: paper.external_ids.and_then(|ids| ids.doi).map(|d| doi::normalize(&d)).unwrap_or_default(),
+        ss_url: paper.url.unwrap_or_default(),
+        citations: paper.citation_count.map(|c| c.to_string()).unwrap_or_default(),
+        ss_abstract: paper.abstract_text.unwrap_or_default(),
+        is_oa: paper.is_open_access.unwrap_or(false),
+        oa_pdf_url: paper.oa_pdf.and_then(|p| p.url).unwrap_or_default(),
+        paper_id: paper.paper_id.unwrap_or_default(),
+        publication_types: paper.publication_types.unwrap_or_default().join("; "),
+    }
+}
+
+/// Lookup result of a paper record
+fn lookup_result(paper: SSPaper) -> SemanticScholarResult {
+    let doi = paper
+        .external_ids
+        .and_then(|ids| ids.doi)
+        .map(|d| doi::normalize(&d))
+        .unwrap_or_default();
+
+    // Extract tldr text
+    let tldr = paper.tldr.and_then(|t| t.text).unwrap_or_default();
+
+    // Convert embedding vector to comma-separated string
+    let embedding = paper
+        .embedding
+        .and_then(|e| e.vector)
+        .map(|v| {
+            v.iter()
+                .map(|f| format!("{:.6}", f))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+
+    SemanticScholarResult {
+        title: paper.title.unwrap_or_default(),
+        doi,
+        ss_abstract: paper.abstract_text.unwrap_or_default(),
+        tldr,
+        ss_url: paper.url.unwrap_or_default(),
+        is_oa: paper.is_open_access.unwrap_or(false),
+        oa_pdf_url: paper.oa_pdf.and_then(|p| p.url).unwrap_or_default(),
+        paper_id: paper.paper_id.unwrap_or_default(),
+        embedding,
+    }
 }
 
 #[cfg(test)]
@@ -246,11 +605,88 @@ mod tests {
     #[test]
     fn test_batch_chunking() {
         // Test that chunking logic is correct
-        let total = 1200;
-        let batch_count = (total + MAX_BATCH_SIZE - 1) / MAX_BATCH_SIZE;
+        let total: usize = 1200;
+        let batch_count = total.div_ceil(MAX_BATCH_SIZE);
         assert_eq!(batch_count, 3); // ceil(1200/500) = 3
         
-        let chunk_size = (total + batch_count - 1) / batch_count;
+        let chunk_size = total.div_ceil(batch_count);
         assert_eq!(chunk_size, 400); // ceil(1200/3) = 400
     }
+
+    #[test]
+    fn test_parse_search_response() -> Result<()> {
+        let body = r#"{"total": 2, "offset": 0, "data": [
+            {"paperId": "abc", "title": "Slope stability", "abstract": null, "year": 2021,
+             "venue": "Landslides", "publicationDate": "2021-03-01", "citationCount": 7,
+             "authors": [{"authorId": "1", "name": "A. Li"}, {"authorId": null, "name": "B. Wu"}],
+             "externalIds": {"DOI": "10.1/a"}, "url": "https://www.semanticscholar.org/paper/abc",
+             "isOpenAccess": true, "openAccessPdf": {"url": "https://x/a.pdf"},
+             "publicationTypes": ["JournalArticle", "Review"]},
+            {"paperId": "def", "title": "No metadata", "publicationTypes": null}
+        ]}"#;
+        let papers = parse_search_response(body)?;
+        assert_eq!(papers.len(), 2);
+        assert_eq!(papers[0].author, "A. Li, B. Wu");
+        assert_eq!((papers[0].year.as_str(), papers[0].citations.as_str()), ("2021", "7"));
+        assert_eq!(papers[0].doi, "10.1/a");
+        assert_eq!(papers[0].oa_pdf_url, "https://x/a.pdf");
+        assert_eq!(papers[0].publication_types, "JournalArticle; Review");
+        assert!(papers[1].doi.is_empty() && papers[1].ss_abstract.is_empty() && papers[1].publication_types.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_key() -> Result<()> {
+        let keyed = SemanticScholarClient::new(Some("s2-key".to_string()));
+        assert!(keyed.has_key());
+        assert_eq!(keyed.concurrency(), KEYED_CONCURRENCY);
+        // Keyed clients are paced by a bucket of their key, not the host's
+        let bucket = keyed.key_bucket.clone().ok_or_else(|| GscholarError::Config("no key bucket".to_string()))?;
+        assert_eq!(bucket.interval(), KEYED_INTERVAL);
+        let same = SemanticScholarClient::new(Some("s2-key".to_string())).key_bucket;
+        assert!(same.is_some_and(|b| Arc::ptr_eq(&b, &bucket)));
+        let other = SemanticScholarClient::new(Some("other-key".to_string())).key_bucket;
+        assert!(other.is_some_and(|b| !Arc::ptr_eq(&b, &bucket)));
+        // A blank key is no key (and does not fall back to the environment)
+        let blank = SemanticScholarClient::new(Some("  ".to_string()));
+        assert!(!blank.has_key());
+        assert_eq!(blank.concurrency(), SHARED_CONCURRENCY);
+        assert!(blank.key_bucket.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_citations_and_recommendations() -> Result<()> {
+        let body = r#"{"offset": 0, "data": [
+            {"citingPaper": {"paperId": "c1", "title": "Rainfall thresholds", "year": 2023,
+             "externalIds": {"DOI": "10.1/C1"}, "authors": [{"name": "C. Zhao"}]}},
+            {"citingPaper": null}
+        ]}"#;
+        let citing = parse_citations_response(body)?;
+        assert_eq!(citing.len(), 1);
+        assert_eq!((citing[0].paper_id.as_str(), citing[0].doi.as_str()), ("c1", "10.1/c1"));
+        assert_eq!(citing[0].author, "C. Zhao");
+
+        let body = r#"{"recommendedPapers": [{"paperId": "r1", "title": "InSAR time series", "citationCount": 12}]}"#;
+        let recommended = parse_recommendations_response(body)?;
+        assert_eq!((recommended[0].title.as_str(), recommended[0].citations.as_str()), ("InSAR time series", "12"));
+        assert!(parse_recommendations_response("{}")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_result() -> Result<()> {
+        let body = r#"{"data": [{"paperId": "abc", "title": "Debris flow run-out prediction",
+            "externalIds": {"DOI": "https://doi.org/10.1/ABC"}, "tldr": {"text": "Short."},
+            "embedding": {"vector": [0.5, -0.25]}}]}"#;
+        let response: SSSearchResponse = serde_json::from_str(body)?;
+        let Some(paper) = response.data.into_iter().next() else {
+            return Err(GscholarError::Parse("no paper".to_string()));
+        };
+        let result = lookup_result(paper);
+        assert_eq!((result.doi.as_str(), result.tldr.as_str()), ("10.1/abc", "Short."));
+        assert_eq!(result.embedding, "0.500000,-0.250000");
+        assert!(!result.is_oa && result.ss_abstract.is_empty());
+        Ok(())
+    }
 }
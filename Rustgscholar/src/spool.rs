@@ -0,0 +1,144 @@
+//! Search records spooled to disk.
+//!
+//! Stage 1 appends each page's records to a [`Spool`] in the run folder's
+//! `records/` directory as the page is kept, instead of collecting every
+//! source's records in one `Vec` until the search ends; a search that fails
+//! late leaves the records found so far on disk. The finished spool is the
+//! search checkpoint: `state.json` records its [`Spooled`] file and count,
+//! and Stages 3-5 (and `--resume`) read the records back from the file.
+
+use crate::error::{GscholarError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Folder of the spools, in the run folder
+pub const SPOOL_DIR: &str = "records";
+
+/// Spool being written, one JSON record per line
+pub struct Spool<T> {
+    file: String,
+    writer: BufWriter<File>,
+    records: usize,
+    _records: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> Spool<T> {
+    /// New spool `records/<name>.jsonl` in the run folder, replacing any earlier one
+    pub fn create(folder: &Path, name: &str) -> Result<Self> {
+        let file = format!("{}/{}.jsonl", SPOOL_DIR, name);
+        std::fs::create_dir_all(folder.join(SPOOL_DIR))?;
+        let writer = BufWriter::new(File::create(folder.join(&file))?);
+        Ok(Self { file, writer, records: 0, _records: PhantomData })
+    }
+
+    /// Append a record
+    pub fn push(&mut self, record: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Append records
+    pub fn extend<'a>(&mut self, records: impl IntoIterator<Item = &'a T>) -> Result<()>
+    where
+        T: 'a,
+    {
+        records.into_iter().try_for_each(|record| self.push(record))
+    }
+
+    /// Records appended so far
+    pub fn len(&self) -> usize {
+        self.records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// Flush the file, returning its reference for the next stage
+    pub fn finish(mut self) -> Result<Spooled> {
+        self.writer.flush()?;
+        Ok(Spooled { file: self.file, records: self.records })
+    }
+}
+
+/// A finished spool: its file, relative to the run folder, and record count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spooled {
+    pub file: String,
+    pub records: usize,
+}
+
+impl Spooled {
+    pub fn len(&self) -> usize {
+        self.records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// Read the records back one at a time, in order
+    pub fn reader<T: DeserializeOwned>(&self, folder: &Path) -> Result<SpoolReader<T>> {
+        let path = folder.join(&self.file);
+        let file =
+            File::open(&path).map_err(|e| GscholarError::Parse(format!("Missing spooled records {:?}: {}", path, e)))?;
+        Ok(SpoolReader { path, lines: BufReader::new(file).lines(), _records: PhantomData })
+    }
+
+    /// Every record at once
+    pub fn read_all<T: DeserializeOwned>(&self, folder: &Path) -> Result<Vec<T>> {
+        self.reader(folder)?.collect()
+    }
+}
+
+/// Records of a [`Spooled`] file
+pub struct SpoolReader<T> {
+    path: PathBuf,
+    lines: std::io::Lines<BufReader<File>>,
+    _records: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Iterator for SpoolReader<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.by_ref().find(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(
+            serde_json::from_str(&line)
+                .map_err(|e| GscholarError::Parse(format!("Invalid record in {:?}: {}", self.path, e))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spool_round_trip() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let mut spool = Spool::create(dir.path(), "1_search")?;
+        let titles: Vec<String> = (0..3).map(|i| format!("Paper {}\nwith a line break", i)).collect();
+        spool.extend(&titles)?;
+        assert_eq!(spool.len(), 3);
+        let spooled = spool.finish()?;
+        assert_eq!(spooled, Spooled { file: "records/1_search.jsonl".to_string(), records: 3 });
+
+        let mut reader = spooled.reader::<String>(dir.path())?;
+        assert_eq!(reader.next().transpose()?, Some(titles[0].clone()));
+        assert_eq!(spooled.read_all::<String>(dir.path())?, titles);
+
+        std::fs::remove_file(dir.path().join(&spooled.file))?;
+        assert!(spooled.reader::<String>(dir.path()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+//! SQLite database of a run's output.
+//!
+//! Collects a run folder's stage CSVs into one `run.db` so results can be
+//! queried with SQL across stages:
+//!
+//! - `papers` - unified results (`5_unified.csv`)
+//! - `rankings` - journal rankings (`3_easyscholar.csv`)
+//! - `llm_verdicts` - Stage 6 verdicts (`6_llm_filtered.csv`)
+//! - `stage_*` - every other stage CSV as-is
+//!
+//! `rankings` and `llm_verdicts` carry the paper's `doi` and a `paper_id`
+//! foreign key into `papers`, resolved by DOI, and every `doi` and
+//! `paper_id` column is indexed. Tables are created and filled through
+//! rusqlite in one transaction (feature "sqlite").
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::output::{cell, infer_type, ColumnType};
+use rusqlite::types::{ToSql, ToSqlOutput};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Database file name inside a run folder
+pub const DATABASE_FILE: &str = "run.db";
+
+const PAPERS_CSV: &str = "5_unified.csv";
+const RANKINGS_CSV: &str = "3_easyscholar.csv";
+const VERDICTS_CSV: &str = "6_llm_filtered.csv";
+
+/// A column value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            Value::Null => ToSqlOutput::from(rusqlite::types::Null),
+            Value::Integer(v) => ToSqlOutput::from(*v),
+            Value::Real(v) => ToSqlOutput::from(*v),
+            Value::Text(v) => ToSqlOutput::from(v.as_str()),
+        })
+    }
+}
+
+/// A table with an implicit `id INTEGER PRIMARY KEY` column
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub name: String,
+    /// (column name, declared type and constraints)
+    pub columns: Vec<(String, String)>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Build a table from string cells, inferring INTEGER/REAL/TEXT per column
+    pub fn from_strings(name: &str, header: &[String], rows: &[Vec<String>]) -> Self {
+        let types: Vec<ColumnType> = (0..header.len())
+            .map(|col| infer_type(rows.iter().map(|r| cell(r, col))))
+            .collect();
+
+        let columns = header
+            .iter()
+            .zip(&types)
+            .map(|(h, t)| {
+                // `id` is the implicit rowid column
+                let name = if h.eq_ignore_ascii_case("id") { "source_id".to_string() } else { h.clone() };
+                let declared = match t {
+                    ColumnType::Int64 => "INTEGER",
+                    ColumnType::Double => "REAL",
+                    ColumnType::Utf8 => "TEXT",
+                };
+                (name, declared.to_string())
+            })
+            .collect();
+
+        let rows = rows
+            .iter()
+            .map(|row| {
+                types
+                    .iter()
+                    .enumerate()
+                    .map(|(col, t)| {
+                        let value = cell(row, col);
+                        match t {
+                            _ if value.is_empty() => Value::Null,
+                            ColumnType::Int64 => value.parse().map(Value::Integer).unwrap_or(Value::Null),
+                            ColumnType::Double => value.parse().map(Value::Real).unwrap_or(Value::Null),
+                            ColumnType::Utf8 => Value::Text(value.to_string()),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { name: name.to_string(), columns, rows }
+    }
+
+    /// Prepend a `paper_id` foreign key into `papers`, looked up by the `doi` column
+    fn link_papers(&mut self, doi_ids: &HashMap<String, i64>) {
+        let doi_col = self.columns.iter().position(|(name, _)| name == "doi");
+        self.columns.insert(0, ("paper_id".to_string(), "INTEGER REFERENCES papers(id)".to_string()));
+        for row in &mut self.rows {
+            let paper_id = match doi_col.and_then(|c| row.get(c)) {
+                Some(Value::Text(doi)) => doi_ids.get(&doi::normalize(doi)).map(|&id| Value::Integer(id)),
+                _ => None,
+            };
+            row.insert(0, paper_id.unwrap_or(Value::Null));
+        }
+    }
+
+    fn create_sql(&self) -> String {
+        let columns: Vec<String> = std::iter::once("id INTEGER PRIMARY KEY".to_string())
+            .chain(self.columns.iter().map(|(name, declared)| format!("{} {}", quote(name), declared)))
+            .collect();
+        format!("CREATE TABLE {} ({})", quote(&self.name), columns.join(", "))
+    }
+
+    /// Indexes on the `doi` and `paper_id` columns, the keys tables are joined by
+    fn index_sql(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .filter(|(name, _)| name == "doi" || name == "paper_id")
+            .map(|(name, _)| {
+                format!("CREATE INDEX {} ON {} ({})", quote(&format!("{}_{}", self.name, name)), quote(&self.name), quote(name))
+            })
+            .collect()
+    }
+
+    fn insert_sql(&self) -> String {
+        let columns: Vec<String> = std::iter::once("id".to_string()).chain(self.columns.iter().map(|(name, _)| quote(name))).collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+        format!("INSERT INTO {} ({}) VALUES ({})", quote(&self.name), columns.join(", "), placeholders.join(", "))
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Collect a run folder's stage CSVs into `run.db`, returning its path
+pub fn write_run_database(folder: &Path) -> Result<PathBuf> {
+    let mut csv_paths: Vec<PathBuf> = std::fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
+        .collect();
+    csv_paths.sort();
+
+    let mut tables = Vec::new();
+    let mut doi_ids = HashMap::new();
+
+    let papers_path = folder.join(PAPERS_CSV);
+    if papers_path.exists() {
+        let (header, rows) = read_csv(&papers_path)?;
+        let papers = Table::from_strings("papers", &header, &rows);
+        if let Some(doi_col) = header.iter().position(|h| h == "doi") {
+            for (i, row) in rows.iter().enumerate() {
+                let doi = doi::normalize(cell(row, doi_col));
+                if !doi.is_empty() {
+                    doi_ids.entry(doi).or_insert(i as i64 + 1);
+                }
+            }
+        }
+        tables.push(papers);
+    }
+
+    for (file, table_name) in [(RANKINGS_CSV, "rankings"), (VERDICTS_CSV, "llm_verdicts")] {
+        let path = folder.join(file);
+        if !path.exists() {
+            continue;
+        }
+        let (mut header, rows) = read_csv(&path)?;
+        // Stage 6 keys verdicts by `id`, which holds the DOI
+        if table_name == "llm_verdicts" {
+            for h in header.iter_mut().filter(|h| h.as_str() == "id") {
+                *h = "doi".to_string();
+            }
+        }
+        let mut table = Table::from_strings(table_name, &header, &rows);
+        if !tables.is_empty() {
+            table.link_papers(&doi_ids);
+        }
+        tables.push(table);
+    }
+
+    for path in &csv_paths {
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if [PAPERS_CSV, RANKINGS_CSV, VERDICTS_CSV].contains(&file_name.as_str()) {
+            continue;
+        }
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let (header, rows) = read_csv(path)?;
+        tables.push(Table::from_strings(&format!("stage_{}", stem), &header, &rows));
+    }
+
+    let db_path = folder.join(DATABASE_FILE);
+    write_database(&db_path, &tables)?;
+    Ok(db_path)
+}
+
+fn read_csv(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+    let header = reader
+        .headers()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?
+        .iter()
+        .map(String::from)
+        .collect();
+    let rows = reader
+        .records()
+        .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+        .collect::<std::result::Result<Vec<Vec<String>>, _>>()
+        .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?;
+    Ok((header, rows))
+}
+
+/// Write tables to a new SQLite database file (replacing any existing file)
+pub fn write_database(path: &Path, tables: &[Table]) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut connection = Connection::open(path).map_err(sqlite_error)?;
+    let transaction = connection.transaction().map_err(sqlite_error)?;
+    for table in tables {
+        transaction.execute(&table.create_sql(), []).map_err(sqlite_error)?;
+        let mut insert = transaction.prepare(&table.insert_sql()).map_err(sqlite_error)?;
+        for (r, row) in table.rows.iter().enumerate() {
+            let id = Value::Integer(r as i64 + 1);
+            let values: Vec<&dyn ToSql> = std::iter::once(&id as &dyn ToSql).chain(row.iter().map(|v| v as &dyn ToSql)).collect();
+            insert.execute(values.as_slice()).map_err(sqlite_error)?;
+        }
+        for sql in table.index_sql() {
+            transaction.execute(&sql, []).map_err(sqlite_error)?;
+        }
+    }
+    transaction.commit().map_err(sqlite_error)?;
+    info!(path = ?path, tables = tables.len(), "Saved SQLite database");
+    Ok(())
+}
+
+fn sqlite_error(error: rusqlite::Error) -> GscholarError {
+    GscholarError::Parse(format!("SQLite database: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_run_database() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(dir.path().join(PAPERS_CSV), "title,doi,if_score\nA,10.1/A,5.2\nB,,\n")?;
+        std::fs::write(dir.path().join(VERDICTS_CSV), "id,title,label,confidence\n10.1/a,A,relevant,0.9\n")?;
+        std::fs::write(dir.path().join("1_openalex.csv"), "title,citations\nA,3\n")?;
+
+        let connection = Connection::open(write_run_database(dir.path())?).map_err(sqlite_error)?;
+        let schema: Vec<String> = connection
+            .prepare("SELECT sql FROM sqlite_schema ORDER BY rowid")
+            .and_then(|mut statement| statement.query_map([], |row| row.get(0))?.collect())
+            .map_err(sqlite_error)?;
+        assert!(schema.contains(&r#"CREATE TABLE "papers" (id INTEGER PRIMARY KEY, "title" TEXT, "doi" TEXT, "if_score" REAL)"#.to_string()));
+        assert!(schema.iter().any(|sql| sql.starts_with(r#"CREATE TABLE "llm_verdicts" (id INTEGER PRIMARY KEY, "paper_id" INTEGER REFERENCES papers(id), "doi" TEXT"#)));
+        assert!(schema.iter().any(|sql| sql.starts_with(r#"CREATE TABLE "stage_1_openalex""#)));
+        assert!(schema.contains(&r#"CREATE INDEX "llm_verdicts_paper_id" ON "llm_verdicts" ("paper_id")"#.to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_run_database() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        // An abstract longer than a page and enough rows to span many pages
+        let long_abstract = "slope ".repeat(5000);
+        let mut papers = format!("title,doi,if_score,abstract\nA,10.1/A,5.2,{}\nB,,,\n", long_abstract);
+        for i in 0..2000 {
+            papers.push_str(&format!("Paper {},10.1/p{},{}.5,short\n", i, i, i % 10));
+        }
+        std::fs::write(dir.path().join(PAPERS_CSV), papers)?;
+        std::fs::write(
+            dir.path().join(VERDICTS_CSV),
+            "id,title,label,confidence\nhttps://doi.org/10.1/a,A,relevant,0.9\ndoi:10.1/P7,Paper 7,irrelevant,0.8\n",
+        )?;
+
+        let conn = rusqlite::Connection::open(write_run_database(dir.path())?)?;
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        assert_eq!(integrity, "ok");
+        let count: i64 = conn.query_row("SELECT count(*) FROM papers", [], |row| row.get(0))?;
+        assert_eq!(count, 2002);
+        let (title, score, length): (String, f64, i64) =
+            conn.query_row("SELECT title, if_score, length(abstract) FROM papers WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+        assert_eq!((title.as_str(), score, length), ("A", 5.2, long_abstract.len() as i64));
+        let last: String = conn.query_row("SELECT title FROM papers WHERE id = 2002", [], |row| row.get(0))?;
+        assert_eq!(last, "Paper 1999");
+
+        let mut verdicts = conn.prepare("SELECT paper_id, label FROM llm_verdicts ORDER BY id")?;
+        let rows: Vec<(i64, String)> =
+            verdicts.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<std::result::Result<_, _>>()?;
+        assert_eq!(rows, [(1, "relevant".to_string()), (10, "irrelevant".to_string())]);
+        Ok(())
+    }
+}
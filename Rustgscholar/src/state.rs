@@ -0,0 +1,147 @@
+//! Run state for resuming the pipeline.
+//!
+//! Every run folder keeps a `state.json` listing the stages that completed,
+//! the PRISMA counts and token usage so far, and the output of each completed
+//! stage. Resuming a folder reloads that output instead of re-running the
+//! stage, so a crash at Stage 6 does not mean scraping Scholar again. The
+//! file is replaced atomically (written to a temporary file, then renamed)
+//! so a crash mid-write leaves the previous state intact.
+
+use crate::error::{GscholarError, Result};
+use crate::llm_filter::TokenUsage;
+use crate::prisma::PrismaCounts;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+/// State file name inside a run folder
+pub const STATE_FILE: &str = "state.json";
+
+/// Pipeline stages that record their output in the run state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Pre-stage LLM query expansion
+    QueryExpansion,
+    /// Stages 1-2: search results (with Crossref metadata)
+    Search,
+    /// Stage 3: EasyScholar rankings and filters
+    Rankings,
+    /// Stages 4-5: Semantic Scholar lookup and unified dataset
+    Unified,
+    /// Stage 6: LLM verdicts
+    Screening,
+    /// Stage 7: relevant papers exported (and pushed to Zotero)
+    Relevant,
+    /// Stage 8: study designs
+    StudyDesign,
+    /// Stage 8: topic tags
+    Tagging,
+}
+
+/// Completed stages and their outputs for one run folder
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunState {
+    pub keyword: String,
+    /// Search source display name (e.g., "OpenAlex")
+    pub source: String,
+    /// Stages completed, in the order they finished
+    pub completed: Vec<Stage>,
+    /// Start year chosen by `--auto-ylo`, kept for `--resume`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ylo: Option<i32>,
+    pub prisma: PrismaCounts,
+    pub token_usage: TokenUsage,
+    /// Output of each completed stage
+    #[serde(default)]
+    data: BTreeMap<Stage, serde_json::Value>,
+}
+
+impl RunState {
+    pub fn new(keyword: &str, source: &str) -> Self {
+        Self {
+            keyword: keyword.to_string(),
+            source: source.to_string(),
+            prisma: PrismaCounts::new(keyword, source),
+            ..Default::default()
+        }
+    }
+
+    /// Load the state of a run folder, if it has one
+    pub fn load(folder: &Path) -> Result<Option<Self>> {
+        let path = folder.join(STATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let state: Self = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| GscholarError::Parse(format!("Invalid run state {:?}: {}", path, e)))?;
+        info!(path = ?path, completed = state.completed.len(), "Loaded run state");
+        Ok(Some(state))
+    }
+
+    /// Write the state into a run folder
+    pub fn save(&self, folder: &Path) -> Result<()> {
+        let tmp = folder.join(format!("{}.tmp", STATE_FILE));
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp, folder.join(STATE_FILE))?;
+        Ok(())
+    }
+
+    pub fn is_completed(&self, stage: Stage) -> bool {
+        self.completed.contains(&stage)
+    }
+
+    /// Last stage that completed
+    pub fn last_completed(&self) -> Option<Stage> {
+        self.completed.last().copied()
+    }
+
+    /// Mark a stage completed with its output
+    pub fn complete<T: Serialize>(&mut self, stage: Stage, data: &T) -> Result<()> {
+        self.data.insert(stage, serde_json::to_value(data)?);
+        if !self.is_completed(stage) {
+            self.completed.push(stage);
+        }
+        Ok(())
+    }
+
+    /// Output recorded for a completed stage
+    pub fn data<T: DeserializeOwned>(&self, stage: Stage) -> Result<Option<T>> {
+        if !self.is_completed(stage) {
+            return Ok(None);
+        }
+        self.data
+            .get(&stage)
+            .map(|value| T::deserialize(value))
+            .transpose()
+            .map_err(|e| GscholarError::Parse(format!("Invalid {:?} data in run state: {}", stage, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_roundtrip() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        assert!(RunState::load(dir.path())?.is_none());
+
+        let mut state = RunState::new("landslide", "OpenAlex");
+        state.complete(Stage::Search, &vec!["a".to_string(), "b".to_string()])?;
+        state.complete(Stage::QueryExpansion, &())?;
+        state.prisma.identified = 2;
+        state.save(dir.path())?;
+
+        let loaded = RunState::load(dir.path())?.expect("state saved");
+        assert_eq!(loaded.completed, vec![Stage::Search, Stage::QueryExpansion]);
+        assert_eq!(loaded.last_completed(), Some(Stage::QueryExpansion));
+        assert_eq!(loaded.prisma.identified, 2);
+        assert_eq!(loaded.data::<Vec<String>>(Stage::Search)?, Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(loaded.data::<Vec<String>>(Stage::Rankings)?, None);
+        assert!(loaded.data::<Vec<u32>>(Stage::Search).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+//! Server-side store of the results of every `serve` job.
+//!
+//! Each finished job's unified results (`5_unified.csv`) are indexed with
+//! the Stage 6 label of every paper, so clients can slice earlier runs with
+//! `GET /papers` instead of running the pipeline again. The run folders stay
+//! the source of truth: the store is rebuilt from them on start and written
+//! to a `results.db` SQLite database per workspace (the output directory,
+//! or a tenant's workspace) for SQL clients (feature "sqlite").
+
+use crate::error::{GscholarError, Result};
+use crate::jobs;
+use crate::llm_filter::FilterResult;
+use crate::manifest::Manifest;
+use crate::network::{field, read_rows};
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{self, Table};
+use crate::unified::UnifiedRow;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+/// Database file of a workspace
+pub const RESULTS_DATABASE: &str = "results.db";
+
+/// Page size when none is requested
+pub const DEFAULT_PER_PAGE: usize = 50;
+
+/// Largest page size
+pub const MAX_PER_PAGE: usize = 500;
+
+/// Fields `sort` accepts (prefix `-` for descending)
+pub const SORT_FIELDS: &[&str] = &["if_score", "jci_score", "altmetric_score", "citations", "citations_per_year", "normalized_citations", "year", "title", "job"];
+
+const UNIFIED_CSV: &str = "5_unified.csv";
+const VERDICTS_CSV: &str = "6_llm_filtered.csv";
+
+/// One paper of one job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredPaper {
+    pub job_id: String,
+    /// Search keyword of the job
+    pub keyword: String,
+    /// Stage 6 label (relevant, irrelevant, uncertain); empty when not screened
+    pub label: String,
+    #[serde(flatten)]
+    pub paper: UnifiedRow,
+    #[serde(skip)]
+    tenant: Option<String>,
+}
+
+impl StoredPaper {
+    fn year(&self) -> Option<i32> {
+        self.paper.date.get(..4).and_then(|y| y.parse().ok())
+    }
+
+    fn number(value: &str) -> Option<f64> {
+        value.trim().parse().ok()
+    }
+}
+
+/// Query parameters of `GET /papers`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PaperQuery {
+    /// Words that must all appear in the title, abstract or journal
+    pub query: Option<String>,
+    /// Minimum impact factor (papers without one are left out)
+    pub min_if: Option<f64>,
+    /// Minimum citation count (papers without one are left out)
+    pub min_citations: Option<u64>,
+    pub label: Option<String>,
+    /// Only this job's papers
+    pub job: Option<String>,
+    /// Published in or after this year
+    pub ylo: Option<i32>,
+    /// One of [`SORT_FIELDS`], `-` prefix for descending (default: newest job first)
+    pub sort: Option<String>,
+    /// 1-based page number
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+/// A page of query results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaperPage {
+    /// Matching papers across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub papers: Vec<StoredPaper>,
+}
+
+/// Indexed results of the server's jobs
+pub struct ResultStore {
+    /// Workspaces get their `results.db` under here
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    output_dir: PathBuf,
+    papers: RwLock<Vec<StoredPaper>>,
+}
+
+impl ResultStore {
+    /// Index the run folders of `output_dir` (`job_*` and `tenants/*/job_*`)
+    pub fn open(output_dir: &Path) -> Result<Self> {
+        let store = Self { output_dir: output_dir.to_path_buf(), papers: RwLock::new(Vec::new()) };
+        let mut workspaces = vec![(None, output_dir.to_path_buf())];
+        let tenants_dir = output_dir.join(jobs::TENANTS_FOLDER);
+        if tenants_dir.is_dir() {
+            for entry in std::fs::read_dir(&tenants_dir)? {
+                let entry = entry?;
+                if entry.path().is_dir() {
+                    workspaces.push((Some(entry.file_name().to_string_lossy().into_owned()), entry.path()));
+                }
+            }
+        }
+
+        let mut papers = Vec::new();
+        for (tenant, workspace) in &workspaces {
+            if !workspace.is_dir() {
+                continue;
+            }
+            let mut runs: Vec<PathBuf> = std::fs::read_dir(workspace)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_dir() && job_id(p).is_some())
+                .collect();
+            runs.sort();
+            for run in runs {
+                match load_run(&run, tenant.as_deref()) {
+                    Ok(run_papers) => papers.extend(run_papers),
+                    Err(e) => warn!(folder = ?run, error = %e, "Skipping unreadable run folder"),
+                }
+            }
+        }
+        info!(papers = papers.len(), "Loaded result store");
+        *store.write()? = papers;
+        for (tenant, _) in &workspaces {
+            store.persist(tenant.as_deref())?;
+        }
+        Ok(store)
+    }
+
+    /// Index (or re-index) a finished job's run folder; returns its paper count
+    pub fn add_run(&self, folder: &Path, tenant: Option<&str>) -> Result<usize> {
+        let run_papers = load_run(folder, tenant)?;
+        let count = run_papers.len();
+        let id = job_id(folder).unwrap_or_default();
+        {
+            let mut papers = self.write()?;
+            papers.retain(|p| !(p.job_id == id && p.tenant.as_deref() == tenant));
+            papers.extend(run_papers);
+        }
+        self.persist(tenant)?;
+        Ok(count)
+    }
+
+    /// Papers of `tenant` matching a query, one page at a time
+    pub fn query(&self, query: &PaperQuery, tenant: Option<&str>) -> Result<PaperPage> {
+        let page = query.page.unwrap_or(1);
+        let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page == 0 || per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(GscholarError::Validation(format!("page must be >= 1 and per_page 1..={}", MAX_PER_PAGE)));
+        }
+        let (sort_field, descending) = match query.sort.as_deref().map(str::trim) {
+            None | Some("") => ("job", true),
+            Some(sort) => {
+                let field = sort.trim_start_matches('-');
+                if !SORT_FIELDS.contains(&field) {
+                    return Err(GscholarError::Validation(format!(
+                        "Unknown sort field '{}' (expected one of {})",
+                        field,
+                        SORT_FIELDS.join(", ")
+                    )));
+                }
+                (field, sort.starts_with('-'))
+            }
+        };
+        let words: Vec<String> =
+            query.query.as_deref().unwrap_or_default().split_whitespace().map(str::to_lowercase).collect();
+        let label = query.label.as_deref().map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty());
+
+        let papers = self.read()?;
+        let mut matches: Vec<&StoredPaper> = papers
+            .iter()
+            .filter(|p| p.tenant.as_deref() == tenant)
+            .filter(|p| query.job.as_deref().is_none_or(|job| p.job_id == job))
+            .filter(|p| label.as_deref().is_none_or(|label| p.label == label))
+            .filter(|p| query.min_if.is_none_or(|min| StoredPaper::number(&p.paper.if_score).is_some_and(|v| v >= min)))
+            .filter(|p| query.min_citations.is_none_or(|min| crate::paper::parse_count(&p.paper.citations).is_some_and(|c| c >= min)))
+            .filter(|p| query.ylo.is_none_or(|ylo| p.year().is_some_and(|year| year >= ylo)))
+            .filter(|p| {
+                let text = format!("{} {} {}", p.paper.title, p.paper.abstract_text, p.paper.journal).to_lowercase();
+                words.iter().all(|w| text.contains(w.as_str()))
+            })
+            .collect();
+
+        let number = |p: &StoredPaper| match sort_field {
+            "if_score" => StoredPaper::number(&p.paper.if_score),
+            "jci_score" => StoredPaper::number(&p.paper.jci_score),
+            "altmetric_score" => StoredPaper::number(&p.paper.altmetric_score),
+            "citations" => crate::paper::parse_count(&p.paper.citations).map(|c| c as f64),
+            "citations_per_year" => StoredPaper::number(&p.paper.citations_per_year),
+            "normalized_citations" => StoredPaper::number(&p.paper.normalized_citations),
+            "year" => p.year().map(f64::from),
+            _ => None,
+        };
+        matches.sort_by(|a, b| {
+            let order = match sort_field {
+                "title" => a.paper.title.to_lowercase().cmp(&b.paper.title.to_lowercase()),
+                "job" => a.job_id.cmp(&b.job_id),
+                // Papers without the value go last either way
+                _ => match (number(a), number(b)) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+            };
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+
+        Ok(PaperPage {
+            total: matches.len(),
+            page,
+            per_page,
+            papers: matches.into_iter().skip((page - 1) * per_page).take(per_page).cloned().collect(),
+        })
+    }
+
+    /// Rewrite the `results.db` of a workspace
+    #[cfg(feature = "sqlite")]
+    fn persist(&self, tenant: Option<&str>) -> Result<()> {
+        let folder = match tenant {
+            Some(tenant) => jobs::workspace(&self.output_dir, tenant),
+            None => self.output_dir.clone(),
+        };
+        let mut header = vec!["job_id".to_string(), "keyword".to_string(), "label".to_string()];
+        header.extend(crate::unified::UNIFIED_COLUMNS.iter().map(|c| c.to_string()));
+        let rows: Vec<Vec<String>> = self
+            .read()?
+            .iter()
+            .filter(|p| p.tenant.as_deref() == tenant)
+            .map(|p| {
+                let mut row = vec![p.job_id.clone(), p.keyword.clone(), p.label.clone()];
+                row.extend(unified_cells(&p.paper));
+                row
+            })
+            .collect();
+        std::fs::create_dir_all(&folder)?;
+        sqlite::write_database(&folder.join(RESULTS_DATABASE), &[Table::from_strings("papers", &header, &rows)])
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn persist(&self, _tenant: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, Vec<StoredPaper>>> {
+        self.papers.read().map_err(|_| GscholarError::Config("Result store lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, Vec<StoredPaper>>> {
+        self.papers.write().map_err(|_| GscholarError::Config("Result store lock poisoned".to_string()))
+    }
+}
+
+/// Job ID of a `job_<id>` run folder
+fn job_id(folder: &Path) -> Option<String> {
+    folder.file_name()?.to_str()?.strip_prefix("job_").map(String::from)
+}
+
+/// Papers of one run folder with their Stage 6 labels (none before Stage 5)
+fn load_run(folder: &Path, tenant: Option<&str>) -> Result<Vec<StoredPaper>> {
+    let unified = folder.join(UNIFIED_CSV);
+    if !unified.exists() {
+        return Ok(Vec::new());
+    }
+    let job_id = job_id(folder).unwrap_or_default();
+    let keyword = Manifest::load(folder)?.map(|m| m.keyword).unwrap_or_default();
+    let verdicts = folder.join(VERDICTS_CSV);
+    let labels: HashMap<String, String> = if verdicts.exists() {
+        read_rows(&verdicts)?
+            .iter()
+            .map(|row| (FilterResult::checkpoint_key(field(row, "id"), field(row, "title")), field(row, "label").to_lowercase()))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(&unified)
+        .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", unified, e)))?;
+    reader
+        .deserialize::<UnifiedRow>()
+        .map(|row| {
+            let paper = row.map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", unified, e)))?;
+            Ok(StoredPaper {
+                job_id: job_id.clone(),
+                keyword: keyword.clone(),
+                label: labels.get(&FilterResult::checkpoint_key(&paper.doi, &paper.title)).cloned().unwrap_or_default(),
+                paper,
+                tenant: tenant.map(String::from),
+            })
+        })
+        .collect()
+}
+
+/// Cells of a row in [`crate::unified::UNIFIED_COLUMNS`] order
+#[cfg(feature = "sqlite")]
+fn unified_cells(row: &UnifiedRow) -> Vec<String> {
+    let value = serde_json::to_value(row).unwrap_or_default();
+    crate::unified::UNIFIED_COLUMNS
+        .iter()
+        .map(|c| value.get(*c).and_then(|v| v.as_str()).unwrap_or_default().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_run(folder: &Path, rows: &[UnifiedRow], verdicts: &str) -> Result<()> {
+        std::fs::create_dir_all(folder)?;
+        let mut writer = csv::Writer::from_path(folder.join(UNIFIED_CSV)).map_err(|e| GscholarError::Parse(e.to_string()))?;
+        for row in rows {
+            writer.serialize(row).map_err(|e| GscholarError::Parse(e.to_string()))?;
+        }
+        writer.flush()?;
+        std::fs::write(folder.join(VERDICTS_CSV), verdicts)?;
+        Ok(())
+    }
+
+    fn row(title: &str, doi: &str, date: &str, if_score: &str) -> UnifiedRow {
+        UnifiedRow {
+            title: title.to_string(),
+            doi: doi.to_string(),
+            date: date.to_string(),
+            if_score: if_score.to_string(),
+            abstract_text: "Rainfall-induced slope failure".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_query_filters_sorts_and_pages() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        write_run(
+            &dir.path().join("job_20250101000000-aaaaaa"),
+            &[
+                UnifiedRow { citations: "1,234".to_string(), ..row("Landslide susceptibility mapping", "10.1/a", "2021-03-01", "5.2") },
+                row("Debris flow runout", "10.1/b", "2018", "2.1"),
+                row("Slope monitoring with InSAR", "", "2023", ""),
+            ],
+            "id,title,label,confidence,evidence,reason\n10.1/a,x,relevant,0.9,,\n10.1/b,x,irrelevant,0.8,,\n,Slope monitoring with InSAR,relevant,0.7,,\n",
+        )?;
+        write_run(
+            &dir.path().join(jobs::TENANTS_FOLDER).join("geo-lab").join("job_20250102000000-bbbbbb"),
+            &[row("Other tenant's paper", "10.1/c", "2022", "9.0")],
+            "id,title,label,confidence,evidence,reason\n",
+        )?;
+        let store = ResultStore::open(dir.path())?;
+        assert_eq!(dir.path().join(RESULTS_DATABASE).exists(), cfg!(feature = "sqlite"));
+
+        let titles = |page: &PaperPage| page.papers.iter().map(|p| p.paper.title.clone()).collect::<Vec<_>>();
+        let relevant = store.query(&PaperQuery { label: Some("relevant".to_string()), ..Default::default() }, None)?;
+        assert_eq!(relevant.total, 2);
+
+        let by_if = store.query(&PaperQuery { min_if: Some(2.0), sort: Some("-if_score".to_string()), ..Default::default() }, None)?;
+        assert_eq!(titles(&by_if), vec!["Landslide susceptibility mapping", "Debris flow runout"]);
+
+        let cited = store.query(&PaperQuery { min_citations: Some(100), sort: Some("-citations".to_string()), ..Default::default() }, None)?;
+        assert_eq!(titles(&cited), vec!["Landslide susceptibility mapping"]);
+
+        let page = store.query(
+            &PaperQuery { query: Some("SLOPE rainfall".to_string()), sort: Some("year".to_string()), page: Some(2), per_page: Some(2), ..Default::default() },
+            None,
+        )?;
+        assert_eq!((page.total, titles(&page)), (3, vec!["Slope monitoring with InSAR".to_string()]));
+
+        let tenant = store.query(&PaperQuery::default(), Some("geo-lab"))?;
+        assert_eq!(titles(&tenant), vec!["Other tenant's paper"]);
+        assert!(store.query(&PaperQuery { sort: Some("doi".to_string()), ..Default::default() }, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_run_replaces_the_job() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let store = ResultStore::open(dir.path())?;
+        let run = dir.path().join("job_20250101000000-cccccc");
+        write_run(&run, &[row("First", "10.1/a", "2020", "")], "")?;
+        assert_eq!(store.add_run(&run, None)?, 1);
+        write_run(&run, &[row("First", "10.1/a", "2020", ""), row("Second", "10.1/b", "2021", "")], "")?;
+        assert_eq!(store.add_run(&run, None)?, 2);
+        assert_eq!(store.query(&PaperQuery::default(), None)?.total, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Search strategy report (`search_strategy.md`).
+//!
+//! Systematic reviews must report where, when and how they searched. The
+//! report renders a run's [`Manifest`] as a methods-section draft: a summary
+//! paragraph with the counts, each source with its exact query URL, the
+//! search parameters, the filters applied, the services used and the
+//! records left after each stage.
+
+use crate::manifest::Manifest;
+use std::fmt::Write;
+
+/// Report file in each run folder
+pub const SEARCH_STRATEGY_FILE: &str = "search_strategy.md";
+
+/// Markdown search strategy of a run
+pub fn render(manifest: &Manifest) -> String {
+    let search = &manifest.search;
+    let prisma = &manifest.prisma;
+    let date = executed_on(&manifest.started_at);
+    let mut out = String::new();
+    let _ = writeln!(out, "# Search strategy: {}\n", manifest.keyword);
+
+    let _ = writeln!(out, "## Summary\n");
+    let mut summary = match (manifest.queries.is_empty(), search.input_records) {
+        (true, Some(records)) => format!(
+            "No database was searched: {} records were taken from a user-provided list on {}.",
+            records, date
+        ),
+        _ => {
+            let sources: Vec<&str> = manifest.queries.iter().map(|q| q.source.as_str()).collect();
+            format!(
+                "{} {} searched on {} for \"{}\", restricted to publications from {} onwards{}.",
+                join_words(&sources),
+                if sources.len() == 1 { "was" } else { "were" },
+                date,
+                manifest.keyword,
+                search.from_year,
+                match page_ranges(&search.pages) {
+                    ranges if ranges.is_empty() => String::new(),
+                    ranges => format!(" (result pages {})", ranges),
+                }
+            )
+        }
+    };
+    if prisma.identified > 0 {
+        let _ = write!(
+            summary,
+            " The search identified {} records; {} duplicates were removed and {} further records were removed by the filters below, leaving {}.",
+            prisma.identified,
+            prisma.duplicates_removed,
+            prisma.removed_before_screening() - prisma.duplicates_removed,
+            prisma.after_removal()
+        );
+    }
+    if let (Some(screened), Some(included)) = (prisma.screened, prisma.included) {
+        let _ = write!(summary, " {} records were screened and {} included.", screened, included);
+    }
+    let _ = writeln!(out, "{}\n", summary);
+
+    if !manifest.queries.is_empty() {
+        let _ = writeln!(out, "## Sources\n\n| Source | Query | Request (first page) |\n|---|---|---|");
+        for query in &manifest.queries {
+            let _ = writeln!(out, "| {} | {} | `{}` |", query.source, cell(&query.query), query.url);
+        }
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Parameters\n");
+    let finished = manifest.finished_at.as_deref().unwrap_or("not finished");
+    let _ = writeln!(out, "- Executed: {} (finished: {})", manifest.started_at, finished);
+    for resumed in &manifest.resumed_at {
+        let _ = writeln!(out, "- Resumed: {}", resumed);
+    }
+    if !manifest.queries.is_empty() {
+        let _ = writeln!(out, "- Publication years: {} onwards", search.from_year);
+        let _ = writeln!(out, "- Result pages: {}", page_ranges(&search.pages));
+    }
+    let stages: Vec<String> = search.stages.iter().map(|s| s.to_string()).collect();
+    let _ = writeln!(out, "- Stages run: {}", stages.join(", "));
+    if let Some(since) = &search.since {
+        let _ = writeln!(out, "- Update of: `{}`", since.display());
+    }
+    let _ = writeln!(out, "- Tool: {} {}\n", manifest.tool, manifest.version);
+
+    let _ = writeln!(out, "## Filters\n");
+    if search.filters.is_empty() {
+        let _ = writeln!(out, "- None");
+    }
+    for filter in &search.filters {
+        let _ = writeln!(out, "- {}", filter);
+    }
+    out.push('\n');
+
+    if !manifest.services.is_empty() {
+        let _ = writeln!(out, "## Services\n\n| Stage | Service | Endpoint | Model |\n|---|---|---|---|");
+        for service in &manifest.services {
+            let _ = writeln!(
+                out,
+                "| {} | {} | `{}` | {} |",
+                service.stage,
+                service.name,
+                service.url,
+                service.model.as_deref().unwrap_or("-")
+            );
+        }
+        out.push('\n');
+    }
+
+    if !manifest.stages.is_empty() {
+        let _ = writeln!(out, "## Records after each stage\n\n| Stage | Records |\n|---|---:|");
+        for stage in &manifest.stages {
+            let _ = writeln!(out, "| {} | {} |", stage.stage, stage.records);
+        }
+    }
+    out
+}
+
+/// Date part of an RFC 3339 timestamp
+fn executed_on(timestamp: &str) -> &str {
+    timestamp.get(..10).unwrap_or(timestamp)
+}
+
+/// "A", "A and B", "A, B and C"
+fn join_words(words: &[&str]) -> String {
+    match words {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// Consecutive pages as ranges: [1, 2, 3, 7] -> "1-3, 7"
+fn page_ranges(pages: &[i32]) -> String {
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    for &page in pages {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Text safe inside a Markdown table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{SearchParameters, SourceQuery};
+    use crate::prisma::PrismaCounts;
+
+    #[test]
+    fn test_render() {
+        let mut manifest = Manifest::new(
+            "landslide susceptibility",
+            SearchParameters {
+                from_year: 2020,
+                pages: vec![1, 2, 3, 7],
+                stages: vec![1, 2, 3, 5],
+                filters: vec!["At least 10 citations".to_string()],
+                ..Default::default()
+            },
+        );
+        manifest.started_at = "2025-03-01T10:00:00+08:00".to_string();
+        for source in ["OpenAlex", "Google Scholar"] {
+            manifest.queries.push(SourceQuery {
+                source: source.to_string(),
+                query: "landslide susceptibility".to_string(),
+                url: format!("https://example.org/{}", source),
+            });
+        }
+        manifest.prisma = PrismaCounts { identified: 120, duplicates_removed: 20, citation_excluded: 30, ..Default::default() };
+
+        let report = render(&manifest);
+        assert!(report.contains(
+            "OpenAlex and Google Scholar were searched on 2025-03-01 for \"landslide susceptibility\", restricted to publications from 2020 onwards (result pages 1-3, 7)."
+        ));
+        assert!(report.contains("identified 120 records; 20 duplicates were removed and 30 further records"));
+        assert!(report.contains("| Google Scholar | landslide susceptibility | `https://example.org/Google Scholar` |"));
+        assert!(report.contains("- At least 10 citations"));
+        assert!(report.contains("finished: not finished"));
+    }
+}
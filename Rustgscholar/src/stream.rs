@@ -0,0 +1,171 @@
+//! Bounded page channels for streaming runs.
+//!
+//! The search clients send every page down a [`PageSender`] as soon as it is
+//! parsed, so the pipeline can run Crossref and EasyScholar lookups on early
+//! pages while later ones are still being fetched. The channels are bounded:
+//! a slow stage holds back the fetcher instead of buffering the whole search.
+//!
+//! Concurrency never decides the order of output rows: pages are put back
+//! in page order ([`collect`], [`PageOrder`]) and per-paper work runs
+//! through [`in_order`], which returns results in input order. Two runs
+//! over the same input thus write identical tables, and incremental runs
+//! diff cleanly.
+
+use crate::error::Result;
+use futures::stream::{self, StreamExt};
+use futures::future::{join, ready};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use tokio::sync::mpsc;
+
+/// Pages a stage may queue before the one feeding it waits
+pub const PAGE_BUFFER: usize = 2;
+
+/// One fetched page of results
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// Page number as requested (1-indexed)
+    pub number: i32,
+    pub items: Vec<T>,
+    /// Why the page has no items, when fetching or parsing it failed
+    pub error: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn new(number: i32, items: Vec<T>) -> Self {
+        Self { number, items, error: None }
+    }
+
+    /// A page that could not be fetched or parsed (the search goes on)
+    pub fn failed(number: i32, error: impl ToString) -> Self {
+        Self { number, items: Vec::new(), error: Some(error.to_string()) }
+    }
+}
+
+pub type PageSender<T> = mpsc::Sender<Page<T>>;
+pub type PageReceiver<T> = mpsc::Receiver<Page<T>>;
+
+/// Bounded page channel holding [`PAGE_BUFFER`] pages
+pub fn channel<T>() -> (PageSender<T>, PageReceiver<T>) {
+    mpsc::channel(PAGE_BUFFER)
+}
+
+/// Run a page fetch to completion and gather its pages in page order.
+///
+/// This is how the non-streaming `query` functions are built on their
+/// streaming counterparts; an error from `fetch` is returned as before.
+pub async fn collect<T>(fetch: impl Future<Output = Result<()>>, mut rx: PageReceiver<T>) -> Result<Vec<T>> {
+    let gather = async {
+        let mut pages = Vec::new();
+        while let Some(page) = rx.recv().await {
+            pages.push(page);
+        }
+        pages
+    };
+    let (fetched, mut pages) = tokio::join!(fetch, gather);
+    fetched?;
+    pages.sort_by_key(|page| page.number);
+    Ok(pages.into_iter().flat_map(|page| page.items).collect())
+}
+
+/// Run `futures` with at most `limit` in flight, returning their outputs
+/// in input order however they complete
+pub async fn in_order<F: Future>(futures: impl IntoIterator<Item = F>, limit: usize) -> Vec<F::Output> {
+    // Collected first: closure types held across the await trip up `Send` inference of callers
+    let tagged: Vec<_> = futures.into_iter().enumerate().map(|(index, future)| join(ready(index), future)).collect();
+    let mut outputs: Vec<(usize, F::Output)> = stream::iter(tagged).buffer_unordered(limit.max(1)).collect().await;
+    outputs.sort_by_key(|(index, _)| *index);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+/// Puts pages back in the order they were requested
+///
+/// Sources fetch several pages at once and send them as they complete. A
+/// page that arrives before an earlier one is held back until the gap is
+/// filled (every requested page arrives, failed ones included), so only
+/// the pages in flight are ever buffered.
+#[derive(Debug)]
+pub struct PageOrder<T> {
+    expected: VecDeque<i32>,
+    pending: BTreeMap<i32, Page<T>>,
+}
+
+impl<T> PageOrder<T> {
+    /// Expect the pages `numbers`, in this order
+    pub fn new(numbers: impl IntoIterator<Item = i32>) -> Self {
+        Self { expected: numbers.into_iter().collect(), pending: BTreeMap::new() }
+    }
+
+    /// Take an arrived page, returning the pages now due, in order
+    /// (a page that was not expected is passed straight through)
+    pub fn push(&mut self, page: Page<T>) -> Vec<Page<T>> {
+        if !self.expected.contains(&page.number) {
+            return vec![page];
+        }
+        self.pending.insert(page.number, page);
+        let mut ready = Vec::new();
+        while let Some(page) = self.expected.front().and_then(|number| self.pending.remove(number)) {
+            self.expected.pop_front();
+            ready.push(page);
+        }
+        ready
+    }
+
+    /// Pages still held back when the source stopped early, in page order
+    pub fn finish(self) -> Vec<Page<T>> {
+        self.pending.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::GscholarError;
+
+    #[tokio::test]
+    async fn test_collect_in_page_order() -> Result<()> {
+        let (tx, rx) = channel();
+        let fetch = async move {
+            // More pages than the buffer holds, sent out of order
+            for number in [3, 1, 4, 2] {
+                if tx.send(Page::new(number, vec![number * 10])).await.is_err() {
+                    break;
+                }
+            }
+            // Failed pages carry no items
+            let _ = tx.send(Page::failed(5, "timeout")).await;
+            Ok(())
+        };
+        assert_eq!(collect(fetch, rx).await?, vec![10, 20, 30, 40]);
+
+        let (tx, rx) = channel::<i32>();
+        let failing = async move {
+            let _ = tx.send(Page::new(1, vec![1])).await;
+            Err(GscholarError::Captcha)
+        };
+        assert!(collect(failing, rx).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_in_order() {
+        // Earlier futures finish last
+        let delays = [30u64, 20, 10, 0];
+        let futures = delays.iter().map(|&ms| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+            ms
+        });
+        assert_eq!(in_order(futures, 4).await, delays);
+    }
+
+    #[test]
+    fn test_page_order() {
+        let mut order = PageOrder::new([1, 2, 3, 4]);
+        let numbers = |pages: Vec<Page<i32>>| pages.iter().map(|p| p.number).collect::<Vec<_>>();
+        assert_eq!(numbers(order.push(Page::new(2, vec![]))), Vec::<i32>::new());
+        assert_eq!(numbers(order.push(Page::failed(1, "timeout"))), vec![1, 2]);
+        assert_eq!(numbers(order.push(Page::new(4, vec![]))), Vec::<i32>::new());
+        assert_eq!(numbers(order.push(Page::new(9, vec![]))), vec![9]);
+        assert_eq!(numbers(order.finish()), vec![4]);
+    }
+}
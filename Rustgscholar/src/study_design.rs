@@ -0,0 +1,138 @@
+//! Study design classification for relevant papers.
+//!
+//! Labels each paper with the one study design that produced its evidence
+//! (experimental, numerical simulation, case study, ...) for structured
+//! evidence tables. The list of designs is the user's, so each domain can
+//! use its own vocabulary (e.g., "laboratory test, field monitoring,
+//! numerical simulation" in geotechnics); [`DEFAULT_DESIGNS`] covers the
+//! common ones.
+
+use crate::error::Result;
+use crate::llm_filter::{self, extract_json, paper_to_json, LlmConfig, LlmSession, TokenUsage};
+use crate::paper::Paper;
+use crate::prompts::study_design::{build_user_prompt, SYSTEM_PROMPT};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+/// Designs used when `--study-designs` is given without a list
+pub const DEFAULT_DESIGNS: &str =
+    "experimental,observational,numerical simulation,case study,survey,theoretical,review,meta-analysis";
+
+/// Study design of a single paper
+#[derive(Debug, Clone)]
+pub struct DesignResult {
+    pub id: String,
+    /// Design from the user's list, spelled as given; empty when unclear
+    pub design: String,
+    pub reason: String,
+}
+
+/// JSON schema for the classification response (the empty string means unclear)
+fn response_schema(designs: &[String]) -> serde_json::Value {
+    let mut allowed: Vec<&str> = designs.iter().map(String::as_str).collect();
+    allowed.push("");
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "design": {"type": "string", "enum": allowed},
+            "reason": {"type": "string"}
+        },
+        "required": ["design", "reason"],
+        "additionalProperties": false
+    })
+}
+
+/// Classify the study design of papers using concurrent LLM requests.
+pub async fn classify_papers(
+    config: &LlmConfig,
+    designs: &[String],
+    papers: &[&Paper],
+) -> Result<(Vec<DesignResult>, TokenUsage)> {
+    if papers.is_empty() || designs.is_empty() {
+        return Ok((Vec::new(), TokenUsage::default()));
+    }
+
+    info!(
+        count = papers.len(),
+        designs = designs.len(),
+        model = %config.model,
+        "Starting LLM study design classification"
+    );
+
+    let session = LlmSession::new(config)?;
+    let schema = response_schema(designs);
+
+    let replies = llm_filter::complete_each(
+        &session,
+        SYSTEM_PROMPT,
+        "design_result",
+        &schema,
+        papers.iter().copied().enumerate(),
+        |paper| Ok(build_user_prompt(designs, &paper_to_json(paper)?)),
+        |content, paper| Ok(parse_design_response(content, paper, designs)),
+    )
+    .await;
+    let results: Vec<DesignResult> = replies
+        .into_iter()
+        .zip(papers)
+        .enumerate()
+        .map(|(idx, (reply, paper))| {
+            reply.unwrap_or_else(|e| {
+                warn!(idx = idx, error = %e, "Failed to classify study design");
+                DesignResult { id: paper.doi.clone(), design: String::new(), reason: format!("API error: {}", e) }
+            })
+        })
+        .collect();
+
+    let usage = session.usage();
+    info!(
+        classified = results.iter().filter(|r| !r.design.is_empty()).count(),
+        total = results.len(),
+        "LLM study design classification complete"
+    );
+
+    Ok((results, usage))
+}
+
+/// Parse classification JSON, keeping only a known design (case-insensitive)
+fn parse_design_response(content: &str, paper: &Paper, designs: &[String]) -> DesignResult {
+    #[derive(Deserialize)]
+    struct DesignOutput {
+        #[serde(default)]
+        design: String,
+        #[serde(default)]
+        reason: String,
+    }
+
+    let (design, reason) = match serde_json::from_str::<DesignOutput>(&extract_json(content)) {
+        Ok(output) => {
+            // Keep the user's spelling of the design
+            let design = designs
+                .iter()
+                .find(|d| output.design.trim().eq_ignore_ascii_case(d))
+                .cloned()
+                .unwrap_or_default();
+            (design, output.reason)
+        }
+        Err(e) => (String::new(), format!("Parse error: {}", e)),
+    };
+
+    DesignResult { id: paper.doi.clone(), design, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_design_response() {
+        let designs = vec!["Case study".to_string(), "numerical simulation".to_string()];
+        let paper = Paper { doi: "10.1/a".to_string(), ..Default::default() };
+        let result = parse_design_response(r#"{"design": "case study", "reason": "one slope"}"#, &paper, &designs);
+        assert_eq!(result.design, "Case study");
+        assert_eq!(result.id, "10.1/a");
+        let result = parse_design_response(r#"{"design": "field trial", "reason": ""}"#, &paper, &designs);
+        assert_eq!(result.design, "");
+        assert!(parse_design_response("oops", &paper, &designs).reason.starts_with("Parse error"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Multi-label topic tagging for relevant papers.
+//!
+//! Assigns each paper to one or more user-defined categories (e.g.,
+//! "method", "case study", "review") with concurrent LLM requests, so large
+//! result sets can be organized without manual sorting.
+
+use crate::error::Result;
+use crate::llm_filter::{self, extract_json, paper_to_json, LlmConfig, LlmSession, TokenUsage};
+use crate::prompts::topic_tagging::{build_user_prompt, SYSTEM_PROMPT};
+use crate::paper::Paper;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+/// Tagging result for a single paper
+#[derive(Debug, Clone)]
+pub struct TagResult {
+    pub id: String,
+    pub title: String,
+    /// Assigned categories, spelled as in the user's list
+    pub tags: Vec<String>,
+    pub reason: String,
+}
+
+/// Parse a comma-separated category list, trimming and dropping duplicates
+pub fn parse_categories(input: &str) -> Vec<String> {
+    let mut categories: Vec<String> = Vec::new();
+    for c in input.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        if !categories.iter().any(|existing| existing.eq_ignore_ascii_case(c)) {
+            categories.push(c.to_string());
+        }
+    }
+    categories
+}
+
+/// File-name-safe form of a category (e.g., "case study" -> "case_study")
+pub fn file_slug(category: &str) -> String {
+    let slug: String = category
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    slug.split('_').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("_")
+}
+
+/// JSON schema for the tagging response
+fn response_schema(categories: &[String]) -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "tags": {"type": "array", "items": {"type": "string", "enum": categories}},
+            "reason": {"type": "string"}
+        },
+        "required": ["tags", "reason"],
+        "additionalProperties": false
+    })
+}
+
+/// Tag papers with categories using concurrent LLM requests.
+pub async fn tag_papers(
+    config: &LlmConfig,
+    categories: &[String],
+    papers: &[&Paper],
+) -> Result<(Vec<TagResult>, TokenUsage)> {
+    if papers.is_empty() || categories.is_empty() {
+        return Ok((Vec::new(), TokenUsage::default()));
+    }
+
+    info!(
+        count = papers.len(),
+        categories = categories.len(),
+        model = %config.model,
+        "Starting LLM topic tagging"
+    );
+
+    let session = LlmSession::new(config)?;
+    let schema = response_schema(categories);
+
+    let replies = llm_filter::complete_each(
+        &session,
+        SYSTEM_PROMPT,
+        "tag_result",
+        &schema,
+        papers.iter().copied().enumerate(),
+        |paper| Ok(build_user_prompt(categories, &paper_to_json(paper)?)),
+        |content, paper| Ok(parse_tag_response(content, paper, categories)),
+    )
+    .await;
+    let results: Vec<TagResult> = replies
+        .into_iter()
+        .zip(papers)
+        .enumerate()
+        .map(|(idx, (reply, paper))| {
+            reply.unwrap_or_else(|e| {
+                warn!(idx = idx, error = %e, "Failed to tag paper");
+                TagResult {
+                    id: paper.doi.clone(),
+                    title: paper.title.clone(),
+                    tags: Vec::new(),
+                    reason: format!("API error: {}", e),
+                }
+            })
+        })
+        .collect();
+
+    let usage = session.usage();
+    info!(
+        tagged = results.iter().filter(|r| !r.tags.is_empty()).count(),
+        total = results.len(),
+        "LLM topic tagging complete"
+    );
+
+    Ok((results, usage))
+}
+
+/// Parse tagging JSON, keeping only known categories (case-insensitive)
+fn parse_tag_response(content: &str, paper: &Paper, categories: &[String]) -> TagResult {
+    #[derive(Deserialize)]
+    struct TagOutput {
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        reason: String,
+    }
+
+    let (tags, reason) = match serde_json::from_str::<TagOutput>(&extract_json(content)) {
+        Ok(output) => {
+            // Keep the user's spelling and order of categories
+            let tags = categories
+                .iter()
+                .filter(|c| output.tags.iter().any(|t| t.trim().eq_ignore_ascii_case(c)))
+                .cloned()
+                .collect();
+            (tags, output.reason)
+        }
+        Err(e) => (Vec::new(), format!("Parse error: {}", e)),
+    };
+
+    TagResult {
+        id: paper.doi.clone(),
+        title: paper.title.clone(),
+        tags,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_categories() {
+        assert_eq!(
+            parse_categories(" method, case study,,Method ,review"),
+            vec!["method", "case study", "review"]
+        );
+        assert_eq!(file_slug("Case Study / Field"), "case_study_field");
+    }
+
+    #[test]
+    fn test_parse_tag_response() {
+        let paper = Paper {
+            title: "Test".to_string(),
+            doi: "10.1/x".to_string(),
+            ..Default::default()
+        };
+        let categories = parse_categories("method,case study,review");
+        let result = parse_tag_response(
+            r#"{"tags": ["Case Study", "unknown", "method"], "reason": "field data"}"#,
+            &paper,
+            &categories,
+        );
+        assert_eq!(result.tags, vec!["method", "case study"]);
+        assert!(parse_tag_response("oops", &paper, &categories).tags.is_empty());
+    }
+}
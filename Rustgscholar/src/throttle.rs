@@ -0,0 +1,218 @@
+//! Process-wide request limits per upstream service for `serve`.
+//!
+//! Requests are paced per host by [`crate::ratelimit`], whose buckets are
+//! shared by the whole process and so by every job of `serve`. What they do
+//! not bound is how many requests are in flight at once: several jobs that
+//! each wait their turn can still pile up slow requests on one upstream.
+//! Once [`configure`] has installed the limits, a request to an upstream
+//! first takes one of that upstream's slots: at most `concurrency` requests
+//! are in flight (by default the burst of the upstream's [`Rate`]) and, when
+//! `--upstream-limit` sets an `interval`, two requests start at least that
+//! far apart, across all jobs. Until then (the CLI) [`acquire`] returns at
+//! once.
+
+use crate::error::{GscholarError, Result};
+use crate::ratelimit::Rate;
+use crate::semanticscholar;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::info;
+
+/// An upstream service with a shared limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Upstream {
+    Scholar,
+    Crossref,
+    OpenAlex,
+    EasyScholar,
+    SemanticScholar,
+    Llm,
+}
+
+impl Upstream {
+    pub const ALL: [Upstream; 6] = [
+        Self::Scholar,
+        Self::Crossref,
+        Self::OpenAlex,
+        Self::EasyScholar,
+        Self::SemanticScholar,
+        Self::Llm,
+    ];
+
+    /// Name used by `--upstream-limit`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Scholar => "scholar",
+            Self::Crossref => "crossref",
+            Self::OpenAlex => "openalex",
+            Self::EasyScholar => "easyscholar",
+            Self::SemanticScholar => "s2",
+            Self::Llm => "llm",
+        }
+    }
+
+    /// Limit shared by all jobs unless overridden: as many requests in
+    /// flight as the upstream's [`Rate`] lets start at once, and no extra
+    /// spacing, since its process-wide [`crate::ratelimit`] bucket already
+    /// paces every job at that rate
+    pub fn default_limit(self) -> UpstreamLimit {
+        let rate = match self {
+            Self::Scholar => Rate::SCHOLAR,
+            Self::Crossref => Rate::CROSSREF,
+            Self::OpenAlex => Rate::OPENALEX,
+            Self::EasyScholar => Rate::EASYSCHOLAR,
+            Self::SemanticScholar if semanticscholar::env_key().is_some() => semanticscholar::KEYED_RATE,
+            Self::SemanticScholar => Rate::SEMANTIC_SCHOLAR,
+            Self::Llm => Rate::UNLIMITED.with_burst(crate::llm_filter::DEFAULT_CONCURRENT_REQUESTS as u32),
+        };
+        UpstreamLimit { concurrency: rate.burst as usize, interval: Duration::ZERO }
+    }
+}
+
+impl FromStr for Upstream {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "scholar" | "gscholar" => Ok(Self::Scholar),
+            "semanticscholar" => Ok(Self::SemanticScholar),
+            _ => Self::ALL.into_iter().find(|u| u.name() == s).ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|u| u.name()).collect();
+                GscholarError::Config(format!("Unknown upstream '{}' (expected one of {})", s, names.join(", ")))
+            }),
+        }
+    }
+}
+
+/// Requests in flight and spacing of one upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpstreamLimit {
+    pub concurrency: usize,
+    /// Minimum time between the starts of two requests
+    pub interval: Duration,
+}
+
+/// Parse an override `NAME=CONCURRENCY[:INTERVAL_MS]`, e.g. `s2=1:1500`
+pub fn parse_override(value: &str) -> Result<(Upstream, UpstreamLimit)> {
+    let invalid = || GscholarError::Config(format!("Invalid upstream limit '{}' (expected NAME=CONCURRENCY[:INTERVAL_MS])", value));
+    let (name, limit) = value.split_once('=').ok_or_else(invalid)?;
+    let upstream: Upstream = name.trim().parse()?;
+    let (concurrency, interval) = match limit.split_once(':') {
+        Some((concurrency, interval)) => (concurrency, Some(interval)),
+        None => (limit, None),
+    };
+    let concurrency: usize = concurrency.trim().parse().map_err(|_| invalid())?;
+    if concurrency == 0 {
+        return Err(invalid());
+    }
+    let interval = match interval {
+        Some(ms) => Duration::from_millis(ms.trim().parse().map_err(|_| invalid())?),
+        None => upstream.default_limit().interval,
+    };
+    Ok((upstream, UpstreamLimit { concurrency, interval }))
+}
+
+/// Slots and start spacing of one upstream
+struct Limiter {
+    slots: Semaphore,
+    interval: Duration,
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl Limiter {
+    fn new(limit: UpstreamLimit) -> Self {
+        Self { slots: Semaphore::new(limit.concurrency.max(1)), interval: limit.interval, next_start: Mutex::new(None) }
+    }
+
+    /// Wait for a free slot and the start time after the previous request's
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = self.slots.acquire().await.ok();
+        let wait = match self.next_start.lock() {
+            Ok(mut next_start) => {
+                let now = Instant::now();
+                let start = next_start.map_or(now, |next| next.max(now));
+                *next_start = Some(start + self.interval);
+                start - now
+            }
+            Err(_) => Duration::ZERO,
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        permit
+    }
+}
+
+static LIMITERS: OnceLock<HashMap<Upstream, Limiter>> = OnceLock::new();
+
+/// Install the shared limits: the defaults, replaced by `overrides`
+///
+/// Only the first call takes effect; returns whether this one did.
+pub fn configure(overrides: &[(Upstream, UpstreamLimit)]) -> bool {
+    let mut installed = false;
+    LIMITERS.get_or_init(|| {
+        installed = true;
+        Upstream::ALL
+            .into_iter()
+            .map(|upstream| {
+                let limit = overrides
+                    .iter()
+                    .rev()
+                    .find(|(u, _)| *u == upstream)
+                    .map_or_else(|| upstream.default_limit(), |(_, limit)| *limit);
+                info!(
+                    upstream = upstream.name(),
+                    concurrency = limit.concurrency,
+                    interval_ms = limit.interval.as_millis() as u64,
+                    "Shared upstream limit"
+                );
+                (upstream, Limiter::new(limit))
+            })
+            .collect()
+    });
+    installed
+}
+
+/// Take a slot for one request to `upstream`, released when dropped
+///
+/// `None` when no limits are installed.
+pub async fn acquire(upstream: Upstream) -> Option<SemaphorePermit<'static>> {
+    LIMITERS.get()?.get(&upstream)?.acquire().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override() -> Result<()> {
+        let (upstream, limit) = parse_override("s2=2:1500")?;
+        assert_eq!(upstream, Upstream::SemanticScholar);
+        assert_eq!(limit, UpstreamLimit { concurrency: 2, interval: Duration::from_millis(1500) });
+        assert_eq!(parse_override("scholar=1")?.1.interval, Duration::ZERO);
+        // One table of rates: the serve limit follows the host's bucket
+        assert_eq!(Upstream::Crossref.default_limit().concurrency, Rate::for_host("api.crossref.org").burst as usize);
+        assert_eq!(Upstream::OpenAlex.default_limit().concurrency, 10);
+        assert!(parse_override("scopus=1").is_err());
+        assert!(parse_override("crossref=0").is_err());
+        assert!(parse_override("crossref").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_limiter_spaces_and_bounds_requests() {
+        let limiter = Limiter::new(UpstreamLimit { concurrency: 1, interval: Duration::from_millis(40) });
+        let started = Instant::now();
+        let first = limiter.acquire().await;
+        assert!(first.is_some());
+        // The second request waits for the slot, not only for the interval
+        let second = tokio::time::timeout(Duration::from_millis(80), limiter.acquire()).await;
+        assert!(second.is_err());
+        drop(first);
+        let _third = limiter.acquire().await;
+        assert!(started.elapsed() >= Duration::from_millis(80));
+    }
+}
@@ -0,0 +1,336 @@
+//! Keyword co-occurrence and topic clusters.
+//!
+//! Builds a term co-occurrence network from a run folder: the terms of a
+//! paper are its OpenAlex keywords and primary topic (joined by DOI from
+//! `1_openalex.csv`) plus the content words of its title. Terms are grouped
+//! into clusters by weighted label propagation on the network, and each paper
+//! joins the cluster most of its terms belong to. The network is written as
+//! VOSviewer map and network files, the matrix and assignments as CSV.
+
+use crate::doi;
+use crate::error::{GscholarError, Result};
+use crate::network::{field, read_rows};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Title words too common to say anything about a paper's topic
+const STOPWORDS: &[&str] = &[
+    "about", "across", "after", "against", "along", "among", "analysis", "analyses", "approach", "based",
+    "between", "case", "could", "different", "during", "effect", "effects", "from", "have", "into", "method",
+    "methods", "more", "model", "new", "novel", "over", "results", "review", "study", "studies", "than",
+    "that", "their", "these", "this", "those", "through", "towards", "under", "using", "various", "very",
+    "which", "while", "with", "within", "without",
+];
+
+/// Label propagation rounds before giving up on convergence
+const MAX_ROUNDS: usize = 50;
+
+/// Which terms make up the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicOptions {
+    /// Terms found in fewer papers are dropped
+    pub min_occurrences: u32,
+    /// Keep at most this many terms (the most frequent)
+    pub max_terms: usize,
+    /// Also use title words, not only OpenAlex keywords and topics
+    pub title_terms: bool,
+}
+
+impl Default for TopicOptions {
+    fn default() -> Self {
+        Self { min_occurrences: 2, max_terms: 200, title_terms: true }
+    }
+}
+
+/// A term of the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub label: String,
+    /// Papers the term occurs in
+    pub occurrences: u32,
+    /// Cluster number (1 = largest)
+    pub cluster: usize,
+}
+
+/// A paper and the cluster it was assigned to
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperCluster {
+    pub doi: String,
+    pub title: String,
+    /// `None` when none of the paper's terms made it into the network
+    pub cluster: Option<usize>,
+}
+
+/// Term co-occurrence network with clusters
+#[derive(Debug, Clone, Default)]
+pub struct TopicMap {
+    pub terms: Vec<Term>,
+    /// (term, term, papers in which both occur), first index smaller
+    pub links: Vec<(usize, usize, u32)>,
+    pub papers: Vec<PaperCluster>,
+}
+
+impl TopicMap {
+    /// Build the map from a run folder: the papers of `7_relevant.csv` when
+    /// present, else `5_unified.csv`
+    pub fn from_run_folder(folder: &Path, options: &TopicOptions) -> Result<Self> {
+        let papers_path = ["7_relevant.csv", "5_unified.csv"]
+            .iter()
+            .map(|name| folder.join(name))
+            .find(|path| path.exists())
+            .ok_or_else(|| {
+                GscholarError::Validation(format!("{:?} has no 7_relevant.csv or 5_unified.csv", folder))
+            })?;
+        let works_path = folder.join("1_openalex.csv");
+        let mut keywords: HashMap<String, Vec<String>> = HashMap::new();
+        if works_path.exists() {
+            for row in read_rows(&works_path)? {
+                let terms = field(&row, "keywords")
+                    .split(',')
+                    .chain(std::iter::once(field(&row, "primary_topic")))
+                    .map(|term| term.trim().to_lowercase())
+                    .filter(|term| !term.is_empty())
+                    .collect();
+                keywords.insert(doi::normalize(field(&row, "doi")), terms);
+            }
+        }
+
+        let papers: Vec<(String, String, Vec<String>)> = read_rows(&papers_path)?
+            .iter()
+            .map(|row| {
+                let doi = field(row, "doi").to_string();
+                let title = field(row, "title").to_string();
+                let mut terms = keywords.get(&doi::normalize(&doi)).cloned().unwrap_or_default();
+                if options.title_terms {
+                    terms.extend(title_terms(&title));
+                }
+                (doi, title, terms)
+            })
+            .collect();
+        Ok(Self::build(&papers, options))
+    }
+
+    /// Build the map from (DOI, title, terms) of each paper
+    pub fn build(papers: &[(String, String, Vec<String>)], options: &TopicOptions) -> Self {
+        // Distinct terms per paper
+        let paper_terms: Vec<Vec<&str>> = papers
+            .iter()
+            .map(|(_, _, terms)| {
+                let mut seen = HashSet::new();
+                terms.iter().map(String::as_str).filter(|t| seen.insert(*t)).collect()
+            })
+            .collect();
+
+        let mut occurrences: HashMap<&str, u32> = HashMap::new();
+        for term in paper_terms.iter().flatten() {
+            *occurrences.entry(term).or_default() += 1;
+        }
+        let mut kept: Vec<(&str, u32)> =
+            occurrences.into_iter().filter(|&(_, n)| n >= options.min_occurrences).collect();
+        kept.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        kept.truncate(options.max_terms);
+        let index: HashMap<&str, usize> = kept.iter().enumerate().map(|(i, (t, _))| (*t, i)).collect();
+
+        let mut weights: BTreeMap<(usize, usize), u32> = BTreeMap::new();
+        let paper_ids: Vec<Vec<usize>> = paper_terms
+            .iter()
+            .map(|terms| {
+                let mut ids: Vec<usize> = terms.iter().filter_map(|t| index.get(t).copied()).collect();
+                ids.sort_unstable();
+                ids
+            })
+            .collect();
+        for ids in &paper_ids {
+            for (i, &a) in ids.iter().enumerate() {
+                for &b in &ids[i + 1..] {
+                    *weights.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+        let links: Vec<(usize, usize, u32)> = weights.into_iter().map(|((a, b), w)| (a, b, w)).collect();
+        let clusters = cluster_terms(kept.len(), &links);
+
+        let terms = kept
+            .iter()
+            .zip(&clusters)
+            .map(|(&(label, occurrences), &cluster)| Term { label: label.to_string(), occurrences, cluster })
+            .collect::<Vec<_>>();
+        let papers = papers
+            .iter()
+            .zip(&paper_ids)
+            .map(|((doi, title, _), ids)| {
+                let mut votes: BTreeMap<usize, usize> = BTreeMap::new();
+                for &id in ids {
+                    *votes.entry(clusters[id]).or_default() += 1;
+                }
+                // Most terms wins; ties go to the larger (lower-numbered) cluster
+                let cluster = votes.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0))).map(|(c, _)| c);
+                PaperCluster { doi: doi.clone(), title: title.clone(), cluster }
+            })
+            .collect();
+
+        info!(terms = terms.len(), links = links.len(), "Built term co-occurrence network");
+        Self { terms, links, papers }
+    }
+
+    /// Number of clusters
+    pub fn cluster_count(&self) -> usize {
+        self.terms.iter().map(|t| t.cluster).max().unwrap_or(0)
+    }
+
+    /// The `n` most frequent terms of a cluster
+    pub fn top_terms(&self, cluster: usize, n: usize) -> Vec<&str> {
+        // Terms are sorted by occurrences already
+        self.terms.iter().filter(|t| t.cluster == cluster).take(n).map(|t| t.label.as_str()).collect()
+    }
+
+    /// Write `topics_vos_map.txt`, `topics_vos_network.txt`,
+    /// `topics_cooccurrence.csv` and `topics_clusters.csv` into `folder`
+    pub fn save(&self, folder: &Path) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        let mut write = |name: &str, content: String| -> Result<()> {
+            let path = folder.join(name);
+            std::fs::write(&path, content)?;
+            written.push(path);
+            Ok(())
+        };
+
+        // VOSviewer map and network files (ids are 1-based)
+        let mut map = String::from("id\tlabel\tcluster\tweight<Occurrences>\n");
+        for (i, term) in self.terms.iter().enumerate() {
+            map.push_str(&format!("{}\t{}\t{}\t{}\n", i + 1, term.label.replace('\t', " "), term.cluster, term.occurrences));
+        }
+        write("topics_vos_map.txt", map)?;
+        let network: String = self.links.iter().map(|(a, b, w)| format!("{}\t{}\t{}\n", a + 1, b + 1, w)).collect();
+        write("topics_vos_network.txt", network)?;
+
+        let mut matrix = vec![vec![0u32; self.terms.len()]; self.terms.len()];
+        for &(a, b, w) in &self.links {
+            matrix[a][b] = w;
+            matrix[b][a] = w;
+        }
+        for (i, term) in self.terms.iter().enumerate() {
+            matrix[i][i] = term.occurrences;
+        }
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        let header: Vec<&str> = std::iter::once("term").chain(self.terms.iter().map(|t| t.label.as_str())).collect();
+        let csv_err = |e: csv::Error| GscholarError::Parse(format!("Failed to write topic tables: {}", e));
+        wtr.write_record(&header).map_err(csv_err)?;
+        for (term, row) in self.terms.iter().zip(&matrix) {
+            let cells: Vec<String> = std::iter::once(term.label.clone()).chain(row.iter().map(u32::to_string)).collect();
+            wtr.write_record(&cells).map_err(csv_err)?;
+        }
+        write("topics_cooccurrence.csv", csv_text(wtr)?)?;
+
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record(["doi", "title", "cluster", "cluster_terms"]).map_err(csv_err)?;
+        for paper in &self.papers {
+            let (cluster, terms) = match paper.cluster {
+                Some(cluster) => (cluster.to_string(), self.top_terms(cluster, 5).join("; ")),
+                None => (String::new(), String::new()),
+            };
+            wtr.write_record([paper.doi.as_str(), paper.title.as_str(), &cluster, &terms]).map_err(csv_err)?;
+        }
+        write("topics_clusters.csv", csv_text(wtr)?)?;
+        Ok(written)
+    }
+}
+
+fn csv_text(wtr: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = wtr
+        .into_inner()
+        .map_err(|e| GscholarError::Parse(format!("Failed to write topic tables: {}", e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Content words of a title (lowercase, at least four letters, no stopwords)
+pub fn title_terms(title: &str) -> Vec<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .map(|word| word.trim_matches('-').to_lowercase())
+        .filter(|word| word.chars().count() >= 4 && !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Weighted label propagation; cluster numbers start at 1, largest first
+fn cluster_terms(count: usize, links: &[(usize, usize, u32)]) -> Vec<usize> {
+    let mut neighbours: Vec<Vec<(usize, u32)>> = vec![Vec::new(); count];
+    for &(a, b, w) in links {
+        neighbours[a].push((b, w));
+        neighbours[b].push((a, w));
+    }
+
+    let mut labels: Vec<usize> = (0..count).collect();
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for term in 0..count {
+            let mut scores: BTreeMap<usize, u32> = BTreeMap::new();
+            for &(other, w) in &neighbours[term] {
+                *scores.entry(labels[other]).or_default() += w;
+            }
+            // Highest weight wins; ties go to the lowest label, so runs are reproducible
+            let Some(best) = scores.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0))).map(|(l, _)| l) else {
+                continue;
+            };
+            if best != labels[term] {
+                labels[term] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Renumber by cluster size (then first term), starting at 1
+    let mut sizes: HashMap<usize, (usize, usize)> = HashMap::new();
+    for (term, &label) in labels.iter().enumerate() {
+        let entry = sizes.entry(label).or_insert((0, term));
+        entry.0 += 1;
+    }
+    let mut order: Vec<(usize, (usize, usize))> = sizes.into_iter().collect();
+    order.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    let number: HashMap<usize, usize> = order.iter().enumerate().map(|(i, (label, _))| (*label, i + 1)).collect();
+    labels.iter().map(|label| number[label]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(doi: &str, terms: &[&str]) -> (String, String, Vec<String>) {
+        (doi.to_string(), format!("Paper {}", doi), terms.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn test_build_clusters() {
+        let papers = vec![
+            paper("10.1/a", &["landslide", "rainfall", "susceptibility"]),
+            paper("10.1/b", &["landslide", "rainfall"]),
+            paper("10.1/c", &["landslide", "susceptibility", "rainfall"]),
+            paper("10.1/d", &["groundwater", "aquifer"]),
+            paper("10.1/e", &["groundwater", "aquifer", "rare"]),
+            paper("10.1/f", &["rare"]),
+            paper("10.1/g", &["unique"]),
+        ];
+        let map = TopicMap::build(&papers, &TopicOptions::default());
+        let labels: Vec<&str> = map.terms.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, vec!["landslide", "rainfall", "aquifer", "groundwater", "rare", "susceptibility"]);
+        assert_eq!(map.cluster_count(), 2);
+        assert_eq!(map.top_terms(1, 2), vec!["landslide", "rainfall"]);
+
+        let clusters: Vec<Option<usize>> = map.papers.iter().map(|p| p.cluster).collect();
+        assert_eq!(clusters, vec![Some(1), Some(1), Some(1), Some(2), Some(2), Some(2), None]);
+        assert!(map.links.contains(&(0, 1, 3)));
+    }
+
+    #[test]
+    fn test_title_terms() {
+        assert_eq!(
+            title_terms("A review of rainfall-induced landslides: case study in 2020 using InSAR"),
+            vec!["rainfall-induced", "landslides", "insar"]
+        );
+    }
+}
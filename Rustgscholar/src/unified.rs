@@ -1,116 +1,264 @@
 //! Unified CSV Generation Module
 //!
 //! Creates the final unified dataset by joining EasyScholar results with Semantic Scholar data.
-//! Handles abstract priority (Semantic Scholar > OpenAlex) and date normalization.
+//! Handles abstract priority (Semantic Scholar > OpenAlex) and the flat row
+//! layout of `5_unified.csv`.
 
+use crate::altmetric::AltmetricMetrics;
+use crate::doi;
+use crate::paper::{self, Paper};
+use crate::rankings::RankingMetrics;
 use crate::semanticscholar::SemanticScholarResult;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// Unified result combining all pipeline stages
-#[derive(Debug, Serialize)]
-pub struct UnifiedResult {
+/// Row of `5_unified.csv` (and the other unified-layout tables)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnifiedRow {
     pub title: String,
     pub author: String,
+    /// Institutions of all authors, "; "-separated (OpenAlex)
+    #[serde(default)]
+    pub institutions: String,
+    /// ISO country codes of the authors, "; "-separated (e.g., "CN; IT")
+    #[serde(default)]
+    pub countries: String,
     pub date: String,
     pub doi: String,
     pub article_url: String,
     pub pdf_url: String,
+    /// Downloaded PDF, relative to the run folder
+    #[serde(default)]
+    pub pdf_path: String,
     pub abstract_text: String,
     pub tldr: String,
     pub journal: String,
+    /// "journal", "conference", "other", or empty when unknown
+    #[serde(default)]
+    pub venue_type: String,
+    /// "article", "review", "preprint", "book-chapter", ... or empty when unknown
+    #[serde(default)]
+    pub work_type: String,
+    /// "true" for review articles, "false" otherwise, empty when not checked
+    #[serde(default)]
+    pub is_review: String,
+    /// Citation count reported by the search source or Semantic Scholar
+    #[serde(default)]
+    pub citations: String,
+    /// Citations per year since publication
+    #[serde(default)]
+    pub citations_per_year: String,
+    /// Citations relative to the paper's field and year (`impact` enricher)
+    #[serde(default)]
+    pub normalized_citations: String,
     pub if_score: String,
     pub jci_score: String,
     pub sci_partition: String,
+    /// Altmetric Attention Score (`altmetric` enricher)
+    #[serde(default)]
+    pub altmetric_score: String,
+    /// Posts mentioning the paper, across all Altmetric sources
+    #[serde(default)]
+    pub altmetric_mentions: String,
+    #[serde(default)]
+    pub altmetric_news: String,
+    #[serde(default)]
+    pub altmetric_policy: String,
+    /// Funder names, "; "-separated (Crossref, OpenAlex)
+    #[serde(default)]
+    pub funders: String,
+    /// Grant (award) numbers, "; "-separated
+    #[serde(default)]
+    pub grants: String,
+    /// Search sources that found the paper (e.g., "gscholar; openalex")
+    #[serde(default)]
+    pub sources: String,
 }
 
-/// Input item from EasyScholar enriched results
-pub struct EnrichedInput {
-    pub title: String,
-    pub author: String,
-    pub year: String,
-    pub publication_date: String,
-    pub doi: String,
-    pub article_url: String,
-    pub abstract_text: String,
-    pub journal: String,
-    pub if_score: String,
-    pub jci_score: String,
-    pub sci_partition: String,
+impl From<&Paper> for UnifiedRow {
+    fn from(paper: &Paper) -> Self {
+        let metric = |value: Option<&Option<String>>| value.cloned().flatten().unwrap_or_default();
+        let rankings = paper.rankings.as_ref();
+        let altmetric = paper.altmetric.as_ref();
+        Self {
+            title: paper.title.clone(),
+            author: paper.author_list(),
+            institutions: paper.institutions.join("; "),
+            countries: paper.countries.join("; "),
+            date: paper.date_string(),
+            doi: paper.doi.clone(),
+            article_url: paper.article_url.clone(),
+            pdf_url: paper.pdf_url.clone(),
+            pdf_path: paper.pdf_path.clone(),
+            abstract_text: paper.abstract_text.clone(),
+            tldr: paper.tldr.clone(),
+            journal: paper.journal.clone(),
+            venue_type: paper.venue_type.to_string(),
+            work_type: paper.work_type.to_string(),
+            is_review: paper.is_review.map(|r| r.to_string()).unwrap_or_default(),
+            citations: paper.citations.map(|c| c.to_string()).unwrap_or_default(),
+            citations_per_year: paper.citations_per_year().map(|c| format!("{:.2}", c)).unwrap_or_default(),
+            normalized_citations: paper.normalized_citations.map(|c| format!("{:.2}", c)).unwrap_or_default(),
+            if_score: metric(rankings.map(|r| &r.sciif)),
+            jci_score: metric(rankings.map(|r| &r.jci)),
+            sci_partition: metric(rankings.map(|r| &r.sci)),
+            altmetric_score: altmetric.map(|a| a.score.to_string()).unwrap_or_default(),
+            altmetric_mentions: altmetric.map(|a| a.mentions.to_string()).unwrap_or_default(),
+            altmetric_news: altmetric.map(|a| a.news.to_string()).unwrap_or_default(),
+            altmetric_policy: altmetric.map(|a| a.policy.to_string()).unwrap_or_default(),
+            funders: paper.funders.join("; "),
+            grants: paper.grants.join("; "),
+            sources: paper.source_list(),
+        }
+    }
+}
+
+impl From<UnifiedRow> for Paper {
+    fn from(row: UnifiedRow) -> Self {
+        let metric = |value: String| Some(value).filter(|v| !v.trim().is_empty());
+        let rankings = RankingMetrics {
+            sciif: metric(row.if_score),
+            jci: metric(row.jci_score),
+            sci: metric(row.sci_partition),
+            ..Default::default()
+        };
+        let ranked = rankings.sciif.is_some() || rankings.jci.is_some() || rankings.sci.is_some();
+        let count = |value: &str| value.trim().parse().unwrap_or_default();
+        let altmetric = row.altmetric_score.trim().parse().ok().map(|score| AltmetricMetrics {
+            score,
+            mentions: count(&row.altmetric_mentions),
+            news: count(&row.altmetric_news),
+            policy: count(&row.altmetric_policy),
+        });
+        let (year, date) = paper::parse_date(&row.date);
+        let mut paper = Paper {
+            title: row.title,
+            year,
+            date,
+            doi: row.doi,
+            article_url: row.article_url,
+            pdf_url: row.pdf_url,
+            pdf_path: row.pdf_path,
+            abstract_text: row.abstract_text,
+            tldr: row.tldr,
+            journal: row.journal,
+            venue_type: row.venue_type.parse().unwrap_or_default(),
+            work_type: row.work_type.parse().unwrap_or_default(),
+            is_review: row.is_review.trim().parse().ok(),
+            institutions: paper::parse_list(&row.institutions),
+            countries: paper::parse_list(&row.countries).into_iter().map(|c| c.to_uppercase()).collect(),
+            funders: paper::parse_list(&row.funders),
+            grants: paper::parse_list(&row.grants),
+            citations: paper::parse_count(&row.citations),
+            normalized_citations: row.normalized_citations.trim().parse().ok(),
+            rankings: ranked.then_some(rankings),
+            altmetric,
+            ..Default::default()
+        };
+        paper.set_authors(&row.author);
+        paper.set_sources(&row.sources);
+        paper
+    }
 }
 
 /// CSV column order for unified output
 pub const UNIFIED_COLUMNS: &[&str] = &[
-    "title", "author", "date", "doi", "article_url", "pdf_url", 
-    "abstract_text", "tldr", "journal", "if_score", "jci_score", "sci_partition"
+    "title", "author", "institutions", "countries", "date", "doi", "article_url", "pdf_url", "pdf_path",
+    "abstract_text", "tldr", "journal", "venue_type", "work_type", "is_review", "citations", "citations_per_year",
+    "normalized_citations", "if_score", "jci_score", "sci_partition",
+    "altmetric_score", "altmetric_mentions", "altmetric_news", "altmetric_policy", "funders", "grants", "sources"
 ];
 
 /// Generate unified results by joining EasyScholar with Semantic Scholar data.
 ///
 /// # Arguments
-/// * `enriched_items` - Items from EasyScholar stage (Stage 3)
+/// * `papers` - Papers from the EasyScholar stage (Stage 3)
 /// * `ss_results` - Results from Semantic Scholar batch lookup (Stage 4)
 ///
 /// # Returns
-/// Vector of unified results with merged data
-pub fn generate_unified(
-    enriched_items: &[EnrichedInput],
-    ss_results: &[SemanticScholarResult],
-) -> Vec<UnifiedResult> {
+/// The papers with a DOI, with Semantic Scholar data merged in
+pub fn generate_unified(papers: &[Paper], ss_results: &[SemanticScholarResult]) -> Vec<Paper> {
     // Build DOI -> Semantic Scholar lookup map
     let ss_map: HashMap<String, &SemanticScholarResult> = ss_results
         .iter()
         .filter(|r| !r.doi.is_empty())
-        .map(|r| (r.doi.to_lowercase(), r))
+        .map(|r| (doi::normalize(&r.doi), r))
         .collect();
 
-    enriched_items
+    papers
         .iter()
-        .filter(|r| !r.doi.is_empty())
-        .map(|r| {
-            let doi_lower = r.doi.to_lowercase();
-            let ss_data = ss_map.get(&doi_lower);
+        .filter(|p| !p.doi.is_empty())
+        .map(|p| {
+            let mut paper = p.clone();
+            let Some(ss_data) = ss_map.get(&doi::normalize(&p.doi)) else {
+                return paper;
+            };
 
             // Abstract priority: Semantic Scholar > OpenAlex/Crossref
-            let abstract_text = ss_data
-                .map(|s| s.ss_abstract.clone())
-                .filter(|a| !a.is_empty())
-                .unwrap_or_else(|| r.abstract_text.clone());
+            if !ss_data.ss_abstract.is_empty() {
+                paper.abstract_text = ss_data.ss_abstract.clone();
+            }
 
             // TLDR from Semantic Scholar
-            let tldr = ss_data.map(|s| s.tldr.clone()).unwrap_or_default();
+            paper.tldr = ss_data.tldr.clone();
 
             // Article URL: prefer OpenAlex/original, fallback to SS
-            let article_url = if !r.article_url.is_empty() {
-                r.article_url.clone()
-            } else {
-                ss_data.map(|s| s.ss_url.clone()).unwrap_or_default()
-            };
-
-            // PDF URL: from Semantic Scholar OA PDF
-            let pdf_url = ss_data.map(|s| s.oa_pdf_url.clone()).unwrap_or_default();
-
-            // Date: prefer publication_date (YYYY-MM-DD), fallback to year
-            let date = if !r.publication_date.is_empty() {
-                r.publication_date.clone()
-            } else {
-                r.year.clone()
-            };
+            if paper.article_url.is_empty() {
+                paper.article_url = ss_data.ss_url.clone();
+            }
 
-            UnifiedResult {
-                title: r.title.clone(),
-                author: r.author.clone(),
-                date,
-                doi: r.doi.clone(),
-                article_url,
-                pdf_url,
-                abstract_text,
-                tldr,
-                journal: r.journal.clone(),
-                if_score: r.if_score.clone(),
-                jci_score: r.jci_score.clone(),
-                sci_partition: r.sci_partition.clone(),
+            // PDF URL: prefer Semantic Scholar's OA PDF
+            if !ss_data.oa_pdf_url.is_empty() {
+                paper.pdf_url = ss_data.oa_pdf_url.clone();
             }
+            paper
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_row_round_trip() {
+        let row = UnifiedRow {
+            title: "Slope stability".to_string(),
+            author: "A. Li, B. Wu".to_string(),
+            date: "2023-05-02".to_string(),
+            doi: "10.1/a".to_string(),
+            if_score: "5.2".to_string(),
+            sci_partition: "Q1".to_string(),
+            normalized_citations: "1.50".to_string(),
+            altmetric_score: "12.5".to_string(),
+            altmetric_mentions: "30".to_string(),
+            altmetric_news: "2".to_string(),
+            altmetric_policy: "0".to_string(),
+            sources: "gscholar; openalex".to_string(),
+            ..Default::default()
+        };
+        let paper = Paper::from(row.clone());
+        assert_eq!(paper.year, Some(2023));
+        assert_eq!(paper.authors.len(), 2);
+        assert_eq!(paper.if_score(), Some(5.2));
+        assert_eq!(paper.normalized_citations, Some(1.5));
+        assert_eq!(paper.altmetric.as_ref().map(|a| a.mentions), Some(30));
+        assert_eq!(UnifiedRow::from(&paper), row);
+    }
+
+    #[test]
+    fn test_generate_unified_matches_doi_spellings() {
+        let papers = vec![
+            Paper { title: "Slope stability".to_string(), doi: "10.1/ABC".to_string(), ..Default::default() },
+            Paper { title: "No DOI".to_string(), ..Default::default() },
+        ];
+        let ss_results = vec![SemanticScholarResult {
+            doi: "https://doi.org/10.1/abc".to_string(),
+            tldr: "Slopes fail.".to_string(),
+            ..Default::default()
+        }];
+        let unified = generate_unified(&papers, &ss_results);
+        assert_eq!(unified.len(), 1);
+        assert_eq!(unified[0].tldr, "Slopes fail.");
+    }
+}
@@ -0,0 +1,153 @@
+//! Unpaywall API client for open-access locations.
+//!
+//! Looks up a DOI and returns the best open-access copy Unpaywall knows of.
+//! The API is free but requires a contact email on every request.
+
+use crate::cache::{self, HttpCache};
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::raw_archive::{self, BodyKind};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+/// Unpaywall API base URL
+pub const UNPAYWALL_API_URL: &str = "https://api.unpaywall.org/v2";
+
+/// Open-access status of one DOI
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OaLocation {
+    pub doi: String,
+    pub is_oa: bool,
+    /// `gold`, `green`, `hybrid`, `bronze` or `closed`
+    pub oa_status: String,
+    /// Direct PDF link of the best location, else its landing page
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnpaywallResponse {
+    #[serde(default)]
+    doi: String,
+    #[serde(default)]
+    is_oa: bool,
+    #[serde(default)]
+    oa_status: Option<String>,
+    #[serde(default)]
+    best_oa_location: Option<UnpaywallLocation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnpaywallLocation {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    url_for_pdf: Option<String>,
+}
+
+/// Unpaywall API client
+pub struct UnpaywallClient {
+    client: reqwest::Client,
+    email: String,
+    cache: Option<HttpCache>,
+}
+
+impl UnpaywallClient {
+    /// Create a new UnpaywallClient
+    ///
+    /// # Arguments
+    ///
+    /// * `email` - Contact email sent with every request (required by Unpaywall)
+    pub fn new(email: &str) -> Result<Self> {
+        let email = email.trim();
+        if !email.contains('@') {
+            return Err(GscholarError::Config(format!("Unpaywall needs a contact email, got '{}'", email)));
+        }
+        Ok(Self { client: http::client(), email: email.to_string(), cache: None })
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/unpaywall`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Look up the open-access status of a DOI (`None` when Unpaywall does not know it)
+    pub async fn lookup(&self, doi: &str) -> Result<Option<OaLocation>> {
+        let doi = doi.trim();
+        if doi.is_empty() {
+            return Ok(None);
+        }
+        let request = self
+            .client
+            .get(format!("{}/{}", UNPAYWALL_API_URL, doi))
+            .query(&[("email", self.email.as_str())])
+            .build()?;
+        let key = cache::request_key("GET", request.url().as_str(), "");
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            debug!(doi = %doi, "Unpaywall cache hit");
+            return Ok(Some(parse_response(&body)?));
+        }
+
+        let url = request.url().to_string();
+        let response = http::send(&self.client, request).await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(None),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => return Err(GscholarError::RateLimited(5)),
+            status if !status.is_success() => {
+                return Err(GscholarError::Api {
+                    code: status.as_u16() as i32,
+                    message: format!("Unpaywall API error: {}", status),
+                });
+            }
+            _ => {}
+        }
+
+        let body = response.text().await?;
+        raw_archive::record(&url, BodyKind::Json, &body);
+        let location = parse_response(&body)?;
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.insert(&key, &body) {
+                warn!(error = %e, "Failed to write Unpaywall cache");
+            }
+        }
+        Ok(Some(location))
+    }
+}
+
+fn parse_response(body: &str) -> Result<OaLocation> {
+    let data: UnpaywallResponse = serde_json::from_str(body)?;
+    let url = data
+        .best_oa_location
+        .and_then(|location| location.url_for_pdf.filter(|u| !u.is_empty()).or(location.url))
+        .unwrap_or_default();
+    Ok(OaLocation {
+        doi: data.doi,
+        is_oa: data.is_oa,
+        oa_status: data.oa_status.unwrap_or_default(),
+        url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() -> Result<()> {
+        let body = r#"{
+            "doi": "10.1038/nature12373",
+            "is_oa": true,
+            "oa_status": "green",
+            "best_oa_location": {"url": "https://europepmc.org/articles/pmc4221854", "url_for_pdf": null}
+        }"#;
+        let location = parse_response(body)?;
+        assert!(location.is_oa);
+        assert_eq!(location.oa_status, "green");
+        assert_eq!(location.url, "https://europepmc.org/articles/pmc4221854");
+
+        let closed = parse_response(r#"{"doi": "10.1/x", "is_oa": false, "oa_status": "closed", "best_oa_location": null}"#)?;
+        assert_eq!(closed.url, "");
+        assert!(UnpaywallClient::new("not-an-email").is_err());
+        Ok(())
+    }
+}
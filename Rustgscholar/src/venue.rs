@@ -0,0 +1,336 @@
+//! Venue types and conference rankings.
+//!
+//! EasyScholar ranks journals; conference papers need CCF and CORE instead.
+//! [`VenueType`] tells journals from conference proceedings: from OpenAlex's
+//! source type or Crossref's work type when the search reported one, else
+//! from the venue name ([`VenueType::guess`]) and, optionally, DBLP's venue
+//! search ([`DblpClient`]). Stage 3 then looks conferences up by name only
+//! (an ISSN would be the proceedings series') and adds their rank in the
+//! CORE conference ranking ([`CoreRankings`], from the CORE portal's CSV
+//! export).
+
+use crate::cache::{self, HttpCache};
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::rankings;
+use crate::raw_archive::{self, BodyKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// DBLP venue search API
+pub const DBLP_VENUE_API_URL: &str = "https://dblp.org/search/venue/api";
+
+/// Words that mark a venue name as proceedings
+const CONFERENCE_WORDS: &[&str] = &[
+    "proceedings", "proc", "conference", "conf", "symposium", "symp", "workshop", "congress", "meeting",
+];
+
+/// Words that mark a venue name as a journal
+const JOURNAL_WORDS: &[&str] = &[
+    "journal", "j", "transactions", "trans", "letters", "lett", "review", "reviews", "rev", "annals", "ann", "bulletin",
+    "bull", "magazine",
+];
+
+/// Journals whose names start like proceedings
+const PROCEEDINGS_JOURNALS: &[&str] = &[
+    "proceedings of the national academy of sciences",
+    "proceedings of the ieee",
+    "proceedings of the royal society",
+    "proceedings of the american mathematical society",
+    "proceedings of the vldb endowment",
+];
+
+/// Kind of venue a paper appeared in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VenueType {
+    #[default]
+    Unknown,
+    Journal,
+    Conference,
+    /// Repositories, books and anything else that is not ranked
+    Other,
+}
+
+impl VenueType {
+    /// From an OpenAlex source type ("journal", "conference", "repository", ...)
+    pub fn from_openalex(source_type: &str) -> Self {
+        match source_type.trim().to_lowercase().as_str() {
+            "" => Self::Unknown,
+            "journal" => Self::Journal,
+            "conference" => Self::Conference,
+            _ => Self::Other,
+        }
+    }
+
+    /// From a Crossref work type ("journal-article", "proceedings-article", ...)
+    pub fn from_crossref(work_type: &str) -> Self {
+        match work_type.trim() {
+            "" => Self::Unknown,
+            "journal-article" => Self::Journal,
+            "proceedings-article" | "proceedings" => Self::Conference,
+            _ => Self::Other,
+        }
+    }
+
+    /// From the words of a venue name ("Proceedings of ...", "Journal of ...");
+    /// `Unknown` when they say neither or both
+    pub fn guess(name: &str) -> Self {
+        let normalized = rankings::normalize_journal_name(name);
+        if PROCEEDINGS_JOURNALS.iter().any(|journal| normalized.starts_with(journal)) {
+            return Self::Journal;
+        }
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+        let has = |list: &[&str]| words.iter().any(|word| list.contains(word));
+        match (has(CONFERENCE_WORDS), has(JOURNAL_WORDS)) {
+            (true, false) => Self::Conference,
+            (false, true) => Self::Journal,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "",
+            Self::Journal => "journal",
+            Self::Conference => "conference",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for VenueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for VenueType {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "unknown" => Ok(Self::Unknown),
+            "journal" => Ok(Self::Journal),
+            "conference" => Ok(Self::Conference),
+            "other" => Ok(Self::Other),
+            other => Err(GscholarError::Validation(format!("Unknown venue type '{}'", other))),
+        }
+    }
+}
+
+/// CORE conference ranks by conference title and acronym
+#[derive(Debug, Clone, Default)]
+pub struct CoreRankings {
+    by_title: HashMap<String, String>,
+    by_acronym: HashMap<String, String>,
+}
+
+impl CoreRankings {
+    /// Load the CSV exported from the CORE portal (no header; columns: id,
+    /// title, acronym, source, rank, ...)
+    pub fn load(path: &Path) -> Result<Self> {
+        let rankings = Self::parse(&std::fs::read_to_string(path)?)?;
+        info!(count = rankings.by_title.len(), path = ?path, "Loaded CORE rankings");
+        Ok(rankings)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(text.as_bytes());
+        let mut rankings = Self::default();
+        for record in reader.records() {
+            let record = record.map_err(|e| GscholarError::Parse(format!("Invalid CORE rankings CSV: {}", e)))?;
+            let (Some(title), Some(acronym), Some(rank)) = (record.get(1), record.get(2), record.get(4)) else {
+                continue;
+            };
+            let rank = rank.trim();
+            if rank.is_empty() || title.trim().eq_ignore_ascii_case("title") {
+                continue;
+            }
+            rankings.by_title.insert(conference_key(title), rank.to_string());
+            if !acronym.trim().is_empty() {
+                rankings.by_acronym.insert(acronym.trim().to_uppercase(), rank.to_string());
+            }
+        }
+        Ok(rankings)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_title.is_empty()
+    }
+
+    /// Rank of a proceedings venue: by its title without "Proceedings of the"
+    /// and edition numbers, else by an acronym it contains ("... (ICML)",
+    /// "CVPR 2023")
+    pub fn rank(&self, venue: &str) -> Option<String> {
+        if let Some(rank) = self.by_title.get(&conference_key(venue)) {
+            return Some(rank.clone());
+        }
+        venue
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.len() >= 2 && word.chars().any(|c| c.is_ascii_uppercase()) && !word.chars().any(|c| c.is_lowercase()))
+            .find_map(|word| self.by_acronym.get(word))
+            .cloned()
+    }
+}
+
+/// Conference title as a comparison key: normalized, without a leading
+/// "proceedings of (the)", years and ordinal edition numbers
+fn conference_key(title: &str) -> String {
+    let normalized = rankings::normalize_journal_name(title);
+    let words: Vec<&str> = normalized
+        .split_whitespace()
+        .filter(|word| !word.starts_with(|c: char| c.is_ascii_digit()))
+        .filter(|word| !matches!(*word, "first" | "second" | "third"))
+        .collect();
+    let words = match words.as_slice() {
+        ["proceedings", "of", "the", rest @ ..] | ["proceedings", "of", rest @ ..] | ["proceedings", rest @ ..] => rest,
+        ["the", rest @ ..] => rest,
+        all => all,
+    };
+    words.join(" ")
+}
+
+#[derive(Debug, Deserialize)]
+struct DblpResponse {
+    result: DblpResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct DblpResult {
+    hits: DblpHits,
+}
+
+#[derive(Debug, Deserialize)]
+struct DblpHits {
+    #[serde(default)]
+    hit: Vec<DblpHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DblpHit {
+    info: DblpVenue,
+}
+
+#[derive(Debug, Deserialize)]
+struct DblpVenue {
+    #[serde(rename = "type", default)]
+    venue_type: String,
+}
+
+/// DBLP venue search (computer science venues)
+pub struct DblpClient {
+    client: reqwest::Client,
+    cache: Option<HttpCache>,
+    types: Mutex<HashMap<String, VenueType>>,
+}
+
+impl Default for DblpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DblpClient {
+    pub fn new() -> Self {
+        Self { client: http::client(), cache: None, types: Mutex::new(HashMap::new()) }
+    }
+
+    /// Enable the HTTP response cache (e.g., `~/.cache/rustgscholar/http/dblp`)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Type of the best DBLP match for a venue name (`Unknown` when DBLP has none)
+    pub async fn venue_type(&self, name: &str) -> Result<VenueType> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(VenueType::Unknown);
+        }
+        if let Some(known) = self.types.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(name) {
+            return Ok(*known);
+        }
+        let url = format!("{}?q={}&format=json&h=1", DBLP_VENUE_API_URL, urlencoding::encode(name));
+        let key = cache::request_key("GET", &url, "");
+        let body = match self.cache.as_ref().and_then(|c| c.get(&key)) {
+            Some(body) => {
+                debug!(venue = %name, "DBLP cache hit");
+                body
+            }
+            None => {
+                let request = self.client.get(&url).build()?;
+                let response = http::send(&self.client, request).await?;
+                if !response.status().is_success() {
+                    return Err(GscholarError::Api {
+                        code: response.status().as_u16() as i32,
+                        message: format!("DBLP API error: {}", response.status()),
+                    });
+                }
+                let body = response.text().await?;
+                raw_archive::record(&url, BodyKind::Json, &body);
+                if let Some(cache) = &self.cache {
+                    if let Err(e) = cache.insert(&key, &body) {
+                        warn!(error = %e, "Failed to write DBLP cache");
+                    }
+                }
+                body
+            }
+        };
+        let venue_type = parse_dblp(&body)?;
+        self.types.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(name.to_string(), venue_type);
+        Ok(venue_type)
+    }
+}
+
+fn parse_dblp(body: &str) -> Result<VenueType> {
+    let response: DblpResponse =
+        serde_json::from_str(body).map_err(|e| GscholarError::Parse(format!("Failed to parse DBLP response: {}", e)))?;
+    Ok(match response.result.hits.hit.first().map(|hit| hit.info.venue_type.as_str()) {
+        None => VenueType::Unknown,
+        Some(kind) if kind.contains("Conference") => VenueType::Conference,
+        Some(kind) if kind.contains("Journal") => VenueType::Journal,
+        Some(_) => VenueType::Other,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_venue_type_detection() -> Result<()> {
+        assert_eq!(VenueType::from_openalex("conference"), VenueType::Conference);
+        assert_eq!(VenueType::from_openalex("repository"), VenueType::Other);
+        assert_eq!(VenueType::from_crossref("proceedings-article"), VenueType::Conference);
+        assert_eq!(VenueType::guess("Proceedings of the AAAI Conference on Artificial Intelligence"), VenueType::Conference);
+        assert_eq!(VenueType::guess("IEEE Trans. Geosci. Remote Sens."), VenueType::Journal);
+        assert_eq!(VenueType::guess("Landslides"), VenueType::Unknown);
+        assert_eq!(VenueType::guess("Proceedings of the National Academy of Sciences"), VenueType::Journal);
+        assert_eq!("conference".parse::<VenueType>()?, VenueType::Conference);
+        assert_eq!("".parse::<VenueType>()?, VenueType::Unknown);
+
+        let dblp = r#"{"result": {"hits": {"@total": "1", "hit": [{"info": {"venue": "Computer Vision and Pattern Recognition", "acronym": "CVPR", "type": "Conference or Workshop"}}]}}}"#;
+        assert_eq!(parse_dblp(dblp)?, VenueType::Conference);
+        assert_eq!(parse_dblp(r#"{"result": {"hits": {"@total": "0"}}}"#)?, VenueType::Unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn test_core_rankings() -> Result<()> {
+        let csv = "\"1\",\"International Conference on Machine Learning\",\"ICML\",\"CORE2023\",\"A*\",\"Yes\",\"4611\"\n\
+                   \"2\",\"Computer Vision and Pattern Recognition\",\"CVPR\",\"CORE2023\",\"A*\",\"Yes\",\"4603\"\n\
+                   \"3\",\"Australasian Database Conference\",\"ADC\",\"CORE2023\",\"B\",\"No\",\"4605\"\n";
+        let core = CoreRankings::parse(csv)?;
+        assert_eq!(core.rank("Proceedings of the 40th International Conference on Machine Learning"), Some("A*".to_string()));
+        assert_eq!(core.rank("2023 IEEE/CVF Conference on Computer Vision and Pattern Recognition (CVPR)"), Some("A*".to_string()));
+        assert_eq!(core.rank("australasian database conference"), Some("B".to_string()));
+        assert_eq!(core.rank("Landslides"), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! Scheduled review updates (`watch`).
+//!
+//! `watch` re-runs a review every `--interval`. Each run is an incremental
+//! update (see [`incremental`](crate::incremental)) of the latest run folder
+//! for the same keyword, so restarting the watcher, or running it from cron
+//! with `--once`, picks up where the last run left off. When an update finds
+//! new papers (the new relevant papers when Stage 7 ran, otherwise every new
+//! record) the configured [`Notifier`]s are told.
+
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::incremental::SEEN_FILE;
+use crate::pipeline::PipelineReport;
+use crate::state::STATE_FILE;
+use crate::paper::Paper;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Parse an interval such as `90m`, `12h`, `7d` or `2w` (a bare number is seconds)
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let invalid = || GscholarError::Validation(format!("Invalid interval '{}' (expected e.g. 12h, 7d, 2w)", value));
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    if number == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(number * seconds))
+}
+
+/// Whether a run folder recorded any records an update can compare against
+pub fn has_records(folder: &Path) -> bool {
+    folder.join(STATE_FILE).exists() || folder.join(SEEN_FILE).exists()
+}
+
+/// Latest run folder in `output_dir` named `{YYYYMMDD_HHMMSS}_{name}` that
+/// [`has_records`]
+pub fn latest_run(output_dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    if !output_dir.is_dir() {
+        return Ok(None);
+    }
+    let mut runs: Vec<PathBuf> = std::fs::read_dir(output_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+                return false;
+            };
+            has_records(path)
+                && file_name.len() == 16 + name.len()
+                && file_name.ends_with(name)
+                && file_name.get(15..16) == Some("_")
+                && file_name
+                    .get(..15)
+                    .is_some_and(|timestamp| chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S").is_ok())
+        })
+        .collect();
+    runs.sort();
+    Ok(runs.pop())
+}
+
+/// New papers found by one update run
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub keyword: String,
+    pub run_folder: PathBuf,
+    pub papers: Vec<Paper>,
+}
+
+impl Update {
+    /// New relevant papers of an update run, or its new records when Stage 7 did not run
+    pub fn from_report(keyword: &str, report: &PipelineReport) -> Self {
+        let papers = match report.prisma.included {
+            Some(_) => report.relevant.clone(),
+            None => report.unified.clone(),
+        };
+        Self { keyword: keyword.to_string(), run_folder: report.output_folder.clone(), papers }
+    }
+
+    /// One-line summary (email subject)
+    pub fn subject(&self) -> String {
+        let plural = if self.papers.len() == 1 { "" } else { "s" };
+        format!("{} new paper{} for \"{}\"", self.papers.len(), plural, self.keyword)
+    }
+
+    /// Plain-text message listing the papers
+    pub fn text(&self) -> String {
+        let mut out = format!("{}\n\n", self.subject());
+        for paper in &self.papers {
+            let year = paper.year.map(|y| y.to_string()).unwrap_or_default();
+            let details: Vec<&str> = [paper.journal.trim(), year.as_str()].into_iter().filter(|d| !d.is_empty()).collect();
+            out.push_str(&format!("- {}", paper.title.trim()));
+            if !details.is_empty() {
+                out.push_str(&format!(" ({})", details.join(", ")));
+            }
+            if !paper.doi.is_empty() {
+                out.push_str(&format!(" https://doi.org/{}", paper.doi));
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("\nResults: {}\n", self.run_folder.display()));
+        out
+    }
+
+    /// JSON payload for generic webhooks
+    fn json(&self) -> serde_json::Value {
+        json!({
+            "keyword": self.keyword,
+            "run_folder": self.run_folder.display().to_string(),
+            "count": self.papers.len(),
+            "papers": self.papers.iter().map(|p| json!({
+                "title": p.title,
+                "doi": p.doi,
+                "journal": p.journal,
+                "date": p.date,
+                "article_url": p.article_url,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Where update notifications go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notifier {
+    /// POST the update as JSON to a URL
+    Webhook(String),
+    /// Slack incoming webhook
+    Slack(String),
+    /// Email via the local `sendmail -t`
+    Email(String),
+}
+
+impl Notifier {
+    pub async fn send(&self, update: &Update) -> Result<()> {
+        match self {
+            Self::Webhook(url) => post(url, &update.json()).await,
+            Self::Slack(url) => post(url, &json!({ "text": update.text() })).await,
+            Self::Email(to) => sendmail(&email_message(to, update)).await,
+        }
+    }
+}
+
+async fn post(url: &str, payload: &serde_json::Value) -> Result<()> {
+    let response = http::client().post(url).json(payload).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(GscholarError::Api {
+            code: status.as_u16() as i32,
+            message: format!("Notification webhook error: {}", status),
+        });
+    }
+    Ok(())
+}
+
+fn email_message(to: &str, update: &Update) -> String {
+    format!(
+        "To: {}\nSubject: {}\nMIME-Version: 1.0\nContent-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n{}",
+        header_value(to),
+        encode_header(&format!("[rustgscholar] {}", header_value(&update.subject()))),
+        update.text()
+    )
+}
+
+/// A header value on one line: the keyword must not add headers of its own
+fn header_value(value: &str) -> String {
+    value.split(['\r', '\n']).map(str::trim).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// RFC 2047 "Q" encoded-words for a non-ASCII header value, folded so each
+/// word stays within the 75 characters the RFC allows
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    const MAX_ENCODED: usize = 75 - "=?UTF-8?Q??=".len();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in value.chars() {
+        let encoded = match c {
+            ' ' => "_".to_string(),
+            c if c.is_ascii_alphanumeric() => c.to_string(),
+            c => c.to_string().bytes().map(|b| format!("={:02X}", b)).collect(),
+        };
+        if word.len() + encoded.len() > MAX_ENCODED {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push_str(&encoded);
+    }
+    words.push(word);
+    words.iter().map(|word| format!("=?UTF-8?Q?{}?=", word)).collect::<Vec<_>>().join("\n ")
+}
+
+async fn sendmail(message: &str) -> Result<()> {
+    let mut child = Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| GscholarError::Config(format!("Failed to run sendmail: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes()).await?;
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(GscholarError::Config(format!("sendmail exited with {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() -> Result<()> {
+        assert_eq!(parse_interval("7d")?, Duration::from_secs(7 * 24 * 3600));
+        assert_eq!(parse_interval("12h")?, Duration::from_secs(12 * 3600));
+        assert_eq!(parse_interval("90")?, Duration::from_secs(90));
+        for invalid in ["", "d", "7y", "0h", "-1d"] {
+            assert!(parse_interval(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_latest_run() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        assert_eq!(latest_run(&dir.path().join("missing"), "landslide")?, None);
+        for name in ["20250101_120000_landslide", "20250201_120000_landslide", "20250301_120000_shallow_landslide", "notes_landslide"] {
+            std::fs::create_dir(dir.path().join(name))?;
+            std::fs::write(dir.path().join(name).join(STATE_FILE), "{}")?;
+        }
+        // A later run that found nothing is not an update base
+        std::fs::create_dir(dir.path().join("20250401_120000_landslide"))?;
+        assert_eq!(latest_run(dir.path(), "landslide")?, Some(dir.path().join("20250201_120000_landslide")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_messages() {
+        let update = Update {
+            keyword: "landslide".to_string(),
+            run_folder: PathBuf::from("output/20250201_120000_landslide"),
+            papers: vec![Paper {
+                title: "Debris flows".to_string(),
+                journal: "Landslides".to_string(),
+                year: Some(2025),
+                doi: "10.1/b".to_string(),
+                ..Default::default()
+            }],
+        };
+        assert_eq!(update.subject(), "1 new paper for \"landslide\"");
+        assert!(update.text().contains("- Debris flows (Landslides, 2025) https://doi.org/10.1/b\n"));
+        assert_eq!(update.json()["papers"][0]["doi"], "10.1/b");
+        assert!(email_message("me@example.org", &update).starts_with("To: me@example.org\nSubject: [rustgscholar] 1 new paper"));
+    }
+
+    #[test]
+    fn test_email_headers() {
+        let mut update = Update {
+            keyword: "landslide\r\nBcc: everyone@example.org".to_string(),
+            run_folder: PathBuf::from("output/20250201_120000_landslide"),
+            papers: Vec::new(),
+        };
+        let message = email_message("me@example.org", &update);
+        let (headers, _) = message.split_once("\n\n").unwrap_or_default();
+        assert!(!headers.lines().any(|line| line.starts_with("Bcc:")));
+        assert!(headers.contains("Subject: [rustgscholar] 0 new papers for \"landslide Bcc: everyone@example.org\""));
+        assert!(headers.contains("\nMIME-Version: 1.0\n"));
+
+        update.keyword = "滑坡 landslide".to_string();
+        let subject = encode_header(&format!("[rustgscholar] {}", update.subject()));
+        assert!(subject.starts_with("=?UTF-8?Q?=5Brustgscholar=5D_0_new_papers_for_=22=E6=BB=91=E5=9D=A1"));
+        assert!(subject.lines().all(|line| line.trim().len() <= 75 && line.trim().ends_with("?=")));
+        assert!(subject.is_ascii());
+        assert_eq!(encode_header("plain"), "plain");
+    }
+}
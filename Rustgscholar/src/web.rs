@@ -0,0 +1,126 @@
+//! Web UI of `serve`.
+//!
+//! A single page bundled into the binary from `web/` and served at `/`, so
+//! colleagues who do not use the CLI can submit jobs, watch their progress,
+//! browse the result store and download a run's files and exports. The page
+//! only talks to the JSON API; with API keys configured the user enters a
+//! key, which the page sends like any other client.
+
+use rust_embed::RustEmbed;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+#[derive(RustEmbed)]
+#[folder = "web/"]
+struct Assets;
+
+/// Path prefix of the UI's static assets
+pub const ASSETS_PATH: &str = "/assets/";
+
+/// An embedded file of the UI
+pub struct Asset {
+    pub content_type: &'static str,
+    pub data: Cow<'static, [u8]>,
+}
+
+/// The UI file at `path` (`index.html` for an empty path)
+pub fn asset(path: &str) -> Option<Asset> {
+    let path = match path.trim_start_matches('/') {
+        "" => "index.html",
+        path => path,
+    };
+    Assets::get(path).map(|file| Asset { content_type: content_type(path), data: file.data })
+}
+
+/// Whether `path` is the UI, which loads without an API key
+pub fn is_ui_path(path: &str) -> bool {
+    path == "/" || path.starts_with(ASSETS_PATH)
+}
+
+/// Content type of the files the UI and the pipeline write
+pub fn content_type(name: &str) -> &'static str {
+    let extension = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "csv" => "text/csv; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "txt" | "bib" | "ris" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "jsonl" => "application/x-ndjson",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "graphml" | "gexf" | "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A downloadable file of a run folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Files directly in a run folder, by name (subfolders such as `pdfs/` are left out)
+pub fn run_files(folder: &Path) -> crate::error::Result<Vec<RunFile>> {
+    let mut files = Vec::new();
+    if !folder.is_dir() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(folder)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if metadata.is_file() && !name.starts_with('.') {
+            files.push(RunFile { name, size: metadata.len() });
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Path of the file `name` directly in a run folder, if there is one
+///
+/// Names with path separators or a leading dot are refused, so a download
+/// cannot leave the run folder.
+pub fn run_file(folder: &Path, name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return None;
+    }
+    Some(folder.join(name)).filter(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_are_embedded() {
+        let index = asset("/").map(|a| (a.content_type, String::from_utf8_lossy(&a.data).contains("app.js")));
+        assert_eq!(index, Some(("text/html; charset=utf-8", true)));
+        assert!(asset("app.js").is_some());
+        assert!(asset("missing.js").is_none());
+        assert!(is_ui_path("/assets/app.js") && !is_ui_path("/jobs"));
+    }
+
+    #[test]
+    fn test_run_files_stay_in_the_folder() -> crate::error::Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(dir.path().join("5_unified.csv"), "title\nA\n")?;
+        std::fs::write(dir.path().join(".hidden"), "")?;
+        std::fs::create_dir(dir.path().join("pdfs"))?;
+
+        let files = run_files(dir.path())?;
+        assert_eq!(files, vec![RunFile { name: "5_unified.csv".to_string(), size: 8 }]);
+        assert!(run_file(dir.path(), "5_unified.csv").is_some());
+        assert!(run_file(dir.path(), "../5_unified.csv").is_none());
+        assert!(run_file(dir.path(), ".hidden").is_none());
+        assert!(run_file(dir.path(), "pdfs").is_none());
+        assert_eq!(content_type("report.html"), "text/html; charset=utf-8");
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+//! Work types (`--work-types`).
+//!
+//! Reviews often have to include or exclude review articles, preprints or
+//! book chapters. [`WorkType`] is the tool's own taxonomy; each source's
+//! types map onto it: OpenAlex `type`, Crossref `type` and Semantic
+//! Scholar `publicationTypes`. OpenAlex and Semantic Scholar are asked for
+//! the wanted types only, Google Scholar for review articles only when
+//! nothing else is wanted; after the search, records of a known unwanted
+//! type are dropped whichever source found them.
+
+use crate::error::{GscholarError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Kind of work a record is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkType {
+    #[default]
+    Unknown,
+    /// Research article, in a journal or conference proceedings
+    Article,
+    /// Review article (including meta-analyses)
+    Review,
+    Preprint,
+    BookChapter,
+    Book,
+    Dissertation,
+    /// Editorials, letters, datasets, errata and the like
+    Other,
+}
+
+impl WorkType {
+    /// Types accepted by `--work-types`
+    pub const SELECTABLE: [WorkType; 7] = [
+        Self::Article,
+        Self::Review,
+        Self::Preprint,
+        Self::BookChapter,
+        Self::Book,
+        Self::Dissertation,
+        Self::Other,
+    ];
+
+    /// From an OpenAlex work type ("article", "review", "book-chapter", ...)
+    pub fn from_openalex(work_type: &str) -> Self {
+        match work_type.trim() {
+            "" => Self::Unknown,
+            "article" => Self::Article,
+            "review" => Self::Review,
+            "preprint" => Self::Preprint,
+            "book-chapter" => Self::BookChapter,
+            "book" => Self::Book,
+            "dissertation" => Self::Dissertation,
+            _ => Self::Other,
+        }
+    }
+
+    /// From a Crossref work type ("journal-article", "posted-content", ...);
+    /// Crossref files review articles as journal articles
+    pub fn from_crossref(work_type: &str) -> Self {
+        match work_type.trim() {
+            "" => Self::Unknown,
+            "journal-article" | "proceedings-article" => Self::Article,
+            "posted-content" => Self::Preprint,
+            "book-chapter" | "book-section" | "book-part" => Self::BookChapter,
+            "book" | "monograph" | "edited-book" | "reference-book" => Self::Book,
+            "dissertation" => Self::Dissertation,
+            _ => Self::Other,
+        }
+    }
+
+    /// From Semantic Scholar publication types ("Review", "JournalArticle",
+    /// ...); a review counts as a review whatever else it is
+    pub fn from_semantic_scholar(types: &[String]) -> Self {
+        let has = |names: &[&str]| types.iter().any(|t| names.contains(&t.as_str()));
+        if types.is_empty() {
+            Self::Unknown
+        } else if has(&["Review", "MetaAnalysis"]) {
+            Self::Review
+        } else if has(&["JournalArticle", "Conference", "Study", "CaseReport", "ClinicalTrial"]) {
+            Self::Article
+        } else if has(&["BookSection"]) {
+            Self::BookChapter
+        } else if has(&["Book"]) {
+            Self::Book
+        } else {
+            Self::Other
+        }
+    }
+
+    /// OpenAlex `type` filter values
+    fn openalex_types(self) -> &'static [&'static str] {
+        match self {
+            Self::Unknown => &[],
+            Self::Article => &["article"],
+            Self::Review => &["review"],
+            Self::Preprint => &["preprint"],
+            Self::BookChapter => &["book-chapter"],
+            Self::Book => &["book"],
+            Self::Dissertation => &["dissertation"],
+            Self::Other => &["editorial", "letter", "dataset", "erratum", "paratext", "other"],
+        }
+    }
+
+    /// Semantic Scholar `publicationTypes` values (`None`: it has no such type)
+    fn semantic_scholar_types(self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Article => Some(&["JournalArticle", "Conference", "Study", "CaseReport", "ClinicalTrial"]),
+            Self::Review => Some(&["Review", "MetaAnalysis"]),
+            Self::BookChapter => Some(&["BookSection"]),
+            Self::Book => Some(&["Book"]),
+            Self::Other => Some(&["Editorial", "LettersAndComments", "News", "Dataset"]),
+            Self::Unknown | Self::Preprint | Self::Dissertation => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "",
+            Self::Article => "article",
+            Self::Review => "review",
+            Self::Preprint => "preprint",
+            Self::BookChapter => "book-chapter",
+            Self::Book => "book",
+            Self::Dissertation => "dissertation",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for WorkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WorkType {
+    type Err = GscholarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "" | "unknown" => Ok(Self::Unknown),
+            "article" | "journal-article" => Ok(Self::Article),
+            "review" => Ok(Self::Review),
+            "preprint" => Ok(Self::Preprint),
+            "book-chapter" | "chapter" => Ok(Self::BookChapter),
+            "book" => Ok(Self::Book),
+            "dissertation" | "thesis" => Ok(Self::Dissertation),
+            "other" => Ok(Self::Other),
+            other => Err(GscholarError::Validation(format!(
+                "Unknown work type '{}' (expected one of: {})",
+                other,
+                Self::SELECTABLE.map(Self::as_str).join(", ")
+            ))),
+        }
+    }
+}
+
+/// OpenAlex `type` filter for the wanted types ("type:article|review");
+/// journal articles only when none are given
+pub fn openalex_filter(types: &[WorkType]) -> String {
+    let values: Vec<&str> = types.iter().flat_map(|t| t.openalex_types().iter().copied()).collect();
+    if values.is_empty() {
+        return "type:article".to_string();
+    }
+    format!("type:{}", values.join("|"))
+}
+
+/// Semantic Scholar `publicationTypes` for the wanted types; `None` when
+/// none are given or one has no Semantic Scholar counterpart (preprints),
+/// as the parameter would then drop records that should stay
+pub fn semantic_scholar_filter(types: &[WorkType]) -> Option<String> {
+    let mut values: Vec<&str> = Vec::new();
+    for work_type in types {
+        values.extend(work_type.semantic_scholar_types()?);
+    }
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+/// Whether Google Scholar can be restricted to review articles (`as_rr=1`)
+pub fn scholar_reviews_only(types: &[WorkType]) -> bool {
+    !types.is_empty() && types.iter().all(|t| *t == WorkType::Review)
+}
+
+/// Whether a record of this type passes the wanted types (unknown types pass)
+pub fn is_wanted(work_type: WorkType, types: &[WorkType]) -> bool {
+    types.is_empty() || work_type == WorkType::Unknown || types.contains(&work_type)
+}
+
+/// Parse the names given to `--work-types` (duplicates are ignored)
+pub fn parse_list(names: &[String]) -> Result<Vec<WorkType>> {
+    let mut types = Vec::new();
+    for name in names.iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        match name.parse()? {
+            WorkType::Unknown => return Err(GscholarError::Validation("'unknown' is not a selectable work type".to_string())),
+            work_type if !types.contains(&work_type) => types.push(work_type),
+            _ => {}
+        }
+    }
+    Ok(types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_mappings() {
+        assert_eq!(WorkType::from_openalex("review"), WorkType::Review);
+        assert_eq!(WorkType::from_openalex("editorial"), WorkType::Other);
+        assert_eq!(WorkType::from_crossref("posted-content"), WorkType::Preprint);
+        assert_eq!(WorkType::from_crossref("proceedings-article"), WorkType::Article);
+        let types = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(WorkType::from_semantic_scholar(&types(&["JournalArticle", "Review"])), WorkType::Review);
+        assert_eq!(WorkType::from_semantic_scholar(&types(&["BookSection"])), WorkType::BookChapter);
+        assert_eq!(WorkType::from_semantic_scholar(&[]), WorkType::Unknown);
+    }
+
+    fn names(list: &str) -> Vec<String> {
+        list.split(',').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_filters() -> Result<()> {
+        let wanted = parse_list(&names("article, review,article"))?;
+        assert_eq!(wanted, vec![WorkType::Article, WorkType::Review]);
+        assert_eq!(openalex_filter(&wanted), "type:article|review");
+        assert_eq!(openalex_filter(&[]), "type:article");
+        assert_eq!(
+            semantic_scholar_filter(&wanted).as_deref(),
+            Some("JournalArticle,Conference,Study,CaseReport,ClinicalTrial,Review,MetaAnalysis")
+        );
+        assert_eq!(semantic_scholar_filter(&parse_list(&names("review,preprint"))?), None);
+        assert!(scholar_reviews_only(&[WorkType::Review]));
+        assert!(!scholar_reviews_only(&wanted));
+        assert!(is_wanted(WorkType::Unknown, &wanted));
+        assert!(!is_wanted(WorkType::Preprint, &wanted));
+        assert!(parse_list(&names("article,poster")).is_err());
+        Ok(())
+    }
+}
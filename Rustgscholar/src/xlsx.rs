@@ -0,0 +1,267 @@
+//! Excel (.xlsx) workbook writer.
+//!
+//! Writes one worksheet per stage CSV with a bold, frozen header row, an
+//! autofilter, and column widths fitted to the content. The workbook itself is
+//! written by `rust_xlsxwriter`. Excel's sheet limits are enforced here: a
+//! table longer than 1,048,576 rows continues on numbered sheets ("5_unified
+//! (2)", ...) that repeat the header, and cells are cut at 32,767 characters.
+
+use crate::error::{GscholarError, Result};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Widest auto-fitted column, in characters
+const MAX_COLUMN_WIDTH: usize = 60;
+
+/// Rows per worksheet, header included (Excel's limit)
+pub const MAX_ROWS: usize = 1_048_576;
+
+/// Characters per cell (Excel's limit)
+pub const MAX_CELL_CHARS: usize = 32_767;
+
+/// A worksheet: header row followed by data rows
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Sheet {
+    /// Load a CSV file (header included) as a sheet
+    pub fn from_csv(name: &str, path: &Path) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| GscholarError::Parse(format!("Failed to open {:?}: {}", path, e)))?;
+
+        let rows = reader
+            .records()
+            .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+            .collect::<std::result::Result<Vec<Vec<String>>, _>>()
+            .map_err(|e| GscholarError::Parse(format!("Failed to read {:?}: {}", path, e)))?;
+
+        Ok(Self { name: name.to_string(), rows })
+    }
+}
+
+/// One worksheet's share of a sheet: name, header row, data rows
+type Part<'a> = (String, &'a [Vec<String>], &'a [Vec<String>]);
+
+/// Write sheets to an .xlsx workbook
+pub fn write_workbook(path: &Path, sheets: &[Sheet]) -> Result<()> {
+    write_sheets(path, sheets, MAX_ROWS)
+}
+
+/// Write sheets, continuing any sheet longer than `max_rows` on further sheets
+fn write_sheets(path: &Path, sheets: &[Sheet], max_rows: usize) -> Result<()> {
+    if sheets.is_empty() {
+        return Err(GscholarError::Validation("Workbook needs at least one sheet".to_string()));
+    }
+
+    let parts: Vec<Part> = sheets.iter().flat_map(|s| split_sheet(s, max_rows)).collect();
+    let names = sheet_names(parts.iter().map(|(name, _, _)| name.as_str()));
+
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    for (name, (_, header, rows)) in names.iter().zip(&parts) {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(name).map_err(write_error)?;
+        write_worksheet(worksheet, header, rows, &bold).map_err(write_error)?;
+    }
+    workbook.save(path).map_err(write_error)?;
+
+    info!(path = ?path, sheets = parts.len(), "Saved workbook");
+    Ok(())
+}
+
+/// Split a sheet at `max_rows` (header included)
+fn split_sheet(sheet: &Sheet, max_rows: usize) -> Vec<Part<'_>> {
+    let (header, data) = sheet.rows.split_at(sheet.rows.len().min(1));
+    if data.is_empty() {
+        return vec![(sheet.name.clone(), header, data)];
+    }
+
+    let chunks: Vec<&[Vec<String>]> = data.chunks(max_rows.saturating_sub(1).max(1)).collect();
+    if chunks.len() > 1 {
+        warn!(sheet = %sheet.name, rows = data.len(), sheets = chunks.len(), "Table exceeds Excel's row limit; continuing on further sheets");
+    }
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, rows)| {
+            let name = if i == 0 {
+                sheet.name.clone()
+            } else {
+                let suffix = format!(" ({})", i + 1);
+                sheet.name.chars().take(31 - suffix.len()).collect::<String>() + &suffix
+            };
+            (name, header, rows)
+        })
+        .collect()
+}
+
+/// Valid, unique sheet names (max 31 chars, no `[]:*?/\`)
+fn sheet_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let mut name: String = raw
+                .chars()
+                .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+                .take(31)
+                .collect();
+            // Excel also rejects names that start or end with an apostrophe
+            name = name.trim_matches('\'').to_string();
+            if name.trim().is_empty() {
+                name = format!("Sheet{}", i + 1);
+            }
+            if !used.insert(name.to_lowercase()) {
+                let suffix = format!("_{}", i + 1);
+                name = name.chars().take(31 - suffix.len()).collect::<String>() + &suffix;
+                used.insert(name.to_lowercase());
+            }
+            name
+        })
+        .collect()
+}
+
+/// One worksheet: frozen bold header, autofilter, fitted widths
+fn write_worksheet(worksheet: &mut Worksheet, header: &[Vec<String>], rows: &[Vec<String>], bold: &Format) -> std::result::Result<(), XlsxError> {
+    let columns = header.iter().chain(rows).map(Vec::len).max().unwrap_or(0);
+
+    worksheet.set_freeze_panes(1, 0)?;
+    for col in 0..columns {
+        let width = header
+            .iter()
+            .chain(rows)
+            .filter_map(|row| row.get(col))
+            .map(|v| v.chars().count())
+            .max()
+            .unwrap_or(0)
+            .clamp(8, MAX_COLUMN_WIDTH);
+        worksheet.set_column_width(col as u16, (width + 2) as f64)?;
+    }
+
+    for (c, value) in header.iter().flatten().enumerate() {
+        worksheet.write_string_with_format(0, c as u16, cell_text(value), bold)?;
+    }
+    for (r, row) in rows.iter().enumerate() {
+        let r = (r + 1) as u32;
+        for (c, value) in row.iter().enumerate() {
+            let c = c as u16;
+            match value.parse::<f64>() {
+                Ok(number) if is_number(value) => {
+                    worksheet.write_number(r, c, number)?;
+                }
+                _ if !value.is_empty() => {
+                    worksheet.write_string(r, c, cell_text(value))?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if columns > 0 {
+        worksheet.autofilter(0, 0, rows.len() as u32, (columns - 1) as u16)?;
+    }
+    Ok(())
+}
+
+/// Cell text cut to Excel's limit
+fn cell_text(value: &str) -> &str {
+    match value.char_indices().nth(MAX_CELL_CHARS) {
+        Some((end, _)) => &value[..end],
+        None => value,
+    }
+}
+
+/// Plain decimal numbers become numeric cells; IDs with leading zeros stay text
+fn is_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    !digits.is_empty()
+        && digits.len() <= 15
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1
+        && !digits.starts_with('.')
+        && !digits.ends_with('.')
+        && !(digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0."))
+}
+
+fn write_error(error: XlsxError) -> GscholarError {
+    GscholarError::Io(std::io::Error::other(format!("Failed to write workbook: {}", error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{open_workbook, Data, Reader, Xlsx};
+
+    fn read_back(path: &Path) -> Result<Vec<(String, Vec<Vec<Data>>)>> {
+        let mut workbook: Xlsx<_> = open_workbook(path).map_err(|e: calamine::XlsxError| GscholarError::Parse(e.to_string()))?;
+        let names = workbook.sheet_names();
+        names
+            .into_iter()
+            .map(|name| {
+                let range = workbook.worksheet_range(&name).map_err(|e| GscholarError::Parse(e.to_string()))?;
+                Ok((name, range.rows().map(<[Data]>::to_vec).collect()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_workbook() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("results.xlsx");
+        let long = "x".repeat(MAX_CELL_CHARS + 10);
+        let sheet = Sheet {
+            name: "a/b".to_string(),
+            rows: vec![
+                vec!["title".to_string(), "citations".to_string(), "issn".to_string()],
+                vec!["A & B <review>\u{1}".to_string(), "42".to_string(), "0123-4567".to_string()],
+                vec![long, "".to_string(), "0123".to_string()],
+            ],
+        };
+        write_workbook(&path, &[sheet.clone(), sheet])?;
+
+        let sheets = read_back(&path)?;
+        let names: Vec<&str> = sheets.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["a_b", "a_b_2"]);
+        let rows = &sheets[0].1;
+        assert_eq!(rows[0][0], Data::String("title".to_string()));
+        assert_eq!(rows[1][0], Data::String("A & B <review>\u{1}".to_string()));
+        assert_eq!(rows[1][1], Data::Float(42.0));
+        assert_eq!(rows[1][2], Data::String("0123-4567".to_string()));
+        assert_eq!(rows[2][0], Data::String("x".repeat(MAX_CELL_CHARS)));
+        assert_eq!(rows[2][1], Data::Empty);
+        assert_eq!(rows[2][2], Data::String("0123".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_at_row_limit() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("results.xlsx");
+        let mut rows = vec![vec!["n".to_string()]];
+        rows.extend((1..=5).map(|n| vec![n.to_string()]));
+        let sheet = Sheet { name: "5_unified".to_string(), rows };
+        write_sheets(&path, &[sheet], 3)?;
+
+        let sheets = read_back(&path)?;
+        let names: Vec<&str> = sheets.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["5_unified", "5_unified (2)", "5_unified (3)"]);
+        let (_, last) = &sheets[2];
+        assert_eq!(last.len(), 2);
+        assert_eq!(last[0][0], Data::String("n".to_string()));
+        assert_eq!(last[1][0], Data::Float(5.0));
+
+        let long = Sheet { name: "y".repeat(40), rows: vec![vec!["n".to_string()]; 3] };
+        let parts = split_sheet(&long, 2);
+        assert_eq!(parts[1].0, "y".repeat(27) + " (2)");
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+//! Zotero Web API client
+//!
+//! Pushes papers into a named collection of a user or group library, with
+//! abstracts, DOIs, and a linked-URL attachment for each PDF.
+//!
+//! API Details:
+//! - Base URL: https://api.zotero.org
+//! - Auth: `Zotero-API-Key` header (key needs write access)
+//! - Max 50 objects per write request
+
+use crate::error::{GscholarError, Result};
+use crate::http;
+use crate::paper::Paper;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Zotero API base URL
+const ZOTERO_API_BASE: &str = "https://api.zotero.org";
+
+/// Maximum objects per write request
+const MAX_WRITE_BATCH: usize = 50;
+
+/// Timeout of one request (a write batch can take a while)
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Outcome of pushing papers to Zotero
+#[derive(Debug, Clone, Default)]
+pub struct PushSummary {
+    pub collection_key: String,
+    pub created: usize,
+    pub attachments: usize,
+    pub failed: usize,
+}
+
+/// Response of a multi-object write
+#[derive(Debug, Deserialize)]
+struct WriteResponse {
+    /// Index in the request -> new object key
+    #[serde(default)]
+    success: HashMap<String, String>,
+    #[serde(default)]
+    failed: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Collection {
+    key: String,
+    data: CollectionData,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionData {
+    name: String,
+}
+
+/// Zotero Web API client for one library
+pub struct ZoteroClient {
+    client: Client,
+    api_key: String,
+    /// `users/{id}` or `groups/{id}`
+    library_path: String,
+}
+
+impl ZoteroClient {
+    /// Create a client for a user library, or a group library if `group` is set
+    pub fn new(api_key: &str, library_id: &str, group: bool) -> Result<Self> {
+        let client = http::client();
+        let kind = if group { "groups" } else { "users" };
+
+        Ok(Self {
+            client,
+            api_key: api_key.to_string(),
+            library_path: format!("{}/{}", kind, library_id),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}/{}", ZOTERO_API_BASE, self.library_path, path)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .header("Zotero-API-Key", &self.api_key)
+            .header("Zotero-API-Version", "3")
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(GscholarError::Api {
+                code: status.as_u16() as i32,
+                message: format!("Zotero API error: {} - {}", status, error_text),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Write objects in batches, returning the new keys by input index
+    async fn write_objects(&self, path: &str, objects: &[Value]) -> Result<(HashMap<usize, String>, usize)> {
+        let mut keys = HashMap::new();
+        let mut failed = 0;
+
+        for (batch_idx, chunk) in objects.chunks(MAX_WRITE_BATCH).enumerate() {
+            let offset = batch_idx * MAX_WRITE_BATCH;
+            let response: WriteResponse = self
+                .send(self.client.post(self.url(path)).json(chunk))
+                .await?
+                .json()
+                .await
+                .map_err(|e| GscholarError::Parse(format!("Failed to parse Zotero response: {}", e)))?;
+
+            for (idx, key) in response.success {
+                if let Ok(idx) = idx.parse::<usize>() {
+                    keys.insert(offset + idx, key);
+                }
+            }
+            for (idx, error) in &response.failed {
+                warn!(index = %idx, error = %error, "Zotero rejected object");
+            }
+            failed += response.failed.len();
+        }
+
+        Ok((keys, failed))
+    }
+
+    /// Find a top-level collection by name (case-insensitive), creating it if missing
+    pub async fn find_or_create_collection(&self, name: &str) -> Result<String> {
+        let collections: Vec<Collection> = self
+            .send(self.client.get(self.url("collections/top")).query(&[("limit", "100")]))
+            .await?
+            .json()
+            .await
+            .map_err(|e| GscholarError::Parse(format!("Failed to parse Zotero collections: {}", e)))?;
+
+        if let Some(existing) = collections.iter().find(|c| c.data.name.eq_ignore_ascii_case(name)) {
+            return Ok(existing.key.clone());
+        }
+
+        let (keys, _) = self.write_objects("collections", &[json!({ "name": name })]).await?;
+        let key = keys
+            .get(&0)
+            .cloned()
+            .ok_or_else(|| GscholarError::Api { code: 400, message: format!("Failed to create Zotero collection: {}", name) })?;
+        info!(collection = name, key = %key, "Created Zotero collection");
+        Ok(key)
+    }
+
+    /// Push papers into the named collection, adding PDF links as attachments
+    pub async fn push_papers(&self, collection: &str, papers: &[&Paper]) -> Result<PushSummary> {
+        let collection_key = self.find_or_create_collection(collection).await?;
+
+        let items: Vec<Value> = papers.iter().map(|p| item_json(p, &collection_key)).collect();
+        let (item_keys, failed) = self.write_objects("items", &items).await?;
+
+        let attachments: Vec<Value> = papers
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.pdf_url.is_empty())
+            .filter_map(|(idx, p)| item_keys.get(&idx).map(|key| pdf_attachment_json(&p.pdf_url, key)))
+            .collect();
+        let attachment_count = if attachments.is_empty() {
+            0
+        } else {
+            match self.write_objects("items", &attachments).await {
+                Ok((keys, _)) => keys.len(),
+                Err(e) => {
+                    warn!(error = %e, "Failed to add PDF attachments");
+                    0
+                }
+            }
+        };
+
+        info!(created = item_keys.len(), attachments = attachment_count, failed = failed, "Zotero push complete");
+
+        Ok(PushSummary {
+            collection_key,
+            created: item_keys.len(),
+            attachments: attachment_count,
+            failed,
+        })
+    }
+}
+
+/// Zotero `journalArticle` item for a paper
+fn item_json(paper: &Paper, collection_key: &str) -> Value {
+    let creators: Vec<Value> = paper
+        .authors
+        .iter()
+        .map(|a| json!({ "creatorType": "author", "name": a.name }))
+        .collect();
+
+    json!({
+        "itemType": "journalArticle",
+        "title": paper.title,
+        "creators": creators,
+        "abstractNote": paper.abstract_text,
+        "publicationTitle": paper.journal,
+        "date": paper.date_string(),
+        "DOI": paper.doi,
+        "url": paper.article_url,
+        "collections": [collection_key],
+    })
+}
+
+/// Linked-URL attachment pointing at a PDF
+fn pdf_attachment_json(pdf_url: &str, parent_key: &str) -> Value {
+    json!({
+        "itemType": "attachment",
+        "linkMode": "linked_url",
+        "parentItem": parent_key,
+        "title": "Full Text PDF",
+        "url": pdf_url,
+        "contentType": "application/pdf",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unified::UnifiedRow;
+
+    #[test]
+    fn test_item_json() {
+        let paper = Paper::from(UnifiedRow {
+            title: "Test".to_string(),
+            author: "Jane Smith, Li Wei ...".to_string(),
+            date: "2021-05-03".to_string(),
+            doi: "10.1/x".to_string(),
+            article_url: String::new(),
+            pdf_url: "https://example.org/x.pdf".to_string(),
+            pdf_path: String::new(),
+            abstract_text: "Abstract".to_string(),
+            tldr: String::new(),
+            journal: "Landslides".to_string(),
+            if_score: String::new(),
+            jci_score: String::new(),
+            sci_partition: String::new(),
+            sources: String::new(),
+            ..Default::default()
+        });
+        let item = item_json(&paper, "ABCD1234");
+        assert_eq!(item["DOI"], "10.1/x");
+        assert_eq!(item["collections"][0], "ABCD1234");
+        assert_eq!(item["creators"].as_array().map(Vec::len), Some(2));
+        assert_eq!(item["creators"][1]["name"], "Li Wei");
+
+        let attachment = pdf_attachment_json(&paper.pdf_url, "ITEM0001");
+        assert_eq!(attachment["linkMode"], "linked_url");
+        assert_eq!(attachment["parentItem"], "ITEM0001");
+    }
+}
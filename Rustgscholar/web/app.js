@@ -0,0 +1,247 @@
+// rustgscholar web UI: submit jobs, watch them, browse and download results.
+// Talks to the JSON API of the same server (see /openapi.json).
+
+const $ = (id) => document.getElementById(id);
+const POLL_MS = 2000;
+let selectedJob = null;
+let pollTimer = null;
+let page = 1;
+
+$("apiKey").value = localStorage.getItem("apiKey") || "";
+$("apiKey").addEventListener("change", () => {
+  localStorage.setItem("apiKey", $("apiKey").value.trim());
+  loadJobs();
+});
+
+function headers(extra = {}) {
+  const key = $("apiKey").value.trim();
+  return key ? { ...extra, Authorization: `Bearer ${key}` } : extra;
+}
+
+// Fetch from the API; errors carry the message of the error envelope
+async function api(path, options = {}) {
+  const response = await fetch(path, { ...options, headers: headers(options.headers) });
+  if (!response.ok) {
+    let message = `${response.status} ${response.statusText}`;
+    try {
+      const error = await response.json();
+      message = error.message || message;
+      if (error.retry_after) message += ` (retry after ${error.retry_after}s)`;
+    } catch (_) {}
+    throw new Error(message);
+  }
+  return response;
+}
+
+function showError(id, error) {
+  $(id).textContent = error ? error.message : "";
+}
+
+function cell(row, content) {
+  const td = document.createElement("td");
+  if (content instanceof Node) td.append(content); else td.textContent = content ?? "";
+  row.append(td);
+  return td;
+}
+
+function link(text, href) {
+  const a = document.createElement("a");
+  a.textContent = text;
+  a.href = href;
+  a.target = "_blank";
+  a.rel = "noopener";
+  return a;
+}
+
+function badge(kind, value) {
+  const span = document.createElement("span");
+  span.className = `${kind} ${kind}-${value}`;
+  span.textContent = value;
+  return span;
+}
+
+// === Jobs ===
+
+$("submit").addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const pages = $("pages").value.split(",").map((p) => parseInt(p, 10)).filter((p) => p > 0);
+  const body = {
+    keyword: $("keyword").value.trim(),
+    sources: [...document.querySelectorAll("input[name=source]:checked")].map((c) => c.value),
+    pages: pages.length ? pages : [1],
+  };
+  if ($("ylo").value) body.ylo = parseInt($("ylo").value, 10);
+  try {
+    const response = await api("/jobs", {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify(body),
+    });
+    const job = await response.json();
+    showError("submitError", null);
+    await loadJobs();
+    selectJob(job.id);
+  } catch (error) {
+    showError("submitError", error);
+  }
+});
+
+async function loadJobs() {
+  try {
+    const jobs = await (await api("/jobs")).json();
+    const rows = $("jobs");
+    rows.replaceChildren();
+    for (const job of jobs) {
+      const row = document.createElement("tr");
+      row.className = job.id === selectedJob ? "job selected" : "job";
+      cell(row, new Date(job.created_at).toLocaleString());
+      cell(row, job.request.keyword);
+      cell(row, badge("status", job.status));
+      cell(row, job.result?.unified);
+      cell(row, job.result?.relevant);
+      row.addEventListener("click", () => selectJob(job.id));
+      rows.append(row);
+    }
+    showError("jobsError", null);
+  } catch (error) {
+    showError("jobsError", error);
+  }
+}
+
+$("refreshJobs").addEventListener("click", loadJobs);
+
+function selectJob(id) {
+  selectedJob = id;
+  clearTimeout(pollTimer);
+  $("job").hidden = false;
+  $("jobId").textContent = id;
+  $("files").replaceChildren();
+  $("downloads").hidden = true;
+  for (const row of document.querySelectorAll("tr.job")) row.classList.remove("selected");
+  watchJob(id);
+}
+
+// Poll the job until it finishes, then list its files
+async function watchJob(id) {
+  if (id !== selectedJob) return;
+  try {
+    const job = await (await api(`/jobs/${encodeURIComponent(id)}`)).json();
+    $("jobStatus").replaceChildren(badge("status", job.status));
+    $("progress").textContent = job.progress.join("\n");
+    $("progress").scrollTop = $("progress").scrollHeight;
+    showError("jobError", job.error ? new Error(job.error) : null);
+    if (job.status === "queued" || job.status === "running") {
+      pollTimer = setTimeout(() => watchJob(id), POLL_MS);
+    } else {
+      await loadJobs();
+      await loadFiles(id);
+      if (job.status === "completed") loadPapers();
+    }
+  } catch (error) {
+    showError("jobError", error);
+  }
+}
+
+async function loadFiles(id) {
+  const files = await (await api(`/jobs/${encodeURIComponent(id)}/files`)).json();
+  const list = $("files");
+  list.replaceChildren();
+  for (const file of files) {
+    const item = document.createElement("li");
+    const button = document.createElement("a");
+    button.href = "#";
+    button.textContent = file.name;
+    button.addEventListener("click", (event) => {
+      event.preventDefault();
+      download(`/jobs/${encodeURIComponent(id)}/files/${encodeURIComponent(file.name)}`, file.name);
+    });
+    item.append(button, ` (${(file.size / 1024).toFixed(1)} KB)`);
+    list.append(item);
+  }
+  $("downloads").hidden = false;
+}
+
+for (const button of document.querySelectorAll("[data-export]")) {
+  button.addEventListener("click", () => {
+    if (!selectedJob) return;
+    const format = button.dataset.export;
+    const extension = { bibtex: "bib", ris: "ris", "csl-json": "json" }[format];
+    download(`/jobs/${encodeURIComponent(selectedJob)}/export?format=${format}`, `${selectedJob}.${extension}`);
+  });
+}
+
+// Downloads go through fetch so the API key header is sent
+async function download(path, name) {
+  try {
+    const blob = await (await api(path)).blob();
+    const url = URL.createObjectURL(blob);
+    const a = document.createElement("a");
+    a.href = url;
+    a.download = name;
+    a.click();
+    URL.revokeObjectURL(url);
+    showError("jobError", null);
+  } catch (error) {
+    showError("jobError", error);
+  }
+}
+
+// === Results ===
+
+$("search").addEventListener("submit", (event) => {
+  event.preventDefault();
+  page = 1;
+  loadPapers();
+});
+
+async function loadPapers() {
+  const params = new URLSearchParams({ page });
+  if ($("query").value.trim()) params.set("query", $("query").value.trim());
+  if ($("label").value) params.set("label", $("label").value);
+  if ($("minIf").value) params.set("min_if", $("minIf").value);
+  if ($("sort").value) params.set("sort", $("sort").value);
+  if ($("onlyJob").checked && selectedJob) params.set("job", selectedJob);
+  try {
+    const result = await (await api(`/papers?${params}`)).json();
+    const rows = $("papers");
+    rows.replaceChildren();
+    for (const paper of result.papers) {
+      const row = document.createElement("tr");
+      cell(row, paper.title);
+      cell(row, paper.journal);
+      cell(row, paper.date);
+      cell(row, paper.if_score);
+      cell(row, paper.label ? badge("label", paper.label) : "");
+      const links = cell(row, "");
+      if (paper.doi) links.append(link("DOI", `https://doi.org/${paper.doi}`), " ");
+      if (paper.article_url) links.append(link("Article", paper.article_url), " ");
+      if (paper.pdf_url) links.append(link("PDF", paper.pdf_url));
+      rows.append(row);
+    }
+    renderPager(result);
+    showError("papersError", null);
+  } catch (error) {
+    showError("papersError", error);
+  }
+}
+
+function renderPager(result) {
+  const pages = Math.max(1, Math.ceil(result.total / result.per_page));
+  $("total").textContent = `${result.total} papers · page ${result.page} of ${pages}`;
+  const pager = $("pager");
+  pager.replaceChildren();
+  for (const [text, target] of [["‹ Previous", result.page - 1], ["Next ›", result.page + 1]]) {
+    const button = document.createElement("button");
+    button.type = "button";
+    button.textContent = text;
+    button.disabled = target < 1 || target > pages;
+    button.addEventListener("click", () => {
+      page = target;
+      loadPapers();
+    });
+    pager.append(button);
+  }
+}
+
+loadJobs();
+loadPapers();